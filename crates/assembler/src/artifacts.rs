@@ -0,0 +1,389 @@
+//! Everything a build can write, rendered in memory.
+//!
+//! [`BuildArtifacts`] holds each output of an assembled program — the
+//! binary, its build id, the listing, the program map, a JSON source map,
+//! the playground bundle and the `--emit` source wrappers — so library
+//! users get them all from one call. Where they end up is up to an
+//! [`ArtifactWriter`]: [`FileWriter`] puts them next to the output binary
+//! the way `build` does, and a `Vec<(ArtifactKind, Vec<u8>)>` just
+//! collects them. A new format is a new [`ArtifactKind`]; the writers and
+//! the `build` control flow stay as they are.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::assembler::{
+    assemble_with_options, AssembleError, AssembleErrorKind, AssembleOptions, AssembleResult,
+    ListingEntry,
+};
+use crate::bundle::ProgramBundle;
+use crate::emit::EmitFormat;
+use crate::include::IncludeError;
+use crate::trace::ProgramMap;
+
+/// One output of a build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactKind {
+    /// Raw binary image.
+    Binary,
+    /// Build id of the binary, on one line.
+    BuildId,
+    /// Address, bytes and source of each emitted line, as printed by
+    /// `build --verbose` and read by `listing-diff`.
+    Listing,
+    /// Program map for `trace-dump`.
+    Map,
+    /// JSON array of the emitted lines with their files and line numbers.
+    SourceMap,
+    /// Playground bundle JSON.
+    Bundle,
+    /// Binary wrapped as source for another project.
+    Emit(EmitFormat),
+}
+
+impl ArtifactKind {
+    /// Human-readable name, as used in error messages.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Binary => "binary",
+            Self::BuildId => "build id",
+            Self::Listing => "listing",
+            Self::Map => "map",
+            Self::SourceMap => "source map",
+            Self::Bundle => "bundle",
+            Self::Emit(format) => format.name(),
+        }
+    }
+
+    /// Where the artifact goes when the binary is written to `output`,
+    /// e.g. `prog.bin.buildid` or `prog.bundle.json` for `prog.bin`.
+    #[must_use]
+    pub fn default_path(self, output: &Path) -> PathBuf {
+        match self {
+            Self::Binary => output.to_path_buf(),
+            Self::BuildId => {
+                let mut path = output.as_os_str().to_owned();
+                path.push(".buildid");
+                PathBuf::from(path)
+            }
+            Self::Listing => output.with_extension("lst"),
+            Self::Map => output.with_extension("map"),
+            Self::SourceMap => output.with_extension("srcmap.json"),
+            Self::Bundle => output.with_extension("bundle.json"),
+            Self::Emit(format) => output.with_extension(format.extension()),
+        }
+    }
+}
+
+/// Every artifact of an assembled program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildArtifacts {
+    /// Input file name, named in the header of emitted sources.
+    pub source_name: String,
+    /// Rendered listing, one line per listing entry.
+    pub listing: String,
+    /// Program map.
+    pub map: ProgramMap,
+    /// Rendered source map JSON.
+    pub source_map: String,
+    /// Bundle, which also carries the binary, entry point, metadata and
+    /// build id.
+    pub bundle: ProgramBundle,
+}
+
+impl BuildArtifacts {
+    /// Renders the artifacts of `result`, assembled from `input`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`IncludeError`] if the sources can no longer be read to
+    /// build the bundle.
+    pub fn new(input: &Path, result: &AssembleResult) -> Result<Self, IncludeError> {
+        Ok(Self {
+            source_name: input
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().into_owned()),
+            listing: result.listing.iter().fold(String::new(), |mut out, entry| {
+                out.push_str(&listing_line(entry));
+                out.push('\n');
+                out
+            }),
+            map: ProgramMap::from_result(result),
+            source_map: source_map_json(result),
+            bundle: ProgramBundle::new(input, result)?,
+        })
+    }
+
+    /// Assembles `input` and renders its artifacts, dropping any warnings.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`AssembleError`] if assembly fails or the sources
+    /// cannot be re-read for the bundle.
+    #[allow(clippy::result_large_err)]
+    pub fn assemble(input: &Path, options: &AssembleOptions) -> Result<Self, AssembleError> {
+        let result = assemble_with_options(input, options)?;
+        Self::new(input, &result).map_err(|e| AssembleError {
+            kind: AssembleErrorKind::Include(e),
+            location: None,
+        })
+    }
+
+    /// Assembled binary image.
+    #[must_use]
+    pub fn binary(&self) -> &[u8] {
+        &self.bundle.binary
+    }
+
+    /// Bytes of the `kind` artifact.
+    #[must_use]
+    pub fn contents(&self, kind: ArtifactKind) -> Vec<u8> {
+        match kind {
+            ArtifactKind::Binary => self.bundle.binary.clone(),
+            ArtifactKind::BuildId => format!("{}\n", self.bundle.build_id).into_bytes(),
+            ArtifactKind::Listing => self.listing.clone().into_bytes(),
+            ArtifactKind::Map => self.map.to_text().into_bytes(),
+            ArtifactKind::SourceMap => self.source_map.clone().into_bytes(),
+            ArtifactKind::Bundle => self.bundle.to_json().into_bytes(),
+            ArtifactKind::Emit(format) => format
+                .render(
+                    &self.bundle.binary,
+                    self.bundle.entry,
+                    &self.bundle.info,
+                    &self.source_name,
+                )
+                .into_bytes(),
+        }
+    }
+
+    /// Hands each of `kinds` to `writer`, in order, stopping at the first
+    /// failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`ArtifactError`] naming the artifact that failed.
+    pub fn write(
+        &self,
+        kinds: &[ArtifactKind],
+        writer: &mut impl ArtifactWriter,
+    ) -> Result<(), ArtifactError> {
+        for &kind in kinds {
+            writer
+                .write(kind, &self.contents(kind))
+                .map_err(|source| ArtifactError { kind, source })?;
+        }
+        Ok(())
+    }
+}
+
+/// Destination for build artifacts.
+pub trait ArtifactWriter {
+    /// Stores the `kind` artifact.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error that prevented storing it.
+    fn write(&mut self, kind: ArtifactKind, contents: &[u8]) -> io::Result<()>;
+}
+
+/// Collects artifacts in memory, in the order they were written.
+impl ArtifactWriter for Vec<(ArtifactKind, Vec<u8>)> {
+    fn write(&mut self, kind: ArtifactKind, contents: &[u8]) -> io::Result<()> {
+        self.push((kind, contents.to_vec()));
+        Ok(())
+    }
+}
+
+/// Writes artifacts to files beside the output binary.
+#[derive(Debug, Clone)]
+pub struct FileWriter {
+    output: PathBuf,
+    paths: Vec<(ArtifactKind, PathBuf)>,
+}
+
+impl FileWriter {
+    /// Writes the binary to `output` and every other artifact to its
+    /// [`ArtifactKind::default_path`].
+    #[must_use]
+    pub const fn new(output: PathBuf) -> Self {
+        Self {
+            output,
+            paths: Vec::new(),
+        }
+    }
+
+    /// Writes the `kind` artifact to `path` instead of its default path.
+    #[must_use]
+    pub fn with_path(mut self, kind: ArtifactKind, path: PathBuf) -> Self {
+        self.paths.retain(|(existing, _)| *existing != kind);
+        self.paths.push((kind, path));
+        self
+    }
+
+    /// Path the `kind` artifact is written to.
+    #[must_use]
+    pub fn path(&self, kind: ArtifactKind) -> PathBuf {
+        self.paths
+            .iter()
+            .find(|(existing, _)| *existing == kind)
+            .map_or_else(|| kind.default_path(&self.output), |(_, path)| path.clone())
+    }
+}
+
+impl ArtifactWriter for FileWriter {
+    fn write(&mut self, kind: ArtifactKind, contents: &[u8]) -> io::Result<()> {
+        let path = self.path(kind);
+        std::fs::write(&path, contents)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))
+    }
+}
+
+/// An artifact an [`ArtifactWriter`] could not store.
+#[derive(Debug)]
+pub struct ArtifactError {
+    /// Artifact that failed.
+    pub kind: ArtifactKind,
+    /// Error from the writer.
+    pub source: io::Error,
+}
+
+impl fmt::Display for ArtifactError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to write {}: {}", self.kind.name(), self.source)
+    }
+}
+
+impl std::error::Error for ArtifactError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// One line of the listing, e.g. `0000: 60 3D 00 02  MOV R0, #2 ; prog.n1:3`.
+#[must_use]
+pub fn listing_line(entry: &ListingEntry) -> String {
+    let hex_bytes: Vec<String> = entry.bytes.iter().map(|b| format!("{b:02X}")).collect();
+    format!(
+        "{:04X}: {:<12} {} ; {}",
+        entry.address,
+        hex_bytes.join(" "),
+        entry.source,
+        entry.location
+    )
+}
+
+/// Emitted lines of `result` as a JSON array, in assembly order.
+#[must_use]
+pub fn source_map_json(result: &AssembleResult) -> String {
+    let lines: Vec<String> = result
+        .listing
+        .iter()
+        .filter(|entry| !entry.bytes.is_empty())
+        .map(|entry| {
+            format!(
+                "{{\"address\": {}, \"len_bytes\": {}, \"file\": \"{}\", \"line\": {}, \"source\": \"{}\"}}",
+                entry.address,
+                entry.bytes.len(),
+                json_escape(&entry.file),
+                entry.line,
+                json_escape(&entry.source)
+            )
+        })
+        .collect();
+    if lines.is_empty() {
+        return "[]\n".to_string();
+    }
+    format!("[\n  {}\n]\n", lines.join(",\n  "))
+}
+
+/// Escapes a value for a JSON string literal.
+#[must_use]
+pub fn json_escape(text: &str) -> String {
+    text.chars()
+        .flat_map(|ch| match ch {
+            '"' | '\\' => vec!['\\', ch],
+            ch if ch.is_control() => format!("\\u{:04x}", u32::from(ch)).chars().collect(),
+            ch => vec![ch],
+        })
+        .collect()
+}
+
+/// Lays out JSON array items one per line inside a top-level object.
+#[must_use]
+pub fn json_list(items: &[String]) -> String {
+    if items.is_empty() {
+        return String::new();
+    }
+    format!("\n    {}\n  ", items.join(",\n    "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+
+    #[test]
+    fn artifacts_render_in_memory_and_write_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("prog.n1");
+        std::fs::write(&input, "main:\n    MOV R0, #2\n    HALT\n").unwrap();
+        let artifacts = BuildArtifacts::assemble(&input, &AssembleOptions::default()).unwrap();
+
+        assert_eq!(artifacts.binary(), assemble(&input).unwrap().binary);
+        assert_eq!(artifacts.listing.lines().count(), 2);
+        assert!(
+            artifacts.listing.contains("0004: 00 10"),
+            "{}",
+            artifacts.listing
+        );
+        assert!(artifacts
+            .source_map
+            .starts_with("[\n  {\"address\": 0, \"len_bytes\": 4, \"file\": \""));
+        assert!(artifacts
+            .source_map
+            .contains("prog.n1\", \"line\": 3, \"source\": \"    HALT\"}\n]\n"));
+
+        let kinds = [
+            ArtifactKind::Binary,
+            ArtifactKind::BuildId,
+            ArtifactKind::Map,
+        ];
+        let mut collected = Vec::new();
+        artifacts.write(&kinds, &mut collected).unwrap();
+        let written: Vec<_> = collected.iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(written, kinds);
+        assert_eq!(
+            collected[1].1,
+            format!("{}\n", artifacts.bundle.build_id).into_bytes()
+        );
+
+        let output = dir.path().join("out.bin");
+        let map = dir.path().join("symbols.map");
+        let mut files = FileWriter::new(output.clone()).with_path(ArtifactKind::Map, map.clone());
+        artifacts.write(&kinds, &mut files).unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), artifacts.binary());
+        assert!(dir.path().join("out.bin.buildid").exists());
+        assert_eq!(
+            std::fs::read_to_string(map).unwrap(),
+            artifacts.map.to_text()
+        );
+    }
+
+    #[test]
+    fn write_errors_name_the_artifact() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("prog.n1");
+        std::fs::write(&input, "HALT\n").unwrap();
+        let artifacts = BuildArtifacts::assemble(&input, &AssembleOptions::default()).unwrap();
+
+        let mut files = FileWriter::new(dir.path().join("missing").join("out.bin"));
+        let error = artifacts
+            .write(&[ArtifactKind::Listing], &mut files)
+            .unwrap_err();
+        assert_eq!(error.kind, ArtifactKind::Listing);
+        assert!(error.to_string().starts_with("failed to write listing: "));
+        assert!(error.to_string().contains("out.lst"), "{error}");
+    }
+}
@@ -11,15 +11,27 @@
 //! - [`assemble`]: File-based assembly with include support
 //! - [`assemble_from_source`]: In-memory assembly for WASM/embedded use (no includes)
 
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
-use crate::encoder::{encode_line, EncodeError};
+use emulator_core::{
+    disassemble_window, MemoryRegion, OpcodeEncoding, FIXED_MEMORY_REGIONS, VEC_FAULT, VEC_TRAP,
+};
+
+use crate::callconv::CallConvViolation;
+use crate::encoder::{encode_line, EncodeError, EncodeErrorKind};
 use crate::include::{
-    expand_includes, format_include_chain, ExpandedLine, ExpandedTestBlock, IncludeError,
+    expand_includes_with_limit, format_include_chain, resolve_incbin, resolve_include_path,
+    ExpandedLine, ExpandedTestBlock, IncludeError, DEFAULT_MAX_INCLUDE_DEPTH,
 };
-use crate::parser::{parse_line, Directive, ParsedLine};
+use crate::info::{InfoField, ProgramInfo};
+use crate::optimize::{optimize, OptimizationKind};
+use crate::parser::{parse_line_with_pseudo_ops, Directive, ParsedLine};
 use crate::source::{extract_source, TestBlock};
-use crate::symbols::{assign_addresses_with_lines, Assignment, SymbolError};
+use crate::strings::{apply_aliases, dedup_strings, StringDedup};
+use crate::symbols::{
+    assign_addresses_with_lines, AddressedLine, Assignment, SymbolError, SymbolTable,
+};
 
 /// ROM region end address (inclusive) for address validation warnings.
 const ROM_END: u16 = 0x3FFF;
@@ -41,6 +53,38 @@ impl std::fmt::Display for AssembleErrorKind {
             Self::Symbol(e) => write!(f, "{e}"),
             Self::Encode(e) => write!(f, "{e}"),
             Self::Io(msg) => write!(f, "I/O error: {msg}"),
+            Self::DuplicateEntry { first_line } => {
+                write!(f, "duplicate .entry (first defined at line {first_line})")
+            }
+            Self::DuplicateInfo { field, first_line } => write!(
+                f,
+                "duplicate .{} (first defined at line {first_line})",
+                field.name()
+            ),
+            Self::SizeMismatch {
+                address,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "internal consistency error: line at 0x{address:04X} was sized at {expected} byte(s) in pass 1 but encoded to {actual} byte(s) in pass 2"
+            ),
+            Self::AddressDrift { expected, actual } => write!(
+                f,
+                "internal consistency error: line assigned to 0x{expected:04X} in pass 1 was emitted at offset 0x{actual:04X} in pass 2"
+            ),
+            Self::LayoutViolation { start, end, range } => {
+                write!(f, "bytes 0x{start:04X}-0x{end:04X} overlap {range}")?;
+                match range {
+                    ReservedRange::VectorTable => {
+                        write!(f, "; place vectors there with .org (.layout standard)")
+                    }
+                    ReservedRange::Region(_) => write!(
+                        f,
+                        ", which a program image must leave untouched (.layout standard)"
+                    ),
+                }
+            }
         }
     }
 }
@@ -69,6 +113,85 @@ pub enum AssembleErrorKind {
     Encode(EncodeError),
     /// I/O error reading source file.
     Io(String),
+    /// Pass 2 emitted a different number of bytes than pass 1 reserved.
+    ///
+    /// This indicates an assembler bug: every label after the offending line
+    /// would otherwise be silently misplaced.
+    SizeMismatch {
+        /// Address assigned to the line in pass 1.
+        address: u16,
+        /// Size computed in pass 1.
+        expected: u16,
+        /// Bytes actually emitted in pass 2.
+        actual: usize,
+    },
+    /// More than one `.entry` directive.
+    DuplicateEntry {
+        /// Line of the first `.entry`.
+        first_line: usize,
+    },
+    /// More than one `.title`, `.author` or `.version` directive.
+    DuplicateInfo {
+        /// The repeated directive.
+        field: InfoField,
+        /// Line of the first one.
+        first_line: usize,
+    },
+    /// Pass 2 output offset disagrees with the pass-1 address of a line.
+    AddressDrift {
+        /// Address assigned to the line in pass 1.
+        expected: u16,
+        /// Output offset at which pass 2 would emit the line.
+        actual: usize,
+    },
+    /// A line emits bytes where `.layout standard` keeps output out.
+    LayoutViolation {
+        /// First byte of the line.
+        start: u16,
+        /// Last byte of the line.
+        end: u16,
+        /// The range it overlaps.
+        range: ReservedRange,
+    },
+}
+
+/// First and last byte of the vector table, from `VEC_TRAP` to the end of
+/// `VEC_FAULT`.
+pub const VECTOR_TABLE: (u16, u16) = (VEC_TRAP, VEC_FAULT + 1);
+
+/// Address range that `.layout standard` keeps program output out of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReservedRange {
+    /// The vector table, which only `.org`-placed lines may fill.
+    VectorTable,
+    /// A fixed region that is not ROM or RAM.
+    Region(MemoryRegion),
+}
+
+impl ReservedRange {
+    /// Inclusive bounds of the range.
+    #[must_use]
+    pub const fn bounds(self) -> (u16, u16) {
+        match self {
+            Self::VectorTable => VECTOR_TABLE,
+            Self::Region(region) => region.bounds(),
+        }
+    }
+}
+
+impl std::fmt::Display for ReservedRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::VectorTable => "the vector table",
+            Self::Region(MemoryRegion::Rom) => "the ROM region",
+            Self::Region(MemoryRegion::Ram) => "the RAM region",
+            Self::Region(MemoryRegion::Mmio) => "the MMIO region",
+            Self::Region(MemoryRegion::Diag) => "the DIAG region",
+            Self::Region(MemoryRegion::Reserved) => "the reserved region",
+        };
+        let (start, end) = self.bounds();
+        write!(f, "{name} (0x{start:04X}-0x{end:04X})")
+    }
 }
 
 impl std::fmt::Display for AssembleError {
@@ -96,6 +219,33 @@ pub enum AssembleWarningKind {
         /// Address of the instruction/data.
         address: u16,
     },
+    /// A routine modifies a callee-saved register without saving it.
+    ///
+    /// Only produced by the opt-in calling convention check.
+    CalleeSavedClobber(CallConvViolation),
+    /// An instruction follows an unconditional `JMP`, `RET` or `ERET` with
+    /// no label in between, so nothing can reach it.
+    UnreachableCode {
+        /// Upper-case mnemonic of the preceding control transfer.
+        after: String,
+        /// Source line of the preceding control transfer.
+        after_line: usize,
+    },
+    /// A `HALT` directly follows another `HALT` with no label in between.
+    DuplicateHalt {
+        /// Source line of the first `HALT`.
+        first_line: usize,
+    },
+    /// Code can run on into data whose first bytes decode as a legal
+    /// instruction, so the data executes instead of faulting.
+    DataFallthrough {
+        /// Address of the first data byte.
+        address: u16,
+        /// Source line of the instruction execution falls through from.
+        after_line: usize,
+        /// Disassembly of the instruction the data decodes as.
+        decodes_as: String,
+    },
 }
 
 impl std::fmt::Display for AssembleWarning {
@@ -107,6 +257,25 @@ impl std::fmt::Display for AssembleWarning {
                     "code at address 0x{address:04X} is outside ROM region (0x0000-0x3FFF)"
                 )
             }
+            AssembleWarningKind::CalleeSavedClobber(violation) => write!(f, "{violation}"),
+            AssembleWarningKind::UnreachableCode { after, after_line } => write!(
+                f,
+                "unreachable code: follows {after} on line {after_line} with no label in between"
+            ),
+            AssembleWarningKind::DuplicateHalt { first_line } => write!(
+                f,
+                "HALT directly follows the HALT on line {first_line}; \
+                 the second only idles for another tick"
+            ),
+            AssembleWarningKind::DataFallthrough {
+                address,
+                after_line,
+                decodes_as,
+            } => write!(
+                f,
+                "execution can fall through from line {after_line} into data at \
+                 0x{address:04X}, which decodes as `{decodes_as}`"
+            ),
         }
     }
 }
@@ -122,6 +291,67 @@ pub struct AssembleResult {
     pub warnings: Vec<AssembleWarning>,
     /// Address-to-source mapping for listing generation.
     pub listing: Vec<ListingEntry>,
+    /// Labels from pass 1, with their addresses.
+    pub symbols: SymbolTable,
+    /// Constants defined by `.equ`, which are not addresses and so are
+    /// kept out of `symbols`.
+    pub constants: BTreeMap<String, u16>,
+    /// Rewrites made by the peephole optimizer (empty unless enabled).
+    pub optimizations: Vec<AppliedOptimization>,
+    /// String runs shared with an earlier identical run (empty unless enabled).
+    pub deduplicated_strings: Vec<DeduplicatedString>,
+    /// Address execution starts at, when set by `.entry` or
+    /// [`AssembleOptions::entry`]; `None` means 0x0000.
+    pub entry: Option<u16>,
+    /// Title, author and version declared by the program.
+    pub info: ProgramInfo,
+    /// Files the binary was built from: the root source, its includes and
+    /// any `.incbin` data, each listed once. Empty for in-memory assembly.
+    pub sources: Vec<PathBuf>,
+}
+
+/// Options controlling the assembly pipeline.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssembleOptions {
+    /// Run the peephole optimizer between parsing and pass 1.
+    pub optimize: bool,
+    /// Store identical labelled string data once and alias its labels.
+    pub dedup_strings: bool,
+    /// Entry label, overriding any `.entry` directive in the source.
+    pub entry: Option<String>,
+    /// Deepest `.include` nesting allowed, or `None` for
+    /// [`DEFAULT_MAX_INCLUDE_DEPTH`].
+    pub max_include_depth: Option<u32>,
+}
+
+/// A peephole rewrite with the source location it was applied to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedOptimization {
+    /// What was changed.
+    pub kind: OptimizationKind,
+    /// Source location of the rewritten line.
+    pub location: SourceLocation,
+}
+
+impl std::fmt::Display for AppliedOptimization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+/// A duplicate string run with the source location of its first label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeduplicatedString {
+    /// What was shared.
+    pub dedup: StringDedup,
+    /// Source location of the removed run.
+    pub location: SourceLocation,
+}
+
+impl std::fmt::Display for DeduplicatedString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.dedup)
+    }
 }
 
 /// A test block with its include context.
@@ -144,6 +374,10 @@ pub struct ListingEntry {
     pub source: String,
     /// Source location for error reporting.
     pub location: String,
+    /// File containing the source line.
+    pub file: String,
+    /// 1-indexed line number within `file`.
+    pub line: usize,
 }
 
 /// Assembles a source file into binary output.
@@ -158,7 +392,9 @@ pub struct ListingEntry {
 /// # Errors
 ///
 /// Returns `AssembleError` if any phase fails:
-/// - Include expansion fails (file not found, circular include)
+/// - Include expansion fails (file not found, circular include, includes
+///   nested too deep)
+/// - An `.incbin` file is missing or its range is out of bounds
 /// - Parsing fails (invalid syntax, unknown mnemonic)
 /// - Symbol table construction fails (duplicate label, address overflow)
 /// - Encoding fails (undefined label, displacement out of range)
@@ -169,24 +405,73 @@ pub struct ListingEntry {
 /// such as code placed outside the ROM region.
 #[allow(clippy::result_large_err)]
 pub fn assemble(path: &Path) -> Result<AssembleResult, AssembleError> {
-    let expanded = expand_includes(path).map_err(|e| AssembleError {
+    assemble_with_options(path, &AssembleOptions::default())
+}
+
+/// Assembles a source file with explicit pipeline options.
+///
+/// Behaves like [`assemble`]; with `options.optimize` set, the peephole
+/// optimizer rewrites the parsed program before addresses are assigned and
+/// each rewrite is recorded in [`AssembleResult::optimizations`]. With
+/// `options.dedup_strings` set, repeated string data is stored once and
+/// each shared run is recorded in [`AssembleResult::deduplicated_strings`].
+///
+/// # Errors
+///
+/// Returns `AssembleError` under the same conditions as [`assemble`].
+#[allow(clippy::result_large_err)]
+pub fn assemble_with_options(
+    path: &Path,
+    options: &AssembleOptions,
+) -> Result<AssembleResult, AssembleError> {
+    let max_depth = options
+        .max_include_depth
+        .unwrap_or(DEFAULT_MAX_INCLUDE_DEPTH);
+    let expanded = expand_includes_with_limit(path, max_depth).map_err(|e| AssembleError {
         kind: AssembleErrorKind::Include(e),
         location: None,
     })?;
 
     let parsed = parse_expanded_lines(&expanded.lines)?;
+    let mut sources = expanded.files;
+    for (line, expanded_line) in parsed.iter().zip(&expanded.lines) {
+        if let ParsedLine::Directive {
+            directive: Directive::IncBin(ops),
+        } = line
+        {
+            let path = resolve_include_path(&ops.path, &expanded_line.file_path);
+            if !sources.contains(&path) {
+                sources.push(path);
+            }
+        }
+    }
 
-    let source_lines: Vec<usize> = parsed.iter().map(|p| p.source_line).collect();
-    let parsed_lines: Vec<ParsedLine> = parsed.iter().map(|p| p.parsed.clone()).collect();
+    let PreparedLines {
+        parsed_lines,
+        lines,
+        optimizations,
+        deduplicated_strings,
+    } = prepare_lines(parsed, &expanded.lines, options);
 
-    let assignment = assign_addresses_with_lines(&parsed_lines, 0, &source_lines).map_err(|e| {
-        AssembleError {
-            kind: AssembleErrorKind::Symbol(e),
-            location: None,
-        }
-    })?;
+    let source_lines: Vec<usize> = lines.iter().map(|l| l.original_line).collect();
+
+    let mut assignment =
+        assign_addresses_with_lines(&parsed_lines, 0, &source_lines).map_err(|e| {
+            AssembleError {
+                kind: AssembleErrorKind::Symbol(e),
+                location: None,
+            }
+        })?;
+    let dedups: Vec<StringDedup> = deduplicated_strings
+        .iter()
+        .map(|shared| shared.dedup.clone())
+        .collect();
+    apply_aliases(&mut assignment.symbols, &dedups);
 
-    let (binary, warnings, listing) = encode_pass2(&assignment, &expanded.lines)?;
+    check_layout(&assignment, &lines)?;
+    let (binary, warnings, listing) = encode_pass2(&assignment, &lines)?;
+    let entry = resolve_entry(&assignment, &lines, options.entry.as_deref())?;
+    let info = resolve_info(&assignment, &lines)?;
 
     let test_blocks = expanded
         .test_blocks
@@ -205,6 +490,13 @@ pub fn assemble(path: &Path) -> Result<AssembleResult, AssembleError> {
         test_blocks,
         warnings,
         listing,
+        symbols: labels_only(assignment.symbols, &assignment.constants),
+        constants: assignment.constants,
+        optimizations,
+        deduplicated_strings,
+        entry,
+        info,
+        sources,
     })
 }
 
@@ -221,7 +513,7 @@ pub fn assemble(path: &Path) -> Result<AssembleResult, AssembleError> {
 /// # Errors
 ///
 /// Returns `AssembleError` if:
-/// - The source contains `.include` directives (not supported in in-memory mode)
+/// - The source contains `.include` or `.incbin` directives (not supported in in-memory mode)
 /// - Parsing fails (invalid syntax, unknown mnemonic)
 /// - Symbol table construction fails (duplicate label, address overflow)
 /// - Encoding fails (undefined label, displacement out of range)
@@ -244,29 +536,35 @@ pub fn assemble_from_source(
         });
     }
 
+    let mut pseudo_ops = Vec::new();
     for line in extracted.lines {
-        let parsed = parse_line(&line.text, line.original_line).map_err(|e| AssembleError {
-            kind: AssembleErrorKind::Parse(e.to_string()),
-            location: Some(SourceLocation {
-                file: file_name.to_string(),
-                line: line.original_line,
-                include_chain: String::new(),
-            }),
-        })?;
+        let parsed = parse_line_with_pseudo_ops(&line.text, line.original_line, &mut pseudo_ops)
+            .map_err(|e| AssembleError {
+                kind: AssembleErrorKind::Parse(e.to_string()),
+                location: Some(SourceLocation {
+                    file: file_name.to_string(),
+                    line: line.original_line,
+                    include_chain: String::new(),
+                }),
+            })?;
 
-        if matches!(
-            parsed,
+        let unsupported = match parsed {
             ParsedLine::Directive {
                 directive: Directive::Include(_),
-            }
-        ) {
+            } => Some(".include"),
+            ParsedLine::Directive {
+                directive: Directive::IncBin(_),
+            } => Some(".incbin"),
+            _ => None,
+        };
+        if let Some(directive) = unsupported {
             return Err(AssembleError {
                 kind: AssembleErrorKind::Include(IncludeError {
                     path,
                     include_chain: Vec::new(),
-                    kind: crate::include::IncludeErrorKind::IoError(
-                        ".include not supported in in-memory mode".to_string(),
-                    ),
+                    kind: crate::include::IncludeErrorKind::IoError(format!(
+                        "{directive} not supported in in-memory mode"
+                    )),
                 }),
                 location: Some(SourceLocation {
                     file: file_name.to_string(),
@@ -285,9 +583,13 @@ pub fn assemble_from_source(
     }
 
     let parsed = parse_expanded_lines(&expanded_lines)?;
+    let PreparedLines {
+        parsed_lines,
+        lines,
+        ..
+    } = prepare_lines(parsed, &expanded_lines, &AssembleOptions::default());
 
-    let source_lines: Vec<usize> = parsed.iter().map(|p| p.source_line).collect();
-    let parsed_lines: Vec<ParsedLine> = parsed.iter().map(|p| p.parsed.clone()).collect();
+    let source_lines: Vec<usize> = lines.iter().map(|l| l.original_line).collect();
 
     let assignment = assign_addresses_with_lines(&parsed_lines, 0, &source_lines).map_err(|e| {
         AssembleError {
@@ -296,7 +598,10 @@ pub fn assemble_from_source(
         }
     })?;
 
-    let (binary, warnings, listing) = encode_pass2(&assignment, &expanded_lines)?;
+    check_layout(&assignment, &lines)?;
+    let (binary, warnings, listing) = encode_pass2(&assignment, &lines)?;
+    let entry = resolve_entry(&assignment, &lines, None)?;
+    let info = resolve_info(&assignment, &lines)?;
 
     let test_blocks = expanded_test_blocks
         .into_iter()
@@ -314,34 +619,301 @@ pub fn assemble_from_source(
         test_blocks,
         warnings,
         listing,
+        symbols: labels_only(assignment.symbols, &assignment.constants),
+        constants: assignment.constants,
+        optimizations: Vec::new(),
+        deduplicated_strings: Vec::new(),
+        entry,
+        info,
+        sources: Vec::new(),
     })
 }
 
-/// Parsed line with source location context.
-struct ParsedWithContext {
-    parsed: ParsedLine,
-    source_line: usize,
+/// Output of [`prepare_lines`].
+struct PreparedLines {
+    parsed_lines: Vec<ParsedLine>,
+    lines: Vec<ExpandedLine>,
+    optimizations: Vec<AppliedOptimization>,
+    deduplicated_strings: Vec<DeduplicatedString>,
+}
+
+/// Runs the source-level passes between parsing and pass 1.
+///
+/// `MEMCPY`/`MEMCMP` uses are expanded into their loops first. The optional
+/// peephole optimizer and string deduplication then rewrite lines in place.
+/// The returned expanded lines stay index-aligned with the returned parsed
+/// lines.
+fn prepare_lines(
+    parsed_lines: Vec<ParsedLine>,
+    expanded_lines: &[ExpandedLine],
+    options: &AssembleOptions,
+) -> PreparedLines {
+    let (mut parsed_lines, lines) = expand_block_ops(parsed_lines, expanded_lines);
+    let expanded_lines = lines.as_slice();
+    let location_of = |index: usize| {
+        let expanded = &expanded_lines[index];
+        SourceLocation {
+            file: expanded.file_path.to_string_lossy().to_string(),
+            line: expanded.original_line,
+            include_chain: format_include_chain(expanded),
+        }
+    };
+
+    let optimizations = if options.optimize {
+        optimize(&mut parsed_lines)
+            .into_iter()
+            .map(|applied| AppliedOptimization {
+                location: location_of(applied.index),
+                kind: applied.kind,
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let deduplicated_strings = if options.dedup_strings {
+        dedup_strings(&mut parsed_lines)
+            .into_iter()
+            .map(|dedup| DeduplicatedString {
+                location: location_of(dedup.index),
+                dedup,
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    PreparedLines {
+        parsed_lines,
+        lines,
+        optimizations,
+        deduplicated_strings,
+    }
+}
+
+/// Replaces each `MEMCPY`/`MEMCMP` with its loop.
+///
+/// Expanded lines keep the location of the pseudo-op and take the text of
+/// the generated instruction, so the listing shows the loop.
+fn expand_block_ops(
+    parsed_lines: Vec<ParsedLine>,
+    expanded_lines: &[ExpandedLine],
+) -> (Vec<ParsedLine>, Vec<ExpandedLine>) {
+    let mut parsed = Vec::with_capacity(parsed_lines.len());
+    let mut lines = Vec::with_capacity(parsed_lines.len());
+    let mut next_id = 0;
+    for (line, expanded) in parsed_lines.into_iter().zip(expanded_lines) {
+        let ParsedLine::Directive {
+            directive: Directive::BlockOp(op),
+        } = line
+        else {
+            parsed.push(line);
+            lines.push(expanded.clone());
+            continue;
+        };
+        for (line, text) in op.expand(next_id) {
+            parsed.push(line);
+            lines.push(ExpandedLine {
+                text,
+                ..expanded.clone()
+            });
+        }
+        next_id += 1;
+    }
+    (parsed, lines)
+}
+
+/// Drops `.equ` constants from a pass-1 symbol table, leaving the labels.
+fn labels_only(mut symbols: SymbolTable, constants: &BTreeMap<String, u16>) -> SymbolTable {
+    symbols.retain(|name, _| !constants.contains_key(name));
+    symbols
 }
 
+/// Resolves the entry label from `override_label` or the program's single
+/// `.entry` directive.
 #[allow(clippy::result_large_err)]
-fn parse_expanded_lines(lines: &[ExpandedLine]) -> Result<Vec<ParsedWithContext>, AssembleError> {
-    let mut result = Vec::with_capacity(lines.len());
+fn resolve_entry(
+    assignment: &Assignment,
+    lines: &[ExpandedLine],
+    override_label: Option<&str>,
+) -> Result<Option<u16>, AssembleError> {
+    let location_of = |index: usize| {
+        lines.get(index).map(|expanded| SourceLocation {
+            file: expanded.file_path.to_string_lossy().to_string(),
+            line: expanded.original_line,
+            include_chain: format_include_chain(expanded),
+        })
+    };
 
-    for expanded in lines {
-        let parsed =
-            parse_line(&expanded.text, expanded.original_line).map_err(|e| AssembleError {
-                kind: AssembleErrorKind::Parse(e.to_string()),
-                location: Some(SourceLocation {
+    let mut directive: Option<(&str, usize)> = None;
+    for (index, addressed) in assignment.lines.iter().enumerate() {
+        let ParsedLine::Directive {
+            directive: Directive::Entry(label),
+        } = &addressed.parsed
+        else {
+            continue;
+        };
+        if let Some((_, first)) = directive {
+            return Err(AssembleError {
+                kind: AssembleErrorKind::DuplicateEntry {
+                    first_line: assignment.lines[first].source_line,
+                },
+                location: location_of(index),
+            });
+        }
+        directive = Some((label, index));
+    }
+
+    let (label, index) = match (override_label, directive) {
+        (Some(label), _) => (label, None),
+        (None, Some((label, index))) => (label, Some(index)),
+        (None, None) => return Ok(None),
+    };
+
+    assignment
+        .symbols
+        .get(label)
+        .map(|symbol| Some(symbol.address))
+        .ok_or_else(|| AssembleError {
+            kind: AssembleErrorKind::Encode(EncodeError {
+                kind: EncodeErrorKind::UndefinedLabel(label.to_string()),
+                line: index.map_or(0, |index| assignment.lines[index].source_line),
+            }),
+            location: index.and_then(location_of),
+        })
+}
+
+/// Collects the program's `.title`, `.author` and `.version` directives.
+#[allow(clippy::result_large_err)]
+fn resolve_info(
+    assignment: &Assignment,
+    lines: &[ExpandedLine],
+) -> Result<ProgramInfo, AssembleError> {
+    let mut info = ProgramInfo::default();
+    let mut first_lines: Vec<(InfoField, usize)> = Vec::new();
+    for (index, addressed) in assignment.lines.iter().enumerate() {
+        let ParsedLine::Directive {
+            directive: Directive::Info(field, value),
+        } = &addressed.parsed
+        else {
+            continue;
+        };
+        if let Some((_, first_line)) = first_lines.iter().find(|(seen, _)| seen == field) {
+            return Err(AssembleError {
+                kind: AssembleErrorKind::DuplicateInfo {
+                    field: *field,
+                    first_line: *first_line,
+                },
+                location: lines.get(index).map(|expanded| SourceLocation {
                     file: expanded.file_path.to_string_lossy().to_string(),
                     line: expanded.original_line,
                     include_chain: format_include_chain(expanded),
                 }),
-            })?;
+            });
+        }
+        first_lines.push((*field, addressed.source_line));
+        info.set(*field, value.clone());
+    }
+    Ok(info)
+}
 
-        result.push(ParsedWithContext {
-            parsed,
-            source_line: expanded.original_line,
+/// Enforces `.layout standard` when the program declares it.
+///
+/// Bytes in the vector table must come after an `.org` that lands inside
+/// it, so code that merely runs on from 0x0000 cannot overwrite the
+/// vectors, and no bytes may fall in a fixed region other than ROM and RAM,
+/// since loading them would write over MMIO, DIAG or reserved space.
+#[allow(clippy::result_large_err)]
+fn check_layout(assignment: &Assignment, lines: &[ExpandedLine]) -> Result<(), AssembleError> {
+    let standard = assignment.lines.iter().any(|addressed| {
+        matches!(
+            addressed.parsed,
+            ParsedLine::Directive {
+                directive: Directive::StandardLayout
+            }
+        )
+    });
+    if !standard {
+        return Ok(());
+    }
+
+    let (vectors_start, vectors_end) = VECTOR_TABLE;
+    let mut placed_in_vectors = false;
+    for (index, addressed) in assignment.lines.iter().enumerate() {
+        if let ParsedLine::Directive {
+            directive: Directive::Org(target),
+        } = addressed.parsed
+        {
+            placed_in_vectors =
+                (u32::from(vectors_start)..=u32::from(vectors_end)).contains(&target);
+            continue;
+        }
+        if addressed.size == 0 {
+            continue;
+        }
+        let start = addressed.address;
+        let end = start.saturating_add(addressed.size - 1);
+        let overlaps = |range: ReservedRange| {
+            let (first, last) = range.bounds();
+            start <= last && first <= end
+        };
+        let vectors = Some(ReservedRange::VectorTable).filter(|_| !placed_in_vectors);
+        let violation = vectors.filter(|range| overlaps(*range)).or_else(|| {
+            FIXED_MEMORY_REGIONS
+                .iter()
+                .map(|descriptor| ReservedRange::Region(descriptor.region))
+                .filter(|range| {
+                    !matches!(
+                        range,
+                        ReservedRange::Region(MemoryRegion::Rom | MemoryRegion::Ram)
+                    )
+                })
+                .find(|range| overlaps(*range))
         });
+        if let Some(range) = violation {
+            return Err(AssembleError {
+                kind: AssembleErrorKind::LayoutViolation { start, end, range },
+                location: lines.get(index).map(|expanded| SourceLocation {
+                    file: expanded.file_path.to_string_lossy().to_string(),
+                    line: expanded.original_line,
+                    include_chain: format_include_chain(expanded),
+                }),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_expanded_lines(lines: &[ExpandedLine]) -> Result<Vec<ParsedLine>, AssembleError> {
+    let mut result = Vec::with_capacity(lines.len());
+    let mut pseudo_ops = Vec::new();
+
+    for expanded in lines {
+        let location = || SourceLocation {
+            file: expanded.file_path.to_string_lossy().to_string(),
+            line: expanded.original_line,
+            include_chain: format_include_chain(expanded),
+        };
+
+        let mut parsed =
+            parse_line_with_pseudo_ops(&expanded.text, expanded.original_line, &mut pseudo_ops)
+                .map_err(|e| AssembleError {
+                    kind: AssembleErrorKind::Parse(e.to_string()),
+                    location: Some(location()),
+                })?;
+
+        if let ParsedLine::Directive {
+            directive: Directive::IncBin(ops),
+        } = &mut parsed
+        {
+            resolve_incbin(ops, expanded).map_err(|e| AssembleError {
+                kind: AssembleErrorKind::Include(e),
+                location: Some(location()),
+            })?;
+        }
+
+        result.push(parsed);
     }
 
     Ok(result)
@@ -359,11 +931,19 @@ fn encode_pass2(
     let mut binary = Vec::new();
     let mut warnings = Vec::new();
     let mut listing = Vec::new();
+    let mut flow_end: Option<FlowEnd> = None;
+    let mut fall_from: Option<usize> = None;
+    let mut data_entries = Vec::new();
 
-    for addressed in &assignment.lines {
+    for (index, addressed) in assignment.lines.iter().enumerate() {
         let expanded = expanded_lines
-            .iter()
-            .find(|el| el.original_line == addressed.source_line)
+            .get(index)
+            .filter(|el| el.original_line == addressed.source_line)
+            .or_else(|| {
+                expanded_lines
+                    .iter()
+                    .find(|el| el.original_line == addressed.source_line)
+            })
             .cloned()
             .unwrap_or_else(|| ExpandedLine {
                 text: String::new(),
@@ -373,20 +953,32 @@ fn encode_pass2(
             });
 
         let location = format_include_chain(&expanded);
+        let source_location = || SourceLocation {
+            file: expanded.file_path.to_string_lossy().to_string(),
+            line: expanded.original_line,
+            include_chain: location.clone(),
+        };
 
         if addressed.size > 0 && addressed.address > ROM_END {
             warnings.push(AssembleWarning {
                 kind: AssembleWarningKind::OutsideRom {
                     address: addressed.address,
                 },
-                location: Some(SourceLocation {
-                    file: expanded.file_path.to_string_lossy().to_string(),
-                    line: expanded.original_line,
-                    include_chain: location.clone(),
-                }),
+                location: Some(source_location()),
+            });
+        }
+
+        if let Some(kind) = check_fallthrough(&mut flow_end, addressed) {
+            warnings.push(AssembleWarning {
+                kind,
+                location: Some(source_location()),
             });
         }
 
+        if let Some(after_line) = check_data_entry(&mut fall_from, addressed) {
+            data_entries.push((addressed.address, after_line, source_location()));
+        }
+
         if let ParsedLine::Directive {
             directive: crate::parser::Directive::Org(target),
         } = &addressed.parsed
@@ -407,11 +999,12 @@ fn encode_pass2(
         )
         .map_err(|e| AssembleError {
             kind: AssembleErrorKind::Encode(e),
-            location: Some(SourceLocation {
-                file: expanded.file_path.to_string_lossy().to_string(),
-                line: expanded.original_line,
-                include_chain: location.clone(),
-            }),
+            location: Some(source_location()),
+        })?;
+
+        check_convergence(addressed, binary.len(), bytes.len()).map_err(|kind| AssembleError {
+            kind,
+            location: Some(source_location()),
         })?;
 
         if !bytes.is_empty() {
@@ -420,15 +1013,150 @@ fn encode_pass2(
                 bytes: bytes.clone(),
                 source: expanded.text.clone(),
                 location: location.clone(),
+                file: expanded.file_path.to_string_lossy().to_string(),
+                line: expanded.original_line,
             });
         }
 
         binary.extend(&bytes);
     }
 
+    warnings.extend(data_fallthrough_warnings(data_entries, &binary));
+
     Ok((binary, warnings, listing))
 }
 
+/// Last instruction seen that does not fall through to the next line.
+struct FlowEnd {
+    mnemonic: &'static str,
+    line: usize,
+}
+
+/// Tracks fall-through across the pass-1 line stream and reports an
+/// instruction that follows `JMP`/`RET`/`ERET` or a `HALT` that follows
+/// `HALT` without a label in between.
+///
+/// Any label or directive ends the run: a label is a jump target, and data,
+/// `.org` or alignment usually starts a new region reached some other way.
+/// Only the first instruction of a dead run is reported.
+fn check_fallthrough(
+    flow_end: &mut Option<FlowEnd>,
+    addressed: &AddressedLine,
+) -> Option<AssembleWarningKind> {
+    let ParsedLine::Instruction { instruction } = &addressed.parsed else {
+        if !matches!(addressed.parsed, ParsedLine::Blank) {
+            *flow_end = None;
+        }
+        return None;
+    };
+
+    let encoding = instruction.resolution.2;
+    let warning = flow_end.take().map(|end| {
+        if end.mnemonic == "HALT" {
+            (encoding == OpcodeEncoding::Halt).then_some(AssembleWarningKind::DuplicateHalt {
+                first_line: end.line,
+            })
+        } else {
+            Some(AssembleWarningKind::UnreachableCode {
+                after: end.mnemonic.to_string(),
+                after_line: end.line,
+            })
+        }
+    });
+
+    let mnemonic = match encoding {
+        OpcodeEncoding::Jmp => Some("JMP"),
+        OpcodeEncoding::CallOrRet if instruction.operand.is_none() => Some("RET"),
+        OpcodeEncoding::Eret => Some("ERET"),
+        OpcodeEncoding::Halt => Some("HALT"),
+        _ => None,
+    };
+    *flow_end = mnemonic.map(|mnemonic| FlowEnd {
+        mnemonic,
+        line: addressed.source_line,
+    });
+
+    warning.flatten()
+}
+
+/// Warns about each data entry from [`check_data_entry`] whose first word
+/// decodes as a legal instruction. Runs on the finished image, since that
+/// word can run on into the line after the entry.
+fn data_fallthrough_warnings(
+    entries: Vec<(u16, usize, SourceLocation)>,
+    binary: &[u8],
+) -> impl Iterator<Item = AssembleWarning> + '_ {
+    entries
+        .into_iter()
+        .filter_map(move |(address, after_line, location)| {
+            let row = disassemble_window(address, 0, 0, binary).pop()?;
+            (!row.is_illegal).then(|| AssembleWarning {
+                kind: AssembleWarningKind::DataFallthrough {
+                    address,
+                    after_line,
+                    decodes_as: format!("{} {}", row.mnemonic, row.operands)
+                        .trim_end()
+                        .to_string(),
+                },
+                location: Some(location),
+            })
+        })
+}
+
+/// Tracks whether execution can run off the end of code into data and
+/// returns the line of the instruction it falls from when `addressed` is
+/// the first data line reached that way.
+///
+/// `JMP`, `RET`, `ERET`, `HALT` and `.org` end the run. Labels and
+/// directives that emit nothing do not, since execution passes straight
+/// over them.
+fn check_data_entry(fall_from: &mut Option<usize>, addressed: &AddressedLine) -> Option<usize> {
+    match &addressed.parsed {
+        ParsedLine::Instruction { instruction } => {
+            let stops = match instruction.resolution.2 {
+                OpcodeEncoding::Jmp | OpcodeEncoding::Eret | OpcodeEncoding::Halt => true,
+                OpcodeEncoding::CallOrRet => instruction.operand.is_none(),
+                _ => false,
+            };
+            *fall_from = (!stops).then_some(addressed.source_line);
+            None
+        }
+        ParsedLine::Directive {
+            directive: Directive::Org(_),
+        } => {
+            *fall_from = None;
+            None
+        }
+        ParsedLine::Directive { .. } if addressed.size > 0 => fall_from.take(),
+        ParsedLine::Blank | ParsedLine::Label { .. } | ParsedLine::Directive { .. } => None,
+    }
+}
+
+/// Verifies that pass 2 agrees with the layout pass 1 decided for a line.
+///
+/// Both the output offset and the emitted length must match; otherwise label
+/// addresses computed in pass 1 no longer describe the produced image.
+fn check_convergence(
+    addressed: &AddressedLine,
+    offset: usize,
+    emitted: usize,
+) -> Result<(), AssembleErrorKind> {
+    if emitted != usize::from(addressed.size) {
+        return Err(AssembleErrorKind::SizeMismatch {
+            address: addressed.address,
+            expected: addressed.size,
+            actual: emitted,
+        });
+    }
+    if emitted > 0 && offset != usize::from(addressed.address) {
+        return Err(AssembleErrorKind::AddressDrift {
+            expected: addressed.address,
+            actual: offset,
+        });
+    }
+    Ok(())
+}
+
 fn format_include_chain_for_test(etb: &ExpandedTestBlock) -> String {
     if etb.include_chain.is_empty() {
         format!("{}:{}", etb.file_path.display(), etb.block.start_line)
@@ -445,7 +1173,7 @@ fn format_include_chain_for_test(etb: &ExpandedTestBlock) -> String {
                 entry.line
             ));
         }
-        parts.join(" (") + &")".repeat(etb.include_chain.len())
+        parts.join(" (") + ")".repeat(etb.include_chain.len()).as_str()
     }
 }
 
@@ -455,6 +1183,8 @@ mod tests {
     use std::fs;
     use std::path::PathBuf;
 
+    use crate::parser::parse_line;
+
     fn create_temp_file(dir: &Path, name: &str, content: &str) -> PathBuf {
         let path = dir.join(name);
         fs::write(&path, content).unwrap();
@@ -596,6 +1326,94 @@ R0 == 0x0001
         ));
     }
 
+    #[test]
+    fn warning_unreachable_after_unconditional_transfer() {
+        let source = "start:\n    JMP #start\n    NOP\n    NOP\nnext:\n    RET\n    ; dead\n    ADD R0, R0, #1\n";
+        let result = assemble_from_source(source, "flow.n1").unwrap();
+
+        let kinds: Vec<_> = result.warnings.iter().map(|w| &w.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &AssembleWarningKind::UnreachableCode {
+                    after: "JMP".to_string(),
+                    after_line: 2,
+                },
+                &AssembleWarningKind::UnreachableCode {
+                    after: "RET".to_string(),
+                    after_line: 6,
+                },
+            ]
+        );
+        assert_eq!(result.warnings[0].location.as_ref().unwrap().line, 3);
+        assert_eq!(result.warnings[1].location.as_ref().unwrap().line, 8);
+        assert_eq!(
+            result.warnings[0].to_string(),
+            "unreachable code: follows JMP on line 2 with no label in between"
+        );
+    }
+
+    #[test]
+    fn warning_duplicate_halt_but_not_code_after_halt() {
+        let source = "MOV R0, #1\nHALT\nADD R0, R0, #1\nHALT\nHALT\n";
+        let result = assemble_from_source(source, "halt.n1").unwrap();
+
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(
+            result.warnings[0].kind,
+            AssembleWarningKind::DuplicateHalt { first_line: 4 }
+        );
+        assert_eq!(result.warnings[0].location.as_ref().unwrap().line, 5);
+    }
+
+    #[test]
+    fn warning_when_code_falls_into_executable_data() {
+        let source = "\
+main:
+    MOV R0, #1
+    BEQ #done
+table:
+    .word 0x0000
+    .word 0x0010
+done:
+    HALT
+msg:
+    .ascii \"hi\"
+";
+        let result = assemble_from_source(source, "data.n1").unwrap();
+
+        assert_eq!(result.warnings.len(), 1, "{:?}", result.warnings);
+        assert_eq!(
+            result.warnings[0].kind,
+            AssembleWarningKind::DataFallthrough {
+                address: 0x0008,
+                after_line: 3,
+                decodes_as: "NOP".to_string(),
+            }
+        );
+        assert_eq!(result.warnings[0].location.as_ref().unwrap().line, 5);
+        assert_eq!(
+            result.warnings[0].to_string(),
+            "execution can fall through from line 3 into data at 0x0008, which decodes as `NOP`"
+        );
+    }
+
+    #[test]
+    fn no_warning_when_fallen_into_data_would_fault() {
+        let source = "MOV R0, #1\n.org 0x0100\n.word 0x0000\nADD R0, R0, #1\n.word 0xFFFF\n";
+        let result = assemble_from_source(source, "data.n1").unwrap();
+
+        assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+    }
+
+    #[test]
+    fn labels_and_data_end_a_dead_run() {
+        let source = "loop:\n    JMP #loop\ntable:\n    .word 1\n    ERET\n    .word 2\n    NOP\n";
+        let result = assemble_from_source(source, "flow.n1").unwrap();
+
+        assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+    }
+
     #[test]
     fn assemble_with_include() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -610,6 +1428,310 @@ R0 == 0x0001
 
         let result = assemble(&main).unwrap();
         assert_eq!(result.binary.len(), 6);
+        assert_eq!(result.sources, vec![main, included]);
+    }
+
+    #[test]
+    fn assemble_with_incbin() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join("sprite.bin"), [0xAA, 0xBB, 0xCC, 0xDD]).unwrap();
+        let content = "JMP #after\nsprite:\n.incbin \"sprite.bin\", 1, 2\nafter:\nHALT\n";
+        let path = create_temp_file(temp_dir.path(), "main.n1", content);
+        let result = assemble(&path).unwrap();
+
+        assert_eq!(&result.binary[4..6], &[0xBB, 0xCC]);
+        let extension = u16::from_be_bytes([result.binary[2], result.binary[3]]);
+        assert_eq!(extension, 0x0002);
+        assert_eq!(result.binary.len(), 8);
+        assert_eq!(result.sources[1], temp_dir.path().join("sprite.bin"));
+    }
+
+    #[test]
+    fn assemble_from_source_rejects_incbin() {
+        let err = assemble_from_source(".incbin \"a.bin\"\n", "mem.n1").unwrap_err();
+        assert!(err.to_string().contains(".incbin not supported"));
+    }
+
+    #[test]
+    fn assemble_with_optimizer_relocates_labels() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let content = "start:\nMOV R2, R2\nADD R1, R1, #0\nMOV R0, #1\nJMP #hop\nhop:\nJMP #end\nend:\nHALT\n";
+        let path = create_temp_file(temp_dir.path(), "opt.n1", content);
+
+        let plain = assemble(&path).unwrap();
+        assert!(plain.optimizations.is_empty());
+
+        let options = AssembleOptions {
+            optimize: true,
+            ..AssembleOptions::default()
+        };
+        let result = assemble_with_options(&path, &options).unwrap();
+
+        assert_eq!(result.binary.len(), plain.binary.len() - 6);
+        assert_eq!(result.symbols.get("end").unwrap().address, 12);
+        let lines: Vec<usize> = result
+            .optimizations
+            .iter()
+            .map(|o| o.location.line)
+            .collect();
+        assert_eq!(lines, vec![2, 3, 5]);
+        assert_eq!(
+            result.optimizations[2].kind,
+            OptimizationKind::JumpThreaded {
+                from: "hop".into(),
+                to: "end".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn equ_constants_resolve_like_labels_but_are_not_symbols() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let content = "MOV R1, #SVC_EXIT\n.equ SVC_EXIT, 0x02\nstart:\nCMP R1, R1, #SVC_EXIT\n.equ SVC_EXIT, 2\n";
+        let path = create_temp_file(temp_dir.path(), "equ.n1", content);
+
+        let result = assemble(&path).unwrap();
+
+        assert_eq!(&result.binary[..4], [0x12, 0x05, 0x00, 0x02]);
+        assert_eq!(&result.binary[6..], [0x00, 0x02]);
+        assert_eq!(result.constants.get("SVC_EXIT"), Some(&2));
+        assert!(!result.symbols.contains_key("SVC_EXIT"));
+        assert!(result.symbols.contains_key("start"));
+
+        let path = create_temp_file(temp_dir.path(), "dup.n1", "start:\n.equ start, 1\n");
+        assert!(assemble(&path).is_err());
+        let path = create_temp_file(temp_dir.path(), "redef.n1", ".equ A, 1\n.equ A, 2\n");
+        assert!(assemble(&path).is_err());
+    }
+
+    #[test]
+    fn standard_layout_guards_vectors_and_reserved_regions() {
+        let layout = |body: &str| format!(".layout standard\n{body}");
+        let violation = |body: &str| match assemble_from_source(&layout(body), "l.n1")
+            .unwrap_err()
+            .kind
+        {
+            AssembleErrorKind::LayoutViolation { start, end, range } => (start, end, range),
+            other => panic!("unexpected error: {other}"),
+        };
+
+        let placed = "JMP #start\n.org 0x0008\n.word 0\n.word 0\n.word 0x0010\n\
+                      .org 0x0010\nstart:\nHALT\n";
+        assert!(assemble_from_source(&layout(placed), "l.n1").is_ok());
+
+        let runs_on = "NOP\nNOP\nNOP\nNOP\nNOP\n";
+        assert!(assemble_from_source(runs_on, "plain.n1").is_ok());
+        assert_eq!(
+            violation(runs_on),
+            (0x0008, 0x0009, ReservedRange::VectorTable)
+        );
+        assert_eq!(
+            violation("HALT\n.org 0xE000\n.word 1\n"),
+            (0xE000, 0xE001, ReservedRange::Region(MemoryRegion::Mmio))
+        );
+
+        let source = layout("HALT\n.org 0x0006\n.word 1\n.word 2\n");
+        let error = assemble_from_source(&source, "l.n1").unwrap_err();
+        assert_eq!(error.location.unwrap().line, 5);
+        assert_eq!(
+            error.kind.to_string(),
+            "bytes 0x0008-0x0009 overlap the vector table (0x0008-0x000D); \
+             place vectors there with .org (.layout standard)"
+        );
+    }
+
+    #[test]
+    fn assemble_with_string_dedup_aliases_labels() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let content =
+            "HALT\ngreet:\n.ascii \"HI\"\n.byte 0\nagain:\n.ascii \"HI\"\n.byte 0\nend:\n.word 7\n";
+        let path = create_temp_file(temp_dir.path(), "dedup.n1", content);
+
+        let plain = assemble(&path).unwrap();
+        assert!(plain.deduplicated_strings.is_empty());
+
+        let options = AssembleOptions {
+            dedup_strings: true,
+            ..AssembleOptions::default()
+        };
+        let result = assemble_with_options(&path, &options).unwrap();
+
+        assert_eq!(result.binary.len(), plain.binary.len() - 3);
+        assert_eq!(result.symbols.get("again").unwrap().address, 2);
+        assert_eq!(result.symbols.get("end").unwrap().address, 5);
+        assert_eq!(result.deduplicated_strings.len(), 1);
+        assert_eq!(result.deduplicated_strings[0].location.line, 5);
+        assert_eq!(result.deduplicated_strings[0].dedup.bytes_saved, 3);
+    }
+
+    #[test]
+    fn entry_directive_sets_start_address() {
+        let source = "vectors:\n.word 0\n.word 0\nmain:\n.entry main\nHALT\n";
+        let result = assemble_from_source(source, "entry.n1").unwrap();
+        assert_eq!(result.entry, Some(4));
+
+        let plain = assemble_from_source("HALT\n", "entry.n1").unwrap();
+        assert_eq!(plain.entry, None);
+    }
+
+    #[test]
+    fn pseudo_ops_encode_declared_mnemonics() {
+        let source = ".pseudo_op ROTX, 0xB, 2\nROTX R1, R2, #4\nROTX\nHALT\n";
+        let result = assemble_from_source(source, "ext.n1").unwrap();
+        assert_eq!(result.binary, [0xB2, 0x94, 0xB0, 0x10, 0x00, 0x10]);
+
+        let err = assemble_from_source("ROTX R1\n.pseudo_op ROTX, 0xB\n", "ext.n1").unwrap_err();
+        assert_eq!(err.to_string(), "parse error: unknown mnemonic: ROTX");
+    }
+
+    #[test]
+    fn info_directives_fill_program_info() {
+        let source = ".title \"Snake\"\n.author \"Ada\"\n.version \"1.2\"\nHALT\n";
+        let result = assemble_from_source(source, "info.n1").unwrap();
+        assert_eq!(result.info.title.as_deref(), Some("Snake"));
+        assert_eq!(result.info.get(InfoField::Author), Some("Ada"));
+        assert_eq!(result.info.version.as_deref(), Some("1.2"));
+        assert_eq!(result.binary, [0x00, 0x10]);
+
+        let plain = assemble_from_source("HALT\n", "info.n1").unwrap();
+        assert!(plain.info.is_empty());
+
+        let err =
+            assemble_from_source(".title \"A\"\nHALT\n.title \"B\"\n", "info.n1").unwrap_err();
+        assert_eq!(
+            err.kind,
+            AssembleErrorKind::DuplicateInfo {
+                field: InfoField::Title,
+                first_line: 1,
+            }
+        );
+        assert_eq!(err.location.unwrap().line, 3);
+    }
+
+    #[test]
+    fn entry_errors_report_undefined_and_duplicate_labels() {
+        let err = assemble_from_source(".entry nowhere\nHALT\n", "entry.n1").unwrap_err();
+        assert_eq!(err.to_string(), "undefined label: nowhere");
+        assert_eq!(err.location.unwrap().line, 1);
+
+        let err = assemble_from_source("a:\n.entry a\nb:\n.entry b\n", "entry.n1").unwrap_err();
+        assert_eq!(
+            err.kind,
+            AssembleErrorKind::DuplicateEntry { first_line: 2 }
+        );
+        assert_eq!(err.location.unwrap().line, 4);
+    }
+
+    #[test]
+    fn entry_option_overrides_directive() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = create_temp_file(
+            temp_dir.path(),
+            "entry.n1",
+            ".entry first\nfirst:\nNOP\nsecond:\nHALT\n",
+        );
+        let options = AssembleOptions {
+            entry: Some("second".into()),
+            ..AssembleOptions::default()
+        };
+
+        assert_eq!(assemble(&path).unwrap().entry, Some(0));
+        assert_eq!(
+            assemble_with_options(&path, &options).unwrap().entry,
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn size_of_tracks_table_contents() {
+        let source = |rows: &str| {
+            format!(
+                "MOV R1, #lengthof(table, table_end)\nMOV R2, #sizeof(table, table_end)\nHALT\n\
+                 table:\n{rows}table_end:\n.word sizeof(table, table_end)\n"
+            )
+        };
+        let word = |binary: &[u8], at: usize| u16::from_be_bytes([binary[at], binary[at + 1]]);
+
+        let result = assemble_from_source(&source(".word 1\n.word 2\n"), "sizes.n1").unwrap();
+        // MOV (4), MOV (4), HALT (2), table (4), size word.
+        assert_eq!(word(&result.binary, 2), 2);
+        assert_eq!(word(&result.binary, 6), 4);
+        assert_eq!(word(&result.binary, 14), 4);
+
+        let result =
+            assemble_from_source(&source(".word 1\n.word 2\n.word 3\n"), "sizes.n1").unwrap();
+        assert_eq!(word(&result.binary, 2), 3);
+        assert_eq!(word(&result.binary, 6), 6);
+        assert_eq!(word(&result.binary, 16), 6);
+
+        let err = assemble_from_source(&source(".byte 1\n"), "sizes.n1").unwrap_err();
+        assert!(err.to_string().contains("odd number of bytes"), "{err}");
+    }
+
+    #[test]
+    fn block_ops_expand_into_loops_that_run_on_the_core() {
+        let source = "\
+```n1asm
+    MOV R0, #0x4000
+    MOV R1, #0x0200
+    MOV R2, #3
+    MEMCPY R0, R1, R2
+    MOV R0, #0x4000
+    MOV R1, #0x0200
+    MOV R2, #3
+    MEMCMP R0, R1, R2
+    MRS R4, FLAGS
+    MOV R0, #0x4000
+    MOV R1, #0x0200
+    MOV R2, #4
+    MEMCMP R0, R1, R2
+    MRS R5, FLAGS
+    HALT
+```
+
+Only three words were copied, so a four-word compare stops at the fourth
+with the flags of `CMP 0x0000, 0x0001`.
+
+```n1test
+[0x4000] == 0x12
+[0x4005] == 0xBC
+R4 == 0x0001
+R0 == 0x4006
+R1 == 0x0206
+R5 == 0x0006
+```
+
+```n1asm
+    .org 0x0200
+    .word 0x1234
+    .word 0x5678
+    .word 0x9ABC
+    .word 0x0001
+```
+";
+        let result = assemble_from_source(source, "block.n1.md").unwrap();
+        let blocks: Vec<_> = result
+            .test_blocks
+            .iter()
+            .map(|tbc| {
+                crate::test_format::parse_test_block(
+                    &tbc.block.content,
+                    tbc.block.start_line,
+                    tbc.block.end_line,
+                )
+                .unwrap()
+            })
+            .collect();
+        let run = crate::test_runner::run_tests(&result.binary, &blocks);
+        assert!(run.all_passed(), "{}", run.summary());
+
+        let first = result.listing.iter().find(|e| e.address == 0x000C).unwrap();
+        assert_eq!(
+            first.source,
+            "CMP R2, R2, #0 ; MEMCPY R0, R1, R2: 14 words, <= 3 + 9 cycles/word"
+        );
+        assert_eq!(first.line, 5);
+        assert!(result.symbols.contains_key(".blk1_loop"));
     }
 
     #[test]
@@ -699,4 +1821,71 @@ loop:
         assert!(!result.binary.is_empty());
         assert!(result.binary.len() <= 0x4000);
     }
+
+    fn expanded(text: &str, line: usize) -> ExpandedLine {
+        ExpandedLine {
+            text: text.to_string(),
+            original_line: line,
+            file_path: PathBuf::from("drift.n1"),
+            include_chain: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn pass2_rejects_size_disagreeing_with_pass1() {
+        let lines = vec![expanded("NOP", 1), expanded("HALT", 2)];
+        let parsed: Vec<ParsedLine> = lines
+            .iter()
+            .map(|l| parse_line(&l.text, l.original_line).unwrap())
+            .collect();
+        let mut assignment = assign_addresses_with_lines(&parsed, 0, &[1, 2]).unwrap();
+        assignment.lines[0].size = 4;
+
+        let err = encode_pass2(&assignment, &lines).unwrap_err();
+        assert_eq!(
+            err.kind,
+            AssembleErrorKind::SizeMismatch {
+                address: 0,
+                expected: 4,
+                actual: 2,
+            }
+        );
+        assert_eq!(err.location.unwrap().line, 1);
+    }
+
+    #[test]
+    fn pass2_rejects_address_drift() {
+        let lines = vec![expanded("NOP", 1), expanded("HALT", 2)];
+        let parsed: Vec<ParsedLine> = lines
+            .iter()
+            .map(|l| parse_line(&l.text, l.original_line).unwrap())
+            .collect();
+        let mut assignment = assign_addresses_with_lines(&parsed, 0, &[1, 2]).unwrap();
+        assignment.lines[1].address = 6;
+
+        let err = encode_pass2(&assignment, &lines).unwrap_err();
+        assert_eq!(
+            err.kind,
+            AssembleErrorKind::AddressDrift {
+                expected: 6,
+                actual: 2,
+            }
+        );
+        assert!(err.to_string().contains("internal consistency error"));
+        assert_eq!(err.location.unwrap().line, 2);
+    }
+
+    #[test]
+    fn convergence_holds_for_mixed_program() {
+        let source =
+            ".word 0x1234\n.byte 1\n.org 0x10\nstart:\nMOV R0, #start\n.ascii \"ok\"\nHALT\n";
+        let result = assemble_from_source(source, "mixed.n1").unwrap();
+        for entry in &result.listing {
+            let offset = usize::from(entry.address);
+            assert_eq!(
+                &result.binary[offset..offset + entry.bytes.len()],
+                entry.bytes.as_slice()
+            );
+        }
+    }
 }
@@ -0,0 +1,306 @@
+//! `MEMCPY` and `MEMCMP` block pseudo-ops.
+//!
+//! `MEMCPY dst, src, count` copies `count` words from `[src]` to `[dst]`, and
+//! `MEMCMP a, b, count` compares `count` words at `[a]` and `[b]`. All three
+//! operands are registers and must be distinct; the core only performs
+//! 16-bit accesses, so counts are in words and addresses should be even.
+//!
+//! Each use is expanded into the canonical loop before addresses are
+//! assigned, so the loop is ordinary code in the binary, listing and source
+//! map. Loop labels are synthetic (`.blkN_loop`, `.blkN_done`) and cannot
+//! clash with user labels. The loop uses R7 (and R6 for `MEMCMP`) as scratch,
+//! so neither may be an operand.
+//!
+//! - After `MEMCPY` both pointers are one word past the block and `count` is
+//!   zero.
+//! - After `MEMCMP`, Z is set when the blocks are equal (or `count` is zero).
+//!   Otherwise the pointers address the first differing words and the flags
+//!   are those of `CMP [a], [b]`, so `BLT`/`BGT` order the blocks.
+//!
+//! Expansions are always CPU loops, never DMA transfers, so their timing
+//! follows the cycle table exactly. [`BlockOp::cost`] reports the expansion
+//! size and a worst-case cycle bound, which the first expanded line carries
+//! into the listing as a comment.
+
+use alloc::{format, string::String, vec, vec::Vec};
+
+use emulator_core::{cycle_cost, CycleCostKind};
+
+use crate::parser::{parse_line, InstructionSize, Operand, ParsedLine, Register};
+
+/// Scratch registers clobbered by block op expansions, as register indices.
+pub const BLOCK_OP_SCRATCH: [u8; 2] = [6, 7];
+
+/// Which block operation a pseudo-op performs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockOpKind {
+    /// `MEMCPY dst, src, count`.
+    Copy,
+    /// `MEMCMP a, b, count`.
+    Compare,
+}
+
+impl BlockOpKind {
+    /// Resolves a mnemonic, case-insensitively.
+    #[must_use]
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Self> {
+        [Self::Copy, Self::Compare]
+            .into_iter()
+            .find(|kind| kind.mnemonic().eq_ignore_ascii_case(mnemonic))
+    }
+
+    /// Returns the upper-case mnemonic.
+    #[must_use]
+    pub const fn mnemonic(self) -> &'static str {
+        match self {
+            Self::Copy => "MEMCPY",
+            Self::Compare => "MEMCMP",
+        }
+    }
+
+    /// Cycle cost kinds of one loop iteration that goes round again.
+    const fn iteration(self) -> &'static [CycleCostKind] {
+        match self {
+            Self::Copy => &[
+                CycleCostKind::Load,
+                CycleCostKind::Store,
+                CycleCostKind::Alu,
+                CycleCostKind::Alu,
+                CycleCostKind::Alu,
+                CycleCostKind::BranchTaken,
+            ],
+            Self::Compare => &[
+                CycleCostKind::Load,
+                CycleCostKind::Load,
+                CycleCostKind::Alu,
+                CycleCostKind::BranchNotTaken,
+                CycleCostKind::Alu,
+                CycleCostKind::Alu,
+                CycleCostKind::Alu,
+                CycleCostKind::BranchTaken,
+            ],
+        }
+    }
+}
+
+/// A parsed `MEMCPY` or `MEMCMP` use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockOp {
+    /// Operation performed.
+    pub kind: BlockOpKind,
+    /// Destination pointer (`MEMCPY`) or first block (`MEMCMP`).
+    pub first: Register,
+    /// Source pointer (`MEMCPY`) or second block (`MEMCMP`).
+    pub second: Register,
+    /// Register holding the word count.
+    pub count: Register,
+}
+
+/// Size and timing of a block op expansion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockOpCost {
+    /// Size of the expanded loop in words.
+    pub words: usize,
+    /// Worst-case cycles spent outside the per-word loop.
+    pub setup_cycles: u32,
+    /// Worst-case cycles per word.
+    pub cycles_per_word: u32,
+}
+
+impl BlockOpCost {
+    /// Upper bound on the cycles taken for a block of `count` words.
+    #[must_use]
+    pub const fn worst_case_cycles(self, count: u16) -> u32 {
+        self.setup_cycles + self.cycles_per_word * count as u32
+    }
+}
+
+impl BlockOp {
+    /// Formats the use as written, e.g. `MEMCPY R0, R1, R2`.
+    #[must_use]
+    pub fn describe(&self) -> String {
+        format!(
+            "{} R{}, R{}, R{}",
+            self.kind.mnemonic(),
+            self.first.0,
+            self.second.0,
+            self.count.0
+        )
+    }
+
+    /// Returns the expansion size and worst-case timing.
+    #[must_use]
+    pub fn cost(&self) -> BlockOpCost {
+        let cycles = |kinds: &[CycleCostKind]| -> u32 {
+            kinds
+                .iter()
+                .filter_map(|kind| cycle_cost(*kind))
+                .map(u32::from)
+                .sum()
+        };
+        let words = self
+            .lines(0)
+            .iter()
+            .map(|(line, _)| match line {
+                ParsedLine::Instruction { instruction } => match instruction.size {
+                    InstructionSize::OneWord => 1,
+                    InstructionSize::TwoWords => 2,
+                },
+                _ => 0,
+            })
+            .sum();
+        BlockOpCost {
+            words,
+            // CMP on the count, then BEQ taken when it is zero.
+            setup_cycles: cycles(&[CycleCostKind::Alu, CycleCostKind::BranchTaken]),
+            cycles_per_word: cycles(self.kind.iteration()),
+        }
+    }
+
+    /// Expands the use into its loop, with the listing text of each line.
+    ///
+    /// `id` numbers the synthetic labels and must be unique per program.
+    /// The first instruction's text ends with a comment giving the
+    /// expansion size and worst-case cycles.
+    #[must_use]
+    pub fn expand(&self, id: usize) -> Vec<(ParsedLine, String)> {
+        let cost = self.cost();
+        let mut lines = self.lines(id);
+        lines[0].1 = format!(
+            "{} ; {}: {} words, <= {} + {} cycles/word",
+            lines[0].1,
+            self.describe(),
+            cost.words,
+            cost.setup_cycles,
+            cost.cycles_per_word
+        );
+        lines
+    }
+
+    fn lines(&self, id: usize) -> Vec<(ParsedLine, String)> {
+        let loop_label = format!(".blk{id}_loop");
+        let done_label = format!(".blk{id}_done");
+        let (first, second, count) = (self.first.0, self.second.0, self.count.0);
+
+        let mut body = match self.kind {
+            BlockOpKind::Copy => vec![
+                format!("LOAD R7, [R{second}]"),
+                format!("STORE R7, [R{first}]"),
+            ],
+            BlockOpKind::Compare => vec![
+                format!("LOAD R7, [R{second}]"),
+                format!("LOAD R6, [R{first}]"),
+                // Register-form CMP takes its second operand from R[SUB] = R7.
+                String::from("CMP R6, R6, R7"),
+                format!("BNE #{done_label}"),
+            ],
+        };
+        body.extend([
+            format!("ADD R{first}, R{first}, #2"),
+            format!("ADD R{second}, R{second}, #2"),
+            format!("SUB R{count}, R{count}, #1"),
+            format!("BNE #{loop_label}"),
+        ]);
+
+        let mut lines = vec![
+            instruction(&format!("CMP R{count}, R{count}, #0")),
+            instruction(&format!("BEQ #{done_label}")),
+            label(loop_label),
+        ];
+        lines.extend(body.iter().map(|text| instruction(text)));
+        lines.push(label(done_label));
+        lines
+    }
+}
+
+/// Parses one line of an expansion; synthetic label operands are patched in
+/// after parsing, since they are not valid source labels.
+fn instruction(text: &str) -> (ParsedLine, String) {
+    let (source, target) = match text.split_once(" #.") {
+        Some((mnemonic, label)) => (format!("{mnemonic} #target"), Some(format!(".{label}"))),
+        None => (String::from(text), None),
+    };
+    let mut parsed = parse_line(&source, 1).expect("block op expansions always parse");
+    if let (ParsedLine::Instruction { instruction }, Some(target)) = (&mut parsed, target) {
+        if let Some(Operand::Immediate(immediate)) = &mut instruction.operand {
+            immediate.label_name = Some(target);
+        }
+    }
+    (parsed, String::from(text))
+}
+
+fn label(name: String) -> (ParsedLine, String) {
+    let text = format!("{name}:");
+    (ParsedLine::Label { name }, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(kind: BlockOpKind) -> BlockOp {
+        BlockOp {
+            kind,
+            first: Register(0),
+            second: Register(1),
+            count: Register(2),
+        }
+    }
+
+    fn texts(op: &BlockOp) -> Vec<String> {
+        op.expand(3).into_iter().map(|(_, text)| text).collect()
+    }
+
+    #[test]
+    fn memcpy_expands_to_a_counted_copy_loop() {
+        assert_eq!(
+            texts(&op(BlockOpKind::Copy)),
+            [
+                "CMP R2, R2, #0 ; MEMCPY R0, R1, R2: 14 words, <= 3 + 9 cycles/word",
+                "BEQ #.blk3_done",
+                ".blk3_loop:",
+                "LOAD R7, [R1]",
+                "STORE R7, [R0]",
+                "ADD R0, R0, #2",
+                "ADD R1, R1, #2",
+                "SUB R2, R2, #1",
+                "BNE #.blk3_loop",
+                ".blk3_done:",
+            ]
+        );
+        let lines = op(BlockOpKind::Copy).expand(3);
+        let ParsedLine::Instruction { instruction } = &lines[1].0 else {
+            panic!("expected BEQ");
+        };
+        assert!(matches!(
+            &instruction.operand,
+            Some(Operand::Immediate(immediate))
+                if immediate.label_name.as_deref() == Some(".blk3_done")
+        ));
+    }
+
+    #[test]
+    fn costs_follow_the_cycle_table() {
+        let copy = op(BlockOpKind::Copy).cost();
+        assert_eq!(copy.words, 14);
+        assert_eq!((copy.setup_cycles, copy.cycles_per_word), (3, 9));
+        assert_eq!(copy.worst_case_cycles(16), 147);
+
+        let compare = op(BlockOpKind::Compare).cost();
+        assert_eq!(compare.words, 17);
+        assert_eq!((compare.setup_cycles, compare.cycles_per_word), (3, 11));
+    }
+
+    #[test]
+    fn mnemonics_resolve_case_insensitively() {
+        assert_eq!(
+            BlockOpKind::from_mnemonic("memcpy"),
+            Some(BlockOpKind::Copy)
+        );
+        assert_eq!(
+            BlockOpKind::from_mnemonic("MEMCMP"),
+            Some(BlockOpKind::Compare)
+        );
+        assert_eq!(BlockOpKind::from_mnemonic("MEMSET"), None);
+    }
+}
@@ -0,0 +1,259 @@
+//! Per-block cycle budgets for editor gutter annotations.
+//!
+//! [`block_budgets`] splits the code of an assembled program into basic
+//! blocks and gives each one a worst-case cycle count from the reference
+//! cycle-cost table, so an editor can show how much of the 640-cycle tick a
+//! stretch of code can use without running it.
+//!
+//! A block starts at the first instruction, at every labelled instruction,
+//! at every branch, jump or call target, and after every instruction that
+//! does not simply fall through. A block that is the target of a backward
+//! branch or jump heads a loop, and also reports the worst case for one
+//! iteration: every block from it up to the last instruction jumping back
+//! to it, with any inner loop counted once. `CALL` is charged for the call
+//! itself, not the routine it calls.
+
+use std::collections::BTreeSet;
+
+use emulator_core::{AddressingMode, DecodedOrFault, Decoder, OpcodeEncoding};
+
+use crate::assembler::{AssembleResult, ListingEntry};
+use crate::parser::{parse_line, ParsedLine};
+use crate::preview::instruction_costs;
+
+/// Worst-case cycle cost of one basic block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockBudget {
+    /// Address of the block's first instruction.
+    pub start: u16,
+    /// Address just past the block's last instruction.
+    pub end: u16,
+    /// Label at `start`, if the source defines one.
+    pub label: Option<String>,
+    /// File containing the block's first instruction.
+    pub file: String,
+    /// 1-indexed line of the block's first instruction.
+    pub line: usize,
+    /// Most cycles one pass through the block can take.
+    pub cycles: u32,
+    /// Most cycles one iteration can take, when the block heads a loop.
+    pub loop_cycles: Option<u32>,
+}
+
+impl BlockBudget {
+    /// Gutter text for the block, e.g. `loop body: 37 cycles/iteration` or
+    /// `main: 12 cycles`.
+    #[must_use]
+    pub fn annotation(&self) -> String {
+        match (self.loop_cycles, &self.label) {
+            (Some(cycles), Some(label)) => format!("{label} body: {cycles} cycles/iteration"),
+            (Some(cycles), None) => format!("loop body: {cycles} cycles/iteration"),
+            (None, Some(label)) => format!("{label}: {} cycles", self.cycles),
+            (None, None) => format!("{} cycles", self.cycles),
+        }
+    }
+}
+
+/// An instruction line of the listing with its decoded control flow.
+struct CodeLine<'a> {
+    entry: &'a ListingEntry,
+    next: u16,
+    cycles: u32,
+    target: Option<u16>,
+    falls_through: bool,
+}
+
+/// Splits the program's code into basic blocks with worst-case cycle
+/// counts, in address order.
+///
+/// Directive lines are data, so `.word` and string bytes never start or
+/// extend a block.
+#[must_use]
+pub fn block_budgets(result: &AssembleResult) -> Vec<BlockBudget> {
+    let code: Vec<CodeLine<'_>> = result.listing.iter().filter_map(code_line).collect();
+
+    let mut leaders: BTreeSet<u16> = code.iter().filter_map(|line| line.target).collect();
+    leaders.extend(result.symbols.values().map(|symbol| symbol.address));
+
+    let mut budgets: Vec<BlockBudget> = Vec::new();
+    for (index, line) in code.iter().enumerate() {
+        let starts_block = index.checked_sub(1).is_none_or(|prev| {
+            let prev = &code[prev];
+            prev.target.is_some() || !prev.falls_through || prev.next != line.entry.address
+        }) || leaders.contains(&line.entry.address);
+        if starts_block {
+            budgets.push(BlockBudget {
+                start: line.entry.address,
+                end: line.next,
+                label: label_at(result, line.entry.address),
+                file: line.entry.file.clone(),
+                line: line.entry.line,
+                cycles: 0,
+                loop_cycles: None,
+            });
+        }
+        if let Some(block) = budgets.last_mut() {
+            block.end = line.next;
+            block.cycles += line.cycles;
+        }
+    }
+
+    for line in &code {
+        let Some(target) = line.target.filter(|target| *target <= line.entry.address) else {
+            continue;
+        };
+        if !budgets.iter().any(|block| block.start == target) {
+            continue;
+        }
+        let iteration = budgets
+            .iter()
+            .filter(|block| (target..=line.entry.address).contains(&block.start))
+            .map(|block| block.cycles)
+            .sum::<u32>();
+        if let Some(head) = budgets.iter_mut().find(|block| block.start == target) {
+            head.loop_cycles = Some(head.loop_cycles.map_or(iteration, |c| c.max(iteration)));
+        }
+    }
+
+    budgets
+}
+
+fn code_line(entry: &ListingEntry) -> Option<CodeLine<'_>> {
+    // Expanded pseudo-op lines name assembler-generated labels the parser
+    // rejects, so anything that is not data, a label or blank is code.
+    if matches!(
+        parse_line(&entry.source, entry.line),
+        Ok(ParsedLine::Blank | ParsedLine::Label { .. } | ParsedLine::Directive { .. })
+    ) {
+        return None;
+    }
+    let word = u16::from_be_bytes([*entry.bytes.first()?, *entry.bytes.get(1)?]);
+    let DecodedOrFault::Instruction(decoded) = Decoder::decode(word) else {
+        return None;
+    };
+    let next = entry
+        .address
+        .checked_add(u16::try_from(entry.bytes.len()).ok()?)?;
+    let mode = decoded.addressing_mode;
+    let is_ret = decoded.encoding == OpcodeEncoding::CallOrRet
+        && mode == Some(AddressingMode::DirectRegister);
+    let transfers = matches!(
+        decoded.encoding,
+        OpcodeEncoding::Beq
+            | OpcodeEncoding::Bne
+            | OpcodeEncoding::Blt
+            | OpcodeEncoding::Ble
+            | OpcodeEncoding::Bgt
+            | OpcodeEncoding::Bge
+            | OpcodeEncoding::Jmp
+            | OpcodeEncoding::CallOrRet
+    );
+    let target = (transfers && !is_ret && mode == Some(AddressingMode::Immediate))
+        .then(|| {
+            let offset = u16::from_be_bytes([*entry.bytes.get(2)?, *entry.bytes.get(3)?]);
+            Some(next.wrapping_add(offset))
+        })
+        .flatten();
+    let falls_through = !is_ret
+        && !matches!(
+            decoded.encoding,
+            OpcodeEncoding::Jmp | OpcodeEncoding::Eret | OpcodeEncoding::Halt
+        );
+    Some(CodeLine {
+        entry,
+        next,
+        cycles: instruction_costs(decoded.encoding, mode)
+            .max()
+            .map_or(0, u32::from),
+        target,
+        falls_through,
+    })
+}
+
+/// The first label at `address` in name order, skipping assembler-generated
+/// local labels.
+fn label_at(result: &AssembleResult, address: u16) -> Option<String> {
+    result
+        .symbols
+        .iter()
+        .filter(|(name, symbol)| symbol.address == address && !name.starts_with('.'))
+        .map(|(name, _)| name.clone())
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble_from_source;
+
+    fn budgets(source: &str) -> Vec<BlockBudget> {
+        block_budgets(&assemble_from_source(source, "budget.n1").unwrap())
+    }
+
+    #[test]
+    fn straight_line_code_is_one_block() {
+        let blocks = budgets("main:\n    MOV R0, #1\n    LOAD R1, [R2]\n    HALT\n");
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].cycles, 1 + 2 + 1);
+        assert_eq!(blocks[0].line, 2);
+        assert_eq!(blocks[0].annotation(), "main: 4 cycles");
+    }
+
+    #[test]
+    fn loops_report_cycles_per_iteration() {
+        let source = "\
+main:
+    MOV R0, #10
+loop:
+    LOAD R1, [R2]
+    ADD R1, R1, #1
+    STORE R1, [R2]
+    SUB R0, R0, #1
+    BNE #loop
+    HALT
+table:
+    .word 0x1234
+";
+        let blocks = budgets(source);
+        let starts: Vec<u16> = blocks.iter().map(|block| block.start).collect();
+        assert_eq!(starts, [0x0000, 0x0004, 0x0014]);
+
+        // LOAD 2 + ADD 1 + STORE 2 + SUB 1 + taken BNE 2.
+        assert_eq!(blocks[1].cycles, 8);
+        assert_eq!(blocks[1].loop_cycles, Some(8));
+        assert_eq!(blocks[1].annotation(), "loop body: 8 cycles/iteration");
+        assert_eq!(blocks[2].loop_cycles, None);
+        assert_eq!(blocks[2].end, 0x0016);
+    }
+
+    #[test]
+    fn loop_iterations_span_every_block_in_the_body() {
+        let source = "\
+spin:
+    CMP R0, R0, #0
+    BEQ #skip
+    OUT R1
+skip:
+    SUB R0, R0, #1
+    JMP #spin
+";
+        let blocks = budgets(source);
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].loop_cycles, Some(1 + 2 + 4 + 1 + 2));
+        assert_eq!(blocks[0].annotation(), "spin body: 10 cycles/iteration");
+        assert_eq!(blocks[2].label.as_deref(), Some("skip"));
+    }
+
+    #[test]
+    fn block_copy_loops_are_unlabelled_loop_bodies() {
+        let blocks = budgets("main:\n    MEMCPY R0, R1, R2\n    HALT\n");
+        let body = blocks
+            .iter()
+            .find(|block| block.loop_cycles.is_some())
+            .unwrap();
+        assert_eq!(body.label, None);
+        // LOAD 2 + STORE 2 + ADD 1 + ADD 1 + SUB 1 + taken BNE 2.
+        assert_eq!(body.loop_cycles, Some(9));
+        assert_eq!(body.annotation(), "loop body: 9 cycles/iteration");
+    }
+}
@@ -0,0 +1,262 @@
+//! One-file program bundles for the web playground.
+//!
+//! A bundle carries everything the playground needs to load and debug a
+//! program without the source tree: the source with every `.include`
+//! inlined (literate prose and test blocks dropped, so it reassembles as a
+//! plain `.n1` file), the assembled binary and entry point, the program's
+//! title, author and version, the source map and the symbol table. Source map lines point into the inlined source.
+//! `.incbin` lines are kept as written; their data is only in the binary.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::artifacts::{json_escape, json_list};
+use crate::assembler::AssembleResult;
+use crate::include::{expand_includes, IncludeError};
+use crate::info::ProgramInfo;
+use crate::reproducible::build_id;
+
+/// `format` tag written into every bundle.
+pub const BUNDLE_FORMAT: &str = "nullbyte-bundle";
+
+/// Bundle layout version; bumped when fields change meaning.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// An emitted line of the bundled program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleLine {
+    /// Address of the line's first byte.
+    pub address: u16,
+    /// Number of bytes the line emitted.
+    pub len_bytes: usize,
+    /// 1-indexed line in [`ProgramBundle::source`].
+    pub line: usize,
+    /// Source text of the line.
+    pub source: String,
+}
+
+/// A program packaged for the playground.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramBundle {
+    /// File name the inlined source should be assembled under.
+    pub name: String,
+    /// Source with includes inlined, one line per expanded source line.
+    pub source: String,
+    /// Assembled binary image.
+    pub binary: Vec<u8>,
+    /// Entry point set by `.entry`, or `None` for 0x0000.
+    pub entry: Option<u16>,
+    /// Title, author and version set by the program.
+    pub info: ProgramInfo,
+    /// Build id of the binary (see [`build_id`]).
+    pub build_id: String,
+    /// Emitted lines in assembly order.
+    pub source_map: Vec<BundleLine>,
+    /// Labels and their addresses, sorted by name.
+    pub symbols: Vec<(String, u16)>,
+}
+
+impl ProgramBundle {
+    /// Bundles `result`, assembled from `input`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`IncludeError`] if the sources can no longer be read.
+    pub fn new(input: &Path, result: &AssembleResult) -> Result<Self, IncludeError> {
+        let expanded = expand_includes(input)?;
+
+        // A file included twice maps to its first copy.
+        let mut inlined = HashMap::new();
+        for (index, line) in expanded.lines.iter().enumerate() {
+            let key = (line.file_path.to_string_lossy(), line.original_line);
+            inlined.entry(key).or_insert(index + 1);
+        }
+        let source_map = result
+            .listing
+            .iter()
+            .filter_map(|entry| {
+                let line = inlined.get(&(entry.file.as_str().into(), entry.line))?;
+                Some(BundleLine {
+                    address: entry.address,
+                    len_bytes: entry.bytes.len(),
+                    line: *line,
+                    source: entry.source.clone(),
+                })
+            })
+            .collect();
+
+        let mut source: String = expanded
+            .lines
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        source.push('\n');
+
+        Ok(Self {
+            name: bundled_name(input),
+            source,
+            binary: result.binary.clone(),
+            entry: result.entry,
+            info: result.info.clone(),
+            build_id: build_id(&result.binary, result.entry),
+            source_map,
+            symbols: result
+                .symbols
+                .iter()
+                .map(|(name, symbol)| (name.clone(), symbol.address))
+                .collect(),
+        })
+    }
+
+    /// Renders the bundle as the JSON the playground loads.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let binary: Vec<String> = self.binary.iter().map(u8::to_string).collect();
+        let source_map: Vec<String> = self
+            .source_map
+            .iter()
+            .map(|line| {
+                format!(
+                    "{{\"address\": {}, \"len_bytes\": {}, \"file\": \"{}\", \"line\": {}, \"source\": \"{}\"}}",
+                    line.address,
+                    line.len_bytes,
+                    json_escape(&self.name),
+                    line.line,
+                    json_escape(&line.source)
+                )
+            })
+            .collect();
+        let symbols: Vec<String> = self
+            .symbols
+            .iter()
+            .map(|(name, address)| {
+                format!(
+                    "{{\"name\": \"{}\", \"address\": {address}}}",
+                    json_escape(name)
+                )
+            })
+            .collect();
+        let text = |value: Option<&String>| {
+            value.map_or_else(
+                || "null".to_string(),
+                |value| format!("\"{}\"", json_escape(value)),
+            )
+        };
+        let info = format!(
+            "{{\"title\": {}, \"author\": {}, \"version\": {}}}",
+            text(self.info.title.as_ref()),
+            text(self.info.author.as_ref()),
+            text(self.info.version.as_ref())
+        );
+        format!(
+            "{{\n  \"format\": \"{BUNDLE_FORMAT}\",\n  \"version\": {BUNDLE_VERSION},\n  \"name\": \"{}\",\n  \"build_id\": \"{}\",\n  \"entry\": {},\n  \"info\": {info},\n  \"source\": \"{}\",\n  \"binary\": [{}],\n  \"source_map\": [{}],\n  \"symbols\": [{}]\n}}\n",
+            json_escape(&self.name),
+            self.build_id,
+            self.entry
+                .map_or_else(|| "null".to_string(), |entry| entry.to_string()),
+            json_escape(&self.source),
+            binary.join(", "),
+            json_list(&source_map),
+            json_list(&symbols)
+        )
+    }
+}
+
+/// `game.n1.md` and `game.n1` both bundle as `game.n1`, since the inlined
+/// source is plain assembly.
+fn bundled_name(input: &Path) -> String {
+    let name = input
+        .file_name()
+        .map_or_else(|| "program".into(), |name| name.to_string_lossy());
+    let stem = name
+        .strip_suffix(".n1.md")
+        .or_else(|| name.strip_suffix(".n1"))
+        .unwrap_or(&name);
+    format!("{stem}.n1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::{assemble, assemble_from_source};
+    use std::fs;
+
+    #[test]
+    fn includes_are_inlined_and_mapped() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("lib.n1"), "work:\nRET\n").unwrap();
+        let main = dir.path().join("game.n1.md");
+        fs::write(
+            &main,
+            "# Game\n\n```n1asm\n.title \"Game\"\nCALL #work\nHALT\n.include \"lib.n1\"\n```\n",
+        )
+        .unwrap();
+
+        let result = assemble(&main).unwrap();
+        let bundle = ProgramBundle::new(&main, &result).unwrap();
+
+        assert_eq!(bundle.name, "game.n1");
+        assert_eq!(
+            bundle.source,
+            ".title \"Game\"\nCALL #work\nHALT\nwork:\nRET\n"
+        );
+        assert_eq!(bundle.info.title.as_deref(), Some("Game"));
+        assert_eq!(bundle.binary, result.binary);
+        assert_eq!(bundle.symbols, [("work".to_string(), 6)]);
+        let lines: Vec<_> = bundle
+            .source_map
+            .iter()
+            .map(|line| (line.address, line.len_bytes, line.line))
+            .collect();
+        assert_eq!(lines, [(0, 4, 2), (4, 2, 3), (6, 2, 5)]);
+
+        let again = assemble_from_source(&bundle.source, &bundle.name).unwrap();
+        assert_eq!(again.binary, bundle.binary);
+    }
+
+    #[test]
+    fn bundle_json_carries_program_and_metadata() {
+        let bundle = ProgramBundle {
+            name: "game.n1".to_string(),
+            source: "start:\nHALT\n".to_string(),
+            binary: vec![0x01, 0x00],
+            entry: Some(0),
+            info: ProgramInfo {
+                title: Some("Game \"1\"".to_string()),
+                author: None,
+                version: Some("1.0".to_string()),
+            },
+            build_id: "00112233aabbccdd".to_string(),
+            source_map: vec![BundleLine {
+                address: 0,
+                len_bytes: 2,
+                line: 2,
+                source: "HALT".to_string(),
+            }],
+            symbols: vec![("start".to_string(), 0)],
+        };
+        let json = bundle.to_json();
+        assert!(json.contains("\"format\": \"nullbyte-bundle\""), "{json}");
+        assert!(json.contains("\"entry\": 0,"), "{json}");
+        assert!(
+            json.contains(
+                "\"info\": {\"title\": \"Game \\\"1\\\"\", \"author\": null, \"version\": \"1.0\"},"
+            ),
+            "{json}"
+        );
+        assert!(
+            json.contains("\"source\": \"start:\\u000aHALT\\u000a\""),
+            "{json}"
+        );
+        assert!(json.contains("\"binary\": [1, 0]"), "{json}");
+        assert!(
+            json.contains("{\"address\": 0, \"len_bytes\": 2, \"file\": \"game.n1\", \"line\": 2, \"source\": \"HALT\"}"),
+            "{json}"
+        );
+        assert!(
+            json.contains("{\"name\": \"start\", \"address\": 0}"),
+            "{json}"
+        );
+    }
+}
@@ -0,0 +1,458 @@
+//! Opt-in calling convention checker.
+//!
+//! The Nullbyte One specification (section 15) defines the calling
+//! convention used by independently written modules:
+//!
+//! | Role          | Registers                 |
+//! | ------------- | ------------------------- |
+//! | Arguments     | R0, R1, R2, R3            |
+//! | Return values | R0 (primary), R1 (second) |
+//! | Caller-saved  | R0..R3, R6, R7, flags     |
+//! | Callee-saved  | R4, R5                    |
+//!
+//! This module statically checks the callee-saved rule on an assembled image.
+//! Every `CALL` target reachable from the reset entry point at 0x0000, the
+//! `.entry` address or a handler in the vector table is treated as a
+//! routine. Each routine's instructions are found by walking its
+//! basic blocks (following branches and jumps, stopping at `RET`), and a
+//! routine that writes R4 or R5 without both pushing and popping that
+//! register is reported.
+//!
+//! The analysis is intentionally conservative about what it follows: indirect
+//! jumps end a path, and callees are assumed to honour the convention.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use emulator_core::{
+    AddressingMode, DecodedOrFault, Decoder, OpcodeEncoding, RegisterField, VEC_EVENT, VEC_FAULT,
+    VEC_TRAP,
+};
+
+use crate::assembler::{AssembleResult, AssembleWarning, AssembleWarningKind, SourceLocation};
+use crate::symbols::SymbolTable;
+
+/// Registers a callee must preserve, as register indices.
+pub const CALLEE_SAVED_REGISTERS: [u8; 2] = [4, 5];
+
+/// Argument registers, as register indices.
+pub const ARGUMENT_REGISTERS: [u8; 4] = [0, 1, 2, 3];
+
+/// Return-value registers (primary first), as register indices.
+pub const RETURN_REGISTERS: [u8; 2] = [0, 1];
+
+/// A routine that clobbers a callee-saved register.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallConvViolation {
+    /// Routine name (label at the entry point, or its hex address).
+    pub routine: String,
+    /// Entry address of the routine.
+    pub entry: u16,
+    /// Register index that is not preserved.
+    pub register: u8,
+    /// Address of the first instruction that modifies the register.
+    pub address: u16,
+}
+
+impl std::fmt::Display for CallConvViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "routine '{}' clobbers callee-saved R{} at 0x{:04X} without a PUSH/POP pair",
+            self.routine, self.register, self.address
+        )
+    }
+}
+
+/// A decoded instruction together with its size and branch target.
+#[derive(Debug, Clone, Copy)]
+struct Instruction {
+    encoding: OpcodeEncoding,
+    addressing_mode: Option<AddressingMode>,
+    rd: Option<RegisterField>,
+    next: u16,
+    target: Option<u16>,
+}
+
+/// Checks every `CALL`-reachable routine for callee-saved register clobbers.
+///
+/// `entry` is the program's `.entry` address, if any. `symbols` is used only
+/// to name routines; routine entry points come from the `CALL` instructions
+/// themselves.
+#[must_use]
+pub fn check_calling_convention(
+    binary: &[u8],
+    entry: Option<u16>,
+    symbols: &SymbolTable,
+) -> Vec<CallConvViolation> {
+    let names = routine_names(symbols);
+
+    let mut violations = Vec::new();
+    for entry in find_routine_entries(binary, entry) {
+        let routine = names
+            .get(&entry)
+            .cloned()
+            .unwrap_or_else(|| format!("0x{entry:04X}"));
+
+        for (register, address) in unpaired_callee_saved_writes(binary, entry) {
+            violations.push(CallConvViolation {
+                routine: routine.clone(),
+                entry,
+                register,
+                address,
+            });
+        }
+    }
+    violations
+}
+
+/// Runs [`check_calling_convention`] on an assembly result and reports each
+/// violation as a warning located at the offending instruction.
+#[must_use]
+pub fn calling_convention_warnings(result: &AssembleResult) -> Vec<AssembleWarning> {
+    check_calling_convention(&result.binary, result.entry, &result.symbols)
+        .into_iter()
+        .map(|violation| {
+            let location = result
+                .listing
+                .iter()
+                .find(|entry| entry.address == violation.address)
+                .map(|entry| SourceLocation {
+                    file: entry.file.clone(),
+                    line: entry.line,
+                    include_chain: entry.location.clone(),
+                });
+            AssembleWarning {
+                kind: AssembleWarningKind::CalleeSavedClobber(violation),
+                location,
+            }
+        })
+        .collect()
+}
+
+/// Returns the lexicographically first label defined at each address.
+fn routine_names(symbols: &SymbolTable) -> BTreeMap<u16, String> {
+    let mut names: BTreeMap<u16, String> = BTreeMap::new();
+    for (name, symbol) in symbols {
+        names
+            .entry(symbol.address)
+            .and_modify(|existing| {
+                if name < &*existing {
+                    existing.clone_from(name);
+                }
+            })
+            .or_insert_with(|| name.clone());
+    }
+    names
+}
+
+/// Walks all code reachable from 0x0000, `entry` and the vector table and
+/// collects `CALL` targets.
+///
+/// The vector table is only read when the code reached from the entry points
+/// does not run through it, so a program that never sets up vectors is not
+/// misread.
+fn find_routine_entries(binary: &[u8], entry: Option<u16>) -> BTreeSet<u16> {
+    let mut entries = BTreeSet::new();
+    let mut visited = BTreeSet::new();
+    let roots: Vec<u16> = std::iter::once(0).chain(entry).collect();
+    walk_calls(binary, roots, &mut visited, &mut entries);
+
+    let table = VEC_TRAP.saturating_sub(2)..=VEC_FAULT + 1;
+    if !visited.iter().any(|pc| table.contains(pc)) {
+        let handlers = [VEC_TRAP, VEC_EVENT, VEC_FAULT]
+            .into_iter()
+            .filter_map(|vector| read_word(binary, vector))
+            .filter(|&handler| handler != 0)
+            .collect();
+        walk_calls(binary, handlers, &mut visited, &mut entries);
+    }
+    entries
+}
+
+/// Walks the code reachable from `worklist`, adding `CALL` targets to
+/// `entries`.
+fn walk_calls(
+    binary: &[u8],
+    mut worklist: Vec<u16>,
+    visited: &mut BTreeSet<u16>,
+    entries: &mut BTreeSet<u16>,
+) {
+    while let Some(pc) = worklist.pop() {
+        if !visited.insert(pc) {
+            continue;
+        }
+        let Some(instr) = decode_at(binary, pc) else {
+            continue;
+        };
+
+        if is_call(&instr) {
+            if let Some(target) = instr.target {
+                entries.insert(target);
+                worklist.push(target);
+            }
+        }
+        worklist.extend(successors(&instr));
+    }
+}
+
+/// Returns `(register, first write address)` for callee-saved registers the
+/// routine at `entry` modifies without a matching PUSH and POP.
+fn unpaired_callee_saved_writes(binary: &[u8], entry: u16) -> Vec<(u8, u16)> {
+    let mut first_write: BTreeMap<u8, u16> = BTreeMap::new();
+    let mut pushed = BTreeSet::new();
+    let mut popped = BTreeSet::new();
+
+    let mut visited = BTreeSet::new();
+    let mut worklist = vec![entry];
+    while let Some(pc) = worklist.pop() {
+        if !visited.insert(pc) {
+            continue;
+        }
+        let Some(instr) = decode_at(binary, pc) else {
+            continue;
+        };
+
+        if let Some(rd) = instr.rd.map(register_index) {
+            match instr.encoding {
+                OpcodeEncoding::Push => {
+                    pushed.insert(rd);
+                }
+                OpcodeEncoding::Pop => {
+                    popped.insert(rd);
+                    first_write.entry(rd).or_insert(pc);
+                }
+                encoding if writes_rd(encoding) => {
+                    first_write.entry(rd).or_insert(pc);
+                }
+                _ => {}
+            }
+        }
+
+        worklist.extend(successors(&instr));
+    }
+
+    CALLEE_SAVED_REGISTERS
+        .iter()
+        .filter(|reg| !(pushed.contains(*reg) && popped.contains(*reg)))
+        .filter_map(|reg| first_write.get(reg).map(|&addr| (*reg, addr)))
+        .collect()
+}
+
+fn decode_at(binary: &[u8], pc: u16) -> Option<Instruction> {
+    let word = read_word(binary, pc)?;
+    let DecodedOrFault::Instruction(decoded) = Decoder::decode(word) else {
+        return None;
+    };
+
+    let has_extension = decoded
+        .addressing_mode
+        .is_some_and(AddressingMode::requires_extension_word);
+    let size: u16 = if has_extension { 4 } else { 2 };
+    let next = pc.checked_add(size)?;
+
+    let target = if decoded.addressing_mode == Some(AddressingMode::Immediate)
+        && is_control_transfer(decoded.encoding)
+    {
+        let offset = read_word(binary, pc.wrapping_add(2))?;
+        Some(next.wrapping_add(offset))
+    } else {
+        None
+    };
+
+    Some(Instruction {
+        encoding: decoded.encoding,
+        addressing_mode: decoded.addressing_mode,
+        rd: decoded.rd,
+        next,
+        target,
+    })
+}
+
+fn read_word(binary: &[u8], addr: u16) -> Option<u16> {
+    let index = usize::from(addr);
+    let bytes = binary.get(index..index + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+/// Returns the addresses execution can reach next within the same routine.
+fn successors(instr: &Instruction) -> Vec<u16> {
+    match instr.encoding {
+        OpcodeEncoding::Jmp => instr.target.into_iter().collect(),
+        OpcodeEncoding::Beq
+        | OpcodeEncoding::Bne
+        | OpcodeEncoding::Blt
+        | OpcodeEncoding::Ble
+        | OpcodeEncoding::Bgt
+        | OpcodeEncoding::Bge => std::iter::once(instr.next).chain(instr.target).collect(),
+        OpcodeEncoding::CallOrRet if !is_call(instr) => Vec::new(),
+        OpcodeEncoding::Eret => Vec::new(),
+        _ => vec![instr.next],
+    }
+}
+
+fn is_call(instr: &Instruction) -> bool {
+    instr.encoding == OpcodeEncoding::CallOrRet
+        && instr.addressing_mode != Some(AddressingMode::DirectRegister)
+}
+
+const fn is_control_transfer(encoding: OpcodeEncoding) -> bool {
+    matches!(
+        encoding,
+        OpcodeEncoding::Jmp
+            | OpcodeEncoding::Beq
+            | OpcodeEncoding::Bne
+            | OpcodeEncoding::Blt
+            | OpcodeEncoding::Ble
+            | OpcodeEncoding::Bgt
+            | OpcodeEncoding::Bge
+            | OpcodeEncoding::CallOrRet
+    )
+}
+
+const fn writes_rd(encoding: OpcodeEncoding) -> bool {
+    matches!(
+        encoding,
+        OpcodeEncoding::Mov
+            | OpcodeEncoding::Mrs
+            | OpcodeEncoding::Load
+            | OpcodeEncoding::Add
+            | OpcodeEncoding::Sub
+            | OpcodeEncoding::And
+            | OpcodeEncoding::Or
+            | OpcodeEncoding::Xor
+            | OpcodeEncoding::Shl
+            | OpcodeEncoding::Shr
+            | OpcodeEncoding::Mul
+            | OpcodeEncoding::Mulh
+            | OpcodeEncoding::Div
+            | OpcodeEncoding::Mod
+            | OpcodeEncoding::Qadd
+            | OpcodeEncoding::Qsub
+            | OpcodeEncoding::Scv
+            | OpcodeEncoding::In
+            | OpcodeEncoding::Eget
+    )
+}
+
+const fn register_index(register: RegisterField) -> u8 {
+    match register {
+        RegisterField::R0 => 0,
+        RegisterField::R1 => 1,
+        RegisterField::R2 => 2,
+        RegisterField::R3 => 3,
+        RegisterField::R4 => 4,
+        RegisterField::R5 => 5,
+        RegisterField::R6 => 6,
+        RegisterField::R7 => 7,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble_from_source;
+    use crate::stdlib::{checkout_dir, STDLIB_MODULES};
+
+    fn check(source: &str) -> Vec<CallConvViolation> {
+        let result = assemble_from_source(source, "callconv.n1").unwrap();
+        check_calling_convention(&result.binary, result.entry, &result.symbols)
+    }
+
+    #[test]
+    fn preserved_registers_pass() {
+        let violations = check("CALL #work\nHALT\nwork:\nPUSH R4\nMOV R4, #1\nPOP R4\nRET\n");
+        assert!(violations.is_empty());
+    }
+
+    #[test]
+    fn clobbered_register_is_reported() {
+        let violations = check("CALL #work\nHALT\nwork:\nMOV R0, #1\nMOV R5, #2\nRET\n");
+        assert_eq!(
+            violations,
+            vec![CallConvViolation {
+                routine: "work".to_string(),
+                entry: 6,
+                register: 5,
+                address: 10,
+            }]
+        );
+        assert!(violations[0].to_string().contains("callee-saved R5"));
+    }
+
+    #[test]
+    fn push_without_pop_is_reported() {
+        let violations = check("CALL #work\nHALT\nwork:\nPUSH R4\nMOV R4, #1\nRET\n");
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].register, 4);
+    }
+
+    #[test]
+    fn clobber_on_branch_path_is_found() {
+        let source =
+            "CALL #work\nHALT\nwork:\nCMP R0, R0, #0\nBEQ #skip\nRET\nskip:\nMOV R4, #9\nRET\n";
+        let violations = check(source);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].register, 4);
+    }
+
+    #[test]
+    fn caller_code_and_nested_calls_are_not_routines() {
+        let source =
+            "MOV R4, #1\nCALL #outer\nHALT\nouter:\nCALL #inner\nRET\ninner:\nMOV R0, #1\nRET\n";
+        assert!(check(source).is_empty());
+    }
+
+    #[test]
+    fn nested_routine_is_checked() {
+        let source = "CALL #outer\nHALT\nouter:\nCALL #inner\nRET\ninner:\nMOV R5, #1\nRET\n";
+        let violations = check(source);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].routine, "inner");
+    }
+
+    #[test]
+    fn routines_reached_from_entry_and_handlers_are_checked() {
+        let source = ".entry start\nHALT\nstart:\nCALL #main_work\nHALT\n\
+                      main_work:\nMOV R4, #1\nRET\n";
+        let violations = check(source);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].routine, "main_work");
+
+        let source = "JMP #start\n.org 0x0008\n.word 0x0000\n.word 0x0010\n\
+                      .org 0x0010\nevent:\nCALL #on_event\nERET\n\
+                      on_event:\nMOV R5, #1\nRET\nstart:\nHALT\n";
+        let violations = check(source);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].routine, "on_event");
+    }
+
+    #[test]
+    fn code_running_through_the_vector_table_is_not_read_as_vectors() {
+        let source = "NOP\nNOP\nNOP\nNOP\nMOV R0, #0x0020\nidle:\nHALT\nJMP #idle\n\
+                      .org 0x0020\nCALL #work\nRET\nwork:\nMOV R4, #1\nRET\n";
+        assert!(check(source).is_empty());
+    }
+
+    #[test]
+    fn warnings_point_at_offending_line() {
+        let source = "CALL #work\nHALT\nwork:\nMOV R4, #1\nRET\n";
+        let result = assemble_from_source(source, "warn.n1").unwrap();
+        let warnings = calling_convention_warnings(&result);
+        assert_eq!(warnings.len(), 1);
+        let location = warnings[0].location.as_ref().unwrap();
+        assert_eq!(location.file, "warn.n1");
+        assert_eq!(location.line, 4);
+        assert!(warnings[0].to_string().contains("'work'"));
+    }
+
+    #[test]
+    fn stdlib_routines_follow_the_convention() {
+        for module in STDLIB_MODULES {
+            let path = checkout_dir().join("tests").join(module.test_file);
+            let result = crate::assembler::assemble(&path).unwrap();
+            let violations =
+                check_calling_convention(&result.binary, result.entry, &result.symbols);
+            assert!(violations.is_empty(), "{}: {violations:?}", module.file);
+        }
+    }
+}
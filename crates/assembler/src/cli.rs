@@ -0,0 +1,1178 @@
+//! Declarative command-line layer for `nullbyte-asm`.
+//!
+//! Every command and option is described once in [`COMMANDS`] and
+//! [`GLOBAL_OPTIONS`]. Argument parsing, help text, "did you mean"
+//! suggestions and the bash/zsh/fish completion scripts are all generated
+//! from those tables, so a new option only needs adding in one place.
+
+use std::ffi::OsString;
+use std::fmt::{self, Write as _};
+
+pub use assembler::artifacts::json_escape;
+use emulator_core::{
+    isa_revision, CORE_VERSION, LATEST_SNAPSHOT_VERSION, OPCODE_ENCODING_TABLE, SERVICES,
+    SNAPSHOT_MAGIC,
+};
+
+/// Name of the installed binary, used in help text and completion scripts.
+pub const BIN_NAME: &str = "nullbyte-asm";
+
+/// One `--long` option, optionally with a `-s` short form and a value.
+#[derive(Debug)]
+pub struct OptionSpec {
+    /// Long name without the leading dashes.
+    pub long: &'static str,
+    /// Single-character short form.
+    pub short: Option<char>,
+    /// Placeholder for the option's value; `None` for flags.
+    pub value: Option<&'static str>,
+    /// One-line description.
+    pub help: &'static str,
+}
+
+/// A subcommand and the options it accepts.
+#[derive(Debug)]
+pub struct CommandSpec {
+    /// Subcommand name as typed.
+    pub name: &'static str,
+    /// One-line description.
+    pub about: &'static str,
+    /// Placeholders for the positional arguments, in order.
+    pub positionals: &'static [&'static str],
+    /// Fixed choices for the positional arguments; empty means file paths.
+    pub positional_values: &'static [&'static str],
+    /// Accepted options, not counting `--help`.
+    pub options: &'static [OptionSpec],
+}
+
+const HELP_OPTION: OptionSpec = OptionSpec {
+    long: "help",
+    short: Some('h'),
+    value: None,
+    help: "Show this help message",
+};
+
+/// Options accepted before the subcommand.
+pub const GLOBAL_OPTIONS: &[OptionSpec] = &[
+    HELP_OPTION,
+    OptionSpec {
+        long: "version",
+        short: Some('V'),
+        value: None,
+        help: "Print the version and build id",
+    },
+    OptionSpec {
+        long: "json",
+        short: None,
+        value: None,
+        help: "With --version, print compatibility info as JSON",
+    },
+    OptionSpec {
+        long: "list-stdlib",
+        short: None,
+        value: None,
+        help: "List bundled standard library modules",
+    },
+    OptionSpec {
+        long: "services-include",
+        short: None,
+        value: None,
+        help: "Print the SWI service numbers as .equ lines",
+    },
+];
+
+/// Every subcommand, in the order help lists them.
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "build",
+        about: "Assemble source to binary",
+        positionals: &["input"],
+        positional_values: &[],
+        options: &[
+            OptionSpec {
+                long: "output",
+                short: Some('o'),
+                value: Some("file"),
+                help: "Output file path (default: input stem + .bin)",
+            },
+            OptionSpec {
+                long: "verbose",
+                short: Some('v'),
+                value: None,
+                help: "Print listing to stderr",
+            },
+            OptionSpec {
+                long: "check-callconv",
+                short: None,
+                value: None,
+                help: "Warn about routines that clobber R4/R5",
+            },
+            OptionSpec {
+                long: "optimize",
+                short: None,
+                value: None,
+                help: "Apply safe peephole optimizations",
+            },
+            OptionSpec {
+                long: "dedup-strings",
+                short: None,
+                value: None,
+                help: "Store identical string data once",
+            },
+            OptionSpec {
+                long: "entry",
+                short: None,
+                value: Some("label"),
+                help: "Start execution at label, overriding .entry",
+            },
+            OptionSpec {
+                long: "reproducible",
+                short: None,
+                value: None,
+                help: "Fail unless the source tree alone reproduces the output",
+            },
+            OptionSpec {
+                long: "map",
+                short: None,
+                value: Some("file"),
+                help: "Write the source map and symbols for trace dump",
+            },
+            OptionSpec {
+                long: "emit",
+                short: None,
+                value: Some("formats"),
+                help: "Also write the image as c-array or rust-include source",
+            },
+            OptionSpec {
+                long: "max-include-depth",
+                short: None,
+                value: Some("n"),
+                help: "Deepest .include nesting allowed (default: 32)",
+            },
+            OptionSpec {
+                long: "stats",
+                short: None,
+                value: None,
+                help: "Report bytes per file and Markdown section, and symbol counts",
+            },
+        ],
+    },
+    CommandSpec {
+        name: "test",
+        about: "Assemble and run inline tests",
+        positionals: &["input"],
+        positional_values: &[],
+        options: &[
+            OptionSpec {
+                long: "capture-fixture",
+                short: None,
+                value: None,
+                help: "Save snapshots for passing capture blocks",
+            },
+            OptionSpec {
+                long: "tag",
+                short: None,
+                value: Some("tags"),
+                help: "Check only blocks with one of these tags",
+            },
+            OptionSpec {
+                long: "skip-tag",
+                short: None,
+                value: Some("tags"),
+                help: "Skip blocks with any of these tags",
+            },
+            OptionSpec {
+                long: "strict",
+                short: None,
+                value: None,
+                help: "Fail blocks that change their frozen: registers",
+            },
+            OptionSpec {
+                long: "watch",
+                short: Some('w'),
+                value: None,
+                help: "Re-run when the source or its includes change",
+            },
+        ],
+    },
+    CommandSpec {
+        name: "verify-determinism",
+        about: "Rerun tests and compare final states",
+        positionals: &["input"],
+        positional_values: &[],
+        options: &[OptionSpec {
+            long: "runs",
+            short: None,
+            value: Some("n"),
+            help: "Fresh runs to compare (default 3)",
+        }],
+    },
+    CommandSpec {
+        name: "cross-check",
+        about: "Compare per-tick state digests of the native and WASM cores",
+        positionals: &["input"],
+        positional_values: &[],
+        options: &[
+            OptionSpec {
+                long: "wasm-pkg",
+                short: None,
+                value: Some("dir"),
+                help: "emulator-wasm package built with wasm-pack --target nodejs",
+            },
+            OptionSpec {
+                long: "events",
+                short: None,
+                value: Some("file"),
+                help: "Script of 'at tick N enqueue event ID' lines",
+            },
+            OptionSpec {
+                long: "ticks",
+                short: None,
+                value: Some("n"),
+                help: "Ticks to compare (default 100)",
+            },
+            OptionSpec {
+                long: "node",
+                short: None,
+                value: Some("path"),
+                help: "Node.js binary (default: node)",
+            },
+        ],
+    },
+    CommandSpec {
+        name: "run",
+        about: "Assemble and run for N ticks",
+        positionals: &["input"],
+        positional_values: &[],
+        options: &[
+            OptionSpec {
+                long: "ticks",
+                short: None,
+                value: Some("n"),
+                help: "Ticks to run (default 100)",
+            },
+            OptionSpec {
+                long: "realtime",
+                short: None,
+                value: None,
+                help: "Pace execution at 100 ticks per second",
+            },
+            OptionSpec {
+                long: "fast-forward",
+                short: None,
+                value: Some("n"),
+                help: "Skip ahead n ticks at full speed before running",
+            },
+            OptionSpec {
+                long: "param",
+                short: None,
+                value: Some("key=value"),
+                help: "Set a host parameter (repeatable)",
+            },
+            OptionSpec {
+                long: "trace",
+                short: None,
+                value: Some("file"),
+                help: "Record every instruction of the --ticks run to file",
+            },
+            OptionSpec {
+                long: "timing",
+                short: None,
+                value: Some("model"),
+                help: "Cycle-cost table: v1 (default) or fast-io",
+            },
+            OptionSpec {
+                long: "open-bus",
+                short: None,
+                value: Some("policy"),
+                help: "Unmapped MMIO reads: zero (default), ones or last",
+            },
+        ],
+    },
+    CommandSpec {
+        name: "verify",
+        about: "Check that a shipped binary rebuilds from source",
+        positionals: &["input"],
+        positional_values: &[],
+        options: &[
+            OptionSpec {
+                long: "binary",
+                short: Some('b'),
+                value: Some("file"),
+                help: "Shipped binary to compare against (required)",
+            },
+            OptionSpec {
+                long: "build-id",
+                short: None,
+                value: Some("id"),
+                help: "Expected build id (default: read <file>.buildid)",
+            },
+            OptionSpec {
+                long: "optimize",
+                short: None,
+                value: None,
+                help: "Rebuild with peephole optimizations",
+            },
+            OptionSpec {
+                long: "dedup-strings",
+                short: None,
+                value: None,
+                help: "Rebuild with string deduplication",
+            },
+            OptionSpec {
+                long: "entry",
+                short: None,
+                value: Some("label"),
+                help: "Rebuild with this entry label",
+            },
+        ],
+    },
+    #[cfg(feature = "script")]
+    CommandSpec {
+        name: "script",
+        about: "Drive a program from a Rhai script",
+        positionals: &["program", "script"],
+        positional_values: &[],
+        options: &[],
+    },
+    CommandSpec {
+        name: "bundle",
+        about: "Package a program as one JSON file for the playground",
+        positionals: &["input"],
+        positional_values: &[],
+        options: &[OptionSpec {
+            long: "output",
+            short: Some('o'),
+            value: Some("file"),
+            help: "Output file path (default: input stem + .bundle.json)",
+        }],
+    },
+    CommandSpec {
+        name: "serve",
+        about: "Rebuild on change and push bundles to the browser over a WebSocket",
+        positionals: &["input"],
+        positional_values: &[],
+        options: &[
+            OptionSpec {
+                long: "watch",
+                short: None,
+                value: Some("dir"),
+                help: "Directory to watch for changes (default: the input's directory)",
+            },
+            OptionSpec {
+                long: "port",
+                short: None,
+                value: Some("n"),
+                help: "Port to listen on (default: 8080)",
+            },
+            OptionSpec {
+                long: "allow-origin",
+                short: None,
+                value: Some("origin"),
+                help: "Also accept browser clients from this origin (repeatable)",
+            },
+        ],
+    },
+    CommandSpec {
+        name: "listing-diff",
+        about: "Compare the listings of two builds",
+        positionals: &["old", "new"],
+        positional_values: &[],
+        options: &[OptionSpec {
+            long: "json",
+            short: None,
+            value: None,
+            help: "Print the differences as JSON",
+        }],
+    },
+    CommandSpec {
+        name: "trace",
+        about: "Print a recorded trace as an annotated execution story",
+        positionals: &["action", "trace"],
+        positional_values: &[],
+        options: &[OptionSpec {
+            long: "map",
+            short: None,
+            value: Some("file"),
+            help: "Program map from build --map for source lines and labels",
+        }],
+    },
+    CommandSpec {
+        name: "devmap",
+        about: "List the MMIO devices attached by run",
+        positionals: &[],
+        positional_values: &[],
+        options: &[OptionSpec {
+            long: "registers",
+            short: None,
+            value: None,
+            help: "Also list each device's registers and bit fields",
+        }],
+    },
+    CommandSpec {
+        name: "corpus",
+        about: "Check or update a golden binary corpus",
+        positionals: &["action", "dir"],
+        positional_values: &[],
+        options: &[],
+    },
+    CommandSpec {
+        name: "isa-suite",
+        about: "Generate the ISA conformance suite",
+        positionals: &["dir"],
+        positional_values: &[],
+        options: &[],
+    },
+    CommandSpec {
+        name: "completions",
+        about: "Print a shell completion script",
+        positionals: &["shell"],
+        positional_values: &["bash", "zsh", "fish"],
+        options: &[],
+    },
+];
+
+const EXAMPLES: &str = "\
+Examples:
+  nullbyte-asm build program.n1.md
+  nullbyte-asm build program.n1.md -o program.bin
+  nullbyte-asm test program.n1.md
+  nullbyte-asm verify-determinism program.n1.md --runs 5
+  nullbyte-asm cross-check program.n1.md --wasm-pkg pkg --events input.events
+  nullbyte-asm run program.n1.md --ticks 500 --realtime
+  nullbyte-asm run program.n1.md --fast-forward 6000 --realtime
+  nullbyte-asm build program.n1.md --reproducible
+  nullbyte-asm verify program.n1.md --binary program.bin
+  nullbyte-asm script program.n1.md experiment.rhai
+  nullbyte-asm bundle program.n1.md -o program.json
+  nullbyte-asm serve program.n1.md --port 8080
+  nullbyte-asm listing-diff old.lst new.lst --json
+  nullbyte-asm run program.n1.md --ticks 2 --trace out.trace
+  nullbyte-asm trace dump out.trace --map program.map
+  nullbyte-asm devmap --registers
+  nullbyte-asm corpus verify crates/assembler/tests/corpus
+  nullbyte-asm isa-suite crates/emulator-core/tests/conformance
+  nullbyte-asm completions bash > /etc/bash_completion.d/nullbyte-asm
+  nullbyte-asm --list-stdlib
+  nullbyte-asm --services-include > services.n1
+";
+
+/// Why argument parsing stopped without producing a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliError {
+    /// `--help` was requested; the payload is the help text to print.
+    Help(String),
+    /// The arguments were invalid.
+    Invalid {
+        /// What was wrong.
+        message: String,
+        /// Command whose help explains the expected form, if any.
+        command: Option<&'static str>,
+    },
+}
+
+impl CliError {
+    fn invalid(message: impl Into<String>, command: Option<&'static str>) -> Self {
+        Self::Invalid {
+            message: message.into(),
+            command,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Help(text) => f.write_str(text),
+            Self::Invalid { message, .. } => f.write_str(message),
+        }
+    }
+}
+
+/// Options and positionals collected for one subcommand.
+#[derive(Debug, Default)]
+pub struct Matches {
+    positionals: Vec<OsString>,
+    flags: Vec<&'static str>,
+    values: Vec<(&'static str, OsString)>,
+    command: Option<&'static str>,
+}
+
+impl Matches {
+    /// Returns true if the flag with this long name was given.
+    pub fn flag(&self, long: &str) -> bool {
+        self.flags.contains(&long)
+    }
+
+    /// Returns the last value given for the option with this long name.
+    pub fn value(&self, long: &str) -> Option<&OsString> {
+        self.values
+            .iter()
+            .rev()
+            .find_map(|(name, value)| (*name == long).then_some(value))
+    }
+
+    /// Returns every value given for the option with this long name, in
+    /// order.
+    pub fn values<'a>(&'a self, long: &'a str) -> impl Iterator<Item = &'a OsString> + 'a {
+        self.values
+            .iter()
+            .filter_map(move |(name, value)| (*name == long).then_some(value))
+    }
+
+    /// Returns the single positional argument.
+    ///
+    /// # Errors
+    ///
+    /// Fails when none or more than one positional was given.
+    pub fn single_positional(&self, what: &str) -> Result<&OsString, CliError> {
+        match self.positionals.as_slice() {
+            [value] => Ok(value),
+            [] => Err(self.error(format!("missing {what}"))),
+            _ => Err(self.error(format!("multiple {what}s provided"))),
+        }
+    }
+
+    /// Returns exactly two positional arguments.
+    ///
+    /// # Errors
+    ///
+    /// Fails when fewer or more than two positionals were given.
+    pub fn positional_pair(&self, what: &str) -> Result<(&OsString, &OsString), CliError> {
+        match self.positionals.as_slice() {
+            [first, second] => Ok((first, second)),
+            [] | [_] => Err(self.error(format!("expected two {what}s"))),
+            _ => Err(self.error(format!("too many {what}s provided"))),
+        }
+    }
+
+    /// Checks that no positional arguments were given.
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first positional argument.
+    pub fn no_positionals(&self) -> Result<(), CliError> {
+        self.positionals.first().map_or(Ok(()), |arg| {
+            Err(self.error(format!("unexpected argument: {}", arg.to_string_lossy())))
+        })
+    }
+
+    /// Builds an error pointing at this subcommand's help.
+    pub fn error(&self, message: impl Into<String>) -> CliError {
+        CliError::invalid(message, self.command)
+    }
+}
+
+/// Looks up a subcommand by name.
+pub fn find_command(name: &str) -> Option<&'static CommandSpec> {
+    COMMANDS.iter().find(|command| command.name == name)
+}
+
+/// Parses the arguments following a subcommand name against its spec.
+///
+/// Accepts `--long value`, `--long=value`, `-s value` and `--` to end
+/// option parsing.
+///
+/// # Errors
+///
+/// Returns [`CliError::Help`] for `--help` and [`CliError::Invalid`] for an
+/// unknown option (with a suggestion when one is close) or a missing value.
+pub fn parse_command(
+    spec: &'static CommandSpec,
+    args: impl IntoIterator<Item = OsString>,
+) -> Result<Matches, CliError> {
+    let mut matches = Matches {
+        command: Some(spec.name),
+        ..Matches::default()
+    };
+    let mut args = args.into_iter();
+    let mut options_done = false;
+
+    while let Some(arg) = args.next() {
+        let text = arg.to_string_lossy().into_owned();
+        if options_done || !text.starts_with('-') || text == "-" {
+            matches.positionals.push(arg);
+            continue;
+        }
+        if text == "--" {
+            options_done = true;
+            continue;
+        }
+        if text == "--help" || text == "-h" {
+            return Err(CliError::Help(command_help(spec)));
+        }
+
+        let (name, inline_value) = match text.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(OsString::from(value))),
+            _ => (text.as_str(), None),
+        };
+        let Some(option) = find_option(spec.options, name) else {
+            return Err(matches.error(unknown_option_message(name, spec.options)));
+        };
+
+        match option.value {
+            Some(_) => {
+                let value = inline_value
+                    .or_else(|| args.next())
+                    .ok_or_else(|| matches.error(format!("missing value for {name}")))?;
+                matches.values.push((option.long, value));
+            }
+            None if inline_value.is_some() => {
+                return Err(matches.error(format!("{name} does not take a value")));
+            }
+            None => matches.flags.push(option.long),
+        }
+    }
+
+    Ok(matches)
+}
+
+fn find_option(options: &'static [OptionSpec], name: &str) -> Option<&'static OptionSpec> {
+    if let Some(long) = name.strip_prefix("--") {
+        return options.iter().find(|option| option.long == long);
+    }
+    let mut chars = name.strip_prefix('-')?.chars();
+    let short = chars.next()?;
+    if chars.next().is_some() {
+        return None;
+    }
+    options.iter().find(|option| option.short == Some(short))
+}
+
+/// Formats an unknown-option error for the top level or a subcommand.
+pub fn unknown_option_message(name: &str, options: &[OptionSpec]) -> String {
+    let candidates = options.iter().map(|option| format!("--{}", option.long));
+    suggest(name, candidates).map_or_else(
+        || format!("unknown option: {name}"),
+        |best| format!("unknown option: {name} (did you mean {best}?)"),
+    )
+}
+
+/// Formats an unknown-command error with the closest command, if any.
+pub fn unknown_command_message(name: &str) -> String {
+    let candidates = COMMANDS.iter().map(|command| command.name.to_string());
+    suggest(name, candidates).map_or_else(
+        || format!("unknown command: {name}"),
+        |best| format!("unknown command: {name} (did you mean {best}?)"),
+    )
+}
+
+/// Picks the candidate closest to `input`, if it is close enough to be a
+/// plausible typo.
+fn suggest(input: &str, candidates: impl Iterator<Item = String>) -> Option<String> {
+    let limit = (input.len() / 3).max(2);
+    candidates
+        .map(|candidate| (edit_distance(input, &candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance over characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Build id baked in at compile time through `NULLBYTE_BUILD_ID`, or `dev`.
+pub fn build_id() -> &'static str {
+    option_env!("NULLBYTE_BUILD_ID").unwrap_or("dev")
+}
+
+/// Text printed by `--version`.
+pub fn version_text() -> String {
+    format!("{BIN_NAME} {} ({})", env!("CARGO_PKG_VERSION"), build_id())
+}
+
+/// Machine-readable compatibility info printed by `--version --json`.
+///
+/// Keys are only ever added, never renamed or removed, so tools can parse
+/// the output of any later build.
+pub fn version_json() -> String {
+    let snapshot_magic = String::from_utf8_lossy(&SNAPSHOT_MAGIC).into_owned();
+    format!(
+        "{{\n\
+        \x20 \"name\": \"{BIN_NAME}\",\n\
+        \x20 \"version\": \"{version}\",\n\
+        \x20 \"build_id\": \"{build_id}\",\n\
+        \x20 \"crates\": {{\n\
+        \x20   \"assembler\": \"{version}\",\n\
+        \x20   \"emulator-core\": \"{CORE_VERSION}\"\n\
+        \x20 }},\n\
+        \x20 \"isa\": {{\n\
+        \x20   \"revision\": \"{revision:016x}\",\n\
+        \x20   \"encodings\": {encodings},\n\
+        \x20   \"services\": {{{services}}}\n\
+        \x20 }},\n\
+        \x20 \"snapshot\": {{\n\
+        \x20   \"magic\": \"{snapshot_magic}\",\n\
+        \x20   \"schema_version\": {schema}\n\
+        \x20 }},\n\
+        \x20 \"formats\": {{\n\
+        \x20   \"source\": [\".n1\", \".n1.md\"],\n\
+        \x20   \"output\": [\".bin\"]\n\
+        \x20 }}\n\
+        }}\n",
+        version = env!("CARGO_PKG_VERSION"),
+        build_id = json_escape(build_id()),
+        revision = isa_revision(),
+        encodings = OPCODE_ENCODING_TABLE.len(),
+        services = services_json(),
+        schema = LATEST_SNAPSHOT_VERSION as u16,
+    )
+}
+
+/// The core's service numbers as JSON object members, in number order.
+fn services_json() -> String {
+    SERVICES
+        .iter()
+        .map(|service| format!("\"{}\": {}", service.name, service.number))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The positional placeholders, each preceded by a space.
+fn positional_labels(command: &CommandSpec) -> String {
+    command
+        .positionals
+        .iter()
+        .fold(String::new(), |mut out, positional| {
+            let _ = write!(out, " <{positional}>");
+            out
+        })
+}
+
+fn option_label(option: &OptionSpec) -> String {
+    let short = option
+        .short
+        .map_or_else(|| "    ".to_string(), |short| format!("-{short}, "));
+    let value = option
+        .value
+        .map(|value| format!(" <{value}>"))
+        .unwrap_or_default();
+    format!("{short}--{}{value}", option.long)
+}
+
+fn write_table(out: &mut String, rows: &[(String, &str)]) {
+    let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    for (label, help) in rows {
+        let _ = writeln!(out, "  {label:<width$}  {help}");
+    }
+}
+
+/// Top-level help listing every command.
+pub fn usage() -> String {
+    let mut out = format!("Usage: {BIN_NAME} <command> [options]\n\nCommands:\n");
+    let commands: Vec<_> = COMMANDS
+        .iter()
+        .map(|command| {
+            (
+                format!("{}{}", command.name, positional_labels(command)),
+                command.about,
+            )
+        })
+        .collect();
+    write_table(&mut out, &commands);
+
+    out.push_str("\nOptions:\n");
+    let options: Vec<_> = GLOBAL_OPTIONS
+        .iter()
+        .map(|option| (option_label(option), option.help))
+        .collect();
+    write_table(&mut out, &options);
+
+    let _ = write!(
+        out,
+        "\nRun `{BIN_NAME} <command> --help` for the options of a command.\n\n{EXAMPLES}"
+    );
+    out
+}
+
+/// Help for one subcommand.
+pub fn command_help(spec: &CommandSpec) -> String {
+    let mut out = format!(
+        "Usage: {BIN_NAME} {}{} [options]\n\n{}\n",
+        spec.name,
+        positional_labels(spec),
+        spec.about
+    );
+    if !spec.positional_values.is_empty() {
+        let _ = writeln!(
+            out,
+            "\n{} is one of: {}",
+            positional_labels(spec).trim_start(),
+            spec.positional_values.join(", ")
+        );
+    }
+    out.push_str("\nOptions:\n");
+    let options: Vec<_> = spec
+        .options
+        .iter()
+        .chain(std::iter::once(&HELP_OPTION))
+        .map(|option| (option_label(option), option.help))
+        .collect();
+    write_table(&mut out, &options);
+    out
+}
+
+/// Shells with generated completion scripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    /// GNU Bash.
+    Bash,
+    /// Z shell.
+    Zsh,
+    /// Friendly interactive shell.
+    Fish,
+}
+
+impl Shell {
+    /// Parses a shell name as accepted by `completions`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bash" => Some(Self::Bash),
+            "zsh" => Some(Self::Zsh),
+            "fish" => Some(Self::Fish),
+            _ => None,
+        }
+    }
+}
+
+/// Generates the completion script for `shell`.
+pub fn completion_script(shell: Shell) -> String {
+    match shell {
+        Shell::Bash => bash_completion(),
+        Shell::Zsh => zsh_completion(),
+        Shell::Fish => fish_completion(),
+    }
+}
+
+fn option_words(options: &[OptionSpec]) -> Vec<String> {
+    options
+        .iter()
+        .chain(std::iter::once(&HELP_OPTION))
+        .flat_map(|option| {
+            option
+                .short
+                .map(|short| format!("-{short}"))
+                .into_iter()
+                .chain(std::iter::once(format!("--{}", option.long)))
+        })
+        .collect()
+}
+
+fn bash_completion() -> String {
+    let top: Vec<String> = COMMANDS
+        .iter()
+        .map(|command| command.name.to_string())
+        .chain(option_words(&GLOBAL_OPTIONS[1..]))
+        .collect();
+    let mut out = format!(
+        "# bash completion for {BIN_NAME}\n\
+         _nullbyte_asm() {{\n\
+        \x20   local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"\n\
+        \x20   if [[ $COMP_CWORD -eq 1 ]]; then\n\
+        \x20       COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n\
+        \x20       return\n\
+        \x20   fi\n\
+        \x20   local opts values\n\
+        \x20   case \"${{COMP_WORDS[1]}}\" in\n",
+        top.join(" ")
+    );
+    for command in COMMANDS {
+        let _ = writeln!(
+            out,
+            "        {}) opts=\"{}\"; values=\"{}\" ;;",
+            command.name,
+            option_words(command.options).join(" "),
+            command.positional_values.join(" ")
+        );
+    }
+    out.push_str(
+        "        *) return ;;\n\
+        \x20   esac\n\
+        \x20   if [[ \"$cur\" == -* ]]; then\n\
+        \x20       COMPREPLY=($(compgen -W \"$opts\" -- \"$cur\"))\n\
+        \x20   elif [[ -n \"$values\" ]]; then\n\
+        \x20       COMPREPLY=($(compgen -W \"$values\" -- \"$cur\"))\n\
+        \x20   else\n\
+        \x20       COMPREPLY=($(compgen -f -- \"$cur\"))\n\
+        \x20   fi\n\
+         }\n",
+    );
+    let _ = writeln!(out, "complete -o filenames -F _nullbyte_asm {BIN_NAME}");
+    out
+}
+
+/// Makes help text safe inside a single-quoted zsh `_arguments` spec.
+fn zsh_escape(text: &str) -> String {
+    text.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn zsh_option_specs(option: &OptionSpec) -> Vec<String> {
+    let help = zsh_escape(option.help);
+    let value = option
+        .value
+        .map(|value| {
+            let action = if value == "file" { "_files" } else { " " };
+            format!(":{value}:{action}")
+        })
+        .unwrap_or_default();
+    option
+        .short
+        .map(|short| format!("-{short}"))
+        .into_iter()
+        .chain(std::iter::once(format!("--{}", option.long)))
+        .map(|name| format!("'{name}[{help}]{value}'"))
+        .collect()
+}
+
+fn zsh_completion() -> String {
+    let mut out = format!(
+        "#compdef {BIN_NAME}\n\n\
+         _nullbyte_asm() {{\n\
+        \x20   local -a commands\n\
+        \x20   commands=(\n"
+    );
+    for command in COMMANDS {
+        let _ = writeln!(
+            out,
+            "        '{}:{}'",
+            command.name,
+            zsh_escape(command.about)
+        );
+    }
+    out.push_str(
+        "    )\n\
+        \x20   if (( CURRENT == 2 )); then\n\
+        \x20       _describe 'command' commands\n",
+    );
+    let globals: Vec<String> = GLOBAL_OPTIONS.iter().flat_map(zsh_option_specs).collect();
+    let _ = writeln!(out, "        _arguments {}", globals.join(" "));
+    out.push_str(
+        "        return\n\
+        \x20   fi\n\
+        \x20   local command=$words[2]\n\
+        \x20   shift words\n\
+        \x20   (( CURRENT-- ))\n\
+        \x20   case $command in\n",
+    );
+    for command in COMMANDS {
+        let _ = writeln!(out, "        {})", command.name);
+        out.push_str("            _arguments");
+        for option in command.options.iter().chain(std::iter::once(&HELP_OPTION)) {
+            for spec in zsh_option_specs(option) {
+                let _ = write!(out, " \\\n                {spec}");
+            }
+        }
+        let action = if command.positional_values.is_empty() {
+            "_files".to_string()
+        } else {
+            format!("({})", command.positional_values.join(" "))
+        };
+        for (index, positional) in command.positionals.iter().enumerate() {
+            let _ = write!(
+                out,
+                " \\\n                '{}:{positional}:{action}'",
+                index + 1
+            );
+        }
+        out.push_str("\n            ;;\n");
+    }
+    out.push_str("    esac\n}\n\n_nullbyte_asm \"$@\"\n");
+    out
+}
+
+/// Makes text safe inside a single-quoted fish string.
+fn fish_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn fish_option_line(condition: &str, option: &OptionSpec) -> String {
+    let mut line = format!("complete -c {BIN_NAME} -n '{condition}'");
+    if let Some(short) = option.short {
+        let _ = write!(line, " -s {short}");
+    }
+    let _ = write!(line, " -l {}", option.long);
+    if option.value.is_some() {
+        line.push_str(" -r");
+    }
+    let _ = write!(line, " -d '{}'", fish_escape(option.help));
+    line
+}
+
+fn fish_completion() -> String {
+    let mut out = format!("# fish completion for {BIN_NAME}\ncomplete -c {BIN_NAME} -f\n");
+    for command in COMMANDS {
+        let _ = writeln!(
+            out,
+            "complete -c {BIN_NAME} -n __fish_use_subcommand -a {} -d '{}'",
+            command.name,
+            fish_escape(command.about)
+        );
+    }
+    for option in GLOBAL_OPTIONS {
+        let _ = writeln!(out, "{}", fish_option_line("__fish_use_subcommand", option));
+    }
+    for command in COMMANDS {
+        let condition = format!("__fish_seen_subcommand_from {}", command.name);
+        for option in command.options.iter().chain(std::iter::once(&HELP_OPTION)) {
+            let _ = writeln!(out, "{}", fish_option_line(&condition, option));
+        }
+        if command.positional_values.is_empty() {
+            let _ = writeln!(out, "complete -c {BIN_NAME} -n '{condition}' -F");
+        } else {
+            let _ = writeln!(
+                out,
+                "complete -c {BIN_NAME} -n '{condition}' -a '{}'",
+                command.positional_values.join(" ")
+            );
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(items: &[&str]) -> Vec<OsString> {
+        items.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn parses_short_long_and_inline_values() {
+        let spec = find_command("build").unwrap();
+        let matches = parse_command(spec, args(&["-o", "a.bin", "in.n1", "--entry=main", "-v"]))
+            .expect("valid arguments");
+
+        assert_eq!(matches.value("output"), Some(&OsString::from("a.bin")));
+        assert_eq!(matches.value("entry"), Some(&OsString::from("main")));
+        assert!(matches.flag("verbose"));
+        assert!(!matches.flag("optimize"));
+        assert_eq!(
+            matches.single_positional("input path").unwrap(),
+            &OsString::from("in.n1")
+        );
+    }
+
+    #[test]
+    fn double_dash_ends_options() {
+        let spec = find_command("test").unwrap();
+        let matches = parse_command(spec, args(&["--", "-odd.n1"])).unwrap();
+        assert_eq!(
+            matches.single_positional("input path").unwrap(),
+            &OsString::from("-odd.n1")
+        );
+    }
+
+    #[test]
+    fn suggests_close_options_and_commands() {
+        let spec = find_command("build").unwrap();
+        let error = parse_command(spec, args(&["in.n1", "--ouptut", "x"])).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unknown option: --ouptut (did you mean --output?)"
+        );
+
+        let error = parse_command(spec, args(&["--zzz"])).unwrap_err();
+        assert_eq!(error.to_string(), "unknown option: --zzz");
+
+        assert_eq!(
+            unknown_command_message("biuld"),
+            "unknown command: biuld (did you mean build?)"
+        );
+        assert_eq!(
+            unknown_command_message("frobnicate"),
+            "unknown command: frobnicate"
+        );
+    }
+
+    #[test]
+    fn reports_missing_values_and_flag_values() {
+        let spec = find_command("run").unwrap();
+        assert_eq!(
+            parse_command(spec, args(&["--ticks"]))
+                .unwrap_err()
+                .to_string(),
+            "missing value for --ticks"
+        );
+        assert_eq!(
+            parse_command(spec, args(&["--realtime=yes"]))
+                .unwrap_err()
+                .to_string(),
+            "--realtime does not take a value"
+        );
+    }
+
+    #[test]
+    fn help_is_generated_from_the_tables() {
+        let spec = find_command("build").unwrap();
+        let Err(CliError::Help(text)) = parse_command(spec, args(&["--help"])) else {
+            panic!("expected help");
+        };
+        assert!(text.starts_with("Usage: nullbyte-asm build <input> [options]"));
+        assert!(text.contains("-o, --output <file>"));
+        assert!(text.contains("--check-callconv"));
+
+        let top = usage();
+        for command in COMMANDS {
+            assert!(top.contains(command.about), "{}", command.name);
+        }
+        assert!(top.contains("-V, --version"));
+    }
+
+    #[test]
+    fn completion_scripts_cover_every_command_and_option() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let script = completion_script(shell);
+            for command in COMMANDS {
+                assert!(script.contains(command.name), "{shell:?}: {}", command.name);
+                for option in command.options {
+                    assert!(script.contains(option.long), "{shell:?}: {}", option.long);
+                }
+            }
+        }
+        assert!(completion_script(Shell::Bash).contains("complete -o filenames -F _nullbyte_asm"));
+        assert!(completion_script(Shell::Zsh).starts_with("#compdef nullbyte-asm"));
+        assert!(completion_script(Shell::Fish).contains("-s o -l output -r"));
+    }
+
+    #[test]
+    fn version_json_reports_compatibility_fields() {
+        let json = version_json();
+        assert!(json.starts_with("{\n  \"name\": \"nullbyte-asm\""));
+        assert!(json.contains(&format!("\"emulator-core\": \"{CORE_VERSION}\"")));
+        assert!(json.contains(&format!("\"revision\": \"{:016x}\"", isa_revision())));
+        assert!(json.contains("\"services\": {\"SVC_NOP\": 0, \"SVC_PUTC\": 1, \"SVC_EXIT\": 2}"));
+        assert!(json.contains("\"magic\": \"N1SN\""));
+        assert!(json.contains("\"schema_version\": 4"));
+        assert!(json.contains("\"source\": [\".n1\", \".n1.md\"]"));
+        assert_eq!(json.matches('{').count(), json.matches('}').count());
+        assert_eq!(json_escape("a\"b\\c\n"), "a\\\"b\\\\c\\u000a");
+    }
+
+    #[test]
+    fn version_includes_crate_version_and_build_id() {
+        let text = version_text();
+        assert!(text.starts_with(&format!("nullbyte-asm {}", env!("CARGO_PKG_VERSION"))));
+        assert!(text.ends_with(&format!("({})", build_id())));
+    }
+}
@@ -0,0 +1,1455 @@
+//! ISA conformance suite generator.
+//!
+//! [`generate_suite`] emits literate `.n1.md` programs with one section per
+//! case, covering every opcode in the encoding table, the operand forms the
+//! assembler can express, flag results and the fault paths. Expected values
+//! are not written by hand: the generator assembles each case, runs it on
+//! the reference core and records what it observed, checking along the way
+//! that every timed case costs exactly what the cycle-cost table says. The
+//! output is runnable with `nullbyte-asm test`, reads as ISA documentation,
+//! and doubles as a conformance kit for alternative implementations.
+//!
+//! The emulator's copy of the suite lives in `emulator-core/tests/conformance/`.
+
+use std::fmt::Write as _;
+
+use emulator_core::encoding::{OpcodeEncoding, OPCODE_ENCODING_TABLE};
+use emulator_core::state::registers::{FLAGS_C, FLAGS_F, FLAGS_I, FLAGS_N, FLAGS_V, FLAGS_Z};
+use emulator_core::{cycle_cost, CoreState, CycleCostKind, FaultCode, GeneralRegister};
+
+use crate::assembler::assemble_from_source;
+use crate::test_format::{parse_source_test_block, Register};
+use crate::test_runner::{new_test_state, run_tests_on_state, TestProgram};
+
+/// One generated suite program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuiteFile {
+    /// File name, such as `alu.n1.md`.
+    pub name: String,
+    /// Literate source with its `n1test` blocks.
+    pub contents: String,
+}
+
+/// What a case's instruction under test is expected to do.
+#[derive(Debug, Clone, Copy)]
+enum Expect {
+    /// Retires and falls through to the code after it.
+    Retire,
+    /// Enters the trap handler.
+    Dispatch,
+    /// Enters the fault handler with this cause.
+    Fault(FaultCode),
+}
+
+/// One section of a suite program.
+#[derive(Debug, Clone, Copy)]
+struct Case {
+    /// Label the section's code starts at; unique within its program.
+    label: &'static str,
+    /// Section heading.
+    title: &'static str,
+    /// Prose under the heading.
+    about: &'static str,
+    /// Encoding the case exercises, if any.
+    encoding: Option<OpcodeEncoding>,
+    expect: Expect,
+    /// Cycle-cost kinds the code spends, checked against the observed
+    /// TICK delta. Empty for untimed cases.
+    timing: &'static [CycleCostKind],
+    /// Lines run before the code under test; label lines are allowed.
+    setup: &'static [&'static str],
+    /// The code under test; label lines are allowed.
+    code: &'static [&'static str],
+    /// Registers to assert.
+    outputs: &'static [Register],
+    /// Memory bytes to assert.
+    memory: &'static [u16],
+    /// Extra `n1test` setup lines, such as scheduled events.
+    test_setup: &'static [&'static str],
+}
+
+const CASE: Case = Case {
+    label: "",
+    title: "",
+    about: "",
+    encoding: None,
+    expect: Expect::Retire,
+    timing: &[],
+    setup: &[],
+    code: &[],
+    outputs: &[],
+    memory: &[],
+    test_setup: &[],
+};
+
+/// One suite program.
+struct Group {
+    name: &'static str,
+    title: &'static str,
+    about: &'static str,
+    cases: &'static [Case],
+}
+
+/// Stack pointer every case starts with.
+const STACK_TOP: u16 = 0xDE00;
+
+const PRELUDE: &str = "\
+Every section starts from reset at its own label with SP at 0xDE00. Timed
+sections read TICK before and after the code under test; `MRS` reads TICK
+before its own cost is added, so the second read is the first plus one `MRS`
+plus the listed costs from the cycle-cost table. FLAGS is read last, after
+the code under test.
+
+The trap, event and fault vectors all point at `dispatch`, which copies
+CAUSE into R0 and halts.
+
+```n1asm
+    HALT
+
+.org 0x0008
+    .word 0x0010
+    .word 0x0010
+    .word 0x0010
+
+.org 0x0010
+dispatch:
+    MRS R0, CAUSE
+    HALT
+```
+";
+
+const GROUPS: &[Group] = &[
+    Group {
+        name: "control",
+        title: "Control",
+        about: "Control instructions (OP=0x0): NOP, SYNC, HALT, TRAP and SWI.",
+        cases: &[
+            Case {
+                label: "nop",
+                title: "NOP",
+                about: "NOP only advances PC.",
+                encoding: Some(OpcodeEncoding::Nop),
+                timing: &[CycleCostKind::Nop],
+                code: &["NOP"],
+                ..CASE
+            },
+            Case {
+                label: "sync",
+                title: "SYNC",
+                about: "SYNC is a visibility barrier with no architectural effect.",
+                encoding: Some(OpcodeEncoding::Sync),
+                timing: &[CycleCostKind::Sync],
+                code: &["SYNC"],
+                ..CASE
+            },
+            Case {
+                label: "halt",
+                title: "HALT",
+                about: "HALT ends the tick with PC at the next instruction, which has not run yet.",
+                encoding: Some(OpcodeEncoding::Halt),
+                setup: &["MOV R1, #0x0001"],
+                code: &["HALT", "MOV R1, #0x0002"],
+                outputs: &[Register::R1, Register::PC],
+                ..CASE
+            },
+            Case {
+                label: "trap",
+                title: "TRAP",
+                about: "TRAP enters the handler at the trap vector.",
+                encoding: Some(OpcodeEncoding::Trap),
+                expect: Expect::Dispatch,
+                code: &["TRAP"],
+                ..CASE
+            },
+            Case {
+                label: "swi",
+                title: "SWI",
+                about: "SWI enters the handler at the trap vector like TRAP.",
+                encoding: Some(OpcodeEncoding::Swi),
+                expect: Expect::Dispatch,
+                code: &["SWI"],
+                ..CASE
+            },
+        ],
+    },
+    Group {
+        name: "mov",
+        title: "Moves",
+        about: "MOV (OP=0x1, SUB=0) in its immediate and register forms, and MRS \
+                (OP=0x1, SUB=1) reading each special register. MOV sets N and Z \
+                from the value moved; MRS leaves FLAGS alone.",
+        cases: &[
+            Case {
+                label: "mov_imm",
+                title: "MOV immediate",
+                encoding: Some(OpcodeEncoding::Mov),
+                timing: &[CycleCostKind::Mov],
+                code: &["MOV R1, #0x1234"],
+                outputs: &[Register::R1],
+                ..CASE
+            },
+            Case {
+                label: "mov_reg",
+                title: "MOV register",
+                encoding: Some(OpcodeEncoding::Mov),
+                timing: &[CycleCostKind::Mov],
+                setup: &["MOV R2, #0xBEEF"],
+                code: &["MOV R1, R2"],
+                outputs: &[Register::R1, Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "mov_zero",
+                title: "MOV sets Z",
+                encoding: Some(OpcodeEncoding::Mov),
+                timing: &[CycleCostKind::Mov],
+                setup: &["MOV R1, #0x0001"],
+                code: &["MOV R1, #0x0000"],
+                outputs: &[Register::R1],
+                ..CASE
+            },
+            Case {
+                label: "mov_negative",
+                title: "MOV sets N",
+                encoding: Some(OpcodeEncoding::Mov),
+                timing: &[CycleCostKind::Mov],
+                code: &["MOV R1, #0x8000"],
+                outputs: &[Register::R1],
+                ..CASE
+            },
+            Case {
+                label: "mrs_flags",
+                title: "MRS FLAGS",
+                about: "The MOV before it leaves Z set.",
+                encoding: Some(OpcodeEncoding::Mrs),
+                timing: &[CycleCostKind::Mrs],
+                setup: &["MOV R1, #0x0000"],
+                code: &["MRS R2, FLAGS"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "mrs_tick",
+                title: "MRS TICK",
+                about: "TICK counts the cycles spent in the current tick.",
+                encoding: Some(OpcodeEncoding::Mrs),
+                timing: &[CycleCostKind::Mrs],
+                code: &["MRS R2, TICK"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "mrs_cap",
+                title: "MRS CAP",
+                about: "CAP lists the capabilities the core was built with.",
+                encoding: Some(OpcodeEncoding::Mrs),
+                timing: &[CycleCostKind::Mrs],
+                code: &["MRS R2, CAP"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "mrs_cause",
+                title: "MRS CAUSE",
+                about: "CAUSE is clear until a trap, event or fault is dispatched.",
+                encoding: Some(OpcodeEncoding::Mrs),
+                timing: &[CycleCostKind::Mrs],
+                code: &["MRS R2, CAUSE"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "mrs_evp",
+                title: "MRS EVP",
+                about: "EVP reports the event queue state.",
+                encoding: Some(OpcodeEncoding::Mrs),
+                timing: &[CycleCostKind::Mrs],
+                code: &["MRS R2, EVP"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "mrs_sp",
+                title: "MRS SP",
+                encoding: Some(OpcodeEncoding::Mrs),
+                timing: &[CycleCostKind::Mrs],
+                code: &["MRS R2, SP"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+        ],
+    },
+    Group {
+        name: "load_store",
+        title: "Loads and Stores",
+        about: "LOAD (OP=0x2) and STORE (OP=0x3) move big-endian words through \
+                `[Rb]` and an absolute `#addr`. Not covered: `[Rb+disp]` and \
+                `[Rb-disp]` assemble to AM=010, which the reference decoder \
+                rejects as an illegal encoding, and auto-increment has no \
+                assembler syntax.",
+        cases: &[
+            Case {
+                label: "load_indirect",
+                title: "LOAD [Rb]",
+                encoding: Some(OpcodeEncoding::Load),
+                timing: &[CycleCostKind::Load],
+                setup: &["MOV R1, #0x4000", "MOV R2, #0x1234", "STORE R2, [R1]"],
+                code: &["LOAD R3, [R1]"],
+                outputs: &[Register::R3],
+                ..CASE
+            },
+            Case {
+                label: "load_abs",
+                title: "LOAD absolute",
+                about: "A loaded word sets N and Z like MOV.",
+                encoding: Some(OpcodeEncoding::Load),
+                timing: &[CycleCostKind::Load],
+                setup: &["MOV R2, #0x8001", "STORE R2, #0x4002"],
+                code: &["LOAD R3, #0x4002"],
+                outputs: &[Register::R3],
+                ..CASE
+            },
+            Case {
+                label: "store_indirect",
+                title: "STORE [Rb]",
+                about: "The high byte is stored first.",
+                encoding: Some(OpcodeEncoding::Store),
+                timing: &[CycleCostKind::Store],
+                setup: &["MOV R1, #0x4000", "MOV R2, #0xA55A"],
+                code: &["STORE R2, [R1]"],
+                memory: &[0x4000, 0x4001],
+                ..CASE
+            },
+            Case {
+                label: "store_abs",
+                title: "STORE absolute",
+                encoding: Some(OpcodeEncoding::Store),
+                timing: &[CycleCostKind::Store],
+                setup: &["MOV R2, #0xC0DE"],
+                code: &["STORE R2, #0x4010"],
+                memory: &[0x4010, 0x4011],
+                ..CASE
+            },
+        ],
+    },
+    Group {
+        name: "alu",
+        title: "ALU",
+        about: "ALU instructions (OP=0x4) in immediate and register form, with \
+                the carry, borrow and overflow edges. In register form the \
+                second operand is `R[SUB]`, the register numbered by the \
+                opcode's SUB field, whatever the third operand names; the \
+                register-form sections name that register so they read the \
+                same either way.",
+        cases: &[
+            Case {
+                label: "add_imm",
+                title: "ADD immediate",
+                encoding: Some(OpcodeEncoding::Add),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0x1234"],
+                code: &["ADD R2, R1, #0x0101"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "add_reg",
+                title: "ADD register",
+                about: "The second operand is R0.",
+                encoding: Some(OpcodeEncoding::Add),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0x1000", "MOV R0, #0x0234"],
+                code: &["ADD R2, R1, R0"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "add_carry",
+                title: "ADD carry",
+                about: "0xFFFF + 1 wraps to zero with carry out.",
+                encoding: Some(OpcodeEncoding::Add),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0xFFFF"],
+                code: &["ADD R2, R1, #0x0001"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "add_overflow",
+                title: "ADD overflow",
+                about: "0x7FFF + 1 carries into the sign bit.",
+                encoding: Some(OpcodeEncoding::Add),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0x7FFF"],
+                code: &["ADD R2, R1, #0x0001"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "sub_imm",
+                title: "SUB immediate",
+                encoding: Some(OpcodeEncoding::Sub),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R2, #0x1234"],
+                code: &["SUB R3, R2, #0x0034"],
+                outputs: &[Register::R3],
+                ..CASE
+            },
+            Case {
+                label: "sub_reg",
+                title: "SUB register",
+                about: "The second operand is R1.",
+                encoding: Some(OpcodeEncoding::Sub),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R2, #0x1234", "MOV R1, #0x0200"],
+                code: &["SUB R3, R2, R1"],
+                outputs: &[Register::R3],
+                ..CASE
+            },
+            Case {
+                label: "sub_borrow",
+                title: "SUB borrow",
+                about: "0 - 1 wraps to 0xFFFF with borrow.",
+                encoding: Some(OpcodeEncoding::Sub),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R2, #0x0000"],
+                code: &["SUB R3, R2, #0x0001"],
+                outputs: &[Register::R3],
+                ..CASE
+            },
+            Case {
+                label: "sub_overflow",
+                title: "SUB overflow",
+                about: "0x8000 - 1 overflows out of the sign bit.",
+                encoding: Some(OpcodeEncoding::Sub),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R2, #0x8000"],
+                code: &["SUB R3, R2, #0x0001"],
+                outputs: &[Register::R3],
+                ..CASE
+            },
+            Case {
+                label: "and_imm",
+                title: "AND immediate",
+                encoding: Some(OpcodeEncoding::And),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0xF0F0"],
+                code: &["AND R3, R1, #0x3C3C"],
+                outputs: &[Register::R3],
+                ..CASE
+            },
+            Case {
+                label: "and_reg",
+                title: "AND register",
+                about: "The second operand is R2; a zero result sets Z.",
+                encoding: Some(OpcodeEncoding::And),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0xF0F0", "MOV R2, #0x0F0F"],
+                code: &["AND R3, R1, R2"],
+                outputs: &[Register::R3],
+                ..CASE
+            },
+            Case {
+                label: "or_imm",
+                title: "OR immediate",
+                encoding: Some(OpcodeEncoding::Or),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0x1200"],
+                code: &["OR R2, R1, #0x0034"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "or_reg",
+                title: "OR register",
+                about: "The second operand is R3.",
+                encoding: Some(OpcodeEncoding::Or),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0x8000", "MOV R3, #0x0001"],
+                code: &["OR R2, R1, R3"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "xor_imm",
+                title: "XOR immediate",
+                encoding: Some(OpcodeEncoding::Xor),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0xFF00"],
+                code: &["XOR R2, R1, #0x0FF0"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "xor_reg",
+                title: "XOR register",
+                about: "The second operand is R4; XOR with itself clears.",
+                encoding: Some(OpcodeEncoding::Xor),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0x5A5A", "MOV R4, #0x5A5A"],
+                code: &["XOR R2, R1, R4"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "shl_imm",
+                title: "SHL immediate",
+                encoding: Some(OpcodeEncoding::Shl),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0x0003"],
+                code: &["SHL R2, R1, #0x0004"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "shl_reg",
+                title: "SHL register",
+                about: "The shift count is R5.",
+                encoding: Some(OpcodeEncoding::Shl),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0x0001", "MOV R5, #0x0008"],
+                code: &["SHL R2, R1, R5"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "shl_carry",
+                title: "SHL carry",
+                about: "The last bit shifted out lands in C.",
+                encoding: Some(OpcodeEncoding::Shl),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0x8001"],
+                code: &["SHL R2, R1, #0x0001"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "shr_imm",
+                title: "SHR immediate",
+                about: "SHR is a logical shift; the sign bit is not copied.",
+                encoding: Some(OpcodeEncoding::Shr),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0x8000"],
+                code: &["SHR R2, R1, #0x0004"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "shr_reg",
+                title: "SHR register",
+                about: "The shift count is R6.",
+                encoding: Some(OpcodeEncoding::Shr),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0x0100", "MOV R6, #0x0008"],
+                code: &["SHR R2, R1, R6"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "shr_carry",
+                title: "SHR carry",
+                about: "The last bit shifted out lands in C.",
+                encoding: Some(OpcodeEncoding::Shr),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0x0001"],
+                code: &["SHR R2, R1, #0x0001"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "cmp_equal",
+                title: "CMP equal",
+                about: "CMP subtracts for flags only; its destination is left alone.",
+                encoding: Some(OpcodeEncoding::Cmp),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0x0005", "MOV R2, #0x1111"],
+                code: &["CMP R2, R1, #0x0005"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "cmp_less",
+                title: "CMP less",
+                about: "The second operand is R7.",
+                encoding: Some(OpcodeEncoding::Cmp),
+                timing: &[CycleCostKind::Alu],
+                setup: &["MOV R1, #0x0003", "MOV R7, #0x0005", "MOV R2, #0x1111"],
+                code: &["CMP R2, R1, R7"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+        ],
+    },
+    Group {
+        name: "math",
+        title: "Math",
+        about: "Math instructions (OP=0x5): multiply, divide and the \
+                saturating helpers. Division by zero does not fault; the \
+                result is zero.",
+        cases: &[
+            Case {
+                label: "mul_imm",
+                title: "MUL immediate",
+                encoding: Some(OpcodeEncoding::Mul),
+                timing: &[CycleCostKind::Mul],
+                setup: &["MOV R1, #0x0012"],
+                code: &["MUL R2, R1, #0x0034"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "mul_reg",
+                title: "MUL register",
+                about: "The second operand is R0.",
+                encoding: Some(OpcodeEncoding::Mul),
+                timing: &[CycleCostKind::Mul],
+                setup: &["MOV R1, #0x0100", "MOV R0, #0x0003"],
+                code: &["MUL R2, R1, R0"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "mul_overflow",
+                title: "MUL keeps the low word",
+                encoding: Some(OpcodeEncoding::Mul),
+                timing: &[CycleCostKind::Mul],
+                setup: &["MOV R1, #0x1234"],
+                code: &["MUL R2, R1, #0x0100"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "mulh",
+                title: "MULH",
+                about: "MULH keeps the high word of the unsigned product.",
+                encoding: Some(OpcodeEncoding::Mulh),
+                timing: &[CycleCostKind::Mul],
+                setup: &["MOV R1, #0x1234"],
+                code: &["MULH R2, R1, #0x0100"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "div",
+                title: "DIV",
+                encoding: Some(OpcodeEncoding::Div),
+                timing: &[CycleCostKind::Div],
+                setup: &["MOV R1, #0x0064"],
+                code: &["DIV R2, R1, #0x0007"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "div_zero",
+                title: "DIV by zero",
+                encoding: Some(OpcodeEncoding::Div),
+                timing: &[CycleCostKind::Div],
+                setup: &["MOV R1, #0x0064", "MOV R2, #0x1111"],
+                code: &["DIV R2, R1, #0x0000"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "mod",
+                title: "MOD",
+                encoding: Some(OpcodeEncoding::Mod),
+                timing: &[CycleCostKind::Div],
+                setup: &["MOV R1, #0x0064"],
+                code: &["MOD R2, R1, #0x0007"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "mod_zero",
+                title: "MOD by zero",
+                encoding: Some(OpcodeEncoding::Mod),
+                timing: &[CycleCostKind::Div],
+                setup: &["MOV R1, #0x0064", "MOV R2, #0x1111"],
+                code: &["MOD R2, R1, #0x0000"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "qadd",
+                title: "QADD",
+                encoding: Some(OpcodeEncoding::Qadd),
+                timing: &[CycleCostKind::SaturatingHelper],
+                setup: &["MOV R1, #0x1000"],
+                code: &["QADD R2, R1, #0x0234"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "qadd_saturate",
+                title: "QADD saturates",
+                about: "Signed overflow clamps to 0x7FFF and sets V.",
+                encoding: Some(OpcodeEncoding::Qadd),
+                timing: &[CycleCostKind::SaturatingHelper],
+                setup: &["MOV R1, #0x7FFF"],
+                code: &["QADD R2, R1, #0x0001"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "qsub_saturate",
+                title: "QSUB saturates",
+                about: "Signed underflow clamps to 0x8000 and sets V.",
+                encoding: Some(OpcodeEncoding::Qsub),
+                timing: &[CycleCostKind::SaturatingHelper],
+                setup: &["MOV R1, #0x8000"],
+                code: &["QSUB R2, R1, #0x0001"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "scv",
+                title: "SCV",
+                encoding: Some(OpcodeEncoding::Scv),
+                timing: &[CycleCostKind::SaturatingHelper],
+                setup: &["MOV R1, #0x8001"],
+                code: &["SCV R2, R1, #0x0000"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+        ],
+    },
+    Group {
+        name: "branch",
+        title: "Branches",
+        about: "Branch instructions (OP=0x6). Each conditional branch is run \
+                once taken and once not taken, jumping over a `MOV R2` so R2 \
+                shows which way it went. A taken branch costs more than a \
+                branch that falls through.",
+        cases: &[
+            Case {
+                label: "beq_taken",
+                title: "BEQ taken",
+                encoding: Some(OpcodeEncoding::Beq),
+                timing: &[CycleCostKind::BranchTaken],
+                setup: &["MOV R2, #0x0000", "MOV R1, #0x0005", "CMP R1, R1, #0x0005"],
+                code: &["BEQ #beq_taken_to", "MOV R2, #0x0001", "beq_taken_to:"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "beq_not_taken",
+                title: "BEQ not taken",
+                encoding: Some(OpcodeEncoding::Beq),
+                timing: &[CycleCostKind::BranchNotTaken, CycleCostKind::Mov],
+                setup: &["MOV R2, #0x0000", "MOV R1, #0x0005", "CMP R1, R1, #0x0003"],
+                code: &[
+                    "BEQ #beq_not_taken_to",
+                    "MOV R2, #0x0001",
+                    "beq_not_taken_to:",
+                ],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "bne_taken",
+                title: "BNE taken",
+                encoding: Some(OpcodeEncoding::Bne),
+                timing: &[CycleCostKind::BranchTaken],
+                setup: &["MOV R2, #0x0000", "MOV R1, #0x0005", "CMP R1, R1, #0x0003"],
+                code: &["BNE #bne_taken_to", "MOV R2, #0x0001", "bne_taken_to:"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "bne_not_taken",
+                title: "BNE not taken",
+                encoding: Some(OpcodeEncoding::Bne),
+                timing: &[CycleCostKind::BranchNotTaken, CycleCostKind::Mov],
+                setup: &["MOV R2, #0x0000", "MOV R1, #0x0005", "CMP R1, R1, #0x0005"],
+                code: &[
+                    "BNE #bne_not_taken_to",
+                    "MOV R2, #0x0001",
+                    "bne_not_taken_to:",
+                ],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "blt_taken",
+                title: "BLT taken",
+                encoding: Some(OpcodeEncoding::Blt),
+                timing: &[CycleCostKind::BranchTaken],
+                setup: &["MOV R2, #0x0000", "MOV R1, #0x0003", "CMP R1, R1, #0x0005"],
+                code: &["BLT #blt_taken_to", "MOV R2, #0x0001", "blt_taken_to:"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "blt_not_taken",
+                title: "BLT not taken",
+                encoding: Some(OpcodeEncoding::Blt),
+                timing: &[CycleCostKind::BranchNotTaken, CycleCostKind::Mov],
+                setup: &["MOV R2, #0x0000", "MOV R1, #0x0005", "CMP R1, R1, #0x0003"],
+                code: &[
+                    "BLT #blt_not_taken_to",
+                    "MOV R2, #0x0001",
+                    "blt_not_taken_to:",
+                ],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "ble_taken",
+                title: "BLE taken",
+                encoding: Some(OpcodeEncoding::Ble),
+                timing: &[CycleCostKind::BranchTaken],
+                setup: &["MOV R2, #0x0000", "MOV R1, #0x0005", "CMP R1, R1, #0x0005"],
+                code: &["BLE #ble_taken_to", "MOV R2, #0x0001", "ble_taken_to:"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "ble_not_taken",
+                title: "BLE not taken",
+                encoding: Some(OpcodeEncoding::Ble),
+                timing: &[CycleCostKind::BranchNotTaken, CycleCostKind::Mov],
+                setup: &["MOV R2, #0x0000", "MOV R1, #0x0005", "CMP R1, R1, #0x0003"],
+                code: &[
+                    "BLE #ble_not_taken_to",
+                    "MOV R2, #0x0001",
+                    "ble_not_taken_to:",
+                ],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "bgt_taken",
+                title: "BGT taken",
+                encoding: Some(OpcodeEncoding::Bgt),
+                timing: &[CycleCostKind::BranchTaken],
+                setup: &["MOV R2, #0x0000", "MOV R1, #0x0005", "CMP R1, R1, #0x0003"],
+                code: &["BGT #bgt_taken_to", "MOV R2, #0x0001", "bgt_taken_to:"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "bgt_not_taken",
+                title: "BGT not taken",
+                encoding: Some(OpcodeEncoding::Bgt),
+                timing: &[CycleCostKind::BranchNotTaken, CycleCostKind::Mov],
+                setup: &["MOV R2, #0x0000", "MOV R1, #0x0005", "CMP R1, R1, #0x0005"],
+                code: &[
+                    "BGT #bgt_not_taken_to",
+                    "MOV R2, #0x0001",
+                    "bgt_not_taken_to:",
+                ],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "bge_taken",
+                title: "BGE taken",
+                encoding: Some(OpcodeEncoding::Bge),
+                timing: &[CycleCostKind::BranchTaken],
+                setup: &["MOV R2, #0x0000", "MOV R1, #0x0005", "CMP R1, R1, #0x0005"],
+                code: &["BGE #bge_taken_to", "MOV R2, #0x0001", "bge_taken_to:"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "bge_not_taken",
+                title: "BGE not taken",
+                encoding: Some(OpcodeEncoding::Bge),
+                timing: &[CycleCostKind::BranchNotTaken, CycleCostKind::Mov],
+                setup: &["MOV R2, #0x0000", "MOV R1, #0x0003", "CMP R1, R1, #0x0005"],
+                code: &[
+                    "BGE #bge_not_taken_to",
+                    "MOV R2, #0x0001",
+                    "bge_not_taken_to:",
+                ],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "jmp",
+                title: "JMP",
+                encoding: Some(OpcodeEncoding::Jmp),
+                timing: &[CycleCostKind::Jump],
+                setup: &["MOV R2, #0x0000"],
+                code: &["JMP #jmp_to", "MOV R2, #0x0001", "jmp_to:"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "call",
+                title: "CALL",
+                about: "CALL pushes the address of the instruction after it.",
+                encoding: Some(OpcodeEncoding::CallOrRet),
+                timing: &[CycleCostKind::Call],
+                setup: &["MOV R2, #0x0000"],
+                code: &["CALL #call_to", "MOV R2, #0x0001", "call_to:"],
+                outputs: &[Register::R2],
+                memory: &[0xDDFE, 0xDDFF],
+                ..CASE
+            },
+            Case {
+                label: "ret",
+                title: "RET",
+                about: "RET pops the return address pushed before it. The setup \
+                        jumps to a CALL placed just before `ret_to`, which pushes \
+                        that address and returns into the timed code.",
+                encoding: Some(OpcodeEncoding::CallOrRet),
+                timing: &[CycleCostKind::Ret],
+                setup: &["MOV R2, #0x0000", "JMP #ret_push", "ret_start:"],
+                code: &[
+                    "RET",
+                    "MOV R2, #0x0001",
+                    "ret_push:",
+                    "CALL #ret_start",
+                    "ret_to:",
+                ],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+        ],
+    },
+    Group {
+        name: "stack",
+        title: "Stack",
+        about: "PUSH and POP (OP=0x7). The stack grows down from SP in \
+                big-endian words.",
+        cases: &[
+            Case {
+                label: "push",
+                title: "PUSH",
+                encoding: Some(OpcodeEncoding::Push),
+                timing: &[CycleCostKind::Push],
+                setup: &["MOV R1, #0x1234"],
+                code: &["PUSH R1"],
+                memory: &[0xDDFE, 0xDDFF],
+                ..CASE
+            },
+            Case {
+                label: "pop",
+                title: "POP",
+                about: "A popped word sets N and Z like MOV.",
+                encoding: Some(OpcodeEncoding::Pop),
+                timing: &[CycleCostKind::Pop],
+                setup: &["MOV R1, #0x8000", "PUSH R1", "MOV R1, #0x0001"],
+                code: &["POP R2"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+        ],
+    },
+    Group {
+        name: "mmio",
+        title: "MMIO",
+        about: "MMIO instructions (OP=0x8 and OP=0x9) against the TELE-7 \
+                registers at 0xE120. ID reads as 0x0745 and BORDER at 0xE125 \
+                keeps the low three bits written to it. IN and OUT take the \
+                address from Ra, and OUT writes Ra itself, so `OUT R1, R1` \
+                with R1 = 0xE125 stores 5 in BORDER. The bit operations take \
+                their bit number from the extension word, which only the \
+                immediate form has, and that form also uses the immediate as \
+                the address; the register-indirect sections below therefore \
+                act on bit 0 of BORDER. BTEST sets Z when the bit is clear.",
+        cases: &[
+            Case {
+                label: "in",
+                title: "IN",
+                encoding: Some(OpcodeEncoding::In),
+                timing: &[CycleCostKind::MmioIn],
+                setup: &["MOV R1, #0xE120"],
+                code: &["IN R2, R1"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "out",
+                title: "OUT",
+                about: "The written value is read back with IN.",
+                encoding: Some(OpcodeEncoding::Out),
+                timing: &[CycleCostKind::MmioOut, CycleCostKind::MmioIn],
+                setup: &["MOV R1, #0xE125"],
+                code: &["OUT R1, R1", "IN R2, R1"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "bset",
+                title: "BSET",
+                encoding: Some(OpcodeEncoding::Bset),
+                timing: &[CycleCostKind::MmioBitSet, CycleCostKind::MmioIn],
+                setup: &["MOV R1, #0xE125", "OUT R1, R1", "BCLR R1, [R1]"],
+                code: &["BSET R1, [R1]", "IN R2, R1"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "bclr",
+                title: "BCLR",
+                encoding: Some(OpcodeEncoding::Bclr),
+                timing: &[CycleCostKind::MmioBitClear, CycleCostKind::MmioIn],
+                setup: &["MOV R1, #0xE125", "OUT R1, R1"],
+                code: &["BCLR R1, [R1]", "IN R2, R1"],
+                outputs: &[Register::R2],
+                ..CASE
+            },
+            Case {
+                label: "btest_set",
+                title: "BTEST set bit",
+                encoding: Some(OpcodeEncoding::Btest),
+                timing: &[CycleCostKind::MmioBitTest],
+                setup: &["MOV R1, #0xE125", "OUT R1, R1"],
+                code: &["BTEST R1, [R1]"],
+                ..CASE
+            },
+            Case {
+                label: "btest_clear",
+                title: "BTEST clear bit",
+                encoding: Some(OpcodeEncoding::Btest),
+                timing: &[CycleCostKind::MmioBitTest],
+                setup: &["MOV R1, #0xE125", "OUT R1, R1", "BCLR R1, [R1]"],
+                code: &["BTEST R1, [R1]"],
+                ..CASE
+            },
+        ],
+    },
+    Group {
+        name: "event",
+        title: "Events",
+        about: "Event instructions (OP=0xA). The runner enqueues the event \
+                before the section's first tick.",
+        cases: &[
+            Case {
+                label: "eget_empty",
+                title: "EGET empty queue",
+                about: "EGET returns 0 when the queue is empty.",
+                encoding: Some(OpcodeEncoding::Eget),
+                timing: &[CycleCostKind::Eget],
+                setup: &["MOV R1, #0x1111"],
+                code: &["EGET R1"],
+                outputs: &[Register::R1],
+                ..CASE
+            },
+            Case {
+                label: "eget_event",
+                title: "EGET",
+                encoding: Some(OpcodeEncoding::Eget),
+                timing: &[CycleCostKind::Eget],
+                code: &["EGET R1"],
+                outputs: &[Register::R1],
+                test_setup: &["at tick 0 enqueue event 0x2A"],
+                ..CASE
+            },
+            Case {
+                label: "ewait",
+                title: "EWAIT",
+                about: "EWAIT falls through once the queue holds an event.",
+                encoding: Some(OpcodeEncoding::Ewait),
+                timing: &[CycleCostKind::Ewait, CycleCostKind::Eget],
+                code: &["EWAIT", "EGET R1"],
+                outputs: &[Register::R1],
+                test_setup: &["at tick 0 enqueue event 0x2A"],
+                ..CASE
+            },
+        ],
+    },
+    Group {
+        name: "faults",
+        title: "Faults",
+        about: "Fault paths that enter the fault handler, which leaves the \
+                fault cause in R0. Faults raised while decoding, such as \
+                illegal encodings, latch the core instead of entering the \
+                handler, so an `n1test` block cannot observe them; the \
+                emulator's unit tests cover those.",
+        cases: &[Case {
+            label: "eret_outside_handler",
+            title: "ERET outside a handler",
+            encoding: Some(OpcodeEncoding::Eret),
+            expect: Expect::Fault(FaultCode::HandlerContextViolation),
+            code: &["ERET"],
+            ..CASE
+        }],
+    },
+];
+
+/// What a case left behind when run on the reference core.
+struct Observed {
+    state: CoreState,
+    /// Registers holding TICK before and after, and FLAGS, for timed cases.
+    probes: Option<Probes>,
+}
+
+#[derive(Clone, Copy)]
+struct Probes {
+    before: Register,
+    after: Register,
+    flags: Register,
+}
+
+/// Generates the suite, running every case on the reference core to fill
+/// in its expected values.
+///
+/// # Errors
+///
+/// Fails when a case does not assemble, does not reach its HALT, faults
+/// with the wrong cause, or spends a different number of cycles than the
+/// cycle-cost table says.
+pub fn generate_suite() -> Result<Vec<SuiteFile>, String> {
+    GROUPS
+        .iter()
+        .map(|group| {
+            let name = format!("{}.n1.md", group.name);
+            let observed = observe(group).map_err(|err| format!("{name}: {err}"))?;
+            Ok(SuiteFile {
+                contents: render(group, Some(&observed)).map_err(|err| format!("{name}: {err}"))?,
+                name,
+            })
+        })
+        .collect()
+}
+
+fn observe(group: &Group) -> Result<Vec<Observed>, String> {
+    let source = render(group, None)?;
+    let result = assemble_from_source(&source, &format!("{}.n1.md", group.name))
+        .map_err(|err| err.to_string())?;
+    let program = TestProgram::of(&result);
+    group
+        .cases
+        .iter()
+        .zip(&result.test_blocks)
+        .map(|(case, context)| {
+            let block = parse_source_test_block(&context.block)
+                .map_err(|err| format!("{}: {}", case.label, err.text))?;
+            let mut state = new_test_state(&program);
+            let run = run_tests_on_state(&mut state, &program, std::slice::from_ref(&block));
+            if let Some(failed) = run.block_results.iter().find(|r| r.faulted) {
+                return Err(format!(
+                    "{}: {}",
+                    case.label,
+                    failed.fault_message.as_deref().unwrap_or("did not halt")
+                ));
+            }
+            let observed = Observed {
+                probes: probes(case),
+                state,
+            };
+            check(case, &observed)?;
+            Ok(observed)
+        })
+        .collect()
+}
+
+/// Checks a run against the case's expectation.
+fn check(case: &Case, observed: &Observed) -> Result<(), String> {
+    if let Expect::Fault(code) = case.expect {
+        let reported = read(&observed.state, Register::R0);
+        if reported != u16::from(code.as_u8()) {
+            return Err(format!(
+                "{}: expected {code:?} (0x{:02X}), CAUSE was 0x{reported:04X}",
+                case.label,
+                code.as_u8()
+            ));
+        }
+    }
+    if let Some(probes) = observed.probes {
+        let before = read(&observed.state, probes.before);
+        let after = read(&observed.state, probes.after);
+        let expected = before.wrapping_add(spent(case));
+        if after != expected {
+            return Err(format!(
+                "{}: TICK advanced by {}, the cycle-cost table says {}",
+                case.label,
+                after.wrapping_sub(before),
+                spent(case)
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Cycles between the two TICK reads: the first `MRS` plus the code.
+fn spent(case: &Case) -> u16 {
+    std::iter::once(CycleCostKind::Mrs)
+        .chain(case.timing.iter().copied())
+        .map(|kind| cycle_cost(kind).map_or(0, u16::from))
+        .sum()
+}
+
+/// Picks the probe registers for a timed case: the highest three the case
+/// does not mention.
+fn probes(case: &Case) -> Option<Probes> {
+    if case.timing.is_empty() {
+        return None;
+    }
+    let text = case
+        .setup
+        .iter()
+        .chain(case.code)
+        .copied()
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut free = GeneralRegister::ALL
+        .iter()
+        .rev()
+        .map(|register| register.index())
+        .filter(|index| !text.contains(&format!("R{index}")))
+        .map(register_of);
+    Some(Probes {
+        before: free.next()?,
+        after: free.next()?,
+        flags: free.next()?,
+    })
+}
+
+/// Renders a suite program; without observations its test blocks carry
+/// only their setup lines.
+fn render(group: &Group, observed: Option<&[Observed]>) -> Result<String, String> {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}\n", group.title);
+    let _ = writeln!(
+        out,
+        "Generated by `nullbyte-asm isa-suite`; regenerate rather than edit.\n"
+    );
+    let _ = writeln!(out, "{}\n", wrap(group.about));
+    out.push_str(PRELUDE);
+    for (index, case) in group.cases.iter().enumerate() {
+        let observed = observed.map(|observed| &observed[index]);
+        render_case(&mut out, case, observed)?;
+    }
+    Ok(out)
+}
+
+fn render_case(out: &mut String, case: &Case, observed: Option<&Observed>) -> Result<(), String> {
+    let probes = probes(case);
+    if !case.timing.is_empty() && probes.is_none() {
+        return Err(format!("{}: no free registers to probe TICK", case.label));
+    }
+    let _ = writeln!(out, "\n## {}\n", case.title);
+    if !case.about.is_empty() {
+        let _ = writeln!(out, "{}\n", wrap(case.about));
+    }
+    if let Some((op, sub, _)) = OPCODE_ENCODING_TABLE
+        .iter()
+        .find(|(_, _, encoding)| Some(*encoding) == case.encoding)
+    {
+        let _ = writeln!(out, "Encoding: OP=0x{op:X}, SUB={sub}.\n");
+    }
+    match case.expect {
+        Expect::Retire if case.timing.is_empty() => {}
+        Expect::Retire => {
+            let costs: Vec<String> = case
+                .timing
+                .iter()
+                .map(|kind| format!("{kind:?} {}", cycle_cost(*kind).unwrap_or(0)))
+                .collect();
+            let _ = writeln!(out, "Cycles: {}.\n", costs.join(", "));
+        }
+        Expect::Dispatch => {
+            let _ = writeln!(
+                out,
+                "Untimed: the handler does not return to the section.\n"
+            );
+        }
+        Expect::Fault(code) => {
+            let _ = writeln!(
+                out,
+                "Faults with {code:?} (CAUSE 0x{:02X}).\n",
+                code.as_u8()
+            );
+        }
+    }
+
+    out.push_str("```n1asm\n");
+    let _ = writeln!(out, "{}:", case.label);
+    let write_line = |out: &mut String, line: &str| {
+        let indent = if line.ends_with(':') { "" } else { "    " };
+        let _ = writeln!(out, "{indent}{line}");
+    };
+    for line in case.setup {
+        write_line(out, line);
+    }
+    if let Some(probes) = probes {
+        let _ = writeln!(out, "    MRS {:?}, TICK", probes.before);
+    }
+    for line in case.code {
+        write_line(out, line);
+    }
+    if let Some(probes) = probes {
+        let _ = writeln!(out, "    MRS {:?}, TICK", probes.after);
+        let _ = writeln!(out, "    MRS {:?}, FLAGS", probes.flags);
+    }
+    out.push_str("    HALT\n```\n\n");
+
+    out.push_str("```n1test\n");
+    let _ = writeln!(out, "reset: true");
+    let _ = writeln!(out, "start at {}", case.label);
+    let _ = writeln!(out, "sp = 0x{STACK_TOP:04X}");
+    for line in case.test_setup {
+        let _ = writeln!(out, "{line}");
+    }
+    if let Some(observed) = observed {
+        render_assertions(out, case, observed);
+    }
+    out.push_str("```\n");
+    Ok(())
+}
+
+fn render_assertions(out: &mut String, case: &Case, observed: &Observed) {
+    let state = &observed.state;
+    let mut outputs = case.outputs.to_vec();
+    if !matches!(case.expect, Expect::Retire) {
+        outputs.insert(0, Register::R0);
+        outputs.push(Register::PC);
+    }
+    for register in outputs {
+        let _ = writeln!(out, "{register:?} == 0x{:04X}", read(state, register));
+    }
+    for address in case.memory {
+        let _ = writeln!(
+            out,
+            "[0x{address:04X}] == 0x{:02X}",
+            state.memory[usize::from(*address)]
+        );
+    }
+    if let Some(probes) = observed.probes {
+        let before = read(state, probes.before);
+        let _ = writeln!(out, "{:?} == 0x{before:04X}", probes.before);
+        let _ = writeln!(
+            out,
+            "{:?} == 0x{:04X} ; TICK + {}",
+            probes.after,
+            read(state, probes.after),
+            spent(case)
+        );
+        let flags = read(state, probes.flags);
+        let _ = writeln!(
+            out,
+            "{:?} == 0x{flags:04X} ; FLAGS {}",
+            probes.flags,
+            flag_names(flags)
+        );
+    }
+}
+
+fn flag_names(flags: u16) -> String {
+    let names: Vec<&str> = [
+        (FLAGS_Z, "Z"),
+        (FLAGS_N, "N"),
+        (FLAGS_C, "C"),
+        (FLAGS_V, "V"),
+        (FLAGS_I, "I"),
+        (FLAGS_F, "F"),
+    ]
+    .iter()
+    .filter(|(bit, _)| flags & bit != 0)
+    .map(|(_, name)| *name)
+    .collect();
+    if names.is_empty() {
+        "clear".to_string()
+    } else {
+        names.join(" ")
+    }
+}
+
+/// Wraps prose at 76 columns.
+fn wrap(text: &str) -> String {
+    let mut out = String::new();
+    let mut width = 0;
+    for word in text.split_whitespace() {
+        if width > 0 && width + 1 + word.len() > 76 {
+            out.push('\n');
+            width = 0;
+        } else if width > 0 {
+            out.push(' ');
+            width += 1;
+        }
+        out.push_str(word);
+        width += word.len();
+    }
+    out
+}
+
+const fn register_of(index: usize) -> Register {
+    match index {
+        0 => Register::R0,
+        1 => Register::R1,
+        2 => Register::R2,
+        3 => Register::R3,
+        4 => Register::R4,
+        5 => Register::R5,
+        6 => Register::R6,
+        _ => Register::R7,
+    }
+}
+
+const fn read(state: &CoreState, register: Register) -> u16 {
+    let index = match register {
+        Register::PC => return state.arch.pc(),
+        Register::R0 => 0,
+        Register::R1 => 1,
+        Register::R2 => 2,
+        Register::R3 => 3,
+        Register::R4 => 4,
+        Register::R5 => 5,
+        Register::R6 => 6,
+        Register::R7 => 7,
+    };
+    state.arch.gpr(GeneralRegister::ALL[index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use emulator_core::cycle_cost_kinds;
+
+    /// Cycle-cost kinds the suite does not time: HALT ends the tick, TRAP
+    /// and SWI leave the section for the shared handler, and the handler
+    /// never returns with ERET.
+    const UNTIMED: &[CycleCostKind] = &[
+        CycleCostKind::Halt,
+        CycleCostKind::TrapIssue,
+        CycleCostKind::SwiIssue,
+        CycleCostKind::EretReturn,
+    ];
+
+    fn cases() -> impl Iterator<Item = &'static Case> {
+        GROUPS.iter().flat_map(|group| group.cases)
+    }
+
+    #[test]
+    fn every_encoding_has_a_case() {
+        for (_, _, encoding) in OPCODE_ENCODING_TABLE {
+            assert!(
+                cases().any(|case| case.encoding == Some(*encoding)),
+                "no case for {encoding:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn every_cycle_cost_kind_is_timed() {
+        for (_, _, encoding) in OPCODE_ENCODING_TABLE {
+            for kind in cycle_cost_kinds(*encoding) {
+                assert!(
+                    UNTIMED.contains(kind) || cases().any(|case| case.timing.contains(kind)),
+                    "{kind:?} is never timed"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn checked_in_suite_is_up_to_date() {
+        let dir = std::path::Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../emulator-core/tests/conformance"
+        ));
+        for file in generate_suite().unwrap() {
+            let checked_in = std::fs::read_to_string(dir.join(&file.name)).unwrap_or_default();
+            assert!(
+                checked_in == file.contents,
+                "{} is stale; run `nullbyte-asm isa-suite {}`",
+                file.name,
+                dir.display()
+            );
+        }
+    }
+}
@@ -0,0 +1,285 @@
+//! Golden binary corpus for encoding stability.
+//!
+//! Unit tests pin the encoding of single instructions, but a change to
+//! operand resolution, pseudo-op expansion or layout can shift the bytes of a
+//! whole program while every one of them still passes. A [`Corpus`] pairs
+//! source files with the binaries they are expected to assemble to, and
+//! [`Corpus::verify`] reports every pair whose output changed. Updating the
+//! expected binaries is a separate, deliberate step ([`Corpus::update`]),
+//! so an encoding change shows up as modified `.bin` files in review.
+//!
+//! [`Corpus::discover`] builds a corpus from a directory: each `.n1` or
+//! `.n1.md` file is paired with the `.bin` file of the same stem next to it.
+//! The assembler's own corpus lives in `tests/corpus/`.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::assembler::{assemble_with_options, AssembleOptions};
+use crate::reproducible::{first_divergence, Divergence};
+
+/// A source file and the binary it must assemble to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorpusEntry {
+    /// Program name used in reports: the source file name without its
+    /// extensions.
+    pub name: String,
+    /// Source file to assemble.
+    pub source: PathBuf,
+    /// Binary the source is expected to assemble to.
+    pub expected: PathBuf,
+    /// Options to assemble with.
+    pub options: AssembleOptions,
+}
+
+/// What [`Corpus::verify`] found for one entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CorpusOutcome {
+    /// The assembled binary matches the expected one.
+    Matched,
+    /// The assembled binary differs from the expected one.
+    Changed(Divergence),
+    /// The expected binary does not exist yet.
+    MissingExpected,
+    /// The expected binary could not be read.
+    Unreadable(String),
+    /// The source no longer assembles.
+    AssembleFailed(String),
+}
+
+impl fmt::Display for CorpusOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let byte = |value: Option<u8>| {
+            value.map_or_else(|| "end of file".to_string(), |b| format!("0x{b:02X}"))
+        };
+        match self {
+            Self::Matched => f.write_str("matched"),
+            Self::Changed(divergence) => write!(
+                f,
+                "changed at 0x{:04X}: expected {}, assembled {}",
+                divergence.offset,
+                byte(divergence.shipped),
+                byte(divergence.rebuilt)
+            ),
+            Self::MissingExpected => f.write_str("no expected binary"),
+            Self::Unreadable(err) => write!(f, "cannot read expected binary: {err}"),
+            Self::AssembleFailed(err) => write!(f, "does not assemble: {err}"),
+        }
+    }
+}
+
+/// The outcome of checking one [`CorpusEntry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorpusCheck {
+    /// Program name from the entry.
+    pub name: String,
+    /// What the check found.
+    pub outcome: CorpusOutcome,
+}
+
+impl CorpusCheck {
+    /// Returns `true` when the entry assembled to its expected binary.
+    #[must_use]
+    pub const fn passed(&self) -> bool {
+        matches!(self.outcome, CorpusOutcome::Matched)
+    }
+}
+
+/// A set of source files with their expected binaries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Corpus {
+    entries: Vec<CorpusEntry>,
+}
+
+impl Corpus {
+    /// Creates an empty corpus.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Builds a corpus from the sources in `dir`, pairing each `<name>.n1`
+    /// or `<name>.n1.md` with `<name>.bin`, in file name order.
+    ///
+    /// # Errors
+    ///
+    /// Fails when `dir` cannot be listed.
+    pub fn discover(dir: &Path) -> io::Result<Self> {
+        let mut sources: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| program_name(path).is_some())
+            .collect();
+        sources.sort();
+        let mut corpus = Self::new();
+        for source in sources {
+            let expected =
+                source.with_file_name(format!("{}.bin", program_name(&source).unwrap_or_default()));
+            corpus.register(source, expected);
+        }
+        Ok(corpus)
+    }
+
+    /// Adds a source file and the binary it must assemble to with default
+    /// options.
+    pub fn register(
+        &mut self,
+        source: impl Into<PathBuf>,
+        expected: impl Into<PathBuf>,
+    ) -> &mut Self {
+        self.register_with_options(source, expected, AssembleOptions::default())
+    }
+
+    /// Adds a source file and the binary it must assemble to with
+    /// `options`.
+    pub fn register_with_options(
+        &mut self,
+        source: impl Into<PathBuf>,
+        expected: impl Into<PathBuf>,
+        options: AssembleOptions,
+    ) -> &mut Self {
+        let source = source.into();
+        let name =
+            program_name(&source).map_or_else(|| source.display().to_string(), ToString::to_string);
+        self.entries.push(CorpusEntry {
+            name,
+            source,
+            expected: expected.into(),
+            options,
+        });
+        self
+    }
+
+    /// Returns the registered entries in registration order.
+    #[must_use]
+    pub fn entries(&self) -> &[CorpusEntry] {
+        &self.entries
+    }
+
+    /// Assembles every entry and compares it with its expected binary.
+    #[must_use]
+    pub fn verify(&self) -> Vec<CorpusCheck> {
+        self.entries
+            .iter()
+            .map(|entry| CorpusCheck {
+                name: entry.name.clone(),
+                outcome: check(entry),
+            })
+            .collect()
+    }
+
+    /// Assembles every entry and writes the output over its expected
+    /// binary, returning the names of the entries whose binary changed.
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first entry that does not assemble or whose binary
+    /// cannot be written; earlier entries stay updated.
+    pub fn update(&self) -> Result<Vec<String>, String> {
+        let mut changed = Vec::new();
+        for entry in &self.entries {
+            let result = assemble_with_options(&entry.source, &entry.options)
+                .map_err(|err| format!("{}: {err}", entry.name))?;
+            if fs::read(&entry.expected).ok().as_deref() == Some(&result.binary[..]) {
+                continue;
+            }
+            fs::write(&entry.expected, &result.binary)
+                .map_err(|err| format!("{}: {err}", entry.expected.display()))?;
+            changed.push(entry.name.clone());
+        }
+        Ok(changed)
+    }
+}
+
+fn check(entry: &CorpusEntry) -> CorpusOutcome {
+    let expected = match fs::read(&entry.expected) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return CorpusOutcome::MissingExpected,
+        Err(err) => return CorpusOutcome::Unreadable(err.to_string()),
+    };
+    match assemble_with_options(&entry.source, &entry.options) {
+        Ok(result) => first_divergence(&expected, &result.binary)
+            .map_or(CorpusOutcome::Matched, CorpusOutcome::Changed),
+        Err(err) => CorpusOutcome::AssembleFailed(err.to_string()),
+    }
+}
+
+/// The name of a corpus source: its file name without `.n1` or `.n1.md`,
+/// or `None` for other files.
+fn program_name(path: &Path) -> Option<&str> {
+    let file_name = path.file_name()?.to_str()?;
+    file_name
+        .strip_suffix(".n1.md")
+        .or_else(|| file_name.strip_suffix(".n1"))
+        .filter(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corpus_dir() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("halt.n1"), "HALT\n").unwrap();
+        fs::write(
+            dir.path().join("nop.n1.md"),
+            "# Nop\n\n```n1asm\nNOP\nHALT\n```\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("notes.txt"), "not a program\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn discover_pairs_sources_with_binaries() {
+        let dir = corpus_dir();
+        let corpus = Corpus::discover(dir.path()).unwrap();
+        let names: Vec<_> = corpus.entries().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["halt", "nop"]);
+        assert_eq!(corpus.entries()[1].expected, dir.path().join("nop.bin"));
+    }
+
+    #[test]
+    fn verify_reports_missing_and_changed_binaries() {
+        let dir = corpus_dir();
+        let corpus = Corpus::discover(dir.path()).unwrap();
+        let outcomes: Vec<_> = corpus.verify().into_iter().map(|c| c.outcome).collect();
+        assert_eq!(
+            outcomes,
+            [
+                CorpusOutcome::MissingExpected,
+                CorpusOutcome::MissingExpected
+            ]
+        );
+
+        assert_eq!(corpus.update().unwrap(), ["halt", "nop"]);
+        assert!(corpus.verify().iter().all(CorpusCheck::passed));
+        assert!(corpus.update().unwrap().is_empty());
+
+        fs::write(dir.path().join("halt.n1"), "NOP\n").unwrap();
+        let checks = corpus.verify();
+        assert!(matches!(checks[0].outcome, CorpusOutcome::Changed(_)));
+        assert!(checks[1].passed());
+    }
+
+    #[test]
+    fn verify_reports_sources_that_stop_assembling() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("broken.n1");
+        fs::write(&source, "BOGUS\n").unwrap();
+        let expected = dir.path().join("golden.bin");
+        fs::write(&expected, [0x00]).unwrap();
+
+        let mut corpus = Corpus::new();
+        corpus.register(&source, &expected);
+        let checks = corpus.verify();
+        assert_eq!(checks[0].name, "broken");
+        assert!(matches!(
+            checks[0].outcome,
+            CorpusOutcome::AssembleFailed(_)
+        ));
+    }
+}
@@ -0,0 +1,203 @@
+//! Native versus WASM determinism cross-check.
+//!
+//! The browser runs programs through `emulator-wasm`, which drives the core
+//! one `tick()` call at a time, while the CLI and test runner batch ticks
+//! natively. [`native_digests`] runs a bundle with the native tick batcher
+//! and records `CoreState::state_digest` after every tick;
+//! [`wasm_digests`] loads the same bundle into a Node.js build of
+//! `emulator-wasm` (`wasm-pack build --target nodejs`) and records the
+//! digest after each `tick()`. Both hosts enqueue the same scripted events
+//! before the tick they are scheduled for, so [`first_tick_divergence`]
+//! points at the first tick where the WASM layer stopped matching native
+//! semantics.
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use emulator_core::{
+    run_ticks_with_budget, CompositeMmio, CoreConfig, CoreState, DebugConsole, DmaController,
+    PasteBuffer, Tele7Config, Tele7Peripheral,
+};
+
+use crate::bundle::ProgramBundle;
+use crate::test_format::ScheduledEvent;
+
+/// Node.js driver for a `wasm-pack --target nodejs` package. Reads the
+/// bundle from stdin and prints one digest per tick.
+const WASM_DRIVER: &str = r#"
+const [pkg, ticks, events] = process.argv.slice(1);
+const { WasmCore } = require(require("path").resolve(pkg));
+const core = new WasmCore();
+core.load_bundle(require("fs").readFileSync(0, "utf8"));
+const schedule = JSON.parse(events);
+for (let tick = 0; tick < Number(ticks); tick++) {
+  for (const [at, id] of schedule) {
+    if (at === tick) core.enqueue_event(id);
+  }
+  core.tick();
+  console.log(core.state_digest());
+}
+"#;
+
+/// First tick where two digest sequences differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickDivergence {
+    /// Tick index, counted from 0.
+    pub tick: usize,
+    /// Native digest after the tick, or `None` past the end of its run.
+    pub native: Option<u64>,
+    /// WASM digest after the tick, or `None` past the end of its run.
+    pub wasm: Option<u64>,
+}
+
+/// Runs `bundle` for `ticks` ticks on the native core, set up the way
+/// `WasmCore` sets itself up, and returns the state digest after each tick.
+#[must_use]
+pub fn native_digests(bundle: &ProgramBundle, events: &[ScheduledEvent], ticks: u32) -> Vec<u64> {
+    let config = CoreConfig {
+        reset_pc: bundle.entry.unwrap_or_default(),
+        ..CoreConfig::default()
+    };
+    let mut state = CoreState::with_config(&config);
+    let len = bundle.binary.len().min(state.memory.len());
+    state.memory[..len].copy_from_slice(&bundle.binary[..len]);
+    let mut mmio = CompositeMmio::new()
+        .with_tele7(Tele7Peripheral::new(Tele7Config::default()))
+        .with_console(DebugConsole::new())
+        .with_dma(DmaController::new())
+        .with_paste(PasteBuffer::new());
+
+    (0..ticks)
+        .map(|tick| {
+            for event in events.iter().filter(|event| u32::from(event.tick) == tick) {
+                // A full queue latches the overflow in the state, which the
+                // digest then covers.
+                let _ = state.enqueue_event(event.event_id);
+            }
+            run_ticks_with_budget(&mut state, &mut mmio, &config, 1);
+            state.state_digest()
+        })
+        .collect()
+}
+
+/// Runs `bundle` for `ticks` ticks in the WASM package at `package` under
+/// the Node.js binary `node`, and returns the state digest after each tick.
+///
+/// # Errors
+///
+/// Returns an error when `node` cannot be started, exits unsuccessfully
+/// (the message carries its stderr) or prints something other than
+/// digests.
+pub fn wasm_digests(
+    node: &Path,
+    package: &Path,
+    bundle: &ProgramBundle,
+    events: &[ScheduledEvent],
+    ticks: u32,
+) -> io::Result<Vec<u64>> {
+    let schedule = events
+        .iter()
+        .enumerate()
+        .fold(String::new(), |mut out, (i, event)| {
+            let _ = write!(
+                out,
+                "{}[{},{}]",
+                if i == 0 { "" } else { "," },
+                event.tick,
+                event.event_id
+            );
+            out
+        });
+    let mut child = Command::new(node)
+        .arg("-e")
+        .arg(WASM_DRIVER)
+        .arg(package)
+        .arg(ticks.to_string())
+        .arg(format!("[{schedule}]"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(bundle.to_json().as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{} exited with {}: {}",
+            node.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| {
+            u64::from_str_radix(line.trim(), 16).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("expected a state digest, got '{line}'"),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Finds the first tick where `native` and `wasm` disagree, including one
+/// run stopping short of the other. Returns `None` when they match.
+#[must_use]
+pub fn first_tick_divergence(native: &[u64], wasm: &[u64]) -> Option<TickDivergence> {
+    (0..native.len().max(wasm.len()))
+        .map(|tick| TickDivergence {
+            tick,
+            native: native.get(tick).copied(),
+            wasm: wasm.get(tick).copied(),
+        })
+        .find(|divergence| divergence.native != divergence.wasm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+    use crate::test_format::parse_event_script;
+
+    #[test]
+    fn native_digests_follow_the_event_script() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cross.n1");
+        std::fs::write(&path, "loop:\nADD R0, R0, #1\nHALT\nJMP #loop\n").unwrap();
+        let bundle = ProgramBundle::new(&path, &assemble(&path).unwrap()).unwrap();
+        let events = parse_event_script("at tick 2 enqueue event 7").unwrap();
+
+        let quiet = native_digests(&bundle, &[], 4);
+        let scripted = native_digests(&bundle, &events, 4);
+        assert_eq!(quiet.len(), 4);
+        assert_eq!(quiet, native_digests(&bundle, &[], 4));
+        assert_eq!(quiet[..2], scripted[..2]);
+        assert_ne!(quiet[2], scripted[2]);
+    }
+
+    #[test]
+    fn divergence_reports_the_first_differing_tick() {
+        assert_eq!(first_tick_divergence(&[1, 2, 3], &[1, 2, 3]), None);
+        assert_eq!(
+            first_tick_divergence(&[1, 2, 3], &[1, 5, 3]),
+            Some(TickDivergence {
+                tick: 1,
+                native: Some(2),
+                wasm: Some(5),
+            })
+        );
+        assert_eq!(
+            first_tick_divergence(&[1, 2], &[1]),
+            Some(TickDivergence {
+                tick: 1,
+                native: Some(2),
+                wasm: None,
+            })
+        );
+    }
+}
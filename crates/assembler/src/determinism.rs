@@ -0,0 +1,229 @@
+//! Determinism self-check for assembled programs.
+//!
+//! [`verify_determinism`] runs a program's test suite several times from a
+//! fresh machine and once more with the state exported to a snapshot and
+//! re-imported halfway through, then compares every final state and result
+//! against the first run. Any difference points at nondeterminism, typically
+//! a peripheral or host integration that depends on something outside the
+//! core state, or a snapshot that drops part of the state.
+//!
+//! A program without test blocks runs to its first `HALT`. With fewer than
+//! two blocks the snapshot is taken before execution starts.
+
+use emulator_core::{
+    diff_states, CoreSnapshot, CoreState, ParamBlock, StateDifference, LATEST_SNAPSHOT_VERSION,
+};
+
+use crate::assembler::AssembleResult;
+use crate::test_format::ParsedTestBlock;
+use crate::test_runner::{new_test_state, run_tests_on_state, TestProgram, TestRunResult};
+
+/// How a checked run diverged from the reference run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeterminismMismatch {
+    /// Which run diverged, e.g. `run 3` or `snapshot restore`.
+    pub run: String,
+    /// Final-state differences from the reference run.
+    pub differences: Vec<StateDifference>,
+    /// Whether pass/fail results also differed.
+    pub results_differ: bool,
+}
+
+/// Outcome of a determinism self-check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeterminismReport {
+    /// Number of fresh runs compared, including the reference run.
+    pub runs: u32,
+    /// Block index the snapshot restore happened before.
+    pub snapshot_block: usize,
+    /// `CoreState::state_digest` of the reference run's final state, for
+    /// comparing against runs on other hosts.
+    pub digest: u64,
+    /// Runs that did not match the reference run.
+    pub mismatches: Vec<DeterminismMismatch>,
+}
+
+impl DeterminismReport {
+    /// Returns true when every run matched the reference run.
+    #[must_use]
+    pub const fn is_deterministic(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Runs `program` with `test_blocks` `runs` times plus once through a
+/// snapshot restore and reports any divergence from the first run.
+///
+/// `runs` is clamped to at least one.
+#[must_use]
+pub fn verify_determinism(
+    program: &AssembleResult,
+    test_blocks: &[ParsedTestBlock],
+    runs: u32,
+) -> DeterminismReport {
+    let runs = runs.max(1);
+    let program_only = [ParsedTestBlock {
+        setup: Vec::new(),
+        events: Vec::new(),
+        reset: false,
+        fixture: None,
+        capture: None,
+        frozen: Vec::new(),
+        skip: false,
+        only: false,
+        tags: Vec::new(),
+        screen: None,
+        params: ParamBlock::new(),
+        assertions: Vec::new(),
+        invariants: Vec::new(),
+        start_line: 0,
+        end_line: 0,
+    }];
+    let blocks = if test_blocks.is_empty() {
+        &program_only[..]
+    } else {
+        test_blocks
+    };
+
+    let program = TestProgram::of(program);
+    let initial = new_test_state(&program);
+    let (reference_state, reference_result) = run_fresh(&initial, &program, blocks);
+    let mut mismatches = Vec::new();
+
+    for run in 2..=runs {
+        let (state, result) = run_fresh(&initial, &program, blocks);
+        if let Some(mismatch) = compare(
+            format!("run {run}"),
+            (&reference_state, &reference_result),
+            (&state, &result),
+        ) {
+            mismatches.push(mismatch);
+        }
+    }
+
+    let snapshot_block = blocks.len() / 2;
+    let (state, result) = run_with_snapshot(&initial, &program, blocks, snapshot_block);
+    if let Some(mismatch) = compare(
+        "snapshot restore".to_string(),
+        (&reference_state, &reference_result),
+        (&state, &result),
+    ) {
+        mismatches.push(mismatch);
+    }
+
+    DeterminismReport {
+        runs,
+        snapshot_block,
+        digest: reference_state.state_digest(),
+        mismatches,
+    }
+}
+
+fn run_fresh(
+    initial: &CoreState,
+    program: &TestProgram<'_>,
+    blocks: &[ParsedTestBlock],
+) -> (CoreState, TestRunResult) {
+    let mut state = initial.clone();
+    let result = run_tests_on_state(&mut state, program, blocks);
+    (state, result)
+}
+
+/// Runs `blocks[..split]`, round-trips the state through a snapshot, then
+/// runs the remaining blocks.
+fn run_with_snapshot(
+    initial: &CoreState,
+    program: &TestProgram<'_>,
+    blocks: &[ParsedTestBlock],
+    split: usize,
+) -> (CoreState, TestRunResult) {
+    let mut state = initial.clone();
+    let (first, rest) = blocks.split_at(split);
+    let mut result = run_tests_on_state(&mut state, program, first);
+
+    let snapshot = CoreSnapshot::from_core_state(LATEST_SNAPSHOT_VERSION, &state);
+    // A snapshot that cannot be re-imported is itself a divergence; carry on
+    // from a fresh machine so the mismatch is reported.
+    let mut restored = snapshot
+        .try_into_core_state()
+        .unwrap_or_else(|_| initial.clone());
+    let tail = run_tests_on_state(&mut restored, program, rest);
+    result.block_results.extend(tail.block_results);
+    result.unexecuted_blocks += tail.unexecuted_blocks;
+    result.captures.extend(tail.captures);
+    (restored, result)
+}
+
+fn compare(
+    run: String,
+    reference: (&CoreState, &TestRunResult),
+    candidate: (&CoreState, &TestRunResult),
+) -> Option<DeterminismMismatch> {
+    let differences = diff_states(reference.0, candidate.0);
+    let results_differ = reference.1 != candidate.1;
+    (!differences.is_empty() || results_differ).then_some(DeterminismMismatch {
+        run,
+        differences,
+        results_differ,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble_from_source;
+    use crate::test_format::parse_test_block;
+
+    #[test]
+    fn deterministic_suite_reports_no_mismatches() {
+        let source = "MOV R0, #1\nHALT\nADD R0, R0, #2\nHALT\nADD R0, R0, #3\nHALT\n";
+        let result = assemble_from_source(source, "det.n1").unwrap();
+        let blocks: Vec<_> = ["R0 == 1", "R0 == 3", "R0 == 6"]
+            .iter()
+            .map(|content| parse_test_block(content, 1, 3).unwrap())
+            .collect();
+
+        let report = verify_determinism(&result, &blocks, 3);
+
+        assert!(report.is_deterministic(), "{:?}", report.mismatches);
+        assert_eq!(report.runs, 3);
+        assert_eq!(report.snapshot_block, 1);
+        assert_eq!(
+            report.digest,
+            verify_determinism(&result, &blocks, 1).digest
+        );
+    }
+
+    #[test]
+    fn program_without_blocks_runs_to_first_halt() {
+        let result = assemble_from_source("MOV R1, #5\nHALT\n", "det.n1").unwrap();
+        let report = verify_determinism(&result, &[], 0);
+
+        assert_eq!(report.runs, 1);
+        assert_eq!(report.snapshot_block, 0);
+        assert!(report.is_deterministic());
+    }
+
+    #[test]
+    fn compare_reports_state_differences() {
+        let symbols = crate::symbols::SymbolTable::new();
+        let program = TestProgram {
+            binary: &[0x00, 0x10],
+            entry: 0,
+            symbols: &symbols,
+            fixture_dir: None,
+            capture_fixtures: false,
+            strict: false,
+            tags: &[],
+            skip_tags: &[],
+        };
+        let (reference, result) = run_fresh(&new_test_state(&program), &program, &[]);
+        let mut diverged = reference.clone();
+        diverged.memory[0x4000] = 0xAA;
+
+        let mismatch = compare("run 2".into(), (&reference, &result), (&diverged, &result))
+            .expect("states differ");
+        assert_eq!(mismatch.differences.len(), 1);
+        assert!(!mismatch.results_differ);
+    }
+}
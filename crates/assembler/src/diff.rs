@@ -0,0 +1,167 @@
+//! Line-based unified diffs for reporting mismatched text output.
+
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt::Write as _;
+
+/// Unchanged lines shown around each change.
+const CONTEXT_LINES: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Edit {
+    Keep,
+    Remove,
+    Add,
+}
+
+/// Renders a unified diff from `expected` to `actual`, or `None` when the
+/// two texts have the same lines.
+///
+/// Hunk headers use 1-based line numbers like `diff -u`; the file headers
+/// are labelled `expected` and `actual`.
+#[must_use]
+pub fn unified_diff(expected: &str, actual: &str) -> Option<String> {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+    if old == new {
+        return None;
+    }
+
+    let edits = edit_script(&old, &new);
+    let mut out = String::from("--- expected\n+++ actual\n");
+    let mut start = 0;
+    while let Some(first_change) = edits[start..]
+        .iter()
+        .position(|(edit, _, _)| *edit != Edit::Keep)
+    {
+        let hunk_start = (start + first_change).saturating_sub(CONTEXT_LINES);
+        let mut hunk_end = start + first_change;
+        // Extend the hunk while the next change is within two contexts.
+        loop {
+            let next_keep = edits[hunk_end..]
+                .iter()
+                .position(|(edit, _, _)| *edit == Edit::Keep)
+                .map_or(edits.len(), |offset| hunk_end + offset);
+            let next_change = edits[next_keep..]
+                .iter()
+                .position(|(edit, _, _)| *edit != Edit::Keep)
+                .map(|offset| next_keep + offset);
+            match next_change {
+                Some(change) if change - next_keep <= 2 * CONTEXT_LINES => hunk_end = change,
+                _ => {
+                    hunk_end = (next_keep + CONTEXT_LINES).min(edits.len());
+                    break;
+                }
+            }
+        }
+        write_hunk(&mut out, &edits[hunk_start..hunk_end], &old, &new);
+        start = hunk_end;
+    }
+    Some(out)
+}
+
+fn write_hunk(out: &mut String, hunk: &[(Edit, usize, usize)], old: &[&str], new: &[&str]) {
+    let old_len = hunk
+        .iter()
+        .filter(|(edit, _, _)| *edit != Edit::Add)
+        .count();
+    let new_len = hunk
+        .iter()
+        .filter(|(edit, _, _)| *edit != Edit::Remove)
+        .count();
+    let (_, old_start, new_start) = hunk[0];
+    let _ = writeln!(
+        out,
+        "@@ -{} +{} @@",
+        range(old_start, old_len),
+        range(new_start, new_len)
+    );
+    for &(edit, old_idx, new_idx) in hunk {
+        let _ = match edit {
+            Edit::Keep => writeln!(out, " {}", old[old_idx]),
+            Edit::Remove => writeln!(out, "-{}", old[old_idx]),
+            Edit::Add => writeln!(out, "+{}", new[new_idx]),
+        };
+    }
+}
+
+/// Formats a hunk range the way `diff -u` does: `start,len`, with an empty
+/// range anchored at the line before it.
+fn range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{len}", start + 1),
+    }
+}
+
+/// Shortest edit script between `old` and `new` via longest common
+/// subsequence. Each entry carries the indices into `old` and `new` the
+/// edit sits at.
+pub(crate) fn edit_script(old: &[&str], new: &[&str]) -> Vec<(Edit, usize, usize)> {
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut edits = Vec::with_capacity(old.len() + new.len());
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            edits.push((Edit::Keep, i, j));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            edits.push((Edit::Remove, i, j));
+            i += 1;
+        } else {
+            edits.push((Edit::Add, i, j));
+            j += 1;
+        }
+    }
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_text_has_no_diff() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb"), None);
+    }
+
+    #[test]
+    fn diff_shows_changes_with_context() {
+        let expected = "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine\n";
+        let actual = "one\ntwo\nthree\nFOUR\nfive\nsix\nseven\neight\nnine\nten\n";
+        assert_eq!(
+            unified_diff(expected, actual).unwrap(),
+            "--- expected\n+++ actual\n\
+             @@ -2,5 +2,5 @@\n two\n three\n-four\n+FOUR\n five\n six\n\
+             @@ -8,2 +8,3 @@\n eight\n nine\n+ten\n"
+        );
+    }
+
+    #[test]
+    fn nearby_changes_share_a_hunk() {
+        let diff = unified_diff("a\nb\nc\nd\ne\n", "A\nb\nc\nd\nE\n").unwrap();
+        assert_eq!(
+            diff,
+            "--- expected\n+++ actual\n@@ -1,5 +1,5 @@\n-a\n+A\n b\n c\n d\n-e\n+E\n"
+        );
+    }
+
+    #[test]
+    fn diff_against_empty_text() {
+        assert_eq!(
+            unified_diff("", "HELLO\n").unwrap(),
+            "--- expected\n+++ actual\n@@ -0,0 +1 @@\n+HELLO\n"
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! Source-code wrappers for assembled images.
+//!
+//! `build --emit` writes the binary as a C header or a Rust include file as
+//! well as a raw `.bin`, so a ROM can be embedded into another project
+//! without a build-time conversion step. The entry point and any `.title`,
+//! `.author` and `.version` come along as constants:
+//!
+//! ```text
+//! pub const PROGRAM: [u8; 6] = [
+//!     0x60, 0x3D, 0x00, 0x02, 0x00, 0x10,
+//! ];
+//! ```
+
+use alloc::{format, string::String};
+use core::fmt::Write;
+
+use crate::info::{InfoField, ProgramInfo};
+
+/// Bytes per line of an emitted array.
+const BYTES_PER_LINE: usize = 12;
+
+/// Source form an assembled image can be emitted as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmitFormat {
+    /// C header declaring `static const uint8_t PROGRAM[]`.
+    CArray,
+    /// Rust file declaring `pub const PROGRAM: [u8; N]`, for `include!`.
+    RustInclude,
+}
+
+impl EmitFormat {
+    /// Names accepted by [`EmitFormat::named`], in declaration order.
+    pub const NAMES: [&'static str; 2] = ["c-array", "rust-include"];
+
+    /// Looks up a format by its command-line name.
+    #[must_use]
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "c-array" => Some(Self::CArray),
+            "rust-include" => Some(Self::RustInclude),
+            _ => None,
+        }
+    }
+
+    /// Command-line name of the format.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::CArray => "c-array",
+            Self::RustInclude => "rust-include",
+        }
+    }
+
+    /// File extension for the emitted source, without the dot.
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::CArray => "h",
+            Self::RustInclude => "rs",
+        }
+    }
+
+    /// Renders `binary` as source. `source_name` is named in the header
+    /// comment; `entry` and each field set in `info` add a `PROGRAM_ENTRY`,
+    /// `PROGRAM_TITLE`, `PROGRAM_AUTHOR` or `PROGRAM_VERSION` constant.
+    #[must_use]
+    pub fn render(
+        self,
+        binary: &[u8],
+        entry: Option<u16>,
+        info: &ProgramInfo,
+        source_name: &str,
+    ) -> String {
+        match self {
+            Self::CArray => c_array(binary, entry, info, source_name),
+            Self::RustInclude => rust_include(binary, entry, info, source_name),
+        }
+    }
+}
+
+/// Metadata fields in the order they are emitted.
+const INFO_FIELDS: [(InfoField, &str); 3] = [
+    (InfoField::Title, "PROGRAM_TITLE"),
+    (InfoField::Author, "PROGRAM_AUTHOR"),
+    (InfoField::Version, "PROGRAM_VERSION"),
+];
+
+fn c_array(binary: &[u8], entry: Option<u16>, info: &ProgramInfo, source_name: &str) -> String {
+    let mut out = format!(
+        "/* Generated by nullbyte-asm from {source_name}. Do not edit. */\n\
+         #include <stdint.h>\n\n\
+         #define PROGRAM_LEN {}\n",
+        binary.len()
+    );
+    if let Some(entry) = entry {
+        let _ = writeln!(out, "#define PROGRAM_ENTRY 0x{entry:04X}");
+    }
+    for (field, name) in INFO_FIELDS {
+        if let Some(value) = info.get(field) {
+            let _ = writeln!(out, "#define {name} \"{}\"", c_escape(value));
+        }
+    }
+    out.push_str("\nstatic const uint8_t PROGRAM[PROGRAM_LEN] = {\n");
+    push_bytes(&mut out, binary);
+    out.push_str("};\n");
+    out
+}
+
+fn rust_include(
+    binary: &[u8],
+    entry: Option<u16>,
+    info: &ProgramInfo,
+    source_name: &str,
+) -> String {
+    let mut out = format!("// Generated by nullbyte-asm from {source_name}. Do not edit.\n\n");
+    let mut constants = String::new();
+    if let Some(entry) = entry {
+        let _ = writeln!(constants, "pub const PROGRAM_ENTRY: u16 = 0x{entry:04X};");
+    }
+    for (field, name) in INFO_FIELDS {
+        if let Some(value) = info.get(field) {
+            let _ = writeln!(constants, "pub const {name}: &str = {value:?};");
+        }
+    }
+    if !constants.is_empty() {
+        out.push_str(&constants);
+        out.push('\n');
+    }
+    let _ = writeln!(out, "pub const PROGRAM: [u8; {}] = [", binary.len());
+    push_bytes(&mut out, binary);
+    out.push_str("];\n");
+    out
+}
+
+/// Escapes `text` for a C string literal, writing anything outside
+/// printable ASCII as octal escapes of its UTF-8 bytes.
+fn c_escape(text: &str) -> String {
+    let mut out = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'"' | b'\\' => {
+                out.push('\\');
+                out.push(char::from(byte));
+            }
+            b' '..=b'~' => out.push(char::from(byte)),
+            _ => {
+                let _ = write!(out, "\\{byte:03o}");
+            }
+        }
+    }
+    out
+}
+
+/// Appends `binary` as indented, comma-terminated hex bytes.
+fn push_bytes(out: &mut String, binary: &[u8]) {
+    for chunk in binary.chunks(BYTES_PER_LINE) {
+        out.push_str("   ");
+        for byte in chunk {
+            let _ = write!(out, " 0x{byte:02X},");
+        }
+        out.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for name in EmitFormat::NAMES {
+            assert_eq!(EmitFormat::named(name).map(EmitFormat::name), Some(name));
+        }
+        assert_eq!(EmitFormat::named("bin"), None);
+    }
+
+    #[test]
+    fn renders_rust_include() {
+        let text = EmitFormat::RustInclude.render(
+            &[0x60, 0x3D, 0x00, 0x10],
+            None,
+            &ProgramInfo::default(),
+            "game.n1",
+        );
+        assert_eq!(
+            text,
+            "// Generated by nullbyte-asm from game.n1. Do not edit.\n\n\
+             pub const PROGRAM: [u8; 4] = [\n    0x60, 0x3D, 0x00, 0x10,\n];\n"
+        );
+    }
+
+    #[test]
+    fn renders_c_array_with_entry_and_wrapped_lines() {
+        let binary: alloc::vec::Vec<u8> = (0..14).collect();
+        let text =
+            EmitFormat::CArray.render(&binary, Some(0x0002), &ProgramInfo::default(), "game.n1");
+        assert!(text.contains("#define PROGRAM_LEN 14\n#define PROGRAM_ENTRY 0x0002\n"));
+        assert!(text.contains(
+            "PROGRAM[PROGRAM_LEN] = {\n    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B,\n    0x0C, 0x0D,\n};\n"
+        ));
+    }
+
+    #[test]
+    fn empty_image_renders_an_empty_array() {
+        let text = EmitFormat::RustInclude.render(&[], None, &ProgramInfo::default(), "empty.n1");
+        assert!(text.ends_with("pub const PROGRAM: [u8; 0] = [\n];\n"));
+    }
+
+    #[test]
+    fn program_info_becomes_escaped_constants() {
+        let info = ProgramInfo {
+            title: Some("Say \"hi\"\u{e9}".into()),
+            author: None,
+            version: Some("1.0".into()),
+        };
+        let c = EmitFormat::CArray.render(&[0x00, 0x10], None, &info, "hi.n1");
+        assert!(c.contains(
+            "#define PROGRAM_LEN 2\n#define PROGRAM_TITLE \"Say \\\"hi\\\"\\303\\251\"\n#define PROGRAM_VERSION \"1.0\"\n"
+        ));
+        assert!(!c.contains("PROGRAM_AUTHOR"));
+
+        let rust = EmitFormat::RustInclude.render(&[0x00, 0x10], Some(0), &info, "hi.n1");
+        assert!(rust.contains(
+            "pub const PROGRAM_ENTRY: u16 = 0x0000;\npub const PROGRAM_TITLE: &str = \"Say \\\"hi\\\"\u{e9}\";\npub const PROGRAM_VERSION: &str = \"1.0\";\n\npub const PROGRAM"
+        ));
+    }
+}
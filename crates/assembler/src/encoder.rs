@@ -3,7 +3,11 @@
 //! This module implements the encoding phase of assembly: converting parsed
 //! instructions and directives into binary bytes suitable for ROM loading.
 
-use crate::parser::{Directive, InstructionSize, Operand, ParsedInstruction, ParsedLine};
+use alloc::{format, string::String, vec, vec::Vec};
+
+use crate::parser::{
+    Directive, Immediate, InstructionSize, Operand, ParsedInstruction, ParsedLine, SizeOf,
+};
 use crate::symbols::SymbolTable;
 
 /// Addressing mode bit values for the AM field.
@@ -49,14 +53,14 @@ pub enum EncodeErrorKind {
     InvalidEncoding(String),
 }
 
-impl std::fmt::Display for EncodeError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", self.kind)
     }
 }
 
-impl std::fmt::Display for EncodeErrorKind {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for EncodeErrorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::UndefinedLabel(name) => write!(f, "undefined label: {name}"),
             Self::DisplacementOutOfRange(disp) => {
@@ -73,7 +77,7 @@ impl std::fmt::Display for EncodeErrorKind {
     }
 }
 
-impl std::error::Error for EncodeError {}
+impl core::error::Error for EncodeError {}
 
 /// Encoded output for a single instruction or directive.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -177,7 +181,10 @@ pub fn encode_instruction(
                 let ext = offset as i16 as u16;
                 (ra, am::PC_RELATIVE, Some(ext))
             } else {
-                let val = imm.value;
+                let val = match &imm.size_of {
+                    Some(size_of) => i64::from(resolve_size_of(size_of, symbols, source_line)?),
+                    None => imm.value,
+                };
                 if !(0..=0xFFFF).contains(&val) {
                     return Err(EncodeError {
                         kind: EncodeErrorKind::ImmediateOutOfRange(val),
@@ -209,6 +216,9 @@ pub fn encode_instruction(
 
 /// Encodes a directive to bytes.
 ///
+/// `.word sizeof(start, end)` needs the symbol table and must go through
+/// [`encode_line`]; here it fails as an undefined label.
+///
 /// # Errors
 ///
 /// Returns `EncodeError` if a value is out of range.
@@ -216,7 +226,7 @@ pub fn encode_instruction(
 pub fn encode_directive(
     directive: &Directive,
     current_address: u16,
-    _source_line: usize,
+    source_line: usize,
 ) -> Result<Vec<u8>, EncodeError> {
     match directive {
         Directive::Org(addr) => {
@@ -232,7 +242,15 @@ pub fn encode_directive(
         Directive::Byte(val) => Ok(vec![*val]),
         Directive::Ascii(s) => Ok(s.as_bytes().to_vec()),
         Directive::Zero(count) => Ok(vec![0u8; *count]),
-        Directive::Include(_) => Ok(Vec::new()),
+        Directive::Include(_)
+        | Directive::Entry(_)
+        | Directive::Info(..)
+        | Directive::PseudoOp(_)
+        | Directive::Equ(..)
+        | Directive::StandardLayout
+        | Directive::BlockOp(_) => Ok(Vec::new()),
+        Directive::IncBin(ops) => Ok(ops.data.clone()),
+        Directive::LiteralWord(value) => literal_word(value, &SymbolTable::new(), source_line),
         Directive::TwChar(ops) => {
             let high = twchar_operand_to_byte(&ops.high);
             let low = twchar_operand_to_byte(&ops.low);
@@ -267,6 +285,49 @@ const fn twchar_operand_to_byte(op: &crate::parser::TwCharOperand) -> u8 {
     }
 }
 
+/// Encodes a word resolved in pass 2: a `sizeof`/`lengthof` size or a
+/// constant.
+fn literal_word(
+    value: &Immediate,
+    symbols: &SymbolTable,
+    source_line: usize,
+) -> Result<Vec<u8>, EncodeError> {
+    let word = match &value.size_of {
+        Some(size_of) => resolve_size_of(size_of, symbols, source_line)?,
+        None => u16::try_from(value.value).map_err(|_| EncodeError {
+            kind: EncodeErrorKind::ImmediateOutOfRange(value.value),
+            line: source_line,
+        })?,
+    };
+    Ok(word.to_be_bytes().to_vec())
+}
+
+/// Evaluates `sizeof` or `lengthof` from the addresses of its labels.
+fn resolve_size_of(
+    size_of: &SizeOf,
+    symbols: &SymbolTable,
+    source_line: usize,
+) -> Result<u16, EncodeError> {
+    let address = |name: &String| {
+        symbols
+            .get(name)
+            .map(|symbol| symbol.address)
+            .ok_or_else(|| EncodeError {
+                kind: EncodeErrorKind::UndefinedLabel(name.clone()),
+                line: source_line,
+            })
+    };
+    let (start, end) = (address(&size_of.start)?, address(&size_of.end)?);
+    size_of.resolve(start, end).ok_or_else(|| EncodeError {
+        kind: EncodeErrorKind::InvalidEncoding(if end < start {
+            format!("{size_of}: {} is before {}", size_of.end, size_of.start)
+        } else {
+            format!("{size_of}: region is an odd number of bytes")
+        }),
+        line: source_line,
+    })
+}
+
 /// Encodes a parsed line to bytes.
 ///
 /// # Errors
@@ -280,6 +341,9 @@ pub fn encode_line(
 ) -> Result<Vec<u8>, EncodeError> {
     match parsed {
         ParsedLine::Blank | ParsedLine::Label { .. } => Ok(Vec::new()),
+        ParsedLine::Directive {
+            directive: Directive::LiteralWord(value),
+        } => literal_word(value, symbols, source_line),
         ParsedLine::Directive { directive } => {
             encode_directive(directive, current_address, source_line)
         }
@@ -773,6 +837,12 @@ mod tests {
                 expected_op: 0x1,
                 expected_sub: 0x0,
             },
+            OpcodeTestCase {
+                mnemonic: "MRS",
+                source: "MRS R0, CAUSE",
+                expected_op: 0x1,
+                expected_sub: 0x1,
+            },
             OpcodeTestCase {
                 mnemonic: "LOAD",
                 source: "LOAD R0, [R1]",
@@ -993,7 +1063,7 @@ mod tests {
 
         assert_eq!(
             test_cases.len(),
-            42,
+            43,
             "Test case count must match mnemonic count (CALL/RET share encoding)"
         );
 
@@ -1168,6 +1238,8 @@ mod tests {
         let am_101_cases: &[(&str, &str)] = &[
             ("MOV immediate", "MOV R0, #0x1234"),
             ("ADD immediate", "ADD R0, R1, #0x42"),
+            ("SHL immediate", "SHL R0, R1, #4"),
+            ("SHR immediate", "SHR R0, R1, #12"),
             ("JMP pc-relative", "JMP #target"),
             ("BEQ pc-relative", "BEQ #target"),
             ("CALL pc-relative", "CALL #target"),
@@ -80,7 +80,7 @@ impl SourceLoc {
                     entry.line
                 ));
             }
-            parts.join(" (") + &")".repeat(self.include_chain.len())
+            parts.join(" (") + ")".repeat(self.include_chain.len()).as_str()
         }
     }
 }
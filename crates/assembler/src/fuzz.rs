@@ -0,0 +1,51 @@
+//! Fuzzing entry points.
+//!
+//! [`fuzz_parse_line`] feeds arbitrary bytes through every text-facing entry
+//! point of the assembler. Malformed input must come back as an error; any
+//! panic is a bug.
+
+use crate::assembler::assemble_from_source;
+use crate::parser::parse_line;
+use crate::preview::encode_single_line;
+use crate::symbols::SymbolTable;
+use crate::test_format::parse_test_block;
+
+/// Parses, previews and assembles arbitrary bytes, discarding the results.
+///
+/// Bytes are decoded lossily as UTF-8. Each line goes through
+/// [`parse_line`] and [`encode_single_line`], the whole text through
+/// [`parse_test_block`], and the text is assembled both as a `.n1` source
+/// and as a literate `.n1.md` document.
+pub fn fuzz_parse_line(data: &[u8]) {
+    let text = String::from_utf8_lossy(data);
+    let symbols = SymbolTable::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let _ = parse_line(line, index + 1);
+        let _ = encode_single_line(line, 0, &symbols);
+    }
+
+    let _ = parse_test_block(&text, 1, text.lines().count() + 1);
+    let _ = assemble_from_source(&text, "fuzz.n1");
+    let _ = assemble_from_source(&text, "fuzz.n1.md");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fuzz_parse_line;
+
+    #[test]
+    fn malformed_input_does_not_panic() {
+        for input in [
+            &b""[..],
+            b"\xFF\xFE\x00MOV R0, #1",
+            b"LOAD R0, [R1",
+            b"LOAD R0, [R1 + #-32768]",
+            b"label: .ascii \"unterminated",
+            b".org 0xFFFF\n.word 1, 2",
+            b"```n1\nMOV R0, #\n```\n```n1test\nR0 ==\n",
+        ] {
+            fuzz_parse_line(input);
+        }
+    }
+}
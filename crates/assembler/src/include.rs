@@ -3,16 +3,22 @@
 //! This module handles expanding `.include` directives before the main
 //! assembly pipeline. It supports:
 //! - Recursive includes (files may include other files)
-//! - Circular include detection
+//! - Circular include detection, reporting the whole cycle
+//! - A nesting depth limit, reporting the chain that exceeded it
 //! - Mixed format includes (`.n1` and `.n1.md`)
 //! - Source location tracking with include chains
+//! - Resolution of `.incbin` binary imports relative to the including file
+//! - Fallback to the bundled standard library for unresolved relative paths
 
-use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use crate::parser::{parse_line, Directive, ParsedLine};
-use crate::source::{extract_source, SourceLine, TestBlock};
+use crate::parser::{parse_line, Directive, IncBinOperands, ParsedLine};
+use crate::source::{decode_source, extract_source, InvalidUtf8, SourceLine, TestBlock};
+use crate::stdlib::{embedded_source, resolve_stdlib};
+
+/// Deepest `.include` nesting [`expand_includes`] accepts.
+pub const DEFAULT_MAX_INCLUDE_DEPTH: u32 = 32;
 
 /// An expanded source line with full include chain context.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -65,10 +71,31 @@ pub enum IncludeErrorKind {
     FileNotFound,
     /// I/O error reading file.
     IoError(String),
-    /// Circular include detected.
-    CircularInclude(PathBuf),
+    /// File is not valid UTF-8.
+    InvalidUtf8(InvalidUtf8),
+    /// Circular include detected. Holds each file on the cycle with the line
+    /// of its `.include` (or `.incbin`), starting and ending at the file
+    /// that is included again.
+    CircularInclude(Vec<IncludeEntry>),
+    /// Includes nest deeper than the limit. The error's `include_chain` and
+    /// `path` name every file on the way down.
+    DepthExceeded {
+        /// Deepest nesting allowed.
+        limit: u32,
+    },
     /// Parse error in the source.
     ParseError(String),
+    /// `.incbin` range lies outside the file.
+    IncbinOutOfRange {
+        /// Size of the file in bytes.
+        file_len: usize,
+        /// Requested start offset.
+        offset: usize,
+        /// Requested length, if given.
+        length: Option<usize>,
+    },
+    /// `.incbin` data does not fit in the 16-bit address space.
+    IncbinTooLarge(usize),
 }
 
 impl std::fmt::Display for IncludeError {
@@ -77,22 +104,63 @@ impl std::fmt::Display for IncludeError {
         match &self.kind {
             IncludeErrorKind::FileNotFound => write!(f, "file not found"),
             IncludeErrorKind::IoError(msg) => write!(f, "I/O error: {msg}"),
-            IncludeErrorKind::CircularInclude(path) => {
-                write!(f, "circular include detected: {}", path.display())
+            IncludeErrorKind::InvalidUtf8(error) => write!(f, "{error}"),
+            IncludeErrorKind::CircularInclude(cycle) => {
+                write!(f, "circular include: ")?;
+                write_chain(f, cycle)?;
+                match cycle.first() {
+                    Some(first) => write!(f, "{}", first.from_file.display()),
+                    None => write!(f, "{}", self.path.display()),
+                }
+            }
+            IncludeErrorKind::DepthExceeded { limit } => {
+                write!(f, "include depth limit of {limit} exceeded: ")?;
+                write_chain(f, &self.include_chain)?;
+                write!(f, "{}", self.path.display())
             }
             IncludeErrorKind::ParseError(msg) => write!(f, "parse error: {msg}"),
+            IncludeErrorKind::IncbinOutOfRange {
+                file_len,
+                offset,
+                length,
+            } => match length {
+                Some(length) => write!(
+                    f,
+                    "incbin range {offset}+{length} exceeds file size of {file_len} bytes"
+                ),
+                None => write!(
+                    f,
+                    "incbin offset {offset} exceeds file size of {file_len} bytes"
+                ),
+            },
+            IncludeErrorKind::IncbinTooLarge(len) => {
+                write!(
+                    f,
+                    "incbin data of {len} bytes exceeds the 64 KiB address space"
+                )
+            }
         }
     }
 }
 
 impl std::error::Error for IncludeError {}
 
+/// Writes `entries` as `file:line -> ` steps, outermost first.
+fn write_chain(f: &mut std::fmt::Formatter<'_>, entries: &[IncludeEntry]) -> std::fmt::Result {
+    for entry in entries {
+        write!(f, "{}:{} -> ", entry.from_file.display(), entry.line)?;
+    }
+    Ok(())
+}
+
 /// Result of include expansion, containing both source lines and test blocks.
 pub struct ExpansionResult {
     /// Expanded source lines in document order.
     pub lines: Vec<ExpandedLine>,
     /// Test blocks in document order (ordered by position in the expanded assembly stream).
     pub test_blocks: Vec<ExpandedTestBlock>,
+    /// Every source file read, in the order first read, each listed once.
+    pub files: Vec<PathBuf>,
 }
 
 /// Expands all `.include` directives in a source file.
@@ -111,43 +179,87 @@ pub struct ExpansionResult {
 /// - The file cannot be read
 /// - A circular include is detected
 /// - An included file does not exist
+/// - Includes nest deeper than [`DEFAULT_MAX_INCLUDE_DEPTH`]
 pub fn expand_includes(root_path: &Path) -> Result<ExpansionResult, IncludeError> {
-    let mut visited = HashSet::new();
-    let mut include_chain = Vec::new();
+    expand_includes_with_limit(root_path, DEFAULT_MAX_INCLUDE_DEPTH)
+}
+
+/// Expands includes like [`expand_includes`], allowing at most `max_depth`
+/// levels of nesting below the root file.
+///
+/// # Errors
+///
+/// Returns an `IncludeError` under the same conditions as
+/// [`expand_includes`], with `max_depth` as the depth limit.
+pub fn expand_includes_with_limit(
+    root_path: &Path,
+    max_depth: u32,
+) -> Result<ExpansionResult, IncludeError> {
+    let mut walk = IncludeWalk {
+        active: Vec::new(),
+        include_chain: Vec::new(),
+        max_depth,
+    };
     let mut result = ExpansionResult {
         lines: Vec::new(),
         test_blocks: Vec::new(),
+        files: Vec::new(),
     };
-    expand_includes_recursive(root_path, &mut visited, &mut include_chain, &mut result)?;
+    expand_includes_recursive(root_path, &mut walk, &mut result)?;
     Ok(result)
 }
 
+/// The path from the root file to the file being expanded.
+struct IncludeWalk {
+    /// Canonical path of each file being expanded, outermost first.
+    active: Vec<PathBuf>,
+    /// The `.include` each active file is expanding, outermost first.
+    include_chain: Vec<IncludeEntry>,
+    max_depth: u32,
+}
+
 fn expand_includes_recursive(
     path: &Path,
-    visited: &mut HashSet<PathBuf>,
-    include_chain: &mut Vec<IncludeEntry>,
+    walk: &mut IncludeWalk,
     result: &mut ExpansionResult,
 ) -> Result<(), IncludeError> {
-    let canonical = path.canonicalize().map_err(|_| IncludeError {
+    let canonical = canonical_source(path).ok_or_else(|| IncludeError {
         path: path.to_path_buf(),
-        include_chain: include_chain.clone(),
+        include_chain: walk.include_chain.clone(),
         kind: IncludeErrorKind::FileNotFound,
     })?;
 
-    if visited.contains(&canonical) {
+    if let Some(start) = walk.active.iter().position(|active| *active == canonical) {
         return Err(IncludeError {
             path: path.to_path_buf(),
-            include_chain: include_chain.clone(),
-            kind: IncludeErrorKind::CircularInclude(canonical),
+            include_chain: walk.include_chain.clone(),
+            kind: IncludeErrorKind::CircularInclude(walk.include_chain[start..].to_vec()),
         });
     }
-    visited.insert(canonical.clone());
+    if walk.include_chain.len() > walk.max_depth as usize {
+        return Err(IncludeError {
+            path: path.to_path_buf(),
+            include_chain: walk.include_chain.clone(),
+            kind: IncludeErrorKind::DepthExceeded {
+                limit: walk.max_depth,
+            },
+        });
+    }
+    walk.active.push(canonical);
 
-    let content = fs::read_to_string(path).map_err(|e| IncludeError {
+    let bytes = read_source(path).map_err(|e| IncludeError {
         path: path.to_path_buf(),
-        include_chain: include_chain.clone(),
+        include_chain: walk.include_chain.clone(),
         kind: IncludeErrorKind::IoError(e.to_string()),
     })?;
+    let content = decode_source(&bytes).map_err(|e| IncludeError {
+        path: path.to_path_buf(),
+        include_chain: walk.include_chain.clone(),
+        kind: IncludeErrorKind::InvalidUtf8(e),
+    })?;
+    if !result.files.contains(&path.to_path_buf()) {
+        result.files.push(path.to_path_buf());
+    }
 
     let source = extract_source(path, &content);
 
@@ -164,7 +276,7 @@ fn expand_includes_recursive(
                 result.test_blocks.push(ExpandedTestBlock {
                     block: test_block,
                     file_path: path.to_path_buf(),
-                    include_chain: include_chain.clone(),
+                    include_chain: walk.include_chain.clone(),
                 });
             } else {
                 break;
@@ -183,24 +295,24 @@ fn expand_includes_recursive(
                     from_file: path.to_path_buf(),
                     line: original_line,
                 };
-                include_chain.push(entry);
+                walk.include_chain.push(entry);
 
-                expand_includes_recursive(&resolved, visited, include_chain, result)?;
+                expand_includes_recursive(&resolved, walk, result)?;
 
-                include_chain.pop();
+                walk.include_chain.pop();
             }
             Ok(_) => {
                 result.lines.push(ExpandedLine {
                     text,
                     original_line,
                     file_path: path.to_path_buf(),
-                    include_chain: include_chain.clone(),
+                    include_chain: walk.include_chain.clone(),
                 });
             }
             Err(e) => {
                 return Err(IncludeError {
                     path: path.to_path_buf(),
-                    include_chain: include_chain.clone(),
+                    include_chain: walk.include_chain.clone(),
                     kind: IncludeErrorKind::ParseError(e.to_string()),
                 });
             }
@@ -211,26 +323,119 @@ fn expand_includes_recursive(
         result.test_blocks.push(ExpandedTestBlock {
             block: test_block,
             file_path: path.to_path_buf(),
-            include_chain: include_chain.clone(),
+            include_chain: walk.include_chain.clone(),
         });
     }
 
-    visited.remove(&canonical);
+    walk.active.pop();
+    Ok(())
+}
+
+/// Canonical path of a source file; embedded stdlib modules are their own
+/// canonical path.
+fn canonical_source(path: &Path) -> Option<PathBuf> {
+    if embedded_source(path).is_some() {
+        return Some(path.to_path_buf());
+    }
+    path.canonicalize().ok()
+}
+
+/// Reads a source file from disk or from the embedded stdlib.
+fn read_source(path: &Path) -> std::io::Result<Vec<u8>> {
+    embedded_source(path).map_or_else(|| fs::read(path), |source| Ok(source.as_bytes().to_vec()))
+}
+
+/// Largest `.incbin` payload that can be placed in the address space.
+const MAX_INCBIN_BYTES: usize = 0xFFFF;
+
+/// Loads the bytes requested by an `.incbin` directive.
+///
+/// The path is resolved relative to the file containing `line`, the same way
+/// `.include` paths are. Embedding a source file that is part of the current
+/// include chain is reported as a circular include.
+///
+/// # Errors
+///
+/// Returns an `IncludeError` if the file is missing or unreadable, is one of
+/// the sources being assembled, or the requested range is out of bounds.
+pub fn resolve_incbin(ops: &mut IncBinOperands, line: &ExpandedLine) -> Result<(), IncludeError> {
+    let resolved = resolve_include_path(&ops.path, &line.file_path);
+    let error = |kind| IncludeError {
+        path: resolved.clone(),
+        include_chain: line.include_chain.clone(),
+        kind,
+    };
+
+    let canonical = resolved
+        .canonicalize()
+        .map_err(|_| error(IncludeErrorKind::FileNotFound))?;
+    let mut chain = line.include_chain.clone();
+    chain.push(IncludeEntry {
+        from_file: line.file_path.clone(),
+        line: line.original_line,
+    });
+    let start = chain.iter().position(|entry| {
+        entry
+            .from_file
+            .canonicalize()
+            .is_ok_and(|source| source == canonical)
+    });
+    if let Some(start) = start {
+        return Err(error(IncludeErrorKind::CircularInclude(
+            chain.split_off(start),
+        )));
+    }
+
+    let content =
+        fs::read(&resolved).map_err(|e| error(IncludeErrorKind::IoError(e.to_string())))?;
+
+    let out_of_range = || {
+        error(IncludeErrorKind::IncbinOutOfRange {
+            file_len: content.len(),
+            offset: ops.offset,
+            length: ops.length,
+        })
+    };
+    if ops.offset > content.len() {
+        return Err(out_of_range());
+    }
+    let end = match ops.length {
+        Some(length) => ops
+            .offset
+            .checked_add(length)
+            .filter(|&end| end <= content.len())
+            .ok_or_else(out_of_range)?,
+        None => content.len(),
+    };
+
+    let data = &content[ops.offset..end];
+    if data.len() > MAX_INCBIN_BYTES {
+        return Err(error(IncludeErrorKind::IncbinTooLarge(data.len())));
+    }
+    ops.data = data.to_vec();
     Ok(())
 }
 
 /// Resolves an include path relative to the containing file's directory.
-fn resolve_include_path(include_path: &str, containing_file: &Path) -> PathBuf {
+///
+/// Relative paths that do not exist next to the containing file fall back to
+/// the bundled standard library, so `.include "mem.n1.md"` finds the stdlib
+/// module unless the program ships its own file of that name.
+pub(crate) fn resolve_include_path(include_path: &str, containing_file: &Path) -> PathBuf {
     let include = PathBuf::from(include_path);
 
     if include.is_absolute() {
-        include
-    } else {
-        match containing_file.parent() {
-            Some(dir) => dir.join(include),
-            None => include,
-        }
+        return include;
     }
+
+    let local = containing_file
+        .parent()
+        .map_or_else(|| include.clone(), |dir| dir.join(&include));
+    if local.exists() || embedded_source(&local).is_some() {
+        return local;
+    }
+
+    resolve_stdlib(&include).unwrap_or(local)
 }
 
 /// Formats an include chain for error messages.
@@ -253,7 +458,7 @@ pub fn format_include_chain(line: &ExpandedLine) -> String {
                 entry.line
             ));
         }
-        parts.join(" (") + &")".repeat(line.include_chain.len())
+        parts.join(" (") + ")".repeat(line.include_chain.len()).as_str()
     }
 }
 
@@ -390,6 +595,61 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn circular_include_reports_the_whole_cycle() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let main = create_temp_file(dir, "main.n1", ".include \"a.n1\"\n");
+        let a = create_temp_file(dir, "a.n1", ".include \"b.n1\"\n");
+        let b = create_temp_file(dir, "b.n1", "NOP\n.include \"c.n1\"\n");
+        let c = create_temp_file(dir, "c.n1", "NOP\nNOP\n.include \"a.n1\"\n");
+
+        let err = expand_includes(&main).err().unwrap();
+        let entry = |from_file: &PathBuf, line| IncludeEntry {
+            from_file: from_file.clone(),
+            line,
+        };
+        assert_eq!(
+            err.kind,
+            IncludeErrorKind::CircularInclude(vec![entry(&a, 1), entry(&b, 2), entry(&c, 3)])
+        );
+        assert_eq!(err.include_chain.len(), 4);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{}: circular include: {}:1 -> {}:2 -> {}:3 -> {}",
+                a.display(),
+                a.display(),
+                b.display(),
+                c.display(),
+                a.display()
+            )
+        );
+    }
+
+    #[test]
+    fn include_depth_limit_names_the_chain() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let main = create_temp_file(dir, "main.n1", ".include \"a.n1\"\n");
+        let a = create_temp_file(dir, "a.n1", ".include \"b.n1\"\n");
+        let b = create_temp_file(dir, "b.n1", "NOP\n");
+
+        assert!(expand_includes_with_limit(&main, 2).is_ok());
+        let err = expand_includes_with_limit(&main, 1).err().unwrap();
+        assert_eq!(err.kind, IncludeErrorKind::DepthExceeded { limit: 1 });
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{}: include depth limit of 1 exceeded: {}:1 -> {}:1 -> {}",
+                b.display(),
+                main.display(),
+                a.display(),
+                b.display()
+            )
+        );
+    }
+
     #[test]
     fn file_not_found_error() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -407,6 +667,26 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn invalid_utf8_include_reports_byte_offset() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join("bad.n1"), b"NOP\n\xC3(\n").unwrap();
+        let path = create_temp_file(temp_dir.path(), "test.n1", ".include \"bad.n1\"\n");
+
+        let Err(error) = expand_includes(&path) else {
+            panic!("invalid UTF-8 should not expand");
+        };
+        assert_eq!(
+            error.kind,
+            IncludeErrorKind::InvalidUtf8(InvalidUtf8 {
+                offset: 4,
+                line: 2,
+                column: 1,
+            })
+        );
+        assert_eq!(error.include_chain.len(), 1);
+    }
+
     #[test]
     fn expand_literate_file() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -641,4 +921,90 @@ R0 == 0x0001
         assert_eq!(result.lines[1].text, ".twchar \"AB\"");
         assert_eq!(result.lines[2].text, ".tstring \"HELLO\"");
     }
+
+    fn incbin_line(
+        dir: &Path,
+        path: &str,
+        offset: usize,
+        length: Option<usize>,
+    ) -> (IncBinOperands, ExpandedLine) {
+        let ops = IncBinOperands {
+            path: path.to_string(),
+            offset,
+            length,
+            data: Vec::new(),
+        };
+        let line = ExpandedLine {
+            text: format!(".incbin \"{path}\""),
+            original_line: 1,
+            file_path: create_temp_file(dir, "main.n1", ""),
+            include_chain: Vec::new(),
+        };
+        (ops, line)
+    }
+
+    #[test]
+    fn incbin_reads_selected_range() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join("data.bin"), [1u8, 2, 3, 4, 5]).unwrap();
+
+        let (mut ops, line) = incbin_line(temp_dir.path(), "data.bin", 0, None);
+        resolve_incbin(&mut ops, &line).unwrap();
+        assert_eq!(ops.data, [1, 2, 3, 4, 5]);
+
+        let (mut ops, line) = incbin_line(temp_dir.path(), "data.bin", 1, Some(3));
+        resolve_incbin(&mut ops, &line).unwrap();
+        assert_eq!(ops.data, [2, 3, 4]);
+    }
+
+    #[test]
+    fn incbin_rejects_range_past_end_of_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join("data.bin"), [1u8, 2, 3]).unwrap();
+
+        let (mut ops, line) = incbin_line(temp_dir.path(), "data.bin", 2, Some(2));
+        let err = resolve_incbin(&mut ops, &line).unwrap_err();
+        assert_eq!(
+            err.kind,
+            IncludeErrorKind::IncbinOutOfRange {
+                file_len: 3,
+                offset: 2,
+                length: Some(2),
+            }
+        );
+
+        let (mut ops, line) = incbin_line(temp_dir.path(), "data.bin", 4, None);
+        assert!(resolve_incbin(&mut ops, &line).is_err());
+    }
+
+    #[test]
+    fn incbin_missing_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (mut ops, line) = incbin_line(temp_dir.path(), "missing.bin", 0, None);
+        let err = resolve_incbin(&mut ops, &line).unwrap_err();
+        assert_eq!(err.kind, IncludeErrorKind::FileNotFound);
+    }
+
+    #[test]
+    fn incbin_of_own_source_is_circular() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (mut ops, line) = incbin_line(temp_dir.path(), "main.n1", 0, None);
+        let err = resolve_incbin(&mut ops, &line).unwrap_err();
+        assert_eq!(
+            err.kind,
+            IncludeErrorKind::CircularInclude(vec![IncludeEntry {
+                from_file: line.file_path.clone(),
+                line: 1,
+            }])
+        );
+    }
+
+    #[test]
+    fn incbin_rejects_oversized_payload() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join("big.bin"), vec![0u8; 0x1_0000]).unwrap();
+        let (mut ops, line) = incbin_line(temp_dir.path(), "big.bin", 0, None);
+        let err = resolve_incbin(&mut ops, &line).unwrap_err();
+        assert_eq!(err.kind, IncludeErrorKind::IncbinTooLarge(0x1_0000));
+    }
 }
@@ -0,0 +1,68 @@
+//! Program metadata set by `.title`, `.author` and `.version`.
+//!
+//! The fields are free text for hosts to display; none of them affect the
+//! binary.
+
+use alloc::string::String;
+
+/// A metadata directive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InfoField {
+    /// `.title "text"`.
+    Title,
+    /// `.author "text"`.
+    Author,
+    /// `.version "text"`.
+    Version,
+}
+
+impl InfoField {
+    /// Directive name without the leading dot.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Title => "title",
+            Self::Author => "author",
+            Self::Version => "version",
+        }
+    }
+}
+
+/// Metadata a program declares about itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProgramInfo {
+    /// Display title, from `.title`.
+    pub title: Option<String>,
+    /// Author, from `.author`.
+    pub author: Option<String>,
+    /// Program version, from `.version`.
+    pub version: Option<String>,
+}
+
+impl ProgramInfo {
+    /// Returns the value of `field`, if the program set it.
+    #[must_use]
+    pub fn get(&self, field: InfoField) -> Option<&str> {
+        match field {
+            InfoField::Title => self.title.as_deref(),
+            InfoField::Author => self.author.as_deref(),
+            InfoField::Version => self.version.as_deref(),
+        }
+    }
+
+    /// Sets `field` to `value`.
+    pub fn set(&mut self, field: InfoField, value: String) {
+        let slot = match field {
+            InfoField::Title => &mut self.title,
+            InfoField::Author => &mut self.author,
+            InfoField::Version => &mut self.version,
+        };
+        *slot = Some(value);
+    }
+
+    /// Returns true when no metadata is set.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.title.is_none() && self.author.is_none() && self.version.is_none()
+    }
+}
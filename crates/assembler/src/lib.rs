@@ -1,24 +1,100 @@
 //! Nullbyte Directive assembler library.
+//!
+//! The parser, encoder and symbol table only need `alloc`. Everything that
+//! touches the filesystem — include expansion, the file-based pipeline in
+//! [`assembler`], the standard library and the test runner — sits behind
+//! the default `std` feature, so embedded hosts can depend on the crate
+//! with `default-features = false` and feed it source lines directly.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 use emulator_core as _;
 
+/// In-memory build artifacts and the writers that store them.
+#[cfg(feature = "std")]
+pub mod artifacts;
 /// Top-level two-pass assembler pipeline.
+#[cfg(feature = "std")]
 pub mod assembler;
+/// `MEMCPY` and `MEMCMP` block pseudo-ops.
+pub mod block_ops;
+/// Per-basic-block worst-case cycle estimates.
+#[cfg(feature = "std")]
+pub mod budget;
+#[cfg(feature = "std")]
+pub mod bundle;
+/// Opt-in calling convention checker.
+#[cfg(feature = "std")]
+pub mod callconv;
+/// ISA conformance suite generator.
+#[cfg(feature = "std")]
+pub mod conformance;
+/// Golden binary corpus for encoding stability.
+#[cfg(feature = "std")]
+pub mod corpus;
+/// Native versus WASM per-tick state digest comparison.
+#[cfg(feature = "std")]
+pub mod cross_check;
+/// Determinism self-check for assembled programs.
+#[cfg(feature = "std")]
+pub mod determinism;
+/// Unified diffs for mismatched text output.
+pub mod diff;
+/// C and Rust source wrappers for assembled images.
+pub mod emit;
 /// Instruction and directive encoding.
 pub mod encoder;
 /// Structured parse/assembly error types.
+#[cfg(feature = "std")]
 pub mod errors;
+/// Panic-free fuzzing entry points.
+#[cfg(feature = "std")]
+pub mod fuzz;
 /// Include expansion (Pass 0).
+#[cfg(feature = "std")]
 pub mod include;
+/// Program metadata directives.
+pub mod info;
+/// Parsing and structural diffs of assembly listings.
+pub mod listing;
+/// Language metadata for editor completion.
+pub mod metadata;
 /// Mnemonic resolution against emulator opcode encoding tables.
 pub mod mnemonic;
+/// Opt-in peephole optimizer.
+pub mod optimize;
 /// Assembly parser for instructions, labels, and directives.
 pub mod parser;
+/// Single-line encoding previews for editor hovers.
+#[cfg(feature = "std")]
+pub mod preview;
+/// Reproducible-build checks and build ids.
+#[cfg(feature = "std")]
+pub mod reproducible;
+/// Rhai scripting against an assembled program.
+#[cfg(feature = "script")]
+pub mod script;
 /// Source loading and literate Markdown extraction.
+#[cfg(feature = "std")]
 pub mod source;
+/// Per-file and per-section byte and symbol statistics.
+#[cfg(feature = "std")]
+pub mod stats;
+/// Bundled standard library of verified routines.
+#[cfg(feature = "std")]
+pub mod stdlib;
+/// Opt-in string table deduplication.
+pub mod strings;
 /// Symbol table and pass-1 address assignment.
 pub mod symbols;
 /// Inline test format parsing (`n1test` blocks).
+#[cfg(feature = "std")]
 pub mod test_format;
 /// HALT-driven test execution engine.
+#[cfg(feature = "std")]
 pub mod test_runner;
+/// Trace files, program maps and symbolic trace dumps.
+#[cfg(feature = "std")]
+pub mod trace;
@@ -0,0 +1,380 @@
+//! Parsing and structural diffs of assembly listings.
+//!
+//! A listing is what `build --verbose` prints: one line per source line
+//! that emits bytes, `AAAA: HH HH ..  source ; location`. [`diff_listings`]
+//! aligns two listings by source text rather than by address, so inserting
+//! one instruction reports one addition plus an address shift for the code
+//! after it instead of a wall of changed hex.
+
+use alloc::{string::String, vec, vec::Vec};
+
+use crate::diff::{edit_script, Edit};
+
+/// One entry of a listing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListingLine {
+    /// Address of the first byte.
+    pub address: u16,
+    /// Bytes emitted by the source line.
+    pub bytes: Vec<u8>,
+    /// Source text, trimmed.
+    pub source: String,
+    /// Source location, such as `prog.n1:12`.
+    pub location: String,
+}
+
+impl ListingLine {
+    /// Parses one listing line, or returns `None` if `line` is not a
+    /// listing entry.
+    #[must_use]
+    pub fn parse(line: &str) -> Option<Self> {
+        let (address, rest) = line.split_once(": ")?;
+        if address.len() != 4 {
+            return None;
+        }
+        let address = u16::from_str_radix(address, 16).ok()?;
+        let (mut body, location) = rest.rsplit_once(" ; ")?;
+
+        let mut bytes = Vec::new();
+        while let Some((token, tail)) = body.split_once(' ') {
+            if token.len() != 2 || !token.bytes().all(|b| b.is_ascii_hexdigit()) {
+                break;
+            }
+            bytes.push(u8::from_str_radix(token, 16).ok()?);
+            body = tail;
+        }
+        if bytes.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            address,
+            bytes,
+            source: body.trim().into(),
+            location: location.trim().into(),
+        })
+    }
+
+    /// Address of the last byte.
+    #[must_use]
+    pub fn end_address(&self) -> u16 {
+        let len = u16::try_from(self.bytes.len()).unwrap_or(u16::MAX);
+        self.address.wrapping_add(len.saturating_sub(1))
+    }
+
+    /// Source text without its comment or repeated whitespace, used to
+    /// align entries across builds.
+    fn key(&self) -> String {
+        let mut in_string = false;
+        let code = self
+            .source
+            .char_indices()
+            .find(|&(_, ch)| {
+                if ch == '"' {
+                    in_string = !in_string;
+                }
+                ch == ';' && !in_string
+            })
+            .map_or(self.source.as_str(), |(index, _)| &self.source[..index]);
+        code.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Parses every listing entry in `text`, skipping other lines such as
+/// warnings or the `Assembled ...` summary.
+#[must_use]
+pub fn parse_listing(text: &str) -> Vec<ListingLine> {
+    text.lines().filter_map(ListingLine::parse).collect()
+}
+
+/// One difference between two listings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListingChange {
+    /// An entry only in the new listing.
+    Added(ListingLine),
+    /// An entry only in the old listing.
+    Removed(ListingLine),
+    /// An entry whose source or encoding changed but not its size.
+    Changed {
+        /// The entry before.
+        old: ListingLine,
+        /// The entry after.
+        new: ListingLine,
+    },
+    /// An entry whose size changed.
+    Resized {
+        /// The entry before.
+        old: ListingLine,
+        /// The entry after.
+        new: ListingLine,
+    },
+}
+
+/// A run of consecutive aligned entries that all moved by the same amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressShift {
+    /// Old address of the run's first entry.
+    pub old_start: u16,
+    /// Old address of the run's last byte.
+    pub old_end: u16,
+    /// New address minus old address.
+    pub delta: i32,
+    /// Entries in the run.
+    pub entries: usize,
+}
+
+/// Structural differences between two listings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListingDiff {
+    /// Added, removed, changed and resized entries, in listing order.
+    pub changes: Vec<ListingChange>,
+    /// Runs of aligned entries that moved, in listing order.
+    pub shifts: Vec<AddressShift>,
+    /// Aligned entries with identical bytes, whether or not they moved.
+    pub unchanged: usize,
+}
+
+impl ListingDiff {
+    /// Returns whether the listings have the same entries at the same
+    /// addresses.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.shifts.is_empty()
+    }
+}
+
+/// Compares two listings entry by entry.
+///
+/// Entries are aligned by their source text, ignoring comments and
+/// whitespace. Aligned entries whose bytes differ are reported as changed
+/// or resized, and so are removals paired with an addition of the same
+/// mnemonic in the same place. Aligned entries that moved are grouped into
+/// [`AddressShift`]s.
+#[must_use]
+pub fn diff_listings(old: &[ListingLine], new: &[ListingLine]) -> ListingDiff {
+    let old_keys: Vec<String> = old.iter().map(ListingLine::key).collect();
+    let new_keys: Vec<String> = new.iter().map(ListingLine::key).collect();
+    let old_refs: Vec<&str> = old_keys.iter().map(String::as_str).collect();
+    let new_refs: Vec<&str> = new_keys.iter().map(String::as_str).collect();
+
+    let mut diff = ListingDiff::default();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    for (edit, old_idx, new_idx) in edit_script(&old_refs, &new_refs) {
+        match edit {
+            Edit::Remove => removed.push(&old[old_idx]),
+            Edit::Add => added.push(&new[new_idx]),
+            Edit::Keep => {
+                flush_replacements(&mut diff, &mut removed, &mut added);
+                let (old, new) = (&old[old_idx], &new[new_idx]);
+                if old.bytes == new.bytes {
+                    diff.unchanged += 1;
+                } else {
+                    diff.changes.push(replacement(old, new));
+                }
+                record_shift(&mut diff.shifts, old, new);
+            }
+        }
+    }
+    flush_replacements(&mut diff, &mut removed, &mut added);
+    diff
+}
+
+fn replacement(old: &ListingLine, new: &ListingLine) -> ListingChange {
+    let (old, new) = (old.clone(), new.clone());
+    if old.bytes.len() == new.bytes.len() {
+        ListingChange::Changed { old, new }
+    } else {
+        ListingChange::Resized { old, new }
+    }
+}
+
+/// Pairs up a run of removals and the additions after it.
+///
+/// A removal pairs with the first unpaired addition of the same mnemonic
+/// or directive, so replacing an immediate reads as one change while an
+/// inserted instruction next to it stays an addition.
+fn flush_replacements(
+    diff: &mut ListingDiff,
+    removed: &mut Vec<&ListingLine>,
+    added: &mut Vec<&ListingLine>,
+) {
+    let mut partners: Vec<Option<&ListingLine>> = vec![None; added.len()];
+    for old in removed.drain(..) {
+        let partner = added.iter().zip(&partners).position(|(new, partner)| {
+            partner.is_none() && mnemonic(&new.key()) == mnemonic(&old.key())
+        });
+        match partner {
+            Some(index) => partners[index] = Some(old),
+            None => diff.changes.push(ListingChange::Removed(old.clone())),
+        }
+    }
+    for (new, partner) in added.drain(..).zip(partners) {
+        diff.changes.push(partner.map_or_else(
+            || ListingChange::Added(new.clone()),
+            |old| replacement(old, new),
+        ));
+    }
+}
+
+fn mnemonic(key: &str) -> &str {
+    key.split_whitespace().next().unwrap_or_default()
+}
+
+/// Extends the last shift with an aligned pair, or starts a new one.
+fn record_shift(shifts: &mut Vec<AddressShift>, old: &ListingLine, new: &ListingLine) {
+    let delta = i32::from(new.address) - i32::from(old.address);
+    if delta == 0 {
+        return;
+    }
+    match shifts.last_mut() {
+        Some(shift) if shift.delta == delta && shift.old_end.wrapping_add(1) == old.address => {
+            shift.old_end = old.end_address();
+            shift.entries += 1;
+        }
+        _ => shifts.push(AddressShift {
+            old_start: old.address,
+            old_end: old.end_address(),
+            delta,
+            entries: 1,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = "\
+0000: 10 05 E1 32      MOV R0, #0xE132 ; boot ; prog.n1:3
+0004: 42 45 00 01      ADD R1, R1, #1 ; prog.n1:5
+0008: 32 01            STORE R1, [R0] ; prog.n1:6
+000A: 00 10            HALT ; prog.n1:7
+000C: 48 45 4C 4C 4F     .ascii \"A;B\" ; prog.n1:9
+Assembled prog.n1 (17 bytes) -> prog.bin
+";
+
+    #[test]
+    fn parses_listing_entries_and_skips_other_lines() {
+        let lines = parse_listing(OLD);
+        assert_eq!(lines.len(), 5);
+        assert_eq!(
+            lines[0],
+            ListingLine {
+                address: 0,
+                bytes: vec![0x10, 0x05, 0xE1, 0x32],
+                source: "MOV R0, #0xE132 ; boot".into(),
+                location: "prog.n1:3".into(),
+            }
+        );
+        assert_eq!(lines[4].bytes, b"HELLO");
+        assert_eq!(lines[4].source, ".ascii \"A;B\"");
+        assert_eq!(lines[4].key(), ".ascii \"A;B\"");
+        assert_eq!(lines[4].end_address(), 0x0010);
+        assert_eq!(lines[0].key(), "MOV R0, #0xE132");
+    }
+
+    #[test]
+    fn identical_listings_have_no_diff() {
+        let lines = parse_listing(OLD);
+        let diff = diff_listings(&lines, &lines);
+        assert!(diff.is_empty());
+        assert_eq!(diff.unchanged, 5);
+    }
+
+    #[test]
+    fn insertion_reports_the_addition_and_the_shift_after_it() {
+        let new = parse_listing(
+            "\
+0000: 10 05 E1 32      MOV R0, #0xE132 ; prog.n1:3
+0004: 00 00            NOP ; prog.n1:4
+0006: 42 45 00 01      ADD R1, R1, #1 ; prog.n1:6
+000A: 32 01            STORE R1, [R0] ; prog.n1:7
+000C: 00 10            HALT ; prog.n1:8
+000E: 48 45 4C 4C 4F     .ascii \"A;B\" ; prog.n1:10
+",
+        );
+        let diff = diff_listings(&parse_listing(OLD), &new);
+
+        assert_eq!(diff.changes, [ListingChange::Added(new[1].clone())]);
+        assert_eq!(
+            diff.shifts,
+            [AddressShift {
+                old_start: 0x0004,
+                old_end: 0x0010,
+                delta: 2,
+                entries: 4,
+            }]
+        );
+        assert_eq!(diff.unchanged, 5);
+    }
+
+    #[test]
+    fn edits_are_reported_as_changed_or_resized() {
+        let old = parse_listing(OLD);
+        let new = parse_listing(
+            "\
+0000: 10 05 E1 32      MOV R0, #0xE132 ; prog.n1:3
+0004: 42 45 00 02      ADD R1, R1, #2 ; prog.n1:5
+0008: 32 01            STORE R1, [R0] ; prog.n1:6
+000A: 00 10            HALT ; prog.n1:7
+000C: 48 49            .ascii \"A;B\" ; prog.n1:9
+",
+        );
+        let diff = diff_listings(&old, &new);
+
+        assert_eq!(
+            diff.changes,
+            [
+                ListingChange::Changed {
+                    old: old[1].clone(),
+                    new: new[1].clone(),
+                },
+                ListingChange::Resized {
+                    old: old[4].clone(),
+                    new: new[4].clone(),
+                },
+            ]
+        );
+        assert!(diff.shifts.is_empty());
+        assert_eq!(diff.unchanged, 3);
+    }
+
+    #[test]
+    fn replacements_pair_by_mnemonic() {
+        let old = parse_listing("0000: 42 45 00 01      ADD R1, R1, #1 ; a.n1:1\n");
+        let new = parse_listing(
+            "\
+0000: 00 00            NOP ; a.n1:1
+0002: 42 45 00 02      ADD R1, R1, #2 ; a.n1:2
+",
+        );
+        let diff = diff_listings(&old, &new);
+
+        assert_eq!(
+            diff.changes,
+            [
+                ListingChange::Added(new[0].clone()),
+                ListingChange::Changed {
+                    old: old[0].clone(),
+                    new: new[1].clone(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn removal_reports_a_negative_shift() {
+        let old = parse_listing(OLD);
+        let mut new = old.clone();
+        new.remove(2);
+        for line in &mut new[2..] {
+            line.address -= 2;
+        }
+        let diff = diff_listings(&old, &new);
+
+        assert_eq!(diff.changes, [ListingChange::Removed(old[2].clone())]);
+        assert_eq!(diff.shifts[0].delta, -2);
+        assert_eq!(diff.shifts[0].entries, 2);
+    }
+}
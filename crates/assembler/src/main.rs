@@ -2,141 +2,584 @@
 
 use std::env;
 use std::ffi::OsString;
+use std::fmt::Write as _;
 use std::fs;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use assembler as _;
-use assembler::assembler::{assemble, AssembleError, AssembleResult};
-use assembler::test_format::parse_test_block;
-use assembler::test_runner::run_tests;
-use emulator_core as _;
+use assembler::artifacts::{json_list, listing_line, ArtifactKind, BuildArtifacts, FileWriter};
+use assembler::assembler::{
+    assemble, assemble_with_options, AssembleError, AssembleOptions, AssembleResult,
+    AssembleWarning,
+};
+use assembler::bundle::ProgramBundle;
+use assembler::callconv::calling_convention_warnings;
+use assembler::conformance::generate_suite;
+use assembler::corpus::Corpus;
+use assembler::cross_check::{first_tick_divergence, native_digests, wasm_digests};
+use assembler::determinism::verify_determinism;
+use assembler::emit::EmitFormat;
+use assembler::include::DEFAULT_MAX_INCLUDE_DEPTH;
+use assembler::listing::{diff_listings, parse_listing, ListingChange, ListingDiff, ListingLine};
+use assembler::reproducible::{build_id, first_divergence, reproducibility_issues};
+#[cfg(feature = "script")]
+use assembler::script::run_script;
+use assembler::stats::assembly_stats;
+use assembler::stdlib::{format_module_listing, services_include};
+use assembler::test_format::{
+    parse_event_script, parse_source_test_block, push_param, ParsedTestBlock,
+};
+use assembler::test_runner::{new_test_state, run_program_tests, TestProgram};
+use assembler::trace::{dump_trace, parse_trace, ProgramMap, TraceWriter};
+use emulator_core::{
+    run_fast_forward, run_ticks_with_budget, run_ticks_with_trace, verify_table, write_params,
+    CompositeMmio, CoreConfig, DebugConsole, DeviceRegisters, DmaController, MmioBus, Mpu, OpenBus,
+    ParamBlock, PasteBuffer, Tele7Config, Tele7Peripheral, TickBatch, TimingModel, TICK_DURATION,
+};
+#[cfg(feature = "script")]
+use rhai as _;
 #[cfg(test)]
 use tempfile as _;
 
-const USAGE_TEXT: &str = "\
-Usage: nullbyte-asm <command> [options]
+mod cli;
+mod serve;
+mod watch;
 
-Commands:
-  build <input> [-o <output>] [--verbose]  Assemble source to binary
-  test  <input>                            Assemble and run inline tests
-
-Options:
-  -o, --output <file>  Output file path (default: input stem + .bin)
-  -v, --verbose        Print listing to stderr (build only)
-  -h, --help           Show this help message
-
-Examples:
-  nullbyte-asm build program.n1.md
-  nullbyte-asm build program.n1.md -o program.bin
-  nullbyte-asm test program.n1.md
-";
+use cli::{CliError, Matches, Shell};
+use serve::{ReloadServer, BUILD_ERROR_FORMAT};
+use watch::{BlockOutcome, SourceStamps};
 
 #[derive(Debug, PartialEq, Eq)]
 enum Command {
     Build(BuildArgs),
     Test(TestArgs),
+    VerifyDeterminism(VerifyArgs),
+    CrossCheck(CrossCheckArgs),
+    Run(RunArgs),
+    VerifyBuild(VerifyBuildArgs),
+    #[cfg(feature = "script")]
+    Script(ScriptArgs),
+    Bundle(BundleArgs),
+    Serve(ServeArgs),
+    ListingDiff(ListingDiffArgs),
+    TraceDump(TraceDumpArgs),
+    Devmap(DevmapArgs),
+    Corpus(CorpusArgs),
+    IsaSuite(IsaSuiteArgs),
+    Completions(Shell),
 }
 
 #[derive(Debug, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
 struct BuildArgs {
     input: PathBuf,
     output: Option<PathBuf>,
     verbose: bool,
+    check_callconv: bool,
+    optimize: bool,
+    dedup_strings: bool,
+    entry: Option<String>,
+    reproducible: bool,
+    map: Option<PathBuf>,
+    emit: Vec<EmitFormat>,
+    max_include_depth: u32,
+    stats: bool,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 struct TestArgs {
     input: PathBuf,
+    capture_fixtures: bool,
+    strict: bool,
+    watch: bool,
+    tags: Vec<String>,
+    skip_tags: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct VerifyArgs {
+    input: PathBuf,
+    runs: u32,
+}
+
+/// Fresh runs compared by `verify-determinism` when `--runs` is omitted.
+const DEFAULT_DETERMINISM_RUNS: u32 = 3;
+
+#[derive(Debug, PartialEq, Eq)]
+struct CrossCheckArgs {
+    input: PathBuf,
+    wasm_pkg: PathBuf,
+    events: Option<PathBuf>,
+    ticks: u32,
+    node: PathBuf,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct RunArgs {
+    input: PathBuf,
+    ticks: u32,
+    realtime: bool,
+    fast_forward: u32,
+    params: ParamBlock,
+    trace: Option<PathBuf>,
+    timing: TimingModel,
+    open_bus: OpenBus,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct VerifyBuildArgs {
+    input: PathBuf,
+    binary: PathBuf,
+    build_id: Option<String>,
+    options: AssembleOptions,
+}
+
+#[cfg(feature = "script")]
+#[derive(Debug, PartialEq, Eq)]
+struct ScriptArgs {
+    program: PathBuf,
+    script: PathBuf,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct BundleArgs {
+    input: PathBuf,
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ServeArgs {
+    input: PathBuf,
+    watch: PathBuf,
+    port: u16,
+    allow_origins: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ListingDiffArgs {
+    old: PathBuf,
+    new: PathBuf,
+    json: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct TraceDumpArgs {
+    trace: PathBuf,
+    map: Option<PathBuf>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct DevmapArgs {
+    registers: bool,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+struct CorpusArgs {
+    update: bool,
+    dir: PathBuf,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct IsaSuiteArgs {
+    dir: PathBuf,
+}
+
+/// Ticks executed by `run` when `--ticks` is omitted: one simulated second.
+const DEFAULT_RUN_TICKS: u32 = 100;
+
 #[derive(Debug)]
 enum ParseResult {
     Command(Command),
-    Help,
+    Version { json: bool },
+    ListStdlib,
+    ServicesInclude,
+    VerifyDecoderTable,
 }
 
-fn parse_args(mut args: impl Iterator<Item = OsString>) -> Result<ParseResult, String> {
-    let first = args.next().ok_or_else(|| "missing command".to_string())?;
-
-    if first == "--help" || first == "-h" {
-        return Ok(ParseResult::Help);
+fn parse_args(mut args: impl Iterator<Item = OsString>) -> Result<ParseResult, CliError> {
+    let first = args.next().ok_or_else(|| CliError::Invalid {
+        message: "missing command".to_string(),
+        command: None,
+    })?;
+    let first = first.to_string_lossy();
+
+    match first.as_ref() {
+        "--help" | "-h" => return Err(CliError::Help(cli::usage())),
+        "--version" | "-V" => return parse_version_args(args),
+        "--list-stdlib" => return Ok(ParseResult::ListStdlib),
+        "--services-include" => return Ok(ParseResult::ServicesInclude),
+        // Hidden: a self-check of the core's decoder for ISA maintainers.
+        "--verify-decoder-table" => return Ok(ParseResult::VerifyDecoderTable),
+        option if option.starts_with('-') => {
+            return Err(CliError::Invalid {
+                message: cli::unknown_option_message(option, cli::GLOBAL_OPTIONS),
+                command: None,
+            })
+        }
+        _ => {}
     }
 
-    let command_str = first.to_string_lossy().to_string();
+    let command = match first.as_ref() {
+        "build" => Command::Build(parse_build_args(args)?),
+        "test" => Command::Test(parse_test_args(args)?),
+        "verify-determinism" => Command::VerifyDeterminism(parse_verify_args(args)?),
+        "cross-check" => Command::CrossCheck(parse_cross_check_args(args)?),
+        "run" => Command::Run(parse_run_args(args)?),
+        "verify" => Command::VerifyBuild(parse_verify_build_args(args)?),
+        #[cfg(feature = "script")]
+        "script" => Command::Script(parse_script_args(args)?),
+        "bundle" => Command::Bundle(parse_bundle_args(args)?),
+        "serve" => Command::Serve(parse_serve_args(args)?),
+        "listing-diff" => Command::ListingDiff(parse_listing_diff_args(args)?),
+        "trace" => Command::TraceDump(parse_trace_args(args)?),
+        "devmap" => Command::Devmap(parse_devmap_args(args)?),
+        "corpus" => Command::Corpus(parse_corpus_args(args)?),
+        "isa-suite" => Command::IsaSuite(parse_isa_suite_args(args)?),
+        "completions" => Command::Completions(parse_completions_args(args)?),
+        other => {
+            return Err(CliError::Invalid {
+                message: cli::unknown_command_message(other),
+                command: None,
+            })
+        }
+    };
+    Ok(ParseResult::Command(command))
+}
 
-    match command_str.as_str() {
-        "build" => parse_build_args(args)
-            .map(Command::Build)
-            .map(ParseResult::Command),
-        "test" => parse_test_args(args)
-            .map(Command::Test)
-            .map(ParseResult::Command),
-        other => Err(format!("unknown command: {other}")),
+fn parse_version_args(args: impl Iterator<Item = OsString>) -> Result<ParseResult, CliError> {
+    let mut json = false;
+    for arg in args {
+        let arg = arg.to_string_lossy();
+        if arg != "--json" {
+            return Err(CliError::Invalid {
+                message: format!("unexpected argument after --version: {arg}"),
+                command: None,
+            });
+        }
+        json = true;
     }
+    Ok(ParseResult::Version { json })
 }
 
-#[allow(clippy::while_let_on_iterator)]
-fn parse_build_args(mut args: impl Iterator<Item = OsString>) -> Result<BuildArgs, String> {
-    let mut input: Option<PathBuf> = None;
-    let mut output: Option<PathBuf> = None;
-    let mut verbose = false;
+fn parse_for(name: &str, args: impl Iterator<Item = OsString>) -> Result<Matches, CliError> {
+    let spec = cli::find_command(name).expect("command is declared in cli::COMMANDS");
+    cli::parse_command(spec, args)
+}
 
-    while let Some(arg) = args.next() {
-        if arg == "--help" || arg == "-h" {
-            return Err(USAGE_TEXT.to_string());
-        }
+fn input_path(matches: &Matches) -> Result<PathBuf, CliError> {
+    matches.single_positional("input path").map(PathBuf::from)
+}
 
-        if arg == "--verbose" || arg == "-v" {
-            verbose = true;
-            continue;
-        }
+/// Parses a count option, rejecting zero and non-numbers as `invalid <what>`.
+fn positive_count(
+    matches: &Matches,
+    long: &str,
+    what: &str,
+    default: u32,
+) -> Result<u32, CliError> {
+    matches.value(long).map_or(Ok(default), |value| {
+        value
+            .to_string_lossy()
+            .parse()
+            .ok()
+            .filter(|&count| count > 0)
+            .ok_or_else(|| matches.error(format!("invalid {what}: {}", value.to_string_lossy())))
+    })
+}
 
-        if arg == "-o" || arg == "--output" {
-            let value = args
-                .next()
-                .ok_or_else(|| "missing value for -o".to_string())?;
-            output = Some(PathBuf::from(value));
-            continue;
-        }
+fn parse_build_args(args: impl Iterator<Item = OsString>) -> Result<BuildArgs, CliError> {
+    let matches = parse_for("build", args)?;
+    Ok(BuildArgs {
+        input: input_path(&matches)?,
+        output: matches.value("output").map(PathBuf::from),
+        verbose: matches.flag("verbose"),
+        check_callconv: matches.flag("check-callconv"),
+        optimize: matches.flag("optimize"),
+        dedup_strings: matches.flag("dedup-strings"),
+        entry: matches
+            .value("entry")
+            .map(|value| value.to_string_lossy().into_owned()),
+        reproducible: matches.flag("reproducible"),
+        map: matches.value("map").map(PathBuf::from),
+        emit: emit_formats(&matches)?,
+        max_include_depth: positive_count(
+            &matches,
+            "max-include-depth",
+            "include depth",
+            DEFAULT_MAX_INCLUDE_DEPTH,
+        )?,
+        stats: matches.flag("stats"),
+    })
+}
 
-        if arg.to_string_lossy().starts_with('-') {
-            return Err(format!("unknown option: {}", arg.to_string_lossy()));
+/// Resolves the repeatable, comma-separated `--emit` option.
+fn emit_formats(matches: &Matches) -> Result<Vec<EmitFormat>, CliError> {
+    let mut formats = Vec::new();
+    for name in tag_list(matches, "emit") {
+        let format = EmitFormat::named(&name).ok_or_else(|| {
+            matches.error(format!(
+                "--emit: unknown format '{name}' (expected {})",
+                EmitFormat::NAMES.join(" or ")
+            ))
+        })?;
+        if !formats.contains(&format) {
+            formats.push(format);
         }
+    }
+    Ok(formats)
+}
 
-        if input.is_some() {
-            return Err("multiple input paths provided".to_string());
-        }
-        input = Some(PathBuf::from(arg));
+fn parse_verify_build_args(
+    args: impl Iterator<Item = OsString>,
+) -> Result<VerifyBuildArgs, CliError> {
+    let matches = parse_for("verify", args)?;
+    Ok(VerifyBuildArgs {
+        input: input_path(&matches)?,
+        binary: matches
+            .value("binary")
+            .map(PathBuf::from)
+            .ok_or_else(|| matches.error("missing --binary"))?,
+        build_id: matches
+            .value("build-id")
+            .map(|value| value.to_string_lossy().into_owned()),
+        options: AssembleOptions {
+            optimize: matches.flag("optimize"),
+            dedup_strings: matches.flag("dedup-strings"),
+            entry: matches
+                .value("entry")
+                .map(|value| value.to_string_lossy().into_owned()),
+            max_include_depth: None,
+        },
+    })
+}
+
+fn parse_test_args(args: impl Iterator<Item = OsString>) -> Result<TestArgs, CliError> {
+    let matches = parse_for("test", args)?;
+    Ok(TestArgs {
+        input: input_path(&matches)?,
+        capture_fixtures: matches.flag("capture-fixture"),
+        strict: matches.flag("strict"),
+        watch: matches.flag("watch"),
+        tags: tag_list(&matches, "tag"),
+        skip_tags: tag_list(&matches, "skip-tag"),
+    })
+}
+
+/// Collects a repeatable, comma-separated tag option.
+fn tag_list(matches: &Matches, long: &str) -> Vec<String> {
+    matches
+        .values(long)
+        .flat_map(|value| {
+            value
+                .to_string_lossy()
+                .split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn parse_verify_args(args: impl Iterator<Item = OsString>) -> Result<VerifyArgs, CliError> {
+    let matches = parse_for("verify-determinism", args)?;
+    Ok(VerifyArgs {
+        input: input_path(&matches)?,
+        runs: positive_count(&matches, "runs", "run count", DEFAULT_DETERMINISM_RUNS)?,
+    })
+}
+
+fn parse_cross_check_args(
+    args: impl Iterator<Item = OsString>,
+) -> Result<CrossCheckArgs, CliError> {
+    let matches = parse_for("cross-check", args)?;
+    Ok(CrossCheckArgs {
+        input: input_path(&matches)?,
+        wasm_pkg: matches
+            .value("wasm-pkg")
+            .map(PathBuf::from)
+            .ok_or_else(|| matches.error("missing --wasm-pkg"))?,
+        events: matches.value("events").map(PathBuf::from),
+        ticks: positive_count(&matches, "ticks", "tick count", DEFAULT_RUN_TICKS)?,
+        node: matches
+            .value("node")
+            .map_or_else(|| PathBuf::from("node"), PathBuf::from),
+    })
+}
+
+fn parse_run_args(args: impl Iterator<Item = OsString>) -> Result<RunArgs, CliError> {
+    let matches = parse_for("run", args)?;
+    Ok(RunArgs {
+        input: input_path(&matches)?,
+        ticks: positive_count(&matches, "ticks", "tick count", DEFAULT_RUN_TICKS)?,
+        realtime: matches.flag("realtime"),
+        fast_forward: positive_count(&matches, "fast-forward", "fast-forward tick count", 0)?,
+        params: param_block(&matches)?,
+        trace: matches.value("trace").map(PathBuf::from),
+        timing: timing_model(&matches)?,
+        open_bus: open_bus(&matches)?,
+    })
+}
+
+/// Resolves `--timing`, defaulting to the reference `v1` table.
+fn timing_model(matches: &Matches) -> Result<TimingModel, CliError> {
+    let Some(name) = matches.value("timing") else {
+        return Ok(TimingModel::V1);
+    };
+    let name = name.to_string_lossy();
+    TimingModel::named(&name).ok_or_else(|| {
+        matches.error(format!(
+            "--timing: unknown timing model '{name}' (expected {})",
+            TimingModel::NAMES.join(" or ")
+        ))
+    })
+}
+
+/// Resolves `--open-bus`, defaulting to reads of zero.
+fn open_bus(matches: &Matches) -> Result<OpenBus, CliError> {
+    let Some(name) = matches.value("open-bus") else {
+        return Ok(OpenBus::Zero);
+    };
+    let name = name.to_string_lossy();
+    OpenBus::named(&name).ok_or_else(|| {
+        matches.error(format!(
+            "--open-bus: unknown open-bus policy '{name}' (expected {})",
+            OpenBus::NAMES.join(", ")
+        ))
+    })
+}
+
+/// Builds the host parameter block from repeated `--param key=value`
+/// options.
+fn param_block(matches: &Matches) -> Result<ParamBlock, CliError> {
+    let mut params = ParamBlock::new();
+    for assignment in matches.values("param") {
+        push_param(&mut params, &assignment.to_string_lossy())
+            .map_err(|message| matches.error(format!("--param: {message}")))?;
     }
+    Ok(params)
+}
 
-    let input = input.ok_or_else(|| "missing input path".to_string())?;
-    Ok(BuildArgs {
-        input,
-        output,
-        verbose,
+fn parse_listing_diff_args(
+    args: impl Iterator<Item = OsString>,
+) -> Result<ListingDiffArgs, CliError> {
+    let matches = parse_for("listing-diff", args)?;
+    let (old, new) = matches.positional_pair("listing path")?;
+    Ok(ListingDiffArgs {
+        old: PathBuf::from(old),
+        new: PathBuf::from(new),
+        json: matches.flag("json"),
     })
 }
 
-fn parse_test_args(args: impl Iterator<Item = OsString>) -> Result<TestArgs, String> {
-    let mut input: Option<PathBuf> = None;
+fn parse_trace_args(args: impl Iterator<Item = OsString>) -> Result<TraceDumpArgs, CliError> {
+    let matches = parse_for("trace", args)?;
+    let (action, trace) = matches.positional_pair("argument")?;
+    if action != "dump" {
+        return Err(matches.error(format!(
+            "unknown trace action: {} (expected dump)",
+            action.to_string_lossy()
+        )));
+    }
+    Ok(TraceDumpArgs {
+        trace: PathBuf::from(trace),
+        map: matches.value("map").map(PathBuf::from),
+    })
+}
 
-    for arg in args {
-        if arg == "--help" || arg == "-h" {
-            return Err(USAGE_TEXT.to_string());
-        }
+fn parse_devmap_args(args: impl Iterator<Item = OsString>) -> Result<DevmapArgs, CliError> {
+    let matches = parse_for("devmap", args)?;
+    matches.no_positionals()?;
+    Ok(DevmapArgs {
+        registers: matches.flag("registers"),
+    })
+}
 
-        if arg.to_string_lossy().starts_with('-') {
-            return Err(format!("unknown option: {}", arg.to_string_lossy()));
+fn parse_corpus_args(args: impl Iterator<Item = OsString>) -> Result<CorpusArgs, CliError> {
+    let matches = parse_for("corpus", args)?;
+    let (action, dir) = matches.positional_pair("argument")?;
+    let update = match action.to_string_lossy().as_ref() {
+        "verify" => false,
+        "update" => true,
+        other => {
+            return Err(matches.error(format!(
+                "unknown corpus action: {other} (expected verify or update)"
+            )))
         }
+    };
+    Ok(CorpusArgs {
+        update,
+        dir: PathBuf::from(dir),
+    })
+}
 
-        if input.is_some() {
-            return Err("multiple input paths provided".to_string());
-        }
-        input = Some(PathBuf::from(arg));
-    }
+fn parse_isa_suite_args(args: impl Iterator<Item = OsString>) -> Result<IsaSuiteArgs, CliError> {
+    let matches = parse_for("isa-suite", args)?;
+    Ok(IsaSuiteArgs {
+        dir: PathBuf::from(matches.single_positional("output directory")?),
+    })
+}
+
+#[cfg(feature = "script")]
+fn parse_script_args(args: impl Iterator<Item = OsString>) -> Result<ScriptArgs, CliError> {
+    let matches = parse_for("script", args)?;
+    let (program, script) = matches.positional_pair("path")?;
+    Ok(ScriptArgs {
+        program: PathBuf::from(program),
+        script: PathBuf::from(script),
+    })
+}
+
+fn parse_bundle_args(args: impl Iterator<Item = OsString>) -> Result<BundleArgs, CliError> {
+    let matches = parse_for("bundle", args)?;
+    Ok(BundleArgs {
+        input: input_path(&matches)?,
+        output: matches.value("output").map(PathBuf::from),
+    })
+}
+
+fn parse_serve_args(args: impl Iterator<Item = OsString>) -> Result<ServeArgs, CliError> {
+    let matches = parse_for("serve", args)?;
+    let input = input_path(&matches)?;
+    let watch = matches.value("watch").map_or_else(
+        || {
+            input
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map_or_else(|| PathBuf::from("."), Path::to_path_buf)
+        },
+        PathBuf::from,
+    );
+    let port = matches
+        .value("port")
+        .map_or(Ok(serve::DEFAULT_PORT), |value| {
+            value
+                .to_string_lossy()
+                .parse()
+                .map_err(|_| matches.error(format!("invalid port: {}", value.to_string_lossy())))
+        })?;
+    let allow_origins = matches
+        .values("allow-origin")
+        .map(|origin| origin.to_string_lossy().into_owned())
+        .collect();
+    Ok(ServeArgs {
+        input,
+        watch,
+        port,
+        allow_origins,
+    })
+}
 
-    let input = input.ok_or_else(|| "missing input path".to_string())?;
-    Ok(TestArgs { input })
+fn parse_completions_args(args: impl Iterator<Item = OsString>) -> Result<Shell, CliError> {
+    let matches = parse_for("completions", args)?;
+    let name = matches.single_positional("shell")?.to_string_lossy();
+    Shell::from_name(&name).ok_or_else(|| {
+        matches.error(format!(
+            "unsupported shell: {name} (expected bash, zsh or fish)"
+        ))
+    })
 }
 
 fn default_output_path(input: &Path) -> PathBuf {
@@ -156,8 +599,14 @@ fn default_output_path(input: &Path) -> PathBuf {
     parent.join(format!("{stem}.bin"))
 }
 
-fn run_build(args: BuildArgs) -> Result<(), i32> {
-    let result = match assemble(&args.input) {
+fn run_build(args: &BuildArgs) -> Result<(), i32> {
+    let options = AssembleOptions {
+        optimize: args.optimize,
+        dedup_strings: args.dedup_strings,
+        entry: args.entry.clone(),
+        max_include_depth: Some(args.max_include_depth),
+    };
+    let result = match assemble_with_options(&args.input, &options) {
         Ok(r) => r,
         Err(e) => {
             report_assemble_error(&e);
@@ -166,32 +615,97 @@ fn run_build(args: BuildArgs) -> Result<(), i32> {
     };
 
     for warning in &result.warnings {
-        eprintln!("warning: {warning}");
+        report_warning(warning);
+    }
+
+    for optimization in &result.optimizations {
+        eprintln!(
+            "{}: optimized: {optimization}",
+            format_source_location(&optimization.location)
+        );
+    }
+
+    if args.dedup_strings {
+        report_string_dedup(&result);
+    }
+
+    if args.check_callconv {
+        for warning in &calling_convention_warnings(&result) {
+            report_warning(warning);
+        }
+    }
+
+    if args.reproducible {
+        let issues = reproducibility_issues(&args.input, &options, &result);
+        for issue in &issues {
+            eprintln!("error: not reproducible: {issue}");
+        }
+        if !issues.is_empty() {
+            return Err(1);
+        }
     }
 
+    let artifacts = BuildArtifacts::new(&args.input, &result).map_err(|e| {
+        eprintln!("error: {e}");
+        1
+    })?;
     let output_path = args
         .output
+        .clone()
         .unwrap_or_else(|| default_output_path(&args.input));
-
-    if let Err(e) = fs::write(&output_path, &result.binary) {
-        eprintln!("error: failed to write output: {e}");
+    let mut writer = FileWriter::new(output_path.clone());
+    if let Some(map_path) = &args.map {
+        writer = writer.with_path(ArtifactKind::Map, map_path.clone());
+    }
+    if let Err(e) = artifacts.write(&build_artifact_kinds(args), &mut writer) {
+        eprintln!("error: {e}");
         return Err(1);
     }
 
     if args.verbose {
-        print_listing(&result);
+        eprint!("{}", artifacts.listing);
+    }
+
+    if args.stats {
+        eprintln!("{}", assembly_stats(&result));
     }
 
+    let entry = result
+        .entry
+        .map(|address| format!(", entry 0x{address:04X}"))
+        .unwrap_or_default();
+    let id = if args.reproducible {
+        format!(", build id {}", artifacts.bundle.build_id)
+    } else {
+        String::new()
+    };
     println!(
-        "Assembled {} ({} bytes) -> {}",
+        "Assembled {} ({} bytes{entry}{id}) -> {}",
         args.input.display(),
         result.binary.len(),
         output_path.display()
     );
+    for format in &args.emit {
+        let path = writer.path(ArtifactKind::Emit(*format));
+        println!("Emitted {} -> {}", format.name(), path.display());
+    }
 
     Ok(())
 }
 
+/// Artifacts `build` writes besides the binary, in the order it writes them.
+fn build_artifact_kinds(args: &BuildArgs) -> Vec<ArtifactKind> {
+    let mut kinds = vec![ArtifactKind::Binary];
+    if args.reproducible {
+        kinds.push(ArtifactKind::BuildId);
+    }
+    if args.map.is_some() {
+        kinds.push(ArtifactKind::Map);
+    }
+    kinds.extend(args.emit.iter().map(|format| ArtifactKind::Emit(*format)));
+    kinds
+}
+
 fn report_assemble_error(e: &AssembleError) {
     if let Some(loc) = &e.location {
         eprintln!("{}: error: {}", format_source_location(loc), e.kind);
@@ -200,6 +714,32 @@ fn report_assemble_error(e: &AssembleError) {
     }
 }
 
+fn report_warning(warning: &AssembleWarning) {
+    if let Some(loc) = &warning.location {
+        eprintln!("{}: warning: {warning}", format_source_location(loc));
+    } else {
+        eprintln!("warning: {warning}");
+    }
+}
+
+fn report_string_dedup(result: &AssembleResult) {
+    for shared in &result.deduplicated_strings {
+        eprintln!(
+            "{}: deduplicated: {shared}",
+            format_source_location(&shared.location)
+        );
+    }
+    let saved: usize = result
+        .deduplicated_strings
+        .iter()
+        .map(|shared| shared.dedup.bytes_saved)
+        .sum();
+    eprintln!(
+        "string dedup: {} run(s) shared, {saved} byte(s) saved",
+        result.deduplicated_strings.len()
+    );
+}
+
 fn format_source_location(loc: &assembler::assembler::SourceLocation) -> String {
     if loc.include_chain.is_empty() {
         format!("{}:{}", loc.file, loc.line)
@@ -208,41 +748,67 @@ fn format_source_location(loc: &assembler::assembler::SourceLocation) -> String
     }
 }
 
-fn print_listing(result: &AssembleResult) {
-    for entry in &result.listing {
-        let hex_bytes: String = entry
-            .bytes
-            .iter()
-            .map(|b| format!("{b:02X}"))
-            .collect::<Vec<_>>()
-            .join(" ");
-
+fn run_verify_build(args: &VerifyBuildArgs) -> Result<(), i32> {
+    let shipped = fs::read(&args.binary).map_err(|e| {
+        eprintln!("error: failed to read {}: {e}", args.binary.display());
+        1
+    })?;
+    let expected_id = args.build_id.clone().or_else(|| {
+        fs::read_to_string(ArtifactKind::BuildId.default_path(&args.binary))
+            .ok()
+            .map(|text| text.trim().to_string())
+    });
+    let result = assemble_with_options(&args.input, &args.options).map_err(|e| {
+        report_assemble_error(&e);
+        1
+    })?;
+
+    if let Some(divergence) = first_divergence(&shipped, &result.binary) {
+        let byte = |value: Option<u8>| {
+            value.map_or_else(|| "end of file".to_string(), |b| format!("0x{b:02X}"))
+        };
         eprintln!(
-            "{:04X}: {:<12} {} ; {}",
-            entry.address, hex_bytes, entry.source, entry.location
+            "error: {} differs from a rebuild of {} at 0x{:04X}: shipped {}, rebuilt {}",
+            args.binary.display(),
+            args.input.display(),
+            divergence.offset,
+            byte(divergence.shipped),
+            byte(divergence.rebuilt)
         );
-    }
-}
-
-fn run_test(args: &TestArgs) -> Result<(), i32> {
-    let result = match assemble(&args.input) {
-        Ok(r) => r,
-        Err(e) => {
-            report_assemble_error(&e);
-            return Err(1);
+        let source = result.listing.iter().find(|entry| {
+            let start = usize::from(entry.address);
+            (start..start + entry.bytes.len()).contains(&divergence.offset)
+        });
+        match source {
+            Some(entry) => eprintln!("  rebuilt from: {}", listing_line(entry)),
+            None => eprintln!("  no source line emits that address in the rebuild"),
         }
-    };
+        return Err(1);
+    }
 
-    if result.test_blocks.is_empty() {
-        println!("No test blocks found in {}", args.input.display());
-        return Ok(());
+    let id = build_id(&result.binary, result.entry);
+    if let Some(expected) = expected_id.filter(|expected| *expected != id) {
+        eprintln!(
+            "error: build id {expected} does not match the rebuild's {id}; the entry point differs"
+        );
+        return Err(1);
     }
 
+    println!(
+        "Verified {} against {} ({} bytes, build id {id})",
+        args.binary.display(),
+        args.input.display(),
+        shipped.len()
+    );
+    Ok(())
+}
+
+fn parse_test_blocks(result: &AssembleResult) -> Result<Vec<ParsedTestBlock>, i32> {
     let parsed_blocks: Vec<_> = result
         .test_blocks
         .iter()
         .filter_map(|tbc| {
-            parse_test_block(&tbc.block.content, tbc.block.start_line, tbc.block.end_line)
+            parse_source_test_block(&tbc.block)
                 .map_err(|e| {
                     eprintln!(
                         "error: failed to parse test block at {}: {}",
@@ -257,7 +823,84 @@ fn run_test(args: &TestArgs) -> Result<(), i32> {
         return Err(1);
     }
 
-    let test_result = run_tests(&result.binary, &parsed_blocks);
+    Ok(parsed_blocks)
+}
+
+fn run_test(args: &TestArgs) -> Result<(), i32> {
+    if args.watch {
+        watch_tests(args);
+    }
+    let outcomes = run_test_suite(args, &mut Vec::new())?;
+    if outcomes.iter().all(|outcome| outcome.passed) {
+        Ok(())
+    } else {
+        Err(1)
+    }
+}
+
+/// Re-runs the suite whenever a file it was built from changes, printing
+/// which blocks started or stopped passing since the previous run.
+fn watch_tests(args: &TestArgs) -> ! {
+    let color = watch::use_color();
+    let mut sources = vec![args.input.clone()];
+    let mut previous: Option<Vec<BlockOutcome>> = None;
+    loop {
+        if let Ok(current) = run_test_suite(args, &mut sources) {
+            if let Some(previous) = &previous {
+                let changes = watch::outcome_changes(previous, &current);
+                if !changes.is_empty() {
+                    println!("Changes since last run:");
+                    for change in changes {
+                        println!("  {}", change.render(color));
+                    }
+                }
+            }
+            previous = Some(current);
+        }
+
+        let stamps = SourceStamps::capture(&sources);
+        println!(
+            "\nWatching {} file(s) for changes (Ctrl-C to stop)",
+            sources.len()
+        );
+        let changed = stamps.wait_for_change();
+        let names: Vec<_> = changed
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        println!("\n--- {} changed, re-running ---\n", names.join(", "));
+    }
+}
+
+/// Assembles and runs every test block once, returning each block's outcome.
+/// `sources` is replaced with the files the program was built from whenever
+/// assembly succeeds.
+fn run_test_suite(args: &TestArgs, sources: &mut Vec<PathBuf>) -> Result<Vec<BlockOutcome>, i32> {
+    let result = match assemble(&args.input) {
+        Ok(r) => r,
+        Err(e) => {
+            report_assemble_error(&e);
+            return Err(1);
+        }
+    };
+    sources.clone_from(&result.sources);
+
+    if result.test_blocks.is_empty() {
+        println!("No test blocks found in {}", args.input.display());
+        return Ok(Vec::new());
+    }
+
+    let parsed_blocks = parse_test_blocks(&result)?;
+
+    let program = TestProgram {
+        fixture_dir: args.input.parent(),
+        capture_fixtures: args.capture_fixtures,
+        strict: args.strict,
+        tags: &args.tags,
+        skip_tags: &args.skip_tags,
+        ..TestProgram::of(&result)
+    };
+    let test_result = run_program_tests(&program, &parsed_blocks);
 
     for block_result in &test_result.block_results {
         println!("{block_result}");
@@ -268,46 +911,793 @@ fn run_test(args: &TestArgs) -> Result<(), i32> {
                     println!("  {ar}");
                 }
             }
+            if let Some(diff) = &block_result.screen_diff {
+                for line in diff.lines() {
+                    println!("    {line}");
+                }
+            }
+        }
+    }
+
+    for capture in &test_result.captures {
+        if let Err(e) = capture.write() {
+            eprintln!(
+                "error: cannot write fixture '{}': {e}",
+                capture.path.display()
+            );
+            return Err(1);
         }
+        println!("Captured fixture {}", capture.path.display());
     }
 
     let summary = test_result.summary();
     println!();
     println!("Test Summary: {summary} (total: {})", summary.total);
 
-    if test_result.all_passed() {
-        Ok(())
-    } else {
-        Err(1)
-    }
+    Ok(watch::block_outcomes(&test_result, parsed_blocks.len()))
 }
 
-fn main() {
-    let exit_code = match parse_args(env::args_os().skip(1)) {
-        Ok(ParseResult::Help) => {
-            println!("{USAGE_TEXT}");
-            0
-        }
-        Ok(ParseResult::Command(Command::Build(args))) => match run_build(args) {
-            Ok(()) => 0,
-            Err(code) => code,
-        },
-        Ok(ParseResult::Command(Command::Test(args))) => match run_test(&args) {
-            Ok(()) => 0,
-            Err(code) => code,
-        },
-        Err(error) => {
-            if error.starts_with("Usage:") {
-                println!("{error}");
-            } else {
-                eprintln!("error: {error}");
-                eprintln!("{USAGE_TEXT}");
-            }
-            1
+/// Decodes every primary word against the opcode table and reports any
+/// overlapping, unreachable or inconsistent encodings.
+fn run_verify_decoder_table() -> i32 {
+    let report = verify_table();
+    for (encoding, words) in &report.words_per_encoding {
+        println!("{:<10} {words:>5} words", format!("{encoding:?}"));
+    }
+    for issue in &report.issues {
+        eprintln!("error: {issue}");
+    }
+    println!(
+        "Decoder table: {} legal, {} illegal words, {} encodings: {}",
+        report.legal_words,
+        report.illegal_words,
+        report.words_per_encoding.len(),
+        if report.is_ok() {
+            "OK".to_string()
+        } else {
+            format!("{} issue(s)", report.issues.len())
         }
-    };
-
-    std::process::exit(exit_code);
+    );
+    i32::from(!report.is_ok())
+}
+
+fn run_verify_determinism(args: &VerifyArgs) -> Result<(), i32> {
+    let result = match assemble(&args.input) {
+        Ok(r) => r,
+        Err(e) => {
+            report_assemble_error(&e);
+            return Err(1);
+        }
+    };
+    let parsed_blocks = parse_test_blocks(&result)?;
+
+    let report = verify_determinism(&result, &parsed_blocks, args.runs);
+
+    for mismatch in &report.mismatches {
+        println!("{}: final state differs from run 1", mismatch.run);
+        if mismatch.results_differ {
+            println!("  test results differ");
+        }
+        for difference in &mismatch.differences {
+            println!("  {difference}");
+        }
+    }
+
+    println!(
+        "Determinism: {} fresh run(s) + snapshot restore before block {}: {}",
+        report.runs,
+        report.snapshot_block + 1,
+        if report.is_deterministic() {
+            "identical"
+        } else {
+            "DIVERGED"
+        }
+    );
+    println!("Final state digest: {:016x}", report.digest);
+
+    if report.is_deterministic() {
+        Ok(())
+    } else {
+        Err(1)
+    }
+}
+
+fn run_cross_check(args: &CrossCheckArgs) -> Result<(), i32> {
+    let result = assemble(&args.input).map_err(|e| {
+        report_assemble_error(&e);
+        1
+    })?;
+    let bundle = ProgramBundle::new(&args.input, &result).map_err(|e| {
+        eprintln!("error: {e}");
+        1
+    })?;
+    let events = match &args.events {
+        Some(path) => {
+            let script = fs::read_to_string(path).map_err(|e| {
+                eprintln!("error: failed to read {}: {e}", path.display());
+                1
+            })?;
+            parse_event_script(&script).map_err(|e| {
+                eprintln!("error: {}: {e}", path.display());
+                1
+            })?
+        }
+        None => Vec::new(),
+    };
+
+    let native = native_digests(&bundle, &events, args.ticks);
+    let wasm =
+        wasm_digests(&args.node, &args.wasm_pkg, &bundle, &events, args.ticks).map_err(|e| {
+            eprintln!("error: failed to run the WASM core: {e}");
+            1
+        })?;
+
+    let digest =
+        |digest: Option<u64>| digest.map_or_else(|| "none".to_string(), |d| format!("{d:016x}"));
+    if let Some(divergence) = first_tick_divergence(&native, &wasm) {
+        println!(
+            "Cross-check: DIVERGED after tick {}: native {}, wasm {}",
+            divergence.tick,
+            digest(divergence.native),
+            digest(divergence.wasm)
+        );
+        return Err(1);
+    }
+    println!(
+        "Cross-check: {} tick(s), {} event(s): native and wasm identical",
+        args.ticks,
+        events.len()
+    );
+    println!("Final state digest: {}", digest(native.last().copied()));
+    Ok(())
+}
+
+fn run_program(args: &RunArgs) -> Result<(), i32> {
+    let result = match assemble(&args.input) {
+        Ok(r) => r,
+        Err(e) => {
+            report_assemble_error(&e);
+            return Err(1);
+        }
+    };
+
+    let mut state = new_test_state(&TestProgram::of(&result));
+    if !args.params.is_empty() {
+        if let Err(e) = write_params(&mut state, &args.params.to_bytes()) {
+            eprintln!("error: {e}");
+            return Err(1);
+        }
+    }
+    let config = CoreConfig {
+        timing: args.timing,
+        open_bus: args.open_bus,
+        ..CoreConfig::default()
+    };
+    state.timing = args.timing;
+    state.open_bus = args.open_bus;
+    let mut mmio = run_mmio();
+    let started = Instant::now();
+    let mut run = TickBatch::default();
+    let mut console = ConsoleProgress::default();
+    let mut trace = args
+        .trace
+        .as_ref()
+        .map(|_| TraceWriter::with_timing(&args.timing));
+
+    if args.fast_forward > 0 {
+        // Output and an exit only surface once the whole skip has run.
+        let batch = run_fast_forward(&mut state, &mut mmio, &config, args.fast_forward);
+        record_batch(&mut run, batch, &mut mmio, &mut console);
+    }
+
+    let paced_from = Instant::now();
+    let mut done = 0u32;
+    while done < args.ticks && run.fault.is_none() && console.exit_status.is_none() {
+        let remaining = args.ticks - done;
+        let due = if args.realtime {
+            ticks_due(paced_from.elapsed())
+                .saturating_sub(done)
+                .min(remaining)
+        } else {
+            // One tick at a time, so console output streams and an exit
+            // stops the run at the end of the tick it happened in.
+            1
+        };
+        if due == 0 {
+            if let Some(wait) = (TICK_DURATION * done).checked_sub(paced_from.elapsed()) {
+                std::thread::sleep(wait);
+            }
+            continue;
+        }
+
+        let batch = match trace.as_mut() {
+            Some(writer) => run_ticks_with_trace(&mut state, &mut mmio, &config, due, writer),
+            None => run_ticks_with_budget(&mut state, &mut mmio, &config, due),
+        };
+        done += u32::try_from(batch.ticks.len()).unwrap_or(u32::MAX);
+        record_batch(&mut run, batch, &mut mmio, &mut console);
+    }
+    let done = run.ticks.len();
+
+    if console.mid_line {
+        println!();
+    }
+
+    // Written before reporting a fault, since a faulting run is the one
+    // most worth reading back.
+    if let (Some(path), Some(writer)) = (&args.trace, &trace) {
+        if let Err(e) = fs::write(path, writer.as_str()) {
+            eprintln!("error: failed to write trace: {e}");
+            return Err(1);
+        }
+    }
+
+    if let Some(dropped) = mmio.console().map(DebugConsole::dropped).filter(|&n| n > 0) {
+        eprintln!("warning: debug console output overflowed; {dropped} byte(s) dropped");
+    }
+    let peak = run.ticks.iter().map(|tick| tick.cycles).max().unwrap_or(0);
+    println!(
+        "Ran {done} tick(s): {} steps, {} cycles, peak {peak} cycles/tick",
+        run.total_steps, run.total_cycles
+    );
+    println!(
+        "Simulated {:.2}s in {:.2}s",
+        run.simulated_duration().as_secs_f64(),
+        started.elapsed().as_secs_f64()
+    );
+
+    if let Some(fault) = run.fault {
+        eprintln!(
+            "error: CPU faulted during tick {} at PC 0x{:04X}: {fault:?}",
+            done,
+            state.arch.pc()
+        );
+        return Err(1);
+    }
+    if let Some(status) = console.exit_status {
+        println!("Exited with status {status}");
+        if status != 0 {
+            return Err(i32::from(status));
+        }
+    }
+    Ok(())
+}
+
+/// The devices `run` attaches to the bus.
+fn run_mmio() -> CompositeMmio {
+    CompositeMmio::new()
+        .with_tele7(Tele7Peripheral::new(Tele7Config::default()))
+        .with_console(DebugConsole::new())
+        .with_dma(DmaController::new())
+        .with_paste(PasteBuffer::new())
+}
+
+fn run_devmap(args: &DevmapArgs) {
+    let mut devices = run_mmio().describe_registers();
+    devices.extend(Mpu::new().describe_registers());
+    devices.sort_by_key(|device| device.base);
+    print!("{}", devmap_text(&devices, args.registers));
+}
+
+fn devmap_text(devices: &[&DeviceRegisters], registers: bool) -> String {
+    let mut out = String::new();
+    for device in devices {
+        let _ = writeln!(
+            out,
+            "0x{:04X}-0x{:04X}  {:<8} {}",
+            device.base, device.end, device.device, device.description
+        );
+        if !registers {
+            continue;
+        }
+        for register in device.registers {
+            let fields: Vec<String> = register
+                .fields
+                .iter()
+                .rev()
+                .map(|field| match field.width {
+                    1 => format!("{}[{}]", field.name, field.lsb),
+                    width => format!("{}[{}:{}]", field.name, field.lsb + width - 1, field.lsb),
+                })
+                .collect();
+            let _ = writeln!(
+                out,
+                "  0x{:04X}  {:<14} {:<2}  {}{}",
+                device.address(register),
+                register.name,
+                register.access.as_str(),
+                register.description,
+                if fields.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", fields.join(" "))
+                }
+            );
+        }
+    }
+    out
+}
+
+#[cfg(feature = "script")]
+fn run_script_file(args: &ScriptArgs) -> Result<(), i32> {
+    let result = assemble(&args.program).map_err(|e| {
+        report_assemble_error(&e);
+        1
+    })?;
+    let script = fs::read_to_string(&args.script).map_err(|e| {
+        eprintln!("error: failed to read {}: {e}", args.script.display());
+        1
+    })?;
+    match run_script(&result, &script, |line| println!("{line}")) {
+        Ok(outcome) => {
+            println!(
+                "Script {} passed ({} assertion(s))",
+                args.script.display(),
+                outcome.assertions
+            );
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("error: {}: {e}", args.script.display());
+            Err(1)
+        }
+    }
+}
+
+fn run_bundle(args: &BundleArgs) -> Result<(), i32> {
+    let result = assemble(&args.input).map_err(|e| {
+        report_assemble_error(&e);
+        1
+    })?;
+    let artifacts = BuildArtifacts::new(&args.input, &result).map_err(|e| {
+        eprintln!("error: {e}");
+        1
+    })?;
+
+    let mut writer = FileWriter::new(default_output_path(&args.input));
+    if let Some(output) = &args.output {
+        writer = writer.with_path(ArtifactKind::Bundle, output.clone());
+    }
+    if let Err(e) = artifacts.write(&[ArtifactKind::Bundle], &mut writer) {
+        eprintln!("error: {e}");
+        return Err(1);
+    }
+    println!(
+        "Bundled {} ({} bytes, {} source lines) -> {}",
+        args.input.display(),
+        artifacts.binary().len(),
+        artifacts.bundle.source.lines().count(),
+        writer.path(ArtifactKind::Bundle).display()
+    );
+    Ok(())
+}
+
+fn run_serve(args: &ServeArgs) -> Result<(), i32> {
+    let server = ReloadServer::bind(args.port, &args.allow_origins).map_err(|e| {
+        eprintln!("error: failed to listen on port {}: {e}", args.port);
+        1
+    })?;
+    println!(
+        "Serving {} on ws://{} (Ctrl-C to stop)",
+        args.input.display(),
+        server.local_addr()
+    );
+    let mut sources = vec![args.input.clone()];
+    loop {
+        let message = serve_message(&args.input, &mut sources);
+        let clients = server.publish(&message);
+        println!("Pushed to {clients} client(s)");
+
+        let mut watched = sources.clone();
+        watched.extend(watch::files_under(&args.watch));
+        watched.sort();
+        watched.dedup();
+        let stamps = SourceStamps::capture(&watched);
+        let changed = stamps.wait_for_change();
+        let names: Vec<_> = changed
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        println!("\n--- {} changed, rebuilding ---\n", names.join(", "));
+    }
+}
+
+/// Assembles and bundles `input` for `serve`, returning the bundle JSON, or
+/// a build error message when assembly fails. `sources` is replaced with the
+/// files the program was built from whenever assembly succeeds.
+fn serve_message(input: &Path, sources: &mut Vec<PathBuf>) -> String {
+    let result = match assemble(input) {
+        Ok(result) => result,
+        Err(e) => {
+            report_assemble_error(&e);
+            return build_error_json(e.location.as_ref(), &e.kind.to_string());
+        }
+    };
+    sources.clone_from(&result.sources);
+    for warning in &result.warnings {
+        report_warning(warning);
+    }
+    match ProgramBundle::new(input, &result) {
+        Ok(bundle) => {
+            println!(
+                "Assembled {} ({} bytes, build id {})",
+                input.display(),
+                bundle.binary.len(),
+                bundle.build_id
+            );
+            bundle.to_json()
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            build_error_json(None, &e.to_string())
+        }
+    }
+}
+
+/// The message `serve` pushes in place of a bundle when a rebuild fails.
+fn build_error_json(
+    location: Option<&assembler::assembler::SourceLocation>,
+    message: &str,
+) -> String {
+    let (file, line) = location.map_or_else(
+        || ("null".to_string(), "null".to_string()),
+        |loc| {
+            (
+                format!("\"{}\"", cli::json_escape(&loc.file)),
+                loc.line.to_string(),
+            )
+        },
+    );
+    format!(
+        "{{\"format\": \"{BUILD_ERROR_FORMAT}\", \"version\": 1, \"file\": {file}, \"line\": {line}, \"message\": \"{}\"}}\n",
+        cli::json_escape(message)
+    )
+}
+
+fn run_listing_diff(args: &ListingDiffArgs) -> Result<(), i32> {
+    let read = |path: &Path| {
+        let text = fs::read_to_string(path).map_err(|e| {
+            eprintln!("error: failed to read {}: {e}", path.display());
+            1
+        })?;
+        let lines = parse_listing(&text);
+        if lines.is_empty() {
+            eprintln!("error: no listing entries in {}", path.display());
+            return Err(1);
+        }
+        Ok(lines)
+    };
+    let diff = diff_listings(&read(&args.old)?, &read(&args.new)?);
+    if args.json {
+        print!("{}", listing_diff_json(&diff));
+    } else {
+        print!("{}", listing_diff_text(&diff));
+    }
+    Ok(())
+}
+
+fn run_trace_dump(args: &TraceDumpArgs) -> Result<(), i32> {
+    let read = |path: &Path| {
+        fs::read_to_string(path).map_err(|e| {
+            eprintln!("error: failed to read {}: {e}", path.display());
+            1
+        })
+    };
+    let events = parse_trace(&read(&args.trace)?).map_err(|e| {
+        eprintln!("error: {}: {e}", args.trace.display());
+        1
+    })?;
+    let map = match &args.map {
+        Some(path) => Some(ProgramMap::parse(&read(path)?).map_err(|e| {
+            eprintln!("error: {}: {e}", path.display());
+            1
+        })?),
+        None => None,
+    };
+    print!("{}", dump_trace(&events, map.as_ref()));
+    Ok(())
+}
+
+fn run_corpus(args: &CorpusArgs) -> Result<(), i32> {
+    let corpus = Corpus::discover(&args.dir).map_err(|e| {
+        eprintln!("error: failed to read {}: {e}", args.dir.display());
+        1
+    })?;
+    if args.update {
+        let changed = corpus.update().map_err(|e| {
+            eprintln!("error: {e}");
+            1
+        })?;
+        for name in &changed {
+            println!("updated {name}");
+        }
+        println!(
+            "{} of {} corpus programs updated",
+            changed.len(),
+            corpus.entries().len()
+        );
+        return Ok(());
+    }
+
+    let checks = corpus.verify();
+    let failed: Vec<_> = checks.iter().filter(|check| !check.passed()).collect();
+    for check in &failed {
+        eprintln!("error: {}: {}", check.name, check.outcome);
+    }
+    if failed.is_empty() {
+        println!("{} corpus programs match", checks.len());
+        Ok(())
+    } else {
+        eprintln!(
+            "{} of {} corpus programs do not match; review the change and run `corpus update`",
+            failed.len(),
+            checks.len()
+        );
+        Err(1)
+    }
+}
+
+fn run_isa_suite(args: &IsaSuiteArgs) -> Result<(), i32> {
+    let files = generate_suite().map_err(|e| {
+        eprintln!("error: {e}");
+        1
+    })?;
+    fs::create_dir_all(&args.dir).map_err(|e| {
+        eprintln!("error: failed to create {}: {e}", args.dir.display());
+        1
+    })?;
+    for file in &files {
+        let path = args.dir.join(&file.name);
+        fs::write(&path, &file.contents).map_err(|e| {
+            eprintln!("error: failed to write {}: {e}", path.display());
+            1
+        })?;
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+fn listing_entry_text(line: &ListingLine) -> String {
+    format!("{:04X} {} ({})", line.address, line.source, line.location)
+}
+
+fn listing_diff_text(diff: &ListingDiff) -> String {
+    let mut out = String::new();
+    for change in &diff.changes {
+        let _ = match change {
+            ListingChange::Added(new) => writeln!(out, "added    {}", listing_entry_text(new)),
+            ListingChange::Removed(old) => writeln!(out, "removed  {}", listing_entry_text(old)),
+            ListingChange::Changed { old, new } => writeln!(
+                out,
+                "changed  {}\n      -> {}",
+                listing_entry_text(old),
+                listing_entry_text(new)
+            ),
+            ListingChange::Resized { old, new } => writeln!(
+                out,
+                "resized  {}\n      -> {} ({} -> {} bytes)",
+                listing_entry_text(old),
+                listing_entry_text(new),
+                old.bytes.len(),
+                new.bytes.len()
+            ),
+        };
+    }
+    for shift in &diff.shifts {
+        let _ = writeln!(
+            out,
+            "shifted  {:04X}-{:04X} by {:+} ({} entries)",
+            shift.old_start, shift.old_end, shift.delta, shift.entries
+        );
+    }
+    let _ = writeln!(
+        out,
+        "{} change(s), {} address shift(s), {} unchanged entries",
+        diff.changes.len(),
+        diff.shifts.len(),
+        diff.unchanged
+    );
+    out
+}
+
+fn listing_entry_json(line: &ListingLine) -> String {
+    let bytes: Vec<String> = line.bytes.iter().map(|b| format!("{b:02X}")).collect();
+    format!(
+        "{{\"address\": {}, \"bytes\": \"{}\", \"source\": \"{}\", \"location\": \"{}\"}}",
+        line.address,
+        bytes.join(" "),
+        cli::json_escape(&line.source),
+        cli::json_escape(&line.location)
+    )
+}
+
+fn listing_diff_json(diff: &ListingDiff) -> String {
+    let changes: Vec<String> = diff
+        .changes
+        .iter()
+        .map(|change| match change {
+            ListingChange::Added(new) => format!(
+                "{{\"kind\": \"added\", \"new\": {}}}",
+                listing_entry_json(new)
+            ),
+            ListingChange::Removed(old) => format!(
+                "{{\"kind\": \"removed\", \"old\": {}}}",
+                listing_entry_json(old)
+            ),
+            ListingChange::Changed { old, new } | ListingChange::Resized { old, new } => {
+                let kind = if matches!(change, ListingChange::Changed { .. }) {
+                    "changed"
+                } else {
+                    "resized"
+                };
+                format!(
+                    "{{\"kind\": \"{kind}\", \"old\": {}, \"new\": {}}}",
+                    listing_entry_json(old),
+                    listing_entry_json(new)
+                )
+            }
+        })
+        .collect();
+    let shifts: Vec<String> = diff
+        .shifts
+        .iter()
+        .map(|shift| {
+            format!(
+                "{{\"old_start\": {}, \"old_end\": {}, \"delta\": {}, \"entries\": {}}}",
+                shift.old_start, shift.old_end, shift.delta, shift.entries
+            )
+        })
+        .collect();
+    format!(
+        "{{\n  \"changes\": [{}],\n  \"shifts\": [{}],\n  \"unchanged\": {}\n}}\n",
+        json_list(&changes),
+        json_list(&shifts),
+        diff.unchanged
+    )
+}
+
+/// Debug console state carried across the batches of a `run`.
+#[derive(Debug, Default)]
+struct ConsoleProgress {
+    mid_line: bool,
+    exit_status: Option<u8>,
+}
+
+/// Adds `batch` to the run totals and streams the console output it
+/// produced.
+fn record_batch(
+    run: &mut TickBatch,
+    batch: TickBatch,
+    mmio: &mut CompositeMmio,
+    progress: &mut ConsoleProgress,
+) {
+    run.ticks.extend(batch.ticks);
+    run.total_steps += batch.total_steps;
+    run.total_cycles += batch.total_cycles;
+    run.fault = batch.fault;
+    if let Some(console) = mmio.console_mut() {
+        let output = console.drain();
+        flush_console(&output);
+        progress.mid_line = output
+            .last()
+            .map_or(progress.mid_line, |&byte| byte != b'\n');
+        progress.exit_status = console.exit_status();
+    }
+}
+
+/// Writes debug console output to stdout as the program produces it.
+fn flush_console(output: &[u8]) {
+    if output.is_empty() {
+        return;
+    }
+    let mut stdout = io::stdout().lock();
+    // A closed stdout should not abort the run.
+    let _ = stdout.write_all(output).and_then(|()| stdout.flush());
+}
+
+/// Ticks that should have started by `elapsed` into a real-time run.
+fn ticks_due(elapsed: Duration) -> u32 {
+    let due = elapsed.as_nanos() / TICK_DURATION.as_nanos() + 1;
+    u32::try_from(due).unwrap_or(u32::MAX)
+}
+
+fn main() {
+    let exit_code = match parse_args(env::args_os().skip(1)) {
+        Ok(ParseResult::Version { json: false }) => {
+            println!("{}", cli::version_text());
+            0
+        }
+        Ok(ParseResult::Version { json: true }) => {
+            print!("{}", cli::version_json());
+            0
+        }
+        Ok(ParseResult::ListStdlib) => {
+            print!("{}", format_module_listing());
+            0
+        }
+        Ok(ParseResult::ServicesInclude) => {
+            print!("{}", services_include());
+            0
+        }
+        Ok(ParseResult::VerifyDecoderTable) => run_verify_decoder_table(),
+        Ok(ParseResult::Command(Command::Build(args))) => match run_build(&args) {
+            Ok(()) => 0,
+            Err(code) => code,
+        },
+        Ok(ParseResult::Command(Command::Test(args))) => match run_test(&args) {
+            Ok(()) => 0,
+            Err(code) => code,
+        },
+        Ok(ParseResult::Command(Command::VerifyDeterminism(args))) => {
+            match run_verify_determinism(&args) {
+                Ok(()) => 0,
+                Err(code) => code,
+            }
+        }
+        Ok(ParseResult::Command(Command::CrossCheck(args))) => match run_cross_check(&args) {
+            Ok(()) => 0,
+            Err(code) => code,
+        },
+        Ok(ParseResult::Command(Command::Run(args))) => match run_program(&args) {
+            Ok(()) => 0,
+            Err(code) => code,
+        },
+        Ok(ParseResult::Command(Command::VerifyBuild(args))) => match run_verify_build(&args) {
+            Ok(()) => 0,
+            Err(code) => code,
+        },
+        #[cfg(feature = "script")]
+        Ok(ParseResult::Command(Command::Script(args))) => match run_script_file(&args) {
+            Ok(()) => 0,
+            Err(code) => code,
+        },
+        Ok(ParseResult::Command(Command::Bundle(args))) => match run_bundle(&args) {
+            Ok(()) => 0,
+            Err(code) => code,
+        },
+        Ok(ParseResult::Command(Command::Serve(args))) => match run_serve(&args) {
+            Ok(()) => 0,
+            Err(code) => code,
+        },
+        Ok(ParseResult::Command(Command::ListingDiff(args))) => match run_listing_diff(&args) {
+            Ok(()) => 0,
+            Err(code) => code,
+        },
+        Ok(ParseResult::Command(Command::TraceDump(args))) => match run_trace_dump(&args) {
+            Ok(()) => 0,
+            Err(code) => code,
+        },
+        Ok(ParseResult::Command(Command::Devmap(args))) => {
+            run_devmap(&args);
+            0
+        }
+        Ok(ParseResult::Command(Command::Corpus(args))) => match run_corpus(&args) {
+            Ok(()) => 0,
+            Err(code) => code,
+        },
+        Ok(ParseResult::Command(Command::IsaSuite(args))) => match run_isa_suite(&args) {
+            Ok(()) => 0,
+            Err(code) => code,
+        },
+        Ok(ParseResult::Command(Command::Completions(shell))) => {
+            print!("{}", cli::completion_script(shell));
+            0
+        }
+        Err(CliError::Help(text)) => {
+            print!("{text}");
+            0
+        }
+        Err(CliError::Invalid { message, command }) => {
+            eprintln!("error: {message}");
+            match command {
+                Some(name) => eprintln!("Run `{} {name} --help` for usage.", cli::BIN_NAME),
+                None => eprint!("\n{}", cli::usage()),
+            }
+            1
+        }
+    };
+
+    std::process::exit(exit_code);
 }
 
 #[cfg(test)]
@@ -335,10 +1725,40 @@ mod tests {
                 input: PathBuf::from("program.n1"),
                 output: Some(PathBuf::from("out.bin")),
                 verbose: true,
+                check_callconv: false,
+                optimize: false,
+                dedup_strings: false,
+                entry: None,
+                reproducible: false,
+                map: None,
+                emit: Vec::new(),
+                max_include_depth: DEFAULT_MAX_INCLUDE_DEPTH,
+                stats: false,
             }
         );
     }
 
+    #[test]
+    fn parse_build_emit_formats() {
+        let result = parse_build_args(
+            [
+                "src.n1",
+                "--emit",
+                "c-array,rust-include",
+                "--emit",
+                "c-array",
+            ]
+            .map(OsString::from)
+            .into_iter(),
+        )
+        .unwrap();
+        assert_eq!(result.emit, [EmitFormat::CArray, EmitFormat::RustInclude]);
+
+        let err = parse_build_args(["src.n1", "--emit", "hex"].map(OsString::from).into_iter())
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown format 'hex'"));
+    }
+
     #[test]
     fn parses_test_command() {
         let result = parse_test_args([OsString::from("program.n1.md")].into_iter())
@@ -348,22 +1768,118 @@ mod tests {
             result,
             TestArgs {
                 input: PathBuf::from("program.n1.md"),
+                capture_fixtures: false,
+                strict: false,
+                watch: false,
+                tags: Vec::new(),
+                skip_tags: Vec::new(),
             }
         );
     }
 
+    #[test]
+    fn parses_capture_fixture_flag() {
+        let result = parse_test_args(
+            [
+                OsString::from("--capture-fixture"),
+                OsString::from("program.n1.md"),
+            ]
+            .into_iter(),
+        )
+        .expect("capture flag should parse");
+
+        assert!(result.capture_fixtures);
+        assert!(!result.watch);
+        assert!(!result.strict);
+
+        let result = parse_test_args(
+            [
+                OsString::from("-w"),
+                OsString::from("--strict"),
+                OsString::from("t.n1"),
+            ]
+            .into_iter(),
+        )
+        .expect("watch and strict flags should parse");
+        assert!(result.watch);
+        assert!(result.strict);
+
+        let result = parse_test_args(
+            [
+                OsString::from("--tag"),
+                OsString::from("slow, io"),
+                OsString::from("--tag=nightly"),
+                OsString::from("--skip-tag"),
+                OsString::from("display"),
+                OsString::from("t.n1"),
+            ]
+            .into_iter(),
+        )
+        .expect("tag filters should parse");
+        assert_eq!(result.tags, ["slow", "io", "nightly"]);
+        assert_eq!(result.skip_tags, ["display"]);
+    }
+
     #[test]
     fn parses_help_flag() {
-        let result = parse_args([OsString::from("--help")].into_iter())
-            .expect("help should parse without error");
-        assert!(matches!(result, ParseResult::Help));
+        let result = parse_args([OsString::from("--help")].into_iter());
+        assert!(matches!(result, Err(CliError::Help(text)) if text.contains("Commands:")));
+
+        let result = parse_args([OsString::from("run"), OsString::from("-h")].into_iter());
+        assert!(matches!(result, Err(CliError::Help(text)) if text.contains("--ticks <n>")));
+    }
+
+    #[test]
+    fn parses_version_and_completions() {
+        let result = parse_args([OsString::from("-V")].into_iter()).unwrap();
+        assert!(matches!(result, ParseResult::Version { json: false }));
+        let result =
+            parse_args([OsString::from("--version"), OsString::from("--json")].into_iter())
+                .unwrap();
+        assert!(matches!(result, ParseResult::Version { json: true }));
+        assert!(
+            parse_args([OsString::from("--version"), OsString::from("--jsn")].into_iter()).is_err()
+        );
+
+        let result =
+            parse_args([OsString::from("completions"), OsString::from("zsh")].into_iter()).unwrap();
+        assert!(matches!(
+            result,
+            ParseResult::Command(Command::Completions(Shell::Zsh))
+        ));
+
+        let error = parse_args([OsString::from("completions"), OsString::from("csh")].into_iter())
+            .unwrap_err();
+        assert!(error.to_string().contains("unsupported shell: csh"));
+    }
+
+    #[test]
+    fn parses_list_stdlib_flag() {
+        let result = parse_args([OsString::from("--list-stdlib")].into_iter())
+            .expect("--list-stdlib should parse");
+        assert!(matches!(result, ParseResult::ListStdlib));
+    }
+
+    #[test]
+    fn parses_services_include_flag() {
+        let result = parse_args([OsString::from("--services-include")].into_iter())
+            .expect("--services-include should parse");
+        assert!(matches!(result, ParseResult::ServicesInclude));
+    }
+
+    #[test]
+    fn parses_hidden_verify_decoder_table_flag() {
+        let result = parse_args([OsString::from("--verify-decoder-table")].into_iter())
+            .expect("--verify-decoder-table should parse");
+        assert!(matches!(result, ParseResult::VerifyDecoderTable));
+        assert!(!cli::usage().contains("verify-decoder-table"));
     }
 
     #[test]
     fn rejects_unknown_command() {
         let error = parse_args([OsString::from("unknown")].into_iter())
             .expect_err("unknown command should fail parse");
-        assert!(error.contains("unknown command"));
+        assert!(error.to_string().contains("unknown command"));
     }
 
     #[test]
@@ -395,16 +1911,449 @@ mod tests {
         assert!(result.verbose);
     }
 
+    #[test]
+    fn parse_build_check_callconv_flag() {
+        let result = parse_build_args(
+            [OsString::from("src.n1"), OsString::from("--check-callconv")].into_iter(),
+        )
+        .expect("--check-callconv should parse");
+
+        assert!(result.check_callconv);
+    }
+
+    #[test]
+    fn parse_build_optimize_flag() {
+        let result =
+            parse_build_args([OsString::from("src.n1"), OsString::from("--optimize")].into_iter())
+                .expect("--optimize should parse");
+
+        assert!(result.optimize);
+    }
+
+    #[test]
+    fn parse_verify_determinism_args() {
+        let result = parse_verify_args(
+            [
+                OsString::from("prog.n1"),
+                OsString::from("--runs"),
+                OsString::from("5"),
+            ]
+            .into_iter(),
+        )
+        .expect("verify args should parse");
+        assert_eq!(
+            result,
+            VerifyArgs {
+                input: PathBuf::from("prog.n1"),
+                runs: 5,
+            }
+        );
+
+        let default = parse_verify_args([OsString::from("prog.n1")].into_iter()).unwrap();
+        assert_eq!(default.runs, DEFAULT_DETERMINISM_RUNS);
+        assert!(parse_verify_args(
+            [
+                OsString::from("prog.n1"),
+                OsString::from("--runs"),
+                OsString::from("0")
+            ]
+            .into_iter()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn parse_run_args_defaults_and_flags() {
+        let result = parse_run_args(
+            [
+                OsString::from("prog.n1"),
+                OsString::from("--ticks"),
+                OsString::from("250"),
+                OsString::from("--realtime"),
+            ]
+            .into_iter(),
+        )
+        .expect("run args should parse");
+        assert_eq!(
+            result,
+            RunArgs {
+                input: PathBuf::from("prog.n1"),
+                ticks: 250,
+                realtime: true,
+                fast_forward: 0,
+                params: ParamBlock::new(),
+                trace: None,
+                timing: TimingModel::V1,
+                open_bus: OpenBus::Zero,
+            }
+        );
+
+        let default = parse_run_args([OsString::from("prog.n1")].into_iter()).unwrap();
+        assert_eq!(default.ticks, DEFAULT_RUN_TICKS);
+        assert!(!default.realtime);
+        assert!(parse_run_args(
+            [
+                OsString::from("prog.n1"),
+                OsString::from("--ticks"),
+                OsString::from("0")
+            ]
+            .into_iter()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn parse_listing_diff_args_needs_two_paths() {
+        let result = parse_listing_diff_args(
+            ["old.lst", "new.lst", "--json"]
+                .map(OsString::from)
+                .into_iter(),
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            ListingDiffArgs {
+                old: PathBuf::from("old.lst"),
+                new: PathBuf::from("new.lst"),
+                json: true,
+            }
+        );
+
+        let err = parse_listing_diff_args([OsString::from("old.lst")].into_iter()).unwrap_err();
+        assert!(err.to_string().contains("expected two listing paths"));
+    }
+
+    #[test]
+    fn parse_corpus_args_accepts_verify_and_update() {
+        let parse = |action: &str| {
+            parse_corpus_args([OsString::from(action), OsString::from("corpus")].into_iter())
+        };
+        assert_eq!(
+            parse("verify").unwrap(),
+            CorpusArgs {
+                update: false,
+                dir: PathBuf::from("corpus"),
+            }
+        );
+        assert!(parse("update").unwrap().update);
+        let err = parse("bless").unwrap_err();
+        assert!(err.to_string().contains("unknown corpus action: bless"));
+    }
+
+    #[test]
+    fn parse_isa_suite_args_needs_one_directory() {
+        let result = parse_isa_suite_args([OsString::from("suite")].into_iter()).unwrap();
+        assert_eq!(
+            result,
+            IsaSuiteArgs {
+                dir: PathBuf::from("suite"),
+            }
+        );
+        assert!(parse_isa_suite_args(std::iter::empty()).is_err());
+    }
+
+    #[test]
+    fn parse_devmap_args_takes_no_paths() {
+        let result = parse_devmap_args([OsString::from("--registers")].into_iter()).unwrap();
+        assert_eq!(result, DevmapArgs { registers: true });
+
+        let err = parse_devmap_args([OsString::from("prog.n1")].into_iter()).unwrap_err();
+        assert!(err.to_string().contains("unexpected argument: prog.n1"));
+    }
+
+    #[test]
+    fn devmap_lists_registers_with_their_fields() {
+        let devices = run_mmio().describe_registers();
+        let summary = devmap_text(&devices, false);
+        assert!(summary.starts_with("0xE120-0xE12F  TELE7    TELE-7 textual display\n"));
+        assert_eq!(summary.lines().count(), 4);
+
+        let detail = devmap_text(&devices, true);
+        assert!(detail.contains("  0xE155  CTRL           RW  Control bits (IRQ[1] START[0])\n"));
+        assert!(detail.contains("(ID[7:0])"));
+    }
+
+    #[test]
+    fn parse_trace_args_accepts_only_dump() {
+        let result = parse_trace_args(
+            ["dump", "out.trace", "--map", "program.map"]
+                .map(OsString::from)
+                .into_iter(),
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            TraceDumpArgs {
+                trace: PathBuf::from("out.trace"),
+                map: Some(PathBuf::from("program.map")),
+            }
+        );
+
+        let err =
+            parse_trace_args(["show", "out.trace"].map(OsString::from).into_iter()).unwrap_err();
+        assert!(err.to_string().contains("unknown trace action: show"));
+        assert!(parse_trace_args([OsString::from("out.trace")].into_iter()).is_err());
+    }
+
+    #[test]
+    fn parse_verify_build_args_requires_binary() {
+        let result = parse_verify_build_args(
+            ["prog.n1", "-b", "prog.bin", "--optimize", "--entry", "main"]
+                .map(OsString::from)
+                .into_iter(),
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            VerifyBuildArgs {
+                input: PathBuf::from("prog.n1"),
+                binary: PathBuf::from("prog.bin"),
+                build_id: None,
+                options: AssembleOptions {
+                    optimize: true,
+                    dedup_strings: false,
+                    entry: Some("main".to_string()),
+                    max_include_depth: None,
+                },
+            }
+        );
+
+        let err = parse_verify_build_args([OsString::from("prog.n1")].into_iter()).unwrap_err();
+        assert!(err.to_string().contains("missing --binary"));
+    }
+
+    #[test]
+    fn parse_cross_check_args_requires_wasm_pkg() {
+        let result = parse_cross_check_args(
+            [
+                "prog.n1",
+                "--wasm-pkg",
+                "pkg",
+                "--events",
+                "in.events",
+                "--ticks",
+                "20",
+            ]
+            .map(OsString::from)
+            .into_iter(),
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            CrossCheckArgs {
+                input: PathBuf::from("prog.n1"),
+                wasm_pkg: PathBuf::from("pkg"),
+                events: Some(PathBuf::from("in.events")),
+                ticks: 20,
+                node: PathBuf::from("node"),
+            }
+        );
+
+        let err = parse_cross_check_args([OsString::from("prog.n1")].into_iter()).unwrap_err();
+        assert!(err.to_string().contains("missing --wasm-pkg"));
+    }
+
+    #[test]
+    #[cfg(feature = "script")]
+    fn parse_script_args_takes_program_and_script() {
+        let result =
+            parse_script_args(["prog.n1", "check.rhai"].map(OsString::from).into_iter()).unwrap();
+        assert_eq!(
+            result,
+            ScriptArgs {
+                program: PathBuf::from("prog.n1"),
+                script: PathBuf::from("check.rhai"),
+            }
+        );
+        assert!(parse_script_args([OsString::from("prog.n1")].into_iter()).is_err());
+    }
+
+    #[test]
+    fn parse_run_args_collects_params() {
+        let result = parse_run_args(
+            ["prog.n1", "--param", "lvl=3", "--param=name=ACE"]
+                .map(OsString::from)
+                .into_iter(),
+        )
+        .unwrap();
+        let mut expected = ParamBlock::new();
+        expected.push_word("lvl", 3).unwrap();
+        expected.push_bytes("name", b"ACE").unwrap();
+        assert_eq!(result.params, expected);
+
+        let err = parse_run_args(
+            ["prog.n1", "--param", "bad-key=1"]
+                .map(OsString::from)
+                .into_iter(),
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("--param: invalid parameter name 'bad-key'"));
+    }
+
+    #[test]
+    fn parse_run_args_selects_a_timing_model() {
+        let result = parse_run_args(
+            ["prog.n1", "--timing", "fast-io"]
+                .map(OsString::from)
+                .into_iter(),
+        )
+        .unwrap();
+        assert_eq!(result.timing, TimingModel::FastIo);
+
+        let err = parse_run_args(
+            ["prog.n1", "--timing", "turbo"]
+                .map(OsString::from)
+                .into_iter(),
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("--timing: unknown timing model 'turbo' (expected v1 or fast-io)"));
+    }
+
+    #[test]
+    fn parse_run_args_selects_an_open_bus_policy() {
+        let result = parse_run_args(
+            ["prog.n1", "--open-bus", "last"]
+                .map(OsString::from)
+                .into_iter(),
+        )
+        .unwrap();
+        assert_eq!(result.open_bus, OpenBus::LastValue);
+
+        let err = parse_run_args(
+            ["prog.n1", "--open-bus", "float"]
+                .map(OsString::from)
+                .into_iter(),
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("--open-bus: unknown open-bus policy 'float' (expected zero, ones, last)"));
+    }
+
+    #[test]
+    fn ticks_due_counts_the_tick_in_progress() {
+        assert_eq!(ticks_due(Duration::ZERO), 1);
+        assert_eq!(ticks_due(Duration::from_millis(9)), 1);
+        assert_eq!(ticks_due(Duration::from_millis(25)), 3);
+    }
+
+    #[test]
+    fn parse_build_max_include_depth() {
+        let result = parse_build_args(
+            ["src.n1", "--max-include-depth", "4"]
+                .map(OsString::from)
+                .into_iter(),
+        )
+        .unwrap();
+        assert_eq!(result.max_include_depth, 4);
+
+        let err = parse_build_args(
+            ["src.n1", "--max-include-depth", "0"]
+                .map(OsString::from)
+                .into_iter(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("invalid include depth: 0"));
+    }
+
+    #[test]
+    fn parse_build_dedup_strings_flag() {
+        let result = parse_build_args(
+            [OsString::from("src.n1"), OsString::from("--dedup-strings")].into_iter(),
+        )
+        .expect("--dedup-strings should parse");
+
+        assert!(result.dedup_strings);
+        assert!(!result.optimize);
+    }
+
+    #[test]
+    fn parse_build_stats_flag() {
+        let result =
+            parse_build_args([OsString::from("src.n1"), OsString::from("--stats")].into_iter())
+                .expect("--stats should parse");
+
+        assert!(result.stats);
+        assert!(!result.verbose);
+    }
+
+    #[test]
+    fn parse_serve_defaults_to_the_input_directory() {
+        let args = parse_serve_args(["games/pong.n1"].map(OsString::from).into_iter()).unwrap();
+        assert_eq!(
+            args,
+            ServeArgs {
+                input: PathBuf::from("games/pong.n1"),
+                watch: PathBuf::from("games"),
+                port: 8080,
+                allow_origins: Vec::new(),
+            }
+        );
+
+        let args = parse_serve_args(
+            ["pong.n1", "--watch", "src", "--port", "9000"]
+                .map(OsString::from)
+                .into_iter(),
+        )
+        .unwrap();
+        assert_eq!((args.watch, args.port), (PathBuf::from("src"), 9000));
+        let args = parse_serve_args(
+            [
+                "pong.n1",
+                "--allow-origin",
+                "http://localhost:3000",
+                "--allow-origin",
+                "https://example.test",
+            ]
+            .map(OsString::from)
+            .into_iter(),
+        )
+        .unwrap();
+        assert_eq!(
+            args.allow_origins,
+            ["http://localhost:3000", "https://example.test"]
+        );
+        assert!(parse_serve_args(
+            ["pong.n1", "--port", "70000"]
+                .map(OsString::from)
+                .into_iter()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn parse_build_entry_flag() {
+        let result = parse_build_args(
+            [
+                OsString::from("src.n1"),
+                OsString::from("--entry"),
+                OsString::from("main"),
+            ]
+            .into_iter(),
+        )
+        .expect("--entry should parse");
+        assert_eq!(result.entry.as_deref(), Some("main"));
+
+        assert!(parse_build_args(
+            [OsString::from("src.n1"), OsString::from("--entry")].into_iter()
+        )
+        .is_err());
+    }
+
     #[test]
     fn parse_build_missing_input() {
         let error = parse_build_args(std::iter::empty()).expect_err("missing input should fail");
-        assert!(error.contains("missing input"));
+        assert!(error.to_string().contains("missing input"));
     }
 
     #[test]
     fn parse_test_rejects_options() {
         let error = parse_test_args([OsString::from("--verbose")].into_iter())
             .expect_err("test should reject options");
-        assert!(error.contains("unknown option"));
+        assert!(error.to_string().contains("unknown option"));
     }
 }
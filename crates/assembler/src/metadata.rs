@@ -0,0 +1,284 @@
+//! Language metadata for editor completion.
+//!
+//! Everything here is derived from the mnemonic table and the parser's own
+//! operand and directive tables, so editors and the language server can offer
+//! completions without keeping a separate copy of the instruction set.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use crate::block_ops::BlockOpKind;
+use crate::mnemonic::{mnemonics, resolve_mnemonic_with_operand_form};
+use crate::parser::{operand_slots, register_names, DIRECTIVE_FORMS, OPERAND_FORMS};
+
+/// A mnemonic and the operand forms it accepts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MnemonicInfo {
+    /// Upper-case mnemonic, e.g. `ADD`.
+    pub name: String,
+    /// Complete usage forms, e.g. `ADD Rd, Ra, #imm`.
+    pub signatures: Vec<String>,
+    /// Whether this is an assembler pseudo-instruction.
+    pub pseudo: bool,
+}
+
+/// A directive and its argument form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectiveInfo {
+    /// Directive name including the leading dot, e.g. `.org`.
+    pub name: String,
+    /// Argument forms, e.g. `.org addr`.
+    pub signatures: Vec<String>,
+}
+
+/// Completion data for the assembly language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageMetadata {
+    /// Instructions and pseudo-instructions.
+    pub mnemonics: Vec<MnemonicInfo>,
+    /// Assembler directives.
+    pub directives: Vec<DirectiveInfo>,
+    /// Register names.
+    pub registers: Vec<String>,
+}
+
+/// Builds completion data from the assembler's tables.
+#[must_use]
+pub fn language_metadata() -> LanguageMetadata {
+    let mut mnemonic_infos: Vec<MnemonicInfo> = mnemonics()
+        .map(|(name, encoding)| {
+            let slots = if resolve_mnemonic_with_operand_form(name, true).is_some() {
+                operand_slots(encoding)
+            } else {
+                &[]
+            };
+            MnemonicInfo {
+                name: name.to_string(),
+                signatures: expand_signatures(name, slots),
+                pseudo: false,
+            }
+        })
+        .collect();
+    for kind in [BlockOpKind::Copy, BlockOpKind::Compare] {
+        mnemonic_infos.push(MnemonicInfo {
+            name: kind.mnemonic().to_string(),
+            signatures: vec![format!("{} Rd, Ra, Rb", kind.mnemonic())],
+            pseudo: true,
+        });
+    }
+
+    let mut directives: Vec<DirectiveInfo> = Vec::new();
+    for (name, form) in DIRECTIVE_FORMS {
+        let name = format!(".{name}");
+        let signature = if form.is_empty() {
+            name.clone()
+        } else {
+            format!("{name} {form}")
+        };
+        match directives.iter_mut().find(|info| info.name == name) {
+            Some(info) => info.signatures.push(signature),
+            None => directives.push(DirectiveInfo {
+                name,
+                signatures: vec![signature],
+            }),
+        }
+    }
+
+    LanguageMetadata {
+        mnemonics: mnemonic_infos,
+        directives,
+        registers: register_names(),
+    }
+}
+
+/// Most suggestions offered for one unknown name.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Mnemonics closest to the unknown `name`, as one-line signatures such as
+/// `ADD Rd, Ra, operand`. Empty when nothing is close enough to be a
+/// plausible typo.
+#[must_use]
+pub fn suggest_mnemonics(name: &str) -> Vec<String> {
+    let pseudo = [
+        BlockOpKind::Copy.mnemonic(),
+        BlockOpKind::Compare.mnemonic(),
+    ];
+    let candidates = mnemonics().map(|(name, _)| name).chain(pseudo);
+    closest(&name.to_ascii_uppercase(), candidates)
+        .into_iter()
+        .map(mnemonic_quick_reference)
+        .collect()
+}
+
+/// Directives closest to the unknown `name` (without its dot), as
+/// signatures such as `.word value`.
+#[must_use]
+pub fn suggest_directives(name: &str) -> Vec<String> {
+    let candidates = DIRECTIVE_FORMS.iter().map(|(name, _)| *name);
+    closest(&name.to_ascii_lowercase(), candidates)
+        .into_iter()
+        .filter_map(|name| {
+            DIRECTIVE_FORMS
+                .iter()
+                .find(|(candidate, _)| *candidate == name)
+                .map(|(name, form)| format!(".{name} {form}").trim_end().to_string())
+        })
+        .collect()
+}
+
+/// One-line signature of a known mnemonic, with `operand` standing for
+/// every operand form.
+fn mnemonic_quick_reference(name: &str) -> String {
+    if BlockOpKind::from_mnemonic(name).is_some() {
+        return format!("{name} Rd, Ra, Rb");
+    }
+    match resolve_mnemonic_with_operand_form(name, true) {
+        Some((_, _, encoding)) if !operand_slots(encoding).is_empty() => {
+            format!("{name} {}", operand_slots(encoding).join(", "))
+        }
+        _ => name.to_string(),
+    }
+}
+
+/// The candidates nearest to `input`, if any is close enough to be a typo:
+/// within one edit, or one per three characters for longer names.
+fn closest<'a>(input: &str, candidates: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let limit = (input.len() / 3).max(1);
+    let mut scored: Vec<(usize, &str)> = candidates
+        .map(|candidate| (edit_distance(input, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .collect();
+    let Some(best) = scored.iter().map(|(distance, _)| *distance).min() else {
+        return Vec::new();
+    };
+    scored.retain(|(distance, _)| *distance == best);
+    let mut names: Vec<&str> = scored.into_iter().map(|(_, name)| name).collect();
+    names.dedup();
+    names.truncate(MAX_SUGGESTIONS);
+    names
+}
+
+/// Edit distance over characters, counting a swap of two adjacent
+/// characters as one edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = Vec::new();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut next = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            next[j] = (row[j] + 1).min(next[j - 1] + 1).min(row[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                next[j] = next[j].min(previous[j - 2] + 1);
+            }
+        }
+        previous = core::mem::replace(&mut row, next);
+    }
+    row[b.len()]
+}
+
+/// Expands operand slots into one signature per operand form.
+fn expand_signatures(name: &str, slots: &[&str]) -> Vec<String> {
+    if slots.is_empty() {
+        return vec![name.to_string()];
+    }
+    let forms: &[&str] = if slots.contains(&"operand") {
+        OPERAND_FORMS
+    } else {
+        &[""]
+    };
+    forms
+        .iter()
+        .map(|form| {
+            let operands: Vec<&str> = slots
+                .iter()
+                .map(|slot| if *slot == "operand" { *form } else { *slot })
+                .collect();
+            format!("{name} {}", operands.join(", "))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_line;
+
+    /// Fills signature placeholders with concrete operands.
+    fn instantiate(signature: &str) -> String {
+        signature
+            .replace("Rd", "R1")
+            .replace("Ra", "R2")
+            .replace("Rb", "R3")
+            .replace("#imm", "#4")
+            .replace("#label", "#target")
+            .replace("=value", "=0x1234")
+            .replace("=label", "=target")
+            .replace("disp", "2")
+            .replace("special", "CAUSE")
+    }
+
+    #[test]
+    fn every_signature_parses() {
+        let metadata = language_metadata();
+        for info in &metadata.mnemonics {
+            for signature in &info.signatures {
+                let line = instantiate(signature);
+                assert!(
+                    parse_line(&line, 1).is_ok(),
+                    "signature `{signature}` does not parse as `{line}`"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lists_expected_entries() {
+        let metadata = language_metadata();
+        let add = metadata
+            .mnemonics
+            .iter()
+            .find(|info| info.name == "ADD")
+            .expect("ADD is listed");
+        assert!(add.signatures.contains(&"ADD Rd, Ra, #imm".to_string()));
+
+        let ret = metadata.mnemonics.iter().find(|info| info.name == "RET");
+        assert_eq!(
+            ret.map(|info| info.signatures.clone()),
+            Some(vec!["RET".to_string()])
+        );
+        assert!(metadata.mnemonics.iter().any(|info| info.pseudo));
+
+        let twchar = metadata
+            .directives
+            .iter()
+            .find(|info| info.name == ".twchar")
+            .expect(".twchar is listed");
+        assert_eq!(twchar.signatures.len(), 2);
+        assert_eq!(metadata.registers.len(), 8);
+    }
+
+    #[test]
+    fn suggests_near_misses_with_their_signatures() {
+        assert_eq!(suggest_mnemonics("addd"), ["ADD Rd, Ra, operand"]);
+        assert_eq!(suggest_mnemonics("MVO"), ["MOV Rd, operand"]);
+        assert_eq!(
+            suggest_mnemonics("SHX"),
+            ["SHL Rd, Ra, operand", "SHR Rd, Ra, operand"]
+        );
+        assert_eq!(suggest_mnemonics("MEMCOPY"), ["MEMCPY Rd, Ra, Rb"]);
+        assert_eq!(suggest_mnemonics("RETT"), ["RET"]);
+        assert_eq!(suggest_mnemonics("XYZZY"), Vec::<String>::new());
+
+        assert_eq!(suggest_directives("wrod"), [".word value"]);
+        assert_eq!(suggest_directives("ENTY"), [".entry label"]);
+        assert_eq!(suggest_directives("twchr"), [".twchar \"AB\""]);
+        assert_eq!(suggest_directives("frobnicate"), Vec::<String>::new());
+    }
+}
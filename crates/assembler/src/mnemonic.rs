@@ -1,6 +1,6 @@
 //! Mnemonic resolution derived from emulator opcode tables.
 
-use std::sync::OnceLock;
+use core::sync::atomic::{AtomicBool, Ordering};
 
 use emulator_core::{OpcodeEncoding, OPCODE_ENCODING_TABLE};
 
@@ -55,6 +55,12 @@ const MNEMONIC_ENTRIES: &[MnemonicEntry] = &[
         sub: 0x0,
         encoding: OpcodeEncoding::Mov,
     },
+    MnemonicEntry {
+        name: "MRS",
+        op: 0x1,
+        sub: 0x1,
+        encoding: OpcodeEncoding::Mrs,
+    },
     MnemonicEntry {
         name: "LOAD",
         op: 0x2,
@@ -274,8 +280,8 @@ const MNEMONIC_ENTRIES: &[MnemonicEntry] = &[
 ];
 
 fn entries_verified_against_core() -> &'static [MnemonicEntry] {
-    static VERIFIED_ENTRIES: OnceLock<Vec<MnemonicEntry>> = OnceLock::new();
-    VERIFIED_ENTRIES.get_or_init(|| {
+    static VERIFIED: AtomicBool = AtomicBool::new(false);
+    if !VERIFIED.load(Ordering::Relaxed) {
         for entry in MNEMONIC_ENTRIES {
             let matches_core = OPCODE_ENCODING_TABLE.iter().any(|(op, sub, encoding)| {
                 (*op == entry.op) && (*sub == entry.sub) && (*encoding == entry.encoding)
@@ -285,8 +291,9 @@ fn entries_verified_against_core() -> &'static [MnemonicEntry] {
                 "mnemonic table diverged from emulator-core table"
             );
         }
-        MNEMONIC_ENTRIES.to_vec()
-    })
+        VERIFIED.store(true, Ordering::Relaxed);
+    }
+    MNEMONIC_ENTRIES
 }
 
 /// Resolves a mnemonic string to its `(OP, SUB, OpcodeEncoding)` tuple.
@@ -300,6 +307,15 @@ pub fn resolve_mnemonic(name: &str) -> Option<MnemonicResolution> {
         .map(|entry| (entry.op, entry.sub, entry.encoding))
 }
 
+/// Returns every mnemonic with its opcode encoding, in table order.
+///
+/// `CALL` and `RET` share [`OpcodeEncoding::CallOrRet`].
+pub fn mnemonics() -> impl Iterator<Item = (&'static str, OpcodeEncoding)> {
+    entries_verified_against_core()
+        .iter()
+        .map(|entry| (entry.name, entry.encoding))
+}
+
 /// Resolves a mnemonic while disambiguating `CALL` and `RET` by operand presence.
 ///
 /// `CALL` requires an operand and `RET` requires no operand.
@@ -386,7 +402,7 @@ mod tests {
             .map(|(_, _, encoding)| *encoding)
             .collect();
 
-        assert_eq!(core_variants.len(), 41);
+        assert_eq!(core_variants.len(), 42);
         assert_eq!(encoded_variants.len(), core_variants.len());
         assert_eq!(encoded_variants, core_variants);
     }
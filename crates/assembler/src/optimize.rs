@@ -0,0 +1,438 @@
+//! Opt-in peephole optimizer.
+//!
+//! The optimizer runs between parsing and pass 1, rewriting the parsed line
+//! list in place. It never inserts or reorders lines: a removed line becomes
+//! [`ParsedLine::Blank`], so line indices still line up with the expanded
+//! source and every rewrite can be reported against its original location.
+//!
+//! The following peepholes are applied:
+//!
+//! - `MOV Rn, Rn` is removed.
+//! - `ADD Rn, Rn, #0` is removed.
+//! - Consecutive `.zero` directives (separated only by blank lines) are
+//!   merged into one.
+//! - A `JMP` or conditional branch to a label whose first instruction is an
+//!   unconditional `JMP #label` is retargeted to the final destination.
+//!
+//! Both removals change the flags register on real hardware, so they are only
+//! applied when the next instruction in program order overwrites all of
+//! N/Z/C/V without reading them first.
+//!
+//! Removing instructions moves every later label. Label references are
+//! re-resolved by pass 1, but hand-computed numeric branch offsets or
+//! addresses inside the optimized region are not adjusted.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use emulator_core::OpcodeEncoding;
+
+use crate::parser::{Directive, Immediate, Operand, ParsedInstruction, ParsedLine};
+
+/// A single rewrite performed by the optimizer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Optimization {
+    /// Index of the rewritten line in the parsed line list.
+    pub index: usize,
+    /// What was changed.
+    pub kind: OptimizationKind,
+}
+
+/// Classification of optimizer rewrites.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptimizationKind {
+    /// `MOV Rn, Rn` removed.
+    RedundantMove {
+        /// Register index.
+        register: u8,
+    },
+    /// `ADD Rn, Rn, #0` removed.
+    AddZero {
+        /// Register index.
+        register: u8,
+    },
+    /// A run of `.zero` directives was merged into this line.
+    MergedZero {
+        /// Number of directives in the run.
+        directives: usize,
+        /// Total byte count of the merged directive.
+        total: usize,
+    },
+    /// A jump or branch was retargeted past a chain of `JMP`s.
+    JumpThreaded {
+        /// Original target label.
+        from: String,
+        /// Final target label.
+        to: String,
+    },
+}
+
+impl core::fmt::Display for OptimizationKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::RedundantMove { register } => {
+                write!(f, "removed redundant MOV R{register}, R{register}")
+            }
+            Self::AddZero { register } => {
+                write!(f, "removed ADD R{register}, R{register}, #0")
+            }
+            Self::MergedZero { directives, total } => {
+                write!(f, "merged {directives} .zero directives into .zero {total}")
+            }
+            Self::JumpThreaded { from, to } => {
+                write!(f, "retargeted jump from #{from} to #{to}")
+            }
+        }
+    }
+}
+
+/// Applies all peepholes to `lines` and returns the rewrites performed.
+///
+/// Rewrites are reported in line order.
+#[must_use]
+pub fn optimize(lines: &mut [ParsedLine]) -> Vec<Optimization> {
+    let mut applied = Vec::new();
+
+    thread_jumps(lines, &mut applied);
+    remove_no_ops(lines, &mut applied);
+    merge_zero_runs(lines, &mut applied);
+
+    applied.sort_by_key(|o| o.index);
+    applied
+}
+
+fn remove_no_ops(lines: &mut [ParsedLine], applied: &mut Vec<Optimization>) {
+    // Walk backwards so a removable instruction can see past later ones that
+    // have already been removed.
+    for index in (0..lines.len()).rev() {
+        let ParsedLine::Instruction { instruction } = &lines[index] else {
+            continue;
+        };
+        let Some(kind) = no_op_kind(instruction) else {
+            continue;
+        };
+        if !flags_dead_after(lines, index) {
+            continue;
+        }
+        lines[index] = ParsedLine::Blank;
+        applied.push(Optimization { index, kind });
+    }
+}
+
+fn no_op_kind(instruction: &ParsedInstruction) -> Option<OptimizationKind> {
+    let rd = instruction.rd?;
+    match (instruction.resolution.2, &instruction.operand) {
+        (OpcodeEncoding::Mov, Some(Operand::Register(src))) if *src == rd => {
+            Some(OptimizationKind::RedundantMove { register: rd.0 })
+        }
+        (
+            OpcodeEncoding::Add,
+            Some(Operand::Immediate(Immediate {
+                value: 0,
+                is_label: false,
+                size_of: None,
+                ..
+            })),
+        ) if instruction.ra == Some(rd) => Some(OptimizationKind::AddZero { register: rd.0 }),
+        _ => None,
+    }
+}
+
+/// Returns true when the flags produced by `lines[index]` cannot be observed.
+fn flags_dead_after(lines: &[ParsedLine], index: usize) -> bool {
+    for line in &lines[index + 1..] {
+        match line {
+            ParsedLine::Blank | ParsedLine::Label { .. } => {}
+            ParsedLine::Instruction { instruction } => return overwrites_flags(instruction),
+            ParsedLine::Directive { .. } => return false,
+        }
+    }
+    false
+}
+
+/// Instructions that set all of N/Z/C/V from their result without reading them.
+const fn overwrites_flags(instruction: &ParsedInstruction) -> bool {
+    match instruction.resolution.2 {
+        OpcodeEncoding::Add
+        | OpcodeEncoding::Sub
+        | OpcodeEncoding::And
+        | OpcodeEncoding::Or
+        | OpcodeEncoding::Xor
+        | OpcodeEncoding::Shl
+        | OpcodeEncoding::Shr
+        | OpcodeEncoding::Cmp => true,
+        OpcodeEncoding::Mov => matches!(
+            instruction.operand,
+            Some(Operand::Register(_) | Operand::Immediate(_))
+        ),
+        _ => false,
+    }
+}
+
+fn merge_zero_runs(lines: &mut [ParsedLine], applied: &mut Vec<Optimization>) {
+    let mut index = 0;
+    while index < lines.len() {
+        let ParsedLine::Directive {
+            directive: Directive::Zero(first),
+        } = lines[index]
+        else {
+            index += 1;
+            continue;
+        };
+
+        let mut total = first;
+        let mut members = vec![index];
+        let mut cursor = index + 1;
+        while cursor < lines.len() {
+            match lines[cursor] {
+                ParsedLine::Blank => {}
+                ParsedLine::Directive {
+                    directive: Directive::Zero(count),
+                } => {
+                    total += count;
+                    members.push(cursor);
+                }
+                _ => break,
+            }
+            cursor += 1;
+        }
+
+        if members.len() > 1 {
+            lines[index] = ParsedLine::Directive {
+                directive: Directive::Zero(total),
+            };
+            for &member in &members[1..] {
+                lines[member] = ParsedLine::Blank;
+            }
+            applied.push(Optimization {
+                index,
+                kind: OptimizationKind::MergedZero {
+                    directives: members.len(),
+                    total,
+                },
+            });
+        }
+        index = cursor;
+    }
+}
+
+fn thread_jumps(lines: &mut [ParsedLine], applied: &mut Vec<Optimization>) {
+    let forwards = jump_forwards(lines);
+    if forwards.is_empty() {
+        return;
+    }
+
+    for (index, line) in lines.iter_mut().enumerate() {
+        let ParsedLine::Instruction { instruction } = line else {
+            continue;
+        };
+        if !is_jump_or_branch(instruction.resolution.2) {
+            continue;
+        }
+        let Some(Operand::Immediate(imm)) = &mut instruction.operand else {
+            continue;
+        };
+        let Some(from) = imm.label_name.clone().filter(|_| imm.is_label) else {
+            continue;
+        };
+
+        let mut target = from.clone();
+        let mut seen = BTreeSet::from([target.clone()]);
+        while let Some(next) = forwards.get(&target) {
+            if !seen.insert(next.clone()) {
+                // A cycle of jumps never reaches a real destination; leave it.
+                target.clone_from(&from);
+                break;
+            }
+            target.clone_from(next);
+        }
+
+        if target != from {
+            imm.label_name = Some(target.clone());
+            applied.push(Optimization {
+                index,
+                kind: OptimizationKind::JumpThreaded { from, to: target },
+            });
+        }
+    }
+}
+
+/// Maps each label whose first instruction is `JMP #other` to `other`.
+fn jump_forwards(lines: &[ParsedLine]) -> BTreeMap<String, String> {
+    let mut forwards = BTreeMap::new();
+    let mut pending = Vec::new();
+
+    for line in lines {
+        match line {
+            ParsedLine::Blank => {}
+            ParsedLine::Label { name } => pending.push(name.clone()),
+            ParsedLine::Instruction { instruction } => {
+                if let Some(target) = jmp_label(instruction) {
+                    for name in core::mem::take(&mut pending) {
+                        if name != target {
+                            forwards.insert(name, target.to_string());
+                        }
+                    }
+                }
+                pending.clear();
+            }
+            ParsedLine::Directive { .. } => pending.clear(),
+        }
+    }
+
+    forwards
+}
+
+fn jmp_label(instruction: &ParsedInstruction) -> Option<&str> {
+    if instruction.resolution.2 != OpcodeEncoding::Jmp {
+        return None;
+    }
+    match &instruction.operand {
+        Some(Operand::Immediate(Immediate {
+            is_label: true,
+            label_name: Some(name),
+            ..
+        })) => Some(name),
+        _ => None,
+    }
+}
+
+const fn is_jump_or_branch(encoding: OpcodeEncoding) -> bool {
+    matches!(
+        encoding,
+        OpcodeEncoding::Jmp
+            | OpcodeEncoding::Beq
+            | OpcodeEncoding::Bne
+            | OpcodeEncoding::Blt
+            | OpcodeEncoding::Ble
+            | OpcodeEncoding::Bgt
+            | OpcodeEncoding::Bge
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_line;
+
+    fn parse(source: &str) -> Vec<ParsedLine> {
+        source
+            .lines()
+            .enumerate()
+            .map(|(i, line)| parse_line(line, i + 1).expect("test source should parse"))
+            .collect()
+    }
+
+    #[test]
+    fn removes_redundant_move_when_flags_are_overwritten() {
+        let mut lines = parse("MOV R1, R1\nADD R2, R2, #1\nHALT");
+        let applied = optimize(&mut lines);
+
+        assert_eq!(lines[0], ParsedLine::Blank);
+        assert_eq!(
+            applied,
+            vec![Optimization {
+                index: 0,
+                kind: OptimizationKind::RedundantMove { register: 1 },
+            }]
+        );
+    }
+
+    #[test]
+    fn keeps_no_op_when_flags_may_be_read() {
+        let mut lines = parse("ADD R3, R3, #0\nBEQ #done\ndone:\nHALT");
+        let applied = optimize(&mut lines);
+
+        assert!(applied.is_empty());
+        assert!(matches!(lines[0], ParsedLine::Instruction { .. }));
+    }
+
+    #[test]
+    fn removes_chain_of_no_ops() {
+        let mut lines = parse("ADD R3, R3, #0\nMOV R2, R2\nSUB R4, R4, #1\nHALT");
+        let applied = optimize(&mut lines);
+
+        assert_eq!(applied.len(), 2);
+        assert_eq!(lines[0], ParsedLine::Blank);
+        assert_eq!(lines[1], ParsedLine::Blank);
+    }
+
+    #[test]
+    fn keeps_add_with_different_registers() {
+        let mut lines = parse("ADD R3, R2, #0\nSUB R4, R4, #1");
+        assert!(optimize(&mut lines).is_empty());
+    }
+
+    #[test]
+    fn merges_zero_runs() {
+        let mut lines = parse(".zero 4\n\n.zero 2\n.zero 1\nlabel:\n.zero 8");
+        let applied = optimize(&mut lines);
+
+        assert_eq!(
+            lines[0],
+            ParsedLine::Directive {
+                directive: Directive::Zero(7),
+            }
+        );
+        assert_eq!(lines[2], ParsedLine::Blank);
+        assert_eq!(lines[3], ParsedLine::Blank);
+        assert_eq!(
+            lines[5],
+            ParsedLine::Directive {
+                directive: Directive::Zero(8),
+            }
+        );
+        assert_eq!(
+            applied,
+            vec![Optimization {
+                index: 0,
+                kind: OptimizationKind::MergedZero {
+                    directives: 3,
+                    total: 7,
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn threads_jump_chain_to_final_target() {
+        let mut lines = parse("BEQ #a\nHALT\na:\nJMP #b\nb:\nJMP #c\nc:\nHALT");
+        let applied = optimize(&mut lines);
+
+        let ParsedLine::Instruction { instruction } = &lines[0] else {
+            panic!("expected instruction");
+        };
+        let Some(Operand::Immediate(imm)) = &instruction.operand else {
+            panic!("expected label operand");
+        };
+        assert_eq!(imm.label_name.as_deref(), Some("c"));
+        assert!(applied.contains(&Optimization {
+            index: 0,
+            kind: OptimizationKind::JumpThreaded {
+                from: "a".into(),
+                to: "c".into(),
+            },
+        }));
+        assert!(applied.contains(&Optimization {
+            index: 3,
+            kind: OptimizationKind::JumpThreaded {
+                from: "b".into(),
+                to: "c".into(),
+            },
+        }));
+    }
+
+    #[test]
+    fn leaves_jump_cycles_alone() {
+        let mut lines = parse("JMP #a\na:\nJMP #b\nb:\nJMP #a");
+        let before = lines.clone();
+        let applied = optimize(&mut lines);
+
+        assert!(applied.is_empty());
+        assert_eq!(lines, before);
+    }
+}
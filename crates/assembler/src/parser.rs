@@ -4,8 +4,17 @@
 //! into structured `ParsedLine` items ready for symbol table construction and
 //! encoding.
 
-use emulator_core::OpcodeEncoding;
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
 
+use emulator_core::{is_reserved_primary_opcode, OpcodeEncoding, SpecialRegisterSelect};
+
+use crate::block_ops::{BlockOp, BlockOpKind, BLOCK_OP_SCRATCH};
+use crate::info::InfoField;
+use crate::metadata::{suggest_directives, suggest_mnemonics};
 use crate::mnemonic::{resolve_mnemonic_with_operand_form, MnemonicResolution};
 
 /// A parsed register operand (R0-R7).
@@ -31,6 +40,54 @@ pub struct Immediate {
     pub is_label: bool,
     /// The label name if this is a label reference.
     pub label_name: Option<String>,
+    /// The region whose size is the value, for `sizeof(start, end)` and
+    /// `lengthof(start, end)` (resolved in pass 2).
+    pub size_of: Option<SizeOf>,
+}
+
+/// Distance between two labels, written `sizeof(start, end)` for bytes or
+/// `lengthof(start, end)` for 16-bit words.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeOf {
+    /// Label at the first byte of the region.
+    pub start: String,
+    /// Label just past the last byte of the region.
+    pub end: String,
+    /// Whether the size counts bytes or words.
+    pub unit: SizeUnit,
+}
+
+/// Unit a [`SizeOf`] counts in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeUnit {
+    /// `sizeof`: bytes.
+    Bytes,
+    /// `lengthof`: 16-bit words.
+    Words,
+}
+
+impl SizeOf {
+    /// Size of the region given the addresses of its labels, or `None` if
+    /// `end` comes before `start` or a `lengthof` region is not a whole
+    /// number of words.
+    #[must_use]
+    pub fn resolve(&self, start: u16, end: u16) -> Option<u16> {
+        let bytes = end.checked_sub(start)?;
+        match self.unit {
+            SizeUnit::Bytes => Some(bytes),
+            SizeUnit::Words => (bytes % 2 == 0).then_some(bytes / 2),
+        }
+    }
+}
+
+impl core::fmt::Display for SizeOf {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let name = match self.unit {
+            SizeUnit::Bytes => "sizeof",
+            SizeUnit::Words => "lengthof",
+        };
+        write!(f, "{name}({}, {})", self.start, self.end)
+    }
 }
 
 /// A memory operand with optional displacement.
@@ -85,6 +142,8 @@ pub enum Directive {
     /// `.org addr` - set output position.
     Org(u32),
     /// `.word val` - emit 16-bit value (big-endian).
+    ///
+    /// `.bcd val` also parses to this, holding `val` as packed BCD.
     Word(u16),
     /// `.byte val` - emit 8-bit value.
     Byte(u8),
@@ -98,6 +157,58 @@ pub enum Directive {
     TwChar(TwCharOperands),
     /// `.tstring "text"` or `.tstring "text", min_chars` - pack string for TELE-7.
     TString(TStringOperands),
+    /// `.incbin "path"[, offset[, length]]` - embed raw bytes from a file.
+    IncBin(IncBinOperands),
+    /// `.entry label` - start execution at `label` instead of 0x0000.
+    Entry(String),
+    /// `.equ NAME, value` - define a named constant usable wherever a
+    /// label is.
+    Equ(String, u16),
+    /// `.layout standard` - keep output out of the vector table unless an
+    /// `.org` places it there, and out of the MMIO, DIAG and reserved
+    /// regions.
+    StandardLayout,
+    /// `.title`, `.author` or `.version "text"` - program metadata.
+    Info(InfoField, String),
+    /// `.pseudo_op NAME, op[, sub]` - declare a mnemonic for a reserved
+    /// primary opcode.
+    PseudoOp(PseudoOpDef),
+    /// A `MEMCPY` or `MEMCMP` use.
+    ///
+    /// Replaced by its loop before pass 1 (see [`crate::block_ops`]).
+    BlockOp(BlockOp),
+    /// A 16-bit word resolved in pass 2 (big-endian):
+    /// `.word sizeof(start, end)`.
+    LiteralWord(Immediate),
+}
+
+/// A mnemonic declared by `.pseudo_op` for a reserved primary opcode, so
+/// programs can use instructions a host implements through the core's
+/// experimental opcode handlers.
+///
+/// A use `NAME [Rd[, Ra[, #n]]]` is encoded as one word in the base
+/// instruction layout: `OP` and `SUB` from the declaration, then `Rd`, `Ra`
+/// and `n` (0-7, in the addressing-mode bits), each 0 when omitted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PseudoOpDef {
+    /// Declared mnemonic, matched case-insensitively.
+    pub name: String,
+    /// Reserved primary opcode (`0xB`-`0xF`).
+    pub op: u8,
+    /// Value of the `SUB` field (0-7).
+    pub sub: u8,
+}
+
+impl PseudoOpDef {
+    /// Encodes a use of this mnemonic with the given operand fields.
+    #[must_use]
+    pub const fn encode(&self, rd: u8, ra: u8, n: u8) -> u16 {
+        ((self.op as u16) << 12)
+            | (((rd & 0x7) as u16) << 9)
+            | (((ra & 0x7) as u16) << 6)
+            | (((self.sub & 0x7) as u16) << 3)
+            | (n & 0x7) as u16
+    }
 }
 
 /// Operands for `.twchar` directive.
@@ -161,6 +272,23 @@ pub struct TStringOperands {
     pub min_chars: Option<usize>,
 }
 
+/// Operands for `.incbin` directive.
+///
+/// The parser only records the request; `data` stays empty until the
+/// directive is resolved against the containing file by
+/// [`crate::include::resolve_incbin`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncBinOperands {
+    /// Path of the binary file, relative to the containing source file.
+    pub path: String,
+    /// Byte offset into the file where copying starts.
+    pub offset: usize,
+    /// Number of bytes to copy (`None` copies to end of file).
+    pub length: Option<usize>,
+    /// Resolved file contents for the selected range.
+    pub data: Vec<u8>,
+}
+
 /// A single parsed source line.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ParsedLine {
@@ -208,6 +336,9 @@ pub enum ParseErrorKind {
     UnknownMnemonic(String),
     /// Invalid register name (not R0-R7).
     InvalidRegister(String),
+    /// Special register (`PC`, `SP`, `FLAGS`, ...) used where only `R0-R7`
+    /// are encodable.
+    SpecialRegister(String),
     /// Duplicate label definition.
     DuplicateLabel(String),
     /// Malformed immediate value.
@@ -228,21 +359,37 @@ pub enum ParseErrorKind {
     MissingOperand,
 }
 
-impl std::fmt::Display for ParseError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", self.kind)
     }
 }
 
-impl std::fmt::Display for ParseErrorKind {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
-            Self::UnknownMnemonic(m) => write!(f, "unknown mnemonic: {m}"),
-            Self::InvalidRegister(r) => write!(f, "invalid register: {r}"),
+            Self::UnknownMnemonic(m) => {
+                write!(f, "unknown mnemonic: {m}")?;
+                write_suggestions(f, &suggest_mnemonics(m))
+            }
+            Self::InvalidRegister(r) => {
+                write!(f, "invalid register: {r} (general registers are R0-R7)")
+            }
+            Self::SpecialRegister(r) => {
+                let upper = r.to_ascii_uppercase();
+                write!(
+                    f,
+                    "{upper} is a special register, not a general register (R0-R7)"
+                )?;
+                special_register_hint(&upper).map_or(Ok(()), |hint| write!(f, "; {hint}"))
+            }
             Self::DuplicateLabel(l) => write!(f, "duplicate label: {l}"),
             Self::InvalidImmediate(v) => write!(f, "invalid immediate value: {v}"),
             Self::InvalidDisplacement(d) => write!(f, "displacement out of range: {d}"),
-            Self::InvalidDirective(d) => write!(f, "unknown directive: {d}"),
+            Self::InvalidDirective(d) => {
+                write!(f, "unknown directive: {d}")?;
+                write_suggestions(f, &suggest_directives(d))
+            }
             Self::InvalidDirectiveValue(v) => write!(f, "invalid directive value: {v}"),
             Self::InvalidSyntax(s) => write!(f, "invalid syntax: {s}"),
             Self::UnterminatedString => write!(f, "unterminated string literal"),
@@ -252,7 +399,27 @@ impl std::fmt::Display for ParseErrorKind {
     }
 }
 
-impl std::error::Error for ParseError {}
+/// Appends `; did you mean `A`, `B` or `C`?` for suggested signatures.
+fn write_suggestions(
+    f: &mut core::fmt::Formatter<'_>,
+    suggestions: &[String],
+) -> core::fmt::Result {
+    let Some((last, rest)) = suggestions.split_last() else {
+        return Ok(());
+    };
+    write!(f, "; did you mean ")?;
+    for (index, suggestion) in rest.iter().enumerate() {
+        let separator = if index + 1 == rest.len() {
+            " or "
+        } else {
+            ", "
+        };
+        write!(f, "`{suggestion}`{separator}")?;
+    }
+    write!(f, "`{last}`?")
+}
+
+impl core::error::Error for ParseError {}
 
 /// Result of parsing a single line.
 pub type ParseResult = Result<ParsedLine, ParseError>;
@@ -320,6 +487,143 @@ fn parse_directive_or_instruction(text: &str, line_number: usize) -> ParseResult
     }
 }
 
+/// Parses a line like [`parse_line`], also accepting mnemonics declared by
+/// earlier `.pseudo_op` lines.
+///
+/// A `.pseudo_op` line is added to `pseudo_ops`; a use of a declared
+/// mnemonic is returned as the `.word` it encodes to.
+///
+/// # Errors
+///
+/// Returns the errors of [`parse_line`], plus
+/// [`ParseErrorKind::InvalidDirectiveValue`] when a mnemonic is declared
+/// twice and [`ParseErrorKind::InvalidSyntax`] for malformed operands.
+pub fn parse_line_with_pseudo_ops(
+    line: &str,
+    line_number: usize,
+    pseudo_ops: &mut Vec<PseudoOpDef>,
+) -> ParseResult {
+    let parsed = parse_line(line, line_number);
+    match parsed {
+        Ok(ParsedLine::Directive {
+            directive: Directive::PseudoOp(ref def),
+        }) => {
+            if pseudo_ops
+                .iter()
+                .any(|existing| existing.name.eq_ignore_ascii_case(&def.name))
+            {
+                return Err(ParseError {
+                    location: SourceLocation {
+                        line: line_number,
+                        column: 1,
+                    },
+                    kind: ParseErrorKind::InvalidDirectiveValue(format!(
+                        "pseudo-op {} is already declared",
+                        def.name
+                    )),
+                });
+            }
+            pseudo_ops.push(def.clone());
+            parsed
+        }
+        Err(ParseError {
+            kind: ParseErrorKind::UnknownMnemonic(ref name),
+            ..
+        }) => {
+            let Some(def) = pseudo_ops
+                .iter()
+                .find(|def| def.name.eq_ignore_ascii_case(name))
+            else {
+                return parsed;
+            };
+            let stripped = strip_comment(line).trim();
+            let text = split_label(stripped).map_or(stripped, |(_, rest)| rest.trim());
+            parse_pseudo_op_use(text, def, line_number)
+        }
+        _ => parsed,
+    }
+}
+
+fn parse_pseudo_op_def(args: &str, line_number: usize) -> Result<PseudoOpDef, ParseError> {
+    let error = |message: String| ParseError {
+        location: SourceLocation {
+            line: line_number,
+            column: 1,
+        },
+        kind: ParseErrorKind::InvalidDirectiveValue(message),
+    };
+
+    let parts: Vec<&str> = args.split(',').map(str::trim).collect();
+    let (name, op, sub) = match parts.as_slice() {
+        [name, op] => (*name, *op, None),
+        [name, op, sub] => (*name, *op, Some(*sub)),
+        _ => return Err(error(format!("expected `NAME, op[, sub]`, got `{args}`"))),
+    };
+    if !is_valid_label(name) {
+        return Err(error(format!("invalid pseudo-op name `{name}`")));
+    }
+    if BlockOpKind::from_mnemonic(name).is_some()
+        || resolve_mnemonic_with_operand_form(name, false).is_some()
+        || resolve_mnemonic_with_operand_form(name, true).is_some()
+    {
+        return Err(error(format!("{name} is already an instruction")));
+    }
+    let op = parse_u8_value(op, line_number)?;
+    if !is_reserved_primary_opcode(op) {
+        return Err(error(format!(
+            "primary opcode 0x{op:X} is not reserved (use 0xB-0xF)"
+        )));
+    }
+    let sub = sub.map_or(Ok(0), |sub| parse_u8_value(sub, line_number))?;
+    if sub > 7 {
+        return Err(error(format!("sub-opcode {sub} out of range (0-7)")));
+    }
+
+    Ok(PseudoOpDef {
+        name: name.to_string(),
+        op,
+        sub,
+    })
+}
+
+fn parse_pseudo_op_use(text: &str, def: &PseudoOpDef, line_number: usize) -> ParseResult {
+    let tokens = tokenize(text);
+    let operands = &tokens[1..];
+    let error = |message: String| ParseError {
+        location: SourceLocation {
+            line: line_number,
+            column: 1,
+        },
+        kind: ParseErrorKind::InvalidSyntax(message),
+    };
+
+    if operands.len() > 3 {
+        return Err(error(format!("{} takes at most `Rd, Ra, #n`", def.name)));
+    }
+    let rd = operands
+        .first()
+        .map_or(Ok(0), |rd| parse_register(rd, line_number).map(|reg| reg.0))?;
+    let ra = operands
+        .get(1)
+        .map_or(Ok(0), |ra| parse_register(ra, line_number).map(|reg| reg.0))?;
+    let n = match operands.get(2) {
+        None => 0,
+        Some(token) => {
+            let value = token
+                .strip_prefix('#')
+                .ok_or_else(|| error(format!("expected `#n`, got {token}")))?;
+            match parse_numeric_value(value, line_number)? {
+                n @ 0..=7 => u8::try_from(n).unwrap_or_default(),
+                _ => return Err(error(format!("{token} out of range (#0-#7)"))),
+            }
+        }
+    };
+
+    Ok(ParsedLine::Directive {
+        directive: Directive::Word(def.encode(rd, ra, n)),
+    })
+}
+
 fn parse_directive(text: &str, line_number: usize) -> ParseResult {
     let without_dot = &text[1..];
     let (name, args) = split_directive(without_dot);
@@ -329,8 +633,17 @@ fn parse_directive(text: &str, line_number: usize) -> ParseResult {
             let addr = parse_u32_value(args, line_number)?;
             Directive::Org(addr)
         }
-        "word" => {
-            let val = parse_u16_value(args, line_number)?;
+        "word" => match parse_size_of(args, line_number)? {
+            Some(size_of) => Directive::LiteralWord(Immediate {
+                value: 0,
+                is_label: false,
+                label_name: None,
+                size_of: Some(size_of),
+            }),
+            None => Directive::Word(parse_u16_value(args, line_number)?),
+        },
+        "bcd" => {
+            let val = parse_bcd_value(args, line_number)?;
             Directive::Word(val)
         }
         "byte" => {
@@ -357,6 +670,37 @@ fn parse_directive(text: &str, line_number: usize) -> ParseResult {
             let operands = parse_tstring_operands(args, line_number)?;
             Directive::TString(operands)
         }
+        "incbin" => {
+            let operands = parse_incbin_operands(args, line_number)?;
+            Directive::IncBin(operands)
+        }
+        "title" => Directive::Info(InfoField::Title, parse_string_literal(args, line_number)?),
+        "author" => Directive::Info(InfoField::Author, parse_string_literal(args, line_number)?),
+        "version" => Directive::Info(InfoField::Version, parse_string_literal(args, line_number)?),
+        "pseudo_op" => Directive::PseudoOp(parse_pseudo_op_def(args, line_number)?),
+        "equ" => parse_equ(args, line_number)?,
+        "layout" if args.eq_ignore_ascii_case("standard") => Directive::StandardLayout,
+        "layout" => {
+            return Err(ParseError {
+                location: SourceLocation {
+                    line: line_number,
+                    column: 1,
+                },
+                kind: ParseErrorKind::InvalidDirectiveValue(format!(
+                    "unknown layout `{args}`; expected `standard`"
+                )),
+            });
+        }
+        "entry" if is_valid_label(args) => Directive::Entry(args.to_string()),
+        "entry" => {
+            return Err(ParseError {
+                location: SourceLocation {
+                    line: line_number,
+                    column: 1,
+                },
+                kind: ParseErrorKind::InvalidDirectiveValue(args.to_string()),
+            });
+        }
         _ => {
             return Err(ParseError {
                 location: SourceLocation {
@@ -371,6 +715,51 @@ fn parse_directive(text: &str, line_number: usize) -> ParseResult {
     Ok(ParsedLine::Directive { directive })
 }
 
+/// Directive names and argument forms accepted by `parse_directive`.
+pub(crate) const DIRECTIVE_FORMS: &[(&str, &str)] = &[
+    ("org", "addr"),
+    ("word", "value"),
+    ("word", "sizeof(start, end)"),
+    ("word", "lengthof(start, end)"),
+    ("bcd", "value"),
+    ("byte", "value"),
+    ("ascii", "\"text\""),
+    ("zero", "count"),
+    ("include", "\"path\""),
+    ("twchar", "\"AB\""),
+    ("twchar", "high, low"),
+    ("tstring", "\"text\"[, min_chars]"),
+    ("incbin", "\"path\"[, offset[, length]]"),
+    ("entry", "label"),
+    ("title", "\"text\""),
+    ("author", "\"text\""),
+    ("version", "\"text\""),
+    ("pseudo_op", "NAME, op[, sub]"),
+    ("equ", "NAME, value"),
+    ("layout", "standard"),
+];
+
+fn parse_equ(args: &str, line_number: usize) -> Result<Directive, ParseError> {
+    let invalid = || ParseError {
+        location: SourceLocation {
+            line: line_number,
+            column: 1,
+        },
+        kind: ParseErrorKind::InvalidDirectiveValue(format!(
+            "expected `NAME, value`, got `{args}`"
+        )),
+    };
+    let (name, value) = args.split_once(',').ok_or_else(invalid)?;
+    let name = name.trim();
+    if !is_valid_label(name) {
+        return Err(invalid());
+    }
+    Ok(Directive::Equ(
+        name.to_string(),
+        parse_u16_value(value.trim(), line_number)?,
+    ))
+}
+
 fn split_directive(text: &str) -> (&str, &str) {
     text.find(|c: char| c.is_whitespace())
         .map_or((text, ""), |pos| (&text[..pos], text[pos..].trim()))
@@ -394,6 +783,25 @@ fn parse_u16_value(s: &str, line: usize) -> Result<u16, ParseError> {
     })
 }
 
+/// Parses a `.bcd` value (0-9999) into its packed-BCD word, one decimal
+/// digit per nibble, so `.bcd 1234` emits `0x1234`.
+fn parse_bcd_value(s: &str, line: usize) -> Result<u16, ParseError> {
+    let value = parse_numeric_value(s, line)?;
+    if !(0..=9999).contains(&value) {
+        return Err(ParseError {
+            location: SourceLocation { line, column: 1 },
+            kind: ParseErrorKind::InvalidDirectiveValue(s.to_string()),
+        });
+    }
+    let mut packed = 0;
+    let mut rest = value;
+    for shift in [0, 4, 8, 12] {
+        packed |= u16::try_from(rest % 10).unwrap_or_default() << shift;
+        rest /= 10;
+    }
+    Ok(packed)
+}
+
 fn parse_u8_value(s: &str, line: usize) -> Result<u8, ParseError> {
     parse_numeric_value(s, line).and_then(|v| {
         u8::try_from(v).map_err(|_| ParseError {
@@ -543,6 +951,41 @@ fn parse_tstring_operands(s: &str, line: usize) -> Result<TStringOperands, Parse
     })
 }
 
+fn parse_incbin_operands(s: &str, line: usize) -> Result<IncBinOperands, ParseError> {
+    let path = parse_string_literal(s, line)?;
+    let trimmed = s.trim();
+    let rest = trimmed[path.len() + 2..].trim();
+
+    let mut numbers = Vec::new();
+    if !rest.is_empty() {
+        let Some(list) = rest.strip_prefix(',') else {
+            return Err(ParseError {
+                location: SourceLocation { line, column: 1 },
+                kind: ParseErrorKind::InvalidDirectiveValue(rest.to_string()),
+            });
+        };
+        for part in list.split(',') {
+            numbers.push(parse_usize_value(part.trim(), line)?);
+        }
+    }
+
+    if numbers.len() > 2 {
+        return Err(ParseError {
+            location: SourceLocation { line, column: 1 },
+            kind: ParseErrorKind::InvalidDirectiveValue(
+                "incbin takes at most an offset and a length".into(),
+            ),
+        });
+    }
+
+    Ok(IncBinOperands {
+        path,
+        offset: numbers.first().copied().unwrap_or(0),
+        length: numbers.get(1).copied(),
+        data: Vec::new(),
+    })
+}
+
 fn parse_instruction(text: &str, line_number: usize) -> ParseResult {
     let tokens = tokenize(text);
     if tokens.is_empty() {
@@ -558,6 +1001,10 @@ fn parse_instruction(text: &str, line_number: usize) -> ParseResult {
     let mnemonic = &tokens[0];
     let operand_tokens = &tokens[1..];
 
+    if let Some(kind) = BlockOpKind::from_mnemonic(mnemonic) {
+        return parse_block_op(kind, operand_tokens, line_number);
+    }
+
     let has_operand = !operand_tokens.is_empty();
     let resolution =
         resolve_mnemonic_with_operand_form(mnemonic, has_operand).ok_or_else(|| ParseError {
@@ -584,10 +1031,58 @@ fn parse_instruction(text: &str, line_number: usize) -> ParseResult {
     })
 }
 
+/// Parses `MEMCPY dst, src, count` or `MEMCMP a, b, count`.
+fn parse_block_op(kind: BlockOpKind, operands: &[String], line_number: usize) -> ParseResult {
+    let error = |message: String| ParseError {
+        location: SourceLocation {
+            line: line_number,
+            column: 1,
+        },
+        kind: ParseErrorKind::InvalidSyntax(message),
+    };
+
+    let mnemonic = kind.mnemonic();
+    let [first, second, count] = operands else {
+        return Err(error(format!(
+            "{mnemonic} expects three registers: `{mnemonic} Ra, Rb, Rcount`"
+        )));
+    };
+    let registers = [
+        parse_register(first, line_number)?,
+        parse_register(second, line_number)?,
+        parse_register(count, line_number)?,
+    ];
+    if let Some(scratch) = registers
+        .iter()
+        .find(|register| BLOCK_OP_SCRATCH.contains(&register.0))
+    {
+        return Err(error(format!(
+            "{mnemonic} uses R6 and R7 as scratch; R{} cannot be an operand",
+            scratch.0
+        )));
+    }
+    if registers[0] == registers[1] || registers[0] == registers[2] || registers[1] == registers[2]
+    {
+        return Err(error(format!(
+            "{mnemonic} operands must be distinct registers"
+        )));
+    }
+
+    Ok(ParsedLine::Directive {
+        directive: Directive::BlockOp(BlockOp {
+            kind,
+            first: registers[0],
+            second: registers[1],
+            count: registers[2],
+        }),
+    })
+}
+
 fn tokenize(text: &str) -> Vec<String> {
     let mut tokens = Vec::new();
     let mut current = String::new();
     let mut in_bracket = false;
+    let mut in_parens = false;
     let mut in_string = false;
 
     for ch in text.chars() {
@@ -604,7 +1099,15 @@ fn tokenize(text: &str) -> Vec<String> {
                 in_bracket = false;
                 current.push(ch);
             }
-            ',' | ' ' | '\t' if !in_bracket && !in_string => {
+            '(' if !in_string => {
+                in_parens = true;
+                current.push(ch);
+            }
+            ')' if !in_string => {
+                in_parens = false;
+                current.push(ch);
+            }
+            ',' | ' ' | '\t' if !in_bracket && !in_parens && !in_string => {
                 if !current.is_empty() {
                     tokens.push(current.clone());
                     current.clear();
@@ -625,6 +1128,63 @@ fn tokenize(text: &str) -> Vec<String> {
 
 type OperandResult = Result<(Option<Register>, Option<Register>, Option<Operand>), ParseError>;
 
+/// Operand slots accepted by `parse_operands` for each encoding.
+///
+/// `operand` stands for any of [`OPERAND_FORMS`] and `special` for a
+/// special register readable by `MRS`; the other slots are registers.
+pub(crate) const fn operand_slots(encoding: OpcodeEncoding) -> &'static [&'static str] {
+    match encoding {
+        OpcodeEncoding::Nop
+        | OpcodeEncoding::Sync
+        | OpcodeEncoding::Halt
+        | OpcodeEncoding::Trap
+        | OpcodeEncoding::Swi
+        | OpcodeEncoding::Ewait
+        | OpcodeEncoding::Eret => &[],
+        OpcodeEncoding::Push | OpcodeEncoding::Pop | OpcodeEncoding::Eget => &["Rd"],
+        OpcodeEncoding::Jmp
+        | OpcodeEncoding::Beq
+        | OpcodeEncoding::Bne
+        | OpcodeEncoding::Blt
+        | OpcodeEncoding::Ble
+        | OpcodeEncoding::Bgt
+        | OpcodeEncoding::Bge
+        | OpcodeEncoding::CallOrRet => &["operand"],
+        OpcodeEncoding::Mov | OpcodeEncoding::Load | OpcodeEncoding::Store => &["Rd", "operand"],
+        OpcodeEncoding::Mrs => &["Rd", "special"],
+        OpcodeEncoding::In => &["Rd", "Ra"],
+        OpcodeEncoding::Out => &["Ra", "Rd"],
+        OpcodeEncoding::Bset | OpcodeEncoding::Bclr | OpcodeEncoding::Btest => &["Ra", "operand"],
+        OpcodeEncoding::Add
+        | OpcodeEncoding::Sub
+        | OpcodeEncoding::And
+        | OpcodeEncoding::Or
+        | OpcodeEncoding::Xor
+        | OpcodeEncoding::Shl
+        | OpcodeEncoding::Shr
+        | OpcodeEncoding::Cmp
+        | OpcodeEncoding::Mul
+        | OpcodeEncoding::Mulh
+        | OpcodeEncoding::Div
+        | OpcodeEncoding::Mod
+        | OpcodeEncoding::Qadd
+        | OpcodeEncoding::Qsub
+        | OpcodeEncoding::Scv => &["Rd", "Ra", "operand"],
+    }
+}
+
+/// Syntactic forms accepted by `parse_operand`.
+pub(crate) const OPERAND_FORMS: &[&str] =
+    &["Rb", "#imm", "#label", "[Rb]", "[Rb+disp]", "[Rb-disp]"];
+
+/// Names of the registers accepted by `parse_register`.
+pub(crate) fn register_names() -> Vec<String> {
+    (0u8..)
+        .map_while(Register::new)
+        .map(|register| format!("R{}", register.0))
+        .collect()
+}
+
 #[allow(clippy::too_many_lines)]
 fn parse_operands(
     tokens: &[String],
@@ -683,6 +1243,21 @@ fn parse_operands(
             };
             Ok((Some(rd), None, operand))
         }
+        OpcodeEncoding::Mrs => {
+            let rd = parse_register(tokens[0].as_str(), line_number)?;
+            let source = tokens.get(1).ok_or(ParseError {
+                location: SourceLocation {
+                    line: line_number,
+                    column: 1,
+                },
+                kind: ParseErrorKind::MissingOperand,
+            })?;
+            Ok((
+                Some(rd),
+                Some(parse_special_register(source, line_number)?),
+                None,
+            ))
+        }
         OpcodeEncoding::In => {
             let rd = parse_register(tokens[0].as_str(), line_number)?;
             let ra = if tokens.len() > 1 {
@@ -711,6 +1286,13 @@ fn parse_operands(
             }
         }
         OpcodeEncoding::Ewait | OpcodeEncoding::Eret => Ok((None, None, None)),
+        // `CMP Ra, #imm` is shorthand for `CMP Ra, Ra, #imm`; CMP has no
+        // destination, so RD only mirrors RA.
+        OpcodeEncoding::Cmp if tokens.len() == 2 && tokens[1].starts_with('#') => {
+            let ra = parse_register(tokens[0].as_str(), line_number)?;
+            let operand = parse_operand(&tokens[1], line_number)?;
+            Ok((Some(ra), Some(ra), Some(operand)))
+        }
         OpcodeEncoding::Add
         | OpcodeEncoding::Sub
         | OpcodeEncoding::And
@@ -742,8 +1324,51 @@ fn parse_operands(
     }
 }
 
+/// Special registers, which no operand field can encode.
+const SPECIAL_REGISTERS: [&str; 7] = ["PC", "SP", "FLAGS", "TICK", "CAP", "CAUSE", "EVP"];
+
+/// Explains how a program reaches a special register without naming it as
+/// an operand.
+fn special_register_hint(name: &str) -> Option<&'static str> {
+    match name {
+        "PC" => Some("change it with JMP, CALL, RET or a branch"),
+        "SP" => Some("it changes only through PUSH, POP, CALL and RET; read it with MRS Rd, SP"),
+        "FLAGS" => Some("it is set by ALU results; read it with MRS Rd, FLAGS"),
+        "TICK" => Some("read it with MRS Rd, TICK"),
+        "CAP" => Some("read it with MRS Rd, CAP"),
+        "CAUSE" => Some("read it with MRS Rd, CAUSE"),
+        "EVP" => Some("read it with MRS Rd, EVP"),
+        _ => None,
+    }
+}
+
+/// Parses an `MRS` source into the register whose index is its `RA`
+/// selector.
+fn parse_special_register(s: &str, line_number: usize) -> Result<Register, ParseError> {
+    SpecialRegisterSelect::from_name(s)
+        .and_then(|select| Register::new(select as u8))
+        .ok_or_else(|| ParseError {
+            location: SourceLocation {
+                line: line_number,
+                column: 1,
+            },
+            kind: ParseErrorKind::InvalidSyntax(format!(
+                "MRS reads FLAGS, TICK, CAP, CAUSE, EVP or SP, not {s}"
+            )),
+        })
+}
+
 fn parse_register(s: &str, line_number: usize) -> Result<Register, ParseError> {
     let upper = s.to_ascii_uppercase();
+    if SPECIAL_REGISTERS.contains(&upper.as_str()) {
+        return Err(ParseError {
+            location: SourceLocation {
+                line: line_number,
+                column: 1,
+            },
+            kind: ParseErrorKind::SpecialRegister(s.to_string()),
+        });
+    }
     if let Some(num_str) = upper.strip_prefix('R') {
         if let Ok(num) = num_str.parse::<u8>() {
             return Register::new(num).ok_or_else(|| ParseError {
@@ -830,19 +1455,69 @@ fn parse_displacement(s: &str, line_number: usize) -> Result<i16, ParseError> {
 }
 
 fn parse_immediate(s: &str, line_number: usize) -> Result<Operand, ParseError> {
+    parse_immediate_value(s, line_number).map(Operand::Immediate)
+}
+
+fn parse_immediate_value(s: &str, line_number: usize) -> Result<Immediate, ParseError> {
+    if let Some(size_of) = parse_size_of(s, line_number)? {
+        return Ok(Immediate {
+            value: 0,
+            is_label: false,
+            label_name: None,
+            size_of: Some(size_of),
+        });
+    }
     if is_valid_label(s) {
-        return Ok(Operand::Immediate(Immediate {
+        return Ok(Immediate {
             value: 0,
             is_label: true,
             label_name: Some(s.to_string()),
-        }));
+            size_of: None,
+        });
     }
 
     let val = parse_numeric_value(s, line_number)?;
-    Ok(Operand::Immediate(Immediate {
+    Ok(Immediate {
         value: val,
         is_label: false,
         label_name: None,
+        size_of: None,
+    })
+}
+
+/// Parses `sizeof(start, end)` or `lengthof(start, end)`. Returns `None`
+/// for anything else, so callers fall back to plain values.
+fn parse_size_of(s: &str, line_number: usize) -> Result<Option<SizeOf>, ParseError> {
+    let s = s.trim();
+    let Some((name, rest)) = s.split_once('(') else {
+        return Ok(None);
+    };
+    let unit = match name.trim().to_ascii_lowercase().as_str() {
+        "sizeof" => SizeUnit::Bytes,
+        "lengthof" => SizeUnit::Words,
+        _ => return Ok(None),
+    };
+    let labels = rest
+        .strip_suffix(')')
+        .and_then(|args| args.split_once(','))
+        .map(|(start, end)| (start.trim(), end.trim()))
+        .filter(|(start, end)| is_valid_label(start) && is_valid_label(end));
+    let Some((start, end)) = labels else {
+        return Err(ParseError {
+            location: SourceLocation {
+                line: line_number,
+                column: 1,
+            },
+            kind: ParseErrorKind::InvalidSyntax(format!(
+                "expected `{}(start_label, end_label)`, got `{s}`",
+                name.trim()
+            )),
+        });
+    };
+    Ok(Some(SizeOf {
+        start: start.to_string(),
+        end: end.to_string(),
+        unit,
     }))
 }
 
@@ -1178,6 +1853,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_directive_bcd_packs_decimal_digits() {
+        for (source, expected) in [
+            (".bcd 1234", 0x1234),
+            (".bcd 9999", 0x9999),
+            (".bcd 7", 0x0007),
+            (".bcd 0", 0x0000),
+        ] {
+            match parse_line(source, 1) {
+                Ok(ParsedLine::Directive { directive }) => {
+                    assert_eq!(directive, Directive::Word(expected), "{source}");
+                }
+                _ => panic!("expected directive for {source}"),
+            }
+        }
+        assert!(parse_line(".bcd 10000", 1).is_err());
+        assert!(parse_line(".bcd -1", 1).is_err());
+    }
+
     #[test]
     fn parse_directive_byte() {
         let result = parse_line(".byte 255", 1);
@@ -1222,6 +1916,109 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_info_directives() {
+        assert_eq!(
+            parse_line(".title \"Snake, the game\" ; shown in the UI", 1),
+            Ok(ParsedLine::Directive {
+                directive: Directive::Info(InfoField::Title, "Snake, the game".into()),
+            })
+        );
+        assert_eq!(
+            parse_line(".VERSION \"1.2\"", 1),
+            Ok(ParsedLine::Directive {
+                directive: Directive::Info(InfoField::Version, "1.2".into()),
+            })
+        );
+        assert!(parse_line(".author Ada", 1).is_err());
+    }
+
+    #[test]
+    fn parse_directive_entry() {
+        assert_eq!(
+            parse_line(".entry main", 1),
+            Ok(ParsedLine::Directive {
+                directive: Directive::Entry("main".into()),
+            })
+        );
+        assert!(parse_line(".entry", 1).is_err());
+        assert!(parse_line(".entry 0x100", 1).is_err());
+    }
+
+    #[test]
+    fn parse_directive_equ() {
+        assert_eq!(
+            parse_line(".equ SVC_PUTC, 0x01 ; write a byte", 1),
+            Ok(ParsedLine::Directive {
+                directive: Directive::Equ("SVC_PUTC".into(), 1),
+            })
+        );
+        assert!(parse_line(".equ SVC_PUTC", 1).is_err());
+        assert!(parse_line(".equ 1X, 2", 1).is_err());
+        assert!(parse_line(".equ BIG, 0x10000", 1).is_err());
+    }
+
+    #[test]
+    fn parse_directive_layout() {
+        assert_eq!(
+            parse_line(".layout standard", 1),
+            Ok(ParsedLine::Directive {
+                directive: Directive::StandardLayout,
+            })
+        );
+        assert!(parse_line(".layout compact", 1).is_err());
+    }
+
+    #[test]
+    fn parse_pseudo_op_declarations_and_uses() {
+        let mut pseudo_ops = Vec::new();
+        let def = PseudoOpDef {
+            name: "XMUL".into(),
+            op: 0xC,
+            sub: 1,
+        };
+        assert_eq!(
+            parse_line_with_pseudo_ops(".pseudo_op XMUL, 0xC, 1", 1, &mut pseudo_ops),
+            Ok(ParsedLine::Directive {
+                directive: Directive::PseudoOp(def.clone()),
+            })
+        );
+        assert_eq!(pseudo_ops, [def]);
+        assert_eq!(
+            parse_line_with_pseudo_ops("top: xmul R3, R4 ; hi word", 2, &mut pseudo_ops),
+            Ok(ParsedLine::Directive {
+                directive: Directive::Word(0xC708),
+            })
+        );
+        assert!(parse_line_with_pseudo_ops(".pseudo_op xmul, 0xD", 3, &mut pseudo_ops).is_err());
+        assert!(parse_line_with_pseudo_ops("XMUL R1, R2, #8", 4, &mut pseudo_ops).is_err());
+        assert!(parse_line_with_pseudo_ops("XMUL R1, R2, #1, R3", 5, &mut pseudo_ops).is_err());
+        assert!(parse_line_with_pseudo_ops("MULX R1", 6, &mut pseudo_ops).is_err());
+    }
+
+    #[test]
+    fn pseudo_op_declarations_need_a_new_name_and_reserved_opcode() {
+        for line in [
+            ".pseudo_op ADD, 0xB",
+            ".pseudo_op FOO, 0x4",
+            ".pseudo_op FOO, 0x10",
+            ".pseudo_op FOO, 0xB, 8",
+            ".pseudo_op FOO",
+            ".pseudo_op 1FOO, 0xB",
+        ] {
+            assert!(
+                matches!(
+                    parse_line(line, 1),
+                    Err(ParseError {
+                        kind: ParseErrorKind::InvalidDirectiveValue(_),
+                        ..
+                    })
+                ),
+                "{line}"
+            );
+        }
+    }
+
     #[test]
     fn parse_directive_include_with_path() {
         let result = parse_line(".include \"lib/utils.n1.md\"", 1);
@@ -1233,6 +2030,97 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_directive_incbin() {
+        let result = parse_line(".incbin \"sprites.bin\"", 1);
+        match result {
+            Ok(ParsedLine::Directive {
+                directive: Directive::IncBin(ops),
+            }) => {
+                assert_eq!(ops.path, "sprites.bin");
+                assert_eq!(ops.offset, 0);
+                assert_eq!(ops.length, None);
+                assert!(ops.data.is_empty());
+            }
+            _ => panic!("expected incbin directive"),
+        }
+    }
+
+    #[test]
+    fn parse_directive_incbin_with_range() {
+        let result = parse_line(".incbin \"sprites.bin\", 0x10, 32", 1);
+        match result {
+            Ok(ParsedLine::Directive {
+                directive: Directive::IncBin(ops),
+            }) => {
+                assert_eq!(ops.offset, 0x10);
+                assert_eq!(ops.length, Some(32));
+            }
+            _ => panic!("expected incbin directive"),
+        }
+    }
+
+    #[test]
+    fn parse_directive_incbin_rejects_extra_operands() {
+        assert!(parse_line(".incbin \"a.bin\", 1, 2, 3", 1).is_err());
+        assert!(parse_line(".incbin \"a.bin\" 4", 1).is_err());
+    }
+
+    #[test]
+    fn parse_size_of_operands() {
+        let table = |unit| SizeOf {
+            start: "table".into(),
+            end: "table_end".into(),
+            unit,
+        };
+        match parse_line("MOV R1, #lengthof(table, table_end)", 1) {
+            Ok(ParsedLine::Instruction { instruction }) => match instruction.operand {
+                Some(Operand::Immediate(imm)) => {
+                    assert!(!imm.is_label);
+                    assert_eq!(imm.size_of, Some(table(SizeUnit::Words)));
+                }
+                _ => panic!("expected immediate operand"),
+            },
+            _ => panic!("expected instruction"),
+        }
+        match parse_line(".word SIZEOF(table,table_end)", 1) {
+            Ok(ParsedLine::Directive {
+                directive: Directive::LiteralWord(imm),
+            }) => assert_eq!(imm.size_of, Some(table(SizeUnit::Bytes))),
+            other => panic!("expected size word, got {other:?}"),
+        }
+        assert_eq!(table(SizeUnit::Words).resolve(0x10, 0x16), Some(3));
+        assert_eq!(table(SizeUnit::Words).resolve(0x10, 0x15), None);
+        assert_eq!(table(SizeUnit::Bytes).resolve(0x10, 0x0E), None);
+
+        let err = parse_line("MOV R1, #sizeof(table)", 1).unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::InvalidSyntax(_)));
+    }
+
+    #[test]
+    fn parse_block_ops() {
+        assert_eq!(
+            parse_line("memcmp r3, r1, r0", 1),
+            Ok(ParsedLine::Directive {
+                directive: Directive::BlockOp(BlockOp {
+                    kind: BlockOpKind::Compare,
+                    first: Register(3),
+                    second: Register(1),
+                    count: Register(0),
+                }),
+            })
+        );
+        for source in [
+            "MEMCPY R0, R1",
+            "MEMCPY R0, R1, R7",
+            "MEMCPY R0, R0, R2",
+            "MEMCPY R0, R1, #4",
+        ] {
+            assert!(parse_line(source, 1).is_err(), "{source}");
+        }
+        assert!(parse_line(".pseudo_op MEMCPY, 0xB", 1).is_err());
+    }
+
     #[test]
     fn parse_comment_stripped() {
         let result = parse_line("MOV R0, #1 ; this is a comment", 1);
@@ -1268,6 +2156,60 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn error_special_register_operand() {
+        for line in [
+            "MOV R0, SP",
+            "PUSH SP",
+            "ADD pc, R1, #1",
+            "LOAD R1, [SP + 2]",
+        ] {
+            let err = parse_line(line, 1).expect_err(line);
+            assert!(
+                matches!(err.kind, ParseErrorKind::SpecialRegister(_)),
+                "{line}: {err:?}"
+            );
+        }
+
+        let err = parse_line("MOV R0, SP", 1).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "SP is a special register, not a general register (R0-R7); \
+             it changes only through PUSH, POP, CALL and RET; read it with MRS Rd, SP"
+        );
+        let err = parse_line("MOV R0, TICK", 1).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "TICK is a special register, not a general register (R0-R7); \
+             read it with MRS Rd, TICK"
+        );
+    }
+
+    #[test]
+    fn parse_mrs_special_register_source() {
+        let Ok(ParsedLine::Instruction { instruction }) = parse_line("MRS R2, cause", 1) else {
+            panic!("expected instruction");
+        };
+        assert_eq!(instruction.rd, Some(Register(2)));
+        assert_eq!(instruction.ra, Some(Register(3)));
+        assert_eq!(instruction.operand, None);
+        assert_eq!(instruction.size, InstructionSize::OneWord);
+
+        let err = parse_line("MRS R2, PC", 1).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid syntax: MRS reads FLAGS, TICK, CAP, CAUSE, EVP or SP, not PC"
+        );
+        assert!(matches!(
+            parse_line("MRS R2", 1).unwrap_err().kind,
+            ParseErrorKind::MissingOperand
+        ));
+        assert!(matches!(
+            parse_line("MRS SP, FLAGS", 1).unwrap_err().kind,
+            ParseErrorKind::SpecialRegister(_)
+        ));
+    }
+
     #[test]
     fn error_invalid_immediate() {
         let result = parse_line("MOV R0, #123abc", 1);
@@ -1292,6 +2234,24 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn unknown_names_suggest_near_misses() {
+        let message = |line| parse_line(line, 1).unwrap_err().to_string();
+        assert_eq!(
+            message("ADDD R0, R1, #1"),
+            "unknown mnemonic: ADDD; did you mean `ADD Rd, Ra, operand`?"
+        );
+        assert_eq!(
+            message("shx R0, R1, #1"),
+            "unknown mnemonic: shx; did you mean `SHL Rd, Ra, operand` or `SHR Rd, Ra, operand`?"
+        );
+        assert_eq!(message("NOTREAL R0"), "unknown mnemonic: NOTREAL");
+        assert_eq!(
+            message(".wrod 1"),
+            "unknown directive: wrod; did you mean `.word value`?"
+        );
+    }
+
     #[test]
     fn case_insensitive_mnemonic() {
         let result = parse_line("mov r0, #1", 1);
@@ -1371,6 +2331,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_cmp_with_immediate_mirrors_three_operand_form() {
+        let short = parse_line("CMP R2, #10", 1).unwrap();
+        let long = parse_line("CMP R2, R2, #10", 1).unwrap();
+        assert_eq!(short, long);
+        match short {
+            ParsedLine::Instruction { instruction } => {
+                assert_eq!(instruction.ra, Some(Register(2)));
+                assert_eq!(instruction.size, InstructionSize::TwoWords);
+            }
+            _ => panic!("expected instruction"),
+        }
+    }
+
     #[test]
     fn error_malformed_operand_unclosed_bracket() {
         let result = parse_line("LOAD R0, [R1", 1);
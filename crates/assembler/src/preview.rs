@@ -0,0 +1,144 @@
+//! Single-line encoding previews for editor hovers.
+//!
+//! [`encode_single_line`] parses and encodes one source line against a
+//! caller-supplied symbol table, without assembling the rest of the document,
+//! and reports what the line costs: its bytes, its size in words, the cycles
+//! it retires in, and the addressing mode of its operand.
+
+use emulator_core::{cycle_cost, cycle_cost_kinds, AddressingMode, Decoder, OpcodeEncoding};
+
+use crate::assembler::{AssembleError, AssembleErrorKind};
+use crate::encoder::encode_line;
+use crate::parser::{parse_line, ParsedLine};
+use crate::symbols::SymbolTable;
+
+/// Encoding of a single source line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineEncoding {
+    /// Emitted bytes, big-endian words for instructions.
+    pub bytes: Vec<u8>,
+    /// Size in 16-bit words, rounded up for odd-length data.
+    pub size_words: usize,
+    /// Fewest cycles the instruction can retire in (`None` for non-instructions).
+    pub min_cycles: Option<u16>,
+    /// Most cycles the instruction can retire in; differs from `min_cycles`
+    /// only for conditional branches.
+    pub max_cycles: Option<u16>,
+    /// Addressing mode of the operand, for instructions that take one.
+    pub addressing_mode: Option<AddressingMode>,
+}
+
+/// Encodes one source line as if it were placed at `pc`.
+///
+/// Labels referenced by the line are resolved through `symbols`; labels and
+/// blank lines encode to nothing.
+///
+/// # Errors
+///
+/// Returns an `AssembleError` if the line fails to parse or encode.
+#[allow(clippy::result_large_err)]
+pub fn encode_single_line(
+    line: &str,
+    pc: u16,
+    symbols: &SymbolTable,
+) -> Result<LineEncoding, AssembleError> {
+    let parsed = parse_line(line, 1).map_err(|err| AssembleError {
+        kind: AssembleErrorKind::Parse(err.to_string()),
+        location: None,
+    })?;
+    let bytes = encode_line(&parsed, symbols, pc, 1).map_err(|err| AssembleError {
+        kind: AssembleErrorKind::Encode(err),
+        location: None,
+    })?;
+
+    let mut encoding = LineEncoding {
+        size_words: bytes.len().div_ceil(2),
+        bytes,
+        min_cycles: None,
+        max_cycles: None,
+        addressing_mode: None,
+    };
+
+    if let ParsedLine::Instruction { instruction } = &parsed {
+        let word = u16::from_be_bytes([encoding.bytes[0], encoding.bytes[1]]);
+        if let Some(decoded) = Decoder::decode(word).instruction() {
+            let costs = instruction_costs(decoded.encoding, decoded.addressing_mode);
+            encoding.min_cycles = costs.clone().min();
+            encoding.max_cycles = costs.max();
+            if instruction.operand.is_some() {
+                encoding.addressing_mode = decoded.addressing_mode;
+            }
+        }
+    }
+
+    Ok(encoding)
+}
+
+/// Cycle costs an instruction can retire with.
+pub(crate) fn instruction_costs(
+    encoding: OpcodeEncoding,
+    mode: Option<AddressingMode>,
+) -> impl Iterator<Item = u16> + Clone {
+    let kinds = cycle_cost_kinds(encoding);
+    // CALL and RET share an encoding; RET is the register-direct form.
+    let kinds = if encoding == OpcodeEncoding::CallOrRet {
+        let is_ret = mode == Some(AddressingMode::DirectRegister);
+        &kinds[usize::from(is_ret)..=usize::from(is_ret)]
+    } else {
+        kinds
+    };
+    kinds.iter().filter_map(|kind| cycle_cost(*kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbols::Symbol;
+
+    #[test]
+    fn previews_immediate_mov() {
+        let preview = encode_single_line("MOV R1, #0x4000", 0, &SymbolTable::new()).unwrap();
+
+        assert_eq!(preview.bytes, vec![0x12, 0x05, 0x40, 0x00]);
+        assert_eq!(preview.size_words, 2);
+        assert_eq!(preview.min_cycles, Some(1));
+        assert_eq!(preview.max_cycles, Some(1));
+        assert_eq!(preview.addressing_mode, Some(AddressingMode::Immediate));
+    }
+
+    #[test]
+    fn branch_reports_cycle_range_and_resolves_symbols() {
+        let mut symbols = SymbolTable::new();
+        symbols.insert(
+            "loop".to_string(),
+            Symbol {
+                address: 0x0010,
+                defined_at: 1,
+            },
+        );
+        let preview = encode_single_line("BEQ #loop", 0x0020, &symbols).unwrap();
+
+        assert_eq!(preview.size_words, 2);
+        assert_eq!(preview.min_cycles, Some(1));
+        assert_eq!(preview.max_cycles, Some(2));
+        let offset = i16::from_be_bytes([preview.bytes[2], preview.bytes[3]]);
+        assert_eq!(0x0024 + i32::from(offset), 0x0010);
+    }
+
+    #[test]
+    fn ret_and_directives_have_no_operand_mode() {
+        let ret = encode_single_line("RET", 0, &SymbolTable::new()).unwrap();
+        assert_eq!(ret.addressing_mode, None);
+        assert_eq!(ret.min_cycles, Some(2));
+
+        let data = encode_single_line(".byte 7", 0, &SymbolTable::new()).unwrap();
+        assert_eq!(data.size_words, 1);
+        assert_eq!(data.min_cycles, None);
+    }
+
+    #[test]
+    fn undefined_label_is_an_encode_error() {
+        let err = encode_single_line("JMP #nowhere", 0, &SymbolTable::new()).unwrap_err();
+        assert!(matches!(err.kind, AssembleErrorKind::Encode(_)));
+    }
+}
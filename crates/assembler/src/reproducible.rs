@@ -0,0 +1,201 @@
+//! Reproducible-build checks and build ids.
+//!
+//! The assembler's output is a flat binary image with no timestamps or
+//! paths in it, so it is a pure function of the source files and the
+//! assembly options. What can still tie a build to one machine is where
+//! those sources come from: an include outside the project, or a standard
+//! library taken from `NULLBYTE_STDLIB_DIR` instead of the bundled copy.
+//! [`reproducibility_issues`] reports those and re-assembles once to catch
+//! anything else, and [`build_id`] fingerprints the output so a shipped
+//! binary can be checked against a fresh build of its sources.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::assembler::{assemble_with_options, AssembleOptions, AssembleResult};
+use crate::stdlib::{stdlib_override, EMBEDDED_STDLIB_DIR};
+
+const FNV_OFFSET: u64 = 0xCBF2_9CE4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Fingerprint of an assembled program: FNV-1a over the binary image and
+/// entry point, as 16 lowercase hex digits.
+#[must_use]
+pub fn build_id(binary: &[u8], entry: Option<u16>) -> String {
+    let entry = entry.map_or([0; 3], |address| {
+        let [hi, lo] = address.to_be_bytes();
+        [1, hi, lo]
+    });
+    format!("{:016x}", fnv1a(fnv1a(FNV_OFFSET, binary), &entry))
+}
+
+/// Something that stops a build from being reproduced elsewhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReproducibilityIssue {
+    /// A source file outside the input file's directory.
+    OutsideSourceTree(PathBuf),
+    /// A standard library module read from `NULLBYTE_STDLIB_DIR`.
+    StdlibOverride(PathBuf),
+    /// Assembling the same sources twice gave different output.
+    Unstable,
+}
+
+impl fmt::Display for ReproducibilityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutsideSourceTree(path) => write!(
+                f,
+                "{} is outside the source tree; rebuilds elsewhere cannot find it",
+                path.display()
+            ),
+            Self::StdlibOverride(path) => write!(
+                f,
+                "{} comes from NULLBYTE_STDLIB_DIR instead of the bundled standard library",
+                path.display()
+            ),
+            Self::Unstable => {
+                f.write_str("assembling the same sources twice gave different output")
+            }
+        }
+    }
+}
+
+/// Checks that `result`, assembled from `input` with `options`, could be
+/// reproduced from the source tree alone.
+///
+/// Every source must live under `input`'s directory or be a bundled
+/// standard library module, and a second assembly must produce the same
+/// binary and entry point.
+#[must_use]
+pub fn reproducibility_issues(
+    input: &Path,
+    options: &AssembleOptions,
+    result: &AssembleResult,
+) -> Vec<ReproducibilityIssue> {
+    let tree = canonical(input.parent().unwrap_or_else(|| Path::new(".")));
+    let overridden = stdlib_override().map(|dir| canonical(&dir));
+
+    let mut issues: Vec<_> = result
+        .sources
+        .iter()
+        .filter_map(|source| {
+            let path = canonical(source);
+            if path.starts_with(&tree) || source.starts_with(EMBEDDED_STDLIB_DIR) {
+                None
+            } else if overridden.as_ref().is_some_and(|dir| path.starts_with(dir)) {
+                Some(ReproducibilityIssue::StdlibOverride(source.clone()))
+            } else {
+                Some(ReproducibilityIssue::OutsideSourceTree(source.clone()))
+            }
+        })
+        .collect();
+
+    let stable = assemble_with_options(input, options)
+        .is_ok_and(|again| again.binary == result.binary && again.entry == result.entry);
+    if !stable {
+        issues.push(ReproducibilityIssue::Unstable);
+    }
+    issues
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// The first byte where a shipped binary and a rebuild differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divergence {
+    /// Offset into the images, which is also the address.
+    pub offset: usize,
+    /// Shipped byte, or `None` past the end of the shipped binary.
+    pub shipped: Option<u8>,
+    /// Rebuilt byte, or `None` past the end of the rebuild.
+    pub rebuilt: Option<u8>,
+}
+
+/// Finds the first difference between `shipped` and `rebuilt`, or `None`
+/// when they are identical.
+#[must_use]
+pub fn first_divergence(shipped: &[u8], rebuilt: &[u8]) -> Option<Divergence> {
+    let offset = shipped
+        .iter()
+        .zip(rebuilt)
+        .position(|(a, b)| a != b)
+        .or_else(|| (shipped.len() != rebuilt.len()).then(|| shipped.len().min(rebuilt.len())))?;
+    Some(Divergence {
+        offset,
+        shipped: shipped.get(offset).copied(),
+        rebuilt: rebuilt.get(offset).copied(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_id_covers_binary_and_entry() {
+        let id = build_id(&[0x00, 0x10], None);
+        assert_eq!(id.len(), 16);
+        assert_eq!(id, build_id(&[0x00, 0x10], None));
+        assert_ne!(id, build_id(&[0x00, 0x11], None));
+        assert_ne!(id, build_id(&[0x00, 0x10], Some(0)));
+        assert_ne!(build_id(&[], Some(0x0100)), build_id(&[], Some(0x0001)));
+    }
+
+    #[test]
+    fn first_divergence_reports_bytes_and_length_changes() {
+        assert_eq!(first_divergence(&[1, 2, 3], &[1, 2, 3]), None);
+        assert_eq!(
+            first_divergence(&[1, 2, 3], &[1, 9, 3]),
+            Some(Divergence {
+                offset: 1,
+                shipped: Some(2),
+                rebuilt: Some(9),
+            })
+        );
+        assert_eq!(
+            first_divergence(&[1, 2], &[1, 2, 3]),
+            Some(Divergence {
+                offset: 2,
+                shipped: None,
+                rebuilt: Some(3),
+            })
+        );
+    }
+
+    #[test]
+    fn sources_outside_the_tree_are_reported() {
+        let outside = tempfile::tempdir().unwrap();
+        let shared = outside.path().join("shared.n1");
+        fs::write(&shared, "NOP\n").unwrap();
+
+        let project = tempfile::tempdir().unwrap();
+        let local = project.path().join("local.n1");
+        fs::write(&local, "HALT\n").unwrap();
+        let main = project.path().join("main.n1");
+        fs::write(
+            &main,
+            format!(
+                ".include \"local.n1\"\n.include \"{}\"\n.include \"mem.n1.md\"\n",
+                shared.display()
+            ),
+        )
+        .unwrap();
+
+        let options = AssembleOptions::default();
+        let result = assemble_with_options(&main, &options).unwrap();
+
+        assert_eq!(
+            reproducibility_issues(&main, &options, &result),
+            [ReproducibilityIssue::OutsideSourceTree(shared)]
+        );
+    }
+}
@@ -0,0 +1,522 @@
+//! Rhai scripts that drive an assembled program.
+//!
+//! `nullbyte-asm script` loads a program the way `run` does, with a TELE-7
+//! and a debug console attached, and hands the machine to a
+//! [Rhai](https://rhai.rs) script through a small API built on the same
+//! primitives as the debugger:
+//!
+//! | Function | Effect |
+//! | --- | --- |
+//! | `step()` | Executes one instruction; returns `#{kind, ...}` |
+//! | `run(ticks)` | Runs whole ticks; returns `#{ticks, steps, cycles, fault}` |
+//! | `step_over(max)`, `step_out(max)` | Call-aware stepping; returns the stop kind |
+//! | `break_at(addr)`, `break_at(addr, cond)`, `clear_break(addr)` | Breakpoints |
+//! | `run_to_break(max)` | Steps until a breakpoint, HALT or fault; returns whether a breakpoint fired |
+//! | `reg(name)`, `set_reg(name, value)` | Register access |
+//! | `peek(addr)`, `peek_word(addr)`, `poke(addr, byte)`, `poke_word(addr, word)` | Memory access |
+//! | `watch(expr)` | Evaluates a watch expression such as `"R1 + [0x4000]"` |
+//! | `symbol(name)` | Address of a label |
+//! | `event(id)` | Enqueues a host event |
+//! | `console()` | Drains debug console output as text |
+//! | `assert(cond)`, `assert(cond, message)` | Fails the script when `cond` is false |
+//!
+//! Addresses and values are integers; anything outside 16 bits is a script
+//! error rather than being truncated. `print` writes a line to the host.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+
+use emulator_core::{
+    end_tick, run_ticks_with_budget, step_one, step_out, step_over, CompositeMmio, CoreConfig,
+    CoreState, DebugConsole, DmaController, GeneralRegister, MmioBus, PasteBuffer, RunState,
+    StepOutcome, StepStop, SteppingOutcome, Tele7Config, Tele7Peripheral, WatchExpr,
+};
+use rhai::{Dynamic, Engine, EvalAltResult, Map, INT};
+
+use crate::assembler::AssembleResult;
+use crate::test_runner::{new_test_state, TestProgram};
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// A script that failed to compile, raised an error or failed an assertion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptError {
+    /// Rhai's description, including the line and position.
+    pub message: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+/// Result of a script that ran to completion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ScriptOutcome {
+    /// Assertions checked, all of which passed.
+    pub assertions: u32,
+}
+
+struct Machine {
+    state: CoreState,
+    mmio: CompositeMmio,
+    config: CoreConfig,
+    symbols: BTreeMap<String, u16>,
+    assertions: u32,
+}
+
+impl Machine {
+    fn resume_from_halted(&mut self) {
+        if matches!(self.state.run_state, RunState::HaltedForTick) {
+            end_tick(&mut self.state, &mut self.mmio, None);
+        }
+    }
+}
+
+/// Runs `script` against a fresh machine loaded with `result`, sending
+/// `print` output to `print`.
+///
+/// # Errors
+///
+/// Returns a [`ScriptError`] when the script does not compile, raises an
+/// error, or fails an assertion.
+pub fn run_script(
+    result: &AssembleResult,
+    script: &str,
+    print: impl Fn(&str) + 'static,
+) -> Result<ScriptOutcome, ScriptError> {
+    let machine = Rc::new(RefCell::new(Machine {
+        state: new_test_state(&TestProgram::of(result)),
+        mmio: CompositeMmio::new()
+            .with_tele7(Tele7Peripheral::new(Tele7Config::default()))
+            .with_console(DebugConsole::new())
+            .with_dma(DmaController::new())
+            .with_paste(PasteBuffer::new()),
+        config: CoreConfig {
+            reset_pc: result.entry.unwrap_or_default(),
+            ..CoreConfig::default()
+        },
+        symbols: result
+            .symbols
+            .iter()
+            .map(|(name, symbol)| (name.clone(), symbol.address))
+            .collect(),
+        assertions: 0,
+    }));
+
+    let mut engine = Engine::new();
+    engine.on_print(print);
+    register_execution(&mut engine, &machine);
+    register_breakpoints(&mut engine, &machine);
+    register_inspection(&mut engine, &machine);
+    register_memory(&mut engine, &machine);
+
+    engine.run(script).map_err(|err| ScriptError {
+        message: err.to_string(),
+    })?;
+    let assertions = machine.borrow().assertions;
+    Ok(ScriptOutcome { assertions })
+}
+
+fn register_execution(engine: &mut Engine, machine: &Rc<RefCell<Machine>>) {
+    let m = Rc::clone(machine);
+    engine.register_fn("step", move || -> Map {
+        let mut m = m.borrow_mut();
+        m.resume_from_halted();
+        let Machine {
+            state,
+            mmio,
+            config,
+            ..
+        } = &mut *m;
+        step_map(step_one(state, mmio, config))
+    });
+
+    let m = Rc::clone(machine);
+    engine.register_fn("run", move |ticks: INT| -> ScriptResult<Map> {
+        let ticks = u32::try_from(ticks).map_err(|_| format!("invalid tick count {ticks}"))?;
+        let mut m = m.borrow_mut();
+        let Machine {
+            state,
+            mmio,
+            config,
+            ..
+        } = &mut *m;
+        let batch = run_ticks_with_budget(state, mmio, config, ticks);
+        let mut map = Map::new();
+        map.insert("ticks".into(), int(batch.ticks.len()));
+        map.insert("steps".into(), int(batch.total_steps));
+        map.insert("cycles".into(), int(batch.total_cycles));
+        map.insert(
+            "fault".into(),
+            batch
+                .fault
+                .map_or(Dynamic::UNIT, |fault| format!("{fault:?}").into()),
+        );
+        Ok(map)
+    });
+
+    for (name, stepper) in [
+        ("step_over", step_over as Stepper),
+        ("step_out", step_out as Stepper),
+    ] {
+        let m = Rc::clone(machine);
+        engine.register_fn(name, move |max_steps: INT| -> ScriptResult<String> {
+            let max_steps = step_limit(max_steps)?;
+            let mut m = m.borrow_mut();
+            m.resume_from_halted();
+            let Machine {
+                state,
+                mmio,
+                config,
+                ..
+            } = &mut *m;
+            Ok(stop_name(stepper(state, mmio, config, max_steps).stop).to_string())
+        });
+    }
+
+    let m = Rc::clone(machine);
+    engine.register_fn("event", move |id: INT| -> ScriptResult<()> {
+        let id = u8::try_from(id).map_err(|_| format!("invalid event id {id}"))?;
+        m.borrow_mut()
+            .state
+            .enqueue_event(id)
+            .map_err(|_| format!("event queue is full; event {id} dropped").into())
+    });
+}
+
+fn register_breakpoints(engine: &mut Engine, machine: &Rc<RefCell<Machine>>) {
+    let m = Rc::clone(machine);
+    engine.register_fn("break_at", move |addr: INT| -> ScriptResult<()> {
+        m.borrow_mut().state.breakpoints.set(word(addr)?, None);
+        Ok(())
+    });
+
+    let m = Rc::clone(machine);
+    engine.register_fn(
+        "break_at",
+        move |addr: INT, cond: &str| -> ScriptResult<()> {
+            let cond = WatchExpr::parse(cond).map_err(|err| err.to_string())?;
+            m.borrow_mut()
+                .state
+                .breakpoints
+                .set(word(addr)?, Some(cond));
+            Ok(())
+        },
+    );
+
+    let m = Rc::clone(machine);
+    engine.register_fn("clear_break", move |addr: INT| -> ScriptResult<bool> {
+        Ok(m.borrow_mut().state.breakpoints.remove(word(addr)?))
+    });
+
+    let m = Rc::clone(machine);
+    engine.register_fn(
+        "run_to_break",
+        move |max_steps: INT| -> ScriptResult<bool> {
+            let max_steps = step_limit(max_steps)?;
+            let mut m = m.borrow_mut();
+            for _ in 0..max_steps {
+                m.resume_from_halted();
+                let Machine {
+                    state,
+                    mmio,
+                    config,
+                    ..
+                } = &mut *m;
+                let outcome = step_one(state, mmio, config);
+                if matches!(
+                    outcome,
+                    StepOutcome::HaltedForTick { .. } | StepOutcome::Fault { .. }
+                ) {
+                    return Ok(false);
+                }
+                if state
+                    .breakpoints
+                    .check(&state.arch, &state.memory)
+                    .is_some()
+                {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        },
+    );
+}
+
+fn register_inspection(engine: &mut Engine, machine: &Rc<RefCell<Machine>>) {
+    let m = Rc::clone(machine);
+    engine.register_fn("reg", move |name: &str| -> ScriptResult<INT> {
+        if !is_register(name) {
+            return Err(format!("unknown register '{name}'").into());
+        }
+        let m = m.borrow();
+        let expr = WatchExpr::parse(name).map_err(|err| err.to_string())?;
+        Ok(INT::from(expr.evaluate(&m.state.arch, &m.state.memory)))
+    });
+
+    let m = Rc::clone(machine);
+    engine.register_fn(
+        "set_reg",
+        move |name: &str, value: INT| -> ScriptResult<()> {
+            let value = word(value)?;
+            let arch = &mut m.borrow_mut().state.arch;
+            match name.to_ascii_uppercase().as_str() {
+                "PC" => arch.set_pc(value),
+                "SP" => arch.set_sp(value),
+                "FLAGS" => arch.set_flags(value),
+                "TICK" => arch.set_tick(value),
+                upper => {
+                    let register = upper
+                        .strip_prefix('R')
+                        .and_then(|index| index.parse::<usize>().ok())
+                        .and_then(|index| GeneralRegister::ALL.get(index))
+                        .ok_or_else(|| format!("register '{name}' cannot be set"))?;
+                    arch.set_gpr(*register, value);
+                }
+            }
+            Ok(())
+        },
+    );
+
+    let m = Rc::clone(machine);
+    engine.register_fn("watch", move |expr: &str| -> ScriptResult<INT> {
+        let expr = WatchExpr::parse(expr).map_err(|err| err.to_string())?;
+        let m = m.borrow();
+        Ok(INT::from(expr.evaluate(&m.state.arch, &m.state.memory)))
+    });
+
+    let m = Rc::clone(machine);
+    engine.register_fn("symbol", move |name: &str| -> ScriptResult<INT> {
+        m.borrow()
+            .symbols
+            .get(name)
+            .map(|&address| INT::from(address))
+            .ok_or_else(|| format!("unknown label '{name}'").into())
+    });
+
+    let m = Rc::clone(machine);
+    engine.register_fn("console", move || -> String {
+        let bytes = m
+            .borrow_mut()
+            .mmio
+            .console_mut()
+            .map(DebugConsole::drain)
+            .unwrap_or_default();
+        String::from_utf8_lossy(&bytes).into_owned()
+    });
+
+    let m = Rc::clone(machine);
+    engine.register_fn("assert", move |cond: bool| -> ScriptResult<()> {
+        m.borrow_mut().assertions += 1;
+        if cond {
+            Ok(())
+        } else {
+            Err("assertion failed".into())
+        }
+    });
+
+    let m = Rc::clone(machine);
+    engine.register_fn(
+        "assert",
+        move |cond: bool, message: &str| -> ScriptResult<()> {
+            m.borrow_mut().assertions += 1;
+            if cond {
+                Ok(())
+            } else {
+                Err(format!("assertion failed: {message}").into())
+            }
+        },
+    );
+}
+
+fn register_memory(engine: &mut Engine, machine: &Rc<RefCell<Machine>>) {
+    let m = Rc::clone(machine);
+    engine.register_fn("peek", move |addr: INT| -> ScriptResult<INT> {
+        Ok(INT::from(m.borrow().state.memory[usize::from(word(addr)?)]))
+    });
+
+    let m = Rc::clone(machine);
+    engine.register_fn("peek_word", move |addr: INT| -> ScriptResult<INT> {
+        let addr = word(addr)?;
+        let memory = &m.borrow().state.memory;
+        let hi = memory[usize::from(addr)];
+        let lo = memory[usize::from(addr.wrapping_add(1))];
+        Ok(INT::from(u16::from_be_bytes([hi, lo])))
+    });
+
+    let m = Rc::clone(machine);
+    engine.register_fn("poke", move |addr: INT, value: INT| -> ScriptResult<()> {
+        let addr = word(addr)?;
+        let value = u8::try_from(value).map_err(|_| format!("value {value} is not a byte"))?;
+        m.borrow_mut().state.memory[usize::from(addr)] = value;
+        Ok(())
+    });
+
+    let m = Rc::clone(machine);
+    engine.register_fn(
+        "poke_word",
+        move |addr: INT, value: INT| -> ScriptResult<()> {
+            let addr = word(addr)?;
+            let [hi, lo] = word(value)?.to_be_bytes();
+            let memory = &mut m.borrow_mut().state.memory;
+            memory[usize::from(addr)] = hi;
+            memory[usize::from(addr.wrapping_add(1))] = lo;
+            Ok(())
+        },
+    );
+}
+
+type Stepper = fn(&mut CoreState, &mut dyn MmioBus, &CoreConfig, u32) -> SteppingOutcome;
+
+fn is_register(name: &str) -> bool {
+    matches!(
+        name.to_ascii_uppercase().as_str(),
+        "R0" | "R1"
+            | "R2"
+            | "R3"
+            | "R4"
+            | "R5"
+            | "R6"
+            | "R7"
+            | "PC"
+            | "SP"
+            | "FLAGS"
+            | "TICK"
+            | "CAP"
+            | "CAUSE"
+            | "EVP"
+    )
+}
+
+fn word(value: INT) -> ScriptResult<u16> {
+    u16::try_from(value).map_err(|_| format!("value {value} does not fit in 16 bits").into())
+}
+
+fn step_limit(value: INT) -> ScriptResult<u32> {
+    u32::try_from(value).map_err(|_| format!("invalid step limit {value}").into())
+}
+
+fn int(value: impl TryInto<INT>) -> Dynamic {
+    Dynamic::from_int(value.try_into().unwrap_or(INT::MAX))
+}
+
+fn step_map(outcome: StepOutcome) -> Map {
+    let mut map = Map::new();
+    let kind = match outcome {
+        StepOutcome::Retired { cycles } => {
+            map.insert("cycles".into(), int(cycles));
+            "retired"
+        }
+        StepOutcome::HaltedForTick { reason } => {
+            map.insert("reason".into(), format!("{reason:?}").into());
+            "halted"
+        }
+        StepOutcome::TrapDispatch { cause } => {
+            map.insert("cause".into(), int(cause));
+            "trap"
+        }
+        StepOutcome::EventDispatch { event_id } => {
+            map.insert("event_id".into(), int(event_id));
+            "event"
+        }
+        StepOutcome::Fault { cause } => {
+            map.insert("cause".into(), format!("{cause:?}").into());
+            "fault"
+        }
+    };
+    map.insert("kind".into(), kind.into());
+    map
+}
+
+const fn stop_name(stop: StepStop) -> &'static str {
+    match stop {
+        StepStop::Completed => "completed",
+        StepStop::Breakpoint(_) => "breakpoint",
+        StepStop::HaltedForTick => "halted",
+        StepStop::Fault => "fault",
+        StepStop::StepLimit => "step_limit",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble_from_source;
+
+    fn program(source: &str) -> AssembleResult {
+        assemble_from_source(source, "script.n1").unwrap()
+    }
+
+    const COUNTER: &str = "\
+start:
+MOV R1, #0
+loop:
+ADD R1, R1, #1
+HALT
+JMP #loop
+";
+
+    #[test]
+    fn script_steps_runs_and_reads_state() {
+        let printed = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&printed);
+        let outcome = run_script(
+            &program(COUNTER),
+            r#"
+                let s = step();
+                assert(s.kind == "retired");
+                let batch = run(3);
+                assert(batch.ticks == 3, "three ticks");
+                assert(reg("r1") == 3);
+                set_reg("R1", 40);
+                poke_word(0x4000, 2);
+                assert(watch("R1 + [0x4000]") == 42);
+                print(`loop at ${symbol("loop")}`);
+            "#,
+            move |line| sink.borrow_mut().push(line.to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(outcome.assertions, 4);
+        assert_eq!(*printed.borrow(), ["loop at 4"]);
+    }
+
+    #[test]
+    fn failed_assertions_and_bad_values_are_errors() {
+        let err = run_script(
+            &program(COUNTER),
+            "assert(reg(\"R1\") == 1, \"R1 set\");",
+            |_| {},
+        )
+        .unwrap_err();
+        assert!(err.message.contains("assertion failed: R1 set"), "{err}");
+
+        let err = run_script(&program(COUNTER), "poke(0x10000, 1);", |_| {}).unwrap_err();
+        assert!(err.message.contains("does not fit in 16 bits"), "{err}");
+
+        let err = run_script(&program(COUNTER), "reg(\"R9\");", |_| {}).unwrap_err();
+        assert!(err.message.contains("unknown register"), "{err}");
+    }
+
+    #[test]
+    fn breakpoints_stop_run_to_break() {
+        let outcome = run_script(
+            &program(COUNTER),
+            r#"
+                break_at(symbol("loop") + 4, "R1 == 2");
+                assert(!run_to_break(100));
+                assert(run_to_break(100));
+                assert(reg("R1") == 2 && reg("PC") == symbol("loop") + 4);
+            "#,
+            |_| {},
+        )
+        .unwrap();
+        assert_eq!(outcome.assertions, 3);
+    }
+}
@@ -0,0 +1,451 @@
+//! `serve`: a minimal WebSocket server that pushes rebuilt bundles to the
+//! browser.
+//!
+//! Every connected client gets each message as one text frame, and a client
+//! that connects late is sent the latest message straight away. Each client
+//! is written to from its own thread, so a slow client never holds up a
+//! rebuild or the other clients. The server never reads frames from
+//! clients; a client whose write fails or times out is dropped.
+//!
+//! Browsers let any page open a WebSocket to localhost, so an upgrade
+//! request carrying an `Origin` header is refused with `403 Forbidden`
+//! unless that origin is the debug tool's or one passed to `bind`.
+//! Requests without one come from non-browser clients and are accepted.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+/// `format` tag of the message sent in place of a bundle when a rebuild
+/// fails.
+pub const BUILD_ERROR_FORMAT: &str = "nullbyte-build-error";
+
+/// Port `serve` listens on when `--port` is omitted.
+pub const DEFAULT_PORT: u16 = 8080;
+
+/// Origins the debug tool is served from: the hosted build and the Vite
+/// dev and preview servers.
+pub const DEBUG_TOOL_ORIGINS: &[&str] = &[
+    "https://lucaspiller.github.io",
+    "http://localhost:5173",
+    "http://127.0.0.1:5173",
+    "http://localhost:4173",
+    "http://127.0.0.1:4173",
+];
+
+/// GUID appended to the client's key to form the handshake accept key
+/// (RFC 6455 section 1.3).
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// How long a client may take to send its upgrade request.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a single write to a client may block before it is dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Frames waiting to be written to one client.
+type Outbox = Sender<Arc<[u8]>>;
+
+#[derive(Default)]
+struct Clients {
+    outboxes: Vec<Outbox>,
+    latest: Option<Arc<[u8]>>,
+}
+
+/// Accepts WebSocket clients on a background thread and broadcasts
+/// messages to them.
+pub struct ReloadServer {
+    address: SocketAddr,
+    clients: Arc<Mutex<Clients>>,
+}
+
+/// The parts of an upgrade request the handshake needs.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Request {
+    key: Option<String>,
+    origin: Option<String>,
+}
+
+impl ReloadServer {
+    /// Listens on `port` on the loopback interface. Port 0 picks a free one.
+    /// Browser clients are accepted from [`DEBUG_TOOL_ORIGINS`] and from
+    /// `extra_origins`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from binding the socket.
+    pub fn bind(port: u16, extra_origins: &[String]) -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        let address = listener.local_addr()?;
+        let clients = Arc::new(Mutex::new(Clients::default()));
+        let shared = Arc::clone(&clients);
+        let origins: Arc<[String]> = DEBUG_TOOL_ORIGINS
+            .iter()
+            .map(|origin| (*origin).to_string())
+            .chain(extra_origins.iter().cloned())
+            .collect();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let shared = Arc::clone(&shared);
+                let origins = Arc::clone(&origins);
+                thread::spawn(move || join(&shared, &origins, stream));
+            }
+        });
+        Ok(Self { address, clients })
+    }
+
+    /// Address the server is listening on.
+    pub const fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Queues `message` for every connected client and keeps it for
+    /// clients that connect later. Returns how many clients it was queued
+    /// for.
+    pub fn publish(&self, message: &str) -> usize {
+        let frame: Arc<[u8]> = text_frame(message).into();
+        let mut clients = self.clients.lock().unwrap_or_else(PoisonError::into_inner);
+        clients
+            .outboxes
+            .retain(|outbox| outbox.send(Arc::clone(&frame)).is_ok());
+        clients.latest = Some(frame);
+        clients.outboxes.len()
+    }
+}
+
+/// Completes the handshake with a new client, adds it to the broadcast
+/// list and writes it the latest message and every later one until a
+/// write fails. Anything that is not a WebSocket upgrade request is
+/// answered with `426 Upgrade Required`, and a request from an origin not
+/// in `origins` with `403 Forbidden`.
+fn join(clients: &Mutex<Clients>, origins: &[String], mut stream: TcpStream) {
+    if stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_err() {
+        return;
+    }
+    let Ok(request) = read_request(&stream) else {
+        return;
+    };
+    if let Some(origin) = &request.origin {
+        if !origin_allowed(origin, origins) {
+            let body = format!(
+                "nullbyte-asm serve: origin {origin} is not allowed (see --allow-origin)\n"
+            );
+            let _ = write!(
+                stream,
+                "HTTP/1.1 403 Forbidden\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            );
+            return;
+        }
+    }
+    let Some(key) = request.key else {
+        let body = "nullbyte-asm serve: connect with a WebSocket client\n";
+        let _ = write!(
+            stream,
+            "HTTP/1.1 426 Upgrade Required\r\nUpgrade: websocket\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        return;
+    };
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(&key)
+    );
+    if stream.write_all(response.as_bytes()).is_err() {
+        return;
+    }
+
+    let (outbox, frames) = mpsc::channel();
+    {
+        let mut clients = clients.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(latest) = &clients.latest {
+            let _ = outbox.send(Arc::clone(latest));
+        }
+        clients.outboxes.push(outbox);
+    }
+    for frame in frames {
+        if stream.write_all(&frame).is_err() {
+            return;
+        }
+    }
+}
+
+/// Reads the request headers and returns the `Sec-WebSocket-Key` and
+/// `Origin` headers. A request cut off before its blank line has neither.
+fn read_request(stream: &TcpStream) -> io::Result<Request> {
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut request = Request::default();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(Request::default());
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            return Ok(request);
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let name = name.trim();
+            if name.eq_ignore_ascii_case("sec-websocket-key") {
+                request.key = Some(value.trim().to_string());
+            } else if name.eq_ignore_ascii_case("origin") {
+                request.origin = Some(value.trim().to_string());
+            }
+        }
+    }
+}
+
+/// Whether `origin` is one of `origins`. Scheme and host compare
+/// case-insensitively and a trailing slash is ignored.
+fn origin_allowed(origin: &str, origins: &[String]) -> bool {
+    let origin = origin.trim_end_matches('/');
+    origins
+        .iter()
+        .any(|allowed| allowed.trim_end_matches('/').eq_ignore_ascii_case(origin))
+}
+
+/// The `Sec-WebSocket-Accept` value answering a client's key.
+fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{key}{HANDSHAKE_GUID}").as_bytes()))
+}
+
+/// An unmasked, unfragmented text frame carrying `payload`.
+fn text_frame(payload: &str) -> Vec<u8> {
+    let payload = payload.as_bytes();
+    let mut frame = vec![0x81];
+    match u16::try_from(payload.len()) {
+        Ok(len @ 0..=125) => frame.push(len.to_be_bytes()[1]),
+        Ok(len) => {
+            frame.push(126);
+            frame.extend_from_slice(&len.to_be_bytes());
+        }
+        Err(_) => {
+            frame.push(127);
+            frame.extend_from_slice(&(payload.len() as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// SHA-1 digest of `data`, needed only for the handshake. Variable names
+/// follow FIPS 180-4.
+#[allow(clippy::many_single_char_names)]
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [
+        0x6745_2301,
+        0xEFCD_AB89,
+        0x98BA_DCFE,
+        0x1032_5476,
+        0xC3D2_E1F0,
+    ];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut words = [0_u32; 80];
+        for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..80 {
+            words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, word) in words.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let next = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = next;
+        }
+        for (value, add) in state.iter_mut().zip([a, b, c, d, e]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (bytes, value) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+/// Standard padded base64.
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0_u32, |bits, (i, &byte)| {
+            bits | u32::from(byte) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(char::from(ALPHABET[(bits >> (18 - 6 * i) & 0x3F) as usize]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write as _;
+    use std::io::Read;
+
+    #[test]
+    fn accept_key_matches_the_rfc_example() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+        assert_eq!(base64(b"ab"), "YWI=");
+    }
+
+    #[test]
+    fn sha1_matches_the_fips_test_vectors() {
+        let hex = |digest: [u8; 20]| -> String {
+            digest.iter().fold(String::new(), |mut hex, byte| {
+                let _ = write!(hex, "{byte:02x}");
+                hex
+            })
+        };
+        assert_eq!(hex(sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(
+            hex(sha1(b"abc")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        // 56 bytes: the length no longer fits in the first block.
+        assert_eq!(
+            hex(sha1(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+        );
+        assert_eq!(
+            hex(sha1("a".repeat(1_000_000).as_bytes())),
+            "34aa973cd4c4daa4f61eeb2bdbad27316534016f"
+        );
+    }
+
+    #[test]
+    fn base64_matches_the_rfc_4648_test_vectors() {
+        for (input, expected) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(base64(input.as_bytes()), expected, "{input:?}");
+        }
+        assert_eq!(base64(&[0xFB, 0xFF, 0xBF]), "+/+/");
+    }
+
+    #[test]
+    fn foreign_origins_are_refused() {
+        let server = ReloadServer::bind(0, &["http://example.test".to_string()]).unwrap();
+        let handshake = |origin: &str| {
+            let mut client = TcpStream::connect(server.local_addr()).unwrap();
+            write!(
+                client,
+                "GET / HTTP/1.1\r\nUpgrade: websocket\r\n{origin}Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n"
+            )
+            .unwrap();
+            let mut status = [0; 12];
+            client.read_exact(&mut status).unwrap();
+            String::from_utf8(status.to_vec()).unwrap()
+        };
+        assert_eq!(
+            handshake("Origin: https://evil.example\r\n"),
+            "HTTP/1.1 403"
+        );
+        assert_eq!(
+            handshake("Origin: http://localhost:8081\r\n"),
+            "HTTP/1.1 403"
+        );
+        assert_eq!(handshake("Origin: null\r\n"), "HTTP/1.1 403");
+        assert_eq!(
+            handshake("Origin: http://localhost:5173\r\n"),
+            "HTTP/1.1 101"
+        );
+        assert_eq!(
+            handshake("Origin: HTTP://Example.test/\r\n"),
+            "HTTP/1.1 101"
+        );
+        assert_eq!(handshake(""), "HTTP/1.1 101");
+    }
+
+    #[test]
+    fn frames_use_the_shortest_length_encoding() {
+        assert_eq!(text_frame("hi"), [0x81, 2, b'h', b'i']);
+        let medium = "x".repeat(300);
+        assert_eq!(text_frame(&medium)[..4], [0x81, 126, 0x01, 0x2C]);
+        let large = "x".repeat(70_000);
+        assert_eq!(
+            text_frame(&large)[..10],
+            [0x81, 127, 0, 0, 0, 0, 0, 0x01, 0x11, 0x70]
+        );
+    }
+
+    #[test]
+    fn clients_that_stop_reading_do_not_block_publishing() {
+        let server = ReloadServer::bind(0, &[]).unwrap();
+        let mut client = TcpStream::connect(server.local_addr()).unwrap();
+        client
+            .write_all(b"GET / HTTP/1.1\r\nUpgrade: websocket\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n")
+            .unwrap();
+        let mut response = [0; 12];
+        client.read_exact(&mut response).unwrap();
+        assert_eq!(&response, b"HTTP/1.1 101");
+
+        // Far more than the socket buffers hold, and the client never reads.
+        let message = "x".repeat(1 << 20);
+        let start = std::time::Instant::now();
+        for _ in 0..32 {
+            server.publish(&message);
+        }
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn late_clients_receive_the_latest_message() {
+        let server = ReloadServer::bind(0, &[]).unwrap();
+        assert_eq!(server.publish("first"), 0);
+        assert_eq!(server.publish("second"), 0);
+
+        let mut client = TcpStream::connect(server.local_addr()).unwrap();
+        client
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n")
+            .unwrap();
+        let expected = b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n\r\n\x81\x06second";
+        let mut received = vec![0; expected.len()];
+        client.read_exact(&mut received).unwrap();
+        assert_eq!(received, expected);
+    }
+}
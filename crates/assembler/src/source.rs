@@ -9,10 +9,72 @@
 //! file's line numbers.
 //!
 //! Inline test blocks (`n1test` fenced code blocks) are also extracted from
-//! literate files and collected separately for the test runner.
+//! literate files and collected separately for the test runner, together
+//! with `n1expect-screen` blocks holding the TELE-7 text expected at a test
+//! block's HALT.
+//!
+//! Files saved on Windows are accepted as-is: a leading UTF-8 byte order mark
+//! is dropped and CRLF (or bare CR) line endings count as a single line break.
 
+use std::borrow::Cow;
 use std::path::Path;
 
+/// UTF-8 byte order mark.
+const BOM: &str = "\u{FEFF}";
+
+/// A source file that is not valid UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidUtf8 {
+    /// Byte offset of the first invalid byte in the file.
+    pub offset: usize,
+    /// 1-indexed line containing the invalid byte.
+    pub line: usize,
+    /// 1-indexed byte column of the invalid byte within its line.
+    pub column: usize,
+}
+
+impl std::fmt::Display for InvalidUtf8 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid UTF-8 at byte offset {} (line {}, column {})",
+            self.offset, self.line, self.column
+        )
+    }
+}
+
+/// Decodes raw file bytes as source text.
+///
+/// # Errors
+///
+/// Returns the location of the first invalid byte if `bytes` is not UTF-8.
+pub fn decode_source(bytes: &[u8]) -> Result<String, InvalidUtf8> {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Ok(text.to_string()),
+        Err(error) => {
+            let offset = error.valid_up_to();
+            let before = std::str::from_utf8(&bytes[..offset]).unwrap_or_default();
+            let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+            Err(InvalidUtf8 {
+                offset,
+                line: before.matches('\n').count() + 1,
+                column: offset - line_start + 1,
+            })
+        }
+    }
+}
+
+/// Drops a leading byte order mark and rewrites CRLF and bare CR line
+/// endings as LF.
+fn normalize_source(content: &str) -> Cow<'_, str> {
+    let content = content.strip_prefix(BOM).unwrap_or(content);
+    if content.contains('\r') {
+        Cow::Owned(content.replace("\r\n", "\n").replace('\r', "\n"))
+    } else {
+        Cow::Borrowed(content)
+    }
+}
+
 /// A line of extracted source with its original location.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SourceLine {
@@ -31,6 +93,25 @@ pub struct TestBlock {
     pub start_line: usize,
     /// 1-indexed line number where the block ends (the closing fence).
     pub end_line: usize,
+    /// The `n1expect-screen` block that follows this one, if any.
+    pub screen: Option<ScreenBlock>,
+}
+
+/// An extracted `n1expect-screen` block: the TELE-7 screen text expected
+/// after a test block's HALT.
+///
+/// A screen block belongs to the `n1test` block before it. One with no
+/// `n1test` block before it (or only one that already has a screen) becomes
+/// a test block of its own with no assertions, so a display demo can check
+/// each HALT with screen blocks alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreenBlock {
+    /// The expected screen text (without fence lines).
+    pub content: String,
+    /// 1-indexed line number of the opening fence.
+    pub start_line: usize,
+    /// 1-indexed line number of the closing fence.
+    pub end_line: usize,
 }
 
 /// Extracted source content from an input file.
@@ -53,6 +134,8 @@ pub struct SourceContent {
 #[must_use]
 pub fn extract_source(file_path: &Path, content: &str) -> SourceContent {
     let file_path_str = file_path.to_string_lossy().to_string();
+    let content = normalize_source(content);
+    let content = content.as_ref();
 
     if is_literate_file(file_path) {
         let (lines, test_blocks) = extract_literate_source(content);
@@ -71,7 +154,7 @@ pub fn extract_source(file_path: &Path, content: &str) -> SourceContent {
 }
 
 /// Returns true if the file should be treated as literate (Markdown) format.
-fn is_literate_file(path: &Path) -> bool {
+pub(crate) fn is_literate_file(path: &Path) -> bool {
     let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
     let lower = file_name.to_ascii_lowercase();
     lower.ends_with(".n1.md")
@@ -101,6 +184,8 @@ enum BlockType {
     N1asm,
     /// Inline test block (`n1test`).
     N1test,
+    /// Expected TELE-7 screen text (`n1expect-screen`).
+    N1expectScreen,
 }
 
 /// Extracts source lines and test blocks from literate (Markdown) format.
@@ -123,13 +208,18 @@ fn extract_literate_source(content: &str) -> (Vec<SourceLine>, Vec<TestBlock>) {
         if let Some(fence_length) = is_fence_start(line) {
             if let Some(block_type) = current_block {
                 if fence_length >= fence_len {
-                    if block_type == BlockType::N1test {
-                        test_blocks.push(TestBlock {
-                            content: test_content.clone(),
+                    let content = std::mem::take(&mut test_content);
+                    match block_type {
+                        BlockType::N1asm => {}
+                        BlockType::N1test => test_blocks.push(TestBlock {
+                            content,
                             start_line: test_start_line,
                             end_line: line_num,
-                        });
-                        test_content.clear();
+                            screen: None,
+                        }),
+                        BlockType::N1expectScreen => {
+                            attach_screen(&mut test_blocks, content, test_start_line, line_num);
+                        }
                     }
                     current_block = None;
                     fence_len = 0;
@@ -149,6 +239,10 @@ fn extract_literate_source(content: &str) -> (Vec<SourceLine>, Vec<TestBlock>) {
                     current_block = Some(BlockType::N1test);
                     fence_len = fence_length;
                     test_start_line = line_num;
+                } else if trimmed.starts_with("n1expect-screen") {
+                    current_block = Some(BlockType::N1expectScreen);
+                    fence_len = fence_length;
+                    test_start_line = line_num;
                 }
             }
         } else if let Some(block_type) = current_block {
@@ -159,7 +253,7 @@ fn extract_literate_source(content: &str) -> (Vec<SourceLine>, Vec<TestBlock>) {
                         original_line: line_num,
                     });
                 }
-                BlockType::N1test => {
+                BlockType::N1test | BlockType::N1expectScreen => {
                     if !test_content.is_empty() {
                         test_content.push('\n');
                     }
@@ -172,6 +266,55 @@ fn extract_literate_source(content: &str) -> (Vec<SourceLine>, Vec<TestBlock>) {
     (lines, test_blocks)
 }
 
+/// Attaches a closed `n1expect-screen` block to the preceding test block,
+/// or makes it a test block of its own when there is none to attach to.
+fn attach_screen(test_blocks: &mut Vec<TestBlock>, content: String, start: usize, end: usize) {
+    let screen = ScreenBlock {
+        content,
+        start_line: start,
+        end_line: end,
+    };
+    match test_blocks.last_mut() {
+        Some(block) if block.screen.is_none() => block.screen = Some(screen),
+        _ => test_blocks.push(TestBlock {
+            content: String::new(),
+            start_line: start,
+            end_line: end,
+            screen: Some(screen),
+        }),
+    }
+}
+
+/// Finds the ATX headings (`#` to `######`) of a Markdown document outside
+/// fenced code blocks, as 1-indexed line and heading text.
+#[must_use]
+pub fn markdown_headings(content: &str) -> Vec<(usize, String)> {
+    let content = normalize_source(content);
+    let mut headings = Vec::new();
+    let mut fence_len = 0;
+    for (idx, line) in content.lines().enumerate() {
+        if let Some(length) = is_fence_start(line) {
+            if fence_len == 0 {
+                fence_len = length;
+            } else if length >= fence_len {
+                fence_len = 0;
+            }
+            continue;
+        }
+        if fence_len > 0 || line.len() - line.trim_start_matches(' ').len() > 3 {
+            continue;
+        }
+        let trimmed = line.trim_start_matches(' ');
+        let level = trimmed.chars().take_while(|&c| c == '#').count();
+        let text = &trimmed[level..];
+        if (1..=6).contains(&level) && (text.is_empty() || text.starts_with([' ', '\t'])) {
+            let text = text.trim().trim_end_matches('#').trim_end();
+            headings.push((idx + 1, text.to_string()));
+        }
+    }
+    headings
+}
+
 /// Checks if a line is a fenced code block delimiter.
 ///
 /// Returns the number of backticks if this is a fence start (>= 3 backticks),
@@ -206,6 +349,46 @@ mod tests {
         assert_eq!(result.lines[2].original_line, 3);
     }
 
+    #[test]
+    fn bom_and_crlf_are_normalized() {
+        let content = "\u{FEFF}MOV R0, #1\r\n\r\nHALT\rNOP\r\n";
+        let result = extract_source(Path::new("test.n1"), content);
+
+        let lines: Vec<_> = result
+            .lines
+            .iter()
+            .map(|line| (line.text.as_str(), line.original_line))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![("MOV R0, #1", 1), ("", 2), ("HALT", 3), ("NOP", 4)]
+        );
+    }
+
+    #[test]
+    fn crlf_literate_blocks_keep_line_numbers() {
+        let content = "# T\r\n```n1asm\r\nNOP\r\n```\r\n```n1test\r\nR0 == 0\r\n```\r\n";
+        let result = extract_source(Path::new("test.n1.md"), content);
+
+        assert_eq!(result.lines[0].text, "NOP");
+        assert_eq!(result.lines[0].original_line, 3);
+        assert_eq!(result.test_blocks[0].content, "R0 == 0");
+        assert_eq!(result.test_blocks[0].start_line, 5);
+    }
+
+    #[test]
+    fn invalid_utf8_reports_offset_and_position() {
+        assert_eq!(decode_source(b"NOP\nHALT").unwrap(), "NOP\nHALT");
+        assert_eq!(
+            decode_source(b"NOP\nMOV R0, \xFF\n"),
+            Err(InvalidUtf8 {
+                offset: 12,
+                line: 2,
+                column: 9,
+            })
+        );
+    }
+
     #[test]
     fn literate_single_block() {
         let content = r"# Title
@@ -361,6 +544,15 @@ ADD R0, R0, R0
         assert_eq!(result.lines[2].text, "ADD R0, R0, R0");
     }
 
+    #[test]
+    fn markdown_headings_skip_fenced_blocks() {
+        let content = "# Intro\n\n```n1asm\n# not a heading\n```\n##Not either\n## Main loop ##\n    # indented code\n";
+        assert_eq!(
+            markdown_headings(content),
+            [(1, "Intro".to_string()), (7, "Main loop".to_string())]
+        );
+    }
+
     #[test]
     fn is_literate_file_detection() {
         assert!(is_literate_file(Path::new("test.n1.md")));
@@ -484,6 +676,39 @@ R0 == 0x0002
         assert_eq!(result.test_blocks[0].content, "");
     }
 
+    #[test]
+    fn literate_screen_blocks_attach_to_preceding_test() {
+        let content = r"```n1asm
+HALT
+```
+
+```n1test
+R0 == 0
+```
+
+```n1expect-screen
+HELLO
+  WORLD
+```
+
+```n1expect-screen
+BYE
+```
+";
+        let result = extract_source(Path::new("test.n1.md"), content);
+
+        assert_eq!(result.lines.len(), 1);
+        assert_eq!(result.test_blocks.len(), 2);
+        let screen = result.test_blocks[0].screen.as_ref().unwrap();
+        assert_eq!(screen.content, "HELLO\n  WORLD");
+        assert_eq!((screen.start_line, screen.end_line), (9, 12));
+
+        let standalone = &result.test_blocks[1];
+        assert_eq!(standalone.content, "");
+        assert_eq!((standalone.start_line, standalone.end_line), (14, 16));
+        assert_eq!(standalone.screen.as_ref().unwrap().content, "BYE");
+    }
+
     #[test]
     fn plain_file_no_test_blocks() {
         let content = "MOV R0, #1\nHALT\n";
@@ -0,0 +1,305 @@
+//! Where the bytes of an assembled program come from.
+//!
+//! [`assembly_stats`] totals the emitted bytes per source file and, for
+//! literate `.n1.md` files, per Markdown section, splitting each total into
+//! instruction and data bytes. `build --stats` prints the result as a table
+//! so authors of large literate programs can see which chapters spend the
+//! ROM budget:
+//!
+//! ```text
+//!    bytes     code     data  source
+//!      150      120       30  game.n1.md
+//!       40       40        0    Setup (line 3)
+//!      110       80       30    Main loop (line 18)
+//!      150      120       30  total, 80% code
+//! symbols: 12 labels, 3 generated
+//! ```
+
+use std::fmt;
+use std::path::Path;
+
+use crate::assembler::{AssembleResult, ListingEntry};
+use crate::parser::{parse_line, ParsedLine};
+use crate::source::{is_literate_file, markdown_headings};
+use crate::stdlib::embedded_source;
+
+/// Line and text of each heading in a Markdown file.
+type Headings = Vec<(usize, String)>;
+
+/// Emitted bytes split into instructions and data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ByteCounts {
+    /// Bytes of encoded instructions, including expanded pseudo-ops.
+    pub code: usize,
+    /// Bytes emitted by data directives.
+    pub data: usize,
+}
+
+impl ByteCounts {
+    /// Code and data bytes together.
+    #[must_use]
+    pub const fn total(self) -> usize {
+        self.code + self.data
+    }
+
+    /// Share of the bytes that are code, as a whole percentage rounded down.
+    #[must_use]
+    pub const fn code_percent(self) -> usize {
+        match self.total() {
+            0 => 0,
+            total => self.code * 100 / total,
+        }
+    }
+
+    fn add(&mut self, entry: &ListingEntry) {
+        if is_data(entry) {
+            self.data += entry.bytes.len();
+        } else {
+            self.code += entry.bytes.len();
+        }
+    }
+}
+
+/// Bytes emitted under one Markdown heading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionStats {
+    /// Heading text, or `None` for bytes before the first heading.
+    pub heading: Option<String>,
+    /// 1-indexed line of the heading, or 0 before the first heading.
+    pub line: usize,
+    /// Bytes emitted from the section's code blocks.
+    pub bytes: ByteCounts,
+}
+
+/// Bytes emitted from one source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStats {
+    /// Source file as named in the listing.
+    pub file: String,
+    /// Bytes emitted from the file.
+    pub bytes: ByteCounts,
+    /// Sections that emitted bytes, in document order. Empty for plain
+    /// assembly files.
+    pub sections: Vec<SectionStats>,
+}
+
+/// Byte and symbol totals for an assembled program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssemblyStats {
+    /// Files that emitted bytes, in the order they first appear in the
+    /// listing.
+    pub files: Vec<FileStats>,
+    /// Bytes of the whole program.
+    pub total: ByteCounts,
+    /// Labels defined by the source.
+    pub labels: usize,
+    /// Local labels the assembler generated for pseudo-op expansions.
+    pub generated_labels: usize,
+}
+
+/// Totals the bytes of `result` per file and per Markdown section, reading
+/// literate sources from disk to find their headings.
+#[must_use]
+pub fn assembly_stats(result: &AssembleResult) -> AssemblyStats {
+    let mut files: Vec<(FileStats, Option<Headings>)> = Vec::new();
+    let mut total = ByteCounts::default();
+    for entry in result
+        .listing
+        .iter()
+        .filter(|entry| !entry.bytes.is_empty())
+    {
+        total.add(entry);
+        let index = files
+            .iter()
+            .position(|(stats, _)| stats.file == entry.file)
+            .unwrap_or_else(|| {
+                let headings = is_literate_file(Path::new(&entry.file)).then(|| {
+                    let path = Path::new(&entry.file);
+                    embedded_source(path).map_or_else(
+                        || {
+                            std::fs::read_to_string(path)
+                                .map(|content| markdown_headings(&content))
+                                .unwrap_or_default()
+                        },
+                        markdown_headings,
+                    )
+                });
+                files.push((
+                    FileStats {
+                        file: entry.file.clone(),
+                        bytes: ByteCounts::default(),
+                        sections: Vec::new(),
+                    },
+                    headings,
+                ));
+                files.len() - 1
+            });
+        let (stats, headings) = &mut files[index];
+        stats.bytes.add(entry);
+        if let Some(headings) = headings {
+            section_for(&mut stats.sections, headings, entry.line).add(entry);
+        }
+    }
+
+    let generated_labels = result
+        .symbols
+        .keys()
+        .filter(|name| name.starts_with('.'))
+        .count();
+    AssemblyStats {
+        files: files.into_iter().map(|(stats, _)| stats).collect(),
+        total,
+        labels: result.symbols.len() - generated_labels,
+        generated_labels,
+    }
+}
+
+/// The counts of the section containing `line`, adding the section in
+/// document order if it has not emitted bytes yet.
+fn section_for<'a>(
+    sections: &'a mut Vec<SectionStats>,
+    headings: &[(usize, String)],
+    line: usize,
+) -> &'a mut ByteCounts {
+    let (heading_line, heading) = headings
+        .iter()
+        .take_while(|(heading_line, _)| *heading_line <= line)
+        .last()
+        .map_or((0, None), |(heading_line, text)| {
+            (*heading_line, Some(text))
+        });
+    let index = match sections.binary_search_by_key(&heading_line, |section| section.line) {
+        Ok(index) => index,
+        Err(index) => {
+            sections.insert(
+                index,
+                SectionStats {
+                    heading: heading.cloned(),
+                    line: heading_line,
+                    bytes: ByteCounts::default(),
+                },
+            );
+            index
+        }
+    };
+    &mut sections[index].bytes
+}
+
+/// Directive lines emit data; anything else that emits bytes is code,
+/// including expanded pseudo-op lines the parser rejects.
+fn is_data(entry: &ListingEntry) -> bool {
+    matches!(
+        parse_line(&entry.source, entry.line),
+        Ok(ParsedLine::Directive { .. })
+    )
+}
+
+impl fmt::Display for AssemblyStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let row = |f: &mut fmt::Formatter<'_>, bytes: ByteCounts, name: &str| {
+            writeln!(
+                f,
+                "{:>8} {:>8} {:>8}  {name}",
+                bytes.total(),
+                bytes.code,
+                bytes.data
+            )
+        };
+        writeln!(f, "{:>8} {:>8} {:>8}  source", "bytes", "code", "data")?;
+        for file in &self.files {
+            row(f, file.bytes, &file.file)?;
+            for section in &file.sections {
+                let name = section.heading.as_ref().map_or_else(
+                    || "  (before first heading)".to_string(),
+                    |heading| format!("  {heading} (line {})", section.line),
+                );
+                row(f, section.bytes, &name)?;
+            }
+        }
+        row(
+            f,
+            self.total,
+            &format!("total, {}% code", self.total.code_percent()),
+        )?;
+        write!(
+            f,
+            "symbols: {} labels, {} generated",
+            self.labels, self.generated_labels
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::{assemble, assemble_from_source};
+
+    #[test]
+    fn plain_sources_split_code_and_data() {
+        let result = assemble_from_source(
+            "main:\n    MOV R0, #1\n    HALT\ntable:\n    .word 0x1234\n    .word 0x5678\n",
+            "stats.n1",
+        )
+        .unwrap();
+        let stats = assembly_stats(&result);
+        assert_eq!(stats.files.len(), 1);
+        assert_eq!(stats.files[0].file, "stats.n1");
+        assert_eq!(stats.files[0].sections, []);
+        assert_eq!(stats.total, ByteCounts { code: 6, data: 4 });
+        assert_eq!(stats.total.code_percent(), 60);
+        assert_eq!((stats.labels, stats.generated_labels), (2, 0));
+        assert!(stats.to_string().ends_with(
+            "      10        6        4  total, 60% code\nsymbols: 2 labels, 0 generated"
+        ));
+    }
+
+    #[test]
+    fn literate_sources_count_bytes_per_section() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("game.n1.md");
+        std::fs::write(
+            &path,
+            "\
+```n1asm
+    NOP
+```
+
+# Setup
+
+```n1asm
+main:
+    MOV R0, #1
+```
+
+# Notes
+
+No code here.
+
+## Data
+
+```n1asm
+    HALT
+table:
+    .word 0x1234
+```
+",
+        )
+        .unwrap();
+
+        let stats = assembly_stats(&assemble(&path).unwrap());
+        let sections: Vec<_> = stats.files[0]
+            .sections
+            .iter()
+            .map(|section| (section.heading.as_deref(), section.line, section.bytes))
+            .collect();
+        assert_eq!(
+            sections,
+            [
+                (None, 0, ByteCounts { code: 2, data: 0 }),
+                (Some("Setup"), 5, ByteCounts { code: 4, data: 0 }),
+                (Some("Data"), 16, ByteCounts { code: 2, data: 2 }),
+            ]
+        );
+        assert_eq!(stats.files[0].bytes.total(), 10);
+    }
+}
@@ -0,0 +1,318 @@
+//! Bundled standard library of verified assembly routines.
+//!
+//! The library ships as literate `.n1.md` modules in the crate's `stdlib/`
+//! directory and is compiled into the assembler, so an installed or relocated
+//! binary needs no files beside it. Include resolution falls back to the
+//! library when a relative path does not exist next to the including file, so
+//! any program can write `.include "print.n1.md"`. Embedded modules appear
+//! under the virtual directory [`EMBEDDED_STDLIB_DIR`] in source paths and
+//! diagnostics.
+//!
+//! Modules contain only routines (no `n1test` blocks), because test blocks of
+//! included files run as part of the including program. Each module instead
+//! has a companion program in `stdlib/tests/` whose inline tests cover every
+//! routine it exports.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use emulator_core::{SERVICES, SERVICE_CONSTANTS};
+
+/// Environment variable naming a directory that replaces the embedded
+/// standard library.
+pub const STDLIB_DIR_ENV: &str = "NULLBYTE_STDLIB_DIR";
+
+/// Virtual directory embedded modules are resolved under.
+pub const EMBEDDED_STDLIB_DIR: &str = "<stdlib>";
+
+/// Include file defining the core's service numbers as `.equ` constants.
+pub const SERVICES_INCLUDE_FILE: &str = "services.n1";
+
+/// Embedded contents of [`SERVICES_INCLUDE_FILE`].
+const SERVICES_INCLUDE_SOURCE: &str = include_str!("../stdlib/services.n1");
+
+/// Description of one bundled standard library module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StdlibModule {
+    /// File name to pass to `.include`.
+    pub file: &'static str,
+    /// Companion test program, relative to the `tests/` subdirectory.
+    pub test_file: &'static str,
+    /// One-line description.
+    pub summary: &'static str,
+    /// Entry-point labels exported by the module.
+    pub routines: &'static [&'static str],
+    /// Module source, embedded at build time.
+    pub source: &'static str,
+}
+
+/// All bundled modules, in listing order.
+pub const STDLIB_MODULES: &[StdlibModule] = &[
+    StdlibModule {
+        file: "print.n1.md",
+        test_file: "print_test.n1.md",
+        summary: "Write packed strings and hex values to a character buffer",
+        routines: &["print_string", "print_hex16", "hex_digit"],
+        source: include_str!("../stdlib/print.n1.md"),
+    },
+    StdlibModule {
+        file: "mem.n1.md",
+        test_file: "mem_test.n1.md",
+        summary: "Word-wise block copy and fill",
+        routines: &["memcpy", "memset"],
+        source: include_str!("../stdlib/mem.n1.md"),
+    },
+    StdlibModule {
+        file: "delay.n1.md",
+        test_file: "delay_test.n1.md",
+        summary: "Deterministic busy-wait loops",
+        routines: &["delay_loop"],
+        source: include_str!("../stdlib/delay.n1.md"),
+    },
+    StdlibModule {
+        file: "decimal.n1.md",
+        test_file: "decimal_test.n1.md",
+        summary: "Packed-BCD add and binary/BCD conversion with carry out",
+        routines: &["bcd_add", "bcd_to_bin", "bin_to_bcd"],
+        source: include_str!("../stdlib/decimal.n1.md"),
+    },
+    StdlibModule {
+        file: "handlers.n1.md",
+        test_file: "handlers_test.n1.md",
+        summary: "Register save/restore and a recording default fault handler",
+        routines: &["default_fault_handler", "handler_save", "handler_restore"],
+        source: include_str!("../stdlib/handlers.n1.md"),
+    },
+    StdlibModule {
+        file: "swi.n1.md",
+        test_file: "swi_test.n1.md",
+        summary: "SWI handler for the core's service numbers",
+        routines: &["swi_dispatch"],
+        source: include_str!("../stdlib/swi.n1.md"),
+    },
+];
+
+/// Returns the directory set by `NULLBYTE_STDLIB_DIR`, if any.
+#[must_use]
+pub fn stdlib_override() -> Option<PathBuf> {
+    std::env::var_os(STDLIB_DIR_ENV).map(PathBuf::from)
+}
+
+/// Resolves `include` as a standard library file: from the
+/// `NULLBYTE_STDLIB_DIR` directory when set, otherwise as an embedded module
+/// under [`EMBEDDED_STDLIB_DIR`].
+#[must_use]
+pub fn resolve_stdlib(include: &Path) -> Option<PathBuf> {
+    if let Some(dir) = stdlib_override() {
+        let path = dir.join(include);
+        return path.exists().then_some(path);
+    }
+    let name = include.to_str()?;
+    embedded_file(name).map(|_| Path::new(EMBEDDED_STDLIB_DIR).join(name))
+}
+
+/// Returns the embedded contents of `path` when it names a file under
+/// [`EMBEDDED_STDLIB_DIR`].
+#[must_use]
+pub fn embedded_source(path: &Path) -> Option<&'static str> {
+    let name = path.strip_prefix(EMBEDDED_STDLIB_DIR).ok()?.to_str()?;
+    embedded_file(name)
+}
+
+fn embedded_file(name: &str) -> Option<&'static str> {
+    if name == SERVICES_INCLUDE_FILE {
+        return Some(SERVICES_INCLUDE_SOURCE);
+    }
+    find_module(name).map(|module| module.source)
+}
+
+/// Looks up a module by its include file name.
+#[must_use]
+pub fn find_module(file: &str) -> Option<&'static StdlibModule> {
+    STDLIB_MODULES.iter().find(|module| module.file == file)
+}
+
+/// Formats the module listing printed by `nullbyte-asm --list-stdlib`.
+#[must_use]
+pub fn format_module_listing() -> String {
+    let mut out = stdlib_override().map_or_else(
+        || String::from("Standard library (embedded):\n"),
+        |dir| format!("Standard library ({}):\n", dir.display()),
+    );
+    for module in STDLIB_MODULES {
+        let _ = writeln!(out, "  {:<12} {}", module.file, module.summary);
+        let _ = writeln!(out, "  {:<12} routines: {}", "", module.routines.join(", "));
+    }
+    out
+}
+
+/// Generates the contents of [`SERVICES_INCLUDE_FILE`] from the core's
+/// service table, as printed by `nullbyte-asm --services-include`.
+#[must_use]
+pub fn services_include() -> String {
+    let mut out = String::from(
+        "; Service numbers for SWI, generated from emulator-core.\n\
+         ; Regenerate with `nullbyte-asm --services-include`; do not edit.\n\
+         ; Load the number into R1, arguments into R2-R3; status returns in R0.\n",
+    );
+    for (name, value) in SERVICE_CONSTANTS {
+        let _ = writeln!(out, ".equ {name}, 0x{value:04X}");
+    }
+    for service in SERVICES {
+        let _ = writeln!(
+            out,
+            ".equ {}, 0x{:04X} ; {}",
+            service.name, service.number, service.summary
+        );
+    }
+    out
+}
+
+/// Returns the `stdlib/` directory in the source checkout, where the
+/// companion test programs live.
+#[cfg(test)]
+pub(crate) fn checkout_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("stdlib")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+    use crate::test_format::parse_source_test_block;
+    use crate::test_runner::run_tests;
+    use std::fs;
+
+    #[test]
+    fn every_module_and_test_program_exists() {
+        let dir = checkout_dir();
+        for module in STDLIB_MODULES {
+            assert_eq!(
+                fs::read_to_string(dir.join(module.file)).unwrap(),
+                module.source
+            );
+            assert!(
+                dir.join("tests").join(module.test_file).is_file(),
+                "missing {}",
+                module.test_file
+            );
+        }
+    }
+
+    #[test]
+    fn modules_define_their_routines_without_tests() {
+        for module in STDLIB_MODULES {
+            let path = checkout_dir().join(module.file);
+            for routine in module.routines {
+                assert!(
+                    module.source.contains(&format!("\n{routine}:")),
+                    "{} does not define {routine}",
+                    module.file
+                );
+            }
+
+            let result = assemble(&path).unwrap();
+            assert!(!result.binary.is_empty());
+            assert!(
+                result.test_blocks.is_empty(),
+                "{} must not contain n1test blocks",
+                module.file
+            );
+        }
+    }
+
+    #[test]
+    fn module_test_programs_pass() {
+        for module in STDLIB_MODULES {
+            let path = checkout_dir().join("tests").join(module.test_file);
+            let result = assemble(&path).unwrap();
+            let blocks: Vec<_> = result
+                .test_blocks
+                .iter()
+                .map(|tbc| parse_source_test_block(&tbc.block).unwrap())
+                .collect();
+            assert!(!blocks.is_empty(), "{} has no tests", module.test_file);
+
+            let run = run_tests(&result.binary, &blocks);
+            assert!(
+                run.all_passed(),
+                "{} failed: {}",
+                module.test_file,
+                run.summary()
+            );
+        }
+    }
+
+    #[test]
+    fn services_include_matches_core_table() {
+        let path = checkout_dir().join(SERVICES_INCLUDE_FILE);
+        assert_eq!(
+            SERVICES_INCLUDE_SOURCE,
+            services_include(),
+            "{SERVICES_INCLUDE_FILE} is stale; regenerate it with --services-include"
+        );
+
+        let result = assemble(&path).unwrap();
+        assert!(result.binary.is_empty());
+        for service in SERVICES {
+            assert_eq!(result.constants.get(service.name), Some(&service.number));
+        }
+    }
+
+    #[test]
+    fn include_falls_back_to_stdlib() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("main.n1");
+        fs::write(&path, "CALL #memset\nHALT\n.include \"mem.n1.md\"\n").unwrap();
+
+        let result = assemble(&path).unwrap();
+        assert!(result.binary.len() > 6);
+        assert_eq!(result.sources[1], Path::new("<stdlib>/mem.n1.md"));
+    }
+
+    #[test]
+    fn embedded_modules_resolve_without_the_checkout() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("main.n1");
+        fs::write(&path, "MOV R1, #SVC_EXIT\n.include \"swi.n1.md\"\n").unwrap();
+
+        let result = assemble(&path).unwrap();
+        assert_eq!(
+            result.sources[1..],
+            [
+                PathBuf::from("<stdlib>/swi.n1.md"),
+                PathBuf::from("<stdlib>/services.n1")
+            ]
+        );
+        assert_eq!(
+            embedded_source(Path::new("<stdlib>/services.n1")),
+            Some(services_include().as_str())
+        );
+        assert!(embedded_source(Path::new("<stdlib>/nope.n1")).is_none());
+        assert!(embedded_source(&checkout_dir().join("mem.n1.md")).is_none());
+    }
+
+    #[test]
+    fn local_file_shadows_stdlib_module() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join("mem.n1.md"), "```n1asm\nNOP\n```\n").unwrap();
+        let path = temp_dir.path().join("main.n1");
+        fs::write(&path, ".include \"mem.n1.md\"\n").unwrap();
+
+        let result = assemble(&path).unwrap();
+        assert_eq!(result.binary, &[0x00, 0x00]);
+    }
+
+    #[test]
+    fn listing_names_every_module() {
+        let listing = format_module_listing();
+        for module in STDLIB_MODULES {
+            assert!(listing.contains(module.file));
+        }
+        assert_eq!(
+            find_module("mem.n1.md").unwrap().routines,
+            ["memcpy", "memset"]
+        );
+        assert!(find_module("nope.n1.md").is_none());
+    }
+}
@@ -0,0 +1,202 @@
+//! Opt-in string table deduplication.
+//!
+//! A *string run* is one or more labels followed by consecutive data
+//! directives (`.ascii`, `.tstring`, `.byte`, `.word`, `.twchar`) of which at
+//! least one is a string, e.g. `msg: .ascii "READY"` followed by a `.byte 0`
+//! terminator. When a run's directives are identical to an earlier run, its
+//! directives are removed and its labels alias the earlier run's first label,
+//! so the bytes are stored once in ROM.
+//!
+//! Like the peephole optimizer, removed lines become [`ParsedLine::Blank`] so
+//! line indices still match the expanded source. Labels stay in place and are
+//! re-pointed with [`apply_aliases`] after pass 1.
+//!
+//! Shared bytes must be read-only: a program that writes into one copy would
+//! see the change through every alias.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::parser::{Directive, ParsedLine};
+use crate::symbols::{line_size, SymbolTable};
+
+/// A string run removed in favour of an earlier identical run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringDedup {
+    /// Index of the run's first label in the parsed line list.
+    pub index: usize,
+    /// Labels of the removed run.
+    pub labels: Vec<String>,
+    /// Label of the run the removed labels now alias.
+    pub alias_of: String,
+    /// ROM bytes saved.
+    pub bytes_saved: usize,
+}
+
+impl core::fmt::Display for StringDedup {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{} shares string data with {} ({} byte(s) saved)",
+            self.labels.join(", "),
+            self.alias_of,
+            self.bytes_saved
+        )
+    }
+}
+
+/// Removes duplicate string runs from `lines` and returns what was removed,
+/// in line order.
+#[must_use]
+pub fn dedup_strings(lines: &mut [ParsedLine]) -> Vec<StringDedup> {
+    let mut seen: Vec<(Vec<Directive>, String)> = Vec::new();
+    let mut removed = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let Some(run) = string_run_at(lines, index) else {
+            index += 1;
+            continue;
+        };
+        index = run.end;
+
+        let Some((_, alias_of)) = seen
+            .iter()
+            .find(|(directives, _)| *directives == run.directives)
+        else {
+            let label = run.labels[0].clone();
+            seen.push((run.directives, label));
+            continue;
+        };
+
+        let bytes_saved = run
+            .data_lines
+            .iter()
+            .map(|&line| usize::from(line_size(&lines[line])))
+            .sum();
+        for &line in &run.data_lines {
+            lines[line] = ParsedLine::Blank;
+        }
+        removed.push(StringDedup {
+            index: run.start,
+            labels: run.labels,
+            alias_of: alias_of.clone(),
+            bytes_saved,
+        });
+    }
+
+    removed
+}
+
+/// Re-points the labels of every removed run at the run it now shares.
+pub fn apply_aliases(symbols: &mut SymbolTable, dedups: &[StringDedup]) {
+    for dedup in dedups {
+        let Some(address) = symbols.get(&dedup.alias_of).map(|symbol| symbol.address) else {
+            continue;
+        };
+        for label in &dedup.labels {
+            if let Some(symbol) = symbols.get_mut(label) {
+                symbol.address = address;
+            }
+        }
+    }
+}
+
+/// A candidate run found in the parsed line list.
+struct StringRun {
+    start: usize,
+    end: usize,
+    labels: Vec<String>,
+    directives: Vec<Directive>,
+    data_lines: Vec<usize>,
+}
+
+/// Returns the string run starting at `start`, if there is one.
+fn string_run_at(lines: &[ParsedLine], start: usize) -> Option<StringRun> {
+    let mut run = StringRun {
+        start,
+        end: start,
+        labels: Vec::new(),
+        directives: Vec::new(),
+        data_lines: Vec::new(),
+    };
+
+    while let Some(line) = lines.get(run.end) {
+        match line {
+            ParsedLine::Label { name } if run.directives.is_empty() => {
+                run.labels.push(name.clone());
+            }
+            ParsedLine::Blank => {}
+            ParsedLine::Directive { directive } if is_run_data(directive) => {
+                run.directives.push(directive.clone());
+                run.data_lines.push(run.end);
+            }
+            _ => break,
+        }
+        run.end += 1;
+    }
+
+    let has_string = run
+        .directives
+        .iter()
+        .any(|directive| matches!(directive, Directive::Ascii(_) | Directive::TString(_)));
+    (!run.labels.is_empty() && has_string).then_some(run)
+}
+
+const fn is_run_data(directive: &Directive) -> bool {
+    matches!(
+        directive,
+        Directive::Ascii(_)
+            | Directive::TString(_)
+            | Directive::Byte(_)
+            | Directive::Word(_)
+            | Directive::TwChar(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_line;
+    use crate::symbols::assign_addresses;
+
+    fn parse(source: &str) -> Vec<ParsedLine> {
+        source
+            .lines()
+            .enumerate()
+            .map(|(i, line)| parse_line(line, i + 1).expect("test source should parse"))
+            .collect()
+    }
+
+    #[test]
+    fn identical_runs_are_stored_once() {
+        let mut lines = parse(
+            "a:\n.ascii \"HELLO\"\n.byte 0\nb:\n.ascii \"WORLD\"\n.byte 0\nc:\n.ascii \"HELLO\"\n.byte 0",
+        );
+        let dedups = dedup_strings(&mut lines);
+
+        assert_eq!(dedups.len(), 1);
+        assert_eq!(dedups[0].index, 6);
+        assert_eq!(dedups[0].labels, vec!["c".to_string()]);
+        assert_eq!(dedups[0].alias_of, "a");
+        assert_eq!(dedups[0].bytes_saved, 6);
+        assert_eq!(lines[7], ParsedLine::Blank);
+        assert_eq!(lines[8], ParsedLine::Blank);
+
+        let mut symbols = assign_addresses(&lines, 0).unwrap().symbols;
+        apply_aliases(&mut symbols, &dedups);
+        assert_eq!(symbols["c"].address, symbols["a"].address);
+        assert_eq!(symbols["b"].address, 6);
+    }
+
+    #[test]
+    fn different_terminators_are_not_merged() {
+        let mut lines = parse("a:\n.ascii \"HI\"\n.byte 0\nb:\n.ascii \"HI\"\n.byte 10");
+        assert!(dedup_strings(&mut lines).is_empty());
+    }
+
+    #[test]
+    fn runs_without_labels_or_strings_are_ignored() {
+        let mut lines = parse(".ascii \"HI\"\n.ascii \"HI\"\na:\n.word 1\nb:\n.word 1");
+        assert!(dedup_strings(&mut lines).is_empty());
+    }
+}
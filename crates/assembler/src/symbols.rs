@@ -4,9 +4,10 @@
 //! assigning addresses to each instruction/datum, and building a symbol table
 //! of label definitions.
 
-use std::collections::HashMap;
+use alloc::collections::BTreeMap;
+use alloc::{string::String, vec::Vec};
 
-use crate::parser::{Directive, InstructionSize, ParsedLine};
+use crate::parser::{Directive, InstructionSize, Operand, ParsedLine};
 
 /// A symbol (label) with its assigned address and definition location.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -18,7 +19,7 @@ pub struct Symbol {
 }
 
 /// Symbol table mapping label names to their definitions.
-pub type SymbolTable = HashMap<String, Symbol>;
+pub type SymbolTable = BTreeMap<String, Symbol>;
 
 /// Error during symbol table construction.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -53,14 +54,14 @@ pub enum SymbolErrorKind {
     },
 }
 
-impl std::fmt::Display for SymbolError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for SymbolError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", self.kind)
     }
 }
 
-impl std::fmt::Display for SymbolErrorKind {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for SymbolErrorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::DuplicateLabel {
                 name,
@@ -87,7 +88,7 @@ impl std::fmt::Display for SymbolErrorKind {
     }
 }
 
-impl std::error::Error for SymbolError {}
+impl core::error::Error for SymbolError {}
 
 /// A line with its assigned address.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -107,8 +108,10 @@ pub struct AddressedLine {
 pub struct Assignment {
     /// All lines with their assigned addresses.
     pub lines: Vec<AddressedLine>,
-    /// Symbol table of label definitions.
+    /// Symbol table of label definitions, including `.equ` constants.
     pub symbols: SymbolTable,
+    /// Names and values defined by `.equ`.
+    pub constants: BTreeMap<String, u16>,
     /// Final address after all content (one past the last byte).
     pub end_address: u16,
 }
@@ -120,6 +123,8 @@ pub struct Assignment {
 /// - `.byte`: 1 byte
 /// - `.ascii`: string length in bytes
 /// - `.zero`: count bytes
+/// - `.incbin`: length of the resolved file range
+/// - `.word sizeof(...)`: 2 bytes
 /// - `.org`: 0 bytes (affects position counter only)
 /// - Labels/blank: 0 bytes
 #[must_use]
@@ -137,11 +142,19 @@ pub const fn line_size(parsed: &ParsedLine) -> u16 {
 #[allow(clippy::cast_possible_truncation)]
 const fn directive_size(directive: &Directive) -> u16 {
     match directive {
-        Directive::Org(_) | Directive::Include(_) => 0,
-        Directive::Word(_) | Directive::TwChar(_) => 2,
+        Directive::Org(_)
+        | Directive::Include(_)
+        | Directive::Entry(_)
+        | Directive::Info(..)
+        | Directive::PseudoOp(_)
+        | Directive::Equ(..)
+        | Directive::StandardLayout
+        | Directive::BlockOp(_) => 0,
+        Directive::Word(_) | Directive::TwChar(_) | Directive::LiteralWord(_) => 2,
         Directive::Byte(_) => 1,
         Directive::Ascii(s) => s.len() as u16,
         Directive::Zero(count) => *count as u16,
+        Directive::IncBin(ops) => ops.data.len() as u16,
         Directive::TString(ops) => {
             let char_count = ops.text.len();
             let padded = if let Some(min) = ops.min_chars {
@@ -196,6 +209,7 @@ pub fn assign_addresses_with_lines(
     source_lines: &[usize],
 ) -> Result<Assignment, SymbolError> {
     let mut symbols = SymbolTable::new();
+    let mut constants = BTreeMap::new();
     let mut addressed = Vec::with_capacity(lines.len());
     let mut pc: u32 = u32::from(start_address);
 
@@ -204,7 +218,22 @@ pub fn assign_addresses_with_lines(
         let size = u32::from(line_size(parsed));
         let line_address = pc as u16;
 
-        if let ParsedLine::Label { name } = parsed {
+        let definition = match parsed {
+            ParsedLine::Label { name } => Some((name, line_address)),
+            // Repeating a constant with its own value is allowed, so several
+            // files can include the same `.equ` list.
+            ParsedLine::Directive {
+                directive: Directive::Equ(name, value),
+            } if constants.get(name) == Some(value) => None,
+            ParsedLine::Directive {
+                directive: Directive::Equ(name, value),
+            } => {
+                constants.insert(name.clone(), *value);
+                Some((name, *value))
+            }
+            _ => None,
+        };
+        if let Some((name, address)) = definition {
             if let Some(existing) = symbols.get(name) {
                 return Err(SymbolError {
                     kind: SymbolErrorKind::DuplicateLabel {
@@ -217,7 +246,7 @@ pub fn assign_addresses_with_lines(
             symbols.insert(
                 name.clone(),
                 Symbol {
-                    address: line_address,
+                    address,
                     defined_at: source_line,
                 },
             );
@@ -257,13 +286,36 @@ pub fn assign_addresses_with_lines(
         }
     }
 
+    for line in &mut addressed {
+        resolve_constant(&mut line.parsed, &constants);
+    }
+
     Ok(Assignment {
         lines: addressed,
         symbols,
+        constants,
         end_address: pc as u16,
     })
 }
 
+/// Turns a label immediate naming a `.equ` constant into a plain immediate.
+///
+/// Label immediates encode PC-relative, but a constant is a value rather
+/// than an address, so it must reach the encoder as an absolute number.
+fn resolve_constant(parsed: &mut ParsedLine, constants: &BTreeMap<String, u16>) {
+    let ParsedLine::Instruction { instruction } = parsed else {
+        return;
+    };
+    let Some(Operand::Immediate(imm)) = &mut instruction.operand else {
+        return;
+    };
+    if let Some(&value) = imm.label_name.as_ref().and_then(|name| constants.get(name)) {
+        imm.value = i64::from(value);
+        imm.is_label = false;
+        imm.label_name = None;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
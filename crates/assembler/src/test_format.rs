@@ -6,6 +6,38 @@
 //!
 //! - Register assertions: `R0 == 0x4000`, `PC != 0x0000`
 //! - Memory assertions: `[0x4000] == 0xFF`, `[0x1000] != 0x00`
+//! - Console assertions: `console == "OK\n"`, comparing the text written to
+//!   the debug console while the block ran (escapes `\n`, `\t`, `\"`,
+//!   `\\` and `\xNN`)
+//! - Exit assertions: `exit == 0`, comparing the status the block wrote to
+//!   the debug console's EXIT register
+//! - Fault record assertions: `fault.cause == IllegalEncoding`,
+//!   `fault.pc == 0x0104`, `fault.flags` and `fault.count`, reading the
+//!   record the standard library's `default_fault_handler` keeps at
+//!   [`FAULT_RECORD`]
+//! - Path assertions: `executed label init_display` and
+//!   `never executed label debug_stub` check whether an instruction at a
+//!   label retired while the block ran; `retired <= 500 instructions`
+//!   bounds how many did (`<=`, `>=` or `==`, counting the HALT)
+//! - Invariants: `every tick assert [0xE100] != 0x00` checks a register,
+//!   memory or fault record assertion at every tick boundary while the
+//!   block runs, not just at its HALT
+//! - Setup: `start at label` (or an address) and `sp = 0xFF00`, applied
+//!   before the block runs
+//! - Parameters: `param level = 3` and `param name = "ACE"` fill the host
+//!   parameter block before the block runs (see [`push_param`])
+//! - Events: `at tick 3 enqueue event 0x07`, delivered by the runner at the
+//!   start of the block's fourth tick
+//! - Metadata: `reset: true` runs the block on a freshly reset machine;
+//!   `fixture: boot.n1snap` starts it from a saved snapshot and
+//!   `capture: boot.n1snap` saves the state after it (with `--capture-fixture`);
+//!   `frozen: R4-R7` lists registers the block must not change (checked
+//!   with `--strict`)
+//! - Triage: `skip: true` and `only: true` exclude a block or narrow the run
+//!   to marked blocks; `tags: slow, display` labels it for `--tag` and
+//!   `--skip-tag` filters
+//! - Screen: an `n1expect-screen` block after the test block holds the
+//!   TELE-7 text expected at its HALT (see [`parse_source_test_block`])
 //! - Comments: `;` to end of line
 //! - Literals: decimal, `0x` hex, `0b` binary
 
@@ -21,6 +53,10 @@
 
 use std::fmt;
 
+use emulator_core::{FaultCode, ParamBlock};
+
+use crate::source::TestBlock;
+
 /// A parsed assertion from an `n1test` block.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Assertion {
@@ -42,6 +78,84 @@ pub enum Assertion {
         /// The expected byte value.
         expected: u8,
     },
+    /// Assert the text written to the debug console while the block ran
+    /// equals or not-equals expected.
+    Console {
+        /// The comparison operator.
+        operator: ComparisonOp,
+        /// The expected console text, with escapes decoded.
+        expected: String,
+    },
+    /// Assert the exit status the block wrote to the debug console equals or
+    /// not-equals expected. Fails either way when the block never exited.
+    Exit {
+        /// The comparison operator.
+        operator: ComparisonOp,
+        /// The expected exit status.
+        expected: u8,
+    },
+    /// Assert a field of the fault record written by the standard
+    /// library's `default_fault_handler` equals or not-equals expected.
+    FaultRecord {
+        /// The record field to check.
+        field: FaultField,
+        /// The comparison operator.
+        operator: ComparisonOp,
+        /// The expected word.
+        expected: u16,
+    },
+    /// Assert whether an instruction at a label retired while the block
+    /// ran: `executed label name` or `never executed label name`.
+    Executed {
+        /// The label whose instruction is checked.
+        label: String,
+        /// True for `executed`, false for `never executed`.
+        expected: bool,
+    },
+    /// Assert how many instructions retired while the block ran, including
+    /// its HALT: `retired <= 500 instructions`.
+    Retired {
+        /// The bound's comparison.
+        operator: CountOp,
+        /// The instruction count compared against.
+        expected: u64,
+    },
+    /// Assert a `frozen:` register still holds the value it had when the
+    /// block started. Built by the runner in strict mode, never parsed.
+    Frozen {
+        /// The register that must not change.
+        register: Register,
+        /// Its value when the block started.
+        expected: u16,
+    },
+}
+
+/// Machine state a test block sets before it starts running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestSetup {
+    /// `start at ...`: move PC so the block exercises one routine directly.
+    StartAt(StartTarget),
+    /// `sp = value`: initialise the stack pointer.
+    StackPointer(u16),
+}
+
+/// An event the runner enqueues at a tick boundary while a block runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledEvent {
+    /// Tick, counted from 0 at the start of the block, before which the
+    /// event is enqueued.
+    pub tick: u16,
+    /// 8-bit event identifier.
+    pub event_id: u8,
+}
+
+/// Where a `start at` setup line moves PC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartTarget {
+    /// A label, resolved against the program's symbol table when run.
+    Label(String),
+    /// A literal address.
+    Address(u16),
 }
 
 /// A register that can be asserted.
@@ -67,6 +181,37 @@ pub enum Register {
     PC,
 }
 
+/// Address of the fault record kept by `default_fault_handler` in the
+/// standard library's `handlers.n1.md`.
+pub const FAULT_RECORD: u16 = 0xDF00;
+
+/// A word of the fault record at [`FAULT_RECORD`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultField {
+    /// CAUSE of the last recorded fault.
+    Cause,
+    /// PC pushed by fault dispatch.
+    Pc,
+    /// FLAGS when the fault happened.
+    Flags,
+    /// Number of faults recorded.
+    Count,
+}
+
+impl FaultField {
+    /// Address of the field's big-endian word.
+    #[must_use]
+    pub const fn address(self) -> u16 {
+        FAULT_RECORD
+            + match self {
+                FaultField::Cause => 0,
+                FaultField::Pc => 2,
+                FaultField::Flags => 4,
+                FaultField::Count => 6,
+            }
+    }
+}
+
 /// Comparison operator for assertions.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ComparisonOp {
@@ -85,11 +230,74 @@ impl fmt::Display for ComparisonOp {
     }
 }
 
+/// Comparison for a `retired` instruction count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountOp {
+    /// At most (`<=`).
+    AtMost,
+    /// At least (`>=`).
+    AtLeast,
+    /// Exactly (`==`).
+    Exactly,
+}
+
+impl CountOp {
+    /// Whether `actual` satisfies the bound `expected`.
+    #[must_use]
+    pub const fn holds(self, actual: u64, expected: u64) -> bool {
+        match self {
+            CountOp::AtMost => actual <= expected,
+            CountOp::AtLeast => actual >= expected,
+            CountOp::Exactly => actual == expected,
+        }
+    }
+}
+
+impl fmt::Display for CountOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CountOp::AtMost => write!(f, "<="),
+            CountOp::AtLeast => write!(f, ">="),
+            CountOp::Exactly => write!(f, "=="),
+        }
+    }
+}
+
 /// A parsed test block with its assertions and source location.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParsedTestBlock {
+    /// Setup lines in order.
+    pub setup: Vec<TestSetup>,
+    /// `at tick N enqueue event ID` lines in source order.
+    pub events: Vec<ScheduledEvent>,
+    /// `reset: true`: run this block on a freshly reset machine with the
+    /// binary reloaded.
+    pub reset: bool,
+    /// `fixture: path`: snapshot file loaded before this block runs.
+    pub fixture: Option<String>,
+    /// `capture: path`: snapshot file to write after this block when
+    /// fixture capture is enabled.
+    pub capture: Option<String>,
+    /// `frozen: R4-R7`: registers the block must leave unchanged, checked
+    /// when the runner is strict.
+    pub frozen: Vec<Register>,
+    /// `skip: true`: run the block to its HALT without checking it.
+    pub skip: bool,
+    /// `only: true`: when any block is marked, unmarked blocks are skipped.
+    pub only: bool,
+    /// `tags: a, b`: labels matched by the runner's tag filters.
+    pub tags: Vec<String>,
+    /// Expected TELE-7 screen text from the following `n1expect-screen`
+    /// block.
+    pub screen: Option<String>,
+    /// `param key = value` lines: the host parameter block written before
+    /// the block runs, when any are given.
+    pub params: ParamBlock,
     /// The parsed assertions in order.
     pub assertions: Vec<Assertion>,
+    /// `every tick assert ...` lines: assertions checked at every tick
+    /// boundary while the block runs, in source order.
+    pub invariants: Vec<Assertion>,
     /// 1-indexed line number where the block starts.
     pub start_line: usize,
     /// 1-indexed line number where the block ends.
@@ -121,8 +329,8 @@ impl std::error::Error for ParseAssertionError {}
 
 /// Parses a test block's content into structured assertions.
 ///
-/// Each non-empty, non-comment line is parsed as an assertion.
-/// Returns a list of assertions or the first parse error encountered.
+/// Each non-empty, non-comment line is parsed as a setup line or an
+/// assertion. Returns the parsed block or the first parse error encountered.
 ///
 /// # Arguments
 ///
@@ -138,7 +346,18 @@ pub fn parse_test_block(
     start_line: usize,
     end_line: usize,
 ) -> Result<ParsedTestBlock, ParseAssertionError> {
+    let mut setup = Vec::new();
+    let mut events = Vec::new();
+    let mut reset = false;
+    let mut fixture = None;
+    let mut capture = None;
+    let mut frozen = Vec::new();
+    let mut skip = false;
+    let mut only = false;
+    let mut tags = Vec::new();
+    let mut params = ParamBlock::new();
     let mut assertions = Vec::new();
+    let mut invariants = Vec::new();
 
     for (idx, line) in content.lines().enumerate() {
         let line_num = idx + 1;
@@ -148,27 +367,341 @@ pub fn parse_test_block(
             continue;
         }
 
-        let assertion = parse_assertion(stripped).map_err(|message| ParseAssertionError {
+        let error = |message| ParseAssertionError {
             line_in_block: line_num,
             text: stripped.to_string(),
             message,
-        })?;
+        };
 
-        assertions.push(assertion);
+        if let Some(value) = parse_bool_metadata(stripped, "reset") {
+            reset = value.map_err(error)?;
+            continue;
+        }
+        if let Some(value) = parse_bool_metadata(stripped, "skip") {
+            skip = value.map_err(error)?;
+            continue;
+        }
+        if let Some(value) = parse_bool_metadata(stripped, "only") {
+            only = value.map_err(error)?;
+            continue;
+        }
+        if let Some(names) = parse_tags(stripped) {
+            tags.extend(names.map_err(error)?);
+            continue;
+        }
+        if let Some(path) = parse_path_metadata(stripped, "fixture") {
+            fixture = Some(path.map_err(error)?);
+            continue;
+        }
+        if let Some(path) = parse_path_metadata(stripped, "capture") {
+            capture = Some(path.map_err(error)?);
+            continue;
+        }
+        if let Some(registers) = parse_frozen(stripped) {
+            for register in registers.map_err(error)? {
+                if !frozen.contains(&register) {
+                    frozen.push(register);
+                }
+            }
+            continue;
+        }
+
+        if let Some(assignment) = strip_keyword(stripped, "param") {
+            push_param(&mut params, assignment).map_err(error)?;
+            continue;
+        }
+
+        if let Some(event) = parse_scheduled_event(stripped) {
+            events.push(event.map_err(error)?);
+            continue;
+        }
+
+        if let Some(invariant) = parse_invariant(stripped) {
+            invariants.push(invariant.map_err(error)?);
+            continue;
+        }
+
+        match parse_setup(stripped) {
+            Some(parsed) => setup.push(parsed.map_err(error)?),
+            None => assertions.push(parse_assertion(stripped).map_err(error)?),
+        }
     }
 
     Ok(ParsedTestBlock {
+        setup,
+        events,
+        reset,
+        fixture,
+        capture,
+        frozen,
+        skip,
+        only,
+        tags,
+        screen: None,
+        params,
         assertions,
+        invariants,
         start_line,
         end_line,
     })
 }
 
-/// Strips a comment from a line (everything from `;` to end of line).
+/// Parses a test block extracted from source, including the expected
+/// screen text of its `n1expect-screen` block.
+///
+/// # Errors
+///
+/// Returns `ParseAssertionError` if any line has invalid syntax.
+pub fn parse_source_test_block(block: &TestBlock) -> Result<ParsedTestBlock, ParseAssertionError> {
+    let mut parsed = parse_test_block(&block.content, block.start_line, block.end_line)?;
+    parsed.screen = block.screen.as_ref().map(|screen| screen.content.clone());
+    Ok(parsed)
+}
+
+/// Parses an event script: `at tick N enqueue event ID` lines, in the
+/// syntax test blocks use, with blank lines and `;` comments ignored.
+///
+/// # Errors
+///
+/// Returns `ParseAssertionError` for the first line that is not an event.
+pub fn parse_event_script(content: &str) -> Result<Vec<ScheduledEvent>, ParseAssertionError> {
+    let mut events = Vec::new();
+    for (idx, line) in content.lines().enumerate() {
+        let stripped = strip_comment(line).trim();
+        if stripped.is_empty() {
+            continue;
+        }
+        let event = parse_scheduled_event(stripped)
+            .unwrap_or_else(|| Err("expected 'at tick N enqueue event ID'".to_string()));
+        events.push(event.map_err(|message| ParseAssertionError {
+            line_in_block: idx + 1,
+            text: stripped.to_string(),
+            message,
+        })?);
+    }
+    Ok(events)
+}
+
+/// Strips a comment from a line (everything from `;` to end of line). A
+/// `;` inside a double-quoted string does not start a comment.
 fn strip_comment(line: &str) -> &str {
-    match line.find(';') {
-        Some(pos) => &line[..pos],
-        None => line,
+    let mut in_string = false;
+    let mut escaped = false;
+    for (pos, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ';' if !in_string => return &line[..pos],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Parses a `key: true|false` metadata line, or returns `None` when
+/// `text` is not one.
+fn parse_bool_metadata(text: &str, key: &str) -> Option<Result<bool, String>> {
+    let lower = text.to_ascii_lowercase();
+    let value = lower.strip_prefix(key)?.trim_start().strip_prefix(':')?;
+    Some(match value.trim() {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        other => Err(format!("expected 'true' or 'false', got '{}'", other)),
+    })
+}
+
+/// Parses a `key: path` metadata line, or returns `None` when `text` is
+/// not one. The key is case-insensitive; the path is kept as written.
+fn parse_path_metadata(text: &str, key: &str) -> Option<Result<String, String>> {
+    let prefix = text.get(..key.len())?;
+    if !prefix.eq_ignore_ascii_case(key) {
+        return None;
+    }
+    let value = text[key.len()..].trim_start().strip_prefix(':')?.trim();
+    Some(if value.is_empty() {
+        Err(format!("expected a snapshot file after '{}:'", key))
+    } else {
+        Ok(value.to_string())
+    })
+}
+
+/// Parses a `tags: slow, display` metadata line, or returns `None` when
+/// `text` is not one.
+fn parse_tags(text: &str) -> Option<Result<Vec<String>, String>> {
+    let prefix = text.get(.."tags".len())?;
+    if !prefix.eq_ignore_ascii_case("tags") {
+        return None;
+    }
+    let value = text["tags".len()..].trim_start().strip_prefix(':')?;
+    Some(
+        value
+            .split(',')
+            .map(|tag| {
+                let tag = tag.trim();
+                if !tag.is_empty()
+                    && tag
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                {
+                    Ok(tag.to_string())
+                } else {
+                    Err(format!(
+                        "tags are letters, digits, '_' and '-', got '{}'",
+                        tag
+                    ))
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Parses a `frozen: R1, R4-R7` metadata line, or returns `None` when
+/// `text` is not one.
+fn parse_frozen(text: &str) -> Option<Result<Vec<Register>, String>> {
+    let prefix = text.get(.."frozen".len())?;
+    if !prefix.eq_ignore_ascii_case("frozen") {
+        return None;
+    }
+    let value = text["frozen".len()..].trim_start().strip_prefix(':')?;
+    Some(
+        value
+            .split(',')
+            .map(parse_register_range)
+            .collect::<Result<Vec<_>, _>>()
+            .map(|ranges| ranges.concat()),
+    )
+}
+
+/// Parses `R4` or `R4-R7` from a `frozen:` line into the registers it names.
+fn parse_register_range(text: &str) -> Result<Vec<Register>, String> {
+    let (first, last) = text.split_once('-').unwrap_or((text, text));
+    let (first, last) = (general_register(first)?, general_register(last)?);
+    if first > last {
+        return Err(format!("register range '{}' is backwards", text.trim()));
+    }
+    Ok(GENERAL_REGISTERS[first..=last].to_vec())
+}
+
+/// General-purpose registers in index order.
+const GENERAL_REGISTERS: [Register; 8] = [
+    Register::R0,
+    Register::R1,
+    Register::R2,
+    Register::R3,
+    Register::R4,
+    Register::R5,
+    Register::R6,
+    Register::R7,
+];
+
+/// Index of a general-purpose register named in a `frozen:` line.
+fn general_register(text: &str) -> Result<usize, String> {
+    let text = text.trim();
+    parse_register(text)
+        .ok()
+        .and_then(|parsed| GENERAL_REGISTERS.iter().position(|r| *r == parsed))
+        .ok_or_else(|| format!("expected a register R0-R7 in 'frozen:', got '{}'", text))
+}
+
+/// Adds a `key = value` parameter to `block`.
+///
+/// A value that parses as a 16-bit literal is stored as a word, a
+/// double-quoted string as its bytes with escapes decoded, and anything
+/// else as its text, so `--param name=ACE` needs no quoting on the command
+/// line.
+///
+/// # Errors
+///
+/// Returns a message when the assignment has no `=`, a quoted value is
+/// malformed, or the block rejects the key or runs out of space.
+pub fn push_param(block: &mut ParamBlock, assignment: &str) -> Result<(), String> {
+    let (key, value) = assignment
+        .split_once('=')
+        .ok_or_else(|| format!("expected 'key = value', got '{}'", assignment.trim()))?;
+    let (key, value) = (key.trim(), value.trim());
+    if value.starts_with('"') {
+        block.push_bytes(key, parse_string_literal(value)?.as_bytes())
+    } else if let Ok(word) = parse_u16(value) {
+        block.push_word(key, word)
+    } else {
+        block.push_bytes(key, value.as_bytes())
+    }
+    .map_err(|e| e.to_string())
+}
+
+/// Parses a setup line, or returns `None` when `text` is not one.
+fn parse_setup(text: &str) -> Option<Result<TestSetup, String>> {
+    let lower = text.to_ascii_lowercase();
+
+    if let Some(target) = lower.strip_prefix("start at") {
+        let target = text[text.len() - target.len()..].trim();
+        return Some(parse_start_target(target).map(TestSetup::StartAt));
+    }
+
+    let rest = lower.strip_prefix("sp")?.trim_start();
+    if !rest.starts_with('=') || rest.starts_with("==") {
+        return None;
+    }
+    Some(parse_u16(&rest[1..]).map(TestSetup::StackPointer))
+}
+
+/// Parses an `at tick N enqueue event ID` line, or returns `None` when
+/// `text` is not one.
+fn parse_scheduled_event(text: &str) -> Option<Result<ScheduledEvent, String>> {
+    let lower = text.to_ascii_lowercase();
+    let rest = lower.strip_prefix("at tick")?;
+    let parts: Vec<&str> = rest.split_whitespace().collect();
+    Some(match parts.as_slice() {
+        [tick, "enqueue", "event", event_id] => parse_u16(tick).and_then(|tick| {
+            Ok(ScheduledEvent {
+                tick,
+                event_id: parse_u8(event_id)?,
+            })
+        }),
+        _ => Err("expected 'at tick N enqueue event ID'".to_string()),
+    })
+}
+
+/// Parses an `every tick assert ...` line, or returns `None` when `text`
+/// is not one.
+///
+/// Console, exit and path assertions describe the whole block, so only
+/// register, memory and fault record assertions can be checked every tick.
+fn parse_invariant(text: &str) -> Option<Result<Assertion, String>> {
+    let rest = strip_keyword(text, "every")?.trim_start();
+    let Some(assertion) = strip_keyword(rest, "tick")
+        .map(str::trim_start)
+        .and_then(|rest| strip_keyword(rest, "assert"))
+    else {
+        return Some(Err("expected 'every tick assert ...'".to_string()));
+    };
+    Some(
+        parse_assertion(assertion).and_then(|assertion| match assertion {
+            Assertion::Console { .. }
+            | Assertion::Exit { .. }
+            | Assertion::Executed { .. }
+            | Assertion::Retired { .. } => {
+                Err("every tick assertions check registers, memory or the fault record".to_string())
+            }
+            assertion => Ok(assertion),
+        }),
+    )
+}
+
+/// Parses the target of a `start at` line: a label or an address.
+fn parse_start_target(text: &str) -> Result<StartTarget, String> {
+    let mut chars = text.chars();
+    match chars.next() {
+        None => Err("expected a label or address after 'start at'".to_string()),
+        Some(c) if c.is_ascii_digit() => parse_u16(text).map(StartTarget::Address),
+        Some(c)
+            if (c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+        {
+            Ok(StartTarget::Label(text.to_string()))
+        }
+        Some(_) => Err(format!("invalid start target '{}'", text)),
     }
 }
 
@@ -178,6 +711,22 @@ fn parse_assertion(text: &str) -> Result<Assertion, String> {
 
     if text.starts_with('[') {
         parse_memory_assertion(text)
+    } else if let Some(rest) = strip_keyword(text, "console") {
+        parse_console_assertion(rest)
+    } else if let Some(rest) = strip_keyword(text, "exit") {
+        let (operator, rest) = parse_comparison_op(rest)?;
+        let expected = parse_u8(rest)?;
+        Ok(Assertion::Exit { operator, expected })
+    } else if let Some(rest) = strip_keyword(text, "fault") {
+        parse_fault_assertion(rest)
+    } else if let Some(rest) = strip_keyword(text, "executed") {
+        parse_executed_label(rest, true)
+    } else if let Some(rest) = strip_keyword(text, "never") {
+        let rest = strip_keyword(rest.trim_start(), "executed")
+            .ok_or_else(|| "expected 'never executed label name'".to_string())?;
+        parse_executed_label(rest, false)
+    } else if let Some(rest) = strip_keyword(text, "retired") {
+        parse_retired_assertion(rest)
     } else {
         parse_register_assertion(text)
     }
@@ -204,6 +753,135 @@ fn parse_memory_assertion(text: &str) -> Result<Assertion, String> {
     })
 }
 
+/// Parses the rest of a fault record assertion like `.cause == 0x01`.
+///
+/// `fault.cause` also accepts a fault name such as `IllegalEncoding`.
+fn parse_fault_assertion(rest: &str) -> Result<Assertion, String> {
+    let rest = rest.strip_prefix('.').ok_or_else(|| {
+        "expected 'fault.cause', 'fault.pc', 'fault.flags' or 'fault.count'".to_string()
+    })?;
+    let (field, rest) = [
+        ("cause", FaultField::Cause),
+        ("pc", FaultField::Pc),
+        ("flags", FaultField::Flags),
+        ("count", FaultField::Count),
+    ]
+    .into_iter()
+    .find_map(|(name, field)| strip_keyword(rest, name).map(|rest| (field, rest)))
+    .ok_or_else(|| {
+        format!(
+            "unknown fault record field 'fault.{}'",
+            rest.split_whitespace().next().unwrap_or("")
+        )
+    })?;
+
+    let (operator, rest) = parse_comparison_op(rest)?;
+    let rest = rest.trim();
+    let named = (1..=u8::MAX)
+        .map_while(FaultCode::from_u8)
+        .find(|code| format!("{:?}", code).eq_ignore_ascii_case(rest));
+    let expected = match named {
+        Some(code) if field == FaultField::Cause => u16::from(code.as_u8()),
+        _ => parse_u16(rest)?,
+    };
+    Ok(Assertion::FaultRecord {
+        field,
+        operator,
+        expected,
+    })
+}
+
+/// Parses the rest of an `executed label name` assertion.
+fn parse_executed_label(rest: &str, expected: bool) -> Result<Assertion, String> {
+    let label = strip_keyword(rest.trim_start(), "label")
+        .map(str::trim)
+        .ok_or_else(|| "expected 'label name' after 'executed'".to_string())?;
+    match parse_start_target(label) {
+        Ok(StartTarget::Label(label)) => Ok(Assertion::Executed { label, expected }),
+        _ => Err(format!("invalid label '{}'", label)),
+    }
+}
+
+/// Parses the rest of a `retired <= 500 instructions` assertion.
+fn parse_retired_assertion(rest: &str) -> Result<Assertion, String> {
+    let rest = rest.trim_start();
+    let (operator, rest) = [
+        ("<=", CountOp::AtMost),
+        (">=", CountOp::AtLeast),
+        ("==", CountOp::Exactly),
+    ]
+    .into_iter()
+    .find_map(|(symbol, operator)| rest.strip_prefix(symbol).map(|rest| (operator, rest)))
+    .ok_or_else(|| "expected '<=', '>=' or '==' after 'retired'".to_string())?;
+    let rest = rest.trim();
+    let count = rest
+        .strip_suffix("instructions")
+        .or_else(|| rest.strip_suffix("instruction"))
+        .unwrap_or(rest)
+        .trim();
+    let expected = count
+        .parse()
+        .map_err(|_| format!("invalid instruction count '{}'", count))?;
+    Ok(Assertion::Retired { operator, expected })
+}
+
+/// Returns the text after a case-insensitive `keyword`, or `None` when
+/// `text` does not start with it as a whole word.
+fn strip_keyword<'a>(text: &'a str, keyword: &str) -> Option<&'a str> {
+    let prefix = text.get(..keyword.len())?;
+    let rest = &text[keyword.len()..];
+    let whole_word = !rest.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_');
+    (prefix.eq_ignore_ascii_case(keyword) && whole_word).then_some(rest)
+}
+
+/// Parses the rest of a console assertion like `console == "OK\n"`.
+fn parse_console_assertion(rest: &str) -> Result<Assertion, String> {
+    let (operator, rest) = parse_comparison_op(rest)?;
+    let expected = parse_string_literal(rest.trim())?;
+    Ok(Assertion::Console { operator, expected })
+}
+
+/// Parses a double-quoted string with `\n`, `\r`, `\t`, `\0`, `\"`, `\\`
+/// and ASCII `\xNN` escapes.
+fn parse_string_literal(text: &str) -> Result<String, String> {
+    let body = text
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .filter(|_| text.len() >= 2)
+        .ok_or_else(|| format!("expected a double-quoted string, got '{}'", text))?;
+
+    let mut value = String::new();
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c == '"' {
+            return Err(format!("unescaped '\"' in string {}", text));
+        }
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('r') => value.push('\r'),
+            Some('t') => value.push('\t'),
+            Some('0') => value.push('\0'),
+            Some('"') => value.push('"'),
+            Some('\\') => value.push('\\'),
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                let byte = u8::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|byte| digits.len() == 2 && byte.is_ascii())
+                    .ok_or_else(|| format!("invalid escape '\\x{}' (expected 00-7F)", digits))?;
+                value.push(char::from(byte));
+            }
+            Some(other) => return Err(format!("invalid escape '\\{}'", other)),
+            None => return Err(format!("unterminated escape in string {}", text)),
+        }
+    }
+    Ok(value)
+}
+
 /// Parses a register assertion like `R0 == 0x4000` or `PC != 0x0000`.
 fn parse_register_assertion(text: &str) -> Result<Assertion, String> {
     let parts: Vec<&str> = text.split_whitespace().collect();
@@ -368,6 +1046,111 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_console_assertions() {
+        let block = parse_test_block(
+            "console == \"OK; done\\n\" ; trailing comment\nConsole != \"\\x41\\t\\\"\\\\\"",
+            1,
+            4,
+        )
+        .unwrap();
+        assert_eq!(
+            block.assertions,
+            vec![
+                Assertion::Console {
+                    operator: ComparisonOp::Equal,
+                    expected: "OK; done\n".to_string(),
+                },
+                Assertion::Console {
+                    operator: ComparisonOp::NotEqual,
+                    expected: "A\t\"\\".to_string(),
+                },
+            ]
+        );
+
+        for (text, message) in [
+            ("console == OK", "expected a double-quoted string, got 'OK'"),
+            ("console == \"a\\q\"", "invalid escape '\\q'"),
+            (
+                "console == \"\\xFF\"",
+                "invalid escape '\\xFF' (expected 00-7F)",
+            ),
+            ("console == \"a\"b\"", "unescaped '\"' in string \"a\"b\""),
+        ] {
+            assert_eq!(parse_assertion(text).unwrap_err(), message, "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_exit_assertions() {
+        assert_eq!(
+            parse_assertion("exit == 0").unwrap(),
+            Assertion::Exit {
+                operator: ComparisonOp::Equal,
+                expected: 0,
+            }
+        );
+        assert_eq!(
+            parse_assertion("EXIT != 0x02").unwrap(),
+            Assertion::Exit {
+                operator: ComparisonOp::NotEqual,
+                expected: 2,
+            }
+        );
+        assert_eq!(
+            parse_assertion("exit == 256").unwrap_err(),
+            "invalid decimal value '256'"
+        );
+    }
+
+    #[test]
+    fn parse_fault_record_assertions() {
+        assert_eq!(
+            parse_assertion("fault.cause == illegalencoding").unwrap(),
+            Assertion::FaultRecord {
+                field: FaultField::Cause,
+                operator: ComparisonOp::Equal,
+                expected: 0x01,
+            }
+        );
+        assert_eq!(
+            parse_assertion("FAULT.PC != 0x0104").unwrap(),
+            Assertion::FaultRecord {
+                field: FaultField::Pc,
+                operator: ComparisonOp::NotEqual,
+                expected: 0x0104,
+            }
+        );
+        assert_eq!(FaultField::Count.address(), 0xDF06);
+        assert!(parse_assertion("fault.count == DoubleFault").is_err());
+        assert_eq!(
+            parse_assertion("fault.sp == 0").unwrap_err(),
+            "unknown fault record field 'fault.sp'"
+        );
+    }
+
+    #[test]
+    fn parse_param_lines() {
+        let block = parse_test_block(
+            "param lvl = 0x10\nPARAM name = \"A;B\"\nparam mode=fast",
+            1,
+            5,
+        )
+        .unwrap();
+        let mut expected = ParamBlock::new();
+        expected.push_word("lvl", 0x10).unwrap();
+        expected.push_bytes("name", b"A;B").unwrap();
+        expected.push_bytes("mode", b"fast").unwrap();
+        assert_eq!(block.params, expected);
+        assert!(block.assertions.is_empty());
+
+        let err = parse_test_block("param lvl = 1\nparam lvl = 2", 1, 4).unwrap_err();
+        assert_eq!(err.line_in_block, 2);
+        assert_eq!(err.message, "parameter 'lvl' is set more than once");
+        let err = parse_test_block("param lvl", 1, 3).unwrap_err();
+        assert_eq!(err.message, "expected 'key = value', got 'lvl'");
+    }
+
     #[test]
     fn parse_memory_decimal() {
         let result = parse_assertion("[16384] == 255").unwrap();
@@ -472,6 +1255,231 @@ mod tests {
         assert!(err.message.contains("unknown register"));
     }
 
+    #[test]
+    fn parse_test_block_setup_lines() {
+        let content = "start at square ; routine\nSP = 0xFF00\nR0 == 4\nstart at 0x0100";
+        let result = parse_test_block(content, 1, 6).unwrap();
+
+        assert_eq!(
+            result.setup,
+            vec![
+                TestSetup::StartAt(StartTarget::Label("square".into())),
+                TestSetup::StackPointer(0xFF00),
+                TestSetup::StartAt(StartTarget::Address(0x0100)),
+            ]
+        );
+        assert_eq!(result.assertions.len(), 1);
+    }
+
+    #[test]
+    fn parse_reset_metadata() {
+        assert!(
+            parse_test_block("reset: true\nR0 == 0", 1, 4)
+                .unwrap()
+                .reset
+        );
+        assert!(!parse_test_block("Reset : false", 1, 3).unwrap().reset);
+        assert!(!parse_test_block("R0 == 0", 1, 3).unwrap().reset);
+
+        let err = parse_test_block("reset: yes", 1, 3).unwrap_err();
+        assert!(err.message.contains("'true' or 'false'"));
+    }
+
+    #[test]
+    fn parse_triage_metadata() {
+        let result = parse_test_block(
+            "skip: true\nOnly: true\ntags: slow, display-io\nTAGS: nightly\nR0 == 0",
+            1,
+            7,
+        )
+        .unwrap();
+        assert!(result.skip);
+        assert!(result.only);
+        assert_eq!(result.tags, vec!["slow", "display-io", "nightly"]);
+        assert_eq!(result.assertions.len(), 1);
+
+        let plain = parse_test_block("R0 == 0", 1, 3).unwrap();
+        assert!(!plain.skip && !plain.only && plain.tags.is_empty());
+
+        let err = parse_test_block("tags: slow,, fast", 1, 3).unwrap_err();
+        assert!(err.message.contains("got ''"), "{}", err.message);
+        let err = parse_test_block("skip: maybe", 1, 3).unwrap_err();
+        assert!(err.message.contains("'true' or 'false'"));
+    }
+
+    #[test]
+    fn parse_fixture_and_capture_metadata() {
+        let result = parse_test_block(
+            "Fixture: snaps/Boot.n1snap
+capture : menu.n1snap
+R0 == 0",
+            1,
+            5,
+        )
+        .unwrap();
+        assert_eq!(result.fixture.as_deref(), Some("snaps/Boot.n1snap"));
+        assert_eq!(result.capture.as_deref(), Some("menu.n1snap"));
+        assert_eq!(result.assertions.len(), 1);
+
+        let err = parse_test_block("fixture:", 1, 3).unwrap_err();
+        assert!(err.message.contains("snapshot file"));
+    }
+
+    #[test]
+    fn parse_frozen_registers() {
+        let result = parse_test_block("frozen: R4-R6, r1\nFROZEN : R5, R7\nR0 == 0", 1, 5).unwrap();
+        assert_eq!(
+            result.frozen,
+            vec![
+                Register::R4,
+                Register::R5,
+                Register::R6,
+                Register::R1,
+                Register::R7
+            ]
+        );
+        assert_eq!(result.assertions.len(), 1);
+
+        let err = parse_test_block("frozen: R5-R2", 1, 3).unwrap_err();
+        assert!(err.message.contains("backwards"));
+        let err = parse_test_block("frozen: R4, PC", 1, 3).unwrap_err();
+        assert!(err.message.contains("got 'PC'"), "{}", err.message);
+        let err = parse_test_block("frozen:", 1, 3).unwrap_err();
+        assert!(err.message.contains("got ''"), "{}", err.message);
+    }
+
+    #[test]
+    fn parse_scheduled_events() {
+        let result = parse_test_block(
+            "at tick 3 enqueue event 0x07\nAT TICK 0 ENQUEUE EVENT 9 ; first\nR0 == 1",
+            1,
+            5,
+        )
+        .unwrap();
+        assert_eq!(
+            result.events,
+            vec![
+                ScheduledEvent {
+                    tick: 3,
+                    event_id: 0x07,
+                },
+                ScheduledEvent {
+                    tick: 0,
+                    event_id: 9,
+                },
+            ]
+        );
+        assert_eq!(result.assertions.len(), 1);
+
+        let err = parse_test_block("at tick 3 event 7", 1, 3).unwrap_err();
+        assert!(err.message.contains("enqueue event"));
+        assert!(parse_test_block("at tick 1 enqueue event 0x100", 1, 3).is_err());
+    }
+
+    #[test]
+    fn parse_event_scripts() {
+        let events = parse_event_script(
+            "; inputs\n\nat tick 2 enqueue event 0x41\nat tick 5 enqueue event 1\n",
+        )
+        .unwrap();
+        assert_eq!(
+            events,
+            [
+                ScheduledEvent {
+                    tick: 2,
+                    event_id: 0x41,
+                },
+                ScheduledEvent {
+                    tick: 5,
+                    event_id: 1,
+                },
+            ]
+        );
+
+        let err = parse_event_script("at tick 1 enqueue event 2\nR0 == 1").unwrap_err();
+        assert_eq!(err.line_in_block, 2);
+        assert!(err.message.contains("enqueue event"));
+    }
+
+    #[test]
+    fn parse_every_tick_invariants() {
+        let result = parse_test_block(
+            "every tick assert [0xE100] != 0x00\nEVERY TICK ASSERT R4 == 7\nR0 == 1",
+            1,
+            5,
+        )
+        .unwrap();
+        assert_eq!(
+            result.invariants,
+            vec![
+                Assertion::Memory {
+                    address: 0xE100,
+                    operator: ComparisonOp::NotEqual,
+                    expected: 0,
+                },
+                Assertion::Register {
+                    register: Register::R4,
+                    operator: ComparisonOp::Equal,
+                    expected: 7,
+                },
+            ]
+        );
+        assert_eq!(result.assertions.len(), 1);
+
+        let err = parse_test_block("every tick R0 == 1", 1, 3).unwrap_err();
+        assert!(err.message.contains("every tick assert"), "{}", err.message);
+        let err = parse_test_block("every tick assert exit == 0", 1, 3).unwrap_err();
+        assert!(err.message.contains("registers, memory"), "{}", err.message);
+    }
+
+    #[test]
+    fn parse_path_assertions() {
+        let result = parse_test_block(
+            "executed label init_display\nnever executed label debug_stub\nretired <= 500 instructions\nretired >= 1",
+            1,
+            6,
+        )
+        .unwrap();
+        assert_eq!(
+            result.assertions,
+            vec![
+                Assertion::Executed {
+                    label: "init_display".to_string(),
+                    expected: true,
+                },
+                Assertion::Executed {
+                    label: "debug_stub".to_string(),
+                    expected: false,
+                },
+                Assertion::Retired {
+                    operator: CountOp::AtMost,
+                    expected: 500,
+                },
+                Assertion::Retired {
+                    operator: CountOp::AtLeast,
+                    expected: 1,
+                },
+            ]
+        );
+
+        assert!(parse_test_block("executed init_display", 1, 3).is_err());
+        assert!(parse_test_block("never executed label 0x10", 1, 3).is_err());
+        assert!(parse_test_block("retired < 5", 1, 3).is_err());
+        let err = parse_test_block("every tick assert retired <= 5", 1, 3).unwrap_err();
+        assert!(err.message.contains("registers, memory"), "{}", err.message);
+    }
+
+    #[test]
+    fn parse_setup_errors() {
+        let err = parse_test_block("R0 == 1\nstart at", 1, 4).unwrap_err();
+        assert_eq!(err.line_in_block, 2);
+        assert!(err.message.contains("label or address"));
+
+        assert!(parse_test_block("start at my-label", 1, 3).is_err());
+        assert!(parse_test_block("sp = 0x1FFFF", 1, 3).is_err());
+        assert!(parse_test_block("sp == 0", 1, 3).is_err());
+    }
+
     #[test]
     fn all_registers_parseable() {
         for (reg, name) in [
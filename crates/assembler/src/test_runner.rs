@@ -7,11 +7,43 @@
 //!
 //! 1. Load assembled binary into an `emulator-core` instance at address 0x0000.
 //! 2. For each `n1test` block in document order:
-//!    a. Execute until HALT (or fault).
+//!    a. Apply setup lines (`start at`, `sp =`, `param`), then execute
+//!       until HALT (or fault), enqueueing scheduled events (`at tick N
+//!       enqueue event ID`) as each tick starts.
 //!    b. Evaluate all assertions against current machine state.
 //!    c. Report failures with expected vs. actual values.
 //!    d. Resume execution (un-halt) for the next test block.
 //! 3. Report summary: passed, failed, total.
+//!
+//! Blocks marked `reset: true` start from a freshly reset machine with the
+//! binary reloaded instead of continuing from the previous block. Blocks
+//! with `fixture: file` start from a snapshot saved by an earlier run whose
+//! `capture: file` block passed with fixture capture enabled, so long boot
+//! sequences run once rather than on every test run.
+//!
+//! Blocks marked `skip: true`, left out by `only: true` on another block,
+//! or excluded by the program's tag filters still run to their HALT, so
+//! later blocks see the same machine state, but their assertions are not
+//! checked and they are reported as skipped.
+//!
+//! Trap, event and fault dispatch enter the program's handlers, which run
+//! in the current tick. A `TRAP` or `SWI` with the trap vector still at
+//! 0x0000 fails the block instead, since no handler was installed.
+//!
+//! The runner attaches a TELE-7 and a debug console at their usual MMIO
+//! windows. A block with an `n1expect-screen` block compares the rendered
+//! screen text with it at its HALT and reports a unified diff when they
+//! differ; `console == "..."` and `exit == N` assertions see the bytes and
+//! exit status written to the debug console since the block started.
+//!
+//! Blocks with `executed label`, `never executed label` or `retired`
+//! assertions run with a trace sink that counts retired instructions and
+//! notes which of the named labels were reached; other blocks run
+//! untraced.
+//!
+//! In strict mode each `frozen:` register is also checked at the block's
+//! HALT against the value it held when the block started, catching
+//! clobbers no assertion mentions.
 
 #![allow(
     clippy::uninlined_format_args,
@@ -28,13 +60,23 @@
 )]
 
 use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use emulator_core::{
-    CoreConfig, CoreState, GeneralRegister, MmioBus, MmioError, MmioWriteResult, RunBoundary,
-    RunState, StepOutcome,
+    read_u16_be, write_params, CoreConfig, CoreSnapshot, CoreState, DebugConsole, Decoder,
+    GeneralRegister, HaltReason, MmioBus, MmioError, MmioWriteResult, OpcodeEncoding, PcHistory,
+    RunBoundary, RunState, StepOutcome, Tele7Peripheral, TraceEvent, TraceSink, CONSOLE_BASE,
+    CONSOLE_END, LATEST_SNAPSHOT_VERSION, TELE7_BASE, TELE7_END, VEC_TRAP,
 };
 
-use crate::test_format::{Assertion, ComparisonOp, ParsedTestBlock, Register};
+use crate::assembler::AssembleResult;
+use crate::diff::unified_diff;
+use crate::symbols::SymbolTable;
+use crate::test_format::{
+    Assertion, ComparisonOp, CountOp, ParsedTestBlock, Register, ScheduledEvent, StartTarget,
+    TestSetup,
+};
 
 /// Result of evaluating a single assertion against machine state.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -60,13 +102,20 @@ pub struct TestBlockResult {
     pub faulted: bool,
     /// Fault message if faulted.
     pub fault_message: Option<String>,
+    /// Whether the block ran without its assertions being checked.
+    pub skipped: bool,
+    /// Unified diff from the expected `n1expect-screen` text to the
+    /// rendered TELE-7 screen, when they differ.
+    pub screen_diff: Option<String>,
 }
 
 impl TestBlockResult {
     /// Returns true if all assertions passed and no fault occurred.
     #[must_use]
     pub fn passed(&self) -> bool {
-        !self.faulted && self.assertion_results.iter().all(|r| r.passed)
+        !self.faulted
+            && self.screen_diff.is_none()
+            && self.assertion_results.iter().all(|r| r.passed)
     }
 }
 
@@ -77,6 +126,31 @@ pub struct TestRunResult {
     pub block_results: Vec<TestBlockResult>,
     /// Number of test blocks that were not executed (more blocks than HALTs).
     pub unexecuted_blocks: usize,
+    /// States saved by passing `capture:` blocks, when capture is enabled.
+    pub captures: Vec<FixtureCapture>,
+}
+
+/// Machine state captured after a `capture: file` block passed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixtureCapture {
+    /// Where the snapshot should be written, resolved against the
+    /// program's fixture directory.
+    pub path: PathBuf,
+    /// State after the block's HALT.
+    pub state: CoreState,
+}
+
+impl FixtureCapture {
+    /// Writes the captured state to [`FixtureCapture::path`] as a binary
+    /// snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns any I/O error from writing the file.
+    pub fn write(&self) -> std::io::Result<()> {
+        let snapshot = CoreSnapshot::from_core_state(LATEST_SNAPSHOT_VERSION, &self.state);
+        fs::write(&self.path, snapshot.to_bytes())
+    }
 }
 
 impl TestRunResult {
@@ -89,11 +163,21 @@ impl TestRunResult {
     /// Returns counts for summary reporting.
     #[must_use]
     pub fn summary(&self) -> TestSummary {
-        let passed = self.block_results.iter().filter(|b| b.passed()).count();
-        let failed = self.block_results.len() - passed;
+        let skipped = self
+            .block_results
+            .iter()
+            .filter(|b| b.skipped && b.passed())
+            .count();
+        let passed = self
+            .block_results
+            .iter()
+            .filter(|b| !b.skipped && b.passed())
+            .count();
+        let failed = self.block_results.len() - passed - skipped;
         TestSummary {
             passed,
             failed,
+            skipped,
             unexecuted: self.unexecuted_blocks,
             total: self.block_results.len() + self.unexecuted_blocks,
         }
@@ -107,12 +191,74 @@ pub struct TestSummary {
     pub passed: usize,
     /// Number of test blocks that failed.
     pub failed: usize,
+    /// Number of test blocks that ran without being checked.
+    pub skipped: usize,
     /// Number of test blocks that were not executed.
     pub unexecuted: usize,
     /// Total number of test blocks.
     pub total: usize,
 }
 
+/// The assembled program a test suite runs against.
+#[derive(Debug, Clone, Copy)]
+pub struct TestProgram<'a> {
+    /// ROM image loaded at address 0x0000.
+    pub binary: &'a [u8],
+    /// PC after load and after every block reset.
+    pub entry: u16,
+    /// Symbols `start at` setup lines resolve against.
+    pub symbols: &'a SymbolTable,
+    /// Directory `fixture:` and `capture:` paths are relative to; `None`
+    /// resolves them against the working directory.
+    pub fixture_dir: Option<&'a Path>,
+    /// Whether passing `capture:` blocks record their final state.
+    pub capture_fixtures: bool,
+    /// Whether `frozen:` registers are checked.
+    pub strict: bool,
+    /// Only blocks with one of these tags are checked; empty checks all.
+    pub tags: &'a [String],
+    /// Blocks with any of these tags are skipped.
+    pub skip_tags: &'a [String],
+}
+
+impl<'a> TestProgram<'a> {
+    /// Describes an assembler result, honouring its `.entry`.
+    #[must_use]
+    pub fn of(result: &'a AssembleResult) -> Self {
+        Self {
+            binary: &result.binary,
+            entry: result.entry.unwrap_or_default(),
+            symbols: &result.symbols,
+            fixture_dir: None,
+            capture_fixtures: false,
+            strict: false,
+            tags: &[],
+            skip_tags: &[],
+        }
+    }
+
+    /// Whether `block` has its assertions checked, given whether any block
+    /// in the suite is marked `only: true`.
+    #[must_use]
+    pub fn checks(&self, block: &ParsedTestBlock, only_marked: bool) -> bool {
+        let tagged = |filter: &[String]| {
+            block
+                .tags
+                .iter()
+                .any(|tag| filter.iter().any(|f| f.eq_ignore_ascii_case(tag)))
+        };
+        !block.skip
+            && (block.only || !only_marked)
+            && (self.tags.is_empty() || tagged(self.tags))
+            && !tagged(self.skip_tags)
+    }
+
+    fn fixture_path(&self, path: &str) -> PathBuf {
+        self.fixture_dir
+            .map_or_else(|| PathBuf::from(path), |dir| dir.join(path))
+    }
+}
+
 /// Runs all test blocks against an assembled binary.
 ///
 /// # Arguments
@@ -125,31 +271,256 @@ pub struct TestSummary {
 /// A `TestRunResult` with results for each test block.
 #[must_use]
 pub fn run_tests(binary: &[u8], test_blocks: &[ParsedTestBlock]) -> TestRunResult {
-    let config = CoreConfig::default();
-    let mut state = CoreState::with_config(&config);
+    let symbols = SymbolTable::new();
+    let program = TestProgram {
+        binary,
+        entry: 0,
+        symbols: &symbols,
+        fixture_dir: None,
+        capture_fixtures: false,
+        strict: false,
+        tags: &[],
+        skip_tags: &[],
+    };
+    run_program_tests(&program, test_blocks)
+}
 
-    load_binary(&mut state, binary);
+/// Runs all test blocks like [`run_tests`], starting execution at the
+/// program's entry point and resolving `start at` labels against its symbols.
+#[must_use]
+pub fn run_program_tests(
+    program: &TestProgram<'_>,
+    test_blocks: &[ParsedTestBlock],
+) -> TestRunResult {
+    let mut state = new_test_state(program);
+    run_tests_on_state(&mut state, program, test_blocks)
+}
 
-    let mut mmio = NullMmio;
+/// Creates the machine state the runner starts from, with the program's
+/// binary loaded at address 0x0000 and PC at its entry point.
+#[must_use]
+pub fn new_test_state(program: &TestProgram<'_>) -> CoreState {
+    let config = CoreConfig {
+        reset_pc: program.entry,
+        ..test_config()
+    };
+    let mut state = CoreState::with_config(&config);
+    load_binary(&mut state, program.binary);
+    state
+}
+
+/// Runs test blocks against an existing machine state, leaving `state` as
+/// the last block left it.
+///
+/// Lets callers continue a suite from a restored snapshot. Blocks after a
+/// latched fault are counted as unexecuted, up to the next `reset: true` or
+/// `fixture:` block, which replaces `state` with a fresh machine or the
+/// saved snapshot.
+#[must_use]
+pub fn run_tests_on_state(
+    state: &mut CoreState,
+    program: &TestProgram<'_>,
+    test_blocks: &[ParsedTestBlock],
+) -> TestRunResult {
+    let config = test_config();
+    let mut mmio = TestBus::default();
     let mut block_results = Vec::new();
+    let mut unexecuted_blocks = 0;
+    let mut captures = Vec::new();
+    let only_marked = test_blocks.iter().any(|block| block.only);
 
     for block in test_blocks {
-        let result = run_test_block(&mut state, &config, &mut mmio, block);
-        block_results.push(result);
+        let checked = program.checks(block, only_marked);
+        if block.reset {
+            *state = new_test_state(program);
+            mmio.reset();
+        }
+        mmio.console.reset();
+
+        if let Some(fixture) = &block.fixture {
+            match load_fixture(program, fixture) {
+                Ok(loaded) => *state = loaded,
+                Err(message) => {
+                    block_results.push(failed_block(block, message));
+                    continue;
+                }
+            }
+        }
 
         if matches!(state.run_state, RunState::FaultLatched(_)) {
-            let remaining = test_blocks.len() - block_results.len();
-            return TestRunResult {
-                block_results,
-                unexecuted_blocks: remaining,
-            };
+            unexecuted_blocks += 1;
+            continue;
+        }
+
+        let setup = apply_setup(state, program.symbols, block)
+            .and_then(|()| PathTrace::for_block(block, program.symbols));
+        let result = match setup {
+            Ok(mut path) => {
+                let frozen = if program.strict && checked {
+                    frozen_assertions(state, &block.frozen)
+                } else {
+                    Vec::new()
+                };
+                let mut result = run_test_block(state, &config, &mut mmio, block, path.as_mut());
+                result.assertion_results.extend(evaluate_assertions(
+                    state,
+                    &mmio.console,
+                    None,
+                    &frozen,
+                ));
+                if let Some(expected) = block.screen.as_deref().filter(|_| !result.faulted) {
+                    result.screen_diff =
+                        screen_diff(expected, &mmio.tele7.render_text(&state.memory));
+                }
+                if !checked {
+                    result.assertion_results.clear();
+                    result.screen_diff = None;
+                    result.skipped = true;
+                }
+                result
+            }
+            Err(message) => failed_block(block, message),
+        };
+        if let Some(capture) = block.capture.as_deref() {
+            if program.capture_fixtures && checked && result.passed() {
+                captures.push(FixtureCapture {
+                    path: program.fixture_path(capture),
+                    state: state.clone(),
+                });
+            }
         }
+        block_results.push(result);
     }
 
     TestRunResult {
         block_results,
-        unexecuted_blocks: 0,
+        unexecuted_blocks,
+        captures,
+    }
+}
+
+fn failed_block(block: &ParsedTestBlock, message: String) -> TestBlockResult {
+    TestBlockResult {
+        start_line: block.start_line,
+        end_line: block.end_line,
+        assertion_results: Vec::new(),
+        faulted: true,
+        fault_message: Some(message),
+        skipped: false,
+        screen_diff: None,
+    }
+}
+
+/// Loads a `fixture:` snapshot, rejecting one whose ROM no longer matches
+/// the program under test.
+fn load_fixture(program: &TestProgram<'_>, fixture: &str) -> Result<CoreState, String> {
+    let path = program.fixture_path(fixture);
+    let bytes =
+        fs::read(&path).map_err(|e| format!("Cannot read fixture '{}': {}", path.display(), e))?;
+    let mut state = CoreSnapshot::from_bytes(&bytes)
+        .map_err(|e| e.to_string())
+        .and_then(|snapshot| snapshot.try_into_core_state().map_err(|e| e.to_string()))
+        .map_err(|e| format!("Invalid fixture '{}': {}", path.display(), e))?;
+
+    let rom = &state.memory[..program.binary.len().min(state.memory.len())];
+    if rom != program.binary {
+        return Err(format!(
+            "Fixture '{}' was captured from a different build; recapture it with --capture-fixture",
+            path.display()
+        ));
+    }
+    // PC history is not part of the snapshot.
+    state.pc_history = PcHistory::new(FAULT_PC_HISTORY_DEPTH);
+    Ok(state)
+}
+
+/// Applies a block's setup lines and parameter block to the machine before
+/// it runs.
+fn apply_setup(
+    state: &mut CoreState,
+    symbols: &SymbolTable,
+    block: &ParsedTestBlock,
+) -> Result<(), String> {
+    if !block.params.is_empty() {
+        write_params(state, &block.params.to_bytes()).map_err(|e| e.to_string())?;
     }
+    for line in &block.setup {
+        match line {
+            TestSetup::StartAt(StartTarget::Address(address)) => state.arch.set_pc(*address),
+            TestSetup::StartAt(StartTarget::Label(label)) => {
+                let symbol = symbols
+                    .get(label)
+                    .ok_or_else(|| format!("Unknown label '{}' in 'start at'", label))?;
+                state.arch.set_pc(symbol.address);
+            }
+            TestSetup::StackPointer(value) => state.arch.set_sp(*value),
+        }
+    }
+    Ok(())
+}
+
+/// What a block's path assertions need from the instruction stream: how
+/// many instructions retired and whether any retired at each named label.
+#[derive(Debug, Clone, Default)]
+struct PathTrace {
+    /// Labels named by `executed` assertions, their addresses and whether
+    /// an instruction there has retired.
+    labels: Vec<(String, u16, bool)>,
+    retired: u64,
+}
+
+impl PathTrace {
+    /// Resolves the labels of the block's path assertions, or returns
+    /// `None` when it has none and can run untraced.
+    fn for_block(block: &ParsedTestBlock, symbols: &SymbolTable) -> Result<Option<Self>, String> {
+        let mut trace = Self::default();
+        let mut traced = false;
+        for assertion in &block.assertions {
+            match assertion {
+                Assertion::Executed { label, .. } => {
+                    traced = true;
+                    let symbol = symbols
+                        .get(label)
+                        .ok_or_else(|| format!("Unknown label '{}' in 'executed label'", label))?;
+                    trace.labels.push((label.clone(), symbol.address, false));
+                }
+                Assertion::Retired { .. } => traced = true,
+                _ => {}
+            }
+        }
+        Ok(traced.then_some(trace))
+    }
+
+    fn executed(&self, label: &str) -> Option<bool> {
+        self.labels
+            .iter()
+            .find(|(name, _, _)| name == label)
+            .map(|(_, _, hit)| *hit)
+    }
+}
+
+impl TraceSink for PathTrace {
+    fn on_event(&mut self, event: TraceEvent) {
+        if let TraceEvent::InstructionRetired { pc, .. } = event {
+            self.retired += 1;
+            for (_, address, hit) in &mut self.labels {
+                *hit |= *address == pc;
+            }
+        }
+    }
+}
+
+fn test_config() -> CoreConfig {
+    CoreConfig {
+        pc_history_depth: FAULT_PC_HISTORY_DEPTH,
+        ..CoreConfig::default()
+    }
+}
+
+/// Whether the program has pointed the trap vector somewhere other than
+/// the reset address, so `TRAP` and `SWI` are expected rather than stray.
+fn trap_handler_installed(state: &CoreState) -> bool {
+    read_u16_be(&state.memory, VEC_TRAP).is_ok_and(|handler| handler != 0)
 }
 
 /// Loads a binary image into ROM starting at address 0x0000.
@@ -163,131 +534,250 @@ fn load_binary(state: &mut CoreState, binary: &[u8]) {
 /// roughly 6.4 million cycles.
 const MAX_TICKS_PER_BLOCK: u32 = 10_000;
 
-/// Returns `true` when the most recent `HaltedForTick` was caused by an
-/// explicit HALT or EWAIT instruction rather than tick-budget exhaustion.
-///
-/// The distinction is made via TICK: budget exhaustion always leaves
-/// `TICK >= budget`, whereas an explicit HALT retires (cost 1) and then
-/// immediately yields, so TICK stays below the budget in all practical
-/// cases.  The only ambiguous scenario is HALT landing exactly on the
-/// budget boundary (TICK == budget), which is treated conservatively as
-/// budget exhaustion; the next tick will re-encounter the HALT with
-/// TICK < budget.
-fn was_explicit_halt_instruction(state: &CoreState, config: &CoreConfig) -> bool {
-    state.arch.tick() < config.tick_budget_cycles
+/// Number of recently retired PCs listed in fault messages.
+const FAULT_PC_HISTORY_DEPTH: u16 = 8;
+
+/// Formats a fault message, appending the recent PC history when recorded.
+fn fault_message_with_history(state: &CoreState, message: String) -> String {
+    let pcs = state.pc_history.pcs();
+    if pcs.is_empty() {
+        return message;
+    }
+    let recent: Vec<String> = pcs.iter().map(|pc| format!("{pc:#06X}")).collect();
+    format!("{message} (recent PCs: {})", recent.join(", "))
 }
 
 /// Runs a single test block to the next explicit HALT and evaluates assertions.
 ///
-/// The test runner acts as the host clock: it resets TICK to 0 before each
-/// `run_one` call so that the emulator's `BudgetOverrun` check does not fire
-/// on resume.  When the tick budget is exhausted (not an explicit HALT) the
-/// runner transparently starts a new tick and continues execution.
+/// The test runner acts as the host clock: it resets TICK to 0 at the start
+/// of each tick so that the emulator's `BudgetOverrun` check does not fire
+/// on resume, and enqueues the block's scheduled events for that tick.  When
+/// the tick budget is exhausted (not an explicit HALT) the runner
+/// transparently starts a new tick and continues execution; event dispatch
+/// continues within the same tick. The block's `every tick assert` lines are
+/// checked at the end of every tick, including the one that reaches HALT,
+/// and the first failure ends the block with the tick's index.
+#[allow(clippy::too_many_lines)]
 fn run_test_block(
     state: &mut CoreState,
     config: &CoreConfig,
-    mmio: &mut dyn MmioBus,
+    mmio: &mut TestBus,
     block: &ParsedTestBlock,
+    mut path: Option<&mut PathTrace>,
 ) -> TestBlockResult {
     if matches!(state.run_state, RunState::FaultLatched(_)) {
-        return TestBlockResult {
-            start_line: block.start_line,
-            end_line: block.end_line,
-            assertion_results: Vec::new(),
-            faulted: true,
-            fault_message: Some(format!("CPU already faulted: {:?}", state.run_state)),
-        };
+        return failed_block(block, format!("CPU already faulted: {:?}", state.run_state));
     }
 
     let mut ticks: u32 = 0;
+    let mut tick_started = false;
     loop {
-        // Simulate the 100 Hz host clock: reset TICK for a fresh tick.
-        state.arch.set_tick(0);
+        if !tick_started {
+            // Simulate the 100 Hz host clock: reset TICK for a fresh tick.
+            state.arch.set_tick(0);
+            if let Err(message) = enqueue_scheduled_events(state, &block.events, ticks) {
+                return failed_block(block, message);
+            }
+            tick_started = true;
+        }
 
-        let outcome = emulator_core::run_one(state, mmio, config, RunBoundary::Halted);
-        ticks += 1;
+        let sink = path.as_deref_mut().map(|path| path as &mut dyn TraceSink);
+        let outcome =
+            emulator_core::run_one_with_trace(state, mmio, config, RunBoundary::Halted, sink);
 
         match outcome.final_step {
-            StepOutcome::HaltedForTick => {
-                if was_explicit_halt_instruction(state, config) {
-                    let assertion_results = evaluate_assertions(state, &block.assertions);
-                    return TestBlockResult {
-                        start_line: block.start_line,
-                        end_line: block.end_line,
-                        assertion_results,
-                        faulted: false,
-                        fault_message: None,
-                    };
+            StepOutcome::HaltedForTick {
+                reason: HaltReason::Instruction,
+            } => {
+                if let Err(message) =
+                    check_invariants(state, &mmio.console, &block.invariants, ticks)
+                {
+                    return failed_block(block, message);
+                }
+                let assertion_results =
+                    evaluate_assertions(state, &mmio.console, path.as_deref(), &block.assertions);
+                let undelivered = block
+                    .events
+                    .iter()
+                    .find(|event| u32::from(event.tick) > ticks);
+                return TestBlockResult {
+                    start_line: block.start_line,
+                    end_line: block.end_line,
+                    assertion_results,
+                    faulted: undelivered.is_some(),
+                    fault_message: undelivered.map(|event| {
+                        format!(
+                            "Event {:#04X} scheduled at tick {} was not delivered: HALT reached at tick {}",
+                            event.event_id, event.tick, ticks
+                        )
+                    }),
+                    skipped: false,
+                    screen_diff: None,
+                };
+            }
+            StepOutcome::HaltedForTick {
+                reason: HaltReason::BudgetExhausted,
+            } => {
+                // Budget exhaustion — start a new tick and keep running,
+                // unless the program is parked on an EWAIT nothing will wake.
+                if let Err(message) =
+                    check_invariants(state, &mmio.console, &block.invariants, ticks)
+                {
+                    return failed_block(block, message);
                 }
-                // Budget exhaustion — start a new tick and keep running.
+                if let Some(pc) = stuck_in_ewait(state, &block.events, ticks) {
+                    let message = format!(
+                        "Program is waiting for an event that is never enqueued (EWAIT at {:#06X}, tick {})",
+                        pc, ticks
+                    );
+                    return failed_block(block, message);
+                }
+                ticks += 1;
+                tick_started = false;
                 if ticks >= MAX_TICKS_PER_BLOCK {
-                    return TestBlockResult {
-                        start_line: block.start_line,
-                        end_line: block.end_line,
-                        assertion_results: Vec::new(),
-                        faulted: true,
-                        fault_message: Some(format!(
+                    return failed_block(
+                        block,
+                        format!(
                             "Exceeded {} ticks without reaching HALT",
                             MAX_TICKS_PER_BLOCK
-                        )),
-                    };
+                        ),
+                    );
                 }
             }
+            // Dispatched to the program's fault handler, which runs in the
+            // current tick.
+            StepOutcome::Fault { .. } if matches!(state.run_state, RunState::HandlerContext) => {}
             StepOutcome::Fault { cause } => {
-                let assertion_results = evaluate_assertions(state, &block.assertions);
+                let assertion_results =
+                    evaluate_assertions(state, &mmio.console, path.as_deref(), &block.assertions);
                 return TestBlockResult {
                     start_line: block.start_line,
                     end_line: block.end_line,
                     assertion_results,
                     faulted: true,
-                    fault_message: Some(format!("CPU faulted before HALT: {:?}", cause)),
+                    fault_message: Some(fault_message_with_history(
+                        state,
+                        format!("CPU faulted before HALT: {:?}", cause),
+                    )),
+                    skipped: false,
+                    screen_diff: None,
                 };
             }
+            // A trap handler the program installed runs in the current tick.
+            StepOutcome::TrapDispatch { .. } if trap_handler_installed(state) => {}
             StepOutcome::TrapDispatch { cause } => {
-                return TestBlockResult {
-                    start_line: block.start_line,
-                    end_line: block.end_line,
-                    assertion_results: Vec::new(),
-                    faulted: true,
-                    fault_message: Some(format!("Unexpected TRAP dispatch (cause={:#06X})", cause)),
-                };
-            }
-            StepOutcome::EventDispatch { event_id } => {
-                return TestBlockResult {
-                    start_line: block.start_line,
-                    end_line: block.end_line,
-                    assertion_results: Vec::new(),
-                    faulted: true,
-                    fault_message: Some(format!(
-                        "Unexpected EVENT dispatch (id={:#04X})",
-                        event_id
-                    )),
-                };
+                return failed_block(
+                    block,
+                    format!("Unexpected TRAP dispatch (cause={:#06X})", cause),
+                );
             }
+            // The handler runs in the current tick.
+            StepOutcome::EventDispatch { .. } => {}
             StepOutcome::Retired { .. } => {
-                return TestBlockResult {
-                    start_line: block.start_line,
-                    end_line: block.end_line,
-                    assertion_results: Vec::new(),
-                    faulted: true,
-                    fault_message: Some("Run loop exited without HALT or fault".to_string()),
-                };
+                return failed_block(block, "Run loop exited without HALT or fault".to_string());
             }
         }
     }
 }
 
-/// Evaluates all assertions against the current machine state.
-fn evaluate_assertions(state: &CoreState, assertions: &[Assertion]) -> Vec<AssertionResult> {
+/// Returns the PC of an `EWAIT` that a tick ended on with an empty queue and
+/// no events scheduled for later ticks.
+///
+/// The runner is the only event source, so such a program spins until the
+/// tick limit; reporting it at the first idle tick saves the wait.
+fn stuck_in_ewait(state: &CoreState, events: &[ScheduledEvent], tick: u32) -> Option<u16> {
+    let pc = state.arch.pc();
+    let word = read_u16_be(&state.memory, pc).ok()?;
+    let waiting = Decoder::decode(word)
+        .instruction()
+        .is_some_and(|instruction| instruction.encoding == OpcodeEncoding::Ewait);
+    let pending = events.iter().any(|event| u32::from(event.tick) > tick);
+    (waiting && state.event_queue.is_empty() && !pending).then_some(pc)
+}
+
+/// Enqueues the events scheduled for `tick`, in source order.
+fn enqueue_scheduled_events(
+    state: &mut CoreState,
+    events: &[ScheduledEvent],
+    tick: u32,
+) -> Result<(), String> {
+    for event in events.iter().filter(|event| u32::from(event.tick) == tick) {
+        state.enqueue_event(event.event_id).map_err(|_| {
+            format!(
+                "Event queue full at tick {}: cannot enqueue event {:#04X}",
+                tick, event.event_id
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Checks the block's `every tick assert` lines at the end of `tick`,
+/// reporting the first that fails.
+fn check_invariants(
+    state: &CoreState,
+    console: &DebugConsole,
+    invariants: &[Assertion],
+    tick: u32,
+) -> Result<(), String> {
+    match evaluate_assertions(state, console, None, invariants)
+        .into_iter()
+        .find(|result| !result.passed)
+    {
+        Some(failed) => Err(format!(
+            "every tick assertion failed at tick {}: {:?} (got {})",
+            tick, failed.assertion, failed.actual
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Records the current value of each `frozen:` register as an assertion
+/// checked once the block halts.
+fn frozen_assertions(state: &CoreState, frozen: &[Register]) -> Vec<Assertion> {
+    frozen
+        .iter()
+        .map(|register| Assertion::Frozen {
+            register: *register,
+            expected: read_register(state, *register),
+        })
+        .collect()
+}
+
+/// Evaluates all assertions against the current machine state, what the
+/// block wrote to the debug console and, when it ran traced, the path it
+/// took.
+fn evaluate_assertions(
+    state: &CoreState,
+    console: &DebugConsole,
+    path: Option<&PathTrace>,
+    assertions: &[Assertion],
+) -> Vec<AssertionResult> {
     assertions
         .iter()
-        .map(|assertion| evaluate_assertion(state, assertion))
+        .map(|assertion| evaluate_assertion(state, console, path, assertion))
         .collect()
 }
 
 /// Evaluates a single assertion against the current machine state.
-fn evaluate_assertion(state: &CoreState, assertion: &Assertion) -> AssertionResult {
+fn evaluate_assertion(
+    state: &CoreState,
+    console: &DebugConsole,
+    path: Option<&PathTrace>,
+    assertion: &Assertion,
+) -> AssertionResult {
     match assertion {
+        Assertion::Executed { label, expected } => executed_result(
+            assertion,
+            path.and_then(|path| path.executed(label)),
+            *expected,
+        ),
+        Assertion::Retired { operator, expected } => retired_result(
+            assertion,
+            path.map(|path| path.retired),
+            *operator,
+            *expected,
+        ),
         Assertion::Register {
             register,
             operator,
@@ -320,6 +810,88 @@ fn evaluate_assertion(state: &CoreState, assertion: &Assertion) -> AssertionResu
                 actual: format!("{:#04X}", actual),
             }
         }
+        Assertion::Console { operator, expected } => {
+            let actual = console.text();
+            let passed = match operator {
+                ComparisonOp::Equal => actual == *expected,
+                ComparisonOp::NotEqual => actual != *expected,
+            };
+            AssertionResult {
+                assertion: assertion.clone(),
+                passed,
+                actual: format!("{:?}", actual),
+            }
+        }
+        Assertion::Exit { operator, expected } => {
+            let actual = console.exit_status();
+            let passed = match (operator, actual) {
+                (_, None) => false,
+                (ComparisonOp::Equal, Some(status)) => status == *expected,
+                (ComparisonOp::NotEqual, Some(status)) => status != *expected,
+            };
+            AssertionResult {
+                assertion: assertion.clone(),
+                passed,
+                actual: actual.map_or_else(|| "no exit".to_string(), |status| status.to_string()),
+            }
+        }
+        Assertion::FaultRecord {
+            field,
+            operator,
+            expected,
+        } => {
+            let address = usize::from(field.address());
+            let actual = u16::from_be_bytes([state.memory[address], state.memory[address + 1]]);
+            let passed = match operator {
+                ComparisonOp::Equal => actual == *expected,
+                ComparisonOp::NotEqual => actual != *expected,
+            };
+            AssertionResult {
+                assertion: assertion.clone(),
+                passed,
+                actual: format!("{:#06X}", actual),
+            }
+        }
+        Assertion::Frozen { register, expected } => {
+            let actual = read_register(state, *register);
+            AssertionResult {
+                assertion: assertion.clone(),
+                passed: actual == *expected,
+                actual: format!("{:#06X}", actual),
+            }
+        }
+    }
+}
+
+/// Result of an `executed label` assertion, given whether the traced block
+/// reached the label (`None` when the block ran untraced).
+fn executed_result(assertion: &Assertion, actual: Option<bool>, expected: bool) -> AssertionResult {
+    AssertionResult {
+        assertion: assertion.clone(),
+        passed: actual == Some(expected),
+        actual: match actual {
+            Some(true) => "executed".to_string(),
+            Some(false) => "never executed".to_string(),
+            None => "not traced".to_string(),
+        },
+    }
+}
+
+/// Result of a `retired` assertion, given how many instructions the traced
+/// block retired (`None` when the block ran untraced).
+fn retired_result(
+    assertion: &Assertion,
+    actual: Option<u64>,
+    operator: CountOp,
+    expected: u64,
+) -> AssertionResult {
+    AssertionResult {
+        assertion: assertion.clone(),
+        passed: actual.is_some_and(|actual| operator.holds(actual, expected)),
+        actual: actual.map_or_else(
+            || "not traced".to_string(),
+            |actual| format!("{} instructions", actual),
+        ),
     }
 }
 
@@ -338,6 +910,49 @@ fn read_register(state: &CoreState, register: Register) -> u16 {
     }
 }
 
+/// Compares expected screen text with the rendered screen, ignoring
+/// trailing spaces and trailing blank lines in the expectation the same way
+/// rendering drops them.
+fn screen_diff(expected: &str, rendered: &str) -> Option<String> {
+    let expected: Vec<&str> = expected.lines().map(str::trim_end).collect();
+    unified_diff(expected.join("\n").trim_end(), rendered)
+}
+
+/// The bus test blocks run against: a TELE-7 and a debug console at their
+/// MMIO windows, with every other address behaving like [`NullMmio`].
+#[derive(Default)]
+struct TestBus {
+    tele7: Tele7Peripheral,
+    console: DebugConsole,
+}
+
+impl MmioBus for TestBus {
+    fn read16(&mut self, addr: u16) -> Result<u16, MmioError> {
+        if (TELE7_BASE..=TELE7_END).contains(&addr) {
+            self.tele7.read16(addr)
+        } else if (CONSOLE_BASE..=CONSOLE_END).contains(&addr) {
+            self.console.read16(addr)
+        } else {
+            NullMmio.read16(addr)
+        }
+    }
+
+    fn write16(&mut self, addr: u16, value: u16) -> Result<MmioWriteResult, MmioError> {
+        if (TELE7_BASE..=TELE7_END).contains(&addr) {
+            self.tele7.write16(addr, value)
+        } else if (CONSOLE_BASE..=CONSOLE_END).contains(&addr) {
+            self.console.write16(addr, value)
+        } else {
+            NullMmio.write16(addr, value)
+        }
+    }
+
+    fn reset(&mut self) {
+        self.tele7.reset();
+        self.console.reset();
+    }
+}
+
 /// A null MMIO bus that returns 0 on reads and denies all writes.
 struct NullMmio;
 
@@ -353,7 +968,9 @@ impl MmioBus for NullMmio {
 
 impl fmt::Display for TestBlockResult {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.passed() {
+        if self.skipped && self.passed() {
+            write!(f, "SKIP (lines {}-{})", self.start_line, self.end_line)
+        } else if self.passed() {
             write!(
                 f,
                 "PASS (lines {}-{}): {} assertions",
@@ -375,13 +992,16 @@ impl fmt::Display for TestBlockResult {
                 .iter()
                 .filter(|r| !r.passed)
                 .collect();
-            write!(
-                f,
-                "FAIL (lines {}-{}): {} assertion(s) failed",
-                self.start_line,
-                self.end_line,
-                failures.len()
-            )
+            write!(f, "FAIL (lines {}-{}): ", self.start_line, self.end_line)?;
+            match (failures.len(), self.screen_diff.is_some()) {
+                (0, true) => write!(f, "screen differs from n1expect-screen"),
+                (count, true) => write!(
+                    f,
+                    "{} assertion(s) failed, screen differs from n1expect-screen",
+                    count
+                ),
+                (count, false) => write!(f, "{} assertion(s) failed", count),
+            }
         }
     }
 }
@@ -403,6 +1023,9 @@ impl fmt::Display for AssertionResult {
 impl fmt::Display for TestSummary {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{} passed, {} failed", self.passed, self.failed)?;
+        if self.skipped > 0 {
+            write!(f, ", {} skipped", self.skipped)?;
+        }
         if self.unexecuted > 0 {
             write!(f, ", {} unexecuted", self.unexecuted)?;
         }
@@ -466,8 +1089,14 @@ mod tests {
 
         let test_block = parse_test_block("R0 == 0x1234", 1, 3).unwrap();
 
-        let mut mmio = NullMmio;
-        let result = run_test_block(&mut state, &CoreConfig::default(), &mut mmio, &test_block);
+        let mut mmio = TestBus::default();
+        let result = run_test_block(
+            &mut state,
+            &CoreConfig::default(),
+            &mut mmio,
+            &test_block,
+            None,
+        );
 
         assert!(result.passed());
     }
@@ -484,8 +1113,14 @@ mod tests {
 
         let test_block = parse_test_block("R0 == 0x5678", 1, 3).unwrap();
 
-        let mut mmio = NullMmio;
-        let result = run_test_block(&mut state, &CoreConfig::default(), &mut mmio, &test_block);
+        let mut mmio = TestBus::default();
+        let result = run_test_block(
+            &mut state,
+            &CoreConfig::default(),
+            &mut mmio,
+            &test_block,
+            None,
+        );
 
         assert!(!result.passed());
         assert_eq!(result.assertion_results[0].actual, "0x1234");
@@ -503,8 +1138,14 @@ mod tests {
 
         let test_block = parse_test_block("R0 == 0x1111\nR1 == 0x2222", 1, 5).unwrap();
 
-        let mut mmio = NullMmio;
-        let result = run_test_block(&mut state, &CoreConfig::default(), &mut mmio, &test_block);
+        let mut mmio = TestBus::default();
+        let result = run_test_block(
+            &mut state,
+            &CoreConfig::default(),
+            &mut mmio,
+            &test_block,
+            None,
+        );
 
         assert!(result.passed());
         assert_eq!(result.assertion_results.len(), 2);
@@ -522,8 +1163,14 @@ mod tests {
 
         let test_block = parse_test_block("R0 == 0x1200", 1, 3).unwrap();
 
-        let mut mmio = NullMmio;
-        let result = run_test_block(&mut state, &CoreConfig::default(), &mut mmio, &test_block);
+        let mut mmio = TestBus::default();
+        let result = run_test_block(
+            &mut state,
+            &CoreConfig::default(),
+            &mut mmio,
+            &test_block,
+            None,
+        );
 
         assert!(result.passed());
     }
@@ -561,8 +1208,14 @@ mod tests {
 
         let test_block = parse_test_block("[0x4000] == 0x12", 1, 5).unwrap();
 
-        let mut mmio = NullMmio;
-        let result = run_test_block(&mut state, &CoreConfig::default(), &mut mmio, &test_block);
+        let mut mmio = TestBus::default();
+        let result = run_test_block(
+            &mut state,
+            &CoreConfig::default(),
+            &mut mmio,
+            &test_block,
+            None,
+        );
 
         assert!(result.passed());
     }
@@ -579,8 +1232,14 @@ mod tests {
 
         let test_block = parse_test_block("R0 != 0x0000", 1, 3).unwrap();
 
-        let mut mmio = NullMmio;
-        let result = run_test_block(&mut state, &CoreConfig::default(), &mut mmio, &test_block);
+        let mut mmio = TestBus::default();
+        let result = run_test_block(
+            &mut state,
+            &CoreConfig::default(),
+            &mut mmio,
+            &test_block,
+            None,
+        );
 
         assert!(result.passed());
     }
@@ -597,12 +1256,498 @@ mod tests {
 
         let test_block = parse_test_block("PC == 0x0004", 1, 3).unwrap();
 
-        let mut mmio = NullMmio;
-        let result = run_test_block(&mut state, &CoreConfig::default(), &mut mmio, &test_block);
+        let mut mmio = TestBus::default();
+        let result = run_test_block(
+            &mut state,
+            &CoreConfig::default(),
+            &mut mmio,
+            &test_block,
+            None,
+        );
 
         assert!(result.passed());
     }
 
+    #[test]
+    fn run_tests_from_starts_at_entry() {
+        let mut binary = encode_halt();
+        binary.extend(encode_nop());
+        binary.extend(encode_halt());
+        let blocks = [parse_test_block("PC == 0x0006", 1, 3).unwrap()];
+
+        let symbols = SymbolTable::new();
+        let program = TestProgram {
+            binary: &binary,
+            entry: 2,
+            symbols: &symbols,
+            fixture_dir: None,
+            capture_fixtures: false,
+            strict: false,
+            tags: &[],
+            skip_tags: &[],
+        };
+        assert!(run_program_tests(&program, &blocks).all_passed());
+        assert!(!run_tests(&binary, &blocks).all_passed());
+    }
+
+    #[test]
+    fn setup_lines_start_blocks_at_labels() {
+        let source = "MOV R0, #1\nHALT\ndouble:\nADD R0, R0, R0\nHALT\n";
+        let program = crate::assembler::assemble_from_source(source, "setup.n1").unwrap();
+        let blocks = [
+            parse_test_block("R0 == 1", 1, 3).unwrap(),
+            parse_test_block("start at double\nsp = 0x7F00\nR0 == 2", 4, 8).unwrap(),
+            parse_test_block("start at double\nR0 == 4", 9, 12).unwrap(),
+        ];
+
+        let program = TestProgram::of(&program);
+        let mut state = new_test_state(&program);
+        let result = run_tests_on_state(&mut state, &program, &blocks);
+
+        assert!(result.all_passed(), "{:?}", result);
+        assert_eq!(state.arch.sp(), 0x7F00);
+    }
+
+    #[test]
+    fn param_lines_write_the_parameter_block() {
+        let blocks = [
+            parse_test_block(
+                "param lvl = 3\nparam name = \"A\"\n[0xDF00] == 0x50\n[0xDF0B] == 3\n[0xDF14] == 0x41",
+                1,
+                6,
+            )
+            .unwrap(),
+            parse_test_block("[0xDF0B] == 3", 7, 9).unwrap(),
+            parse_test_block("param other = 1\n[0xDF0D] == 1\n[0xDF14] == 0", 10, 14).unwrap(),
+        ];
+
+        let result = run_tests(&[0x00, 0x10, 0x00, 0x10, 0x00, 0x10], &blocks);
+
+        assert!(result.all_passed(), "{:?}", result);
+    }
+
+    #[test]
+    fn strict_mode_reports_clobbered_frozen_registers() {
+        let source = "MOV R4, #3\nHALT\nMOV R0, #1\nADD R5, R5, #1\nHALT\n";
+        let result = crate::assembler::assemble_from_source(source, "strict.n1").unwrap();
+        let blocks = [
+            parse_test_block("R4 == 3", 1, 3).unwrap(),
+            parse_test_block("frozen: R4-R5\nR0 == 1", 4, 7).unwrap(),
+        ];
+
+        let mut program = TestProgram::of(&result);
+        assert!(run_program_tests(&program, &blocks).all_passed());
+
+        program.strict = true;
+        let strict = run_program_tests(&program, &blocks);
+        assert!(!strict.all_passed());
+        let failures: Vec<_> = strict.block_results[1]
+            .assertion_results
+            .iter()
+            .filter(|r| !r.passed)
+            .collect();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(
+            failures[0].assertion,
+            Assertion::Frozen {
+                register: Register::R5,
+                expected: 0
+            }
+        );
+        assert_eq!(failures[0].actual, "0x0001");
+    }
+
+    #[test]
+    fn skipped_blocks_run_but_are_not_checked() {
+        let source = "MOV R0, #1\nHALT\nMOV R0, #2\nHALT\nMOV R0, #3\nHALT\n";
+        let result = crate::assembler::assemble_from_source(source, "skip.n1").unwrap();
+        let blocks = [
+            parse_test_block("skip: true\nR0 == 9", 1, 4).unwrap(),
+            parse_test_block("tags: slow\nR0 == 2", 5, 8).unwrap(),
+            parse_test_block("tags: display\nR0 == 3", 9, 12).unwrap(),
+        ];
+
+        let mut program = TestProgram::of(&result);
+        let run = run_program_tests(&program, &blocks);
+        assert!(run.all_passed());
+        assert!(run.block_results[0].skipped);
+        assert_eq!(run.block_results[0].to_string(), "SKIP (lines 1-4)");
+        assert_eq!(run.summary().to_string(), "2 passed, 0 failed, 1 skipped");
+
+        let skip = ["DISPLAY".to_string()];
+        program.skip_tags = &skip;
+        let summary = run_program_tests(&program, &blocks).summary();
+        assert_eq!((summary.passed, summary.skipped), (1, 2));
+
+        let only = ["slow".to_string()];
+        program.skip_tags = &[];
+        program.tags = &only;
+        let run = run_program_tests(&program, &blocks);
+        let skipped: Vec<_> = run.block_results.iter().map(|b| b.skipped).collect();
+        assert_eq!(skipped, [true, false, true]);
+
+        program.tags = &[];
+        let mut marked = blocks;
+        marked[2].only = true;
+        let run = run_program_tests(&program, &marked);
+        let skipped: Vec<_> = run.block_results.iter().map(|b| b.skipped).collect();
+        assert_eq!(skipped, [true, true, false]);
+        assert_eq!(run.summary().total, 3);
+    }
+
+    #[test]
+    fn screen_blocks_compare_rendered_tele7_text() {
+        let source = "```n1asm
+MOV R0, #0x4000
+MOV R1, #0x4849
+STORE R1, [R0]
+MOV R0, #0xE122
+MOV R1, #1
+STORE R1, [R0]
+HALT
+HALT
+```
+
+```n1expect-screen
+HI
+```
+
+```n1expect-screen
+HO
+```
+";
+        let result = crate::assembler::assemble_from_source(source, "screen.n1.md").unwrap();
+        let blocks: Vec<_> = result
+            .test_blocks
+            .iter()
+            .map(|tbc| crate::test_format::parse_source_test_block(&tbc.block).unwrap())
+            .collect();
+
+        let run = run_program_tests(&TestProgram::of(&result), &blocks);
+        assert!(run.block_results[0].passed(), "{:?}", run.block_results[0]);
+        let mismatch = &run.block_results[1];
+        assert_eq!(
+            mismatch.screen_diff.as_deref(),
+            Some("--- expected\n+++ actual\n@@ -1 +1 @@\n-HO\n+HI\n")
+        );
+        assert_eq!(
+            mismatch.to_string(),
+            "FAIL (lines 16-18): screen differs from n1expect-screen"
+        );
+    }
+
+    #[test]
+    fn console_assertions_see_output_written_during_the_block() {
+        let source = "MOV R0, #0xE132
+MOV R1, #0x4F
+STORE R1, [R0]
+MOV R1, #0x4B
+STORE R1, [R0]
+MOV R1, #0x0A
+STORE R1, [R0]
+HALT
+MOV R1, #0x58
+STORE R1, [R0]
+HALT
+";
+        let result = crate::assembler::assemble_from_source(source, "console.n1").unwrap();
+        let blocks = vec![
+            parse_test_block("console == \"OK\\n\"", 1, 3).unwrap(),
+            parse_test_block("console == \"Y\"", 4, 6).unwrap(),
+        ];
+
+        let run = run_program_tests(&TestProgram::of(&result), &blocks);
+        assert!(run.block_results[0].passed(), "{:?}", run.block_results[0]);
+        let mismatch = &run.block_results[1].assertion_results[0];
+        assert!(!mismatch.passed);
+        assert_eq!(mismatch.actual, "\"X\"");
+    }
+
+    #[test]
+    fn exit_assertions_need_an_exit_during_the_block() {
+        let source = "MOV R0, #0xE133\nMOV R1, #3\nSTORE R1, [R0]\nHALT\nHALT\n";
+        let result = crate::assembler::assemble_from_source(source, "exit.n1").unwrap();
+        let blocks = vec![
+            parse_test_block("exit == 3\nexit != 0", 1, 4).unwrap(),
+            parse_test_block("exit != 3", 5, 7).unwrap(),
+        ];
+
+        let run = run_program_tests(&TestProgram::of(&result), &blocks);
+        assert!(run.block_results[0].passed(), "{:?}", run.block_results[0]);
+        let never_exited = &run.block_results[1].assertion_results[0];
+        assert!(!never_exited.passed);
+        assert_eq!(never_exited.actual, "no exit");
+    }
+
+    #[test]
+    fn path_assertions_see_the_instructions_the_block_retired() {
+        let source = "main:
+    CALL #init_display
+    HALT
+    CALL #debug_stub
+    HALT
+init_display:
+    NOP
+    RET
+debug_stub:
+    RET
+";
+        let result = crate::assembler::assemble_from_source(source, "path.n1").unwrap();
+        let blocks = vec![
+            parse_test_block(
+                "executed label init_display\nnever executed label debug_stub\nretired == 4",
+                1,
+                4,
+            )
+            .unwrap(),
+            parse_test_block("never executed label debug_stub\nretired <= 1", 5, 7).unwrap(),
+        ];
+
+        let run = run_program_tests(&TestProgram::of(&result), &blocks);
+        assert!(run.block_results[0].passed(), "{:?}", run.block_results[0]);
+        let failures: Vec<&str> = run.block_results[1]
+            .assertion_results
+            .iter()
+            .map(|result| result.actual.as_str())
+            .collect();
+        assert_eq!(failures, ["executed", "3 instructions"]);
+        assert!(!run.block_results[1].passed());
+
+        let unknown = [parse_test_block("executed label missing", 1, 3).unwrap()];
+        let run = run_program_tests(&TestProgram::of(&result), &unknown);
+        assert_eq!(
+            run.block_results[0].fault_message.as_deref(),
+            Some("Unknown label 'missing' in 'executed label'")
+        );
+    }
+
+    #[test]
+    fn reset_blocks_start_from_a_fresh_machine() {
+        let source = "ADD R0, R0, #1\nHALT\nJMP #0\n";
+        let program = crate::assembler::assemble_from_source(source, "reset.n1").unwrap();
+        let blocks = [
+            parse_test_block("R0 == 1", 1, 3).unwrap(),
+            parse_test_block("R0 == 2", 4, 6).unwrap(),
+            parse_test_block("reset: true\nR0 == 1", 7, 10).unwrap(),
+        ];
+
+        let result = run_program_tests(&TestProgram::of(&program), &blocks);
+
+        assert!(result.all_passed(), "{:?}", result);
+    }
+
+    #[test]
+    fn trap_runs_an_installed_handler() {
+        let source = "TRAP\nHALT\n.org 0x0008\n.word 0x0010\n.org 0x0010\nMOV R1, #7\nHALT\n";
+        let program = crate::assembler::assemble_from_source(source, "trap.n1").unwrap();
+        let blocks = [parse_test_block("R1 == 7", 1, 3).unwrap()];
+        let result = run_program_tests(&TestProgram::of(&program), &blocks);
+        assert!(result.all_passed(), "{:?}", result);
+
+        let stray = crate::assembler::assemble_from_source("TRAP\nHALT\n", "stray.n1").unwrap();
+        let result = run_program_tests(&TestProgram::of(&stray), &blocks);
+        let message = result.block_results[0].fault_message.as_deref().unwrap();
+        assert!(message.contains("Unexpected TRAP dispatch"), "{message}");
+    }
+
+    #[test]
+    fn reset_block_recovers_after_fault() {
+        let mut binary = encode_halt();
+        binary.extend([0xFF, 0xFF]);
+        let blocks = [
+            parse_test_block("PC == 2", 1, 3).unwrap(),
+            parse_test_block("PC == 4", 4, 6).unwrap(),
+            parse_test_block("PC == 4", 7, 9).unwrap(),
+            parse_test_block("reset: true\nPC == 2", 10, 13).unwrap(),
+        ];
+
+        let result = run_tests(&binary, &blocks);
+
+        let passed: Vec<bool> = result.block_results.iter().map(|b| b.passed()).collect();
+        assert_eq!(passed, vec![true, false, true]);
+        assert_eq!(result.unexecuted_blocks, 1);
+        assert_eq!(result.block_results[2].start_line, 10);
+    }
+
+    #[test]
+    fn fixture_blocks_resume_from_captured_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = "ADD R0, R0, #1\nHALT\nADD R0, R0, #10\nHALT\n";
+        let result = crate::assembler::assemble_from_source(source, "fixture.n1").unwrap();
+        let mut program = TestProgram::of(&result);
+        program.fixture_dir = Some(dir.path());
+
+        let capture = [parse_test_block("capture: boot.n1snap\nR0 == 1", 1, 4).unwrap()];
+        assert!(run_program_tests(&program, &capture).captures.is_empty());
+
+        program.capture_fixtures = true;
+        let captured = run_program_tests(&program, &capture);
+        assert_eq!(captured.captures.len(), 1);
+        assert_eq!(captured.captures[0].path, dir.path().join("boot.n1snap"));
+        captured.captures[0].write().unwrap();
+
+        program.capture_fixtures = false;
+        let blocks = [
+            parse_test_block("R0 == 1", 1, 3).unwrap(),
+            parse_test_block("fixture: boot.n1snap\nR0 == 11", 4, 7).unwrap(),
+        ];
+        let resumed = run_program_tests(&program, &blocks);
+        assert!(resumed.all_passed(), "{:?}", resumed.block_results);
+    }
+
+    #[test]
+    fn missing_or_stale_fixture_fails_block() {
+        let dir = tempfile::tempdir().unwrap();
+        let symbols = SymbolTable::new();
+        let binary = encode_halt();
+        let mut rebuilt = binary.clone();
+        rebuilt.extend(encode_add(1, 1));
+        let mut program = TestProgram {
+            binary: &binary,
+            entry: 0,
+            symbols: &symbols,
+            fixture_dir: Some(dir.path()),
+            capture_fixtures: true,
+            strict: false,
+            tags: &[],
+            skip_tags: &[],
+        };
+        let blocks = [parse_test_block("fixture: boot.n1snap\nPC == 2", 1, 4).unwrap()];
+
+        let missing = run_program_tests(&program, &blocks);
+        let message = missing.block_results[0].fault_message.as_deref().unwrap();
+        assert!(message.starts_with("Cannot read fixture"), "{message}");
+
+        FixtureCapture {
+            path: dir.path().join("boot.n1snap"),
+            state: new_test_state(&program),
+        }
+        .write()
+        .unwrap();
+        assert!(run_program_tests(&program, &blocks).all_passed());
+
+        program.binary = &rebuilt;
+        let stale = run_program_tests(&program, &blocks);
+        let message = stale.block_results[0].fault_message.as_deref().unwrap();
+        assert!(message.contains("different build"), "{message}");
+    }
+
+    #[test]
+    fn scheduled_events_wake_ewait() {
+        let source = "EWAIT\nEGET R1\nEGET R2\nHALT\n";
+        let program = crate::assembler::assemble_from_source(source, "events.n1").unwrap();
+        let blocks = [parse_test_block(
+            "at tick 2 enqueue event 0x07\nat tick 2 enqueue event 9\nR1 == 7\nR2 == 9\nPC == 8",
+            1,
+            7,
+        )
+        .unwrap()];
+
+        let result = run_program_tests(&TestProgram::of(&program), &blocks);
+
+        assert!(result.all_passed(), "{:?}", result.block_results);
+    }
+
+    #[test]
+    fn scheduled_event_dispatches_to_handler() {
+        let source = "JMP #main\n.org 0x000A\n.word 0x0020\nmain:\nEWAIT\nHALT\n\
+                      .org 0x0020\nADD R1, R1, #1\nHALT\n";
+        let assembled = crate::assembler::assemble_from_source(source, "handler.n1").unwrap();
+        let program = TestProgram::of(&assembled);
+        let blocks =
+            [parse_test_block("at tick 1 enqueue event 0x05\nR0 == 5\nR1 == 1", 1, 5).unwrap()];
+
+        let mut state = new_test_state(&program);
+        state.arch.set_flags(0x10);
+        let result = run_tests_on_state(&mut state, &program, &blocks);
+
+        assert!(result.all_passed(), "{:?}", result.block_results);
+        assert!(matches!(state.run_state, RunState::HaltedForTick));
+    }
+
+    #[test]
+    fn halt_on_budget_boundary_ends_block() {
+        // 639 one-cycle NOPs leave HALT retiring as the tick's last cycle.
+        let mut binary = [0x00, 0x00].repeat(639);
+        binary.extend(encode_halt());
+        let blocks = [parse_test_block("PC == 1280", 1, 3).unwrap()];
+
+        let result = run_tests(&binary, &blocks);
+
+        assert!(result.all_passed(), "{:?}", result.block_results);
+    }
+
+    #[test]
+    fn ewait_without_events_fails_fast() {
+        let source = "MOV R0, #1\nEWAIT\nHALT\n";
+        let program = crate::assembler::assemble_from_source(source, "wait.n1").unwrap();
+        let blocks = [parse_test_block("R0 == 1", 1, 3).unwrap()];
+
+        let result = run_program_tests(&TestProgram::of(&program), &blocks);
+
+        assert_eq!(
+            result.block_results[0].fault_message.as_deref(),
+            Some(
+                "Program is waiting for an event that is never enqueued (EWAIT at 0x0004, tick 0)"
+            )
+        );
+    }
+
+    #[test]
+    fn every_tick_assertions_catch_transient_glitches() {
+        // Each delay loop runs for more than a tick, so [0x4000] is zero at
+        // the end of tick 1 and restored before HALT.
+        let source = "MOV R0, #0x4000\nMOV R1, #0x0101\nSTORE R1, [R0]\n\
+                      MOV R2, #300\nfirst:\nSUB R2, R2, #1\nBNE #first\n\
+                      MOV R1, #0\nSTORE R1, [R0]\n\
+                      MOV R2, #300\nsecond:\nSUB R2, R2, #1\nBNE #second\n\
+                      MOV R1, #0x0101\nSTORE R1, [R0]\nHALT\n";
+        let program = crate::assembler::assemble_from_source(source, "glitch.n1").unwrap();
+        let program = TestProgram::of(&program);
+
+        let blocks = [parse_test_block("[0x4000] == 0x01", 1, 3).unwrap()];
+        assert!(run_program_tests(&program, &blocks).all_passed());
+
+        let blocks = [parse_test_block(
+            "every tick assert R0 == 0x4000\nevery tick assert [0x4000] != 0x00\n[0x4000] == 0x01",
+            1,
+            5,
+        )
+        .unwrap()];
+        let result = run_program_tests(&program, &blocks);
+        assert_eq!(
+            result.block_results[0].fault_message.as_deref(),
+            Some(
+                "every tick assertion failed at tick 1: Memory { address: 16384, \
+                 operator: NotEqual, expected: 0 } (got 0x00)"
+            )
+        );
+    }
+
+    #[test]
+    fn undelivered_scheduled_event_fails_block() {
+        let blocks = [parse_test_block("at tick 3 enqueue event 1\nPC == 2", 1, 4).unwrap()];
+        let result = run_tests(&encode_halt(), &blocks);
+
+        assert!(!result.all_passed());
+        assert_eq!(
+            result.block_results[0].fault_message.as_deref(),
+            Some("Event 0x01 scheduled at tick 3 was not delivered: HALT reached at tick 0")
+        );
+    }
+
+    #[test]
+    fn unknown_start_label_fails_block() {
+        let blocks = [parse_test_block("start at nowhere\nR0 == 0", 1, 4).unwrap()];
+        let result = run_tests(&encode_halt(), &blocks);
+
+        assert!(!result.all_passed());
+        assert_eq!(
+            result.block_results[0].fault_message.as_deref(),
+            Some("Unknown label 'nowhere' in 'start at'")
+        );
+    }
+
     #[test]
     fn zero_test_blocks() {
         let mut state = CoreState::with_config(&CoreConfig::default());
@@ -682,24 +1827,54 @@ mod tests {
 
         let test_block = parse_test_block("R0 == 0x0000", 1, 3).unwrap();
 
-        let mut mmio = NullMmio;
-        let result = run_test_block(&mut state, &CoreConfig::default(), &mut mmio, &test_block);
+        let mut mmio = TestBus::default();
+        let result = run_test_block(
+            &mut state,
+            &CoreConfig::default(),
+            &mut mmio,
+            &test_block,
+            None,
+        );
 
         assert!(!result.passed());
         assert!(result.faulted);
         assert!(result.fault_message.is_some());
     }
 
+    #[test]
+    fn fault_message_lists_recent_pcs() {
+        let config = CoreConfig {
+            pc_history_depth: FAULT_PC_HISTORY_DEPTH,
+            ..CoreConfig::default()
+        };
+        let mut state = CoreState::with_config(&config);
+
+        // NOP, NOP, then an illegal word.
+        load_binary(&mut state, &[0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF]);
+
+        let test_block = parse_test_block("R0 == 0x0000", 1, 3).unwrap();
+
+        let mut mmio = TestBus::default();
+        let result = run_test_block(&mut state, &config, &mut mmio, &test_block, None);
+
+        assert!(result.faulted);
+        let message = result.fault_message.expect("fault message");
+        assert!(
+            message.ends_with("(recent PCs: 0x0000, 0x0002)"),
+            "unexpected message: {message}"
+        );
+    }
+
     fn run_tests_with_state(
         state: &mut CoreState,
         test_blocks: &[ParsedTestBlock],
     ) -> TestRunResult {
         let config = CoreConfig::default();
-        let mut mmio = NullMmio;
+        let mut mmio = TestBus::default();
         let mut block_results = Vec::new();
 
         for block in test_blocks {
-            let result = run_test_block(state, &config, &mut mmio, block);
+            let result = run_test_block(state, &config, &mut mmio, block, None);
             block_results.push(result);
 
             if matches!(state.run_state, RunState::FaultLatched(_)) {
@@ -707,6 +1882,7 @@ mod tests {
                 return TestRunResult {
                     block_results,
                     unexecuted_blocks: remaining,
+                    captures: Vec::new(),
                 };
             }
         }
@@ -714,6 +1890,7 @@ mod tests {
         TestRunResult {
             block_results,
             unexecuted_blocks: 0,
+            captures: Vec::new(),
         }
     }
 }
@@ -0,0 +1,642 @@
+//! Trace files, program maps and the symbolic trace dump.
+//!
+//! `nullbyte-asm run --trace` records every instruction the core runs as a
+//! line-based trace file, and `nullbyte-asm build --map` writes the
+//! program's source map and symbols next to the binary. [`dump_trace`]
+//! combines the two into an execution story: each retired instruction with
+//! its source line and cycle cost, the RAM and MMIO words it touched, the
+//! labels execution passed through, faults, and where each tick ended.
+//!
+//! Both files are plain text with a version header, one record per line:
+//!
+//! ```text
+//! nullbyte-trace 1          nullbyte-map 1
+//! timing v1                 symbol<TAB>0x0000<TAB>main
+//! exec 0x0008 0x2281
+//! read 0x4000 0x0041        line<TAB>0x0008<TAB>2<TAB>game.n1<TAB>5<TAB>LOAD R1, [R2]
+//! retire 0x0008 2
+//! tick-end 7
+//! ```
+//!
+//! A trace names the core's timing model on its second line, `timing NAME`,
+//! or `timing custom` followed by every cycle cost in
+//! [`CYCLE_COST_TABLE`] order; traces without it were recorded with `v1`.
+//! Trace records are `exec PC WORD`, `read`/`write`/`mmio-read`/
+//! `mmio-write ADDR VALUE`, `retire PC CYCLES`, `fault PC CAUSE` and
+//! `tick-end CYCLES`. Map fields are tab-separated so file names and source
+//! text may contain spaces.
+
+use std::fmt::{self, Write as _};
+
+use emulator_core::{
+    CycleCostTable, FaultCode, TimingModel, TraceEvent, TraceSink, CYCLE_COST_TABLE,
+};
+
+use crate::assembler::AssembleResult;
+
+/// First line of every trace file.
+pub const TRACE_HEADER: &str = "nullbyte-trace 1";
+
+/// First line of every map file.
+pub const MAP_HEADER: &str = "nullbyte-map 1";
+
+/// A malformed trace or map file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceFileError {
+    /// 1-indexed line of the problem.
+    pub line: usize,
+    /// What was wrong.
+    pub message: String,
+}
+
+impl fmt::Display for TraceFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for TraceFileError {}
+
+/// A [`TraceSink`] that formats events as trace file lines.
+#[derive(Debug, Clone)]
+pub struct TraceWriter {
+    text: String,
+}
+
+impl Default for TraceWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TraceWriter {
+    /// Creates a trace holding only the header.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            text: format!("{TRACE_HEADER}\n"),
+        }
+    }
+
+    /// Creates a trace holding the header and the `timing` record.
+    #[must_use]
+    pub fn with_timing(timing: &TimingModel) -> Self {
+        let mut writer = Self::new();
+        writer.text.push_str("timing ");
+        writer.text.push_str(timing.name());
+        if let TimingModel::Custom(table) = timing {
+            for (_, cycles) in table.entries() {
+                let _ = write!(writer.text, " {cycles}");
+            }
+        }
+        writer.text.push('\n');
+        writer
+    }
+
+    /// Returns the trace file text so far.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+impl TraceSink for TraceWriter {
+    fn on_event(&mut self, event: TraceEvent) {
+        self.text.push_str(&format_event(&event));
+        self.text.push('\n');
+    }
+}
+
+/// Formats one event as a trace file line, without the newline.
+#[must_use]
+pub fn format_event(event: &TraceEvent) -> String {
+    match *event {
+        TraceEvent::InstructionStart { pc, raw_word } => {
+            format!("exec 0x{pc:04X} 0x{raw_word:04X}")
+        }
+        TraceEvent::InstructionRetired { pc, cycles } => format!("retire 0x{pc:04X} {cycles}"),
+        TraceEvent::MemoryAccess {
+            addr,
+            value,
+            is_write,
+            is_mmio,
+        } => format!(
+            "{} 0x{addr:04X} 0x{value:04X}",
+            access_name(is_write, is_mmio)
+        ),
+        TraceEvent::FaultRaised { cause, pc } => {
+            format!("fault 0x{pc:04X} 0x{:02X}", cause.as_u8())
+        }
+        TraceEvent::TickEnded { cycles } => format!("tick-end {cycles}"),
+    }
+}
+
+const fn access_name(is_write: bool, is_mmio: bool) -> &'static str {
+    match (is_write, is_mmio) {
+        (false, false) => "read",
+        (true, false) => "write",
+        (false, true) => "mmio-read",
+        (true, true) => "mmio-write",
+    }
+}
+
+/// Parses a trace file written by [`TraceWriter`].
+///
+/// # Errors
+///
+/// Returns a [`TraceFileError`] for a missing header or a malformed line.
+pub fn parse_trace(text: &str) -> Result<Vec<TraceEvent>, TraceFileError> {
+    let mut lines = text.lines().enumerate();
+    expect_header(lines.next(), TRACE_HEADER)?;
+    lines
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with("timing "))
+        .map(|(index, line)| {
+            parse_event(line).map_err(|message| TraceFileError {
+                line: index + 1,
+                message,
+            })
+        })
+        .collect()
+}
+
+/// Reads the timing model a trace file was recorded with, [`TimingModel::V1`]
+/// when it has no `timing` record.
+///
+/// # Errors
+///
+/// Returns a [`TraceFileError`] for a missing header or a malformed
+/// `timing` record.
+pub fn parse_trace_timing(text: &str) -> Result<TimingModel, TraceFileError> {
+    let mut lines = text.lines().enumerate();
+    expect_header(lines.next(), TRACE_HEADER)?;
+    let Some((index, line)) = lines.find(|(_, line)| line.starts_with("timing ")) else {
+        return Ok(TimingModel::V1);
+    };
+    parse_timing(line).map_err(|message| TraceFileError {
+        line: index + 1,
+        message,
+    })
+}
+
+fn parse_timing(line: &str) -> Result<TimingModel, String> {
+    let fields: Vec<&str> = line.split_whitespace().skip(1).collect();
+    match fields.as_slice() {
+        ["custom", costs @ ..] if costs.len() == CYCLE_COST_TABLE.len() => {
+            let mut table = CycleCostTable::V1;
+            for ((kind, _), cycles) in CYCLE_COST_TABLE.iter().zip(costs) {
+                table = table.with_cost(*kind, parse_decimal(Some(cycles))?);
+            }
+            Ok(TimingModel::Custom(table))
+        }
+        ["custom", costs @ ..] => Err(format!(
+            "custom timing needs {} cycle costs, found {}",
+            CYCLE_COST_TABLE.len(),
+            costs.len()
+        )),
+        [name] => TimingModel::named(name).ok_or_else(|| format!("unknown timing model '{name}'")),
+        _ => Err(format!("malformed timing record '{line}'")),
+    }
+}
+
+fn expect_header(first: Option<(usize, &str)>, header: &str) -> Result<(), TraceFileError> {
+    match first {
+        Some((_, line)) if line.trim() == header => Ok(()),
+        _ => Err(TraceFileError {
+            line: 1,
+            message: format!("expected '{header}' header"),
+        }),
+    }
+}
+
+fn parse_event(line: &str) -> Result<TraceEvent, String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let access = |is_write, is_mmio| -> Result<TraceEvent, String> {
+        Ok(TraceEvent::MemoryAccess {
+            addr: parse_hex(fields.get(1))?,
+            value: parse_hex(fields.get(2))?,
+            is_write,
+            is_mmio,
+        })
+    };
+    let event = match fields.first().copied() {
+        Some("exec") => TraceEvent::InstructionStart {
+            pc: parse_hex(fields.get(1))?,
+            raw_word: parse_hex(fields.get(2))?,
+        },
+        Some("retire") => TraceEvent::InstructionRetired {
+            pc: parse_hex(fields.get(1))?,
+            cycles: parse_decimal(fields.get(2))?,
+        },
+        Some("read") => access(false, false)?,
+        Some("write") => access(true, false)?,
+        Some("mmio-read") => access(false, true)?,
+        Some("mmio-write") => access(true, true)?,
+        Some("fault") => {
+            let code = parse_hex(fields.get(2))?;
+            TraceEvent::FaultRaised {
+                pc: parse_hex(fields.get(1))?,
+                cause: u8::try_from(code)
+                    .ok()
+                    .and_then(FaultCode::from_u8)
+                    .ok_or_else(|| format!("unknown fault code 0x{code:02X}"))?,
+            }
+        }
+        Some("tick-end") => TraceEvent::TickEnded {
+            cycles: parse_decimal(fields.get(1))?,
+        },
+        _ => return Err(format!("unknown trace record '{line}'")),
+    };
+    Ok(event)
+}
+
+fn parse_hex(field: Option<&&str>) -> Result<u16, String> {
+    let field = field.ok_or_else(|| "missing field".to_string())?;
+    field
+        .strip_prefix("0x")
+        .and_then(|digits| u16::from_str_radix(digits, 16).ok())
+        .ok_or_else(|| format!("invalid hex word '{field}'"))
+}
+
+fn parse_decimal(field: Option<&&str>) -> Result<u16, String> {
+    let field = field.ok_or_else(|| "missing field".to_string())?;
+    field
+        .parse()
+        .map_err(|_| format!("invalid cycle count '{field}'"))
+}
+
+/// An emitted source line in a [`ProgramMap`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapLine {
+    /// Address of the line's first byte.
+    pub address: u16,
+    /// Number of bytes the line emitted.
+    pub len_bytes: usize,
+    /// File containing the line.
+    pub file: String,
+    /// 1-indexed line number within `file`.
+    pub line: usize,
+    /// Source text of the line.
+    pub source: String,
+}
+
+/// Source map and symbols of an assembled program.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProgramMap {
+    /// Emitted lines in assembly order.
+    pub lines: Vec<MapLine>,
+    /// Labels and their addresses, sorted by name.
+    pub symbols: Vec<(String, u16)>,
+}
+
+impl ProgramMap {
+    /// Builds the map of an assembled program.
+    #[must_use]
+    pub fn from_result(result: &AssembleResult) -> Self {
+        Self {
+            lines: result
+                .listing
+                .iter()
+                .filter(|entry| !entry.bytes.is_empty())
+                .map(|entry| MapLine {
+                    address: entry.address,
+                    len_bytes: entry.bytes.len(),
+                    file: entry.file.clone(),
+                    line: entry.line,
+                    source: entry.source.trim().to_string(),
+                })
+                .collect(),
+            symbols: result
+                .symbols
+                .iter()
+                .map(|(name, symbol)| (name.clone(), symbol.address))
+                .collect(),
+        }
+    }
+
+    /// Formats the map as a map file.
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{MAP_HEADER}\n");
+        for (name, address) in &self.symbols {
+            let _ = writeln!(text, "symbol\t0x{address:04X}\t{name}");
+        }
+        for line in &self.lines {
+            let _ = writeln!(
+                text,
+                "line\t0x{:04X}\t{}\t{}\t{}\t{}",
+                line.address, line.len_bytes, line.file, line.line, line.source
+            );
+        }
+        text
+    }
+
+    /// Parses a map file written by [`to_text`](Self::to_text).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`TraceFileError`] for a missing header or a malformed line.
+    pub fn parse(text: &str) -> Result<Self, TraceFileError> {
+        let mut lines = text.lines().enumerate();
+        expect_header(lines.next(), MAP_HEADER)?;
+        let mut map = Self::default();
+        for (index, line) in lines.filter(|(_, line)| !line.trim().is_empty()) {
+            let error = |message: String| TraceFileError {
+                line: index + 1,
+                message,
+            };
+            let fields: Vec<&str> = line.splitn(6, '\t').collect();
+            match fields.as_slice() {
+                ["symbol", address, name] => {
+                    map.symbols.push((
+                        (*name).to_string(),
+                        parse_hex(Some(address)).map_err(error)?,
+                    ));
+                }
+                ["line", address, len_bytes, file, line, source] => map.lines.push(MapLine {
+                    address: parse_hex(Some(address)).map_err(error)?,
+                    len_bytes: len_bytes
+                        .parse()
+                        .map_err(|_| error(format!("invalid length '{len_bytes}'")))?,
+                    file: (*file).to_string(),
+                    line: line
+                        .parse()
+                        .map_err(|_| error(format!("invalid line number '{line}'")))?,
+                    source: (*source).to_string(),
+                }),
+                _ => return Err(error(format!("unknown map record '{line}'"))),
+            }
+        }
+        Ok(map)
+    }
+
+    /// Returns the emitted line covering `address`.
+    #[must_use]
+    pub fn line_at(&self, address: u16) -> Option<&MapLine> {
+        self.lines.iter().find(|line| {
+            let start = usize::from(line.address);
+            (start..start + line.len_bytes).contains(&usize::from(address))
+        })
+    }
+
+    /// Returns the labels at exactly `address`, in name order.
+    pub fn labels_at(&self, address: u16) -> impl Iterator<Item = &str> {
+        self.symbols
+            .iter()
+            .filter(move |(_, symbol)| *symbol == address)
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+/// Formats a trace as an execution story, annotated from `map` when given.
+///
+/// Each retired instruction is one line with its address, source location
+/// and text (or raw word without a map), its cycle cost and the cycles used
+/// so far in the tick, followed by the memory and MMIO words it accessed.
+/// Labels are printed where execution reaches them, and every tick is
+/// bracketed by a header and its total.
+#[must_use]
+pub fn dump_trace(events: &[TraceEvent], map: Option<&ProgramMap>) -> String {
+    let mut dump = TraceDump {
+        map,
+        out: String::new(),
+        tick: 0,
+        tick_open: false,
+        tick_cycles: 0,
+        pending: None,
+    };
+    for event in events {
+        dump.event(*event);
+    }
+    dump.flush(None);
+    dump.out
+}
+
+/// An instruction whose start has been seen but not its retirement.
+struct PendingInstruction {
+    pc: u16,
+    raw_word: u16,
+    accesses: Vec<String>,
+}
+
+struct TraceDump<'a> {
+    map: Option<&'a ProgramMap>,
+    out: String,
+    tick: u32,
+    tick_open: bool,
+    tick_cycles: u32,
+    pending: Option<PendingInstruction>,
+}
+
+impl TraceDump<'_> {
+    fn event(&mut self, event: TraceEvent) {
+        match event {
+            TraceEvent::InstructionStart { pc, raw_word } => {
+                // Trap and event dispatch start the handler without a
+                // retirement record for the instruction before it.
+                self.flush(None);
+                if !self.tick_open {
+                    let _ = writeln!(self.out, "== tick {} ==", self.tick);
+                    self.tick_open = true;
+                }
+                if let Some(map) = self.map {
+                    for label in map.labels_at(pc) {
+                        let _ = writeln!(self.out, "{label}:");
+                    }
+                }
+                self.pending = Some(PendingInstruction {
+                    pc,
+                    raw_word,
+                    accesses: Vec::new(),
+                });
+            }
+            TraceEvent::MemoryAccess {
+                addr,
+                value,
+                is_write,
+                is_mmio,
+            } => {
+                let target = self.address_name(addr);
+                let arrow = if is_write { "<-" } else { "->" };
+                let line = format!(
+                    "{} {target} {arrow} 0x{value:04X}",
+                    access_name(is_write, is_mmio)
+                );
+                if let Some(pending) = self.pending.as_mut() {
+                    pending.accesses.push(line);
+                }
+            }
+            TraceEvent::InstructionRetired { cycles, .. } => self.flush(Some(cycles)),
+            TraceEvent::FaultRaised { cause, pc } => {
+                self.flush(None);
+                let _ = writeln!(self.out, "        FAULT {cause:?} at 0x{pc:04X}");
+            }
+            TraceEvent::TickEnded { cycles } => {
+                self.flush(None);
+                let _ = writeln!(self.out, "== end of tick {}: {cycles} cycles ==", self.tick);
+                self.tick += 1;
+                self.tick_open = false;
+                self.tick_cycles = 0;
+            }
+        }
+    }
+
+    /// Writes the pending instruction, with its cost when it retired.
+    fn flush(&mut self, cycles: Option<u16>) {
+        let Some(pending) = self.pending.take() else {
+            return;
+        };
+        let (location, text) = self
+            .map
+            .and_then(|map| map.line_at(pending.pc))
+            .map_or_else(
+                || (String::new(), format!("(0x{:04X})", pending.raw_word)),
+                |line| (format!("{}:{}", line.file, line.line), line.source.clone()),
+            );
+        let cost = cycles.map_or_else(
+            || "dispatch".to_string(),
+            |cycles| {
+                self.tick_cycles += u32::from(cycles);
+                format!("{cycles:>2} cyc  [{}]", self.tick_cycles)
+            },
+        );
+        let _ = writeln!(
+            self.out,
+            "0x{:04X}  {location:<20} {text:<28} {cost}",
+            pending.pc
+        );
+        for access in &pending.accesses {
+            let _ = writeln!(self.out, "        {access}");
+        }
+    }
+
+    /// `[0x4000]`, or `[score 0x4000]` when a label sits at the address.
+    fn address_name(&self, addr: u16) -> String {
+        self.map
+            .and_then(|map| map.labels_at(addr).next())
+            .map_or_else(
+                || format!("[0x{addr:04X}]"),
+                |label| format!("[{label} 0x{addr:04X}]"),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble_from_source;
+    use emulator_core::{run_ticks_with_trace, CompositeMmio, CoreConfig, DebugConsole};
+
+    use crate::test_runner::{new_test_state, TestProgram};
+
+    const SOURCE: &str = "\
+main:
+    MOV R0, #0xE132
+    MOV R2, #0x0010
+    LOAD R1, [R2]
+    STORE R1, [R0]
+    HALT
+.org 0x0010
+score:
+    .word 0x0041
+";
+
+    fn traced_run() -> (String, ProgramMap) {
+        let result = assemble_from_source(SOURCE, "story.n1").unwrap();
+        let mut state = new_test_state(&TestProgram::of(&result));
+        let mut mmio = CompositeMmio::new().with_console(DebugConsole::new());
+        let mut writer = TraceWriter::new();
+        run_ticks_with_trace(
+            &mut state,
+            &mut mmio,
+            &CoreConfig::default(),
+            1,
+            &mut writer,
+        );
+        (
+            writer.as_str().to_string(),
+            ProgramMap::from_result(&result),
+        )
+    }
+
+    #[test]
+    fn trace_files_round_trip() {
+        let (text, _) = traced_run();
+        assert!(text.starts_with("nullbyte-trace 1\nexec 0x0000 0x"));
+        let events = parse_trace(&text).unwrap();
+        let mut writer = TraceWriter::new();
+        for event in &events {
+            writer.on_event(*event);
+        }
+        assert_eq!(writer.as_str(), text);
+
+        let fault = TraceEvent::FaultRaised {
+            cause: FaultCode::IllegalEncoding,
+            pc: 0x0010,
+        };
+        assert_eq!(parse_event(&format_event(&fault)), Ok(fault));
+
+        let err = parse_trace("nullbyte-trace 1\nexec 0x0000\n").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(parse_trace("exec 0x0000 0x0000\n").is_err());
+    }
+
+    #[test]
+    fn trace_files_record_the_timing_model() {
+        assert_eq!(parse_trace_timing(&traced_run().0), Ok(TimingModel::V1));
+
+        let fast = TraceWriter::with_timing(&TimingModel::FastIo);
+        assert_eq!(fast.as_str(), "nullbyte-trace 1\ntiming fast-io\n");
+        assert_eq!(parse_trace_timing(fast.as_str()), Ok(TimingModel::FastIo));
+        assert_eq!(parse_trace(fast.as_str()), Ok(Vec::new()));
+
+        let custom = TimingModel::Custom(
+            CycleCostTable::V1.with_cost(emulator_core::CycleCostKind::Div, 12),
+        );
+        let writer = TraceWriter::with_timing(&custom);
+        assert_eq!(parse_trace_timing(writer.as_str()), Ok(custom));
+
+        let err = parse_trace_timing("nullbyte-trace 1\ntiming custom 1 2\n").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(parse_trace_timing("nullbyte-trace 1\ntiming turbo\n").is_err());
+    }
+
+    #[test]
+    fn map_files_round_trip() {
+        let (_, map) = traced_run();
+        assert_eq!(ProgramMap::parse(&map.to_text()), Ok(map.clone()));
+        assert_eq!(map.labels_at(0).collect::<Vec<_>>(), ["main"]);
+        assert_eq!(
+            map.line_at(0x0002).map(|line| line.source.as_str()),
+            Some("MOV R0, #0xE132")
+        );
+        assert!(ProgramMap::parse("nullbyte-map 1\nline\t0x0000\n").is_err());
+    }
+
+    #[test]
+    fn dump_interleaves_source_accesses_and_ticks() {
+        let (text, map) = traced_run();
+        let dump = dump_trace(&parse_trace(&text).unwrap(), Some(&map));
+        let lines: Vec<&str> = dump.lines().collect();
+
+        assert_eq!(lines[0], "== tick 0 ==");
+        assert_eq!(lines[1], "main:");
+        assert!(lines[2].starts_with("0x0000  story.n1:2"), "{dump}");
+        assert!(lines[2].contains("MOV R0, #0xE132"), "{dump}");
+        assert!(
+            lines.contains(&"        read [score 0x0010] -> 0x0041"),
+            "{dump}"
+        );
+        assert!(
+            lines.contains(&"        mmio-write [0xE132] <- 0x0041"),
+            "{dump}"
+        );
+        assert!(lines.iter().any(|line| line.contains("HALT")), "{dump}");
+        assert_eq!(*lines.last().unwrap(), "== end of tick 0: 7 cycles ==");
+
+        let bare = dump_trace(&parse_trace(&text).unwrap(), None);
+        assert!(
+            bare.contains("0x0000                       (0x1005)"),
+            "{bare}"
+        );
+    }
+}
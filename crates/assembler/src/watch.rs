@@ -0,0 +1,286 @@
+//! `test --watch` and `serve`: polls the files a program was built from
+//! and compares test outcomes between runs.
+
+use std::fmt;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use assembler::test_runner::TestRunResult;
+
+/// How often watched files are checked for changes.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Last-seen modification time and size of each watched file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceStamps {
+    stamps: Vec<(PathBuf, Option<(SystemTime, u64)>)>,
+}
+
+impl SourceStamps {
+    /// Records the current state of `paths`; missing files are recorded as
+    /// absent so their reappearance counts as a change.
+    pub fn capture(paths: &[PathBuf]) -> Self {
+        Self {
+            stamps: paths
+                .iter()
+                .map(|path| (path.clone(), stamp(path)))
+                .collect(),
+        }
+    }
+
+    /// Files whose modification time or size differs from when they were
+    /// captured.
+    pub fn changed(&self) -> Vec<&Path> {
+        self.stamps
+            .iter()
+            .filter(|(path, seen)| stamp(path) != *seen)
+            .map(|(path, _)| path.as_path())
+            .collect()
+    }
+
+    /// Blocks until at least one watched file changes and returns them.
+    pub fn wait_for_change(&self) -> Vec<&Path> {
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let changed = self.changed();
+            if !changed.is_empty() {
+                return changed;
+            }
+        }
+    }
+}
+
+/// Every file and directory under `dir`, skipping hidden entries, so a
+/// watch notices files being added as well as edited.
+pub fn files_under(dir: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![dir.to_path_buf()];
+    let mut index = 0;
+    while let Some(path) = paths.get(index) {
+        index += 1;
+        let Ok(entries) = fs::read_dir(path) else {
+            continue;
+        };
+        let mut children: Vec<PathBuf> = entries
+            .flatten()
+            .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+            .map(|entry| entry.path())
+            .collect();
+        children.sort();
+        paths.extend(children);
+    }
+    paths
+}
+
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Pass/fail state of one test block, in document order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockOutcome {
+    /// 1-indexed source line where the block starts.
+    pub start_line: usize,
+    /// 1-indexed source line where the block ends.
+    pub end_line: usize,
+    /// Whether the block passed; `false` for blocks that never ran.
+    pub passed: bool,
+}
+
+/// Outcomes of every test block in one run.
+pub fn block_outcomes(result: &TestRunResult, total: usize) -> Vec<BlockOutcome> {
+    let mut outcomes: Vec<_> = result
+        .block_results
+        .iter()
+        .map(|block| BlockOutcome {
+            start_line: block.start_line,
+            end_line: block.end_line,
+            passed: block.passed(),
+        })
+        .collect();
+    // Blocks past the last HALT have no result; keep their slots so later
+    // blocks line up between runs.
+    outcomes.resize(
+        total.max(outcomes.len()),
+        BlockOutcome {
+            start_line: 0,
+            end_line: 0,
+            passed: false,
+        },
+    );
+    outcomes
+}
+
+/// How a block's outcome moved between two runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutcomeChange {
+    /// Failed last run, passes now.
+    Fixed(BlockOutcome),
+    /// Passed last run, fails now.
+    Broken(BlockOutcome),
+    /// Did not exist last run.
+    Added(BlockOutcome),
+    /// Existed last run; `index` is its 1-based position.
+    Removed {
+        /// 1-based position of the block in the previous run.
+        index: usize,
+    },
+}
+
+impl OutcomeChange {
+    const fn is_good(self) -> bool {
+        match self {
+            Self::Fixed(_) => true,
+            Self::Added(outcome) => outcome.passed,
+            Self::Broken(_) | Self::Removed { .. } => false,
+        }
+    }
+
+    /// The change formatted for a terminal: `+` lines in green, `-` lines in
+    /// red when `color` is set.
+    pub fn render(self, color: bool) -> String {
+        let (sign, ansi) = if self.is_good() {
+            ('+', "32")
+        } else {
+            ('-', "31")
+        };
+        if color {
+            format!("\x1b[{ansi}m{sign} {self}\x1b[0m")
+        } else {
+            format!("{sign} {self}")
+        }
+    }
+}
+
+impl fmt::Display for OutcomeChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fixed(outcome) => write!(f, "now passing: {}", Lines(outcome)),
+            Self::Broken(outcome) => write!(f, "now failing: {}", Lines(outcome)),
+            Self::Added(outcome) => write!(
+                f,
+                "new block {}: {}",
+                Lines(outcome),
+                if outcome.passed { "PASS" } else { "FAIL" }
+            ),
+            Self::Removed { index } => write!(f, "block {index} removed"),
+        }
+    }
+}
+
+struct Lines<'a>(&'a BlockOutcome);
+
+impl fmt::Display for Lines<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.start_line == 0 {
+            write!(f, "(not reached)")
+        } else {
+            write!(f, "(lines {}-{})", self.0.start_line, self.0.end_line)
+        }
+    }
+}
+
+/// Compares two runs block by block in document order. Blocks are matched
+/// by position rather than line number, so editing code above a block does
+/// not read as a new block.
+pub fn outcome_changes(previous: &[BlockOutcome], current: &[BlockOutcome]) -> Vec<OutcomeChange> {
+    let mut changes = Vec::new();
+    for (index, outcome) in current.iter().enumerate() {
+        match previous.get(index) {
+            Some(before) if before.passed && !outcome.passed => {
+                changes.push(OutcomeChange::Broken(*outcome));
+            }
+            Some(before) if !before.passed && outcome.passed => {
+                changes.push(OutcomeChange::Fixed(*outcome));
+            }
+            Some(_) => {}
+            None => changes.push(OutcomeChange::Added(*outcome)),
+        }
+    }
+    changes.extend(
+        (current.len()..previous.len()).map(|index| OutcomeChange::Removed { index: index + 1 }),
+    );
+    changes
+}
+
+/// Whether to colour watch output: only when stdout is a terminal and
+/// `NO_COLOR` is unset.
+pub fn use_color() -> bool {
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn outcome(start_line: usize, passed: bool) -> BlockOutcome {
+        BlockOutcome {
+            start_line,
+            end_line: start_line + 3,
+            passed,
+        }
+    }
+
+    #[test]
+    fn unchanged_outcomes_report_nothing() {
+        let run = [outcome(5, true), outcome(12, false)];
+        let shifted = [outcome(7, true), outcome(14, false)];
+        assert!(outcome_changes(&run, &shifted).is_empty());
+    }
+
+    #[test]
+    fn reports_fixed_broken_added_and_removed_blocks() {
+        let previous = [outcome(5, false), outcome(12, true), outcome(20, true)];
+        let current = [outcome(5, true), outcome(12, false)];
+        assert_eq!(
+            outcome_changes(&previous, &current),
+            vec![
+                OutcomeChange::Fixed(outcome(5, true)),
+                OutcomeChange::Broken(outcome(12, false)),
+                OutcomeChange::Removed { index: 3 },
+            ]
+        );
+        assert_eq!(
+            outcome_changes(&current, &previous),
+            vec![
+                OutcomeChange::Broken(outcome(5, false)),
+                OutcomeChange::Fixed(outcome(12, true)),
+                OutcomeChange::Added(outcome(20, true)),
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_changes_with_optional_color() {
+        let fixed = OutcomeChange::Fixed(outcome(5, true));
+        assert_eq!(fixed.render(false), "+ now passing: (lines 5-8)");
+        assert_eq!(
+            fixed.render(true),
+            "\x1b[32m+ now passing: (lines 5-8)\x1b[0m"
+        );
+        let unreached = OutcomeChange::Added(BlockOutcome {
+            start_line: 0,
+            end_line: 0,
+            passed: false,
+        });
+        assert_eq!(unreached.render(false), "- new block (not reached): FAIL");
+    }
+
+    #[test]
+    fn detects_modified_and_deleted_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("main.n1");
+        fs::write(&source, "HALT\n").unwrap();
+        let stamps = SourceStamps::capture(std::slice::from_ref(&source));
+        assert!(stamps.changed().is_empty());
+
+        fs::write(&source, "NOP\nHALT\n").unwrap();
+        assert_eq!(stamps.changed(), vec![source.as_path()]);
+
+        fs::remove_file(&source).unwrap();
+        assert_eq!(stamps.changed(), vec![source.as_path()]);
+    }
+}
@@ -1,7 +1,8 @@
 //! Integration tests for the nullbyte-asm CLI.
 
 use assembler as _;
-use emulator_core as _;
+#[cfg(feature = "script")]
+use rhai as _;
 use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
@@ -108,6 +109,93 @@ fn build_reports_errors() {
     assert!(stderr.contains("error"));
 }
 
+#[test]
+fn build_accepts_bom_and_crlf_source() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = create_temp_file(temp_dir.path(), "win.n1", "\u{FEFF}MOV R0, #1\r\nHALT\r\n");
+
+    let output = Command::new(binary_path())
+        .args(["build", source.to_str().unwrap()])
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn build_reports_invalid_utf8_offset() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = temp_dir.path().join("bad.n1");
+    fs::write(&source, b"NOP\nMOV R0, \xFF\n").unwrap();
+
+    let output = Command::new(binary_path())
+        .args(["build", source.to_str().unwrap()])
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("invalid UTF-8 at byte offset 12"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn build_check_callconv_warns_on_clobber() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = create_temp_file(
+        temp_dir.path(),
+        "clobber.n1",
+        "CALL #work\nHALT\nwork:\nMOV R4, #1\nRET\n",
+    );
+
+    let plain = Command::new(binary_path())
+        .args(["build", source.to_str().unwrap()])
+        .output()
+        .expect("failed to run nullbyte-asm");
+    assert!(plain.status.success());
+    assert!(!String::from_utf8_lossy(&plain.stderr).contains("callee-saved"));
+
+    let checked = Command::new(binary_path())
+        .args(["build", source.to_str().unwrap(), "--check-callconv"])
+        .output()
+        .expect("failed to run nullbyte-asm");
+    assert!(checked.status.success());
+    let stderr = String::from_utf8_lossy(&checked.stderr);
+    assert!(stderr.contains("clobber.n1:4"));
+    assert!(stderr.contains("warning:"));
+    assert!(stderr.contains("callee-saved R4"));
+}
+
+#[test]
+fn build_optimize_reports_rewrites() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = create_temp_file(temp_dir.path(), "opt.n1", "MOV R1, R1\nMOV R0, #1\nHALT\n");
+    let output = temp_dir.path().join("opt.bin");
+
+    let result = Command::new(binary_path())
+        .args([
+            "build",
+            source.to_str().unwrap(),
+            "-o",
+            output.to_str().unwrap(),
+            "--optimize",
+        ])
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    assert!(result.status.success());
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.contains("opt.n1:1"));
+    assert!(stderr.contains("optimized: removed redundant MOV R1, R1"));
+    assert_eq!(fs::read(&output).unwrap().len(), 6);
+}
+
 #[test]
 fn build_verbose_prints_listing() {
     let temp_dir = tempfile::tempdir().unwrap();
@@ -166,6 +254,87 @@ fn test_with_passing_assertions() {
     assert!(stdout.contains("Test Summary"));
 }
 
+#[test]
+fn test_watch_reruns_when_an_include_changes() {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let lib = create_temp_file(temp_dir.path(), "lib.n1", "MOV R0, #1\n");
+    let source = create_temp_file(
+        temp_dir.path(),
+        "main.n1.md",
+        "```n1asm\n.include \"lib.n1\"\nHALT\n```\n\n```n1test\nR0 == 2\n```\n",
+    );
+
+    let mut child = Command::new(binary_path())
+        .args(["test", "--watch", source.to_str().unwrap()])
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to run nullbyte-asm");
+    let stdout = child.stdout.take().unwrap();
+    let (sender, lines) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            if sender.send(line.unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+    let mut seen = String::new();
+    let mut wait_for = |needle: &str| {
+        while !seen.contains(needle) {
+            let line = lines.recv_timeout(Duration::from_secs(10));
+            let Ok(line) = line else {
+                break;
+            };
+            seen.push_str(&line);
+            seen.push('\n');
+        }
+        seen.contains(needle)
+    };
+
+    let watching = wait_for("Watching 2 file(s)");
+    if watching {
+        fs::write(&lib, "MOV R0, #2 ; fixed\n").unwrap();
+    }
+    let fixed = watching && wait_for("+ now passing: (lines 6-8)");
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert!(fixed, "stdout: {seen}");
+    assert!(
+        seen.contains("lib.n1 changed, re-running"),
+        "stdout: {seen}"
+    );
+}
+
+#[test]
+fn test_reports_screen_diff() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = create_temp_file(
+        temp_dir.path(),
+        "screen.n1.md",
+        "```n1asm\nMOV R0, #0x4000\nMOV R1, #0x4F4B\nSTORE R1, [R0]\n\
+         MOV R0, #0xE122\nMOV R1, #1\nSTORE R1, [R0]\nHALT\n```\n\n\
+         ```n1expect-screen\nNO\n```\n",
+    );
+
+    let result = Command::new(binary_path())
+        .args(["test", source.to_str().unwrap()])
+        .output()
+        .expect("failed to run nullbyte-asm");
+    let stdout = String::from_utf8_lossy(&result.stdout);
+
+    assert!(!result.status.success());
+    assert!(
+        stdout.contains("screen differs from n1expect-screen"),
+        "stdout: {stdout}"
+    );
+    assert!(stdout.contains("    -NO\n    +OK\n"), "stdout: {stdout}");
+}
+
 #[test]
 fn test_with_no_test_blocks() {
     let temp_dir = tempfile::tempdir().unwrap();
@@ -182,6 +351,309 @@ fn test_with_no_test_blocks() {
     assert!(stdout.contains("No test blocks"));
 }
 
+#[test]
+fn verify_determinism_reports_identical_runs() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = create_temp_file(temp_dir.path(), "det.n1.md", PASSING_TEST_CONTENT);
+
+    let result = Command::new(binary_path())
+        .args([
+            "verify-determinism",
+            source.to_str().unwrap(),
+            "--runs",
+            "4",
+        ])
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    let stdout = String::from_utf8_lossy(&result.stdout);
+
+    assert!(result.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("4 fresh run(s)"));
+    assert!(stdout.contains("identical"));
+    assert!(stdout.contains("Final state digest: "));
+}
+
+#[test]
+fn run_executes_ticks_and_reports_cycles() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = create_temp_file(temp_dir.path(), "run.n1", "HALT\n");
+
+    let result = Command::new(binary_path())
+        .args(["run", source.to_str().unwrap(), "--ticks", "3"])
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    let stdout = String::from_utf8_lossy(&result.stdout);
+
+    assert!(result.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Ran 3 tick(s): 1281 steps, 1281 cycles, peak 640 cycles/tick"),
+        "stdout: {stdout}"
+    );
+}
+
+#[test]
+fn run_prints_debug_console_output() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = create_temp_file(
+        temp_dir.path(),
+        "run.n1",
+        "MOV R0, #0xE132\nMOV R1, #0x48\nSTORE R1, [R0]\nMOV R1, #0x49\nSTORE R1, [R0]\n\
+         idle:\nHALT\nJMP #idle\n",
+    );
+
+    let result = Command::new(binary_path())
+        .args(["run", source.to_str().unwrap(), "--ticks", "3"])
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    let stdout = String::from_utf8_lossy(&result.stdout);
+
+    assert!(result.status.success(), "stdout: {stdout}");
+    assert!(stdout.starts_with("HI\nRan 3 tick(s)"), "stdout: {stdout}");
+}
+
+#[test]
+fn run_exits_with_the_program_status() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = create_temp_file(
+        temp_dir.path(),
+        "run.n1",
+        "MOV R0, #0xE133\nMOV R1, #3\nSTORE R1, [R0]\nidle:\nHALT\nJMP #idle\n",
+    );
+
+    let result = Command::new(binary_path())
+        .args(["run", source.to_str().unwrap(), "--ticks", "50"])
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    let stdout = String::from_utf8_lossy(&result.stdout);
+
+    assert_eq!(result.status.code(), Some(3), "stdout: {stdout}");
+    assert!(stdout.contains("Ran 1 tick(s)"), "stdout: {stdout}");
+    assert!(stdout.contains("Exited with status 3"), "stdout: {stdout}");
+}
+
+#[test]
+fn run_fast_forward_skips_ahead_before_running() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = create_temp_file(temp_dir.path(), "run.n1", "HALT\n");
+
+    let result = Command::new(binary_path())
+        .args([
+            "run",
+            source.to_str().unwrap(),
+            "--fast-forward",
+            "10",
+            "--ticks",
+            "3",
+        ])
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    let stdout = String::from_utf8_lossy(&result.stdout);
+
+    assert!(result.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Ran 13 tick(s): 7681 steps, 7681 cycles"),
+        "stdout: {stdout}"
+    );
+}
+
+#[test]
+fn run_realtime_paces_ticks() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = create_temp_file(temp_dir.path(), "run.n1", "HALT\n");
+
+    let started = std::time::Instant::now();
+    let result = Command::new(binary_path())
+        .args([
+            "run",
+            source.to_str().unwrap(),
+            "--ticks",
+            "5",
+            "--realtime",
+        ])
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    assert!(result.status.success());
+    assert!(started.elapsed() >= std::time::Duration::from_millis(40));
+}
+
+#[test]
+fn listing_diff_reports_insertions_and_shifts_as_json() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let old = create_temp_file(
+        temp_dir.path(),
+        "old.lst",
+        "0000: 00 00            NOP ; p.n1:1\n0002: 00 10            HALT ; p.n1:2\n",
+    );
+    let new = create_temp_file(
+        temp_dir.path(),
+        "new.lst",
+        "0000: 00 00            NOP ; p.n1:1\n0002: 00 00            NOP ; p.n1:2\n\
+         0004: 00 10            HALT ; p.n1:3\n",
+    );
+
+    let result = Command::new(binary_path())
+        .args([
+            "listing-diff",
+            old.to_str().unwrap(),
+            new.to_str().unwrap(),
+            "--json",
+        ])
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    let stdout = String::from_utf8_lossy(&result.stdout);
+
+    assert!(result.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains(
+            "{\"kind\": \"added\", \"new\": {\"address\": 2, \"bytes\": \"00 00\", \
+             \"source\": \"NOP\", \"location\": \"p.n1:2\"}}"
+        ),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("{\"old_start\": 2, \"old_end\": 3, \"delta\": 2, \"entries\": 1}"),
+        "stdout: {stdout}"
+    );
+    assert!(stdout.contains("\"unchanged\": 2"), "stdout: {stdout}");
+}
+
+#[test]
+fn run_reports_faults() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = create_temp_file(temp_dir.path(), "run.n1", "NOP\n.word 0xF000\n");
+
+    let result = Command::new(binary_path())
+        .args(["run", source.to_str().unwrap()])
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    let stderr = String::from_utf8_lossy(&result.stderr);
+
+    assert!(!result.status.success());
+    assert!(
+        stderr.contains("CPU faulted during tick 0 at PC 0x0002: IllegalEncoding"),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn golden_corpus_still_assembles_to_its_binaries() {
+    let corpus = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let result = Command::new(binary_path())
+        .arg("corpus")
+        .arg("verify")
+        .arg(&corpus)
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(
+        result.status.success(),
+        "encoding changed; run `nullbyte-asm corpus update crates/assembler/tests/corpus` \
+         if intended:\n{stderr}"
+    );
+    assert!(String::from_utf8_lossy(&result.stdout).contains("3 corpus programs match"));
+}
+
+#[test]
+fn corpus_verify_reports_changed_programs() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    create_temp_file(temp_dir.path(), "prog.n1", "HALT\n");
+    fs::write(temp_dir.path().join("prog.bin"), [0xFF, 0xFF]).unwrap();
+
+    let result = Command::new(binary_path())
+        .arg("corpus")
+        .arg("verify")
+        .arg(temp_dir.path())
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert_eq!(result.status.code(), Some(1));
+    assert!(
+        stderr.contains("error: prog: changed at 0x0000"),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn devmap_lists_devices_in_address_order() {
+    let result = Command::new(binary_path())
+        .args(["devmap", "--registers"])
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    let stdout = String::from_utf8_lossy(&result.stdout);
+
+    assert!(result.status.success(), "stdout: {stdout}");
+    let devices: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("0x"))
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .collect();
+    assert_eq!(devices, ["TELE7", "CONSOLE", "MPU", "DMA", "PASTE"]);
+    assert!(
+        stdout.contains("  0xE122  CTRL           RW  Control bits (LIVE_READ[1] ENABLE[0])"),
+        "stdout: {stdout}"
+    );
+}
+
+#[test]
+fn trace_dump_tells_the_story_of_a_traced_run() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = create_temp_file(
+        temp_dir.path(),
+        "story.n1",
+        "MOV R0, #0xE132\nMOV R1, #0x48\nSTORE R1, [R0]\nidle:\nHALT\nJMP #idle\n",
+    );
+    let map = temp_dir.path().join("story.map");
+    let trace = temp_dir.path().join("story.trace");
+
+    let build = Command::new(binary_path())
+        .args(["build", source.to_str().unwrap(), "--map"])
+        .arg(&map)
+        .output()
+        .expect("failed to run nullbyte-asm");
+    assert!(build.status.success());
+    let run = Command::new(binary_path())
+        .args(["run", source.to_str().unwrap(), "--ticks", "2", "--trace"])
+        .arg(&trace)
+        .output()
+        .expect("failed to run nullbyte-asm");
+    assert!(run.status.success());
+
+    let result = Command::new(binary_path())
+        .args(["trace", "dump", trace.to_str().unwrap(), "--map"])
+        .arg(&map)
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    let stdout = String::from_utf8_lossy(&result.stdout);
+
+    assert!(result.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.starts_with("== tick 0 ==\n0x0000  "),
+        "stdout: {stdout}"
+    );
+    assert!(stdout.contains("story.n1:3"), "stdout: {stdout}");
+    assert!(
+        stdout.contains("        mmio-write [0xE132] <- 0x0048"),
+        "stdout: {stdout}"
+    );
+    assert!(stdout.contains("\nidle:\n"), "stdout: {stdout}");
+    assert!(
+        stdout.contains("== end of tick 1: 3 cycles =="),
+        "stdout: {stdout}"
+    );
+}
+
 const FAILING_TEST_CONTENT: &str = r"# Test
 
 ```n1asm
@@ -209,6 +681,59 @@ fn test_reports_failing_assertions() {
     assert!(stdout.contains("FAIL"));
 }
 
+const FIXTURE_TEST_CONTENT: &str = r"# Fixture
+
+```n1asm
+ADD R0, R0, #1
+HALT
+ADD R0, R0, #10
+HALT
+```
+
+```n1test
+capture: boot.n1snap
+R0 == 1
+```
+
+```n1test
+R0 == 11
+```
+
+```n1test
+fixture: boot.n1snap
+R0 == 11
+```
+";
+
+#[test]
+fn test_capture_fixture_then_resume_from_it() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = create_temp_file(temp_dir.path(), "fixture.n1.md", FIXTURE_TEST_CONTENT);
+    let fixture = temp_dir.path().join("boot.n1snap");
+
+    let without_fixture = Command::new(binary_path())
+        .args(["test", source.to_str().unwrap()])
+        .output()
+        .expect("failed to run nullbyte-asm");
+    assert!(!without_fixture.status.success());
+    assert!(String::from_utf8_lossy(&without_fixture.stdout).contains("Cannot read fixture"));
+
+    let capture = Command::new(binary_path())
+        .args(["test", source.to_str().unwrap(), "--capture-fixture"])
+        .output()
+        .expect("failed to run nullbyte-asm");
+    let stdout = String::from_utf8_lossy(&capture.stdout);
+    assert!(stdout.contains("Captured fixture"), "stdout: {stdout}");
+    assert!(fixture.exists());
+
+    let replay = Command::new(binary_path())
+        .args(["test", source.to_str().unwrap()])
+        .output()
+        .expect("failed to run nullbyte-asm");
+    let stdout = String::from_utf8_lossy(&replay.stdout);
+    assert!(replay.status.success(), "stdout: {stdout}");
+}
+
 #[test]
 fn help_shows_usage() {
     let result = Command::new(binary_path())
@@ -222,6 +747,51 @@ fn help_shows_usage() {
     assert!(stdout.contains("test"));
 }
 
+#[test]
+fn version_and_completions() {
+    let version = Command::new(binary_path())
+        .args(["--version"])
+        .output()
+        .expect("failed to run nullbyte-asm");
+    assert!(version.status.success());
+    let stdout = String::from_utf8_lossy(&version.stdout);
+    assert!(stdout.starts_with(&format!("nullbyte-asm {}", env!("CARGO_PKG_VERSION"))));
+
+    let json = Command::new(binary_path())
+        .args(["--version", "--json"])
+        .output()
+        .expect("failed to run nullbyte-asm");
+    assert!(json.status.success());
+    let stdout = String::from_utf8_lossy(&json.stdout);
+    assert!(stdout.contains(&format!(
+        "\"revision\": \"{:016x}\"",
+        emulator_core::isa_revision()
+    )));
+    assert!(stdout.contains("\"schema_version\": 4"));
+
+    let completions = Command::new(binary_path())
+        .args(["completions", "fish"])
+        .output()
+        .expect("failed to run nullbyte-asm");
+    assert!(completions.status.success());
+    let stdout = String::from_utf8_lossy(&completions.stdout);
+    assert!(stdout.contains("complete -c nullbyte-asm"));
+    assert!(stdout.contains("-l check-callconv"));
+}
+
+#[test]
+fn misspelled_option_suggests_the_closest_one() {
+    let result = Command::new(binary_path())
+        .args(["build", "prog.n1", "--ouput", "x.bin"])
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    assert!(!result.status.success());
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.contains("unknown option: --ouput (did you mean --output?)"));
+    assert!(stderr.contains("nullbyte-asm build --help"));
+}
+
 #[test]
 fn unknown_command_fails() {
     let result = Command::new(binary_path())
@@ -257,3 +827,183 @@ fn blinker_program_tests_pass() {
     assert!(result.status.success(), "blinker tests failed:\n{stdout}");
     assert!(stdout.contains("Test Summary: 3 passed"));
 }
+
+#[test]
+fn reproducible_build_writes_a_build_id_that_verify_accepts() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = create_temp_file(temp_dir.path(), "prog.n1", "NOP\nHALT\n");
+    let output = temp_dir.path().join("prog.bin");
+
+    let build = Command::new(binary_path())
+        .args([
+            "build",
+            source.to_str().unwrap(),
+            "-o",
+            output.to_str().unwrap(),
+            "--reproducible",
+        ])
+        .output()
+        .expect("failed to run nullbyte-asm");
+    assert!(build.status.success());
+    let build_id = fs::read_to_string(temp_dir.path().join("prog.bin.buildid")).unwrap();
+    assert_eq!(build_id.trim().len(), 16);
+    assert!(String::from_utf8_lossy(&build.stdout).contains(build_id.trim()));
+
+    let verify = Command::new(binary_path())
+        .args([
+            "verify",
+            source.to_str().unwrap(),
+            "--binary",
+            output.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run nullbyte-asm");
+    let stdout = String::from_utf8_lossy(&verify.stdout);
+    assert!(verify.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("Verified"), "stdout: {stdout}");
+
+    let wrong_id = Command::new(binary_path())
+        .args([
+            "verify",
+            source.to_str().unwrap(),
+            "--binary",
+            output.to_str().unwrap(),
+            "--build-id",
+            "0000000000000000",
+        ])
+        .output()
+        .expect("failed to run nullbyte-asm");
+    assert!(!wrong_id.status.success());
+}
+
+#[test]
+fn reproducible_build_rejects_includes_outside_the_tree() {
+    let outside = tempfile::tempdir().unwrap();
+    let shared = create_temp_file(outside.path(), "shared.n1", "NOP\n");
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = create_temp_file(
+        temp_dir.path(),
+        "prog.n1",
+        &format!(".include \"{}\"\nHALT\n", shared.display()),
+    );
+
+    let result = Command::new(binary_path())
+        .args(["build", source.to_str().unwrap(), "--reproducible"])
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(!result.status.success());
+    assert!(
+        stderr.contains("not reproducible") && stderr.contains("shared.n1"),
+        "stderr: {stderr}"
+    );
+    assert!(!temp_dir.path().join("prog.bin").exists());
+}
+
+#[test]
+fn verify_reports_the_first_divergence_with_its_source_line() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = create_temp_file(temp_dir.path(), "prog.n1", "NOP\nHALT\n");
+    let shipped = temp_dir.path().join("shipped.bin");
+    fs::write(&shipped, [0x00, 0x00, 0x00, 0x00]).unwrap();
+
+    let result = Command::new(binary_path())
+        .args([
+            "verify",
+            source.to_str().unwrap(),
+            "--binary",
+            shipped.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run nullbyte-asm");
+
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(!result.status.success());
+    assert!(
+        stderr.contains("at 0x0003: shipped 0x00, rebuilt 0x10"),
+        "stderr: {stderr}"
+    );
+    assert!(stderr.contains("0002: 00 10"), "stderr: {stderr}");
+    assert!(stderr.contains("HALT ; "), "stderr: {stderr}");
+}
+
+#[test]
+#[cfg(feature = "script")]
+fn script_drives_the_program_and_reports_assertions() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = create_temp_file(
+        temp_dir.path(),
+        "count.n1",
+        "MOV R1, #0\nloop:\nADD R1, R1, #1\nHALT\nJMP #loop\n",
+    );
+    let script = create_temp_file(
+        temp_dir.path(),
+        "check.rhai",
+        "run(5);\nprint(`R1 = ${reg(\"R1\")}`);\nassert(reg(\"R1\") == 5, \"one per tick\");\n",
+    );
+    let failing = create_temp_file(
+        temp_dir.path(),
+        "fail.rhai",
+        "run(1);\nassert(reg(\"R1\") == 2, \"too fast\");\n",
+    );
+
+    let result = Command::new(binary_path())
+        .args(["script", source.to_str().unwrap(), script.to_str().unwrap()])
+        .output()
+        .expect("failed to run nullbyte-asm");
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    assert!(result.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("R1 = 5"), "stdout: {stdout}");
+    assert!(
+        stdout.contains("passed (1 assertion(s))"),
+        "stdout: {stdout}"
+    );
+
+    let result = Command::new(binary_path())
+        .args([
+            "script",
+            source.to_str().unwrap(),
+            failing.to_str().unwrap(),
+        ])
+        .output()
+        .expect("failed to run nullbyte-asm");
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(!result.status.success());
+    assert!(
+        stderr.contains("assertion failed: too fast"),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn bundle_packages_inlined_source_and_binary() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    create_temp_file(temp_dir.path(), "lib.n1", "work:\nRET\n");
+    let source = create_temp_file(
+        temp_dir.path(),
+        "game.n1",
+        "CALL #work\nHALT\n.include \"lib.n1\"\n",
+    );
+
+    let result = Command::new(binary_path())
+        .args(["bundle", source.to_str().unwrap()])
+        .output()
+        .expect("failed to run nullbyte-asm");
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    assert!(result.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains("(8 bytes, 4 source lines)"),
+        "stdout: {stdout}"
+    );
+
+    let json = fs::read_to_string(temp_dir.path().join("game.bundle.json")).unwrap();
+    assert!(
+        json.contains("\"source\": \"CALL #work\\u000aHALT\\u000awork:\\u000aRET\\u000a\""),
+        "{json}"
+    );
+    assert!(
+        json.contains("{\"name\": \"work\", \"address\": 6}"),
+        "{json}"
+    );
+}
@@ -0,0 +1,112 @@
+//! Native versus WASM determinism contract for `nullbyte-asm cross-check`.
+//!
+//! Both tests need Node.js on the path, and comparing against the real WASM
+//! core also needs an `emulator-wasm` package built with
+//! `wasm-pack build --target nodejs`, found through `NULLBYTE_WASM_PKG`.
+//! They are ignored by default; `make cross-check-wasm` builds the package
+//! and runs them, as CI does.
+
+use assembler as _;
+use emulator_core as _;
+#[cfg(feature = "script")]
+use rhai as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Exercises trap dispatch mid-tick and a scripted event.
+const PROGRAM: &str = "\
+JMP #main
+.org 0x0008
+.word 0x0020
+main:
+ADD R0, R0, #1
+SWI
+HALT
+JMP #main
+.org 0x0020
+ADD R1, R1, #1
+ERET
+";
+
+const EVENTS: &str = "; host inputs\nat tick 3 enqueue event 0x41\nat tick 7 enqueue event 2\n";
+
+fn binary_path() -> PathBuf {
+    let mut path = std::env::current_exe().unwrap();
+    path.pop();
+    path.pop();
+    path.join("nullbyte-asm")
+}
+
+fn node_available() -> bool {
+    Command::new("node")
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+fn cross_check(dir: &Path, package: &Path) -> Output {
+    let source = dir.join("dispatch.n1");
+    let events = dir.join("input.events");
+    fs::write(&source, PROGRAM).unwrap();
+    fs::write(&events, EVENTS).unwrap();
+    Command::new(binary_path())
+        .arg("cross-check")
+        .arg(&source)
+        .arg("--wasm-pkg")
+        .arg(package)
+        .arg("--events")
+        .arg(&events)
+        .args(["--ticks", "20"])
+        .output()
+        .expect("failed to run nullbyte-asm")
+}
+
+#[test]
+#[ignore = "needs Node.js and a wasm-pack build; run with `make cross-check-wasm`"]
+fn cross_check_matches_the_wasm_build() {
+    let package = std::env::var_os("NULLBYTE_WASM_PKG")
+        .expect("set NULLBYTE_WASM_PKG to a wasm-pack nodejs build of emulator-wasm");
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    let output = cross_check(temp_dir.path(), Path::new(&package));
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{stdout}{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("20 tick(s), 2 event(s): native and wasm identical"));
+}
+
+#[test]
+#[ignore = "needs Node.js; run with `make cross-check-wasm`"]
+fn cross_check_reports_the_first_diverging_tick() {
+    assert!(node_available(), "node is not on the path");
+    let temp_dir = tempfile::tempdir().unwrap();
+    let package = temp_dir.path().join("pkg");
+    fs::create_dir(&package).unwrap();
+    fs::write(package.join("package.json"), r#"{"main": "index.js"}"#).unwrap();
+    fs::write(
+        package.join("index.js"),
+        "class WasmCore {\n\
+           load_bundle(json) { JSON.parse(json); }\n\
+           enqueue_event(id) { return true; }\n\
+           tick() {}\n\
+           state_digest() { return \"0000000000000000\"; }\n\
+         }\n\
+         module.exports = { WasmCore };\n",
+    )
+    .unwrap();
+
+    let output = cross_check(temp_dir.path(), &package);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{stdout}");
+    assert!(
+        stdout.contains("DIVERGED after tick 0: native ")
+            && stdout.contains("wasm 0000000000000000"),
+        "{stdout}"
+    );
+}
@@ -63,7 +63,7 @@ fn fingerprint() -> String {
             hash_bytes(&mut hash, &[0x10]);
             hash_bytes(&mut hash, &cycles.to_le_bytes());
         }
-        emulator_core::StepOutcome::HaltedForTick => hash_bytes(&mut hash, &[0x11]),
+        emulator_core::StepOutcome::HaltedForTick { .. } => hash_bytes(&mut hash, &[0x11]),
         emulator_core::StepOutcome::TrapDispatch { cause } => {
             hash_bytes(&mut hash, &[0x12]);
             hash_bytes(&mut hash, &cause.to_le_bytes());
@@ -76,14 +76,7 @@ fn fingerprint() -> String {
         }
     }
 
-    hash_bytes(&mut hash, &replay.final_state.arch.pc().to_le_bytes());
-    hash_bytes(&mut hash, &replay.final_state.arch.sp().to_le_bytes());
-    hash_bytes(&mut hash, &replay.final_state.arch.tick().to_le_bytes());
-    hash_bytes(&mut hash, &replay.final_state.arch.flags().to_le_bytes());
-    hash_bytes(&mut hash, &replay.final_state.arch.cause().to_le_bytes());
-    hash_bytes(&mut hash, &replay.final_state.arch.cap().to_le_bytes());
-    hash_bytes(&mut hash, &replay.final_state.arch.evp().to_le_bytes());
-    hash_bytes(&mut hash, &replay.final_state.memory);
+    hash_bytes(&mut hash, &replay.final_state.state_digest().to_le_bytes());
 
     format!("{hash:016x}")
 }
@@ -25,7 +25,8 @@
 #![allow(clippy::pedantic)]
 
 use emulator_core::{
-    run_one, CoreConfig, CoreProfile, CoreState, MmioBus, MmioError, MmioWriteResult, RunBoundary,
+    run_one, BlockCache, CoreConfig, CoreProfile, CoreState, ExperimentalOpcodes, MmioBus,
+    MmioError, MmioWriteResult, OpenBus, RunBoundary, RunLimits, TimingModel,
 };
 use proptest as _;
 use rstest as _;
@@ -94,6 +95,14 @@ fn benchmark_nop_loop(duration: Duration) -> BenchmarkResult {
                     profile: CoreProfile::Authority,
                     tick_budget_cycles: TICK_BUDGET_CYCLES,
                     tracing_enabled: false,
+                    pc_history_depth: 0,
+                    tick_usage_depth: 0,
+                    memory_write_log_depth: 0,
+                    reset_pc: 0,
+                    timing: TimingModel::V1,
+                    open_bus: OpenBus::Zero,
+                    run_limits: RunLimits::UNBOUNDED,
+                    experimental_opcodes: ExperimentalOpcodes::new(),
                 };
                 let mut mmio = NoopMmio;
 
@@ -163,6 +172,14 @@ fn benchmark_alu_loop(duration: Duration) -> BenchmarkResult {
                     profile: CoreProfile::Authority,
                     tick_budget_cycles: TICK_BUDGET_CYCLES,
                     tracing_enabled: false,
+                    pc_history_depth: 0,
+                    tick_usage_depth: 0,
+                    memory_write_log_depth: 0,
+                    reset_pc: 0,
+                    timing: TimingModel::V1,
+                    open_bus: OpenBus::Zero,
+                    run_limits: RunLimits::UNBOUNDED,
+                    experimental_opcodes: ExperimentalOpcodes::new(),
                 };
                 let mut mmio = NoopMmio;
 
@@ -233,6 +250,14 @@ fn benchmark_memory_loop(duration: Duration) -> BenchmarkResult {
                     profile: CoreProfile::Authority,
                     tick_budget_cycles: TICK_BUDGET_CYCLES,
                     tracing_enabled: false,
+                    pc_history_depth: 0,
+                    tick_usage_depth: 0,
+                    memory_write_log_depth: 0,
+                    reset_pc: 0,
+                    timing: TimingModel::V1,
+                    open_bus: OpenBus::Zero,
+                    run_limits: RunLimits::UNBOUNDED,
+                    experimental_opcodes: ExperimentalOpcodes::new(),
                 };
                 let mut mmio = NoopMmio;
 
@@ -304,6 +329,14 @@ fn benchmark_mixed_loop(duration: Duration) -> BenchmarkResult {
                     profile: CoreProfile::Authority,
                     tick_budget_cycles: TICK_BUDGET_CYCLES,
                     tracing_enabled: false,
+                    pc_history_depth: 0,
+                    tick_usage_depth: 0,
+                    memory_write_log_depth: 0,
+                    reset_pc: 0,
+                    timing: TimingModel::V1,
+                    open_bus: OpenBus::Zero,
+                    run_limits: RunLimits::UNBOUNDED,
+                    experimental_opcodes: ExperimentalOpcodes::new(),
                 };
                 let mut mmio = NoopMmio;
 
@@ -354,6 +387,85 @@ fn benchmark_mixed_loop(duration: Duration) -> BenchmarkResult {
     }
 }
 
+fn benchmark_cached_mixed_loop(duration: Duration) -> BenchmarkResult {
+    let (tx, rx) = mpsc::channel();
+
+    let handles: Vec<_> = (0..NUM_THREADS)
+        .map(|_| {
+            let tx = tx.clone();
+            thread::spawn(move || {
+                let mut state = CoreState::default();
+
+                load_word(&mut state, 0x0000, encode(0x0, 0, 0, 0x0, 0));
+                load_word(&mut state, 0x0002, encode(0x4, 0, 1, 0x0, 0));
+                load_word(&mut state, 0x0004, encode(0x5, 1, 0, 0x0, 0));
+                load_word(&mut state, 0x0006, encode(0x6, 2, 1, 0x0, 0));
+                load_word(&mut state, 0x0008, encode(0x3, 0, 0, 0x0, 0));
+                load_word(&mut state, 0x000A, encode(0x2, 0, 0, 0x0, 0));
+
+                let config = CoreConfig {
+                    profile: CoreProfile::Authority,
+                    tick_budget_cycles: TICK_BUDGET_CYCLES,
+                    tracing_enabled: false,
+                    pc_history_depth: 0,
+                    tick_usage_depth: 0,
+                    memory_write_log_depth: 0,
+                    reset_pc: 0,
+                    timing: TimingModel::V1,
+                    open_bus: OpenBus::Zero,
+                    run_limits: RunLimits::UNBOUNDED,
+                    experimental_opcodes: ExperimentalOpcodes::new(),
+                };
+                let mut mmio = NoopMmio;
+                let mut cache = BlockCache::new();
+
+                let mut total_instructions = 0u64;
+                let mut total_cycles = 0u64;
+                let start = Instant::now();
+
+                while start.elapsed() < duration {
+                    state.arch.set_tick(0);
+                    state.run_state = emulator_core::RunState::Running;
+                    state.arch.set_pc(0x0000);
+
+                    let outcome =
+                        cache.run(&mut state, &mut mmio, &config, RunBoundary::TickBoundary);
+                    total_instructions += u64::from(outcome.steps);
+                    total_cycles += u64::from(state.arch.tick());
+                }
+
+                tx.send((total_instructions, total_cycles)).ok();
+            })
+        })
+        .collect();
+
+    for h in handles {
+        h.join().ok();
+    }
+
+    drop(tx);
+
+    let mut total_instructions = 0u64;
+    let mut total_cycles = 0u64;
+    for (inst, cyc) in rx {
+        total_instructions += inst;
+        total_cycles += cyc;
+    }
+
+    let elapsed_secs = duration.as_secs_f64();
+    let instructions_per_second = total_instructions as f64 / elapsed_secs;
+    let cycles_per_second = total_cycles as f64 / elapsed_secs;
+    let cores_at_100hz =
+        instructions_per_second / (f64::from(TICK_BUDGET_CYCLES) * TICKS_PER_SECOND as f64);
+
+    BenchmarkResult {
+        name: "cached_mixed",
+        instructions_per_second,
+        cycles_per_second,
+        core_equivalents_100hz: cores_at_100hz,
+    }
+}
+
 fn format_number(n: f64) -> String {
     if n >= 1_000_000.0 {
         format!("{:.2}M", n / 1_000_000.0)
@@ -436,8 +548,15 @@ fn main() {
     let alu_result = benchmark_alu_loop(benchmark_duration);
     let memory_result = benchmark_memory_loop(benchmark_duration);
     let mixed_result = benchmark_mixed_loop(benchmark_duration);
-
-    print_results(&[nop_result, alu_result, memory_result, mixed_result]);
+    let cached_result = benchmark_cached_mixed_loop(benchmark_duration);
+
+    print_results(&[
+        nop_result,
+        alu_result,
+        memory_result,
+        mixed_result,
+        cached_result,
+    ]);
 }
 
 #[cfg(test)]
@@ -469,4 +588,10 @@ mod tests {
         let result = benchmark_mixed_loop(Duration::from_millis(100));
         assert!(result.instructions_per_second > 0.0);
     }
+
+    #[test]
+    fn test_benchmark_cached_mixed_loop_runs() {
+        let result = benchmark_cached_mixed_loop(Duration::from_millis(100));
+        assert!(result.instructions_per_second > 0.0);
+    }
 }
@@ -2,11 +2,15 @@
 //!
 //! These are intentionally type-only scaffolds for FR-8/9/11/15 and NFR-4.
 
-use std::fmt::Write;
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::fmt::Write;
 
 use crate::{
-    new_address_space, run_one, run_one_with_trace, ArchitecturalState, FaultCode, GeneralRegister,
-    RunState, CAP_AUTHORITY_DEFAULT_MASK, CAP_RESTRICTED_DEFAULT_MASK, GENERAL_REGISTER_COUNT,
+    new_address_space, run_one, run_one_with_trace, ArchitecturalState, BreakpointHit,
+    BreakpointTable, DeviceRegisters, ExperimentalOpcodes, FaultCode, GeneralRegister,
+    MemoryWriteLog, Mpu, PcHistory, RunState, TickUsageHistory, TimingModel,
+    CAP_AUTHORITY_DEFAULT_MASK, CAP_RESTRICTED_DEFAULT_MASK, EVP_OVERFLOW, GENERAL_REGISTER_COUNT,
+    TICKS_PER_SECOND,
 };
 use thiserror::Error;
 
@@ -26,6 +30,64 @@ pub use crate::memory::ADDRESS_SPACE_BYTES;
 /// Default cycle budget per tick.
 pub const DEFAULT_TICK_BUDGET_CYCLES: u16 = 640;
 
+/// Cycle budget per tick of [`CoreConfig::sandbox`]: half the default.
+pub const SANDBOX_TICK_BUDGET_CYCLES: u16 = 320;
+
+/// Upper bounds on the work one host run call does, whatever count the
+/// caller asks for.
+///
+/// Batch runs are cut to [`RunLimits::max_ticks`] ticks and step-limited
+/// runs (step over/out) to [`RunLimits::max_steps`] instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct RunLimits {
+    /// Most ticks one batch call runs.
+    pub max_ticks: u32,
+    /// Most instructions one step-limited call executes.
+    pub max_steps: u32,
+}
+
+impl RunLimits {
+    /// No limits beyond the caller's own counts.
+    pub const UNBOUNDED: Self = Self {
+        max_ticks: u32::MAX,
+        max_steps: u32::MAX,
+    };
+
+    /// Limits of [`CoreConfig::sandbox`]: one simulated second per batch,
+    /// and as many steps as that second can hold at one cycle each.
+    pub const SANDBOX: Self = Self {
+        max_ticks: TICKS_PER_SECOND,
+        max_steps: TICKS_PER_SECOND * SANDBOX_TICK_BUDGET_CYCLES as u32,
+    };
+
+    /// Returns `requested` ticks cut to [`RunLimits::max_ticks`].
+    #[must_use]
+    pub const fn ticks(self, requested: u32) -> u32 {
+        if requested < self.max_ticks {
+            requested
+        } else {
+            self.max_ticks
+        }
+    }
+
+    /// Returns `requested` steps cut to [`RunLimits::max_steps`].
+    #[must_use]
+    pub const fn steps(self, requested: u32) -> u32 {
+        if requested < self.max_steps {
+            requested
+        } else {
+            self.max_steps
+        }
+    }
+}
+
+impl Default for RunLimits {
+    fn default() -> Self {
+        Self::UNBOUNDED
+    }
+}
+
 /// Core execution profile controls capability defaults and policy hooks.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -47,6 +109,37 @@ pub struct CoreConfig {
     pub tick_budget_cycles: u16,
     /// Enables deterministic trace callback dispatch.
     pub tracing_enabled: bool,
+    /// Number of recently retired instructions kept in
+    /// [`CoreState::pc_history`] (0 disables the history).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pc_history_depth: u16,
+    /// Number of ended ticks whose cycle usage is kept in
+    /// [`CoreState::tick_usage`] (0 disables the history).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tick_usage_depth: u16,
+    /// Number of memory writes kept in [`CoreState::write_log`] between
+    /// clears (0 disables the log).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub memory_write_log_depth: u16,
+    /// PC of a freshly created core: the program entry point (reset vector).
+    ///
+    /// [`CoreState::reset_canonical`] has no configuration and always
+    /// resumes at 0x0000; hosts with a relocated entry re-create the state.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reset_pc: u16,
+    /// Cycle-cost table instructions are charged from.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub timing: TimingModel,
+    /// Value reads of unmapped MMIO addresses return.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub open_bus: OpenBus,
+    /// Caps on the work of one run call.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub run_limits: RunLimits,
+    /// Host handlers for reserved primary opcodes; empty by default, so
+    /// reserved encodings fault. Not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub experimental_opcodes: ExperimentalOpcodes,
 }
 
 impl Default for CoreConfig {
@@ -55,11 +148,37 @@ impl Default for CoreConfig {
             profile: CoreProfile::Authority,
             tick_budget_cycles: DEFAULT_TICK_BUDGET_CYCLES,
             tracing_enabled: false,
+            pc_history_depth: 0,
+            tick_usage_depth: 0,
+            memory_write_log_depth: 0,
+            reset_pc: 0,
+            timing: TimingModel::V1,
+            open_bus: OpenBus::Zero,
+            run_limits: RunLimits::UNBOUNDED,
+            experimental_opcodes: ExperimentalOpcodes::new(),
         }
     }
 }
 
 impl CoreConfig {
+    /// Preset for running untrusted programs, such as arbitrary code in a
+    /// web playground, with predictable resource use.
+    ///
+    /// It uses the [`CoreProfile::Restricted`] capability defaults, a
+    /// [`SANDBOX_TICK_BUDGET_CYCLES`] tick budget and [`RunLimits::SANDBOX`],
+    /// and registers no experimental opcodes, so reserved encodings fault.
+    /// Denied MMIO writes are suppressed and counted, as in every profile,
+    /// rather than faulting.
+    #[must_use]
+    pub fn sandbox() -> Self {
+        Self {
+            profile: CoreProfile::Restricted,
+            tick_budget_cycles: SANDBOX_TICK_BUDGET_CYCLES,
+            run_limits: RunLimits::SANDBOX,
+            ..Self::default()
+        }
+    }
+
     /// Returns the profile-specific default capability mask.
     #[must_use]
     pub const fn default_capability_mask(&self) -> u16 {
@@ -86,6 +205,35 @@ pub struct CoreState {
     pub run_state: RunState,
     /// Counter for denied MMIO writes (saturating).
     pub mmio_denied_write_count: u16,
+    /// Most recently retired instructions, for fault context; not part of
+    /// the canonical snapshot.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pc_history: PcHistory,
+    /// Cycle-cost table instructions are charged from.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub timing: TimingModel,
+    /// Value reads of unmapped MMIO addresses return.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub open_bus: OpenBus,
+    /// Last word the CPU moved over the MMIO bus, returned by unmapped reads
+    /// under [`OpenBus::LastValue`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mmio_bus_latch: u16,
+    /// Cycles used by recently ended ticks; not part of the canonical
+    /// snapshot.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tick_usage: TickUsageHistory,
+    /// Memory writes since the host last cleared the log; not part of the
+    /// canonical snapshot.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub write_log: MemoryWriteLog,
+    /// Memory protection unit registers, live while `CAP` bit
+    /// [`CAP_MPU_BIT`](crate::CAP_MPU_BIT) is set.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mpu: Mpu,
+    /// Host breakpoints; not part of the canonical snapshot.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub breakpoints: BreakpointTable,
 }
 
 impl Default for CoreState {
@@ -100,6 +248,7 @@ impl CoreState {
     pub fn with_config(config: &CoreConfig) -> Self {
         let mut arch = ArchitecturalState::default();
         arch.set_cap_core_owned(config.default_capability_mask());
+        arch.set_pc(config.reset_pc);
 
         Self {
             profile: config.profile,
@@ -108,6 +257,14 @@ impl CoreState {
             event_queue: EventQueueSnapshot::default(),
             run_state: RunState::Running,
             mmio_denied_write_count: 0,
+            pc_history: PcHistory::new(config.pc_history_depth),
+            timing: config.timing,
+            open_bus: config.open_bus,
+            mmio_bus_latch: 0,
+            tick_usage: TickUsageHistory::new(config.tick_usage_depth),
+            write_log: MemoryWriteLog::new(config.memory_write_log_depth),
+            mpu: Mpu::new(),
+            breakpoints: BreakpointTable::new(),
         }
     }
 
@@ -117,10 +274,28 @@ impl CoreState {
         self.arch.capability_enabled(bit_index)
     }
 
+    /// Enqueues a host event, latching [`EVP_OVERFLOW`] when the queue is full.
+    ///
+    /// The rejected event is dropped. The latch raises
+    /// [`FaultCode::EventQueueOverflow`] at the next event dispatch check and
+    /// stays readable in `EVP` until then.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`EventEnqueueError::QueueFull`] when the queue is already at capacity.
+    pub fn enqueue_event(&mut self, event_id: u8) -> Result<(), EventEnqueueError> {
+        let result = self.event_queue.enqueue(event_id);
+        if result.is_err() {
+            self.arch.set_evp_core_owned(self.arch.evp() | EVP_OVERFLOW);
+        }
+        result
+    }
+
     /// Applies canonical reset semantics to the host-visible execution state.
     ///
     /// Reset restores architectural defaults, resumes at ROM entry
-    /// (`PC=0x0000`), clears pending events, and clears any latched fault.
+    /// (`PC=0x0000`), clears pending events and PC history, and clears any
+    /// latched fault.
     pub fn reset_canonical(&mut self) {
         self.arch = ArchitecturalState::default();
         let cap_mask = match self.profile {
@@ -131,6 +306,11 @@ impl CoreState {
         self.event_queue = EventQueueSnapshot::default();
         self.run_state = RunState::Running;
         self.mmio_denied_write_count = 0;
+        self.mmio_bus_latch = 0;
+        self.pc_history.clear();
+        self.tick_usage.clear();
+        self.write_log.clear();
+        self.mpu = Mpu::new();
     }
 }
 
@@ -214,6 +394,63 @@ pub enum MmioError {
     ReadFailed,
     /// Host adapter reported transport/device failure on write.
     WriteFailed,
+    /// No device decodes the address; the core reads the [`OpenBus`] value
+    /// instead.
+    Unmapped,
+}
+
+/// Value a read of an unmapped MMIO address returns, selected through
+/// [`CoreConfig::open_bus`].
+///
+/// The choice is part of [`CoreState`] and of canonical snapshots, so a
+/// restored machine keeps the behaviour it was saved with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum OpenBus {
+    /// Reads return `0x0000`.
+    #[default]
+    Zero,
+    /// Reads return `0xFFFF`, as on a pulled-up bus.
+    Ones,
+    /// Reads return the last word the CPU moved over the MMIO bus
+    /// ([`CoreState::mmio_bus_latch`]).
+    LastValue,
+}
+
+impl OpenBus {
+    /// Names accepted by [`OpenBus::named`].
+    pub const NAMES: [&'static str; 3] = ["zero", "ones", "last"];
+
+    /// Looks up a policy by name.
+    #[must_use]
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "zero" => Some(Self::Zero),
+            "ones" => Some(Self::Ones),
+            "last" => Some(Self::LastValue),
+            _ => None,
+        }
+    }
+
+    /// Name of the policy: one of [`NAMES`](Self::NAMES).
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Zero => "zero",
+            Self::Ones => "ones",
+            Self::LastValue => "last",
+        }
+    }
+
+    /// Value an unmapped read returns when the bus last carried `latch`.
+    #[must_use]
+    pub const fn value(self, latch: u16) -> u16 {
+        match self {
+            Self::Zero => 0x0000,
+            Self::Ones => 0xFFFF,
+            Self::LastValue => latch,
+        }
+    }
 }
 
 /// Result categories for MMIO write integration.
@@ -242,6 +479,58 @@ pub trait MmioBus {
     /// Returns [`MmioError::WriteFailed`] when the adapter cannot complete the
     /// write.
     fn write16(&mut self, addr: u16, value: u16) -> Result<MmioWriteResult, MmioError>;
+
+    /// Runs per-tick device work such as blink or timer counters.
+    ///
+    /// Called once per tick by [`end_tick`](crate::end_tick); the default
+    /// does nothing.
+    fn on_tick(&mut self) {}
+
+    /// Runs the per-tick work of `ticks` ticks at once.
+    ///
+    /// [`run_fast_forward`](crate::run_fast_forward) batches tick work
+    /// through this. The default calls [`MmioBus::on_tick`] `ticks` times;
+    /// devices whose tick work is a counter override it to catch up in one
+    /// step.
+    fn advance_ticks(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            self.on_tick();
+        }
+    }
+
+    /// Runs per-tick work that reads or writes core state, such as DMA
+    /// transfers.
+    ///
+    /// Called once per tick by [`end_tick`](crate::end_tick), after
+    /// [`MmioBus::on_tick`] and with `TICK` already reset; the default does
+    /// nothing. The program can observe this work, so
+    /// [`run_fast_forward`](crate::run_fast_forward) never defers it, and it
+    /// must not depend on state that `on_tick` updates.
+    fn on_tick_memory(&mut self, _state: &mut CoreState) {}
+
+    /// Returns the devices behind this bus to their power-on state.
+    ///
+    /// Hosts call this whenever they reset the core, so device registers,
+    /// counters and output do not leak from one run into the next. Input the
+    /// host supplied, such as paste text, is kept. The default does nothing.
+    fn reset(&mut self) {}
+
+    /// Returns the value a read of `addr` would return, without the read's
+    /// side effects, for debugger views.
+    ///
+    /// Returns `None` when the bus cannot tell, which is the default.
+    fn peek16(&self, _addr: u16) -> Option<u16> {
+        None
+    }
+
+    /// Returns the register layouts of the devices behind this bus, in
+    /// address order, for debugger register views.
+    ///
+    /// The default describes nothing, so buses that model no documented
+    /// device need not override it.
+    fn describe_registers(&self) -> Vec<&'static DeviceRegisters> {
+        Vec::new()
+    }
 }
 
 /// Output status from one instruction retirement attempt.
@@ -252,8 +541,11 @@ pub enum StepOutcome {
         /// Fixed cycle cost consumed by the retired instruction.
         cycles: u16,
     },
-    /// Core halted for the current tick after retiring `HALT`.
-    HaltedForTick,
+    /// Core halted for the rest of the current tick.
+    HaltedForTick {
+        /// Whether a `HALT` retired or the tick's cycle budget ran out.
+        reason: HaltReason,
+    },
     /// Trap dispatch path was entered.
     TrapDispatch {
         /// ISA-visible trap cause payload.
@@ -271,6 +563,15 @@ pub enum StepOutcome {
     },
 }
 
+/// Why the core halted for the current tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HaltReason {
+    /// A `HALT` instruction retired.
+    Instruction,
+    /// The instruction that just retired used up the tick's cycle budget.
+    BudgetExhausted,
+}
+
 /// Run loop boundary modes for host-facing batched execution.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RunBoundary {
@@ -280,6 +581,13 @@ pub enum RunBoundary {
     Halted,
     /// Stop when any fault is raised or latched.
     Fault,
+    /// Stop after this many steps, or earlier when the core halts for the
+    /// tick. At least one step always executes.
+    StepCount(u32),
+    /// Stop when PC reaches an armed breakpoint, or earlier when the core
+    /// halts for the tick. The instruction at the starting PC always
+    /// executes first, so a run resumes past the breakpoint it stopped at.
+    Breakpoint,
 }
 
 /// Aggregated outcome from running multiple steps until a selected boundary.
@@ -289,6 +597,8 @@ pub struct RunOutcome {
     pub steps: u32,
     /// Last step-level status observed before returning.
     pub final_step: StepOutcome,
+    /// Breakpoint that ended a [`RunBoundary::Breakpoint`] run, if any.
+    pub breakpoint: Option<BreakpointHit>,
 }
 
 /// Stable snapshot wire-version identifiers.
@@ -298,6 +608,12 @@ pub struct RunOutcome {
 pub enum SnapshotVersion {
     /// Initial schema revision for emulator-core v0.1.x.
     V1 = 1,
+    /// Records the core's [`TimingModel`].
+    V2 = 2,
+    /// Records the core's [`OpenBus`] policy and MMIO bus latch.
+    V3 = 3,
+    /// Records the core's [`Mpu`] registers.
+    V4 = 4,
 }
 
 impl SnapshotVersion {
@@ -306,6 +622,9 @@ impl SnapshotVersion {
     pub const fn from_u16(version: u16) -> Option<Self> {
         match version {
             1 => Some(Self::V1),
+            2 => Some(Self::V2),
+            3 => Some(Self::V3),
+            4 => Some(Self::V4),
             _ => None,
         }
     }
@@ -378,6 +697,18 @@ pub struct CanonicalStateLayout {
     pub latched_fault_code: u8,
     /// Counter for denied MMIO writes.
     pub mmio_denied_write_count: u16,
+    /// Timing model the core was running with.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub timing: TimingModel,
+    /// Open-bus policy the core was running with.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub open_bus: OpenBus,
+    /// Last word moved over the MMIO bus.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mmio_bus_latch: u16,
+    /// Memory protection unit registers.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mpu: Mpu,
 }
 
 impl CanonicalStateLayout {
@@ -387,6 +718,16 @@ impl CanonicalStateLayout {
     const RUN_STATE_FAULT_LATCHED: u8 = 3;
     const NO_LATCHED_FAULT: u8 = 0;
 
+    /// Run-state tag and latched fault code encoding `run_state`.
+    pub(crate) const fn run_state_tags(run_state: RunState) -> (u8, u8) {
+        match run_state {
+            RunState::Running => (Self::RUN_STATE_RUNNING, Self::NO_LATCHED_FAULT),
+            RunState::HaltedForTick => (Self::RUN_STATE_HALTED_FOR_TICK, Self::NO_LATCHED_FAULT),
+            RunState::HandlerContext => (Self::RUN_STATE_HANDLER_CONTEXT, Self::NO_LATCHED_FAULT),
+            RunState::FaultLatched(cause) => (Self::RUN_STATE_FAULT_LATCHED, cause.as_u8()),
+        }
+    }
+
     /// Encodes host-visible core state into canonical snapshot layout.
     #[must_use]
     pub fn from_core_state(state: &CoreState) -> Self {
@@ -395,12 +736,7 @@ impl CanonicalStateLayout {
             gpr[reg.index()] = state.arch.gpr(reg);
         }
 
-        let (run_state_tag, latched_fault_code) = match state.run_state {
-            RunState::Running => (Self::RUN_STATE_RUNNING, Self::NO_LATCHED_FAULT),
-            RunState::HaltedForTick => (Self::RUN_STATE_HALTED_FOR_TICK, Self::NO_LATCHED_FAULT),
-            RunState::HandlerContext => (Self::RUN_STATE_HANDLER_CONTEXT, Self::NO_LATCHED_FAULT),
-            RunState::FaultLatched(cause) => (Self::RUN_STATE_FAULT_LATCHED, cause.as_u8()),
-        };
+        let (run_state_tag, latched_fault_code) = Self::run_state_tags(state.run_state);
 
         Self {
             profile: state.profile,
@@ -418,6 +754,10 @@ impl CanonicalStateLayout {
             run_state_tag,
             latched_fault_code,
             mmio_denied_write_count: state.mmio_denied_write_count,
+            timing: state.timing,
+            open_bus: state.open_bus,
+            mmio_bus_latch: state.mmio_bus_latch,
+            mpu: state.mpu,
         }
     }
 
@@ -476,6 +816,14 @@ impl CanonicalStateLayout {
             },
             run_state,
             mmio_denied_write_count: self.mmio_denied_write_count,
+            pc_history: PcHistory::default(),
+            timing: self.timing,
+            open_bus: self.open_bus,
+            mmio_bus_latch: self.mmio_bus_latch,
+            tick_usage: TickUsageHistory::default(),
+            write_log: MemoryWriteLog::default(),
+            mpu: self.mpu,
+            breakpoints: BreakpointTable::new(),
         })
     }
 }
@@ -536,6 +884,11 @@ pub enum TraceEvent {
         /// Program counter active when fault was observed.
         pc: u16,
     },
+    /// The core halted for the rest of the tick.
+    TickEnded {
+        /// Cycles the tick used: `TICK` when the core halted.
+        cycles: u16,
+    },
 }
 
 /// Sink trait for deterministic trace hooks.
@@ -544,6 +897,21 @@ pub trait TraceSink {
     fn on_event(&mut self, event: TraceEvent);
 }
 
+/// Host callback run once per tick by [`end_tick`](crate::end_tick), after
+/// [`MmioBus::on_tick`].
+///
+/// Implemented for any `FnMut(&mut CoreState)` closure.
+pub trait TickHook {
+    /// Runs deterministic per-tick host work before the next tick starts.
+    fn on_tick(&mut self, state: &mut CoreState);
+}
+
+impl<F: FnMut(&mut CoreState)> TickHook for F {
+    fn on_tick(&mut self, state: &mut CoreState) {
+        self(state);
+    }
+}
+
 /// A trace sink that collects events in memory for later analysis.
 ///
 /// This provides the golden trace format for diff-based debugging.
@@ -588,7 +956,7 @@ impl SimpleTraceSink {
                 TraceEvent::InstructionRetired { pc: _, cycles } => {
                     output.write_fmt(format_args!("{cycles} cycles\n")).unwrap();
                 }
-                TraceEvent::MemoryAccess { .. } => {}
+                TraceEvent::MemoryAccess { .. } | TraceEvent::TickEnded { .. } => {}
                 TraceEvent::FaultRaised { cause, pc: _ } => {
                     output
                         .write_fmt(format_args!("FAULT {:02X}\n", cause.as_u8()))
@@ -732,12 +1100,12 @@ impl TraceSink for SimpleTraceSink {
 mod tests {
     use super::{
         CanonicalStateLayout, CoreConfig, CoreProfile, CoreSnapshot, CoreState, EventEnqueueError,
-        EventQueueSnapshot, SnapshotLayoutError, SnapshotVersion, ADDRESS_SPACE_BYTES,
-        DEFAULT_TICK_BUDGET_CYCLES, EVENT_QUEUE_CAPACITY,
+        EventQueueSnapshot, RunLimits, SnapshotLayoutError, SnapshotVersion, ADDRESS_SPACE_BYTES,
+        DEFAULT_TICK_BUDGET_CYCLES, EVENT_QUEUE_CAPACITY, SANDBOX_TICK_BUDGET_CYCLES,
     };
     use crate::{
-        ArchitecturalState, FaultCode, GeneralRegister, RunState, CAP_AUTHORITY_DEFAULT_MASK,
-        CAP_RESTRICTED_DEFAULT_MASK,
+        ArchitecturalState, FaultCode, GeneralRegister, PcHistory, PcHistoryEntry, RunState,
+        CAP_AUTHORITY_DEFAULT_MASK, CAP_RESTRICTED_DEFAULT_MASK,
     };
 
     #[test]
@@ -747,6 +1115,23 @@ mod tests {
         assert_eq!(config.profile, CoreProfile::Authority);
         assert_eq!(config.tick_budget_cycles, DEFAULT_TICK_BUDGET_CYCLES);
         assert!(!config.tracing_enabled);
+        assert_eq!(config.run_limits, RunLimits::UNBOUNDED);
+    }
+
+    #[test]
+    fn sandbox_config_restricts_profile_budget_and_run_length() {
+        let config = CoreConfig::sandbox();
+
+        assert_eq!(config.profile, CoreProfile::Restricted);
+        assert_eq!(
+            config.default_capability_mask(),
+            CAP_RESTRICTED_DEFAULT_MASK
+        );
+        assert_eq!(config.tick_budget_cycles, SANDBOX_TICK_BUDGET_CYCLES);
+        assert!(config.experimental_opcodes.is_empty());
+        assert_eq!(config.run_limits.ticks(1_000_000), 100);
+        assert_eq!(config.run_limits.ticks(3), 3);
+        assert_eq!(config.run_limits.steps(u32::MAX), 32_000);
     }
 
     #[test]
@@ -776,7 +1161,10 @@ mod tests {
     #[test]
     fn snapshot_version_roundtrip_is_stable() {
         assert_eq!(SnapshotVersion::from_u16(1), Some(SnapshotVersion::V1));
-        assert_eq!(SnapshotVersion::from_u16(2), None);
+        assert_eq!(SnapshotVersion::from_u16(2), Some(SnapshotVersion::V2));
+        assert_eq!(SnapshotVersion::from_u16(3), Some(SnapshotVersion::V3));
+        assert_eq!(SnapshotVersion::from_u16(4), Some(SnapshotVersion::V4));
+        assert_eq!(SnapshotVersion::from_u16(5), None);
     }
 
     #[test]
@@ -787,6 +1175,16 @@ mod tests {
         assert_eq!(state.arch.cap(), CAP_AUTHORITY_DEFAULT_MASK);
     }
 
+    #[test]
+    fn core_state_starts_at_configured_reset_pc() {
+        let config = CoreConfig {
+            reset_pc: 0x0100,
+            ..CoreConfig::default()
+        };
+        assert_eq!(CoreState::with_config(&config).arch.pc(), 0x0100);
+        assert_eq!(CoreState::default().arch.pc(), 0x0000);
+    }
+
     #[test]
     fn canonical_reset_restores_defaults_and_boot_entry() {
         let mut state = CoreState::default();
@@ -900,6 +1298,23 @@ mod tests {
         assert_eq!(restored, state);
     }
 
+    #[test]
+    fn canonical_layout_leaves_out_pc_history() {
+        let mut state = CoreState::with_config(&CoreConfig {
+            pc_history_depth: 2,
+            ..CoreConfig::default()
+        });
+        state.pc_history.record(PcHistoryEntry {
+            pc: 0x0100,
+            raw_word: 0x1000,
+        });
+
+        let restored = CoreSnapshot::from_core_state(SnapshotVersion::V1, &state)
+            .try_into_core_state()
+            .expect("canonical layout should decode");
+        assert_eq!(restored.pc_history, PcHistory::default());
+    }
+
     #[test]
     fn canonical_layout_rejects_invalid_memory_length() {
         let mut layout = CanonicalStateLayout::from_core_state(&CoreState::default());
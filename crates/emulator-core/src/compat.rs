@@ -0,0 +1,68 @@
+//! Compatibility identifiers for tools that check a build before driving it.
+//!
+//! Editor extensions and CI compare these against the values they were built
+//! for instead of probing behaviour. [`isa_revision`] changes whenever the
+//! instruction set this build implements changes in a way programs can
+//! observe.
+
+use alloc::format;
+
+use crate::encoding::{SpecialRegisterSelect, OPCODE_ENCODING_TABLE};
+use crate::timing::CYCLE_COST_TABLE;
+use crate::SnapshotVersion;
+
+/// Version of the `emulator-core` crate.
+pub const CORE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Newest snapshot schema this build writes and reads.
+pub const LATEST_SNAPSHOT_VERSION: SnapshotVersion = SnapshotVersion::V4;
+
+pub(crate) const FNV_OFFSET: u64 = 0xCBF2_9CE4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+pub(crate) fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Revision hash of the implemented instruction set.
+///
+/// FNV-1a over the opcode encoding table, the cycle-cost table and the `MRS`
+/// selector assignments, in table order. Adding, removing or reassigning an
+/// encoding or changing a cycle cost changes the value.
+#[must_use]
+pub fn isa_revision() -> u64 {
+    let mut hash = FNV_OFFSET;
+    for (op, sub, encoding) in OPCODE_ENCODING_TABLE {
+        hash = fnv1a(hash, &[*op, *sub]);
+        hash = fnv1a(hash, format!("{encoding:?}").as_bytes());
+    }
+    for (kind, cycles) in CYCLE_COST_TABLE {
+        hash = fnv1a(hash, format!("{kind:?}").as_bytes());
+        hash = fnv1a(hash, &cycles.to_be_bytes());
+    }
+    for select in SpecialRegisterSelect::ALL {
+        hash = fnv1a(hash, &[select as u8]);
+        hash = fnv1a(hash, select.name().as_bytes());
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fnv1a, isa_revision, FNV_OFFSET};
+
+    #[test]
+    fn fnv1a_matches_reference_vectors() {
+        assert_eq!(fnv1a(FNV_OFFSET, b""), 0xCBF2_9CE4_8422_2325);
+        assert_eq!(fnv1a(FNV_OFFSET, b"a"), 0xAF63_DC4C_8601_EC8C);
+    }
+
+    #[test]
+    fn isa_revision_is_pinned() {
+        // Update deliberately when the instruction set changes, and note the
+        // change for tools that key off this value.
+        assert_eq!(isa_revision(), 0x76A7_CD97_0DF2_6E8F);
+    }
+}
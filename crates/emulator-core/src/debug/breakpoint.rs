@@ -0,0 +1,264 @@
+//! Address breakpoints with optional conditions.
+//!
+//! A breakpoint fires when the core is about to execute the instruction at
+//! its address. A condition, if present, is evaluated in the core at that
+//! moment and the breakpoint only fires when it is true, so hosts do not
+//! need a round trip per hit to filter rarely-true conditions.
+//!
+//! A breakpoint may also skip its first N qualifying hits (an ignore count)
+//! and may be temporary, in which case it removes itself after firing once.
+
+use alloc::collections::BTreeMap;
+
+use crate::debug::expr::WatchExpr;
+use crate::state::ArchitecturalState;
+
+/// One breakpoint in a [`BreakpointTable`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Breakpoint {
+    /// Instruction address the breakpoint is attached to.
+    pub address: u16,
+    /// Condition that must be true for the breakpoint to fire.
+    pub condition: Option<WatchExpr>,
+    /// Remaining qualifying hits to skip before the breakpoint fires.
+    pub ignore_count: u32,
+    /// Whether the breakpoint is removed after it fires.
+    pub temporary: bool,
+    /// Qualifying hits so far, including skipped ones.
+    pub hit_count: u32,
+}
+
+impl Breakpoint {
+    /// Creates an unconditional breakpoint at `address`.
+    #[must_use]
+    pub const fn new(address: u16) -> Self {
+        Self {
+            address,
+            condition: None,
+            ignore_count: 0,
+            temporary: false,
+            hit_count: 0,
+        }
+    }
+
+    /// Sets the condition that must hold for the breakpoint to fire.
+    #[must_use]
+    pub fn with_condition(mut self, condition: WatchExpr) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    /// Skips the first `count` qualifying hits.
+    #[must_use]
+    pub const fn with_ignore_count(mut self, count: u32) -> Self {
+        self.ignore_count = count;
+        self
+    }
+
+    /// Makes the breakpoint one-shot.
+    #[must_use]
+    pub const fn temporary(mut self) -> Self {
+        self.temporary = true;
+        self
+    }
+}
+
+/// A breakpoint that fired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BreakpointHit {
+    /// Address of the breakpoint.
+    pub address: u16,
+    /// Qualifying hits including this one.
+    pub hit_count: u32,
+    /// Whether the breakpoint was temporary and has been removed.
+    pub removed: bool,
+}
+
+/// Breakpoints keyed by address (at most one per address).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BreakpointTable {
+    breakpoints: BTreeMap<u16, Breakpoint>,
+}
+
+impl BreakpointTable {
+    /// Creates an empty table.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            breakpoints: BTreeMap::new(),
+        }
+    }
+
+    /// Sets a breakpoint, replacing any existing one at `address`.
+    pub fn set(&mut self, address: u16, condition: Option<WatchExpr>) {
+        self.insert(Breakpoint {
+            condition,
+            ..Breakpoint::new(address)
+        });
+    }
+
+    /// Inserts a breakpoint, replacing any existing one at its address.
+    pub fn insert(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.insert(breakpoint.address, breakpoint);
+    }
+
+    /// Removes the breakpoint at `address`, returning whether one existed.
+    pub fn remove(&mut self, address: u16) -> bool {
+        self.breakpoints.remove(&address).is_some()
+    }
+
+    /// Removes all breakpoints.
+    pub fn clear(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Returns the breakpoint at `address`, if any.
+    #[must_use]
+    pub fn get(&self, address: u16) -> Option<&Breakpoint> {
+        self.breakpoints.get(&address)
+    }
+
+    /// Iterates breakpoints in address order.
+    pub fn iter(&self) -> impl Iterator<Item = &Breakpoint> {
+        self.breakpoints.values()
+    }
+
+    /// Number of breakpoints.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.breakpoints.len()
+    }
+
+    /// Returns true when no breakpoints are set.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.breakpoints.is_empty()
+    }
+
+    /// Checks whether a breakpoint fires at the current PC.
+    ///
+    /// A hit whose condition holds counts towards `hit_count` and consumes
+    /// one unit of the ignore count if any remains. Temporary breakpoints
+    /// are removed when they fire.
+    pub fn check(&mut self, arch: &ArchitecturalState, memory: &[u8]) -> Option<BreakpointHit> {
+        let address = arch.pc();
+        let breakpoint = self.breakpoints.get_mut(&address)?;
+        let qualifies = breakpoint
+            .condition
+            .as_ref()
+            .is_none_or(|condition| condition.is_true(arch, memory));
+        if !qualifies {
+            return None;
+        }
+
+        breakpoint.hit_count = breakpoint.hit_count.saturating_add(1);
+        if breakpoint.ignore_count > 0 {
+            breakpoint.ignore_count -= 1;
+            return None;
+        }
+
+        let hit = BreakpointHit {
+            address,
+            hit_count: breakpoint.hit_count,
+            removed: breakpoint.temporary,
+        };
+        if breakpoint.temporary {
+            self.breakpoints.remove(&address);
+        }
+        Some(hit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::new_address_space;
+    use crate::state::GeneralRegister;
+
+    #[test]
+    fn unconditional_breakpoint_fires_at_address() {
+        let mut table = BreakpointTable::new();
+        table.set(0x0010, None);
+        let mut arch = ArchitecturalState::default();
+        let memory = new_address_space();
+
+        assert_eq!(table.check(&arch, &memory), None);
+        arch.set_pc(0x0010);
+        assert_eq!(
+            table.check(&arch, &memory),
+            Some(BreakpointHit {
+                address: 0x0010,
+                hit_count: 1,
+                removed: false,
+            })
+        );
+    }
+
+    #[test]
+    fn conditional_breakpoint_fires_only_when_true() {
+        let mut table = BreakpointTable::new();
+        let condition = WatchExpr::parse("R3 == 0 && [0x4100] != 0").unwrap();
+        table.set(0x0010, Some(condition));
+        let mut arch = ArchitecturalState::default();
+        arch.set_pc(0x0010);
+        arch.set_gpr(GeneralRegister::R3, 1);
+        let mut memory = new_address_space();
+
+        assert_eq!(table.check(&arch, &memory), None);
+        arch.set_gpr(GeneralRegister::R3, 0);
+        assert_eq!(table.check(&arch, &memory), None);
+        memory[0x4101] = 1;
+        assert!(table.check(&arch, &memory).is_some());
+    }
+
+    #[test]
+    fn ignore_count_skips_qualifying_hits() {
+        let mut table = BreakpointTable::new();
+        let condition = WatchExpr::parse("R0 != 0").unwrap();
+        table.insert(
+            Breakpoint::new(0x0010)
+                .with_condition(condition)
+                .with_ignore_count(2),
+        );
+        let mut arch = ArchitecturalState::default();
+        arch.set_pc(0x0010);
+        let memory = new_address_space();
+
+        // Non-qualifying hits do not consume the ignore count.
+        assert_eq!(table.check(&arch, &memory), None);
+        arch.set_gpr(GeneralRegister::R0, 1);
+        assert_eq!(table.check(&arch, &memory), None);
+        assert_eq!(table.check(&arch, &memory), None);
+        let hit = table.check(&arch, &memory).expect("third hit fires");
+        assert_eq!(hit.hit_count, 3);
+        assert!(table.check(&arch, &memory).is_some());
+    }
+
+    #[test]
+    fn temporary_breakpoint_removes_itself() {
+        let mut table = BreakpointTable::new();
+        table.insert(Breakpoint::new(0x0010).with_ignore_count(1).temporary());
+        let mut arch = ArchitecturalState::default();
+        arch.set_pc(0x0010);
+        let memory = new_address_space();
+
+        assert_eq!(table.check(&arch, &memory), None);
+        let hit = table.check(&arch, &memory).expect("second hit fires");
+        assert!(hit.removed);
+        assert!(table.is_empty());
+        assert_eq!(table.check(&arch, &memory), None);
+    }
+
+    #[test]
+    fn set_replaces_and_remove_reports_presence() {
+        let mut table = BreakpointTable::new();
+        table.set(0x0010, Some(WatchExpr::parse("0").unwrap()));
+        table.set(0x0010, None);
+
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.get(0x0010).unwrap().condition, None);
+        assert!(table.remove(0x0010));
+        assert!(!table.remove(0x0010));
+        assert!(table.is_empty());
+    }
+}
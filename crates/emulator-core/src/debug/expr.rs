@@ -0,0 +1,527 @@
+//! Watch-expression parser and evaluator.
+//!
+//! Expressions are evaluated against live machine state and are shared by
+//! watch panels and breakpoint conditions. All values are 16-bit words and
+//! arithmetic wraps. Comparisons and logical operators yield `1` or `0`.
+//!
+//! ```text
+//! expr    := or
+//! or      := and ("||" and)*
+//! and     := compare ("&&" compare)*
+//! compare := bitor (("==" | "!=" | "<" | "<=" | ">" | ">=") bitor)?
+//! bitor   := bitxor ("|" bitxor)*
+//! bitxor  := bitand ("^" bitand)*
+//! bitand  := sum ("&" sum)*
+//! sum     := unary (("+" | "-") unary)*
+//! unary   := ("!" | "~" | "-") unary | primary
+//! primary := number | register | "[" expr "]" | "(" expr ")"
+//! ```
+//!
+//! Registers are `R0`-`R7`, `PC`, `SP`, `FLAGS`, `TICK`, `CAP`, `CAUSE` and
+//! `EVP` (case-insensitive). `[expr]` reads the big-endian word at the
+//! computed address from the backing memory image; MMIO registers are not
+//! polled. Numbers are decimal, `0x` hex or `0b` binary.
+
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use thiserror::Error;
+
+use crate::state::{ArchitecturalState, GeneralRegister};
+
+/// Error produced when a watch expression fails to parse.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Error)]
+#[error("{message} at column {column}")]
+pub struct WatchExprError {
+    /// 1-based column of the offending input.
+    pub column: usize,
+    /// Description of the problem.
+    pub message: String,
+}
+
+/// Register operand of a watch expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WatchRegister {
+    /// General-purpose register.
+    General(GeneralRegister),
+    /// Program counter.
+    Pc,
+    /// Stack pointer.
+    Sp,
+    /// Flags register.
+    Flags,
+    /// Tick counter.
+    Tick,
+    /// Capability register.
+    Cap,
+    /// Fault/trap cause register.
+    Cause,
+    /// Event pending register.
+    Evp,
+}
+
+impl WatchRegister {
+    fn parse(name: &str) -> Option<Self> {
+        let upper = name.to_ascii_uppercase();
+        let register = match upper.as_str() {
+            "R0" => Self::General(GeneralRegister::R0),
+            "R1" => Self::General(GeneralRegister::R1),
+            "R2" => Self::General(GeneralRegister::R2),
+            "R3" => Self::General(GeneralRegister::R3),
+            "R4" => Self::General(GeneralRegister::R4),
+            "R5" => Self::General(GeneralRegister::R5),
+            "R6" => Self::General(GeneralRegister::R6),
+            "R7" => Self::General(GeneralRegister::R7),
+            "PC" => Self::Pc,
+            "SP" => Self::Sp,
+            "FLAGS" => Self::Flags,
+            "TICK" => Self::Tick,
+            "CAP" => Self::Cap,
+            "CAUSE" => Self::Cause,
+            "EVP" => Self::Evp,
+            _ => return None,
+        };
+        Some(register)
+    }
+
+    const fn read(self, arch: &ArchitecturalState) -> u16 {
+        match self {
+            Self::General(register) => arch.gpr(register),
+            Self::Pc => arch.pc(),
+            Self::Sp => arch.sp(),
+            Self::Flags => arch.flags(),
+            Self::Tick => arch.tick(),
+            Self::Cap => arch.cap(),
+            Self::Cause => arch.cause(),
+            Self::Evp => arch.evp(),
+        }
+    }
+}
+
+/// Unary operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnaryOp {
+    /// Logical not (`!`).
+    Not,
+    /// Bitwise complement (`~`).
+    Complement,
+    /// Two's-complement negation (`-`).
+    Negate,
+}
+
+/// Binary operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    /// Logical or (`||`).
+    Or,
+    /// Logical and (`&&`).
+    And,
+    /// Equality (`==`).
+    Eq,
+    /// Inequality (`!=`).
+    Ne,
+    /// Unsigned less-than (`<`).
+    Lt,
+    /// Unsigned less-or-equal (`<=`).
+    Le,
+    /// Unsigned greater-than (`>`).
+    Gt,
+    /// Unsigned greater-or-equal (`>=`).
+    Ge,
+    /// Bitwise or (`|`).
+    BitOr,
+    /// Bitwise xor (`^`).
+    BitXor,
+    /// Bitwise and (`&`).
+    BitAnd,
+    /// Wrapping addition (`+`).
+    Add,
+    /// Wrapping subtraction (`-`).
+    Sub,
+}
+
+impl BinaryOp {
+    fn apply(self, lhs: u16, rhs: u16) -> u16 {
+        match self {
+            Self::Or => u16::from(lhs != 0 || rhs != 0),
+            Self::And => u16::from(lhs != 0 && rhs != 0),
+            Self::Eq => u16::from(lhs == rhs),
+            Self::Ne => u16::from(lhs != rhs),
+            Self::Lt => u16::from(lhs < rhs),
+            Self::Le => u16::from(lhs <= rhs),
+            Self::Gt => u16::from(lhs > rhs),
+            Self::Ge => u16::from(lhs >= rhs),
+            Self::BitOr => lhs | rhs,
+            Self::BitXor => lhs ^ rhs,
+            Self::BitAnd => lhs & rhs,
+            Self::Add => lhs.wrapping_add(rhs),
+            Self::Sub => lhs.wrapping_sub(rhs),
+        }
+    }
+}
+
+/// Parsed watch expression.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WatchExpr {
+    /// Literal word.
+    Literal(u16),
+    /// Register read.
+    Register(WatchRegister),
+    /// Memory word read at the computed address.
+    Memory(Box<Self>),
+    /// Unary operation.
+    Unary(UnaryOp, Box<Self>),
+    /// Binary operation.
+    Binary(BinaryOp, Box<Self>, Box<Self>),
+}
+
+impl WatchExpr {
+    /// Parses an expression such as `R3 == 0 && [0x4100] != 0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`WatchExprError`] for malformed input.
+    pub fn parse(text: &str) -> Result<Self, WatchExprError> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            end_column: text.chars().count() + 1,
+        };
+        let expr = parser.parse_or()?;
+        if let Some((column, token)) = parser.tokens.get(parser.position) {
+            return Err(WatchExprError {
+                column: *column,
+                message: format!("unexpected '{token}'"),
+            });
+        }
+        Ok(expr)
+    }
+
+    /// Evaluates the expression against registers and the memory image.
+    #[must_use]
+    pub fn evaluate(&self, arch: &ArchitecturalState, memory: &[u8]) -> u16 {
+        match self {
+            Self::Literal(value) => *value,
+            Self::Register(register) => register.read(arch),
+            Self::Memory(address) => {
+                let address = address.evaluate(arch, memory);
+                let hi = memory.get(usize::from(address)).copied().unwrap_or(0);
+                let lo = memory
+                    .get(usize::from(address.wrapping_add(1)))
+                    .copied()
+                    .unwrap_or(0);
+                u16::from_be_bytes([hi, lo])
+            }
+            Self::Unary(op, operand) => {
+                let value = operand.evaluate(arch, memory);
+                match op {
+                    UnaryOp::Not => u16::from(value == 0),
+                    UnaryOp::Complement => !value,
+                    UnaryOp::Negate => value.wrapping_neg(),
+                }
+            }
+            Self::Binary(BinaryOp::And, lhs, rhs) => {
+                u16::from(lhs.evaluate(arch, memory) != 0 && rhs.evaluate(arch, memory) != 0)
+            }
+            Self::Binary(BinaryOp::Or, lhs, rhs) => {
+                u16::from(lhs.evaluate(arch, memory) != 0 || rhs.evaluate(arch, memory) != 0)
+            }
+            Self::Binary(op, lhs, rhs) => {
+                op.apply(lhs.evaluate(arch, memory), rhs.evaluate(arch, memory))
+            }
+        }
+    }
+
+    /// Evaluates the expression as a condition (non-zero is true).
+    #[must_use]
+    pub fn is_true(&self, arch: &ArchitecturalState, memory: &[u8]) -> bool {
+        self.evaluate(arch, memory) != 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(u16),
+    Ident(String),
+    Punct(&'static str),
+}
+
+impl core::fmt::Display for Token {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Number(value) => write!(f, "{value}"),
+            Self::Ident(name) => f.write_str(name),
+            Self::Punct(punct) => f.write_str(punct),
+        }
+    }
+}
+
+const PUNCTUATION: [&str; 19] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "|", "^", "&", "+", "-", "!", "~", "[", "]", "(",
+    ")",
+];
+
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, WatchExprError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let ch = chars[index];
+        let column = index + 1;
+        if ch.is_whitespace() {
+            index += 1;
+        } else if ch.is_ascii_digit() {
+            let start = index;
+            while index < chars.len() && chars[index].is_ascii_alphanumeric() {
+                index += 1;
+            }
+            let literal: String = chars[start..index].iter().collect();
+            let value = parse_number(&literal).ok_or_else(|| WatchExprError {
+                column,
+                message: format!("invalid number '{literal}'"),
+            })?;
+            tokens.push((column, Token::Number(value)));
+        } else if ch.is_ascii_alphabetic() || ch == '_' {
+            let start = index;
+            while index < chars.len()
+                && (chars[index].is_ascii_alphanumeric() || chars[index] == '_')
+            {
+                index += 1;
+            }
+            tokens.push((column, Token::Ident(chars[start..index].iter().collect())));
+        } else {
+            let rest: String = chars[index..chars.len().min(index + 2)].iter().collect();
+            let punct = PUNCTUATION
+                .iter()
+                .find(|punct| rest.starts_with(**punct))
+                .ok_or_else(|| WatchExprError {
+                    column,
+                    message: format!("unexpected character '{ch}'"),
+                })?;
+            index += punct.len();
+            tokens.push((column, Token::Punct(punct)));
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_number(literal: &str) -> Option<u16> {
+    let lower = literal.to_ascii_lowercase();
+    let (digits, radix) = lower
+        .strip_prefix("0x")
+        .map(|hex| (hex, 16))
+        .or_else(|| lower.strip_prefix("0b").map(|binary| (binary, 2)))
+        .unwrap_or((&lower, 10));
+    u16::from_str_radix(digits, radix).ok()
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    end_column: usize,
+}
+
+impl Parser {
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.tokens.get(self.position), Some((_, Token::Punct(p))) if *p == punct) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), WatchExprError> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{punct}'")))
+        }
+    }
+
+    fn error(&self, message: &str) -> WatchExprError {
+        let column = self
+            .tokens
+            .get(self.position)
+            .map_or(self.end_column, |(column, _)| *column);
+        WatchExprError {
+            column,
+            message: message.to_string(),
+        }
+    }
+
+    fn binary_level(
+        &mut self,
+        operators: &[(&str, BinaryOp)],
+        next: fn(&mut Self) -> Result<WatchExpr, WatchExprError>,
+    ) -> Result<WatchExpr, WatchExprError> {
+        let mut lhs = next(self)?;
+        'outer: loop {
+            for (punct, op) in operators {
+                if self.eat(punct) {
+                    let rhs = next(self)?;
+                    lhs = WatchExpr::Binary(*op, Box::new(lhs), Box::new(rhs));
+                    continue 'outer;
+                }
+            }
+            return Ok(lhs);
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<WatchExpr, WatchExprError> {
+        self.binary_level(&[("||", BinaryOp::Or)], Self::parse_and)
+    }
+
+    fn parse_and(&mut self) -> Result<WatchExpr, WatchExprError> {
+        self.binary_level(&[("&&", BinaryOp::And)], Self::parse_compare)
+    }
+
+    fn parse_compare(&mut self) -> Result<WatchExpr, WatchExprError> {
+        let lhs = self.parse_bitor()?;
+        for (punct, op) in [
+            ("==", BinaryOp::Eq),
+            ("!=", BinaryOp::Ne),
+            ("<=", BinaryOp::Le),
+            (">=", BinaryOp::Ge),
+            ("<", BinaryOp::Lt),
+            (">", BinaryOp::Gt),
+        ] {
+            if self.eat(punct) {
+                let rhs = self.parse_bitor()?;
+                return Ok(WatchExpr::Binary(op, Box::new(lhs), Box::new(rhs)));
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn parse_bitor(&mut self) -> Result<WatchExpr, WatchExprError> {
+        self.binary_level(&[("|", BinaryOp::BitOr)], Self::parse_bitxor)
+    }
+
+    fn parse_bitxor(&mut self) -> Result<WatchExpr, WatchExprError> {
+        self.binary_level(&[("^", BinaryOp::BitXor)], Self::parse_bitand)
+    }
+
+    fn parse_bitand(&mut self) -> Result<WatchExpr, WatchExprError> {
+        self.binary_level(&[("&", BinaryOp::BitAnd)], Self::parse_sum)
+    }
+
+    fn parse_sum(&mut self) -> Result<WatchExpr, WatchExprError> {
+        self.binary_level(
+            &[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+            Self::parse_unary,
+        )
+    }
+
+    fn parse_unary(&mut self) -> Result<WatchExpr, WatchExprError> {
+        for (punct, op) in [
+            ("!", UnaryOp::Not),
+            ("~", UnaryOp::Complement),
+            ("-", UnaryOp::Negate),
+        ] {
+            if self.eat(punct) {
+                let operand = self.parse_unary()?;
+                return Ok(WatchExpr::Unary(op, Box::new(operand)));
+            }
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<WatchExpr, WatchExprError> {
+        if self.eat("[") {
+            let address = self.parse_or()?;
+            self.expect("]")?;
+            return Ok(WatchExpr::Memory(Box::new(address)));
+        }
+        if self.eat("(") {
+            let inner = self.parse_or()?;
+            self.expect(")")?;
+            return Ok(inner);
+        }
+        match self.tokens.get(self.position).cloned() {
+            Some((_, Token::Number(value))) => {
+                self.position += 1;
+                Ok(WatchExpr::Literal(value))
+            }
+            Some((column, Token::Ident(name))) => {
+                let register = WatchRegister::parse(&name).ok_or_else(|| WatchExprError {
+                    column,
+                    message: format!("unknown register '{name}'"),
+                })?;
+                self.position += 1;
+                Ok(WatchExpr::Register(register))
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::new_address_space;
+
+    fn eval(text: &str, arch: &ArchitecturalState, memory: &[u8]) -> u16 {
+        WatchExpr::parse(text)
+            .expect("expression should parse")
+            .evaluate(arch, memory)
+    }
+
+    #[test]
+    fn evaluates_registers_and_memory() {
+        let mut arch = ArchitecturalState::default();
+        arch.set_gpr(GeneralRegister::R3, 0);
+        let mut memory = new_address_space();
+        memory[0x4100] = 0x12;
+        memory[0x4101] = 0x34;
+
+        assert_eq!(eval("[0x4100]", &arch, &memory), 0x1234);
+        assert_eq!(eval("R3 == 0 && [0x4100] != 0", &arch, &memory), 1);
+        assert_eq!(eval("r3 != 0 || [0x4100] == 0", &arch, &memory), 0);
+    }
+
+    #[test]
+    fn respects_precedence() {
+        let arch = ArchitecturalState::default();
+        let memory = new_address_space();
+
+        assert_eq!(eval("1 + 2 & 3", &arch, &memory), 3);
+        assert_eq!(eval("1 | 2 == 3", &arch, &memory), 1);
+        assert_eq!(eval("(1 | 2) ^ 0b11", &arch, &memory), 0);
+        assert_eq!(eval("-1", &arch, &memory), 0xFFFF);
+        assert_eq!(eval("!0 && ~0xFFFF == 0", &arch, &memory), 1);
+    }
+
+    #[test]
+    fn memory_operand_takes_expression() {
+        let mut arch = ArchitecturalState::default();
+        arch.set_gpr(GeneralRegister::R1, 0x40FE);
+        let mut memory = new_address_space();
+        memory[0x4101] = 0x07;
+
+        assert_eq!(eval("[R1 + 2]", &arch, &memory), 0x0007);
+    }
+
+    #[test]
+    fn reports_error_columns() {
+        let err = WatchExpr::parse("R9 == 1").unwrap_err();
+        assert_eq!(err.column, 1);
+        assert_eq!(err.message, "unknown register 'R9'");
+
+        let err = WatchExpr::parse("[0x4100").unwrap_err();
+        assert_eq!(err.column, 8);
+
+        let err = WatchExpr::parse("R0 == 1 R1").unwrap_err();
+        assert_eq!(err.to_string(), "unexpected 'R1' at column 9");
+
+        assert!(WatchExpr::parse("0x10000").is_err());
+        assert!(WatchExpr::parse("R0 @ 1").is_err());
+    }
+}
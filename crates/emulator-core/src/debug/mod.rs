@@ -0,0 +1,16 @@
+//! Host debugging support: watch expressions, breakpoints, call-aware
+//! stepping and memory search.
+
+/// Breakpoint table with optional per-breakpoint conditions.
+pub mod breakpoint;
+/// Watch-expression parser and evaluator.
+pub mod expr;
+/// Wildcard byte-pattern search over the memory image.
+pub mod search;
+/// Call/return aware step-over and step-out.
+pub mod stepping;
+
+pub use breakpoint::{Breakpoint, BreakpointHit, BreakpointTable};
+pub use expr::{BinaryOp, UnaryOp, WatchExpr, WatchExprError, WatchRegister};
+pub use search::{BytePattern, BytePatternError};
+pub use stepping::{step_out, step_over, StepStop, SteppingOutcome};
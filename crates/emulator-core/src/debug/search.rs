@@ -0,0 +1,163 @@
+//! Memory pattern search.
+//!
+//! Patterns are whitespace-separated hex bytes with `??` matching any byte,
+//! for example `12 ?? 34`. Words are searched as their big-endian byte
+//! pair, matching how the core stores them. Matches may overlap and are
+//! reported in ascending address order.
+
+use alloc::{string::String, vec::Vec};
+
+use thiserror::Error;
+
+/// Error produced when a byte pattern fails to parse.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Error)]
+pub enum BytePatternError {
+    /// The pattern has no bytes.
+    #[error("pattern is empty")]
+    Empty,
+    /// A token is neither two hex digits nor `??`.
+    #[error("'{token}' is not a hex byte or '??' (token {index})")]
+    InvalidToken {
+        /// 1-based position of the token.
+        index: usize,
+        /// The offending token.
+        token: String,
+    },
+}
+
+/// A byte sequence with wildcard positions.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BytePattern {
+    bytes: Vec<Option<u8>>,
+}
+
+impl BytePattern {
+    /// Parses a pattern such as `12 ?? 34`.
+    ///
+    /// Tokens are case-insensitive and may carry a `0x` prefix.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BytePatternError::Empty`] for a blank pattern and
+    /// [`BytePatternError::InvalidToken`] for anything that is not a byte
+    /// or `??`.
+    pub fn parse(text: &str) -> Result<Self, BytePatternError> {
+        let bytes = text
+            .split_whitespace()
+            .enumerate()
+            .map(|(index, token)| {
+                if token == "??" {
+                    return Ok(None);
+                }
+                let digits = token
+                    .strip_prefix("0x")
+                    .or_else(|| token.strip_prefix("0X"))
+                    .unwrap_or(token);
+                if digits.len() != 2 {
+                    return Err(invalid(index, token));
+                }
+                u8::from_str_radix(digits, 16)
+                    .map(Some)
+                    .map_err(|_| invalid(index, token))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if bytes.is_empty() {
+            return Err(BytePatternError::Empty);
+        }
+        Ok(Self { bytes })
+    }
+
+    /// Pattern matching exactly `bytes`.
+    #[must_use]
+    pub fn exact(bytes: &[u8]) -> Self {
+        Self {
+            bytes: bytes.iter().copied().map(Some).collect(),
+        }
+    }
+
+    /// Pattern matching the big-endian encoding of `value`.
+    #[must_use]
+    pub fn word(value: u16) -> Self {
+        Self::exact(&value.to_be_bytes())
+    }
+
+    /// Number of bytes the pattern spans, wildcards included.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns whether the pattern has no bytes; parsed patterns never do.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn matches(&self, window: &[u8]) -> bool {
+        self.bytes
+            .iter()
+            .zip(window)
+            .all(|(expected, actual)| expected.is_none_or(|byte| byte == *actual))
+    }
+
+    /// Returns the start address of every match in `memory`.
+    ///
+    /// An empty pattern matches nowhere.
+    #[must_use]
+    pub fn find(&self, memory: &[u8]) -> Vec<u16> {
+        if self.bytes.is_empty() {
+            return Vec::new();
+        }
+        memory
+            .windows(self.bytes.len())
+            .enumerate()
+            .filter(|(_, window)| self.matches(window))
+            .filter_map(|(address, _)| u16::try_from(address).ok())
+            .collect()
+    }
+}
+
+fn invalid(index: usize, token: &str) -> BytePatternError {
+    BytePatternError::InvalidToken {
+        index: index + 1,
+        token: token.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bytes_and_wildcards() {
+        let pattern = BytePattern::parse("12 ?? 0x3a").unwrap();
+        assert_eq!(pattern.bytes, [Some(0x12), None, Some(0x3A)]);
+        assert_eq!(BytePattern::parse("  "), Err(BytePatternError::Empty));
+        assert_eq!(
+            BytePattern::parse("12 345"),
+            Err(BytePatternError::InvalidToken {
+                index: 2,
+                token: "345".into(),
+            })
+        );
+        assert!(BytePattern::parse("?").is_err());
+    }
+
+    #[test]
+    fn finds_overlapping_matches_with_wildcards() {
+        let memory = [0x12, 0x00, 0x34, 0x12, 0x34, 0x34, 0x12];
+        let pattern = BytePattern::parse("12 ?? 34").unwrap();
+        assert_eq!(pattern.find(&memory), [0, 3]);
+        assert_eq!(
+            BytePattern::parse("34 ??").unwrap().find(&memory),
+            [2, 4, 5]
+        );
+    }
+
+    #[test]
+    fn words_match_big_endian() {
+        let memory = [0x00, 0xBE, 0xEF, 0xEF, 0xBE];
+        assert_eq!(BytePattern::word(0xBEEF).find(&memory), [1]);
+        assert!(BytePattern::word(0x1234).find(&memory).is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+//! Call/return aware stepping.
+//!
+//! `step_over` runs a `CALL` and its callee as one step; `step_out` runs
+//! until the current subroutine returns to its caller. Both track call depth
+//! by recognising the instruction at PC before each step: `CALL` and
+//! trap/event dispatch enter a frame, `RET` and `ERET` leave one. Stepping
+//! stops early at a breakpoint, at a tick boundary, on a fault, or after
+//! `max_steps` instructions.
+
+use crate::debug::breakpoint::BreakpointHit;
+use crate::decoder::{AddressingMode, Decoder};
+use crate::encoding::OpcodeEncoding;
+use crate::{step_one, CoreConfig, CoreState, MmioBus, StepOutcome};
+
+/// Why a call-aware step stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StepStop {
+    /// The requested step-over or step-out finished.
+    Completed,
+    /// A breakpoint fired inside the callee.
+    Breakpoint(BreakpointHit),
+    /// The core halted for the current tick; the host must start a new
+    /// tick before stepping again.
+    HaltedForTick,
+    /// A fault was raised.
+    Fault,
+    /// `max_steps` instructions executed without finishing.
+    StepLimit,
+}
+
+/// Result of [`step_over`] or [`step_out`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SteppingOutcome {
+    /// Number of steps executed.
+    pub steps: u32,
+    /// Outcome of the last step executed, if any.
+    pub final_step: Option<StepOutcome>,
+    /// Why stepping stopped.
+    pub stop: StepStop,
+}
+
+/// Executes one instruction, running a `CALL` through to its return.
+///
+/// Non-call instructions behave like a single [`step_one`].
+pub fn step_over(
+    state: &mut CoreState,
+    mmio: &mut dyn MmioBus,
+    config: &CoreConfig,
+    max_steps: u32,
+) -> SteppingOutcome {
+    run_until_depth(state, mmio, config, max_steps, 0)
+}
+
+/// Runs until the current subroutine or handler returns to its caller.
+pub fn step_out(
+    state: &mut CoreState,
+    mmio: &mut dyn MmioBus,
+    config: &CoreConfig,
+    max_steps: u32,
+) -> SteppingOutcome {
+    run_until_depth(state, mmio, config, max_steps, -1)
+}
+
+/// Steps until the relative call depth drops to `target`.
+fn run_until_depth(
+    state: &mut CoreState,
+    mmio: &mut dyn MmioBus,
+    config: &CoreConfig,
+    max_steps: u32,
+    target: i32,
+) -> SteppingOutcome {
+    let max_steps = config.run_limits.steps(max_steps);
+    let mut depth = 0i32;
+    let mut outcome = SteppingOutcome {
+        steps: 0,
+        final_step: None,
+        stop: StepStop::StepLimit,
+    };
+
+    while outcome.steps < max_steps {
+        let delta = frame_delta(state);
+        let step = step_one(state, mmio, config);
+        outcome.steps += 1;
+        outcome.final_step = Some(step);
+
+        match step {
+            StepOutcome::Fault { .. } => {
+                outcome.stop = StepStop::Fault;
+                return outcome;
+            }
+            StepOutcome::HaltedForTick { .. } => {
+                outcome.stop = StepStop::HaltedForTick;
+                return outcome;
+            }
+            StepOutcome::TrapDispatch { .. } | StepOutcome::EventDispatch { .. } => {
+                depth += delta + 1;
+            }
+            StepOutcome::Retired { .. } => depth += delta,
+        }
+
+        if depth <= target {
+            outcome.stop = StepStop::Completed;
+            return outcome;
+        }
+        if let Some(hit) = state.breakpoints.check(&state.arch, &state.memory) {
+            outcome.stop = StepStop::Breakpoint(hit);
+            return outcome;
+        }
+    }
+
+    outcome
+}
+
+/// Call-depth change caused by retiring the instruction at PC.
+fn frame_delta(state: &CoreState) -> i32 {
+    let pc = state.arch.pc();
+    let raw_word = u16::from_be_bytes([
+        state.memory[usize::from(pc)],
+        state.memory[usize::from(pc.wrapping_add(1))],
+    ]);
+    let Some(instruction) = Decoder::decode(raw_word).instruction() else {
+        return 0;
+    };
+    match instruction.encoding {
+        OpcodeEncoding::CallOrRet => {
+            if matches!(
+                instruction.addressing_mode,
+                Some(AddressingMode::DirectRegister)
+            ) {
+                -1
+            } else {
+                1
+            }
+        }
+        OpcodeEncoding::Eret => -1,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MmioError, MmioWriteResult};
+
+    struct NoMmio;
+
+    impl MmioBus for NoMmio {
+        fn read16(&mut self, _addr: u16) -> Result<u16, MmioError> {
+            Err(MmioError::ReadFailed)
+        }
+
+        fn write16(&mut self, _addr: u16, _value: u16) -> Result<MmioWriteResult, MmioError> {
+            Err(MmioError::WriteFailed)
+        }
+    }
+
+    /// `CALL sub; NOP; HALT; sub: NOP; NOP; RET`.
+    fn call_program() -> CoreState {
+        let mut state = CoreState::default();
+        let words: [u16; 7] = [0x603D, 0x0004, 0x0000, 0x0010, 0x0000, 0x0000, 0x6038];
+        for (index, word) in words.iter().enumerate() {
+            let [hi, lo] = word.to_be_bytes();
+            state.memory[index * 2] = hi;
+            state.memory[index * 2 + 1] = lo;
+        }
+        state.arch.set_sp(0x8000);
+        state
+    }
+
+    #[test]
+    fn step_over_runs_callee_to_return() {
+        let mut state = call_program();
+        let outcome = step_over(&mut state, &mut NoMmio, &CoreConfig::default(), 100);
+
+        assert_eq!(outcome.stop, StepStop::Completed);
+        assert_eq!(outcome.steps, 4);
+        assert_eq!(state.arch.pc(), 0x0004);
+        assert_eq!(state.arch.sp(), 0x8000);
+    }
+
+    #[test]
+    fn step_over_plain_instruction_is_single_step() {
+        let mut state = call_program();
+        state.arch.set_pc(0x0004);
+        let outcome = step_over(&mut state, &mut NoMmio, &CoreConfig::default(), 100);
+
+        assert_eq!(outcome.stop, StepStop::Completed);
+        assert_eq!(outcome.steps, 1);
+        assert_eq!(state.arch.pc(), 0x0006);
+    }
+
+    #[test]
+    fn step_out_returns_to_caller() {
+        let mut state = call_program();
+        let config = CoreConfig::default();
+        step_one(&mut state, &mut NoMmio, &config);
+        assert_eq!(state.arch.pc(), 0x0008);
+
+        let outcome = step_out(&mut state, &mut NoMmio, &config, 100);
+
+        assert_eq!(outcome.stop, StepStop::Completed);
+        assert_eq!(outcome.steps, 3);
+        assert_eq!(state.arch.pc(), 0x0004);
+    }
+
+    #[test]
+    fn step_over_stops_at_breakpoint_in_callee() {
+        let mut state = call_program();
+        state.breakpoints.set(0x000A, None);
+        let outcome = step_over(&mut state, &mut NoMmio, &CoreConfig::default(), 100);
+
+        assert!(matches!(outcome.stop, StepStop::Breakpoint(hit) if hit.address == 0x000A));
+        assert_eq!(state.arch.pc(), 0x000A);
+    }
+
+    #[test]
+    fn step_limit_is_reported() {
+        let mut state = call_program();
+        let outcome = step_over(&mut state, &mut NoMmio, &CoreConfig::default(), 2);
+
+        assert_eq!(outcome.stop, StepStop::StepLimit);
+        assert_eq!(outcome.steps, 2);
+    }
+}
@@ -7,6 +7,7 @@
 
 use crate::encoding::{
     classify_opcode, decode_primary_word_op_sub, is_reserved_primary_opcode, OpcodeEncoding,
+    SpecialRegisterSelect, OPCODE_ENCODING_TABLE,
 };
 use crate::fault::{FaultCode, FaultReason};
 
@@ -43,6 +44,21 @@ impl AddressingMode {
         }
     }
 
+    /// Returns the 3-bit field value for this addressing mode.
+    #[must_use]
+    pub const fn to_u3(self) -> u16 {
+        match self {
+            Self::DirectRegister => 0,
+            Self::IndirectRegister => 1,
+            Self::SignExtendedDisplacement => 2,
+            Self::ZeroExtendedDisplacement => 3,
+            Self::IndirectAutoIncrement => 4,
+            Self::Immediate => 5,
+            Self::Reserved110 => 6,
+            Self::Reserved111 => 7,
+        }
+    }
+
     /// Returns true if this addressing mode is valid (not reserved).
     #[must_use]
     pub const fn is_valid(self) -> bool {
@@ -83,6 +99,21 @@ pub enum RegisterField {
 }
 
 impl RegisterField {
+    /// Returns the 3-bit field value for this register.
+    #[must_use]
+    pub const fn to_u3(self) -> u16 {
+        match self {
+            Self::R0 => 0,
+            Self::R1 => 1,
+            Self::R2 => 2,
+            Self::R3 => 3,
+            Self::R4 => 4,
+            Self::R5 => 5,
+            Self::R6 => 6,
+            Self::R7 => 7,
+        }
+    }
+
     /// Converts a 3-bit register field value into a register field.
     #[must_use]
     pub const fn from_u3(value: u8) -> Option<Self> {
@@ -121,60 +152,24 @@ pub struct DecodedInstruction {
 }
 
 impl DecodedInstruction {
-    /// Re-encodes this decoded instruction back to a 16-bit word.
+    /// Re-encodes this decoded instruction back to a 16-bit primary word.
+    ///
+    /// Immediates and displacements live in the extension word and are not
+    /// part of the result.
     #[must_use]
-    #[allow(clippy::missing_const_for_fn)]
     pub fn encode(self) -> u16 {
-        let mut word = 0u16;
-
-        if let Some(rd) = self.rd {
-            let rd_val: u16 = match rd {
-                RegisterField::R0 => 0,
-                RegisterField::R1 => 1,
-                RegisterField::R2 => 2,
-                RegisterField::R3 => 3,
-                RegisterField::R4 => 4,
-                RegisterField::R5 => 5,
-                RegisterField::R6 => 6,
-                RegisterField::R7 => 7,
-            };
-            word |= rd_val << 9;
-        }
-
-        if let Some(ra) = self.ra {
-            let ra_val: u16 = match ra {
-                RegisterField::R0 => 0,
-                RegisterField::R1 => 1,
-                RegisterField::R2 => 2,
-                RegisterField::R3 => 3,
-                RegisterField::R4 => 4,
-                RegisterField::R5 => 5,
-                RegisterField::R6 => 6,
-                RegisterField::R7 => 7,
-            };
-            word |= ra_val << 6;
-        }
-
-        if let Some(rb) = self.rb {
-            let rb_val: u16 = match rb {
-                RegisterField::R0 => 0,
-                RegisterField::R1 => 1,
-                RegisterField::R2 => 2,
-                RegisterField::R3 => 3,
-                RegisterField::R4 => 4,
-                RegisterField::R5 => 5,
-                RegisterField::R6 => 6,
-                RegisterField::R7 => 7,
-            };
-            word |= rb_val << 3;
-        }
-
-        if let Some(immediate) = self.immediate_value {
-            word |= immediate & 0x07;
-            word |= (immediate & 0xFF00) >> 8;
-        }
-
-        word
+        let (op, sub) = OPCODE_ENCODING_TABLE
+            .iter()
+            .find_map(|(op, sub, encoding)| (*encoding == self.encoding).then_some((*op, *sub)))
+            .unwrap_or((0, 0));
+
+        // RB shares its bits with SUB, so a decoded RB already carries SUB.
+        let sub_field = self.rb.map_or_else(|| u16::from(sub), RegisterField::to_u3);
+        let dest_field = self.rd.map_or(0, RegisterField::to_u3);
+        let src_field = self.ra.map_or(0, RegisterField::to_u3);
+        let mode_field = self.addressing_mode.map_or(0, AddressingMode::to_u3);
+
+        (u16::from(op) << 12) | (dest_field << 9) | (src_field << 6) | (sub_field << 3) | mode_field
     }
 }
 
@@ -233,6 +228,13 @@ const fn validates_unused_ra_bits(encoding: OpcodeEncoding) -> bool {
     matches!(encoding, OpcodeEncoding::Nop)
 }
 
+/// `MRS` takes no addressing mode and its `RA` field must name an assigned
+/// special register.
+const fn is_invalid_mrs(encoding: OpcodeEncoding, ra_bits: u8, am_bits: u8) -> bool {
+    matches!(encoding, OpcodeEncoding::Mrs)
+        && (am_bits != 0 || SpecialRegisterSelect::from_u3(ra_bits).is_none())
+}
+
 impl Decoder {
     /// Decodes a 16-bit instruction word.
     ///
@@ -287,6 +289,10 @@ impl Decoder {
             return DecodedOrFault::Fault(FaultReason::new(FaultCode::IllegalEncoding));
         }
 
+        if is_invalid_mrs(encoding, ra_bits, am_bits) {
+            return DecodedOrFault::Fault(FaultReason::new(FaultCode::IllegalEncoding));
+        }
+
         let immediate_value = None;
 
         DecodedOrFault::Instruction(DecodedInstruction {
@@ -305,6 +311,14 @@ mod tests {
     use super::*;
     use crate::encoding::OpcodeEncoding;
 
+    #[test]
+    fn encode_restores_opcode_and_addressing_mode() {
+        for word in [0x0001u16, 0x603D, 0x6038, 0x4C05] {
+            let decoded = Decoder::decode(word).instruction().unwrap();
+            assert_eq!(decoded.encode(), word, "word {word:#06X}");
+        }
+    }
+
     #[test]
     fn addressing_mode_valid_range_000_to_101() {
         for am in 0u8..=5u8 {
@@ -352,7 +366,7 @@ mod tests {
     fn unassigned_sub_opcode_faults() {
         let fault_cases: [(u8, u8); 9] = [
             (0x0, 0x7),
-            (0x1, 0x1),
+            (0x1, 0x2),
             (0x2, 0x3),
             (0x3, 0x6),
             (0x5, 0x7),
@@ -368,6 +382,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn mrs_requires_assigned_selector_and_am_000() {
+        // MRS R3, CAUSE
+        let word = 0x1000 | (3 << 9) | (3 << 6) | (1 << 3);
+        let instruction = Decoder::decode(word).instruction().unwrap();
+        assert_eq!(instruction.encoding, OpcodeEncoding::Mrs);
+        assert_eq!(instruction.rd, Some(RegisterField::R3));
+        assert_eq!(instruction.ra, Some(RegisterField::R3));
+        assert_eq!(instruction.encode(), word);
+
+        for selector in [6u16, 7] {
+            let word = 0x1000 | (selector << 6) | (1 << 3);
+            assert!(
+                Decoder::decode(word).fault().is_some(),
+                "selector {selector}"
+            );
+        }
+        assert!(Decoder::decode(0x1000 | (1 << 3) | 0b100).fault().is_some());
+    }
+
+    #[test]
+    fn mov_sub_values_keep_their_revision_1_3_decoding() {
+        // Only SUB=001 changed meaning when MRS was added; see the core
+        // spec's revision history.
+        for am in [0u16, 4] {
+            let word = 0x1000 | (2 << 9) | am;
+            let instruction = Decoder::decode(word).instruction().unwrap();
+            assert_eq!(instruction.encoding, OpcodeEncoding::Mov, "AM {am}");
+        }
+        let word = 0x1000 | (1 << 3);
+        assert_eq!(
+            Decoder::decode(word).instruction().unwrap().encoding,
+            OpcodeEncoding::Mrs
+        );
+        for sub in 2u16..=7 {
+            for am in [0u16, 4] {
+                let word = 0x1000 | (sub << 3) | am;
+                assert!(
+                    Decoder::decode(word).fault().is_some(),
+                    "SUB {sub} AM {am} should fault"
+                );
+            }
+        }
+    }
+
     #[test]
     fn am_110_faults() {
         let word = 0x0006u16;
@@ -414,13 +473,14 @@ mod tests {
 
     #[test]
     fn all_valid_opcodes_decode() {
-        let valid_encodings: [(u8, u8, OpcodeEncoding); 41] = [
+        let valid_encodings: [(u8, u8, OpcodeEncoding); 42] = [
             (0x0, 0x0, OpcodeEncoding::Nop),
             (0x0, 0x1, OpcodeEncoding::Sync),
             (0x0, 0x2, OpcodeEncoding::Halt),
             (0x0, 0x3, OpcodeEncoding::Trap),
             (0x0, 0x4, OpcodeEncoding::Swi),
             (0x1, 0x0, OpcodeEncoding::Mov),
+            (0x1, 0x1, OpcodeEncoding::Mrs),
             (0x2, 0x0, OpcodeEncoding::Load),
             (0x3, 0x0, OpcodeEncoding::Store),
             (0x4, 0x0, OpcodeEncoding::Add),
@@ -502,6 +562,8 @@ mod tests {
                                 && (((word >> 8) & 0xFF) != 0xFF)
                         })
                     };
+                    let is_mrs_field_violation =
+                        op == 0x1 && sub == 0x1 && (am != 0 || ((word >> 6) & 0x7) >= 6);
                     let is_nop_unused_field_violation = {
                         let (op, sub) = decode_primary_word_op_sub(word);
                         op == 0
@@ -513,6 +575,7 @@ mod tests {
                         is_illegal
                             || is_invalid_am
                             || is_sign_ext_problem
+                            || is_mrs_field_violation
                             || is_nop_unused_field_violation,
                         "Fault at {word:X} (OP={op}, SUB={sub}, AM={am}) has no valid fault reason"
                     );
@@ -1,4 +1,7 @@
-//! Diagnostics window (DIAG) model and provider trait.
+//! Diagnostics window (DIAG) model and provider trait, and the per-tick
+//! cycle usage history.
+
+use alloc::vec::Vec;
 
 use crate::{FaultClass, FaultCode};
 
@@ -184,6 +187,115 @@ impl DiagProvider for StaticDiagProvider {
     }
 }
 
+/// Fixed-size ring of the cycles used by the most recently ended ticks.
+///
+/// [`end_tick`](crate::end_tick) records `TICK` just before resetting it, so
+/// each entry is what one tick consumed against the cycle budget. Counts come
+/// from the core's cycle accounting only, never the wall clock, so a replayed
+/// run reports the same usage. A capacity of zero disables recording, which
+/// is the default.
+///
+/// Equality compares capacity and the oldest-first contents, not where the
+/// ring currently wraps.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct TickUsageHistory {
+    capacity: u16,
+    cycles: Vec<u16>,
+    next: usize,
+}
+
+impl TickUsageHistory {
+    /// Creates an empty ring holding at most `capacity` ticks.
+    #[must_use]
+    pub fn new(capacity: u16) -> Self {
+        Self {
+            capacity,
+            cycles: Vec::with_capacity(usize::from(capacity)),
+            next: 0,
+        }
+    }
+
+    /// Maximum number of retained ticks.
+    #[must_use]
+    pub const fn capacity(&self) -> u16 {
+        self.capacity
+    }
+
+    /// Returns true when recording is enabled.
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Number of ticks currently retained.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.cycles.len()
+    }
+
+    /// Returns true when no tick has ended since the ring was created or
+    /// cleared.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.cycles.is_empty()
+    }
+
+    /// Records the cycles one tick used, evicting the oldest tick when full.
+    pub fn record(&mut self, cycles: u16) {
+        let capacity = usize::from(self.capacity);
+        if capacity == 0 {
+            return;
+        }
+        if self.cycles.len() < capacity {
+            self.cycles.push(cycles);
+        } else {
+            self.cycles[self.next] = cycles;
+        }
+        self.next = (self.next + 1) % capacity;
+    }
+
+    /// Returns retained per-tick cycle counts, oldest first.
+    #[must_use]
+    pub fn cycles(&self) -> Vec<u16> {
+        if self.cycles.len() < usize::from(self.capacity) {
+            return self.cycles.clone();
+        }
+        let (newer, older) = self.cycles.split_at(self.next);
+        older.iter().chain(newer).copied().collect()
+    }
+
+    /// Cycles used by the most recently ended tick.
+    #[must_use]
+    pub fn latest(&self) -> Option<u16> {
+        let newest = match self.next {
+            0 => self.cycles.len().checked_sub(1)?,
+            next => next - 1,
+        };
+        self.cycles.get(newest).copied()
+    }
+
+    /// Most cycles any retained tick used.
+    #[must_use]
+    pub fn peak(&self) -> Option<u16> {
+        self.cycles.iter().copied().max()
+    }
+
+    /// Discards all entries while keeping the capacity.
+    pub fn clear(&mut self) {
+        self.cycles.clear();
+        self.next = 0;
+    }
+}
+
+impl PartialEq for TickUsageHistory {
+    fn eq(&self, other: &Self) -> bool {
+        self.capacity == other.capacity && self.cycles() == other.cycles()
+    }
+}
+
+impl Eq for TickUsageHistory {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -270,4 +382,30 @@ mod tests {
         fields.record_fault(FaultCode::CapabilityViolation, 0, 0);
         assert_eq!(fields.fault_count_capability, 1);
     }
+
+    #[test]
+    fn tick_usage_keeps_newest_ticks_in_order() {
+        let mut usage = TickUsageHistory::new(3);
+        assert_eq!(usage.latest(), None);
+        for cycles in [10, 640, 25, 300, 7] {
+            usage.record(cycles);
+        }
+
+        assert_eq!(usage.cycles(), vec![25, 300, 7]);
+        assert_eq!(usage.latest(), Some(7));
+        assert_eq!(usage.peak(), Some(300));
+
+        usage.clear();
+        assert!(usage.is_empty());
+        assert_eq!(usage.capacity(), 3);
+    }
+
+    #[test]
+    fn disabled_tick_usage_records_nothing() {
+        let mut usage = TickUsageHistory::default();
+        usage.record(640);
+
+        assert!(!usage.is_enabled());
+        assert_eq!(usage.latest(), None);
+    }
 }
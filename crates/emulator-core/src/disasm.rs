@@ -3,8 +3,14 @@
 //! This module provides utilities for converting raw instruction bytes into
 //! human-readable assembly format.
 
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
 use crate::decoder::{AddressingMode, Decoder, RegisterField};
-use crate::encoding::OpcodeEncoding;
+use crate::encoding::{OpcodeEncoding, SpecialRegisterSelect};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -203,6 +209,7 @@ fn format_mnemonic(encoding: OpcodeEncoding, addressing_mode: Option<AddressingM
         OpcodeEncoding::Trap => "TRAP",
         OpcodeEncoding::Swi => "SWI",
         OpcodeEncoding::Mov => "MOV",
+        OpcodeEncoding::Mrs => "MRS",
         OpcodeEncoding::Load => "LOAD",
         OpcodeEncoding::Store => "STORE",
         OpcodeEncoding::Add => "ADD",
@@ -300,6 +307,14 @@ fn format_operands(instr: &crate::decoder::DecodedInstruction) -> String {
         #[allow(clippy::match_same_arms)]
         AddressingMode::DirectRegister => match instr.encoding {
             OpcodeEncoding::Push | OpcodeEncoding::Pop => rd.unwrap_or_default(),
+            OpcodeEncoding::Mrs => {
+                let special = instr
+                    .ra
+                    .and_then(|ra| u8::try_from(ra.to_u3()).ok())
+                    .and_then(SpecialRegisterSelect::from_u3)
+                    .map_or("?", SpecialRegisterSelect::name);
+                format!("{}, {special}", rd.unwrap_or_default())
+            }
             OpcodeEncoding::CallOrRet => rb.unwrap_or_default(),
             OpcodeEncoding::In => match (&rd, &ra) {
                 (Some(d), Some(s)) => format!("{d}, {s}"),
@@ -1,3 +1,9 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::decoder::{DecodedOrFault, Decoder};
+use crate::FaultCode;
+
 /// Opcode classes with assigned primary opcode values (`OP` field, bits 15..12).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(u8)]
@@ -47,6 +53,7 @@ pub enum OpcodeEncoding {
     Trap,
     Swi,
     Mov,
+    Mrs,
     Load,
     Store,
     Add,
@@ -94,6 +101,7 @@ pub const OPCODE_ENCODING_TABLE: &[(u8, u8, OpcodeEncoding)] = &[
     (0x0, 0x3, OpcodeEncoding::Trap),
     (0x0, 0x4, OpcodeEncoding::Swi),
     (0x1, 0x0, OpcodeEncoding::Mov),
+    (0x1, 0x1, OpcodeEncoding::Mrs),
     (0x2, 0x0, OpcodeEncoding::Load),
     (0x3, 0x0, OpcodeEncoding::Store),
     (0x4, 0x0, OpcodeEncoding::Add),
@@ -131,6 +139,68 @@ pub const OPCODE_ENCODING_TABLE: &[(u8, u8, OpcodeEncoding)] = &[
     (0xA, 0x2, OpcodeEncoding::Eret),
 ];
 
+/// Special registers `MRS` can read, selected by the `RA` field.
+///
+/// Selectors `110` and `111` are unassigned and decode as illegal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+#[allow(missing_docs)]
+pub enum SpecialRegisterSelect {
+    Flags = 0,
+    Tick = 1,
+    Cap = 2,
+    Cause = 3,
+    Evp = 4,
+    Sp = 5,
+}
+
+impl SpecialRegisterSelect {
+    /// Every assigned selector, in encoding order.
+    pub const ALL: [Self; 6] = [
+        Self::Flags,
+        Self::Tick,
+        Self::Cap,
+        Self::Cause,
+        Self::Evp,
+        Self::Sp,
+    ];
+
+    /// Converts a 3-bit `RA` field value into a selector.
+    #[must_use]
+    pub const fn from_u3(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Flags),
+            1 => Some(Self::Tick),
+            2 => Some(Self::Cap),
+            3 => Some(Self::Cause),
+            4 => Some(Self::Evp),
+            5 => Some(Self::Sp),
+            _ => None,
+        }
+    }
+
+    /// Upper-case register name as written in assembly.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Flags => "FLAGS",
+            Self::Tick => "TICK",
+            Self::Cap => "CAP",
+            Self::Cause => "CAUSE",
+            Self::Evp => "EVP",
+            Self::Sp => "SP",
+        }
+    }
+
+    /// Looks up a selector by register name, ignoring ASCII case.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|select| select.name().eq_ignore_ascii_case(name))
+    }
+}
+
 /// Returns true if the primary opcode nibble is in the reserved range (`0xB..=0xF`).
 #[must_use]
 pub const fn is_reserved_primary_opcode(op: u8) -> bool {
@@ -159,13 +229,252 @@ pub const fn decode_primary_word_op_sub(word: u16) -> (u8, u8) {
     (((word >> 12) & 0x000F) as u8, ((word >> 3) & 0x0007) as u8)
 }
 
+/// A problem [`verify_table`] found in the encoding table or decoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableIssue {
+    /// A table entry's `OP` or `SUB` does not fit its field.
+    OutOfRange {
+        /// Primary opcode of the entry.
+        op: u8,
+        /// Sub-opcode of the entry.
+        sub: u8,
+        /// Encoding the entry assigns.
+        encoding: OpcodeEncoding,
+    },
+    /// A table entry uses a reserved primary opcode.
+    ReservedOpcode {
+        /// Primary opcode of the entry.
+        op: u8,
+        /// Sub-opcode of the entry.
+        sub: u8,
+        /// Encoding the entry assigns.
+        encoding: OpcodeEncoding,
+    },
+    /// Two entries claim the same `(OP, SUB)` pair; only the first decodes.
+    OverlappingPair {
+        /// Primary opcode both entries use.
+        op: u8,
+        /// Sub-opcode both entries use.
+        sub: u8,
+        /// Encoding of the earlier entry.
+        first: OpcodeEncoding,
+        /// Encoding of the later entry.
+        second: OpcodeEncoding,
+    },
+    /// One encoding is assigned to two pairs, so it cannot re-encode
+    /// unambiguously.
+    DuplicateEncoding {
+        /// The encoding assigned twice.
+        encoding: OpcodeEncoding,
+    },
+    /// No primary word decodes to a table entry's encoding.
+    Unreachable {
+        /// The encoding nothing decodes to.
+        encoding: OpcodeEncoding,
+    },
+    /// A word decodes differently from what the table assigns its
+    /// `(OP, SUB)` pair; `expected` is `None` for pairs the table leaves
+    /// illegal.
+    Inconsistent {
+        /// The primary word.
+        word: u16,
+        /// What the decoder produced, `None` for a fault.
+        decoded: Option<OpcodeEncoding>,
+        /// What the table assigns the word's pair.
+        expected: Option<OpcodeEncoding>,
+    },
+    /// A word faults with a code other than `IllegalEncoding`.
+    UnexpectedFault {
+        /// The primary word.
+        word: u16,
+        /// The fault the decoder raised.
+        code: FaultCode,
+    },
+    /// A legal word does not re-encode to itself.
+    NotRoundTrip {
+        /// The primary word.
+        word: u16,
+        /// What its decoded instruction encodes back to.
+        encoded: u16,
+    },
+}
+
+impl fmt::Display for TableIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfRange { op, sub, encoding } => {
+                write!(
+                    f,
+                    "({op:#X}, {sub:#X}) {encoding:?} does not fit the OP/SUB fields"
+                )
+            }
+            Self::ReservedOpcode { op, sub, encoding } => {
+                write!(
+                    f,
+                    "({op:#X}, {sub:#X}) {encoding:?} uses a reserved primary opcode"
+                )
+            }
+            Self::OverlappingPair {
+                op,
+                sub,
+                first,
+                second,
+            } => write!(
+                f,
+                "({op:#X}, {sub:#X}) is assigned to both {first:?} and {second:?}"
+            ),
+            Self::DuplicateEncoding { encoding } => {
+                write!(f, "{encoding:?} is assigned to more than one pair")
+            }
+            Self::Unreachable { encoding } => write!(f, "no primary word decodes to {encoding:?}"),
+            Self::Inconsistent {
+                word,
+                decoded,
+                expected,
+            } => write!(
+                f,
+                "{word:#06X} decodes to {decoded:?} but the table assigns {expected:?}"
+            ),
+            Self::UnexpectedFault { word, code } => {
+                write!(
+                    f,
+                    "{word:#06X} faults with {code:?} instead of IllegalEncoding"
+                )
+            }
+            Self::NotRoundTrip { word, encoded } => {
+                write!(f, "{word:#06X} re-encodes as {encoded:#06X}")
+            }
+        }
+    }
+}
+
+/// Result of [`verify_table`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableReport {
+    /// Primary words that decode to an instruction.
+    pub legal_words: u32,
+    /// Primary words that fault.
+    pub illegal_words: u32,
+    /// How many primary words decode to each table entry, in table order.
+    pub words_per_encoding: Vec<(OpcodeEncoding, u32)>,
+    /// Problems found, table checks first, then words in ascending order.
+    pub issues: Vec<TableIssue>,
+}
+
+impl TableReport {
+    /// Returns true when no issues were found.
+    #[must_use]
+    pub const fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Checks [`OPCODE_ENCODING_TABLE`] and the decoder against each other.
+///
+/// Every entry must fit the `OP`/`SUB` fields, avoid reserved opcodes
+/// ([`is_reserved_primary_opcode`]) and claim a pair and an encoding no
+/// other entry claims. Then all 65 536 primary words are decoded: each must
+/// either decode to exactly what the table assigns its `(OP, SUB)` pair and
+/// re-encode to itself, or fault with `IllegalEncoding`, and every entry must
+/// be reached by at least one word. Guards against table edits that create
+/// overlapping or unreachable encodings.
+#[must_use]
+pub fn verify_table() -> TableReport {
+    verify_entries(OPCODE_ENCODING_TABLE)
+}
+
+fn verify_entries(table: &[(u8, u8, OpcodeEncoding)]) -> TableReport {
+    let mut issues = Vec::new();
+    for (index, &(op, sub, encoding)) in table.iter().enumerate() {
+        if op > 0xF || sub > 0x7 {
+            issues.push(TableIssue::OutOfRange { op, sub, encoding });
+        } else if is_reserved_primary_opcode(op) {
+            issues.push(TableIssue::ReservedOpcode { op, sub, encoding });
+        }
+        let earlier = &table[..index];
+        if let Some(&(_, _, first)) = earlier.iter().find(|(o, s, _)| (*o, *s) == (op, sub)) {
+            issues.push(TableIssue::OverlappingPair {
+                op,
+                sub,
+                first,
+                second: encoding,
+            });
+        }
+        if earlier.iter().any(|(_, _, e)| *e == encoding) {
+            issues.push(TableIssue::DuplicateEncoding { encoding });
+        }
+    }
+
+    let mut words_per_encoding: Vec<(OpcodeEncoding, u32)> = table
+        .iter()
+        .map(|&(_, _, encoding)| (encoding, 0))
+        .collect();
+    let mut legal_words = 0;
+    for word in 0..=u16::MAX {
+        let (op, sub) = decode_primary_word_op_sub(word);
+        let expected = table
+            .iter()
+            .find(|(o, s, _)| (*o, *s) == (op, sub) && !is_reserved_primary_opcode(op))
+            .map(|&(_, _, encoding)| encoding);
+        match Decoder::decode(word) {
+            DecodedOrFault::Instruction(decoded) => {
+                legal_words += 1;
+                if expected != Some(decoded.encoding) {
+                    issues.push(TableIssue::Inconsistent {
+                        word,
+                        decoded: Some(decoded.encoding),
+                        expected,
+                    });
+                }
+                if let Some((_, count)) = words_per_encoding
+                    .iter_mut()
+                    .find(|(encoding, _)| *encoding == decoded.encoding)
+                {
+                    *count += 1;
+                }
+                let encoded = decoded.encode();
+                if encoded != word {
+                    issues.push(TableIssue::NotRoundTrip { word, encoded });
+                }
+            }
+            DecodedOrFault::Fault(reason) => {
+                if reason.code() != FaultCode::IllegalEncoding {
+                    issues.push(TableIssue::UnexpectedFault {
+                        word,
+                        code: reason.code(),
+                    });
+                } else if expected.is_some() && classify_opcode(op, sub) != expected {
+                    issues.push(TableIssue::Inconsistent {
+                        word,
+                        decoded: None,
+                        expected,
+                    });
+                }
+            }
+        }
+    }
+    for &(encoding, count) in &words_per_encoding {
+        if count == 0 {
+            issues.push(TableIssue::Unreachable { encoding });
+        }
+    }
+
+    TableReport {
+        legal_words,
+        illegal_words: 0x1_0000 - legal_words,
+        words_per_encoding,
+        issues,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashSet;
 
     use super::{
-        classify_opcode, decode_primary_word_op_sub, is_reserved_primary_opcode, OpcodeClass,
-        OpcodeEncoding, OPCODE_ENCODING_TABLE,
+        classify_opcode, decode_primary_word_op_sub, is_reserved_primary_opcode, verify_entries,
+        verify_table, OpcodeClass, OpcodeEncoding, SpecialRegisterSelect, TableIssue,
+        OPCODE_ENCODING_TABLE,
     };
 
     #[test]
@@ -205,7 +514,7 @@ mod tests {
     #[test]
     fn unassigned_sub_opcodes_are_illegal() {
         assert_eq!(classify_opcode(0x0, 0x7), None);
-        assert_eq!(classify_opcode(0x1, 0x1), None);
+        assert_eq!(classify_opcode(0x1, 0x2), None);
         assert_eq!(classify_opcode(0x2, 0x3), None);
         assert_eq!(classify_opcode(0x3, 0x6), None);
         assert_eq!(classify_opcode(0x5, 0x7), None);
@@ -215,6 +524,24 @@ mod tests {
         assert_eq!(classify_opcode(0xA, 0x7), None);
     }
 
+    #[test]
+    fn special_register_selectors_roundtrip_by_value_and_name() {
+        for select in SpecialRegisterSelect::ALL {
+            assert_eq!(SpecialRegisterSelect::from_u3(select as u8), Some(select));
+            assert_eq!(
+                SpecialRegisterSelect::from_name(select.name()),
+                Some(select)
+            );
+        }
+        assert_eq!(
+            SpecialRegisterSelect::from_name("cause"),
+            Some(SpecialRegisterSelect::Cause)
+        );
+        assert_eq!(SpecialRegisterSelect::from_u3(6), None);
+        assert_eq!(SpecialRegisterSelect::from_u3(7), None);
+        assert_eq!(SpecialRegisterSelect::from_name("PC"), None);
+    }
+
     #[test]
     fn primary_word_decode_extracts_op_and_sub_fields() {
         let word = 0b1010_0010_1101_0101_u16;
@@ -228,4 +555,57 @@ mod tests {
         assert_eq!(OpcodeClass::from_u4(0xB), None);
         assert_eq!(OpcodeClass::from_u4(0xF), None);
     }
+
+    #[test]
+    fn decoder_table_verifies_clean() {
+        let report = verify_table();
+        assert_eq!(report.issues, []);
+        assert_eq!(report.legal_words + report.illegal_words, 0x1_0000);
+        assert_eq!(report.words_per_encoding.len(), OPCODE_ENCODING_TABLE.len());
+        assert!(report
+            .words_per_encoding
+            .iter()
+            .all(|(_, count)| *count > 0));
+    }
+
+    #[test]
+    fn table_edits_show_up_as_issues() {
+        let mut table = OPCODE_ENCODING_TABLE.to_vec();
+        table.push((0x4, 0x0, OpcodeEncoding::Sub));
+        table.push((0xB, 0x0, OpcodeEncoding::Nop));
+        let report = verify_entries(&table);
+        assert!(report.issues.contains(&TableIssue::OverlappingPair {
+            op: 0x4,
+            sub: 0x0,
+            first: OpcodeEncoding::Add,
+            second: OpcodeEncoding::Sub,
+        }));
+        assert!(report.issues.contains(&TableIssue::ReservedOpcode {
+            op: 0xB,
+            sub: 0x0,
+            encoding: OpcodeEncoding::Nop,
+        }));
+        assert!(report.issues.contains(&TableIssue::DuplicateEncoding {
+            encoding: OpcodeEncoding::Sub,
+        }));
+
+        let without_eret: Vec<_> = OPCODE_ENCODING_TABLE
+            .iter()
+            .copied()
+            .filter(|(_, _, encoding)| *encoding != OpcodeEncoding::Eret)
+            .collect();
+        let report = verify_entries(&without_eret);
+        assert_eq!(
+            report.issues[0],
+            TableIssue::Inconsistent {
+                word: 0xA010,
+                decoded: Some(OpcodeEncoding::Eret),
+                expected: None,
+            }
+        );
+        assert_eq!(
+            report.issues[0].to_string(),
+            "0xA010 decodes to Some(Eret) but the table assigns None"
+        );
+    }
 }
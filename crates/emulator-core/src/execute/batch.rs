@@ -0,0 +1,264 @@
+//! Multi-tick batch execution for hosts that pace the core at 100 Hz.
+//!
+//! A host scheduling real-time execution wakes up, works out how many ticks
+//! are due, runs them in one [`run_ticks_with_budget`] call and sleeps until
+//! the next tick is due. [`TickBatch::simulated_duration`] and
+//! [`TICK_DURATION`] give the simulated time to compare against the wall
+//! clock.
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use super::{end_tick, step_one};
+use crate::{CoreConfig, CoreState, FaultCode, HaltReason, MmioBus, RunState, StepOutcome};
+
+/// Ticks per second of simulated time.
+pub const TICKS_PER_SECOND: u32 = 100;
+
+/// Simulated time covered by one tick.
+pub const TICK_DURATION: Duration = Duration::from_millis(10);
+
+/// Work done by the core during one tick of a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TickStats {
+    /// Steps executed during the tick.
+    pub steps: u32,
+    /// Cycles consumed: the `TICK` value when the core halted for the tick.
+    pub cycles: u16,
+    /// Why the core halted for the tick.
+    pub reason: HaltReason,
+}
+
+/// Outcome of [`run_ticks_with_budget`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TickBatch {
+    /// Completed ticks, in execution order.
+    pub ticks: Vec<TickStats>,
+    /// Steps executed across the batch, including a tick cut short by a fault.
+    pub total_steps: u64,
+    /// Cycles consumed across the batch, including a tick cut short by a fault.
+    pub total_cycles: u64,
+    /// Fault that latched and ended the batch early, if any.
+    pub fault: Option<FaultCode>,
+}
+
+impl TickBatch {
+    /// Appends a batch that ran straight after this one, so a host that
+    /// splits a run into several calls can report it as one.
+    pub fn append(&mut self, later: Self) {
+        self.ticks.extend(later.ticks);
+        self.total_steps += later.total_steps;
+        self.total_cycles += later.total_cycles;
+        self.fault = later.fault;
+    }
+
+    /// Simulated time covered by the completed ticks.
+    #[must_use]
+    pub fn simulated_duration(&self) -> Duration {
+        TICK_DURATION * u32::try_from(self.ticks.len()).unwrap_or(u32::MAX)
+    }
+}
+
+/// Runs up to `n_ticks` whole ticks, ending each one with [`end_tick`].
+///
+/// At most [`RunLimits::max_ticks`](crate::RunLimits::max_ticks) ticks run,
+/// whatever `n_ticks` asks for.
+///
+/// Trap, event and fault dispatch run inside the tick as they would on the
+/// machine; the batch only stops early when a fault latches. A core left
+/// `HaltedForTick` by an earlier run has that tick ended first.
+pub fn run_ticks_with_budget(
+    state: &mut CoreState,
+    mmio: &mut dyn MmioBus,
+    config: &CoreConfig,
+    n_ticks: u32,
+) -> TickBatch {
+    run_ticks_with(state, mmio, config, n_ticks, |state, mmio, config| {
+        run_tick_with(state, mmio, config, step_one)
+    })
+}
+
+/// [`run_ticks_with_budget`] with each tick run by `run_tick`, which steps
+/// the core until it halts for the tick or latches a fault and returns the
+/// step count and final outcome.
+pub(super) fn run_ticks_with(
+    state: &mut CoreState,
+    mmio: &mut dyn MmioBus,
+    config: &CoreConfig,
+    n_ticks: u32,
+    mut run_tick: impl FnMut(&mut CoreState, &mut dyn MmioBus, &CoreConfig) -> (u32, StepOutcome),
+) -> TickBatch {
+    if matches!(state.run_state, RunState::HaltedForTick) {
+        end_tick(state, mmio, None);
+    }
+
+    let n_ticks = config.run_limits.ticks(n_ticks);
+    let mut batch = TickBatch {
+        ticks: Vec::with_capacity(n_ticks as usize),
+        ..TickBatch::default()
+    };
+    for _ in 0..n_ticks {
+        let (steps, outcome) = run_tick(state, mmio, config);
+
+        if let StepOutcome::HaltedForTick { reason } = outcome {
+            let cycles = state.arch.tick();
+            batch.total_steps += u64::from(steps);
+            batch.total_cycles += u64::from(cycles);
+            batch.ticks.push(TickStats {
+                steps,
+                cycles,
+                reason,
+            });
+            end_tick(state, mmio, None);
+            continue;
+        }
+
+        if let RunState::FaultLatched(cause) = state.run_state {
+            batch.total_steps += u64::from(steps);
+            batch.total_cycles += u64::from(state.arch.tick());
+            batch.fault = Some(cause);
+            return batch;
+        }
+    }
+    batch
+}
+
+/// Runs `step` until the core halts for the tick or latches a fault,
+/// returning the step count and the last outcome.
+pub(super) fn run_tick_with(
+    state: &mut CoreState,
+    mmio: &mut dyn MmioBus,
+    config: &CoreConfig,
+    mut step: impl FnMut(&mut CoreState, &mut dyn MmioBus, &CoreConfig) -> StepOutcome,
+) -> (u32, StepOutcome) {
+    let mut steps = 0u32;
+    loop {
+        let outcome = step(state, mmio, config);
+        steps += 1;
+        if ends_tick(state, outcome) {
+            return (steps, outcome);
+        }
+    }
+}
+
+/// Whether `outcome` ends a tick of a batch: the core halted for the tick
+/// or latched a fault.
+pub(super) const fn ends_tick(state: &CoreState, outcome: StepOutcome) -> bool {
+    matches!(outcome, StepOutcome::HaltedForTick { .. })
+        || matches!(state.run_state, RunState::FaultLatched(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_ticks_with_budget, TickStats, TICK_DURATION};
+    use crate::{
+        CoreConfig, CoreState, FaultCode, HaltReason, MmioBus, MmioError, MmioWriteResult, RunState,
+    };
+
+    struct NoMmio;
+
+    impl MmioBus for NoMmio {
+        fn read16(&mut self, _addr: u16) -> Result<u16, MmioError> {
+            Err(MmioError::ReadFailed)
+        }
+
+        fn write16(&mut self, _addr: u16, _value: u16) -> Result<MmioWriteResult, MmioError> {
+            Err(MmioError::WriteFailed)
+        }
+    }
+
+    #[test]
+    fn runs_whole_ticks_and_reports_per_tick_work() {
+        let mut state = CoreState::default();
+        // HALT at 0x0000, then NOPs until the budget runs out.
+        state.memory[0x0001] = 0x10;
+
+        let batch = run_ticks_with_budget(&mut state, &mut NoMmio, &CoreConfig::default(), 3);
+
+        assert_eq!(
+            batch.ticks[0],
+            TickStats {
+                steps: 1,
+                cycles: 1,
+                reason: HaltReason::Instruction,
+            }
+        );
+        assert_eq!(
+            batch.ticks[1],
+            TickStats {
+                steps: 640,
+                cycles: 640,
+                reason: HaltReason::BudgetExhausted,
+            }
+        );
+        assert_eq!(batch.ticks.len(), 3);
+        assert_eq!(batch.total_steps, 1281);
+        assert_eq!(batch.total_cycles, 1281);
+        assert_eq!(batch.fault, None);
+        assert_eq!(batch.simulated_duration(), TICK_DURATION * 3);
+        assert_eq!(state.arch.tick(), 0);
+        assert_eq!(state.run_state, RunState::Running);
+    }
+
+    #[test]
+    fn split_batches_append_to_the_unsplit_result() {
+        let run = |state: &mut CoreState, n_ticks| {
+            run_ticks_with_budget(state, &mut NoMmio, &CoreConfig::default(), n_ticks)
+        };
+        let mut whole_state = CoreState::default();
+        whole_state.memory[0x0001] = 0x10;
+        let mut split_state = whole_state.clone();
+
+        let whole = run(&mut whole_state, 5);
+        let mut split = run(&mut split_state, 2);
+        split.append(run(&mut split_state, 3));
+
+        assert_eq!(split, whole);
+        assert_eq!(split_state, whole_state);
+    }
+
+    #[test]
+    fn ended_ticks_are_recorded_in_the_usage_history() {
+        let config = CoreConfig {
+            tick_usage_depth: 2,
+            ..CoreConfig::default()
+        };
+        let mut state = CoreState::with_config(&config);
+        state.memory[0x0001] = 0x10;
+
+        run_ticks_with_budget(&mut state, &mut NoMmio, &config, 3);
+
+        assert_eq!(state.tick_usage.cycles(), vec![640, 640]);
+        assert_eq!(state.tick_usage.latest(), Some(640));
+    }
+
+    #[test]
+    fn sandbox_batches_stop_at_the_run_limit_and_budget() {
+        let config = CoreConfig::sandbox();
+        let mut state = CoreState::with_config(&config);
+        state.memory[0x0001] = 0x10;
+
+        let batch = run_ticks_with_budget(&mut state, &mut NoMmio, &config, u32::MAX);
+
+        assert_eq!(batch.ticks.len(), 100);
+        assert_eq!(batch.ticks[1].cycles, 320);
+        assert_eq!(batch.simulated_duration(), TICK_DURATION * 100);
+    }
+
+    #[test]
+    fn stops_when_a_fault_latches() {
+        let mut state = CoreState::default();
+        state.memory[0x0004] = 0xF0;
+
+        let batch = run_ticks_with_budget(&mut state, &mut NoMmio, &CoreConfig::default(), 5);
+
+        assert!(batch.ticks.is_empty());
+        assert_eq!(batch.total_steps, 3);
+        assert_eq!(batch.total_cycles, 2);
+        assert_eq!(batch.fault, Some(FaultCode::IllegalEncoding));
+        assert_eq!(
+            state.run_state,
+            RunState::FaultLatched(FaultCode::IllegalEncoding)
+        );
+    }
+}
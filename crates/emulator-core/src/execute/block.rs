@@ -0,0 +1,572 @@
+//! Basic-block pre-decode cache.
+//!
+//! [`BlockCache`] decodes a straight-line run of instructions once and
+//! replays the decoded ops on later visits, so a hot loop skips the decoder.
+//! A block ends after the first branch, `HALT`, trap or event instruction,
+//! after an `IN`/`OUT`/bit-op device access, or at [`MAX_BLOCK_LEN`]
+//! instructions.
+//!
+//! Execution itself is unchanged: each cached op still goes through the
+//! same commit path as [`step_one`], so a cached run is step-for-step
+//! identical to an uncached one.
+//!
+//! Stores, pushes and calls run through the cache drop every block they
+//! overwrite. Hosts, DMA and tick hooks write memory directly, so a run
+//! compares a block with the bytes it was decoded from when it first enters
+//! it, and again after a tick boundary or dispatch; in between, the ops run
+//! back to back without re-reading code. [`BlockCache::step`] cannot see
+//! what the host did since the previous call, so it checks the bytes of
+//! each op it runs instead.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use super::batch::{ends_tick, run_ticks_with};
+use super::{check_run_boundary, fetch_and_decode, step_with};
+use crate::decoder::DecodedInstruction;
+use crate::encoding::OpcodeEncoding;
+use crate::{CoreConfig, CoreState, MmioBus, RunBoundary, RunOutcome, StepOutcome, TickBatch};
+
+/// Most instructions decoded into one block.
+const MAX_BLOCK_LEN: usize = 64;
+
+/// Most bytes one block spans: [`MAX_BLOCK_LEN`] two-word instructions.
+const MAX_BLOCK_BYTES: u16 = 256;
+
+/// One pre-decoded instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CachedOp {
+    pc: u16,
+    len: u8,
+    instruction: DecodedInstruction,
+}
+
+impl CachedOp {
+    /// Decodes the instruction at `pc`, or `None` if it does not decode or
+    /// its bytes wrap past the end of memory.
+    fn decode(pc: u16, memory: &[u8]) -> Option<Self> {
+        let instruction = fetch_and_decode(pc, memory).ok()?;
+        let len = if instruction
+            .addressing_mode
+            .is_some_and(|mode| mode.requires_extension_word())
+        {
+            4
+        } else {
+            2
+        };
+        (usize::from(pc) + usize::from(len) <= memory.len()).then_some(Self {
+            pc,
+            len,
+            instruction,
+        })
+    }
+
+    const fn next_pc(&self) -> u16 {
+        self.pc.wrapping_add(self.len as u16)
+    }
+
+    const fn ends_block(&self) -> bool {
+        matches!(
+            self.instruction.encoding,
+            OpcodeEncoding::Halt
+                | OpcodeEncoding::Trap
+                | OpcodeEncoding::Swi
+                | OpcodeEncoding::Beq
+                | OpcodeEncoding::Bne
+                | OpcodeEncoding::Blt
+                | OpcodeEncoding::Ble
+                | OpcodeEncoding::Bgt
+                | OpcodeEncoding::Bge
+                | OpcodeEncoding::Jmp
+                | OpcodeEncoding::CallOrRet
+                | OpcodeEncoding::In
+                | OpcodeEncoding::Out
+                | OpcodeEncoding::Bset
+                | OpcodeEncoding::Bclr
+                | OpcodeEncoding::Btest
+                | OpcodeEncoding::Ewait
+                | OpcodeEncoding::Eret
+        )
+    }
+}
+
+/// Decoded ops and the contiguous bytes they were decoded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Block {
+    start: u16,
+    bytes: Vec<u8>,
+    ops: Vec<CachedOp>,
+    /// [`BlockCache::epoch`] at which `bytes` last matched memory.
+    checked: u64,
+}
+
+impl Block {
+    /// Decodes the block starting at `start`, or `None` if its first
+    /// instruction cannot be cached.
+    fn decode(start: u16, memory: &[u8], epoch: u64) -> Option<Self> {
+        let mut ops = Vec::new();
+        let mut next = start;
+        while ops.len() < MAX_BLOCK_LEN {
+            let Some(op) = CachedOp::decode(next, memory) else {
+                break;
+            };
+            let ends_block = op.ends_block() || op.next_pc() < op.pc;
+            next = op.next_pc();
+            ops.push(op);
+            if ends_block {
+                break;
+            }
+        }
+        let last = ops.last()?;
+        let end = usize::from(last.pc) + usize::from(last.len);
+        Some(Self {
+            start,
+            bytes: memory[usize::from(start)..end].to_vec(),
+            ops,
+            checked: epoch,
+        })
+    }
+
+    /// One past the block's last byte.
+    fn end(&self) -> usize {
+        usize::from(self.start) + self.bytes.len()
+    }
+
+    fn matches(&self, memory: &[u8]) -> bool {
+        memory[usize::from(self.start)..self.end()] == self.bytes[..]
+    }
+
+    fn op_matches(&self, index: usize, memory: &[u8]) -> bool {
+        let op = &self.ops[index];
+        let offset = usize::from(op.pc - self.start);
+        let len = usize::from(op.len);
+        memory[usize::from(op.pc)..][..len] == self.bytes[offset..][..len]
+    }
+}
+
+/// Pre-decoded basic blocks, keyed by start address.
+///
+/// A cache belongs to one core: reusing it for another core's memory is
+/// safe, as blocks are checked before they run, but mostly misses.
+/// [`BlockCache::step`], [`BlockCache::run`] and
+/// [`BlockCache::run_ticks_with_budget`] are drop-in replacements for
+/// [`step_one`](super::step_one), [`run_one`](super::run_one) and
+/// [`run_ticks_with_budget`](super::run_ticks_with_budget).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockCache {
+    /// Block storage; slots of dropped blocks are listed in `free`.
+    blocks: Vec<Block>,
+    /// Slot of the block starting at each address.
+    starts: BTreeMap<u16, usize>,
+    free: Vec<usize>,
+    /// One bit per 256-byte page that holds cached code.
+    code_pages: [u64; 4],
+    /// Advanced whenever memory may have changed without the cache seeing
+    /// the write.
+    epoch: u64,
+    /// Slot and op index expected to run next.
+    cursor: Option<(usize, usize)>,
+}
+
+impl BlockCache {
+    /// Creates an empty cache.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            blocks: Vec::new(),
+            starts: BTreeMap::new(),
+            free: Vec::new(),
+            code_pages: [0; 4],
+            epoch: 0,
+            cursor: None,
+        }
+    }
+
+    /// Drops every cached block.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Returns the number of cached blocks.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Returns whether no blocks are cached.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    /// Executes one instruction like [`step_one`](super::step_one), using
+    /// the cached decode of the instruction at `PC` when it is still
+    /// current.
+    pub fn step(
+        &mut self,
+        state: &mut CoreState,
+        mmio: &mut dyn MmioBus,
+        config: &CoreConfig,
+    ) -> StepOutcome {
+        self.step_cached(state, mmio, config)
+    }
+
+    /// Runs cached steps until `boundary`, like [`run_one`](super::run_one).
+    pub fn run(
+        &mut self,
+        state: &mut CoreState,
+        mmio: &mut dyn MmioBus,
+        config: &CoreConfig,
+        boundary: RunBoundary,
+    ) -> RunOutcome {
+        self.run_until(state, mmio, config, |state, outcome, steps| {
+            check_run_boundary(state, boundary, outcome, steps)
+        })
+    }
+
+    /// Runs up to `n_ticks` whole ticks with cached steps, like
+    /// [`run_ticks_with_budget`](super::run_ticks_with_budget).
+    pub fn run_ticks_with_budget(
+        &mut self,
+        state: &mut CoreState,
+        mmio: &mut dyn MmioBus,
+        config: &CoreConfig,
+        n_ticks: u32,
+    ) -> TickBatch {
+        run_ticks_with(state, mmio, config, n_ticks, |state, mmio, config| {
+            self.run_until(state, mmio, config, |state, outcome, steps| {
+                ends_tick(state, outcome).then_some((steps, outcome))
+            })
+        })
+    }
+
+    /// Runs steps until `stop` returns a value for the latest outcome and
+    /// the number of steps so far.
+    ///
+    /// Each block is looked up and checked against memory once on entry;
+    /// its ops then run back to back until control leaves the block, a
+    /// store drops a cached block, or the tick ends or a dispatch is taken.
+    fn run_until<T>(
+        &mut self,
+        state: &mut CoreState,
+        mmio: &mut dyn MmioBus,
+        config: &CoreConfig,
+        mut stop: impl FnMut(&mut CoreState, StepOutcome, u32) -> Option<T>,
+    ) -> T {
+        self.epoch += 1;
+        self.cursor = None;
+        let check_ops = !config.experimental_opcodes.is_empty();
+        let mut steps = 0u32;
+
+        loop {
+            let Some(slot) = self.enter(state.arch.pc(), &state.memory, check_ops) else {
+                let mut written = None;
+                let outcome = step_with(state, mmio, config, fetch_and_decode, &mut written);
+                steps += 1;
+                self.after_step(written, outcome);
+                if let Some(done) = stop(state, outcome, steps) {
+                    return done;
+                }
+                continue;
+            };
+
+            for index in 0..self.blocks[slot].ops.len() {
+                if index > 0 && check_ops && !self.is_current(slot, index, &state.memory, true) {
+                    break;
+                }
+                let op = self.blocks[slot].ops[index];
+                let mut written = None;
+                let outcome =
+                    step_with(state, mmio, config, |_, _| Ok(op.instruction), &mut written);
+                steps += 1;
+                let stale = self.after_step(written, outcome);
+                if let Some(done) = stop(state, outcome, steps) {
+                    return done;
+                }
+                if stale || state.arch.pc() != op.next_pc() {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Runs one instruction, from the cache when it can, comparing the
+    /// op's bytes with memory first: a host stepping one instruction at a
+    /// time can write code the cache never sees.
+    fn step_cached(
+        &mut self,
+        state: &mut CoreState,
+        mmio: &mut dyn MmioBus,
+        config: &CoreConfig,
+    ) -> StepOutcome {
+        let mut written = None;
+        let outcome = match self.fetch(state.arch.pc(), &state.memory) {
+            Some(instruction) => {
+                step_with(state, mmio, config, |_, _| Ok(instruction), &mut written)
+            }
+            None => {
+                self.cursor = None;
+                step_with(state, mmio, config, fetch_and_decode, &mut written)
+            }
+        };
+        self.after_step(written, outcome);
+        outcome
+    }
+
+    /// Drops blocks the step wrote over and starts a new epoch when the
+    /// step may have changed memory behind the cache's back. Returns
+    /// whether either happened, so a running block must be looked up again.
+    fn after_step(&mut self, written: Option<u16>, outcome: StepOutcome) -> bool {
+        let mut stale = written.is_some_and(|addr| {
+            let first = self.invalidate(addr);
+            self.invalidate(addr.wrapping_add(1)) || first
+        });
+        // The tick may end, running DMA and tick hooks, or a dispatch frame
+        // may have been pushed.
+        if !matches!(outcome, StepOutcome::Retired { .. }) {
+            self.epoch += 1;
+            stale = true;
+        }
+        stale
+    }
+
+    /// Returns the cached decode of the instruction at `pc`, decoding a
+    /// new block if needed.
+    fn fetch(&mut self, pc: u16, memory: &[u8]) -> Option<DecodedInstruction> {
+        if let Some((slot, index)) = self.cursor {
+            let next = self.blocks[slot].ops.get(index);
+            if next.is_some_and(|op| op.pc == pc) && self.is_current(slot, index, memory, true) {
+                self.cursor = Some((slot, index + 1));
+                return Some(self.blocks[slot].ops[index].instruction);
+            }
+        }
+
+        let slot = self.enter(pc, memory, true)?;
+        self.cursor = Some((slot, 1));
+        Some(self.blocks[slot].ops[0].instruction)
+    }
+
+    /// Returns the slot of a current block starting at `pc`, decoding one
+    /// if needed, or `None` if the instruction at `pc` cannot be cached.
+    fn enter(&mut self, pc: u16, memory: &[u8], check_op: bool) -> Option<usize> {
+        match self.starts.get(&pc).copied() {
+            Some(slot) if self.is_current(slot, 0, memory, check_op) => Some(slot),
+            _ => {
+                let block = Block::decode(pc, memory, self.epoch)?;
+                Some(self.insert(block))
+            }
+        }
+    }
+
+    /// Checks the op at `index` of the block in `slot` against memory,
+    /// dropping the block if it changed. Without `check_op` the whole block
+    /// is compared once per epoch.
+    fn is_current(&mut self, slot: usize, index: usize, memory: &[u8], check_op: bool) -> bool {
+        let block = &mut self.blocks[slot];
+        let current = if check_op {
+            block.op_matches(index, memory)
+        } else if block.checked == self.epoch {
+            true
+        } else {
+            block.checked = self.epoch;
+            block.matches(memory)
+        };
+        if !current {
+            self.remove(slot);
+        }
+        current
+    }
+
+    fn insert(&mut self, block: Block) -> usize {
+        for page in usize::from(block.start >> 8)..=(block.end() - 1) >> 8 {
+            self.code_pages[page / 64] |= 1 << (page % 64);
+        }
+        self.remove_at(block.start);
+        let start = block.start;
+        let slot = if let Some(slot) = self.free.pop() {
+            self.blocks[slot] = block;
+            slot
+        } else {
+            self.blocks.push(block);
+            self.blocks.len() - 1
+        };
+        self.starts.insert(start, slot);
+        slot
+    }
+
+    fn remove_at(&mut self, start: u16) {
+        if let Some(&slot) = self.starts.get(&start) {
+            self.remove(slot);
+        }
+    }
+
+    fn remove(&mut self, slot: usize) {
+        let block = &mut self.blocks[slot];
+        self.starts.remove(&block.start);
+        block.ops.clear();
+        block.bytes.clear();
+        self.free.push(slot);
+        if self.cursor.is_some_and(|(cursor, _)| cursor == slot) {
+            self.cursor = None;
+        }
+    }
+
+    /// Drops every block holding the byte at `addr`, returning whether
+    /// there were any.
+    fn invalidate(&mut self, addr: u16) -> bool {
+        let page = usize::from(addr >> 8);
+        if self.code_pages[page / 64] & (1 << (page % 64)) == 0 {
+            return false;
+        }
+        let stale: Vec<usize> = self
+            .starts
+            .range(addr.saturating_sub(MAX_BLOCK_BYTES - 1)..=addr)
+            .map(|(_, &slot)| slot)
+            .filter(|&slot| self.blocks[slot].end() > usize::from(addr))
+            .collect();
+        for &slot in &stale {
+            self.remove(slot);
+        }
+        !stale.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BlockCache;
+    use crate::{
+        end_tick, run_one, run_ticks_with_budget, step_one, CompositeMmio, CoreConfig, CoreState,
+        DebugConsole, GeneralRegister, RunBoundary, StepOutcome,
+    };
+
+    /// Increments the word at 0x4000 and writes it to the debug console
+    /// once per tick.
+    const COUNTER_PROGRAM: [u8; 24] = [
+        0x10, 0x05, 0xE1, 0x32, // MOV R0, #0xE132
+        0x14, 0x05, 0x40, 0x00, // MOV R2, #0x4000
+        0x22, 0x81, // loop: LOAD R1, [R2]
+        0x42, 0x45, 0x00, 0x01, // ADD R1, R1, #0x0001
+        0x32, 0x81, // STORE R1, [R2]
+        0x32, 0x01, // STORE R1, [R0]
+        0x00, 0x10, // HALT
+        0x60, 0x35, 0xFF, 0xF0, // JMP #loop
+    ];
+
+    /// Runs from 0x4000 in RAM, rewriting the immediate of its own
+    /// `ADD R1` each pass so R1 accumulates 1 + 2 + 3 + ...
+    const SELF_MODIFYING_PROGRAM: [u8; 20] = [
+        0x14, 0x05, 0x40, 0x0C, // MOV R2, #0x400C
+        0x46, 0xC5, 0x00, 0x01, // loop: ADD R3, R3, #0x0001
+        0x36, 0x81, // STORE R3, [R2]
+        0x42, 0x45, 0x00, 0x00, // ADD R1, R1, #0x0000 (patched)
+        0x00, 0x10, // HALT
+        0x60, 0x35, 0xFF, 0xF0, // JMP #loop
+    ];
+
+    fn core_with(program: &[u8], origin: u16) -> (CoreState, CompositeMmio) {
+        let mut state = CoreState::with_config(&CoreConfig::default());
+        state.memory[usize::from(origin)..][..program.len()].copy_from_slice(program);
+        state.arch.set_pc(origin);
+        (
+            state,
+            CompositeMmio::new().with_console(DebugConsole::new()),
+        )
+    }
+
+    fn console_output(mmio: &mut CompositeMmio) -> Vec<u8> {
+        mmio.console_mut()
+            .map(DebugConsole::drain)
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn cached_steps_match_uncached_steps() {
+        let config = CoreConfig::default();
+        let (mut expected, mut expected_mmio) = core_with(&COUNTER_PROGRAM, 0);
+        let (mut state, mut mmio) = core_with(&COUNTER_PROGRAM, 0);
+        let mut cache = BlockCache::new();
+
+        for _ in 0..200 {
+            let outcome = cache.step(&mut state, &mut mmio, &config);
+            assert_eq!(
+                outcome,
+                step_one(&mut expected, &mut expected_mmio, &config)
+            );
+            assert_eq!(state, expected);
+            if matches!(outcome, StepOutcome::HaltedForTick { .. }) {
+                end_tick(&mut state, &mut mmio, None);
+                end_tick(&mut expected, &mut expected_mmio, None);
+            }
+        }
+        assert_eq!(
+            console_output(&mut mmio),
+            console_output(&mut expected_mmio)
+        );
+        // Entry block, the JMP, and the loop body it jumps into.
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn cached_batches_match_uncached_batches() {
+        let config = CoreConfig::default();
+        let (mut expected, mut expected_mmio) = core_with(&COUNTER_PROGRAM, 0);
+        let expected_batch = run_ticks_with_budget(&mut expected, &mut expected_mmio, &config, 30);
+
+        let (mut state, mut mmio) = core_with(&COUNTER_PROGRAM, 0);
+        let batch = BlockCache::new().run_ticks_with_budget(&mut state, &mut mmio, &config, 30);
+
+        assert_eq!(batch, expected_batch);
+        assert_eq!(state, expected);
+        assert_eq!(console_output(&mut mmio), (1..=30).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn writes_into_a_cached_block_invalidate_it() {
+        let config = CoreConfig::default();
+        let (mut expected, mut expected_mmio) = core_with(&SELF_MODIFYING_PROGRAM, 0x4000);
+        let (mut state, mut mmio) = core_with(&SELF_MODIFYING_PROGRAM, 0x4000);
+        let mut cache = BlockCache::new();
+
+        run_ticks_with_budget(&mut expected, &mut expected_mmio, &config, 10);
+        cache.run_ticks_with_budget(&mut state, &mut mmio, &config, 10);
+
+        assert_eq!(state, expected);
+        assert_eq!(state.arch.gpr(GeneralRegister::R1), 55);
+    }
+
+    #[test]
+    fn host_edits_to_cached_code_take_effect() {
+        let config = CoreConfig::default();
+        let (mut state, mut mmio) = core_with(&COUNTER_PROGRAM, 0);
+        let mut cache = BlockCache::new();
+        cache.run_ticks_with_budget(&mut state, &mut mmio, &config, 3);
+
+        // Count by 0x10 from now on.
+        state.memory[0x000D] = 0x10;
+        cache.run_ticks_with_budget(&mut state, &mut mmio, &config, 2);
+
+        assert_eq!(console_output(&mut mmio), [1, 2, 3, 0x13, 0x23]);
+    }
+
+    #[test]
+    fn undecodable_instructions_fault_without_being_cached() {
+        let config = CoreConfig::default();
+        let (mut expected, mut expected_mmio) = core_with(&[0xF0, 0x00], 0);
+        let (mut state, mut mmio) = core_with(&[0xF0, 0x00], 0);
+        let mut cache = BlockCache::new();
+
+        let outcome = cache.run(&mut state, &mut mmio, &config, RunBoundary::Fault);
+
+        assert_eq!(
+            outcome,
+            run_one(
+                &mut expected,
+                &mut expected_mmio,
+                &config,
+                RunBoundary::Fault
+            )
+        );
+        assert_eq!(state, expected);
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+//! Fast-forward execution for "skip ahead" controls.
+//!
+//! [`run_fast_forward`] runs many ticks as quickly as the host allows. It
+//! steps through a [`BlockCache`] and defers peripheral tick work: instead
+//! of calling [`MmioBus::on_tick`] at every tick boundary it counts the
+//! ticks and hands them to [`MmioBus::advance_ticks`] in one go. Deferred
+//! ticks are caught up before any MMIO access and at the end of the run, so
+//! the program never observes the difference and the result is identical
+//! to [`run_ticks_with_budget`](super::run_ticks_with_budget).
+
+use super::BlockCache;
+use crate::{CoreConfig, CoreState, MmioBus, MmioError, MmioWriteResult, TickBatch};
+
+/// Bus adapter that accumulates tick work until it is observable.
+struct DeferredTicks<'a> {
+    bus: &'a mut dyn MmioBus,
+    pending: u32,
+}
+
+impl DeferredTicks<'_> {
+    fn catch_up(&mut self) {
+        if self.pending > 0 {
+            self.bus.advance_ticks(self.pending);
+            self.pending = 0;
+        }
+    }
+}
+
+impl MmioBus for DeferredTicks<'_> {
+    fn read16(&mut self, addr: u16) -> Result<u16, MmioError> {
+        self.catch_up();
+        self.bus.read16(addr)
+    }
+
+    fn write16(&mut self, addr: u16, value: u16) -> Result<MmioWriteResult, MmioError> {
+        self.catch_up();
+        self.bus.write16(addr, value)
+    }
+
+    fn on_tick(&mut self) {
+        self.pending = self.pending.saturating_add(1);
+    }
+
+    fn advance_ticks(&mut self, ticks: u32) {
+        self.pending = self.pending.saturating_add(ticks);
+    }
+
+    fn on_tick_memory(&mut self, state: &mut CoreState) {
+        self.bus.on_tick_memory(state);
+    }
+}
+
+/// Runs up to `n_ticks` whole ticks like
+/// [`run_ticks_with_budget`](super::run_ticks_with_budget), batching
+/// peripheral tick work.
+///
+/// Architectural state, MMIO traffic and the returned batch match the
+/// unbatched run exactly; only the number of calls into the bus differs.
+/// Hosts skip ahead by calling this once and rendering the display
+/// afterwards rather than after every tick.
+pub fn run_fast_forward(
+    state: &mut CoreState,
+    mmio: &mut dyn MmioBus,
+    config: &CoreConfig,
+    n_ticks: u32,
+) -> TickBatch {
+    let mut bus = DeferredTicks {
+        bus: mmio,
+        pending: 0,
+    };
+    let batch = BlockCache::new().run_ticks_with_budget(state, &mut bus, config, n_ticks);
+    bus.catch_up();
+    batch
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run_fast_forward;
+    use crate::{
+        run_ticks_with_budget, CompositeMmio, CoreConfig, CoreState, DebugConsole, DmaController,
+        MmioBus, MmioError, MmioWriteResult, Tele7Config, Tele7Peripheral,
+    };
+
+    /// Enables TELE-7, then once per tick copies its STATUS register (which
+    /// carries the blink phase) to the debug console.
+    const BLINK_PROGRAM: [u8; 28] = [
+        0x10, 0x05, 0xE1, 0x22, // MOV R0, #0xE122
+        0x12, 0x05, 0x00, 0x01, // MOV R1, #0x0001
+        0x32, 0x01, // STORE R1, [R0]
+        0x10, 0x05, 0xE1, 0x32, // MOV R0, #0xE132
+        0x14, 0x05, 0xE1, 0x23, // MOV R2, #0xE123
+        0x26, 0x81, // loop: LOAD R3, [R2]
+        0x36, 0x01, // STORE R3, [R0]
+        0x00, 0x10, // HALT
+        0x60, 0x35, 0xFF, 0xF6, // JMP #loop
+    ];
+
+    fn blink_core() -> (CoreState, CompositeMmio) {
+        let mut state = CoreState::with_config(&CoreConfig::default());
+        state.memory[..BLINK_PROGRAM.len()].copy_from_slice(&BLINK_PROGRAM);
+        let mmio = CompositeMmio::new()
+            .with_tele7(Tele7Peripheral::new(Tele7Config::default()))
+            .with_console(DebugConsole::new());
+        (state, mmio)
+    }
+
+    #[test]
+    fn fast_forward_matches_a_normal_run() {
+        let config = CoreConfig::default();
+        let (mut expected, mut expected_mmio) = blink_core();
+        let expected_batch = run_ticks_with_budget(&mut expected, &mut expected_mmio, &config, 240);
+
+        let (mut state, mut mmio) = blink_core();
+        let batch = run_fast_forward(&mut state, &mut mmio, &config, 240);
+
+        assert_eq!(batch, expected_batch);
+        assert_eq!(state, expected);
+        assert_eq!(
+            mmio.tele7().map(|t7| t7.state().status_bits()),
+            expected_mmio.tele7().map(|t7| t7.state().status_bits())
+        );
+        let output = mmio.console_mut().map(DebugConsole::drain);
+        assert_eq!(output, expected_mmio.console_mut().map(DebugConsole::drain));
+        // The blink phase flipped every 50 ticks and the program saw it.
+        assert_eq!(output.map(|bytes| bytes.contains(&0x0B)), Some(true));
+    }
+
+    /// Starts a 500-word DMA copy of ROM to 0x4000 with a completion
+    /// event, then halts every tick.
+    const DMA_PROGRAM: [u8; 38] = [
+        0x12, 0x05, 0x00, 0x00, // MOV R1, #0x0000
+        0x32, 0x05, 0xE1, 0x52, // STORE R1, #0xE152 (SRC)
+        0x12, 0x05, 0x40, 0x00, // MOV R1, #0x4000
+        0x32, 0x05, 0xE1, 0x53, // STORE R1, #0xE153 (DST)
+        0x12, 0x05, 0x01, 0xF4, // MOV R1, #500
+        0x32, 0x05, 0xE1, 0x54, // STORE R1, #0xE154 (LEN)
+        0x12, 0x05, 0x00, 0x03, // MOV R1, #3
+        0x32, 0x05, 0xE1, 0x55, // STORE R1, #0xE155 (CTRL = START | IRQ)
+        0x00, 0x10, // loop: HALT
+        0x60, 0x35, 0xFF, 0xFA, // JMP #loop
+    ];
+
+    #[test]
+    fn dma_transfers_are_not_deferred() {
+        let config = CoreConfig::default();
+        let dma_core = || {
+            let mut state = CoreState::with_config(&config);
+            state.memory[..DMA_PROGRAM.len()].copy_from_slice(&DMA_PROGRAM);
+            (state, CompositeMmio::new().with_dma(DmaController::new()))
+        };
+        let (mut expected, mut expected_mmio) = dma_core();
+        let expected_batch = run_ticks_with_budget(&mut expected, &mut expected_mmio, &config, 12);
+
+        let (mut state, mut mmio) = dma_core();
+        let batch = run_fast_forward(&mut state, &mut mmio, &config, 12);
+
+        assert_eq!(batch, expected_batch);
+        assert_eq!(state, expected);
+        assert_eq!(&state.memory[0x4000..0x4000 + 1000], &state.memory[..1000]);
+        assert_eq!(state.event_queue.len, 1);
+        assert!(!mmio.dma().unwrap().is_busy());
+    }
+
+    #[derive(Default)]
+    struct TickCounter {
+        on_tick_calls: u32,
+        ticks: u32,
+    }
+
+    impl MmioBus for TickCounter {
+        fn read16(&mut self, _addr: u16) -> Result<u16, MmioError> {
+            Ok(0)
+        }
+
+        fn write16(&mut self, _addr: u16, _value: u16) -> Result<MmioWriteResult, MmioError> {
+            Ok(MmioWriteResult::Applied)
+        }
+
+        fn on_tick(&mut self) {
+            self.on_tick_calls += 1;
+            self.ticks += 1;
+        }
+
+        fn advance_ticks(&mut self, ticks: u32) {
+            self.ticks += ticks;
+        }
+    }
+
+    #[test]
+    fn tick_work_is_batched_when_the_program_never_touches_mmio() {
+        let config = CoreConfig::default();
+        let mut state = CoreState::with_config(&config);
+        let mut bus = TickCounter::default();
+
+        run_fast_forward(&mut state, &mut bus, &config, 500);
+
+        assert_eq!(bus.ticks, 500);
+        assert_eq!(bus.on_tick_calls, 0);
+    }
+}
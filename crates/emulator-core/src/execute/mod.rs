@@ -22,20 +22,29 @@
     missing_docs
 )]
 
+mod batch;
+mod block;
+mod fast_forward;
 mod flags;
 mod helpers;
+mod trace;
 
+pub use batch::{run_ticks_with_budget, TickBatch, TickStats, TICKS_PER_SECOND, TICK_DURATION};
+pub use block::BlockCache;
+pub use fast_forward::run_fast_forward;
 pub use flags::FlagsUpdate;
 pub use helpers::{compute_effective_address, compute_effective_address_with_pc};
+pub use trace::{run_ticks_with_trace, step_one_with_trace};
 
 use crate::decoder::{AddressingMode, DecodedInstruction, DecodedOrFault, RegisterField};
-use crate::encoding::OpcodeEncoding;
+use crate::encoding::{OpcodeEncoding, SpecialRegisterSelect};
 use crate::memory::{read_u16_be, write_u16_be};
-use crate::state::registers::FLAGS_ACTIVE_MASK;
+use crate::state::registers::{EVP_OVERFLOW, FLAGS_ACTIVE_MASK};
 use crate::timing::CycleCostKind;
 use crate::{
-    CoreConfig, CoreState, Decoder, GeneralRegister, MmioBus, RunBoundary, RunOutcome, RunState,
-    StepOutcome, TraceSink, VEC_EVENT, VEC_FAULT, VEC_TRAP,
+    CoreConfig, CoreState, Decoder, GeneralRegister, HaltReason, MmioBus, MmioError, MpuAccess,
+    OpcodeHandler, PcHistoryEntry, RunBoundary, RunOutcome, RunState, StepOutcome, TickHook,
+    TraceSink, CAP_MPU_BIT, MPU_BASE, MPU_END, TRAP_CAUSE_SOFTWARE, VEC_EVENT, VEC_FAULT, VEC_TRAP,
 };
 
 /// Outcome of executing a single instruction.
@@ -117,6 +126,12 @@ pub struct ExecuteState {
     pub eret_new_sp: Option<u16>,
     /// ERET: whether this ERET was executed outside handler context (should fault).
     pub eret_outside_handler_context: bool,
+    /// MPU register write (address, value) to apply at commit.
+    pub mpu_write: Option<(u16, u16)>,
+    /// Last word moved over the MMIO bus, latched at commit.
+    pub mmio_bus_value: Option<u16>,
+    /// Fault raised before the instruction had any effect.
+    pub fault: Option<crate::fault::FaultCode>,
 }
 
 impl Default for ExecuteState {
@@ -146,6 +161,9 @@ impl Default for ExecuteState {
             eret_restore_flags: None,
             eret_new_sp: None,
             eret_outside_handler_context: false,
+            mpu_write: None,
+            mmio_bus_value: None,
+            fault: None,
         }
     }
 }
@@ -186,12 +204,13 @@ pub fn execute_instruction(
     let mut exec = ExecuteState::default();
 
     match instr.encoding {
-        OpcodeEncoding::Nop => execute_nop(&mut exec, next_pc),
-        OpcodeEncoding::Sync => execute_sync(&mut exec, next_pc),
-        OpcodeEncoding::Halt => execute_halt(&mut exec, next_pc),
-        OpcodeEncoding::Trap => execute_trap(&mut exec, next_pc),
-        OpcodeEncoding::Swi => execute_swi(&mut exec, next_pc),
+        OpcodeEncoding::Nop => execute_nop(state, &mut exec, next_pc),
+        OpcodeEncoding::Sync => execute_sync(state, &mut exec, next_pc),
+        OpcodeEncoding::Halt => execute_halt(state, &mut exec, next_pc),
+        OpcodeEncoding::Trap => execute_trap(state, &mut exec, next_pc),
+        OpcodeEncoding::Swi => execute_swi(state, &mut exec, next_pc),
         OpcodeEncoding::Mov => execute_mov(instr, state, &mut exec, next_pc),
+        OpcodeEncoding::Mrs => execute_mrs(instr, state, &mut exec, next_pc),
         OpcodeEncoding::Load => execute_load(instr, state, mmio, &mut exec, next_pc),
         OpcodeEncoding::Store => execute_store(instr, state, mmio, &mut exec, next_pc),
         OpcodeEncoding::Add => execute_alu(instr, state, &mut exec, next_pc, AluOp::Add),
@@ -229,6 +248,10 @@ pub fn execute_instruction(
         OpcodeEncoding::Eret => execute_eret(instr, state, &mut exec, next_pc),
     }
 
+    if let Some(cause) = exec.fault {
+        return (ExecuteOutcome::Fault { cause }, exec);
+    }
+
     if exec.trap_pending {
         return (
             ExecuteOutcome::TrapDispatch {
@@ -322,15 +345,115 @@ pub fn commit_execution(state: &mut CoreState, exec: &ExecuteState) {
                 let bytes = value.to_be_bytes();
                 state.memory[usize::from(addr)] = bytes[0];
                 state.memory[usize::from(addr.wrapping_add(1))] = bytes[1];
+                state.write_log.record(addr, value);
             }
         }
     }
 
+    if let Some((addr, value)) = exec.mpu_write {
+        state.mpu.set_register(addr, value);
+    }
+
+    if let Some(value) = exec.mmio_bus_value {
+        state.mmio_bus_latch = value;
+    }
+
     if exec.mmio_write_denied {
         state.mmio_denied_write_count = state.mmio_denied_write_count.saturating_add(1);
     }
 }
 
+/// Returns `true` when `addr` is an MPU register the core serves itself
+/// instead of the host bus.
+fn mpu_owns(state: &CoreState, addr: u16) -> bool {
+    state.capability_enabled(CAP_MPU_BIT) && (MPU_BASE..=MPU_END).contains(&addr)
+}
+
+/// Checks `access` at `addr` against the MPU. Handlers run with the MPU
+/// bypassed.
+fn mpu_check(
+    state: &CoreState,
+    addr: u16,
+    access: MpuAccess,
+) -> Result<(), crate::fault::FaultCode> {
+    if !state.capability_enabled(CAP_MPU_BIT) || matches!(state.run_state, RunState::HandlerContext)
+    {
+        return Ok(());
+    }
+    state.mpu.check(addr, access)
+}
+
+/// Checks the fetch at `pc` against the MPU, recording and dispatching the
+/// fault when it is denied.
+fn deny_fetch(state: &mut CoreState, pc: u16) -> Option<StepOutcome> {
+    let cause = mpu_check(state, pc, MpuAccess::Execute).err()?;
+    state.mpu.record_violation(pc);
+    if perform_fault_dispatch(state, cause) {
+        let fault = state
+            .run_state
+            .latched_fault()
+            .unwrap_or(crate::fault::FaultCode::IllegalEncoding);
+        return Some(StepOutcome::Fault { cause: fault });
+    }
+    Some(StepOutcome::Fault { cause })
+}
+
+/// [`mpu_check`] for a data access, recording a denial in `exec`.
+fn mpu_denies(state: &CoreState, exec: &mut ExecuteState, addr: u16, access: MpuAccess) -> bool {
+    match mpu_check(state, addr, access) {
+        Ok(()) => false,
+        Err(cause) => {
+            exec.memory_addr = Some(addr);
+            exec.fault = Some(cause);
+            true
+        }
+    }
+}
+
+/// Reads `addr` over the MMIO bus, substituting the open-bus value when no
+/// device decodes it.
+fn read_mmio(
+    state: &CoreState,
+    mmio: &mut dyn MmioBus,
+    exec: &mut ExecuteState,
+    addr: u16,
+) -> Result<u16, MmioError> {
+    let value = if mpu_owns(state, addr) {
+        state.mpu.register(addr)
+    } else {
+        match mmio.read16(addr) {
+            Ok(value) => value,
+            Err(MmioError::Unmapped) => return Ok(state.open_bus.value(state.mmio_bus_latch)),
+            Err(err) => return Err(err),
+        }
+    };
+    exec.mmio_bus_value = Some(value);
+    Ok(value)
+}
+
+fn write_mmio(
+    state: &CoreState,
+    mmio: &mut dyn MmioBus,
+    exec: &mut ExecuteState,
+    addr: u16,
+    value: u16,
+) {
+    exec.mmio_bus_value = Some(value);
+    if mpu_owns(state, addr) {
+        exec.mpu_write = Some((addr, value));
+        return;
+    }
+    match mmio.write16(addr, value) {
+        Ok(crate::api::MmioWriteResult::Applied) => {}
+        Ok(crate::api::MmioWriteResult::DeniedSuppressed) => {
+            exec.mmio_write_denied = true;
+        }
+        Err(_) => {
+            exec.mmio_write_denied = true;
+        }
+    }
+}
+
 const fn decoder_register_to_general(field: RegisterField) -> GeneralRegister {
     match field {
         RegisterField::R0 => GeneralRegister::R0,
@@ -360,38 +483,38 @@ fn resolve_b_operand(instr: &DecodedInstruction, state: &CoreState) -> u16 {
     }
 }
 
-fn execute_nop(exec: &mut ExecuteState, next_pc: u16) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::Nop).unwrap_or(1);
+fn execute_nop(state: &CoreState, exec: &mut ExecuteState, next_pc: u16) {
+    exec.cycles = state.timing.cost(CycleCostKind::Nop);
     exec.next_pc = Some(next_pc);
     exec.flags_update = FlagsUpdate::None;
 }
 
-fn execute_sync(exec: &mut ExecuteState, next_pc: u16) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::Sync).unwrap_or(1);
+fn execute_sync(state: &CoreState, exec: &mut ExecuteState, next_pc: u16) {
+    exec.cycles = state.timing.cost(CycleCostKind::Sync);
     exec.next_pc = Some(next_pc);
     exec.flags_update = FlagsUpdate::None;
 }
 
-fn execute_halt(exec: &mut ExecuteState, next_pc: u16) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::Halt).unwrap_or(1);
+fn execute_halt(state: &CoreState, exec: &mut ExecuteState, next_pc: u16) {
+    exec.cycles = state.timing.cost(CycleCostKind::Halt);
     exec.next_pc = Some(next_pc);
     exec.halt_for_tick = true;
     exec.flags_update = FlagsUpdate::None;
 }
 
-fn execute_trap(exec: &mut ExecuteState, next_pc: u16) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::TrapIssue).unwrap_or(1);
+fn execute_trap(state: &CoreState, exec: &mut ExecuteState, next_pc: u16) {
+    exec.cycles = state.timing.cost(CycleCostKind::TrapIssue);
     exec.next_pc = Some(next_pc);
     exec.trap_pending = true;
-    exec.trap_cause = Some(0);
+    exec.trap_cause = Some(TRAP_CAUSE_SOFTWARE);
     exec.flags_update = FlagsUpdate::None;
 }
 
-fn execute_swi(exec: &mut ExecuteState, next_pc: u16) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::SwiIssue).unwrap_or(1);
+fn execute_swi(state: &CoreState, exec: &mut ExecuteState, next_pc: u16) {
+    exec.cycles = state.timing.cost(CycleCostKind::SwiIssue);
     exec.next_pc = Some(next_pc);
     exec.trap_pending = true;
-    exec.trap_cause = Some(0);
+    exec.trap_cause = Some(TRAP_CAUSE_SOFTWARE);
     exec.flags_update = FlagsUpdate::None;
 }
 
@@ -401,7 +524,7 @@ fn execute_mov(
     exec: &mut ExecuteState,
     next_pc: u16,
 ) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::Mov).unwrap_or(1);
+    exec.cycles = state.timing.cost(CycleCostKind::Mov);
     exec.next_pc = Some(next_pc);
 
     let Some(rd) = instr.rd else {
@@ -429,6 +552,41 @@ fn execute_mov(
     }
 }
 
+/// Copies a special register into `R[RD]`. FLAGS is left unchanged so a
+/// handler can sample it without disturbing the state it inspects.
+fn execute_mrs(
+    instr: &DecodedInstruction,
+    state: &CoreState,
+    exec: &mut ExecuteState,
+    next_pc: u16,
+) {
+    exec.cycles = state.timing.cost(CycleCostKind::Mrs);
+    exec.next_pc = Some(next_pc);
+    exec.flags_update = FlagsUpdate::None;
+
+    let Some(rd) = instr.rd else {
+        return;
+    };
+    let Some(select) = instr
+        .ra
+        .and_then(|ra| u8::try_from(ra.to_u3()).ok())
+        .and_then(SpecialRegisterSelect::from_u3)
+    else {
+        return;
+    };
+
+    let arch = &state.arch;
+    exec.dest_reg = Some(rd);
+    exec.dest_value = Some(match select {
+        SpecialRegisterSelect::Flags => arch.flags(),
+        SpecialRegisterSelect::Tick => arch.tick(),
+        SpecialRegisterSelect::Cap => arch.cap(),
+        SpecialRegisterSelect::Cause => arch.cause(),
+        SpecialRegisterSelect::Evp => arch.evp(),
+        SpecialRegisterSelect::Sp => arch.sp(),
+    });
+}
+
 fn execute_load(
     instr: &DecodedInstruction,
     state: &CoreState,
@@ -436,7 +594,7 @@ fn execute_load(
     exec: &mut ExecuteState,
     next_pc: u16,
 ) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::Load).unwrap_or(2);
+    exec.cycles = state.timing.cost(CycleCostKind::Load);
     exec.next_pc = Some(next_pc);
 
     let Some(rd) = instr.rd else {
@@ -449,6 +607,10 @@ fn execute_load(
         return;
     };
 
+    if mpu_denies(state, exec, ea, MpuAccess::Read) {
+        return;
+    }
+
     exec.memory_addr = Some(ea);
     exec.is_mmio_operation = false;
     exec.is_mmio_write = false;
@@ -459,7 +621,7 @@ fn execute_load(
     }
 
     let value = if exec.is_mmio_operation {
-        mmio.read16(ea).unwrap_or_default()
+        read_mmio(state, mmio, exec, ea).unwrap_or_default()
     } else {
         let lo = state.memory[usize::from(ea)];
         let hi = state.memory[usize::from(ea.wrapping_add(1))];
@@ -483,7 +645,7 @@ fn execute_store(
     exec: &mut ExecuteState,
     next_pc: u16,
 ) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::Store).unwrap_or(2);
+    exec.cycles = state.timing.cost(CycleCostKind::Store);
     exec.next_pc = Some(next_pc);
     exec.flags_update = FlagsUpdate::None;
 
@@ -495,6 +657,10 @@ fn execute_store(
         return;
     };
 
+    if mpu_denies(state, exec, ea, MpuAccess::Write) {
+        return;
+    }
+
     exec.memory_addr = Some(ea);
     exec.memory_write_pending = true;
     exec.memory_write_value = Some(value);
@@ -503,15 +669,7 @@ fn execute_store(
     if matches!(addr_region, crate::memory::MemoryRegion::Mmio) {
         exec.is_mmio_operation = true;
         exec.is_mmio_write = true;
-        match mmio.write16(ea, value) {
-            Ok(crate::api::MmioWriteResult::Applied) => {}
-            Ok(crate::api::MmioWriteResult::DeniedSuppressed) => {
-                exec.mmio_write_denied = true;
-            }
-            Err(_) => {
-                exec.mmio_write_denied = true;
-            }
-        }
+        write_mmio(state, mmio, exec, ea, value);
     }
 }
 
@@ -534,7 +692,7 @@ fn execute_alu(
     next_pc: u16,
     op: AluOp,
 ) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::Alu).unwrap_or(1);
+    exec.cycles = state.timing.cost(CycleCostKind::Alu);
     exec.next_pc = Some(next_pc);
 
     let Some(rd) = instr.rd else {
@@ -605,7 +763,7 @@ fn execute_cmp(
     exec: &mut ExecuteState,
     next_pc: u16,
 ) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::Alu).unwrap_or(1);
+    exec.cycles = state.timing.cost(CycleCostKind::Alu);
     exec.next_pc = Some(next_pc);
     exec.flags_update = FlagsUpdate::None;
 
@@ -645,7 +803,7 @@ fn execute_math(
         MathOp::Div | MathOp::Mod => CycleCostKind::Div,
         MathOp::Qadd | MathOp::Qsub | MathOp::Scv => CycleCostKind::SaturatingHelper,
     };
-    exec.cycles = crate::timing::cycle_cost(cost_kind).unwrap_or(1);
+    exec.cycles = state.timing.cost(cost_kind);
     exec.next_pc = Some(next_pc);
 
     let Some(rd) = instr.rd else {
@@ -670,12 +828,13 @@ fn execute_math(
             let res = u16::try_from((u32::from(reg_a) * u32::from(reg_b)) >> 16).unwrap_or(0);
             (res, compute_nzcv_flags(res, false, false))
         }
+        // A zero divisor yields 0 and never faults (spec section 5).
         MathOp::Div => {
-            let res = if reg_b == 0 { 0 } else { reg_a / reg_b };
+            let res = reg_a.checked_div(reg_b).unwrap_or(0);
             (res, compute_nzcv_flags(res, false, false))
         }
         MathOp::Mod => {
-            let res = if reg_b == 0 { 0 } else { reg_a % reg_b };
+            let res = reg_a.checked_rem(reg_b).unwrap_or(0);
             (res, compute_nzcv_flags(res, false, false))
         }
         MathOp::Qadd => {
@@ -750,7 +909,7 @@ fn execute_branch(
     };
 
     if taken {
-        exec.cycles = crate::timing::cycle_cost(CycleCostKind::BranchTaken).unwrap_or(2);
+        exec.cycles = state.timing.cost(CycleCostKind::BranchTaken);
         // Compute target the same way JMP does: PC-relative for AM=Immediate.
         let target = match instr.addressing_mode {
             Some(AddressingMode::Immediate) => {
@@ -766,7 +925,7 @@ fn execute_branch(
         };
         exec.next_pc = Some(ea);
     } else {
-        exec.cycles = crate::timing::cycle_cost(CycleCostKind::BranchNotTaken).unwrap_or(1);
+        exec.cycles = state.timing.cost(CycleCostKind::BranchNotTaken);
         exec.next_pc = Some(next_pc);
     }
     exec.flags_update = FlagsUpdate::None;
@@ -778,7 +937,7 @@ fn execute_jmp(
     exec: &mut ExecuteState,
     next_pc: u16,
 ) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::Jump).unwrap_or(2);
+    exec.cycles = state.timing.cost(CycleCostKind::Jump);
 
     let target = match instr.addressing_mode {
         Some(AddressingMode::Immediate) => {
@@ -807,7 +966,7 @@ fn execute_call_or_ret(
     // CALL: any other AM (typically Immediate/PC-relative).
     if matches!(instr.addressing_mode, Some(AddressingMode::DirectRegister)) {
         // --- RET path ---
-        exec.cycles = crate::timing::cycle_cost(CycleCostKind::Ret).unwrap_or(2);
+        exec.cycles = state.timing.cost(CycleCostKind::Ret);
         let sp = state.arch.sp();
         let lo = state.memory[usize::from(sp)];
         let hi = state.memory[usize::from(sp.wrapping_add(1))];
@@ -835,7 +994,7 @@ fn execute_call_or_ret(
         return;
     };
 
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::Call).unwrap_or(2);
+    exec.cycles = state.timing.cost(CycleCostKind::Call);
     let sp = state.arch.sp().wrapping_sub(2);
     state.arch.set_sp(sp);
     exec.memory_addr = Some(sp);
@@ -851,11 +1010,12 @@ fn execute_push(
     exec: &mut ExecuteState,
     next_pc: u16,
 ) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::Push).unwrap_or(1);
+    exec.cycles = state.timing.cost(CycleCostKind::Push);
     exec.next_pc = Some(next_pc);
     exec.flags_update = FlagsUpdate::None;
 
-    let Some(value) = read_register(state, instr.ra) else {
+    // PUSH stores R[RD], mirroring POP which loads into R[RD].
+    let Some(value) = read_register(state, instr.rd) else {
         return;
     };
 
@@ -872,7 +1032,7 @@ fn execute_pop(
     exec: &mut ExecuteState,
     next_pc: u16,
 ) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::Pop).unwrap_or(1);
+    exec.cycles = state.timing.cost(CycleCostKind::Pop);
     exec.next_pc = Some(next_pc);
 
     let Some(rd) = instr.rd else {
@@ -903,7 +1063,7 @@ fn execute_mmio_in(
     exec: &mut ExecuteState,
     next_pc: u16,
 ) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::MmioIn).unwrap_or(4);
+    exec.cycles = state.timing.cost(CycleCostKind::MmioIn);
     exec.next_pc = Some(next_pc);
 
     let Some(rd) = instr.rd else {
@@ -916,7 +1076,11 @@ fn execute_mmio_in(
         return;
     };
 
-    let value = mmio.read16(ea).unwrap_or_default();
+    if mpu_denies(state, exec, ea, MpuAccess::Read) {
+        return;
+    }
+
+    let value = read_mmio(state, mmio, exec, ea).unwrap_or_default();
 
     exec.dest_reg = Some(rd);
     exec.dest_value = Some(value);
@@ -935,7 +1099,7 @@ fn execute_mmio_out(
     exec: &mut ExecuteState,
     next_pc: u16,
 ) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::MmioOut).unwrap_or(4);
+    exec.cycles = state.timing.cost(CycleCostKind::MmioOut);
     exec.next_pc = Some(next_pc);
     exec.flags_update = FlagsUpdate::None;
 
@@ -947,19 +1111,15 @@ fn execute_mmio_out(
         return;
     };
 
+    if mpu_denies(state, exec, ea, MpuAccess::Write) {
+        return;
+    }
+
     exec.is_mmio_operation = true;
     exec.is_mmio_write = true;
     exec.memory_addr = Some(ea);
 
-    match mmio.write16(ea, value) {
-        Ok(crate::api::MmioWriteResult::Applied) => {}
-        Ok(crate::api::MmioWriteResult::DeniedSuppressed) => {
-            exec.mmio_write_denied = true;
-        }
-        Err(_) => {
-            exec.mmio_write_denied = true;
-        }
-    }
+    write_mmio(state, mmio, exec, ea, value);
 }
 
 fn execute_bitop(
@@ -975,7 +1135,7 @@ fn execute_bitop(
         OpcodeEncoding::Btest => CycleCostKind::MmioBitTest,
         _ => CycleCostKind::MmioIn,
     };
-    exec.cycles = crate::timing::cycle_cost(cost_kind).unwrap_or(4);
+    exec.cycles = state.timing.cost(cost_kind);
     exec.next_pc = Some(next_pc);
 
     let Some(ea) = compute_effective_address(instr, state) else {
@@ -983,9 +1143,16 @@ fn execute_bitop(
         return;
     };
 
+    let writes = matches!(instr.encoding, OpcodeEncoding::Bset | OpcodeEncoding::Bclr);
+    if mpu_denies(state, exec, ea, MpuAccess::Read)
+        || (writes && mpu_denies(state, exec, ea, MpuAccess::Write))
+    {
+        return;
+    }
+
     let bit = instr.immediate_value.map_or(0, |v| v & 0x0F);
 
-    let value = match mmio.read16(ea) {
+    let value = match read_mmio(state, mmio, exec, ea) {
         Ok(v) => v,
         Err(_) => {
             exec.flags_update = FlagsUpdate::None;
@@ -1002,18 +1169,10 @@ fn execute_bitop(
 
     exec.is_mmio_operation = true;
 
-    if matches!(instr.encoding, OpcodeEncoding::Bset | OpcodeEncoding::Bclr) {
+    if writes {
         exec.is_mmio_write = true;
         exec.memory_addr = Some(ea);
-        match mmio.write16(ea, result) {
-            Ok(crate::api::MmioWriteResult::Applied) => {}
-            Ok(crate::api::MmioWriteResult::DeniedSuppressed) => {
-                exec.mmio_write_denied = true;
-            }
-            Err(_) => {
-                exec.mmio_write_denied = true;
-            }
-        }
+        write_mmio(state, mmio, exec, ea, result);
     }
 
     exec.flags_update = FlagsUpdate::UpdateNZ {
@@ -1030,7 +1189,7 @@ fn execute_ewait(
     exec: &mut ExecuteState,
     next_pc: u16,
 ) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::Ewait).unwrap_or(1);
+    exec.cycles = state.timing.cost(CycleCostKind::Ewait);
 
     if state.event_queue.is_empty() {
         exec.next_pc = Some(state.arch.pc());
@@ -1046,7 +1205,7 @@ fn execute_eget(
     exec: &mut ExecuteState,
     next_pc: u16,
 ) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::Eget).unwrap_or(1);
+    exec.cycles = state.timing.cost(CycleCostKind::Eget);
     exec.next_pc = Some(next_pc);
 
     let Some(rd) = instr.rd else {
@@ -1090,7 +1249,7 @@ fn execute_eret(
     exec: &mut ExecuteState,
     next_pc: u16,
 ) {
-    exec.cycles = crate::timing::cycle_cost(CycleCostKind::EretReturn).unwrap_or(4);
+    exec.cycles = state.timing.cost(CycleCostKind::EretReturn);
 
     if !matches!(state.run_state, crate::state::RunState::HandlerContext) {
         exec.flags_update = FlagsUpdate::None;
@@ -1143,6 +1302,23 @@ fn check_event_dispatch(state: &mut CoreState) -> Option<u8> {
     state.event_queue.dequeue()
 }
 
+/// Consumes a latched `EVP_OVERFLOW` at the event dispatch check.
+///
+/// Returns true if the overflow fault should be raised. The check runs under
+/// the same conditions as event dispatch, so an overflow that happens while
+/// a handler runs with events disabled is raised after `ERET`.
+fn check_event_overflow(state: &mut CoreState) -> bool {
+    if !state.capability_enabled(0) || !state.arch.flag_is_set(0x10) {
+        return false;
+    }
+    let evp = state.arch.evp();
+    if evp & EVP_OVERFLOW == 0 {
+        return false;
+    }
+    state.arch.set_evp_core_owned(evp & !EVP_OVERFLOW);
+    true
+}
+
 const fn capability_bit_for_encoding(encoding: OpcodeEncoding) -> Option<u8> {
     match encoding {
         OpcodeEncoding::Ewait | OpcodeEncoding::Eget => Some(0), // CAP_EVTQ
@@ -1153,10 +1329,18 @@ const fn capability_bit_for_encoding(encoding: OpcodeEncoding) -> Option<u8> {
         | OpcodeEncoding::Scv => {
             Some(2) // CAP_FXH
         }
+        OpcodeEncoding::Mrs => Some(3), // CAP_TRC
         _ => None,
     }
 }
 
+/// Writes one word of a dispatch frame at `sp`, logging the write.
+fn push_dispatch_word(state: &mut CoreState, sp: u16, value: u16) {
+    if write_u16_be(state.memory.as_mut(), sp, value).is_ok() {
+        state.write_log.record(sp, value);
+    }
+}
+
 /// Performs the trap dispatch sequence:
 /// 1. Latch cause into CAUSE register
 /// 2. Set R0 with cause value
@@ -1168,13 +1352,13 @@ fn perform_trap_dispatch(state: &mut CoreState, cause: u16) {
     state.arch.set_gpr(GeneralRegister::R0, cause);
     let sp = state.arch.sp().wrapping_sub(2);
     state.arch.set_sp(sp);
-    let _ = write_u16_be(state.memory.as_mut(), sp, state.arch.pc());
+    push_dispatch_word(state, sp, state.arch.pc());
     let sp = sp.wrapping_sub(2);
     state.arch.set_sp(sp);
-    let _ = write_u16_be(state.memory.as_mut(), sp, state.arch.flags());
+    push_dispatch_word(state, sp, state.arch.flags());
     let sp = sp.wrapping_sub(2);
     state.arch.set_sp(sp);
-    let _ = write_u16_be(state.memory.as_mut(), sp, cause);
+    push_dispatch_word(state, sp, cause);
     let mut flags = state.arch.flags();
     flags &= !0x10;
     state.arch.set_flags(flags);
@@ -1190,19 +1374,19 @@ fn perform_trap_dispatch(state: &mut CoreState, cause: u16) {
 /// 2. Set R0 with event_id
 /// 3. Push PC, FLAGS, CAUSE to stack
 /// 4. Disable events (FLAGS.I = 0)
-/// 5. Jump to VEC_EVENT
+/// 5. Jump to VEC_EVENT and enter handler context
 fn perform_event_dispatch(state: &mut CoreState, event_id: u8) {
     state.arch.set_cause(u16::from(event_id));
     state.arch.set_gpr(GeneralRegister::R0, u16::from(event_id));
     let sp = state.arch.sp().wrapping_sub(2);
     state.arch.set_sp(sp);
-    let _ = write_u16_be(state.memory.as_mut(), sp, state.arch.pc());
+    push_dispatch_word(state, sp, state.arch.pc());
     let sp = sp.wrapping_sub(2);
     state.arch.set_sp(sp);
-    let _ = write_u16_be(state.memory.as_mut(), sp, state.arch.flags());
+    push_dispatch_word(state, sp, state.arch.flags());
     let sp = sp.wrapping_sub(2);
     state.arch.set_sp(sp);
-    let _ = write_u16_be(state.memory.as_mut(), sp, u16::from(event_id));
+    push_dispatch_word(state, sp, u16::from(event_id));
     let mut flags = state.arch.flags();
     flags &= !0x10;
     state.arch.set_flags(flags);
@@ -1210,6 +1394,7 @@ fn perform_event_dispatch(state: &mut CoreState, event_id: u8) {
         return;
     };
     state.arch.set_pc(handler_pc);
+    state.run_state = RunState::HandlerContext;
 }
 
 /// Performs the fault dispatch sequence:
@@ -1240,13 +1425,13 @@ fn perform_fault_dispatch(state: &mut CoreState, cause: crate::fault::FaultCode)
         .set_gpr(GeneralRegister::R0, u16::from(cause.as_u8()));
     let sp = state.arch.sp().wrapping_sub(2);
     state.arch.set_sp(sp);
-    let _ = write_u16_be(state.memory.as_mut(), sp, state.arch.pc());
+    push_dispatch_word(state, sp, state.arch.pc());
     let sp = sp.wrapping_sub(2);
     state.arch.set_sp(sp);
-    let _ = write_u16_be(state.memory.as_mut(), sp, state.arch.flags());
+    push_dispatch_word(state, sp, state.arch.flags());
     let sp = sp.wrapping_sub(2);
     state.arch.set_sp(sp);
-    let _ = write_u16_be(state.memory.as_mut(), sp, u16::from(cause.as_u8()));
+    push_dispatch_word(state, sp, u16::from(cause.as_u8()));
     let mut flags = state.arch.flags();
     flags &= !0x10;
     state.arch.set_flags(flags);
@@ -1263,6 +1448,22 @@ fn perform_fault_dispatch(state: &mut CoreState, cause: crate::fault::FaultCode)
 /// - Tick budget checking after commit
 /// - Budget fault handling
 pub fn step_one(state: &mut CoreState, mmio: &mut dyn MmioBus, config: &CoreConfig) -> StepOutcome {
+    step_with(state, mmio, config, fetch_and_decode, &mut None)
+}
+
+/// [`step_one`] with the instruction at `PC` supplied by `fetch` instead of
+/// decoded from memory, so a [`BlockCache`] can hand over a pre-decoded
+/// instruction. `fetch` must return exactly what [`fetch_and_decode`] would.
+///
+/// `written` receives the address of the word the instruction stored to
+/// memory, if any. Dispatch frames are not reported.
+fn step_with(
+    state: &mut CoreState,
+    mmio: &mut dyn MmioBus,
+    config: &CoreConfig,
+    fetch: impl FnOnce(u16, &[u8]) -> Result<DecodedInstruction, crate::fault::FaultCode>,
+    written: &mut Option<u16>,
+) -> StepOutcome {
     match state.run_state {
         RunState::FaultLatched(_) => {
             return StepOutcome::Fault {
@@ -1288,7 +1489,18 @@ pub fn step_one(state: &mut CoreState, mmio: &mut dyn MmioBus, config: &CoreConf
     }
 
     let pc = state.arch.pc();
-    let fetch_result = fetch_and_decode(pc, &state.memory);
+    if !config.experimental_opcodes.is_empty() {
+        let raw_word = u16::from_be_bytes([
+            state.memory[usize::from(pc)],
+            state.memory[usize::from(pc.wrapping_add(1))],
+        ]);
+        let op = raw_word.to_be_bytes()[0] >> 4;
+        if let Some(handler) = config.experimental_opcodes.handler(op) {
+            return step_extension(state, mmio, config, handler, pc, raw_word);
+        }
+    }
+
+    let fetch_result = fetch(pc, &state.memory);
     let instruction = match fetch_result {
         Ok(instr) => instr,
         Err(cause) => {
@@ -1325,8 +1537,29 @@ pub fn step_one(state: &mut CoreState, mmio: &mut dyn MmioBus, config: &CoreConf
         }
     }
 
+    if let Some(outcome) = deny_fetch(state, pc) {
+        return outcome;
+    }
+
+    let history_entry = state.pc_history.is_enabled().then(|| PcHistoryEntry {
+        pc,
+        raw_word: u16::from_be_bytes([
+            state.memory[usize::from(pc)],
+            state.memory[usize::from(pc.wrapping_add(1))],
+        ]),
+    });
+
     let (outcome, exec_state) = execute_instruction(&instruction, state, mmio);
 
+    if !matches!(outcome, ExecuteOutcome::Fault { .. }) {
+        if let Some(entry) = history_entry {
+            state.pc_history.record(entry);
+        }
+        if exec_state.memory_write_pending && !exec_state.is_mmio_operation {
+            *written = exec_state.memory_addr;
+        }
+    }
+
     match outcome {
         ExecuteOutcome::Retired { cycles } => {
             commit_execution(state, &exec_state);
@@ -1356,23 +1589,14 @@ pub fn step_one(state: &mut CoreState, mmio: &mut dyn MmioBus, config: &CoreConf
                 state.run_state = crate::state::RunState::Running;
             }
 
-            let new_tick = state.arch.tick();
-            if new_tick >= config.tick_budget_cycles {
-                state.run_state = crate::state::RunState::HaltedForTick;
-                return StepOutcome::HaltedForTick;
-            }
-
-            if let Some(event_id) = check_event_dispatch(state) {
-                perform_event_dispatch(state, event_id);
-                return StepOutcome::EventDispatch { event_id };
-            }
-
-            StepOutcome::Retired { cycles }
+            finish_retired(state, config, cycles)
         }
         ExecuteOutcome::HaltedForTick => {
             commit_execution(state, &exec_state);
             state.run_state = crate::state::RunState::HaltedForTick;
-            StepOutcome::HaltedForTick
+            StepOutcome::HaltedForTick {
+                reason: HaltReason::Instruction,
+            }
         }
         ExecuteOutcome::TrapDispatch { cause } => {
             commit_execution(state, &exec_state);
@@ -1385,6 +1609,80 @@ pub fn step_one(state: &mut CoreState, mmio: &mut dyn MmioBus, config: &CoreConf
             StepOutcome::EventDispatch { event_id }
         }
         ExecuteOutcome::Fault { cause } => {
+            if let (crate::fault::FaultCode::MpuViolation, Some(addr)) =
+                (cause, exec_state.memory_addr)
+            {
+                state.mpu.record_violation(addr);
+            }
+            if perform_fault_dispatch(state, cause) {
+                let fault = state
+                    .run_state
+                    .latched_fault()
+                    .unwrap_or(crate::fault::FaultCode::IllegalEncoding);
+                return StepOutcome::Fault { cause: fault };
+            }
+            StepOutcome::Fault { cause }
+        }
+    }
+}
+
+/// Completes a retired instruction: ends the tick when the budget is spent,
+/// otherwise raises a latched event-queue overflow or dispatches a pending
+/// event.
+fn finish_retired(state: &mut CoreState, config: &CoreConfig, cycles: u16) -> StepOutcome {
+    let new_tick = state.arch.tick();
+    if new_tick >= config.tick_budget_cycles {
+        state.run_state = crate::state::RunState::HaltedForTick;
+        return StepOutcome::HaltedForTick {
+            reason: HaltReason::BudgetExhausted,
+        };
+    }
+
+    if check_event_overflow(state) {
+        let cause = crate::fault::FaultCode::EventQueueOverflow;
+        if perform_fault_dispatch(state, cause) {
+            let fault = state
+                .run_state
+                .latched_fault()
+                .unwrap_or(crate::fault::FaultCode::IllegalEncoding);
+            return StepOutcome::Fault { cause: fault };
+        }
+        return StepOutcome::Fault { cause };
+    }
+
+    if let Some(event_id) = check_event_dispatch(state) {
+        perform_event_dispatch(state, event_id);
+        return StepOutcome::EventDispatch { event_id };
+    }
+
+    StepOutcome::Retired { cycles }
+}
+
+/// Runs an instruction whose primary opcode has a host handler registered
+/// in [`CoreConfig::experimental_opcodes`].
+fn step_extension(
+    state: &mut CoreState,
+    mmio: &mut dyn MmioBus,
+    config: &CoreConfig,
+    handler: &dyn OpcodeHandler,
+    pc: u16,
+    raw_word: u16,
+) -> StepOutcome {
+    if let Some(outcome) = deny_fetch(state, pc) {
+        return outcome;
+    }
+    state.arch.set_pc(pc.wrapping_add(2));
+    match handler.execute(raw_word, state, mmio) {
+        Ok(cycles) => {
+            let cycles = cycles.max(1);
+            if state.pc_history.is_enabled() {
+                state.pc_history.record(PcHistoryEntry { pc, raw_word });
+            }
+            state.arch.set_tick(state.arch.tick().wrapping_add(cycles));
+            finish_retired(state, config, cycles)
+        }
+        Err(cause) => {
+            state.arch.set_pc(pc);
             if perform_fault_dispatch(state, cause) {
                 let fault = state
                     .run_state
@@ -1436,43 +1734,78 @@ pub fn run_one(
         let outcome = step_one(state, mmio, config);
         steps += 1;
 
-        let should_stop = match boundary {
-            RunBoundary::TickBoundary => {
-                matches!(outcome, StepOutcome::HaltedForTick)
-            }
-            RunBoundary::Halted => {
-                matches!(outcome, StepOutcome::HaltedForTick)
-            }
-            RunBoundary::Fault => {
-                matches!(outcome, StepOutcome::Fault { .. })
-            }
-        };
-
-        if should_stop {
-            return RunOutcome {
-                steps,
-                final_step: outcome,
-            };
+        if let Some(done) = check_run_boundary(state, boundary, outcome, steps) {
+            return done;
         }
+    }
+}
 
-        match outcome {
-            StepOutcome::TrapDispatch { .. }
+/// Closes the current tick and prepares the core for the next one.
+///
+/// Records the cycles the tick used in [`CoreState::tick_usage`], resets
+/// `TICK` to 0 and resumes a core that is `HaltedForTick`, then runs
+/// per-tick work: the bus's [`MmioBus::on_tick`] followed by `hook`, if any.
+/// Hosts call this once per tick so that peripheral timers and host
+/// callbacks advance in one place however the tick itself was run.
+pub fn end_tick(state: &mut CoreState, mmio: &mut dyn MmioBus, hook: Option<&mut dyn TickHook>) {
+    state.tick_usage.record(state.arch.tick());
+    state.arch.set_tick(0);
+    if matches!(state.run_state, RunState::HaltedForTick) {
+        state.run_state = RunState::Running;
+    }
+    mmio.on_tick();
+    mmio.on_tick_memory(state);
+    if let Some(hook) = hook {
+        hook.on_tick(state);
+    }
+}
+
+/// Decides whether a batched run toward `boundary` stops after `outcome`,
+/// its `steps`-th step, and returns the run's result if so.
+///
+/// Trap/event dispatch and faults end every run. For
+/// [`RunBoundary::Breakpoint`] this checks the breakpoint table at the new
+/// PC, which counts the hit. Shared by [`run_one`], [`run_one_with_trace`]
+/// and hosts that drive [`step_one`] themselves.
+pub fn check_run_boundary(
+    state: &mut CoreState,
+    boundary: RunBoundary,
+    outcome: StepOutcome,
+    steps: u32,
+) -> Option<RunOutcome> {
+    let mut breakpoint = None;
+    let interrupted = matches!(
+        outcome,
+        StepOutcome::TrapDispatch { .. }
             | StepOutcome::EventDispatch { .. }
-            | StepOutcome::Fault { .. } => {
-                return RunOutcome {
-                    steps,
-                    final_step: outcome,
-                };
+            | StepOutcome::Fault { .. }
+    );
+    let halted = matches!(outcome, StepOutcome::HaltedForTick { .. });
+    let should_stop = interrupted
+        || match boundary {
+            RunBoundary::TickBoundary | RunBoundary::Halted => halted,
+            RunBoundary::Fault => false,
+            RunBoundary::StepCount(limit) => halted || steps >= limit,
+            RunBoundary::Breakpoint => {
+                breakpoint = (!halted)
+                    .then(|| state.breakpoints.check(&state.arch, &state.memory))
+                    .flatten();
+                halted || breakpoint.is_some()
             }
-            StepOutcome::Retired { .. } | StepOutcome::HaltedForTick => {}
-        }
-    }
+        };
+
+    should_stop.then_some(RunOutcome {
+        steps,
+        final_step: outcome,
+        breakpoint,
+    })
 }
 
 /// Runs multiple steps with deterministic trace callback dispatch.
 ///
-/// When `trace_sink` is `None`, tracing is disabled and this function has
-/// zero/neat-zero overhead compared to `run_one`.
+/// Each step is reported through [`step_one_with_trace`]. When `trace_sink`
+/// is `None`, tracing is disabled and this function has zero/neat-zero
+/// overhead compared to `run_one`.
 pub fn run_one_with_trace(
     state: &mut CoreState,
     mmio: &mut dyn MmioBus,
@@ -1483,55 +1816,14 @@ pub fn run_one_with_trace(
     let mut steps = 0u32;
 
     loop {
-        let pc = state.arch.pc();
-        let raw_word = {
-            let lo = state.memory[usize::from(pc)];
-            let hi = state.memory[usize::from(pc.wrapping_add(1))];
-            u16::from_be_bytes([lo, hi])
+        let outcome = match trace_sink.as_deref_mut() {
+            Some(sink) => step_one_with_trace(state, mmio, config, sink),
+            None => step_one(state, mmio, config),
         };
-
-        if let Some(sink) = trace_sink.as_deref_mut() {
-            sink.on_event(crate::api::TraceEvent::InstructionStart { pc, raw_word });
-        }
-
-        let outcome = step_one(state, mmio, config);
         steps += 1;
 
-        if let Some(sink) = trace_sink.as_deref_mut() {
-            match outcome {
-                StepOutcome::Retired { cycles } => {
-                    sink.on_event(crate::api::TraceEvent::InstructionRetired { pc, cycles });
-                }
-                StepOutcome::Fault { cause } => {
-                    sink.on_event(crate::api::TraceEvent::FaultRaised { cause, pc });
-                }
-                _ => {}
-            }
-        }
-
-        let should_stop = match boundary {
-            RunBoundary::TickBoundary => matches!(outcome, StepOutcome::HaltedForTick),
-            RunBoundary::Halted => matches!(outcome, StepOutcome::HaltedForTick),
-            RunBoundary::Fault => matches!(outcome, StepOutcome::Fault { .. }),
-        };
-
-        if should_stop {
-            return RunOutcome {
-                steps,
-                final_step: outcome,
-            };
-        }
-
-        match outcome {
-            StepOutcome::TrapDispatch { .. }
-            | StepOutcome::EventDispatch { .. }
-            | StepOutcome::Fault { .. } => {
-                return RunOutcome {
-                    steps,
-                    final_step: outcome,
-                };
-            }
-            StepOutcome::Retired { .. } | StepOutcome::HaltedForTick => {}
+        if let Some(done) = check_run_boundary(state, boundary, outcome, steps) {
+            return done;
         }
     }
 }
@@ -1541,7 +1833,9 @@ mod tests {
     use super::*;
     use crate::decoder::Decoder;
     use crate::encoding::OpcodeEncoding;
-    use crate::{EventQueueSnapshot, RunBoundary, SimpleTraceSink};
+    use crate::{
+        CompositeMmio, EventQueueSnapshot, MemoryWrite, OpenBus, RunBoundary, SimpleTraceSink,
+    };
 
     fn decode_instr(word: u16) -> DecodedInstruction {
         let result = Decoder::decode(word);
@@ -1591,6 +1885,66 @@ mod tests {
         assert_eq!(exec.dest_value, Some(12));
     }
 
+    #[test]
+    fn shifts_take_their_count_from_an_immediate() {
+        let mut state = CoreState::default();
+        state.arch.set_gpr(GeneralRegister::R1, 0x9A0F);
+        // SHL R0, R1, #4 then SHR R2, R1, #0x13 (count uses its low 4 bits).
+        for (index, word) in [0x406D_u16, 0x0004, 0x4475, 0x0013].iter().enumerate() {
+            write_u16_be(&mut state.memory, 2 * index as u16, *word).unwrap();
+        }
+        let mut mmio = ZeroMmio;
+        let config = CoreConfig::default();
+
+        let outcome = step_one(&mut state, &mut mmio, &config);
+        assert!(matches!(outcome, StepOutcome::Retired { cycles: 1 }));
+        assert_eq!(state.arch.gpr(GeneralRegister::R0), 0xA0F0);
+        assert_ne!(
+            state.arch.flags() & crate::state::registers::FLAGS_C,
+            0,
+            "bit 12 shifted out"
+        );
+        assert_eq!(state.arch.pc(), 0x0004);
+
+        step_one(&mut state, &mut mmio, &config);
+        assert_eq!(state.arch.gpr(GeneralRegister::R2), 0x1341);
+        assert_eq!(state.arch.gpr(GeneralRegister::R1), 0x9A0F);
+    }
+
+    #[test]
+    fn cmp_with_immediate_sets_flags_at_boundaries() {
+        use crate::state::registers::{FLAGS_C, FLAGS_N, FLAGS_V, FLAGS_Z};
+
+        // (R0, immediate, expected NZCV)
+        let cases = [
+            (5, 5, FLAGS_Z),
+            (0, 1, FLAGS_N | FLAGS_C),
+            (0xFFFF, 0, FLAGS_N),
+            (0x8000, 1, FLAGS_V),
+            (0x7FFF, 0xFFFF, FLAGS_N | FLAGS_C | FLAGS_V),
+            (0x7FFF, 0x8000, FLAGS_N | FLAGS_C | FLAGS_V),
+            (0xFFFF, 0xFFFF, FLAGS_Z),
+        ];
+        for (value, imm, expected) in cases {
+            let mut state = CoreState::default();
+            state.arch.set_gpr(GeneralRegister::R0, value);
+            // CMP R0, #imm - OP=4, SUB=7, RD=0, RA=0, AM=5
+            write_u16_be(&mut state.memory, 0x0000, 0x403D).unwrap();
+            write_u16_be(&mut state.memory, 0x0002, imm).unwrap();
+
+            let outcome = step_one(&mut state, &mut ZeroMmio, &CoreConfig::default());
+
+            assert!(matches!(outcome, StepOutcome::Retired { cycles: 1 }));
+            assert_eq!(
+                state.arch.flags(),
+                expected,
+                "CMP 0x{value:04X}, #0x{imm:04X}"
+            );
+            assert_eq!(state.arch.gpr(GeneralRegister::R0), value);
+            assert_eq!(state.arch.pc(), 0x0004);
+        }
+    }
+
     #[test]
     fn div_by_zero_returns_zero() {
         let mut state = CoreState::default();
@@ -1710,6 +2064,24 @@ mod tests {
         assert_eq!(state.arch.tick(), 1);
     }
 
+    #[test]
+    fn step_one_charges_the_configured_timing_model() {
+        let config = CoreConfig {
+            timing: crate::TimingModel::Custom(
+                crate::CycleCostTable::V1.with_cost(CycleCostKind::Nop, 3),
+            ),
+            ..CoreConfig::default()
+        };
+        let mut state = CoreState::with_config(&config);
+        let mut mmio = crate::CompositeMmio::new();
+
+        assert_eq!(
+            step_one(&mut state, &mut mmio, &config),
+            StepOutcome::Retired { cycles: 3 }
+        );
+        assert_eq!(state.arch.tick(), 3);
+    }
+
     #[test]
     fn step_one_halt_advances_pc_and_sets_halted_for_tick() {
         let mut state = CoreState::default();
@@ -1735,7 +2107,12 @@ mod tests {
 
         let outcome = step_one(&mut state, &mut mmio, &config);
 
-        assert!(matches!(outcome, StepOutcome::HaltedForTick));
+        assert_eq!(
+            outcome,
+            StepOutcome::HaltedForTick {
+                reason: HaltReason::Instruction
+            }
+        );
         assert_eq!(state.arch.pc(), 0x0002);
         assert_eq!(state.run_state, RunState::HaltedForTick);
     }
@@ -1773,6 +2150,46 @@ mod tests {
         assert_eq!(state.run_state, RunState::Running);
     }
 
+    #[test]
+    fn end_tick_resumes_and_runs_tick_hooks_in_order() {
+        let mut state = CoreState {
+            run_state: RunState::HaltedForTick,
+            ..CoreState::default()
+        };
+        state.arch.set_tick(640);
+
+        struct CountingMmio {
+            ticks: u16,
+        }
+        impl MmioBus for CountingMmio {
+            fn read16(&mut self, _addr: u16) -> Result<u16, crate::api::MmioError> {
+                Err(crate::api::MmioError::ReadFailed)
+            }
+            fn write16(
+                &mut self,
+                _addr: u16,
+                _value: u16,
+            ) -> Result<crate::api::MmioWriteResult, crate::api::MmioError> {
+                Err(crate::api::MmioError::WriteFailed)
+            }
+            fn on_tick(&mut self) {
+                self.ticks += 1;
+            }
+        }
+
+        let mut mmio = CountingMmio { ticks: 0 };
+        let mut seen = Vec::new();
+        let mut hook = |state: &mut CoreState| seen.push((state.arch.tick(), state.run_state));
+
+        end_tick(&mut state, &mut mmio, Some(&mut hook));
+        end_tick(&mut state, &mut mmio, None);
+
+        assert_eq!(mmio.ticks, 2);
+        assert_eq!(seen, vec![(0, RunState::Running)]);
+        assert_eq!(state.arch.tick(), 0);
+        assert_eq!(state.run_state, RunState::Running);
+    }
+
     #[test]
     fn step_one_budget_exceeded_triggers_halt() {
         let mut state = CoreState::default();
@@ -1799,7 +2216,12 @@ mod tests {
 
         let outcome = step_one(&mut state, &mut mmio, &config);
 
-        assert!(matches!(outcome, StepOutcome::HaltedForTick));
+        assert_eq!(
+            outcome,
+            StepOutcome::HaltedForTick {
+                reason: HaltReason::BudgetExhausted
+            }
+        );
         assert_eq!(state.arch.tick(), 640);
         assert_eq!(state.run_state, RunState::HaltedForTick);
     }
@@ -1938,6 +2360,116 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn step_one_records_retired_pcs_in_history() {
+        let config = CoreConfig {
+            pc_history_depth: 2,
+            ..CoreConfig::default()
+        };
+        let mut state = CoreState::with_config(&config);
+        // NOP, NOP, NOP, then an illegal word that never retires.
+        state.memory[0x0006] = 0xB0;
+
+        struct NoMmio;
+        impl MmioBus for NoMmio {
+            fn read16(&mut self, _addr: u16) -> Result<u16, crate::api::MmioError> {
+                Err(crate::api::MmioError::ReadFailed)
+            }
+            fn write16(
+                &mut self,
+                _addr: u16,
+                _value: u16,
+            ) -> Result<crate::api::MmioWriteResult, crate::api::MmioError> {
+                Err(crate::api::MmioError::WriteFailed)
+            }
+        }
+
+        let mut mmio = NoMmio;
+        for _ in 0..4 {
+            step_one(&mut state, &mut mmio, &config);
+        }
+
+        assert!(matches!(state.run_state, RunState::FaultLatched(_)));
+        assert_eq!(state.pc_history.pcs(), vec![0x0002, 0x0004]);
+    }
+
+    #[test]
+    fn step_one_logs_stores_and_dispatch_frames() {
+        let config = CoreConfig {
+            memory_write_log_depth: 8,
+            ..CoreConfig::default()
+        };
+        let mut state = CoreState::with_config(&config);
+        state.memory[0x0100..0x010C].copy_from_slice(&[
+            0x14, 0x05, 0x40, 0x00, // MOV R2, #0x4000
+            0x12, 0x05, 0x12, 0x34, // MOV R1, #0x1234
+            0x32, 0x81, // STORE R1, [R2]
+            0x00, 0x20, // SWI
+        ]);
+        state.memory[0x0008..0x000A].copy_from_slice(&[0x02, 0x00]);
+        state.arch.set_pc(0x0100);
+        state.arch.set_sp(0x8000);
+        let mut mmio = CompositeMmio::new();
+
+        step_one(&mut state, &mut mmio, &config);
+        step_one(&mut state, &mut mmio, &config);
+        assert!(state.write_log.writes().is_empty());
+
+        step_one(&mut state, &mut mmio, &config);
+        let store = MemoryWrite {
+            address: 0x4000,
+            value: 0x1234,
+        };
+        assert_eq!(state.write_log.writes(), [store]);
+
+        state.write_log.clear();
+        let outcome = step_one(&mut state, &mut mmio, &config);
+        assert!(matches!(outcome, StepOutcome::TrapDispatch { .. }));
+        let frame: Vec<u16> = state
+            .write_log
+            .writes()
+            .iter()
+            .map(|write| write.address)
+            .collect();
+        assert_eq!(frame, [0x7FFE, 0x7FFC, 0x7FFA]);
+        assert_eq!(state.write_log.writes()[0].value, 0x010C);
+    }
+
+    #[test]
+    fn unmapped_mmio_reads_follow_the_open_bus_policy() {
+        let program = [
+            0x14, 0x05, 0xE2, 0x00, // MOV R2, #0xE200
+            0x12, 0x05, 0xA5, 0x5A, // MOV R1, #0xA55A
+            0x32, 0x81, // STORE R1, [R2]
+            0x86, 0x81, // IN R3, [R2]
+        ];
+        let cases = [
+            (OpenBus::Zero, 0x0000),
+            (OpenBus::Ones, 0xFFFF),
+            (OpenBus::LastValue, 0xA55A),
+        ];
+        for (open_bus, expected) in cases {
+            let config = CoreConfig {
+                open_bus,
+                ..CoreConfig::default()
+            };
+            let mut state = CoreState::with_config(&config);
+            state.memory[..program.len()].copy_from_slice(&program);
+            let mut mmio = CompositeMmio::new();
+
+            for _ in 0..4 {
+                step_one(&mut state, &mut mmio, &config);
+            }
+
+            assert_eq!(
+                state.arch.gpr(GeneralRegister::R3),
+                expected,
+                "{open_bus:?}"
+            );
+            assert_eq!(state.mmio_bus_latch, 0xA55A);
+        }
+    }
+
     #[test]
     fn step_one_fault_latched_returns_fault_immediately() {
         let mut state = CoreState {
@@ -2042,6 +2574,7 @@ mod tests {
             StepOutcome::EventDispatch { event_id: 0x42 }
         ));
         assert_eq!(state.arch.pc(), 0x0030);
+        assert!(matches!(state.run_state, RunState::HandlerContext));
     }
 
     #[test]
@@ -2075,6 +2608,129 @@ mod tests {
         assert_eq!(state.event_queue.len, 1);
     }
 
+    #[test]
+    fn mrs_reads_special_registers_without_touching_flags() {
+        let mut state = CoreState::default();
+        // MRS R1..R6, FLAGS/TICK/CAP/CAUSE/EVP/SP
+        for select in 0u16..6 {
+            let word = 0x1008 | ((select + 1) << 9) | (select << 6);
+            let addr = usize::from(select) * 2;
+            state.memory[addr..addr + 2].copy_from_slice(&word.to_be_bytes());
+        }
+        state.arch.set_flags(0x13);
+        state.arch.set_cause(0x0042);
+        state.arch.set_sp(0x8000);
+
+        struct NoMmio;
+        impl MmioBus for NoMmio {
+            fn read16(&mut self, _addr: u16) -> Result<u16, crate::api::MmioError> {
+                Err(crate::api::MmioError::ReadFailed)
+            }
+            fn write16(
+                &mut self,
+                _addr: u16,
+                _value: u16,
+            ) -> Result<crate::api::MmioWriteResult, crate::api::MmioError> {
+                Err(crate::api::MmioError::WriteFailed)
+            }
+        }
+
+        let mut mmio = NoMmio;
+        let config = CoreConfig::default();
+        for _ in 0..6 {
+            let outcome = step_one(&mut state, &mut mmio, &config);
+            assert!(matches!(outcome, StepOutcome::Retired { cycles: 1 }));
+        }
+
+        assert_eq!(state.arch.gpr(GeneralRegister::R1), 0x13);
+        assert_eq!(state.arch.gpr(GeneralRegister::R2), 1);
+        assert_eq!(state.arch.gpr(GeneralRegister::R3), 0x000F);
+        assert_eq!(state.arch.gpr(GeneralRegister::R4), 0x0042);
+        assert_eq!(state.arch.gpr(GeneralRegister::R5), 0);
+        assert_eq!(state.arch.gpr(GeneralRegister::R6), 0x8000);
+        assert_eq!(state.arch.flags(), 0x13);
+    }
+
+    #[test]
+    fn mrs_requires_cap_trc() {
+        let mut state = CoreState::default();
+        // MRS R0, CAUSE
+        state.memory[0x0000] = 0x10;
+        state.memory[0x0001] = 0xC8;
+        state.arch.set_cap_core_owned(0x0007);
+
+        struct NoMmio;
+        impl MmioBus for NoMmio {
+            fn read16(&mut self, _addr: u16) -> Result<u16, crate::api::MmioError> {
+                Err(crate::api::MmioError::ReadFailed)
+            }
+            fn write16(
+                &mut self,
+                _addr: u16,
+                _value: u16,
+            ) -> Result<crate::api::MmioWriteResult, crate::api::MmioError> {
+                Err(crate::api::MmioError::WriteFailed)
+            }
+        }
+
+        let outcome = step_one(&mut state, &mut NoMmio, &CoreConfig::default());
+
+        assert_eq!(
+            outcome,
+            StepOutcome::Fault {
+                cause: crate::fault::FaultCode::CapabilityViolation
+            }
+        );
+    }
+
+    #[test]
+    fn event_queue_overflow_faults_at_next_dispatch_check() {
+        let mut state = CoreState::default();
+        for event_id in 1..=4 {
+            state.enqueue_event(event_id).expect("enqueue event");
+        }
+        assert!(state.enqueue_event(5).is_err());
+        assert_eq!(state.arch.evp(), EVP_OVERFLOW);
+        state.memory[0x000C] = 0x00;
+        state.memory[0x000D] = 0x40;
+
+        struct NoMmio;
+        impl MmioBus for NoMmio {
+            fn read16(&mut self, _addr: u16) -> Result<u16, crate::api::MmioError> {
+                Err(crate::api::MmioError::ReadFailed)
+            }
+            fn write16(
+                &mut self,
+                _addr: u16,
+                _value: u16,
+            ) -> Result<crate::api::MmioWriteResult, crate::api::MmioError> {
+                Err(crate::api::MmioError::WriteFailed)
+            }
+        }
+
+        let mut mmio = NoMmio;
+        let config = CoreConfig::default();
+
+        let outcome = step_one(&mut state, &mut mmio, &config);
+        assert!(matches!(outcome, StepOutcome::Retired { .. }));
+        assert_eq!(state.arch.evp(), EVP_OVERFLOW);
+
+        state.arch.set_flags(0x10);
+        let outcome = step_one(&mut state, &mut mmio, &config);
+
+        assert_eq!(
+            outcome,
+            StepOutcome::Fault {
+                cause: crate::fault::FaultCode::EventQueueOverflow
+            }
+        );
+        assert_eq!(state.arch.pc(), 0x0040);
+        assert_eq!(state.arch.gpr(GeneralRegister::R0), 0x07);
+        assert_eq!(state.arch.evp(), 0);
+        assert_eq!(state.event_queue.len, 4);
+        assert!(matches!(state.run_state, RunState::HandlerContext));
+    }
+
     #[test]
     fn trap_dispatch_sets_handler_context() {
         let mut state = CoreState::default();
@@ -2372,7 +3028,12 @@ mod tests {
         let result = run_one(&mut state, &mut mmio, &config, RunBoundary::TickBoundary);
 
         assert!(result.steps >= 1);
-        assert!(matches!(result.final_step, StepOutcome::HaltedForTick));
+        assert!(matches!(
+            result.final_step,
+            StepOutcome::HaltedForTick {
+                reason: HaltReason::BudgetExhausted
+            }
+        ));
     }
 
     #[test]
@@ -2404,6 +3065,76 @@ mod tests {
         assert!(matches!(result.final_step, StepOutcome::Fault { .. }));
     }
 
+    #[test]
+    fn run_one_stops_after_step_count() {
+        // Zeroed memory decodes as NOPs.
+        let mut state = CoreState::default();
+        struct NoMmio;
+        impl MmioBus for NoMmio {
+            fn read16(&mut self, _addr: u16) -> Result<u16, crate::api::MmioError> {
+                Err(crate::api::MmioError::ReadFailed)
+            }
+            fn write16(
+                &mut self,
+                _addr: u16,
+                _value: u16,
+            ) -> Result<crate::api::MmioWriteResult, crate::api::MmioError> {
+                Err(crate::api::MmioError::WriteFailed)
+            }
+        }
+
+        let mut mmio = NoMmio;
+        let config = CoreConfig::default();
+
+        let result = run_one(&mut state, &mut mmio, &config, RunBoundary::StepCount(5));
+
+        assert_eq!(result.steps, 5);
+        assert_eq!(state.arch.pc(), 10);
+        assert!(result.breakpoint.is_none());
+
+        let result = run_one(&mut state, &mut mmio, &config, RunBoundary::StepCount(0));
+        assert_eq!(result.steps, 1);
+    }
+
+    #[test]
+    fn run_one_stops_at_breakpoint() {
+        let mut state = CoreState::default();
+        state.breakpoints.set(0x0000, None);
+        state.breakpoints.set(0x0006, None);
+        struct NoMmio;
+        impl MmioBus for NoMmio {
+            fn read16(&mut self, _addr: u16) -> Result<u16, crate::api::MmioError> {
+                Err(crate::api::MmioError::ReadFailed)
+            }
+            fn write16(
+                &mut self,
+                _addr: u16,
+                _value: u16,
+            ) -> Result<crate::api::MmioWriteResult, crate::api::MmioError> {
+                Err(crate::api::MmioError::WriteFailed)
+            }
+        }
+
+        let mut mmio = NoMmio;
+        let config = CoreConfig::default();
+
+        let result = run_one(&mut state, &mut mmio, &config, RunBoundary::Breakpoint);
+
+        assert_eq!(result.steps, 3);
+        assert_eq!(state.arch.pc(), 0x0006);
+        assert_eq!(result.breakpoint.map(|hit| hit.address), Some(0x0006));
+
+        state.breakpoints.clear();
+        let result = run_one(&mut state, &mut mmio, &config, RunBoundary::Breakpoint);
+        assert!(matches!(
+            result.final_step,
+            StepOutcome::HaltedForTick {
+                reason: HaltReason::BudgetExhausted
+            }
+        ));
+        assert!(result.breakpoint.is_none());
+    }
+
     #[test]
     fn run_one_with_trace_collects_events() {
         let mut state = CoreState::default();
@@ -2548,4 +3279,150 @@ mod tests {
         assert_eq!(state.memory[0x4000], 0x12);
         assert_eq!(state.memory[0x4001], 0x34);
     }
+
+    struct ZeroMmio;
+    impl MmioBus for ZeroMmio {
+        fn read16(&mut self, _addr: u16) -> Result<u16, crate::api::MmioError> {
+            Ok(0)
+        }
+        fn write16(
+            &mut self,
+            _addr: u16,
+            _value: u16,
+        ) -> Result<crate::api::MmioWriteResult, crate::api::MmioError> {
+            Ok(crate::api::MmioWriteResult::Applied)
+        }
+    }
+
+    /// ROM is read/execute, RAM read/write and the MPU window read/write.
+    /// The program at 0x0010 stores to RAM, reads the MPU ID and stores to
+    /// ROM; the fault handler at 0x0200 stores to ROM too.
+    fn mpu_state(cap: u16) -> CoreState {
+        let mut state = CoreState::default();
+        state.arch.set_cap_core_owned(cap);
+        state.arch.set_pc(0x0010);
+        state.arch.set_sp(0xDE00);
+        state.arch.set_gpr(GeneralRegister::R1, 0xABCD);
+        let words: [(u16, &[u16]); 3] = [
+            (0x000C, &[0x0200]),
+            (0x0010, &[0x3205, 0x4000, 0x2405, 0xE140, 0x3205, 0x0100]),
+            (0x0200, &[0x3205, 0x0100]),
+        ];
+        for (start, words) in words {
+            for (index, word) in words.iter().enumerate() {
+                let addr = start + 2 * index as u16;
+                write_u16_be(&mut state.memory, addr, *word).unwrap();
+            }
+        }
+        let rx = crate::MPU_ATTR_ENABLE | crate::MPU_ATTR_READ | crate::MPU_ATTR_EXECUTE;
+        let rw = crate::MPU_ATTR_ENABLE | crate::MPU_ATTR_READ | crate::MPU_ATTR_WRITE;
+        for (addr, value) in [
+            (0xE144, 0x0000),
+            (0xE145, 0x3FFF),
+            (0xE146, rx),
+            (0xE147, 0x4000),
+            (0xE148, 0xDFFF),
+            (0xE149, rw),
+            (0xE14A, MPU_BASE),
+            (0xE14B, MPU_END),
+            (0xE14C, rw),
+            (crate::MPU_CTRL, crate::MPU_CTRL_ENABLE),
+        ] {
+            state.mpu.set_register(addr, value);
+        }
+        state
+    }
+
+    #[test]
+    fn mpu_violation_dispatches_before_the_store_lands() {
+        let cap = crate::CAP_AUTHORITY_DEFAULT_MASK | 1 << CAP_MPU_BIT;
+        let mut state = mpu_state(cap);
+        let config = CoreConfig::default();
+        let mut mmio = ZeroMmio;
+
+        assert!(matches!(
+            step_one(&mut state, &mut mmio, &config),
+            StepOutcome::Retired { .. }
+        ));
+        assert_eq!(state.memory[0x4000], 0xAB);
+        step_one(&mut state, &mut mmio, &config);
+        assert_eq!(state.arch.gpr(GeneralRegister::R2), crate::MPU_ID);
+
+        let outcome = step_one(&mut state, &mut mmio, &config);
+        assert_eq!(
+            outcome,
+            StepOutcome::Fault {
+                cause: crate::fault::FaultCode::MpuViolation
+            }
+        );
+        assert_eq!(state.memory[0x0100], 0);
+        assert_eq!(state.mpu.fault_addr(), 0x0100);
+        assert_eq!(state.run_state, RunState::HandlerContext);
+        assert_eq!(state.arch.pc(), 0x0200);
+        let sp = state.arch.sp();
+        assert_eq!(read_u16_be(&state.memory, sp.wrapping_add(4)), Ok(0x0018));
+
+        // Handlers bypass the MPU.
+        assert!(matches!(
+            step_one(&mut state, &mut mmio, &config),
+            StepOutcome::Retired { .. }
+        ));
+        assert_eq!(state.memory[0x0100], 0xAB);
+    }
+
+    #[test]
+    fn mpu_denies_fetch_outside_executable_regions() {
+        let cap = crate::CAP_AUTHORITY_DEFAULT_MASK | 1 << CAP_MPU_BIT;
+        let mut state = mpu_state(cap);
+        state.arch.set_pc(0x4000);
+
+        let outcome = step_one(&mut state, &mut ZeroMmio, &CoreConfig::default());
+        assert_eq!(
+            outcome,
+            StepOutcome::Fault {
+                cause: crate::fault::FaultCode::MpuViolation
+            }
+        );
+        assert_eq!(state.mpu.fault_addr(), 0x4000);
+        assert_eq!(state.arch.pc(), 0x0200);
+    }
+
+    #[test]
+    fn mpu_regions_survive_a_snapshot_round_trip() {
+        let cap = crate::CAP_AUTHORITY_DEFAULT_MASK | 1 << CAP_MPU_BIT;
+        let mut state = mpu_state(cap);
+        state.arch.set_pc(0x0018);
+
+        let bytes =
+            crate::CoreSnapshot::from_core_state(crate::LATEST_SNAPSHOT_VERSION, &state).to_bytes();
+        let mut restored = crate::CoreSnapshot::from_bytes(&bytes)
+            .unwrap()
+            .try_into_core_state()
+            .unwrap();
+        assert_eq!(restored.mpu, state.mpu);
+
+        let outcome = step_one(&mut restored, &mut ZeroMmio, &CoreConfig::default());
+        assert_eq!(
+            outcome,
+            StepOutcome::Fault {
+                cause: crate::fault::FaultCode::MpuViolation
+            }
+        );
+        assert_eq!(restored.memory[0x0100], 0);
+        assert_eq!(restored.mpu.fault_addr(), 0x0100);
+    }
+
+    #[test]
+    fn mpu_is_inert_without_its_capability_bit() {
+        let mut state = mpu_state(crate::CAP_AUTHORITY_DEFAULT_MASK);
+        let config = CoreConfig::default();
+        for _ in 0..3 {
+            assert!(matches!(
+                step_one(&mut state, &mut ZeroMmio, &config),
+                StepOutcome::Retired { .. }
+            ));
+        }
+        assert_eq!(state.arch.gpr(GeneralRegister::R2), 0);
+        assert_eq!(state.memory[0x0100], 0xAB);
+    }
 }
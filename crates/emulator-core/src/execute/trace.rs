@@ -0,0 +1,274 @@
+//! Traced stepping.
+//!
+//! [`step_one_with_trace`] runs one instruction exactly as [`step_one`]
+//! does and reports it to a [`TraceSink`]: the fetch, every MMIO access the
+//! instruction made on the host bus, the RAM word a `LOAD`, `STORE`,
+//! `PUSH`, `POP`, `CALL` or `RET` touched, then its retirement or fault,
+//! and [`TraceEvent::TickEnded`] when it ends the tick. MMIO accesses are
+//! recorded as the bus sees them; RAM accesses are worked out from the
+//! decoded instruction before it runs and reported with the word in memory
+//! afterwards, so tracing never changes what the core does.
+
+use alloc::vec::Vec;
+
+use super::batch::{run_tick_with, run_ticks_with};
+use super::{compute_effective_address, fetch_and_decode, step_one};
+use crate::decoder::AddressingMode;
+use crate::encoding::OpcodeEncoding;
+use crate::{
+    decode_memory_region, read_u16_be, CoreConfig, CoreState, MemoryRegion, MmioBus, MmioError,
+    MmioWriteResult, StepOutcome, TickBatch, TraceEvent, TraceSink,
+};
+
+/// Runs one instruction like [`step_one`], reporting it to `sink`.
+pub fn step_one_with_trace(
+    state: &mut CoreState,
+    mmio: &mut dyn MmioBus,
+    config: &CoreConfig,
+    sink: &mut dyn TraceSink,
+) -> StepOutcome {
+    let pc = state.arch.pc();
+    let raw_word = read_u16_be(&state.memory, pc).unwrap_or_default();
+    sink.on_event(TraceEvent::InstructionStart { pc, raw_word });
+
+    let ram_access = ram_access(state, config, pc);
+    let tick_before = state.arch.tick();
+    let mut bus = RecordingBus {
+        inner: mmio,
+        accesses: Vec::new(),
+    };
+    let outcome = step_one(state, &mut bus, config);
+    for event in bus.accesses {
+        sink.on_event(event);
+    }
+
+    if !matches!(outcome, StepOutcome::Fault { .. }) {
+        if let Some((addr, is_write)) = ram_access {
+            sink.on_event(TraceEvent::MemoryAccess {
+                addr,
+                value: read_u16_be(&state.memory, addr).unwrap_or_default(),
+                is_write,
+                is_mmio: false,
+            });
+        }
+    }
+
+    match outcome {
+        StepOutcome::Retired { cycles } => {
+            sink.on_event(TraceEvent::InstructionRetired { pc, cycles });
+        }
+        StepOutcome::HaltedForTick { .. } => {
+            let cycles = state.arch.tick();
+            sink.on_event(TraceEvent::InstructionRetired {
+                pc,
+                cycles: cycles.wrapping_sub(tick_before),
+            });
+            sink.on_event(TraceEvent::TickEnded { cycles });
+        }
+        StepOutcome::Fault { cause } => {
+            sink.on_event(TraceEvent::FaultRaised { cause, pc });
+        }
+        StepOutcome::TrapDispatch { .. } | StepOutcome::EventDispatch { .. } => {}
+    }
+    outcome
+}
+
+/// [`run_ticks_with_budget`](crate::run_ticks_with_budget) with every
+/// instruction reported to `sink` by [`step_one_with_trace`].
+pub fn run_ticks_with_trace(
+    state: &mut CoreState,
+    mmio: &mut dyn MmioBus,
+    config: &CoreConfig,
+    n_ticks: u32,
+    sink: &mut dyn TraceSink,
+) -> TickBatch {
+    run_ticks_with(state, mmio, config, n_ticks, |state, mmio, config| {
+        run_tick_with(state, mmio, config, |state, mmio, config| {
+            step_one_with_trace(state, mmio, config, sink)
+        })
+    })
+}
+
+/// Returns the RAM word the instruction at `pc` will read or write, as
+/// `(address, is_write)`. Accesses to MMIO space go through the bus and are
+/// recorded there instead.
+fn ram_access(state: &CoreState, config: &CoreConfig, pc: u16) -> Option<(u16, bool)> {
+    let op = state.memory[usize::from(pc)] >> 4;
+    if config.experimental_opcodes.handler(op).is_some() {
+        return None;
+    }
+    let instr = fetch_and_decode(pc, &state.memory).ok()?;
+    let sp = state.arch.sp();
+    let (addr, is_write) = match instr.encoding {
+        OpcodeEncoding::Load => (compute_effective_address(&instr, state)?, false),
+        OpcodeEncoding::Store => (compute_effective_address(&instr, state)?, true),
+        OpcodeEncoding::Push => (sp.wrapping_sub(2), true),
+        OpcodeEncoding::Pop => (sp, false),
+        OpcodeEncoding::CallOrRet
+            if matches!(instr.addressing_mode, Some(AddressingMode::DirectRegister)) =>
+        {
+            (sp, false)
+        }
+        OpcodeEncoding::CallOrRet => (sp.wrapping_sub(2), true),
+        _ => return None,
+    };
+    (decode_memory_region(addr) != MemoryRegion::Mmio).then_some((addr, is_write))
+}
+
+/// Forwards to the host bus, recording each register access.
+struct RecordingBus<'a> {
+    inner: &'a mut dyn MmioBus,
+    accesses: Vec<TraceEvent>,
+}
+
+impl RecordingBus<'_> {
+    fn record(&mut self, addr: u16, value: u16, is_write: bool) {
+        self.accesses.push(TraceEvent::MemoryAccess {
+            addr,
+            value,
+            is_write,
+            is_mmio: true,
+        });
+    }
+}
+
+impl MmioBus for RecordingBus<'_> {
+    fn read16(&mut self, addr: u16) -> Result<u16, MmioError> {
+        let value = self.inner.read16(addr)?;
+        self.record(addr, value, false);
+        Ok(value)
+    }
+
+    fn write16(&mut self, addr: u16, value: u16) -> Result<MmioWriteResult, MmioError> {
+        let result = self.inner.write16(addr, value)?;
+        self.record(addr, value, true);
+        Ok(result)
+    }
+
+    fn on_tick(&mut self) {
+        self.inner.on_tick();
+    }
+
+    fn advance_ticks(&mut self, ticks: u32) {
+        self.inner.advance_ticks(ticks);
+    }
+
+    fn on_tick_memory(&mut self, state: &mut CoreState) {
+        self.inner.on_tick_memory(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_ticks_with_trace, step_one_with_trace};
+    use crate::{
+        CompositeMmio, CoreConfig, CoreState, DebugConsole, HaltReason, SimpleTraceSink,
+        StepOutcome, TraceEvent,
+    };
+
+    #[test]
+    fn reports_ram_and_mmio_accesses_in_order() {
+        let mut state = CoreState::default();
+        state.memory[..16].copy_from_slice(&[
+            0x10, 0x05, 0xE1, 0x32, // MOV R0, #0xE132
+            0x14, 0x05, 0x40, 0x00, // MOV R2, #0x4000
+            0x22, 0x81, // LOAD R1, [R2]
+            0x32, 0x01, // STORE R1, [R0]
+            0x00, 0x10, // HALT
+            0x00, 0x00,
+        ]);
+        state.memory[0x4000..0x4002].copy_from_slice(&[0x00, 0x41]);
+        let mut mmio = CompositeMmio::new().with_console(DebugConsole::new());
+        let config = CoreConfig::default();
+        let mut trace = SimpleTraceSink::new();
+
+        for _ in 0..4 {
+            step_one_with_trace(&mut state, &mut mmio, &config, &mut trace);
+        }
+        let outcome = step_one_with_trace(&mut state, &mut mmio, &config, &mut trace);
+
+        assert_eq!(
+            outcome,
+            StepOutcome::HaltedForTick {
+                reason: HaltReason::Instruction
+            }
+        );
+        assert_eq!(
+            trace.events()[4..],
+            [
+                TraceEvent::InstructionStart {
+                    pc: 0x0008,
+                    raw_word: 0x2281
+                },
+                TraceEvent::MemoryAccess {
+                    addr: 0x4000,
+                    value: 0x0041,
+                    is_write: false,
+                    is_mmio: false
+                },
+                TraceEvent::InstructionRetired {
+                    pc: 0x0008,
+                    cycles: 2
+                },
+                TraceEvent::InstructionStart {
+                    pc: 0x000A,
+                    raw_word: 0x3201
+                },
+                TraceEvent::MemoryAccess {
+                    addr: 0xE132,
+                    value: 0x0041,
+                    is_write: true,
+                    is_mmio: true
+                },
+                TraceEvent::InstructionRetired {
+                    pc: 0x000A,
+                    cycles: 2
+                },
+                TraceEvent::InstructionStart {
+                    pc: 0x000C,
+                    raw_word: 0x0010
+                },
+                TraceEvent::InstructionRetired {
+                    pc: 0x000C,
+                    cycles: 1
+                },
+                TraceEvent::TickEnded { cycles: 7 },
+            ]
+        );
+        assert_eq!(mmio.console().map(DebugConsole::text).as_deref(), Some("A"));
+    }
+
+    #[test]
+    fn traced_ticks_match_untraced_ticks() {
+        let program = [
+            0x10, 0x05, 0x40, 0x00, // MOV R0, #0x4000
+            0x30, 0x01, // loop: STORE R0, [R0]
+            0x40, 0x05, 0x00, 0x02, // ADD R0, R0, #2
+            0x60, 0x35, 0xFF, 0xF6, // JMP #loop
+        ];
+        let config = CoreConfig::default();
+        let mut plain = CoreState::default();
+        plain.memory[..program.len()].copy_from_slice(&program);
+        let mut traced = plain.clone();
+
+        let expected =
+            crate::run_ticks_with_budget(&mut plain, &mut CompositeMmio::new(), &config, 3);
+        let mut trace = SimpleTraceSink::new();
+        let batch = run_ticks_with_trace(
+            &mut traced,
+            &mut CompositeMmio::new(),
+            &config,
+            3,
+            &mut trace,
+        );
+
+        assert_eq!(batch, expected);
+        assert_eq!(traced.memory, plain.memory);
+        let ticks: Vec<_> = trace
+            .events()
+            .iter()
+            .filter(|event| matches!(event, TraceEvent::TickEnded { .. }))
+            .collect();
+        assert_eq!(ticks.len(), 3);
+    }
+}
@@ -0,0 +1,291 @@
+//! Host-provided handlers for reserved primary opcodes.
+//!
+//! Primary opcodes `0xB..=0xF` are reserved and normally fault with
+//! [`FaultCode::IllegalEncoding`]. A host prototyping an ISA extension
+//! registers an [`OpcodeHandler`] for one of them in
+//! [`CoreConfig::experimental_opcodes`](crate::CoreConfig::experimental_opcodes);
+//! the core then hands every instruction with that primary opcode to the
+//! handler instead of decoding it.
+//!
+//! Extension instructions are always one word and the handler interprets
+//! the low 12 bits however it likes. When the handler runs, `PC` already
+//! points at the next instruction, so a handler that branches just sets it.
+//! The cycles it reports are charged to `TICK` like any other instruction's;
+//! a fault it reports is dispatched like any execution fault, with `PC`
+//! restored to the extension instruction. Capability checks do not
+//! apply to extension instructions, but the MPU checks their fetch like
+//! any other.
+
+use alloc::{collections::BTreeMap, sync::Arc};
+use core::fmt;
+
+use thiserror::Error;
+
+use crate::{is_reserved_primary_opcode, CoreState, FaultCode, MmioBus};
+
+/// Executes the instructions of one experimental primary opcode.
+///
+/// Handlers must be deterministic: the same word, state and MMIO responses
+/// must always produce the same result, or replays and snapshots diverge.
+pub trait OpcodeHandler: Send + Sync {
+    /// Executes `word` against `state`, returning the cycles it took.
+    ///
+    /// A cost of 0 is charged as 1 cycle so a loop of extension
+    /// instructions still exhausts the tick budget.
+    ///
+    /// # Errors
+    ///
+    /// Returns the fault to raise. Changes already made to `state` are kept,
+    /// so handlers should validate operands before writing anything.
+    fn execute(
+        &self,
+        word: u16,
+        state: &mut CoreState,
+        mmio: &mut dyn MmioBus,
+    ) -> Result<u16, FaultCode>;
+}
+
+/// Failures registering an experimental opcode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+pub enum ExtensionError {
+    /// The primary opcode is assigned by the base ISA.
+    #[error("primary opcode 0x{0:X} is not reserved; only 0xB-0xF can be extended")]
+    NotReserved(u8),
+    /// A handler is already registered for the primary opcode.
+    #[error("primary opcode 0x{0:X} already has a handler")]
+    AlreadyRegistered(u8),
+}
+
+/// Handlers for reserved primary opcodes, keyed by opcode.
+///
+/// Cloning shares the handlers. Two registries are equal when they map the
+/// same opcodes to the same handler instances.
+#[derive(Clone, Default)]
+pub struct ExperimentalOpcodes {
+    handlers: BTreeMap<u8, Arc<dyn OpcodeHandler>>,
+}
+
+impl ExperimentalOpcodes {
+    /// Creates an empty registry; every reserved opcode faults.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            handlers: BTreeMap::new(),
+        }
+    }
+
+    /// Routes primary opcode `op` to `handler`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ExtensionError::NotReserved`] for an opcode outside
+    /// `0xB..=0xF` and [`ExtensionError::AlreadyRegistered`] when `op`
+    /// already has a handler.
+    pub fn register(
+        &mut self,
+        op: u8,
+        handler: impl OpcodeHandler + 'static,
+    ) -> Result<(), ExtensionError> {
+        if !is_reserved_primary_opcode(op) {
+            return Err(ExtensionError::NotReserved(op));
+        }
+        if self.handlers.contains_key(&op) {
+            return Err(ExtensionError::AlreadyRegistered(op));
+        }
+        self.handlers.insert(op, Arc::new(handler));
+        Ok(())
+    }
+
+    /// Returns whether no opcodes are registered.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// Returns the registered primary opcodes in ascending order.
+    pub fn opcodes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.handlers.keys().copied()
+    }
+
+    /// Returns the handler for primary opcode `op`, if one is registered.
+    #[must_use]
+    pub fn handler(&self, op: u8) -> Option<&dyn OpcodeHandler> {
+        self.handlers.get(&op).map(AsRef::as_ref)
+    }
+}
+
+impl fmt::Debug for ExperimentalOpcodes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.handlers.keys()).finish()
+    }
+}
+
+impl PartialEq for ExperimentalOpcodes {
+    fn eq(&self, other: &Self) -> bool {
+        self.handlers.len() == other.handlers.len()
+            && self
+                .handlers
+                .iter()
+                .zip(&other.handlers)
+                .all(|((a_op, a), (b_op, b))| a_op == b_op && Arc::ptr_eq(a, b))
+    }
+}
+
+impl Eq for ExperimentalOpcodes {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        step_one, CoreConfig, GeneralRegister, MmioError, MmioWriteResult, RunState, StepOutcome,
+        VEC_FAULT,
+    };
+
+    struct NoMmio;
+
+    impl MmioBus for NoMmio {
+        fn read16(&mut self, _addr: u16) -> Result<u16, MmioError> {
+            Ok(0)
+        }
+
+        fn write16(&mut self, _addr: u16, _value: u16) -> Result<MmioWriteResult, MmioError> {
+            Ok(MmioWriteResult::Applied)
+        }
+    }
+
+    /// `0xBdas`: R[d] = R[a] << s, 3 cycles; shift 0 faults.
+    struct ShiftLeft;
+
+    impl OpcodeHandler for ShiftLeft {
+        fn execute(
+            &self,
+            word: u16,
+            state: &mut CoreState,
+            _mmio: &mut dyn MmioBus,
+        ) -> Result<u16, FaultCode> {
+            let shift = word & 0x7;
+            if shift == 0 {
+                return Err(FaultCode::IllegalEncoding);
+            }
+            let rd = GeneralRegister::ALL[usize::from((word >> 9) & 0x7)];
+            let ra = GeneralRegister::ALL[usize::from((word >> 6) & 0x7)];
+            state.arch.set_gpr(rd, state.arch.gpr(ra) << shift);
+            Ok(3)
+        }
+    }
+
+    fn config_with_shift() -> CoreConfig {
+        let mut config = CoreConfig::default();
+        config
+            .experimental_opcodes
+            .register(0xB, ShiftLeft)
+            .unwrap();
+        config
+    }
+
+    #[test]
+    fn register_accepts_only_free_reserved_opcodes() {
+        let mut opcodes = ExperimentalOpcodes::new();
+        assert_eq!(
+            opcodes.register(0x4, ShiftLeft),
+            Err(ExtensionError::NotReserved(0x4))
+        );
+        opcodes.register(0xF, ShiftLeft).unwrap();
+        assert_eq!(
+            opcodes.register(0xF, ShiftLeft),
+            Err(ExtensionError::AlreadyRegistered(0xF))
+        );
+        assert_eq!(opcodes.opcodes().collect::<Vec<_>>(), [0xF]);
+        assert_eq!(opcodes.clone(), opcodes);
+        assert_ne!(opcodes, ExperimentalOpcodes::new());
+    }
+
+    #[test]
+    fn registered_opcode_runs_the_handler() {
+        let config = config_with_shift();
+        let mut state = CoreState::with_config(&config);
+        state.arch.set_gpr(GeneralRegister::R2, 0x0011);
+        // R1 = R2 << 4
+        state.memory[..2].copy_from_slice(&0xB284_u16.to_be_bytes());
+
+        let outcome = step_one(&mut state, &mut NoMmio, &config);
+
+        assert_eq!(outcome, StepOutcome::Retired { cycles: 3 });
+        assert_eq!(state.arch.gpr(GeneralRegister::R1), 0x0110);
+        assert_eq!(state.arch.pc(), 2);
+        assert_eq!(state.arch.tick(), 3);
+    }
+
+    #[test]
+    fn handler_faults_are_dispatched_from_the_instruction() {
+        let config = config_with_shift();
+        let mut state = CoreState::with_config(&config);
+        state.arch.set_sp(0x8000);
+        state.memory[..2].copy_from_slice(&0xB280_u16.to_be_bytes());
+        state.memory[usize::from(VEC_FAULT)..][..2].copy_from_slice(&0x0100_u16.to_be_bytes());
+
+        let outcome = step_one(&mut state, &mut NoMmio, &config);
+
+        assert_eq!(
+            outcome,
+            StepOutcome::Fault {
+                cause: FaultCode::IllegalEncoding
+            }
+        );
+        assert_eq!(state.run_state, RunState::HandlerContext);
+        assert_eq!(state.arch.pc(), 0x0100);
+        // The return address pushed first is the extension instruction.
+        assert_eq!(state.memory[0x7FFE..0x8000], [0x00, 0x00]);
+        assert_eq!(state.arch.tick(), 0);
+    }
+
+    #[test]
+    fn mpu_denies_extension_fetches_outside_executable_regions() {
+        let config = config_with_shift();
+        let mut state = CoreState::with_config(&config);
+        state
+            .arch
+            .set_cap_core_owned(state.arch.cap() | 1 << crate::CAP_MPU_BIT);
+        state.arch.set_sp(0x8000);
+        state.arch.set_pc(0x4000);
+        state.arch.set_gpr(GeneralRegister::R2, 0x0011);
+        state.memory[0x4000..0x4002].copy_from_slice(&0xB284_u16.to_be_bytes());
+        state.memory[usize::from(VEC_FAULT)..][..2].copy_from_slice(&0x0100_u16.to_be_bytes());
+        // Only ROM is executable.
+        state.mpu.set_register(crate::MPU_REGION_BASE + 1, 0x3FFF);
+        state.mpu.set_register(
+            crate::MPU_REGION_BASE + 2,
+            crate::MPU_ATTR_ENABLE | crate::MPU_ATTR_EXECUTE,
+        );
+        state
+            .mpu
+            .set_register(crate::MPU_CTRL, crate::MPU_CTRL_ENABLE);
+
+        let outcome = step_one(&mut state, &mut NoMmio, &config);
+
+        assert_eq!(
+            outcome,
+            StepOutcome::Fault {
+                cause: FaultCode::MpuViolation
+            }
+        );
+        assert_eq!(state.arch.gpr(GeneralRegister::R1), 0);
+        assert_eq!(state.mpu.fault_addr(), 0x4000);
+        assert_eq!(state.run_state, RunState::HandlerContext);
+        assert_eq!(state.arch.pc(), 0x0100);
+    }
+
+    #[test]
+    fn unregistered_reserved_opcodes_still_fault() {
+        let config = config_with_shift();
+        let mut state = CoreState::with_config(&config);
+        state.memory[..2].copy_from_slice(&0xC284_u16.to_be_bytes());
+
+        assert_eq!(
+            step_one(&mut state, &mut NoMmio, &config),
+            StepOutcome::Fault {
+                cause: FaultCode::IllegalEncoding
+            }
+        );
+    }
+}
@@ -61,6 +61,9 @@ pub enum FaultCode {
     /// A second fault happened while handling a fault.
     #[error("fault occurred while already handling a fault")]
     DoubleFault = 0x0C,
+    /// Access denied by the memory protection unit.
+    #[error("access denied by memory protection unit")]
+    MpuViolation = 0x0D,
 }
 
 impl FaultCode {
@@ -86,6 +89,7 @@ impl FaultCode {
             0x0A => Some(Self::BudgetOverrun),
             0x0B => Some(Self::InvalidFaultVector),
             0x0C => Some(Self::DoubleFault),
+            0x0D => Some(Self::MpuViolation),
             _ => None,
         }
     }
@@ -95,9 +99,10 @@ impl FaultCode {
     pub const fn class(self) -> FaultClass {
         match self {
             Self::IllegalEncoding => FaultClass::Decode,
-            Self::NonExecutableFetch | Self::IllegalMemoryAccess | Self::UnalignedDataAccess => {
-                FaultClass::Memory
-            }
+            Self::NonExecutableFetch
+            | Self::IllegalMemoryAccess
+            | Self::UnalignedDataAccess
+            | Self::MpuViolation => FaultClass::Memory,
             Self::MmioWidthViolation | Self::MmioAlignmentViolation => FaultClass::Mmio,
             Self::EventQueueOverflow => FaultClass::Event,
             Self::HandlerContextViolation | Self::InvalidFaultVector | Self::DoubleFault => {
@@ -141,7 +146,7 @@ mod tests {
 
     #[test]
     fn stable_code_roundtrip_is_bijective_for_defined_values() {
-        for code in 0x01u8..=0x0C {
+        for code in 0x01u8..=0x0D {
             let fault = FaultCode::from_u8(code).expect("defined taxonomy code");
             assert_eq!(fault.as_u8(), code);
         }
@@ -164,6 +169,7 @@ mod tests {
     fn class_mapping_matches_fault_taxonomy() {
         assert_eq!(FaultCode::IllegalEncoding.class(), FaultClass::Decode);
         assert_eq!(FaultCode::IllegalMemoryAccess.class(), FaultClass::Memory);
+        assert_eq!(FaultCode::MpuViolation.class(), FaultClass::Memory);
         assert_eq!(FaultCode::MmioWidthViolation.class(), FaultClass::Mmio);
         assert_eq!(FaultCode::EventQueueOverflow.class(), FaultClass::Event);
         assert_eq!(
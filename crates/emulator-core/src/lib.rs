@@ -1,4 +1,7 @@
 #![doc = include_str!("../README.md")]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 /// Memory model primitives and fixed region map.
 pub mod memory;
@@ -10,39 +13,42 @@ pub use memory::{
     ROM_END, ROM_START, WORD_ACCESS_BYTES,
 };
 
-/// Diagnostics window (DIAG) model and provider trait.
+/// Diagnostics window (DIAG) model, provider trait and tick usage history.
 pub mod diag;
 pub use diag::{
-    DiagCoreFields, DiagProvider, StaticDiagProvider, DIAG_DENIED_WRITE_COUNT_OFFSET,
-    DIAG_FAULT_COUNT_BUDGET_OFFSET, DIAG_FAULT_COUNT_CAPABILITY_OFFSET,
-    DIAG_FAULT_COUNT_DECODE_OFFSET, DIAG_FAULT_COUNT_DISPATCH_OFFSET,
-    DIAG_FAULT_COUNT_EVENT_OFFSET, DIAG_FAULT_COUNT_MEMORY_OFFSET, DIAG_FAULT_COUNT_MMIO_OFFSET,
-    DIAG_INSTRUCTION_COUNT_OFFSET, DIAG_LAST_FAULT_CODE_OFFSET, DIAG_LAST_FAULT_PC_OFFSET,
-    DIAG_LAST_FAULT_TICK_OFFSET,
+    DiagCoreFields, DiagProvider, StaticDiagProvider, TickUsageHistory,
+    DIAG_DENIED_WRITE_COUNT_OFFSET, DIAG_FAULT_COUNT_BUDGET_OFFSET,
+    DIAG_FAULT_COUNT_CAPABILITY_OFFSET, DIAG_FAULT_COUNT_DECODE_OFFSET,
+    DIAG_FAULT_COUNT_DISPATCH_OFFSET, DIAG_FAULT_COUNT_EVENT_OFFSET,
+    DIAG_FAULT_COUNT_MEMORY_OFFSET, DIAG_FAULT_COUNT_MMIO_OFFSET, DIAG_INSTRUCTION_COUNT_OFFSET,
+    DIAG_LAST_FAULT_CODE_OFFSET, DIAG_LAST_FAULT_PC_OFFSET, DIAG_LAST_FAULT_TICK_OFFSET,
 };
 
 /// Public host-facing API contract and integration types.
 pub mod api;
 pub use api::{
     replay_from_snapshot, replay_with_trace, CanonicalStateLayout, CoreConfig, CoreProfile,
-    CoreSnapshot, CoreState, EventEnqueueError, EventQueueSnapshot, MmioBus, MmioError,
-    MmioWriteResult, ReplayEventStream, ReplayResult, RunBoundary, RunOutcome, SimpleTraceSink,
-    SnapshotLayoutError, SnapshotVersion, StepOutcome, TraceEvent, TraceSink,
-    DEFAULT_TICK_BUDGET_CYCLES, EVENT_QUEUE_CAPACITY, VEC_EVENT, VEC_FAULT, VEC_TRAP,
+    CoreSnapshot, CoreState, EventEnqueueError, EventQueueSnapshot, HaltReason, MmioBus, MmioError,
+    MmioWriteResult, OpenBus, ReplayEventStream, ReplayResult, RunBoundary, RunLimits, RunOutcome,
+    SimpleTraceSink, SnapshotLayoutError, SnapshotVersion, StepOutcome, TickHook, TraceEvent,
+    TraceSink, DEFAULT_TICK_BUDGET_CYCLES, EVENT_QUEUE_CAPACITY, SANDBOX_TICK_BUDGET_CYCLES,
+    VEC_EVENT, VEC_FAULT, VEC_TRAP,
 };
 
 /// Architectural CPU state model primitives.
 pub mod state;
 pub use state::{
-    ArchitecturalState, GeneralRegister, RunState, CAP_AUTHORITY_DEFAULT_MASK,
-    CAP_RESTRICTED_DEFAULT_MASK, GENERAL_REGISTER_COUNT,
+    diff_states, ArchitecturalState, GeneralRegister, MemoryWrite, MemoryWriteLog, PcHistory,
+    PcHistoryEntry, RunState, StateDifference, CAP_AUTHORITY_DEFAULT_MASK,
+    CAP_RESTRICTED_DEFAULT_MASK, EVP_OVERFLOW, GENERAL_REGISTER_COUNT,
 };
 
 /// Deterministic opcode and encoding classification tables.
 pub mod encoding;
 pub use encoding::{
-    classify_opcode, decode_primary_word_op_sub, is_reserved_primary_opcode, OpcodeClass,
-    OpcodeEncoding, OPCODE_ENCODING_TABLE,
+    classify_opcode, decode_primary_word_op_sub, is_reserved_primary_opcode, verify_table,
+    OpcodeClass, OpcodeEncoding, SpecialRegisterSelect, TableIssue, TableReport,
+    OPCODE_ENCODING_TABLE,
 };
 
 /// Instruction decode pipeline with field extraction and validation.
@@ -54,26 +60,80 @@ pub mod fault;
 pub use fault::{FaultClass, FaultCode};
 /// Deterministic instruction cycle-cost table and lookup helpers.
 pub mod timing;
-pub use timing::{cycle_cost, CycleCostKind, CYCLE_COST_TABLE};
+pub use timing::{
+    cycle_cost, cycle_cost_kinds, CycleCostKind, CycleCostTable, TimingModel,
+    CYCLE_COST_KIND_COUNT, CYCLE_COST_TABLE,
+};
+
+/// Watch expressions, breakpoints and call-aware stepping for host debuggers.
+pub mod debug;
+pub use debug::{
+    step_out, step_over, Breakpoint, BreakpointHit, BreakpointTable, BytePattern, BytePatternError,
+    StepStop, SteppingOutcome, WatchExpr, WatchExprError, WatchRegister,
+};
 
 /// Instruction disassembly utilities for debugging and visualization.
 pub mod disasm;
 pub use disasm::{disassemble_window, DisassemblyRow};
 
+/// Binary snapshot encoding for std-only hosts.
+pub mod snapshot_bytes;
+pub use snapshot_bytes::{SnapshotDecodeError, SNAPSHOT_MAGIC};
+
+/// Host parameter block placed in RAM before a program starts.
+pub mod params;
+pub use params::{
+    write_params, ParamBlock, ParamError, PARAMS_BASE, PARAMS_CAPACITY, PARAMS_MAGIC,
+    PARAM_KEY_MAX_LEN,
+};
+
+/// Panic-free fuzzing entry points.
+pub mod fuzz;
+pub use fuzz::fuzz_decode;
+
 /// Instruction execution pipeline.
 pub mod execute;
 pub use execute::{
-    commit_execution, execute_instruction, run_one, run_one_with_trace, step_one, ExecuteOutcome,
-    ExecuteState, FlagsUpdate,
+    check_run_boundary, commit_execution, end_tick, execute_instruction, run_fast_forward, run_one,
+    run_one_with_trace, run_ticks_with_budget, run_ticks_with_trace, step_one, step_one_with_trace,
+    BlockCache, ExecuteOutcome, ExecuteState, FlagsUpdate, TickBatch, TickStats, TICKS_PER_SECOND,
+    TICK_DURATION,
+};
+
+/// Host handlers for reserved primary opcodes.
+pub mod extension;
+pub use extension::{ExperimentalOpcodes, ExtensionError, OpcodeHandler};
+
+/// Version and ISA revision identifiers for compatibility checks.
+pub mod compat;
+pub use compat::{isa_revision, CORE_VERSION, LATEST_SNAPSHOT_VERSION};
+
+/// Trap cause and software service numbers shared with assembly programs.
+pub mod services;
+pub use services::{
+    find_service, Service, SERVICES, SERVICE_CONSTANTS, SERVICE_REGISTER, SVC_EXIT, SVC_NOP,
+    SVC_OK, SVC_PUTC, SVC_UNKNOWN, TRAP_CAUSE_SOFTWARE,
 };
 
 /// Peripheral devices and MMIO adapters.
 pub mod peripherals;
 pub use peripherals::{
-    CompositeMmio, Tele7Config, Tele7Peripheral, Tele7State, TELE7_BASE, TELE7_END, TELE7_ID,
+    BitField, CompositeMmio, DebugConsole, DeviceRegisters, DmaController, FieldValue, Mpu,
+    MpuAccess, MpuRegion, PasteBuffer, RegisterAccess, RegisterDecode, RegisterInfo, Tele7Config,
+    Tele7Peripheral, Tele7State, CAP_MPU_BIT, CONSOLE_BASE, CONSOLE_CAPACITY, CONSOLE_DATA,
+    CONSOLE_END, CONSOLE_EXIT, CONSOLE_ID, CONSOLE_REGISTERS, CONSOLE_VERSION, DMA_BASE, DMA_CTRL,
+    DMA_CTRL_IRQ, DMA_CTRL_START, DMA_CYCLES_PER_WORD, DMA_DEFAULT_RATE, DMA_DST, DMA_END,
+    DMA_EVENT, DMA_ID, DMA_LEN, DMA_MAX_RATE, DMA_RATE, DMA_REGISTERS, DMA_SRC, DMA_STATUS,
+    DMA_STATUS_BUSY, DMA_STATUS_DONE, DMA_STATUS_ERROR, DMA_VERSION, MPU_ATTR_ENABLE,
+    MPU_ATTR_EXECUTE, MPU_ATTR_READ, MPU_ATTR_WRITE, MPU_BASE, MPU_CTRL, MPU_CTRL_ENABLE, MPU_END,
+    MPU_FAULT_ADDR, MPU_ID, MPU_REGION_BASE, MPU_REGION_COUNT, MPU_REGISTERS, MPU_VERSION,
+    PASTE_BASE, PASTE_CAPACITY, PASTE_CLEAR, PASTE_DATA, PASTE_END, PASTE_ID, PASTE_LEN, PASTE_POS,
+    PASTE_REGISTERS, PASTE_SEQ, PASTE_VERSION, TELE7_BASE, TELE7_END, TELE7_ID, TELE7_REGISTERS,
     TELE7_VERSION,
 };
 
+mod thread_safety;
+
 #[cfg(test)]
 use proptest as _;
 #[cfg(test)]
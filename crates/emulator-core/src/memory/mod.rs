@@ -1,5 +1,7 @@
 //! Memory model primitives and fixed address-space policies.
 
+use alloc::{boxed::Box, vec};
+
 use crate::FaultCode;
 
 /// Deterministic fetch/write legality policy helpers.
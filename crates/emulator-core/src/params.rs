@@ -0,0 +1,236 @@
+//! Host parameter block: configuration a host places in RAM before a
+//! program starts, so the same binary can be run with different settings.
+//!
+//! The block occupies the top [`PARAMS_CAPACITY`] bytes of RAM, starting at
+//! [`PARAMS_BASE`]. All multi-byte integers are big-endian and every field
+//! starts on a word boundary:
+//!
+//! | Field | Size |
+//! |-------|------|
+//! | magic `PB` ([`PARAMS_MAGIC`]) | 2 |
+//! | entry count `n` | 2 |
+//! | entries | variable |
+//!
+//! Each entry is its key as NUL-terminated ASCII, padded with one more NUL
+//! when needed to reach an even length, then the value length in bytes as a
+//! word, then the value bytes padded with a zero byte to an even length.
+//! Numeric values are stored as a single big-endian word (length 2).
+//!
+//! A program that finds no magic word at [`PARAMS_BASE`] was started
+//! without parameters. Bytes after the last entry are zero.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use thiserror::Error;
+
+use crate::CoreState;
+
+/// Address of the parameter block's magic word.
+pub const PARAMS_BASE: u16 = 0xDF00;
+
+/// Bytes reserved for the parameter block, up to the end of RAM.
+pub const PARAMS_CAPACITY: usize = 0x100;
+
+/// First word of a parameter block: ASCII `PB`.
+pub const PARAMS_MAGIC: u16 = 0x5042;
+
+/// Longest key, excluding its NUL terminator.
+pub const PARAM_KEY_MAX_LEN: usize = 31;
+
+/// Failures building or loading a parameter block.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Error)]
+pub enum ParamError {
+    /// Key was empty, too long, or not letters, digits and `_`.
+    #[error("invalid parameter name '{0}': use 1-31 letters, digits and '_'")]
+    InvalidKey(String),
+    /// The same key was given twice.
+    #[error("parameter '{0}' is set more than once")]
+    DuplicateKey(String),
+    /// Encoded block would not fit in [`PARAMS_CAPACITY`] bytes.
+    #[error("parameter block needs {0} bytes; at most 256 fit")]
+    TooLarge(usize),
+}
+
+/// A parameter block under construction.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ParamBlock {
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+impl ParamBlock {
+    /// Creates an empty block.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Returns whether no parameters have been added.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds a parameter whose value is raw bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParamError`] when the key is invalid or repeated, or the
+    /// block would no longer fit in [`PARAMS_CAPACITY`] bytes. The block is
+    /// unchanged on error.
+    pub fn push_bytes(&mut self, key: &str, value: &[u8]) -> Result<(), ParamError> {
+        let valid = !key.is_empty()
+            && key.len() <= PARAM_KEY_MAX_LEN
+            && key.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_');
+        if !valid {
+            return Err(ParamError::InvalidKey(key.to_string()));
+        }
+        if self.entries.iter().any(|(existing, _)| existing == key) {
+            return Err(ParamError::DuplicateKey(key.to_string()));
+        }
+        let len = self.encoded_len() + entry_len(key, value);
+        if len > PARAMS_CAPACITY {
+            return Err(ParamError::TooLarge(len));
+        }
+        self.entries.push((key.to_string(), value.to_vec()));
+        Ok(())
+    }
+
+    /// Adds a parameter whose value is a 16-bit word.
+    ///
+    /// # Errors
+    ///
+    /// As for [`push_bytes`](Self::push_bytes).
+    pub fn push_word(&mut self, key: &str, value: u16) -> Result<(), ParamError> {
+        self.push_bytes(key, &value.to_be_bytes())
+    }
+
+    /// Size of the encoded block in bytes.
+    #[must_use]
+    pub fn encoded_len(&self) -> usize {
+        4 + self
+            .entries
+            .iter()
+            .map(|(key, value)| entry_len(key, value))
+            .sum::<usize>()
+    }
+
+    /// Encodes the block in the layout described in the
+    /// [module docs](crate::params).
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.encoded_len());
+        out.extend_from_slice(&PARAMS_MAGIC.to_be_bytes());
+        let count = u16::try_from(self.entries.len()).unwrap_or(u16::MAX);
+        out.extend_from_slice(&count.to_be_bytes());
+        for (key, value) in &self.entries {
+            out.extend_from_slice(key.as_bytes());
+            out.push(0);
+            pad_to_word(&mut out);
+            let len = u16::try_from(value.len()).unwrap_or(u16::MAX);
+            out.extend_from_slice(&len.to_be_bytes());
+            out.extend_from_slice(value);
+            pad_to_word(&mut out);
+        }
+        out
+    }
+}
+
+/// Encoded size of one entry, including padding.
+const fn entry_len(key: &str, value: &[u8]) -> usize {
+    (key.len() + 1).div_ceil(2) * 2 + 2 + value.len().div_ceil(2) * 2
+}
+
+fn pad_to_word(out: &mut Vec<u8>) {
+    if out.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+/// Copies an encoded parameter block to [`PARAMS_BASE`], zeroing the rest
+/// of the reserved area. An empty slice clears the block.
+///
+/// # Errors
+///
+/// Returns [`ParamError::TooLarge`] when `bytes` exceeds
+/// [`PARAMS_CAPACITY`]; memory is unchanged.
+pub fn write_params(state: &mut CoreState, bytes: &[u8]) -> Result<(), ParamError> {
+    if bytes.len() > PARAMS_CAPACITY {
+        return Err(ParamError::TooLarge(bytes.len()));
+    }
+    let base = usize::from(PARAMS_BASE);
+    let area = &mut state.memory[base..base + PARAMS_CAPACITY];
+    area.fill(0);
+    area[..bytes.len()].copy_from_slice(bytes);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RAM_END;
+
+    #[test]
+    fn block_reaches_the_end_of_ram() {
+        assert_eq!(
+            usize::from(PARAMS_BASE) + PARAMS_CAPACITY - 1,
+            usize::from(RAM_END)
+        );
+    }
+
+    #[test]
+    fn encodes_word_and_text_entries() {
+        let mut block = ParamBlock::new();
+        block.push_word("lvl", 3).unwrap();
+        block.push_bytes("name", b"ACE").unwrap();
+        assert_eq!(
+            block.to_bytes(),
+            [
+                0x50, 0x42, 0x00, 0x02, // magic, count
+                b'l', b'v', b'l', 0, 0x00, 0x02, 0x00, 0x03, // lvl = 3
+                b'n', b'a', b'm', b'e', 0, 0, 0x00, 0x03, b'A', b'C', b'E', 0, // name = "ACE"
+            ]
+        );
+        assert_eq!(block.encoded_len(), block.to_bytes().len());
+    }
+
+    #[test]
+    fn rejects_bad_keys_duplicates_and_overflow() {
+        let mut block = ParamBlock::new();
+        assert_eq!(
+            block.push_word("bad-key", 1),
+            Err(ParamError::InvalidKey("bad-key".to_string()))
+        );
+        block.push_word("seed", 1).unwrap();
+        assert_eq!(
+            block.push_word("seed", 2),
+            Err(ParamError::DuplicateKey("seed".to_string()))
+        );
+        assert_eq!(
+            block.push_bytes("text", &[b'x'; 250]),
+            Err(ParamError::TooLarge(272))
+        );
+        assert_eq!(block.encoded_len(), 14);
+    }
+
+    #[test]
+    fn write_params_replaces_the_reserved_area() {
+        let mut state = CoreState::default();
+        state.memory[usize::from(PARAMS_BASE) + 200] = 0xAA;
+        let mut block = ParamBlock::new();
+        block.push_word("x", 7).unwrap();
+        write_params(&mut state, &block.to_bytes()).unwrap();
+
+        let base = usize::from(PARAMS_BASE);
+        assert_eq!(&state.memory[base..base + 10], &block.to_bytes()[..]);
+        assert_eq!(state.memory[base + 200], 0);
+        assert_eq!(
+            write_params(&mut state, &[0; PARAMS_CAPACITY + 1]),
+            Err(ParamError::TooLarge(PARAMS_CAPACITY + 1))
+        );
+    }
+}
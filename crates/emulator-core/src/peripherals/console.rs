@@ -0,0 +1,219 @@
+//! Debug console peripheral implementation.
+//!
+//! A write-only character sink for host tooling: bytes a program writes to
+//! its DATA register are collected for the test runner, the CLI and the web
+//! debugger to read back, and a write to its EXIT register reports the
+//! program's exit status. It has no in-game counterpart.
+
+use alloc::{string::String, vec, vec::Vec};
+
+use super::registers::{
+    register, DeviceRegisters,
+    RegisterAccess::{ReadOnly, WriteOnly},
+};
+use crate::api::{MmioBus, MmioError, MmioWriteResult};
+
+/// Debug console MMIO register base address.
+pub const CONSOLE_BASE: u16 = 0xE130;
+
+/// Debug console MMIO register end address.
+pub const CONSOLE_END: u16 = 0xE133;
+
+/// Debug console device identification constant.
+pub const CONSOLE_ID: u16 = 0x0DC0;
+
+/// Debug console device version.
+pub const CONSOLE_VERSION: u16 = 0x0001;
+
+/// Register that appends the low byte of each write to the output.
+pub const CONSOLE_DATA: u16 = 0xE132;
+
+/// Register that records the low byte of a write as the exit status.
+pub const CONSOLE_EXIT: u16 = 0xE133;
+
+/// Debug console register layout.
+pub const CONSOLE_REGISTERS: DeviceRegisters = DeviceRegisters {
+    device: "CONSOLE",
+    description: "Debug console",
+    base: CONSOLE_BASE,
+    end: CONSOLE_END,
+    registers: &[
+        register("ID", 0, ReadOnly, "Device identifier", &[]),
+        register("VERSION", 1, ReadOnly, "Device revision", &[]),
+        register(
+            "DATA",
+            2,
+            WriteOnly,
+            "Low byte is appended to the output",
+            &[],
+        ),
+        register(
+            "EXIT",
+            3,
+            WriteOnly,
+            "Low byte is recorded as the exit status",
+            &[],
+        ),
+    ],
+};
+
+/// Bytes kept before further output is dropped, so a program stuck in a
+/// print loop cannot grow the host's memory without bound.
+pub const CONSOLE_CAPACITY: usize = 64 * 1024;
+
+/// Debug console peripheral.
+///
+/// Reads of ID and VERSION identify the device; every other read returns
+/// 0. Writes to DATA append their low byte to the output, writes to EXIT
+/// record their low byte as the exit status (the last write wins), and
+/// writes to other registers are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct DebugConsole {
+    output: Vec<u8>,
+    dropped: usize,
+    exit_status: Option<u8>,
+}
+
+impl DebugConsole {
+    /// Creates an empty debug console.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            output: Vec::new(),
+            dropped: 0,
+            exit_status: None,
+        }
+    }
+
+    /// Returns the bytes written since the last drain.
+    #[must_use]
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// Returns the output written since the last drain as text, with
+    /// invalid UTF-8 replaced.
+    #[must_use]
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.output).into_owned()
+    }
+
+    /// Takes the bytes written since the last drain, leaving the buffer
+    /// empty.
+    pub fn drain(&mut self) -> Vec<u8> {
+        core::mem::take(&mut self.output)
+    }
+
+    /// Returns how many bytes were dropped because the buffer was full.
+    #[must_use]
+    pub const fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Returns the status the program last wrote to EXIT, or `None` if it
+    /// has not exited.
+    #[must_use]
+    pub const fn exit_status(&self) -> Option<u8> {
+        self.exit_status
+    }
+
+    /// Clears buffered output, the dropped-byte count and the exit status.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    fn push(&mut self, byte: u8) {
+        if self.output.len() < CONSOLE_CAPACITY {
+            self.output.push(byte);
+        } else {
+            self.dropped += 1;
+        }
+    }
+}
+
+/// Value read from `addr`; DATA and EXIT are write-only and read as 0.
+const fn register_value(addr: u16) -> u16 {
+    match addr {
+        CONSOLE_BASE => CONSOLE_ID,
+        0xE131 => CONSOLE_VERSION,
+        _ => 0,
+    }
+}
+
+impl MmioBus for DebugConsole {
+    fn read16(&mut self, addr: u16) -> Result<u16, MmioError> {
+        Ok(register_value(addr))
+    }
+
+    fn write16(&mut self, addr: u16, value: u16) -> Result<MmioWriteResult, MmioError> {
+        let low = value.to_be_bytes()[1];
+        match addr {
+            CONSOLE_DATA => self.push(low),
+            CONSOLE_EXIT => self.exit_status = Some(low),
+            _ => {}
+        }
+        Ok(MmioWriteResult::Applied)
+    }
+
+    fn peek16(&self, addr: u16) -> Option<u16> {
+        Some(register_value(addr))
+    }
+
+    fn reset(&mut self) {
+        Self::reset(self);
+    }
+
+    fn describe_registers(&self) -> Vec<&'static DeviceRegisters> {
+        vec![&CONSOLE_REGISTERS]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn console_identifies_itself() {
+        let mut console = DebugConsole::new();
+        assert_eq!(console.read16(CONSOLE_BASE).unwrap(), CONSOLE_ID);
+        assert_eq!(console.read16(0xE131).unwrap(), CONSOLE_VERSION);
+        assert_eq!(console.read16(CONSOLE_DATA).unwrap(), 0);
+    }
+
+    #[test]
+    fn data_writes_append_low_byte() {
+        let mut console = DebugConsole::new();
+        for value in [0x004F, 0xFF4B, 0x000A] {
+            console.write16(CONSOLE_DATA, value).unwrap();
+        }
+        console.write16(0xE131, 0x0058).unwrap();
+        assert_eq!(console.text(), "OK\n");
+        assert_eq!(console.drain(), b"OK\n");
+        assert!(console.output().is_empty());
+    }
+
+    #[test]
+    fn exit_writes_record_the_last_status() {
+        let mut console = DebugConsole::new();
+        assert_eq!(console.exit_status(), None);
+        console.write16(CONSOLE_EXIT, 0x0103).unwrap();
+        assert_eq!(console.exit_status(), Some(3));
+        console.write16(CONSOLE_EXIT, 0).unwrap();
+        assert_eq!(console.exit_status(), Some(0));
+        assert!(console.output().is_empty());
+        console.reset();
+        assert_eq!(console.exit_status(), None);
+    }
+
+    #[test]
+    fn output_past_capacity_is_dropped() {
+        let mut console = DebugConsole::new();
+        for _ in 0..CONSOLE_CAPACITY + 3 {
+            console.write16(CONSOLE_DATA, u16::from(b'.')).unwrap();
+        }
+        assert_eq!(console.output().len(), CONSOLE_CAPACITY);
+        assert_eq!(console.dropped(), 3);
+        console.reset();
+        assert_eq!(console.dropped(), 0);
+    }
+}
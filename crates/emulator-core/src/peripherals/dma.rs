@@ -0,0 +1,388 @@
+//! DMA controller peripheral implementation.
+//!
+//! Copies blocks of words into RAM at tick boundaries, so a program can
+//! redraw a whole TELE-7 page without spending its tick budget on a copy
+//! loop. The program programs SRC, DST and LEN, then sets `CTRL.START`; at
+//! each following tick boundary the controller copies up to RATE words and
+//! charges [`DMA_CYCLES_PER_WORD`] cycles per word to the next tick, so
+//! the copy is interleaved with the program at fixed points and its cost
+//! shows up in the cycle budget. When the last word lands (or a word falls
+//! outside the allowed regions) the transfer stops, STATUS reports it, and
+//! with `CTRL.IRQ` set the EVENT id is enqueued on the core's event queue.
+//!
+//! | Address  | Register  | Access | Contents                                   |
+//! | -------- | --------- | ------ | ------------------------------------------ |
+//! | `0xE150` | `ID`      | R      | [`DMA_ID`]                                 |
+//! | `0xE151` | `VERSION` | R      | [`DMA_VERSION`]                            |
+//! | `0xE152` | `SRC`     | RW     | Next source address (ROM or RAM)           |
+//! | `0xE153` | `DST`     | RW     | Next destination address (RAM)             |
+//! | `0xE154` | `LEN`     | RW     | Words left to copy                         |
+//! | `0xE155` | `CTRL`    | RW     | bit 0 `START`, bit 1 `IRQ`                 |
+//! | `0xE156` | `STATUS`  | R      | bit 0 `BUSY`, bit 1 `DONE`, bit 2 `ERROR`  |
+//! | `0xE157` | `RATE`    | RW     | Words per tick, 1 to [`DMA_MAX_RATE`]      |
+//! | `0xE158` | `EVENT`   | RW     | Event id enqueued on completion (low byte) |
+//!
+//! SRC, DST and LEN advance as words are copied and ignore writes while a
+//! transfer is running; clearing `CTRL.START` aborts it without an event.
+
+use alloc::{vec, vec::Vec};
+
+use super::registers::{
+    field, register, DeviceRegisters,
+    RegisterAccess::{ReadOnly, ReadWrite},
+};
+use crate::api::{MmioBus, MmioError, MmioWriteResult};
+use crate::{decode_memory_region, CoreState, MemoryRegion};
+
+/// DMA controller MMIO register base address.
+pub const DMA_BASE: u16 = 0xE150;
+
+/// DMA controller MMIO register end address.
+pub const DMA_END: u16 = 0xE15F;
+
+/// DMA controller device identification constant.
+pub const DMA_ID: u16 = 0x0D3A;
+
+/// DMA controller device version.
+pub const DMA_VERSION: u16 = 0x0001;
+
+/// Register holding the next source address.
+pub const DMA_SRC: u16 = 0xE152;
+
+/// Register holding the next destination address.
+pub const DMA_DST: u16 = 0xE153;
+
+/// Register holding the number of words left to copy.
+pub const DMA_LEN: u16 = 0xE154;
+
+/// Control register.
+pub const DMA_CTRL: u16 = 0xE155;
+
+/// Read-only status register.
+pub const DMA_STATUS: u16 = 0xE156;
+
+/// Register holding the number of words copied per tick.
+pub const DMA_RATE: u16 = 0xE157;
+
+/// Register holding the completion event id.
+pub const DMA_EVENT: u16 = 0xE158;
+
+/// `CTRL` bit that starts a transfer; reads back set while one runs.
+pub const DMA_CTRL_START: u16 = 1 << 0;
+
+/// `CTRL` bit that enqueues `EVENT` when a transfer stops.
+pub const DMA_CTRL_IRQ: u16 = 1 << 1;
+
+/// `STATUS` bit set while a transfer runs.
+pub const DMA_STATUS_BUSY: u16 = 1 << 0;
+
+/// `STATUS` bit set when the last transfer stopped, cleared by a start.
+pub const DMA_STATUS_DONE: u16 = 1 << 1;
+
+/// `STATUS` bit set when the last transfer stopped at an illegal address.
+pub const DMA_STATUS_ERROR: u16 = 1 << 2;
+
+/// Words copied per tick after reset.
+pub const DMA_DEFAULT_RATE: u16 = 64;
+
+/// Largest accepted `RATE`; larger writes are clamped.
+pub const DMA_MAX_RATE: u16 = 256;
+
+/// Cycles each copied word takes from the next tick's budget.
+pub const DMA_CYCLES_PER_WORD: u16 = 1;
+
+/// DMA controller register layout.
+pub const DMA_REGISTERS: DeviceRegisters = DeviceRegisters {
+    device: "DMA",
+    description: "DMA controller",
+    base: DMA_BASE,
+    end: DMA_END,
+    registers: &[
+        register("ID", 0, ReadOnly, "Device identifier", &[]),
+        register("VERSION", 1, ReadOnly, "Device revision", &[]),
+        register("SRC", 2, ReadWrite, "Next source address", &[]),
+        register("DST", 3, ReadWrite, "Next destination address", &[]),
+        register("LEN", 4, ReadWrite, "Words left to copy", &[]),
+        register(
+            "CTRL",
+            5,
+            ReadWrite,
+            "Control bits",
+            &[field("START", 0, 1), field("IRQ", 1, 1)],
+        ),
+        register(
+            "STATUS",
+            6,
+            ReadOnly,
+            "Transfer status",
+            &[
+                field("BUSY", 0, 1),
+                field("DONE", 1, 1),
+                field("ERROR", 2, 1),
+            ],
+        ),
+        register("RATE", 7, ReadWrite, "Words copied per tick", &[]),
+        register(
+            "EVENT",
+            8,
+            ReadWrite,
+            "Event enqueued on completion",
+            &[field("ID", 0, 8)],
+        ),
+    ],
+};
+
+/// DMA controller peripheral.
+///
+/// Register access goes through [`MmioBus`]; the copying happens in
+/// [`MmioBus::on_tick_memory`], which [`end_tick`](crate::end_tick) calls
+/// at every tick boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DmaController {
+    src: u16,
+    dst: u16,
+    len: u16,
+    ctrl: u16,
+    status: u16,
+    rate: u16,
+    event: u16,
+}
+
+impl Default for DmaController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DmaController {
+    /// Creates an idle DMA controller.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            src: 0,
+            dst: 0,
+            len: 0,
+            ctrl: 0,
+            status: 0,
+            rate: DMA_DEFAULT_RATE,
+            event: 0,
+        }
+    }
+
+    /// Returns `true` while a transfer is running.
+    #[must_use]
+    pub const fn is_busy(&self) -> bool {
+        self.status & DMA_STATUS_BUSY != 0
+    }
+
+    /// Returns the register at `addr`; unmapped registers read as 0.
+    #[must_use]
+    pub const fn register(&self, addr: u16) -> u16 {
+        match addr {
+            DMA_BASE => DMA_ID,
+            0xE151 => DMA_VERSION,
+            DMA_SRC => self.src,
+            DMA_DST => self.dst,
+            DMA_LEN => self.len,
+            DMA_CTRL => self.ctrl,
+            DMA_STATUS => self.status,
+            DMA_RATE => self.rate,
+            DMA_EVENT => self.event,
+            _ => 0,
+        }
+    }
+
+    /// Returns the controller to its power-on state.
+    pub const fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Copies up to `RATE` words of a running transfer into `state.memory`.
+    ///
+    /// Sets `TICK` to the cycles spent and, when the transfer stops with
+    /// `CTRL.IRQ` set, enqueues `EVENT`. Does nothing while idle.
+    pub fn transfer(&mut self, state: &mut CoreState) {
+        if !self.is_busy() {
+            return;
+        }
+        let mut words = 0;
+        while self.len > 0 && words < self.rate {
+            if !readable(self.src) || !writable(self.dst) {
+                self.stop(state, DMA_STATUS_ERROR);
+                break;
+            }
+            let (src, dst) = (usize::from(self.src), usize::from(self.dst));
+            state.memory.copy_within(src..src + 2, dst);
+            self.src = self.src.wrapping_add(2);
+            self.dst = self.dst.wrapping_add(2);
+            self.len -= 1;
+            words += 1;
+        }
+        if self.is_busy() && self.len == 0 {
+            self.stop(state, 0);
+        }
+        let cycles = words * DMA_CYCLES_PER_WORD;
+        state.arch.set_tick(state.arch.tick().wrapping_add(cycles));
+    }
+
+    fn stop(&mut self, state: &mut CoreState, error: u16) {
+        self.status = DMA_STATUS_DONE | error;
+        self.ctrl &= !DMA_CTRL_START;
+        if self.ctrl & DMA_CTRL_IRQ != 0 {
+            // A full queue latches EVP overflow, as for any host event.
+            let _ = state.enqueue_event(self.event.to_be_bytes()[1]);
+        }
+    }
+}
+
+/// Source words may come from ROM or RAM.
+fn readable(addr: u16) -> bool {
+    [addr, addr.wrapping_add(1)].into_iter().all(|byte| {
+        matches!(
+            decode_memory_region(byte),
+            MemoryRegion::Rom | MemoryRegion::Ram
+        )
+    })
+}
+
+/// Destination words must lie in RAM.
+const fn writable(addr: u16) -> bool {
+    MemoryRegion::Ram.contains(addr) && MemoryRegion::Ram.contains(addr.wrapping_add(1))
+}
+
+impl MmioBus for DmaController {
+    fn read16(&mut self, addr: u16) -> Result<u16, MmioError> {
+        Ok(self.register(addr))
+    }
+
+    fn write16(&mut self, addr: u16, value: u16) -> Result<MmioWriteResult, MmioError> {
+        let busy = self.is_busy();
+        match addr {
+            DMA_SRC if !busy => self.src = value,
+            DMA_DST if !busy => self.dst = value,
+            DMA_LEN if !busy => self.len = value,
+            DMA_CTRL => {
+                self.ctrl = value & (DMA_CTRL_START | DMA_CTRL_IRQ);
+                if self.ctrl & DMA_CTRL_START == 0 {
+                    self.status &= !DMA_STATUS_BUSY;
+                } else if !busy {
+                    self.status = DMA_STATUS_BUSY;
+                }
+            }
+            DMA_RATE => self.rate = value.clamp(1, DMA_MAX_RATE),
+            DMA_EVENT => self.event = value & 0x00FF,
+            _ => {}
+        }
+        Ok(MmioWriteResult::Applied)
+    }
+
+    fn on_tick_memory(&mut self, state: &mut CoreState) {
+        self.transfer(state);
+    }
+
+    fn peek16(&self, addr: u16) -> Option<u16> {
+        Some(self.register(addr))
+    }
+
+    fn reset(&mut self) {
+        Self::reset(self);
+    }
+
+    fn describe_registers(&self) -> Vec<&'static DeviceRegisters> {
+        vec![&DMA_REGISTERS]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{end_tick, write_u16_be};
+
+    fn start(dma: &mut DmaController, src: u16, dst: u16, len: u16, rate: u16) {
+        for (addr, value) in [
+            (DMA_SRC, src),
+            (DMA_DST, dst),
+            (DMA_LEN, len),
+            (DMA_RATE, rate),
+            (DMA_EVENT, 0x42),
+            (DMA_CTRL, DMA_CTRL_START | DMA_CTRL_IRQ),
+        ] {
+            dma.write16(addr, value).unwrap();
+        }
+    }
+
+    #[test]
+    fn dma_identifies_itself() {
+        let mut dma = DmaController::new();
+        assert_eq!(dma.read16(DMA_BASE).unwrap(), DMA_ID);
+        assert_eq!(dma.read16(0xE151).unwrap(), DMA_VERSION);
+        assert_eq!(dma.read16(DMA_RATE).unwrap(), DMA_DEFAULT_RATE);
+        dma.write16(DMA_RATE, 0xFFFF).unwrap();
+        assert_eq!(dma.read16(DMA_RATE).unwrap(), DMA_MAX_RATE);
+    }
+
+    #[test]
+    fn copies_rate_words_per_tick_and_signals_completion() {
+        let mut state = CoreState::default();
+        for index in 0..5u16 {
+            write_u16_be(&mut state.memory, 0x0100 + 2 * index, 0x1110 + index).unwrap();
+        }
+        let mut dma = DmaController::new();
+        start(&mut dma, 0x0100, 0x4000, 5, 2);
+        assert_eq!(dma.read16(DMA_STATUS).unwrap(), DMA_STATUS_BUSY);
+
+        end_tick(&mut state, &mut dma, None);
+        assert_eq!(state.arch.tick(), 2 * DMA_CYCLES_PER_WORD);
+        assert_eq!(
+            &state.memory[0x4000..0x4006],
+            [0x11, 0x10, 0x11, 0x11, 0, 0]
+        );
+        assert_eq!(dma.read16(DMA_LEN).unwrap(), 3);
+        assert_eq!(dma.read16(DMA_SRC).unwrap(), 0x0104);
+        assert!(state.event_queue.is_empty());
+
+        // Programming registers mid-transfer is ignored.
+        dma.write16(DMA_DST, 0x5000).unwrap();
+        end_tick(&mut state, &mut dma, None);
+        end_tick(&mut state, &mut dma, None);
+        assert_eq!(state.arch.tick(), DMA_CYCLES_PER_WORD);
+        assert_eq!(state.memory[0x4009], 0x14);
+        assert_eq!(dma.read16(DMA_STATUS).unwrap(), DMA_STATUS_DONE);
+        assert_eq!(dma.read16(DMA_CTRL).unwrap(), DMA_CTRL_IRQ);
+        assert_eq!(state.event_queue.dequeue(), Some(0x42));
+
+        // Idle ticks copy nothing and cost nothing.
+        end_tick(&mut state, &mut dma, None);
+        assert_eq!(state.arch.tick(), 0);
+        assert!(state.event_queue.is_empty());
+    }
+
+    #[test]
+    fn illegal_destination_stops_with_error() {
+        let mut state = CoreState::default();
+        let mut dma = DmaController::new();
+        start(&mut dma, 0x4000, 0xDFFE, 3, 8);
+
+        end_tick(&mut state, &mut dma, None);
+        assert_eq!(
+            dma.read16(DMA_STATUS).unwrap(),
+            DMA_STATUS_DONE | DMA_STATUS_ERROR
+        );
+        assert_eq!(dma.read16(DMA_LEN).unwrap(), 2);
+        assert_eq!(dma.read16(DMA_DST).unwrap(), 0xE000);
+        assert_eq!(state.event_queue.dequeue(), Some(0x42));
+    }
+
+    #[test]
+    fn clearing_start_aborts_without_an_event() {
+        let mut state = CoreState::default();
+        let mut dma = DmaController::new();
+        start(&mut dma, 0x0000, 0x4000, 100, 1);
+        end_tick(&mut state, &mut dma, None);
+        dma.write16(DMA_CTRL, DMA_CTRL_IRQ).unwrap();
+        end_tick(&mut state, &mut dma, None);
+        assert_eq!(dma.read16(DMA_LEN).unwrap(), 99);
+        assert!(!dma.is_busy());
+        assert!(state.event_queue.is_empty());
+    }
+}
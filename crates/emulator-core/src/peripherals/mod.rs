@@ -1,5 +1,32 @@
+pub mod console;
+pub mod dma;
+pub mod mpu;
+pub mod paste;
+pub mod registers;
 pub mod tele7;
 
+pub use console::{
+    DebugConsole, CONSOLE_BASE, CONSOLE_CAPACITY, CONSOLE_DATA, CONSOLE_END, CONSOLE_EXIT,
+    CONSOLE_ID, CONSOLE_REGISTERS, CONSOLE_VERSION,
+};
+pub use dma::{
+    DmaController, DMA_BASE, DMA_CTRL, DMA_CTRL_IRQ, DMA_CTRL_START, DMA_CYCLES_PER_WORD,
+    DMA_DEFAULT_RATE, DMA_DST, DMA_END, DMA_EVENT, DMA_ID, DMA_LEN, DMA_MAX_RATE, DMA_RATE,
+    DMA_REGISTERS, DMA_SRC, DMA_STATUS, DMA_STATUS_BUSY, DMA_STATUS_DONE, DMA_STATUS_ERROR,
+    DMA_VERSION,
+};
+pub use mpu::{
+    Mpu, MpuAccess, MpuRegion, CAP_MPU_BIT, MPU_ATTR_ENABLE, MPU_ATTR_EXECUTE, MPU_ATTR_READ,
+    MPU_ATTR_WRITE, MPU_BASE, MPU_CTRL, MPU_CTRL_ENABLE, MPU_END, MPU_FAULT_ADDR, MPU_ID,
+    MPU_REGION_BASE, MPU_REGION_COUNT, MPU_REGISTERS, MPU_VERSION,
+};
+pub use paste::{
+    PasteBuffer, PASTE_BASE, PASTE_CAPACITY, PASTE_CLEAR, PASTE_DATA, PASTE_END, PASTE_ID,
+    PASTE_LEN, PASTE_POS, PASTE_REGISTERS, PASTE_SEQ, PASTE_VERSION,
+};
 pub use tele7::{CompositeMmio, Tele7Config, Tele7Peripheral, Tele7State};
 
-pub use tele7::{TELE7_BASE, TELE7_END, TELE7_ID, TELE7_VERSION};
+pub use registers::{
+    BitField, DeviceRegisters, FieldValue, RegisterAccess, RegisterDecode, RegisterInfo,
+};
+pub use tele7::{TELE7_BASE, TELE7_END, TELE7_ID, TELE7_REGISTERS, TELE7_VERSION};
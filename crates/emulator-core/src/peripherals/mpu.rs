@@ -0,0 +1,435 @@
+//! Memory protection unit (MPU) toy model.
+//!
+//! A teaching peripheral that lets a program fence off parts of the address
+//! space. Unlike the other devices it is owned by the core, because every
+//! fetch and data access has to be checked against it, and it only exists
+//! when `CAP` bit [`CAP_MPU_BIT`] is set: with the bit clear its window is
+//! ordinary MMIO handled by the host bus and nothing is checked, so the
+//! default profiles behave exactly as before.
+//!
+//! The program configures up to [`MPU_REGION_COUNT`] regions through the
+//! MMIO window, then sets `CTRL.EN`. While enabled, code outside handler
+//! context may only fetch from, read from (`LOAD`, `IN`, `BTEST`) and write
+//! to (`STORE`, `OUT`; `BSET` and `BCLR` need both) addresses inside an
+//! enabled region granting that access; the lowest-numbered matching
+//! region decides, and an address no region covers is denied. A denied
+//! access raises [`FaultCode::MpuViolation`] before the instruction has any
+//! effect. Handlers run with the MPU bypassed, so a fault handler can always
+//! inspect and reconfigure it. Stack traffic (`PUSH`, `POP`, `CALL`, `RET`
+//! and dispatch frames) is not checked.
+//!
+//! | Address  | Register     | Access | Contents                              |
+//! | -------- | ------------ | ------ | ------------------------------------- |
+//! | `0xE140` | `ID`         | R      | [`MPU_ID`]                            |
+//! | `0xE141` | `VERSION`    | R      | [`MPU_VERSION`]                       |
+//! | `0xE142` | `CTRL`       | RW     | bit 0 `EN` enables checking           |
+//! | `0xE143` | `FAULT_ADDR` | R      | Address of the last denied access     |
+//! | `0xE144` | `REGIONn`    | RW     | Three registers per region, `n = 0-3` |
+//!
+//! Region `n` occupies `0xE144 + 3n`: BASE, LIMIT (both inclusive) and ATTR,
+//! whose bits are [`MPU_ATTR_READ`], [`MPU_ATTR_WRITE`],
+//! [`MPU_ATTR_EXECUTE`] and [`MPU_ATTR_ENABLE`].
+//!
+//! Version 4 snapshots record the MPU registers; states restored from
+//! earlier versions start with the MPU disabled and its regions cleared.
+
+use alloc::{vec, vec::Vec};
+
+use super::registers::{
+    field, register, BitField, DeviceRegisters,
+    RegisterAccess::{ReadOnly, ReadWrite},
+};
+use crate::api::{MmioBus, MmioError, MmioWriteResult};
+use crate::FaultCode;
+
+/// `CAP` bit index that makes the MPU present.
+pub const CAP_MPU_BIT: u8 = 4;
+
+/// MPU MMIO register base address.
+pub const MPU_BASE: u16 = 0xE140;
+
+/// MPU MMIO register end address.
+pub const MPU_END: u16 = 0xE14F;
+
+/// MPU device identification constant.
+pub const MPU_ID: u16 = 0x4D50;
+
+/// MPU device version.
+pub const MPU_VERSION: u16 = 0x0001;
+
+/// Control register; bit 0 enables checking.
+pub const MPU_CTRL: u16 = 0xE142;
+
+/// Read-only register holding the address of the last denied access.
+pub const MPU_FAULT_ADDR: u16 = 0xE143;
+
+/// First register of region 0; each region has BASE, LIMIT and ATTR.
+pub const MPU_REGION_BASE: u16 = 0xE144;
+
+/// Number of region descriptors.
+pub const MPU_REGION_COUNT: usize = 4;
+
+/// `CTRL` bit that enables checking.
+pub const MPU_CTRL_ENABLE: u16 = 1 << 0;
+
+/// `ATTR` bit allowing data reads.
+pub const MPU_ATTR_READ: u16 = 1 << 0;
+
+/// `ATTR` bit allowing data writes.
+pub const MPU_ATTR_WRITE: u16 = 1 << 1;
+
+/// `ATTR` bit allowing instruction fetch.
+pub const MPU_ATTR_EXECUTE: u16 = 1 << 2;
+
+/// `ATTR` bit marking the region as in use.
+pub const MPU_ATTR_ENABLE: u16 = 1 << 15;
+
+const ATTR_FIELDS: &[BitField] = &[
+    field("READ", 0, 1),
+    field("WRITE", 1, 1),
+    field("EXECUTE", 2, 1),
+    field("ENABLE", 15, 1),
+];
+
+/// MPU register layout.
+pub const MPU_REGISTERS: DeviceRegisters = DeviceRegisters {
+    device: "MPU",
+    description: "Memory protection unit (CAP bit 4)",
+    base: MPU_BASE,
+    end: MPU_END,
+    registers: &[
+        register("ID", 0, ReadOnly, "Device identifier", &[]),
+        register("VERSION", 1, ReadOnly, "Device revision", &[]),
+        register("CTRL", 2, ReadWrite, "Control bits", &[field("EN", 0, 1)]),
+        register(
+            "FAULT_ADDR",
+            3,
+            ReadOnly,
+            "Address of the last denied access",
+            &[],
+        ),
+        register(
+            "REGION0_BASE",
+            4,
+            ReadWrite,
+            "First address of region 0",
+            &[],
+        ),
+        register(
+            "REGION0_LIMIT",
+            5,
+            ReadWrite,
+            "Last address of region 0",
+            &[],
+        ),
+        register(
+            "REGION0_ATTR",
+            6,
+            ReadWrite,
+            "Region 0 permissions",
+            ATTR_FIELDS,
+        ),
+        register(
+            "REGION1_BASE",
+            7,
+            ReadWrite,
+            "First address of region 1",
+            &[],
+        ),
+        register(
+            "REGION1_LIMIT",
+            8,
+            ReadWrite,
+            "Last address of region 1",
+            &[],
+        ),
+        register(
+            "REGION1_ATTR",
+            9,
+            ReadWrite,
+            "Region 1 permissions",
+            ATTR_FIELDS,
+        ),
+        register(
+            "REGION2_BASE",
+            10,
+            ReadWrite,
+            "First address of region 2",
+            &[],
+        ),
+        register(
+            "REGION2_LIMIT",
+            11,
+            ReadWrite,
+            "Last address of region 2",
+            &[],
+        ),
+        register(
+            "REGION2_ATTR",
+            12,
+            ReadWrite,
+            "Region 2 permissions",
+            ATTR_FIELDS,
+        ),
+        register(
+            "REGION3_BASE",
+            13,
+            ReadWrite,
+            "First address of region 3",
+            &[],
+        ),
+        register(
+            "REGION3_LIMIT",
+            14,
+            ReadWrite,
+            "Last address of region 3",
+            &[],
+        ),
+        register(
+            "REGION3_ATTR",
+            15,
+            ReadWrite,
+            "Region 3 permissions",
+            ATTR_FIELDS,
+        ),
+    ],
+};
+
+/// Kind of access checked against the region descriptors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MpuAccess {
+    /// Instruction fetch; needs [`MPU_ATTR_EXECUTE`].
+    Execute,
+    /// Data read; needs [`MPU_ATTR_READ`].
+    Read,
+    /// Data write; needs [`MPU_ATTR_WRITE`].
+    Write,
+}
+
+impl MpuAccess {
+    const fn attr_bit(self) -> u16 {
+        match self {
+            Self::Execute => MPU_ATTR_EXECUTE,
+            Self::Read => MPU_ATTR_READ,
+            Self::Write => MPU_ATTR_WRITE,
+        }
+    }
+}
+
+/// One protection region: an inclusive address range and its permissions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct MpuRegion {
+    /// First address covered.
+    pub base: u16,
+    /// Last address covered.
+    pub limit: u16,
+    /// Permission and enable bits.
+    pub attr: u16,
+}
+
+impl MpuRegion {
+    const fn covers(self, addr: u16) -> bool {
+        self.attr & MPU_ATTR_ENABLE != 0 && addr >= self.base && addr <= self.limit
+    }
+}
+
+/// MPU register file.
+///
+/// Register reads and writes go through [`MmioBus`]; the core routes its
+/// window here only while [`CAP_MPU_BIT`] is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Mpu {
+    ctrl: u16,
+    fault_addr: u16,
+    regions: [MpuRegion; MPU_REGION_COUNT],
+}
+
+impl Mpu {
+    /// Creates a disabled MPU with all regions cleared.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            ctrl: 0,
+            fault_addr: 0,
+            regions: [MpuRegion {
+                base: 0,
+                limit: 0,
+                attr: 0,
+            }; MPU_REGION_COUNT],
+        }
+    }
+
+    /// Returns `true` when `CTRL.EN` is set.
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.ctrl & MPU_CTRL_ENABLE != 0
+    }
+
+    /// Returns the region descriptors in priority order.
+    #[must_use]
+    pub const fn regions(&self) -> &[MpuRegion; MPU_REGION_COUNT] {
+        &self.regions
+    }
+
+    /// Returns the address of the last denied access.
+    #[must_use]
+    pub const fn fault_addr(&self) -> u16 {
+        self.fault_addr
+    }
+
+    /// Checks `access` at `addr` against the regions.
+    ///
+    /// Always allowed while the MPU is disabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FaultCode::MpuViolation`] when the first region covering
+    /// `addr` lacks the permission, or no region covers it.
+    pub fn check(&self, addr: u16, access: MpuAccess) -> Result<(), FaultCode> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        match self.regions.iter().find(|region| region.covers(addr)) {
+            Some(region) if region.attr & access.attr_bit() != 0 => Ok(()),
+            _ => Err(FaultCode::MpuViolation),
+        }
+    }
+
+    /// Records `addr` in `FAULT_ADDR` after a denied access.
+    pub const fn record_violation(&mut self, addr: u16) {
+        self.fault_addr = addr;
+    }
+
+    /// Returns the value of the register at `addr` without side effects.
+    #[must_use]
+    pub fn register(&self, addr: u16) -> u16 {
+        match addr {
+            MPU_BASE => MPU_ID,
+            0xE141 => MPU_VERSION,
+            MPU_CTRL => self.ctrl,
+            MPU_FAULT_ADDR => self.fault_addr,
+            _ => region_field(addr).map_or(0, |(index, field)| {
+                let region = &self.regions[index];
+                [region.base, region.limit, region.attr][field]
+            }),
+        }
+    }
+
+    /// Writes the register at `addr`; read-only and unmapped registers
+    /// ignore writes.
+    pub fn set_register(&mut self, addr: u16, value: u16) {
+        if addr == MPU_CTRL {
+            self.ctrl = value & MPU_CTRL_ENABLE;
+        } else if let Some((index, field)) = region_field(addr) {
+            let region = &mut self.regions[index];
+            match field {
+                0 => region.base = value,
+                1 => region.limit = value,
+                _ => region.attr = value,
+            }
+        }
+    }
+}
+
+/// Maps a region register address to its region index and field
+/// (`0=BASE`, `1=LIMIT`, `2=ATTR`).
+fn region_field(addr: u16) -> Option<(usize, usize)> {
+    let offset = usize::from(addr.checked_sub(MPU_REGION_BASE)?);
+    (offset < MPU_REGION_COUNT * 3).then_some((offset / 3, offset % 3))
+}
+
+impl MmioBus for Mpu {
+    fn read16(&mut self, addr: u16) -> Result<u16, MmioError> {
+        Ok(self.register(addr))
+    }
+
+    fn write16(&mut self, addr: u16, value: u16) -> Result<MmioWriteResult, MmioError> {
+        self.set_register(addr, value);
+        Ok(MmioWriteResult::Applied)
+    }
+
+    fn peek16(&self, addr: u16) -> Option<u16> {
+        Some(self.register(addr))
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    fn describe_registers(&self) -> Vec<&'static DeviceRegisters> {
+        vec![&MPU_REGISTERS]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configured() -> Mpu {
+        let mut mpu = Mpu::new();
+        // Region 0: ROM, read and execute.
+        mpu.set_register(0xE144, 0x0000);
+        mpu.set_register(0xE145, 0x3FFF);
+        mpu.set_register(0xE146, MPU_ATTR_ENABLE | MPU_ATTR_READ | MPU_ATTR_EXECUTE);
+        // Region 1: a read-only table inside RAM, shadowing region 2.
+        mpu.set_register(0xE147, 0x4100);
+        mpu.set_register(0xE148, 0x41FF);
+        mpu.set_register(0xE149, MPU_ATTR_ENABLE | MPU_ATTR_READ);
+        // Region 2: the rest of RAM, read and write.
+        mpu.set_register(0xE14A, 0x4000);
+        mpu.set_register(0xE14B, 0xDFFF);
+        mpu.set_register(0xE14C, MPU_ATTR_ENABLE | MPU_ATTR_READ | MPU_ATTR_WRITE);
+        mpu.set_register(MPU_CTRL, MPU_CTRL_ENABLE);
+        mpu
+    }
+
+    #[test]
+    fn mpu_identifies_itself_and_reads_back_registers() {
+        let mut mpu = configured();
+        assert_eq!(mpu.read16(MPU_BASE).unwrap(), MPU_ID);
+        assert_eq!(mpu.read16(0xE141).unwrap(), MPU_VERSION);
+        assert_eq!(mpu.read16(MPU_CTRL).unwrap(), MPU_CTRL_ENABLE);
+        assert_eq!(mpu.read16(0xE148).unwrap(), 0x41FF);
+        assert_eq!(mpu.regions()[2].base, 0x4000);
+
+        mpu.write16(MPU_FAULT_ADDR, 0x1234).unwrap();
+        mpu.write16(MPU_BASE, 0).unwrap();
+        assert_eq!(mpu.read16(MPU_FAULT_ADDR).unwrap(), 0);
+        assert_eq!(mpu.read16(MPU_BASE).unwrap(), MPU_ID);
+    }
+
+    #[test]
+    fn first_matching_region_decides() {
+        let mpu = configured();
+        assert_eq!(mpu.check(0x0100, MpuAccess::Execute), Ok(()));
+        assert_eq!(mpu.check(0x0100, MpuAccess::Read), Ok(()));
+        assert_eq!(
+            mpu.check(0x0100, MpuAccess::Write),
+            Err(FaultCode::MpuViolation)
+        );
+        assert_eq!(mpu.check(0x4000, MpuAccess::Write), Ok(()));
+        assert_eq!(mpu.check(0x4100, MpuAccess::Read), Ok(()));
+        assert_eq!(
+            mpu.check(0x4100, MpuAccess::Write),
+            Err(FaultCode::MpuViolation)
+        );
+        assert_eq!(
+            mpu.check(0x4000, MpuAccess::Execute),
+            Err(FaultCode::MpuViolation)
+        );
+    }
+
+    #[test]
+    fn uncovered_addresses_are_denied_only_while_enabled() {
+        let mut mpu = configured();
+        assert_eq!(
+            mpu.check(0xE000, MpuAccess::Read),
+            Err(FaultCode::MpuViolation)
+        );
+        mpu.set_register(MPU_CTRL, 0);
+        assert_eq!(mpu.check(0xE000, MpuAccess::Read), Ok(()));
+        assert_eq!(Mpu::new().check(0x0000, MpuAccess::Write), Ok(()));
+    }
+}
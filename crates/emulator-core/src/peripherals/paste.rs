@@ -0,0 +1,284 @@
+//! Paste buffer peripheral implementation.
+//!
+//! A read-only text source filled by the host: the web debugger copies the
+//! browser clipboard into it, and a program reads the text back one byte
+//! at a time. The host replaces the whole buffer in one step between
+//! ticks, so a program sees either the old text or the new text, never a
+//! mix, and replaying the same pastes at the same ticks replays the run.
+//!
+//! | Address  | Register  | Access | Contents                                   |
+//! | -------- | --------- | ------ | ------------------------------------------ |
+//! | `0xE160` | `ID`      | R      | [`PASTE_ID`]                               |
+//! | `0xE161` | `VERSION` | R      | [`PASTE_VERSION`]                          |
+//! | `0xE162` | `LEN`     | R      | Bytes in the buffer                        |
+//! | `0xE163` | `POS`     | RW     | Read cursor; writes are clamped to `LEN`   |
+//! | `0xE164` | `DATA`    | R      | Byte at `POS`, then `POS` advances         |
+//! | `0xE165` | `SEQ`     | R      | Count of host pastes, wrapping             |
+//! | `0xE166` | `CLEAR`   | W      | Any write empties the buffer               |
+//!
+//! Reading `DATA` at the end of the buffer returns 0 and leaves `POS`
+//! alone. A program polls `SEQ` to notice a new paste; each paste rewinds
+//! `POS` to 0. Text is stored as UTF-8, so non-ASCII characters arrive as
+//! several bytes.
+//!
+//! Core snapshots cover core state only, so a host that snapshots a run
+//! also keeps a clone of the paste buffer (it derives `serde` traits under
+//! the `serde` feature) and restores both together.
+
+use alloc::{vec, vec::Vec};
+
+use super::registers::{
+    register, DeviceRegisters,
+    RegisterAccess::{ReadOnly, ReadWrite, WriteOnly},
+};
+use crate::api::{MmioBus, MmioError, MmioWriteResult};
+
+/// Paste buffer MMIO register base address.
+pub const PASTE_BASE: u16 = 0xE160;
+
+/// Paste buffer MMIO register end address.
+pub const PASTE_END: u16 = 0xE16F;
+
+/// Paste buffer device identification constant.
+pub const PASTE_ID: u16 = 0x0CB0;
+
+/// Paste buffer device version.
+pub const PASTE_VERSION: u16 = 0x0001;
+
+/// Register holding the number of bytes in the buffer.
+pub const PASTE_LEN: u16 = 0xE162;
+
+/// Register holding the read cursor.
+pub const PASTE_POS: u16 = 0xE163;
+
+/// Register that returns the byte at the cursor and advances it.
+pub const PASTE_DATA: u16 = 0xE164;
+
+/// Register counting host pastes.
+pub const PASTE_SEQ: u16 = 0xE165;
+
+/// Register that empties the buffer on any write.
+pub const PASTE_CLEAR: u16 = 0xE166;
+
+/// Bytes kept from one paste; the rest of a longer paste is dropped.
+pub const PASTE_CAPACITY: usize = 4096;
+
+/// Paste buffer register layout.
+pub const PASTE_REGISTERS: DeviceRegisters = DeviceRegisters {
+    device: "PASTE",
+    description: "Paste buffer",
+    base: PASTE_BASE,
+    end: PASTE_END,
+    registers: &[
+        register("ID", 0, ReadOnly, "Device identifier", &[]),
+        register("VERSION", 1, ReadOnly, "Device revision", &[]),
+        register("LEN", 2, ReadOnly, "Bytes in the buffer", &[]),
+        register("POS", 3, ReadWrite, "Read cursor", &[]),
+        register("DATA", 4, ReadOnly, "Byte at POS, then POS advances", &[]),
+        register("SEQ", 5, ReadOnly, "Count of host pastes", &[]),
+        register("CLEAR", 6, WriteOnly, "Any write empties the buffer", &[]),
+    ],
+};
+
+/// Paste buffer peripheral.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct PasteBuffer {
+    data: Vec<u8>,
+    pos: u16,
+    seq: u16,
+}
+
+impl PasteBuffer {
+    /// Creates an empty paste buffer.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            data: Vec::new(),
+            pos: 0,
+            seq: 0,
+        }
+    }
+
+    /// Replaces the buffer with `bytes`, rewinds `POS` and bumps `SEQ`.
+    ///
+    /// Returns how many bytes were kept, at most [`PASTE_CAPACITY`]. Hosts
+    /// call this between ticks so the change lands at a tick boundary.
+    pub fn paste(&mut self, bytes: &[u8]) -> usize {
+        let kept = bytes.len().min(PASTE_CAPACITY);
+        self.data.clear();
+        self.data.extend_from_slice(&bytes[..kept]);
+        self.pos = 0;
+        self.seq = self.seq.wrapping_add(1);
+        kept
+    }
+
+    /// Pastes the UTF-8 bytes of `text`; see [`paste`](Self::paste).
+    pub fn paste_text(&mut self, text: &str) -> usize {
+        self.paste(text.as_bytes())
+    }
+
+    /// Returns the buffered bytes.
+    #[must_use]
+    pub fn bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the read cursor.
+    #[must_use]
+    pub const fn position(&self) -> u16 {
+        self.pos
+    }
+
+    /// Returns the number of host pastes so far, wrapping.
+    #[must_use]
+    pub const fn sequence(&self) -> u16 {
+        self.seq
+    }
+
+    /// Rewinds the read cursor, keeping the text and `SEQ`, as the core
+    /// does on reset so a reset program can read the same paste again.
+    pub const fn rewind(&mut self) {
+        self.pos = 0;
+    }
+
+    /// Empties the buffer and clears `SEQ`.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    const fn len(&self) -> u16 {
+        // Bounded by PASTE_CAPACITY.
+        self.data.len() as u16
+    }
+
+    /// The byte at the read cursor, or 0 past the end.
+    fn peek_byte(&self) -> u16 {
+        self.data
+            .get(usize::from(self.pos))
+            .map_or(0, |&byte| u16::from(byte))
+    }
+
+    fn next_byte(&mut self) -> u16 {
+        let byte = self.peek_byte();
+        if usize::from(self.pos) < self.data.len() {
+            self.pos += 1;
+        }
+        byte
+    }
+
+    /// The value a read of `addr` returns, with `DATA` not advancing.
+    fn register(&self, addr: u16) -> u16 {
+        match addr {
+            PASTE_BASE => PASTE_ID,
+            0xE161 => PASTE_VERSION,
+            PASTE_LEN => self.len(),
+            PASTE_POS => self.pos,
+            PASTE_DATA => self.peek_byte(),
+            PASTE_SEQ => self.seq,
+            _ => 0,
+        }
+    }
+}
+
+impl MmioBus for PasteBuffer {
+    fn read16(&mut self, addr: u16) -> Result<u16, MmioError> {
+        Ok(match addr {
+            PASTE_DATA => self.next_byte(),
+            _ => self.register(addr),
+        })
+    }
+
+    fn write16(&mut self, addr: u16, value: u16) -> Result<MmioWriteResult, MmioError> {
+        match addr {
+            PASTE_POS => self.pos = value.min(self.len()),
+            PASTE_CLEAR => {
+                self.data.clear();
+                self.pos = 0;
+            }
+            _ => {}
+        }
+        Ok(MmioWriteResult::Applied)
+    }
+
+    fn peek16(&self, addr: u16) -> Option<u16> {
+        Some(self.register(addr))
+    }
+
+    /// Rewinds the cursor instead of emptying the buffer: the text is host
+    /// input, so a reset program can read the same paste again.
+    fn reset(&mut self) {
+        self.rewind();
+    }
+
+    fn describe_registers(&self) -> Vec<&'static DeviceRegisters> {
+        vec![&PASTE_REGISTERS]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paste_buffer_identifies_itself() {
+        let mut paste = PasteBuffer::new();
+        assert_eq!(paste.read16(PASTE_BASE).unwrap(), PASTE_ID);
+        assert_eq!(paste.read16(0xE161).unwrap(), PASTE_VERSION);
+        assert_eq!(paste.read16(PASTE_LEN).unwrap(), 0);
+        assert_eq!(paste.read16(PASTE_DATA).unwrap(), 0);
+    }
+
+    #[test]
+    fn data_reads_pasted_bytes_in_order() {
+        let mut paste = PasteBuffer::new();
+        assert_eq!(paste.paste_text("42\n"), 3);
+        assert_eq!(paste.read16(PASTE_LEN).unwrap(), 3);
+        assert_eq!(paste.read16(PASTE_SEQ).unwrap(), 1);
+
+        let read: Vec<u16> = (0..4).map(|_| paste.read16(PASTE_DATA).unwrap()).collect();
+        assert_eq!(read, [u16::from(b'4'), u16::from(b'2'), 10, 0]);
+        assert_eq!(paste.read16(PASTE_POS).unwrap(), 3);
+
+        paste.write16(PASTE_POS, 1).unwrap();
+        assert_eq!(paste.read16(PASTE_DATA).unwrap(), u16::from(b'2'));
+        paste.write16(PASTE_POS, 0xFFFF).unwrap();
+        assert_eq!(paste.read16(PASTE_POS).unwrap(), 3);
+    }
+
+    #[test]
+    fn each_paste_replaces_the_text_and_rewinds() {
+        let mut paste = PasteBuffer::new();
+        paste.paste_text("first");
+        paste.read16(PASTE_DATA).unwrap();
+        paste.paste_text("hi");
+        assert_eq!(paste.bytes(), b"hi");
+        assert_eq!((paste.position(), paste.sequence()), (0, 2));
+
+        paste.write16(PASTE_CLEAR, 1).unwrap();
+        assert_eq!(paste.read16(PASTE_LEN).unwrap(), 0);
+        assert_eq!(paste.sequence(), 2);
+    }
+
+    #[test]
+    fn long_pastes_are_truncated() {
+        let mut paste = PasteBuffer::new();
+        assert_eq!(paste.paste(&[b'x'; PASTE_CAPACITY + 10]), PASTE_CAPACITY);
+        assert_eq!(
+            usize::from(paste.read16(PASTE_LEN).unwrap()),
+            PASTE_CAPACITY
+        );
+    }
+
+    #[test]
+    fn clones_restore_the_read_position() {
+        let mut paste = PasteBuffer::new();
+        paste.paste_text("abc");
+        paste.read16(PASTE_DATA).unwrap();
+        let saved = paste.clone();
+        paste.read16(PASTE_DATA).unwrap();
+        paste = saved;
+        assert_eq!(paste.read16(PASTE_DATA).unwrap(), u16::from(b'b'));
+    }
+}
@@ -0,0 +1,265 @@
+//! Register layout metadata for debugger views.
+//!
+//! Every peripheral describes its MMIO window as a static
+//! [`DeviceRegisters`] table and returns it from
+//! [`MmioBus::describe_registers`](crate::MmioBus::describe_registers), so
+//! the CLI's `devmap --registers` and the web debugger can label register
+//! addresses and split values into named bit fields instead of showing raw
+//! hex. The tables only describe layout; [`DeviceRegisters::decode`]
+//! splits a value the caller read from the bus, for which
+//! [`MmioBus::peek16`](crate::MmioBus::peek16) avoids read side effects.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Which directions a program may access a register in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum RegisterAccess {
+    /// Reads return device state; writes are ignored.
+    ReadOnly,
+    /// Writes act on the device; reads return 0.
+    WriteOnly,
+    /// Reads and writes both act on the device.
+    ReadWrite,
+}
+
+impl RegisterAccess {
+    /// Returns the short form used in register tables: `R`, `W` or `RW`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::ReadOnly => "R",
+            Self::WriteOnly => "W",
+            Self::ReadWrite => "RW",
+        }
+    }
+}
+
+/// A named run of bits within a register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BitField {
+    /// Field name, e.g. `ENABLE`.
+    pub name: &'static str,
+    /// Lowest bit of the field.
+    pub lsb: u8,
+    /// Number of bits in the field.
+    pub width: u8,
+}
+
+impl BitField {
+    /// Returns this field's bits of `value`, shifted down to bit 0.
+    #[must_use]
+    pub const fn extract(self, value: u16) -> u16 {
+        let mask = if self.width >= 16 {
+            u16::MAX
+        } else {
+            (1 << self.width) - 1
+        };
+        (value >> self.lsb) & mask
+    }
+}
+
+/// Layout of one device register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RegisterInfo {
+    /// Register name, e.g. `CTRL`.
+    pub name: &'static str,
+    /// Word offset from the device's base address.
+    pub offset: u16,
+    /// Access a program has to the register.
+    pub access: RegisterAccess,
+    /// One-line description of the register's contents.
+    pub description: &'static str,
+    /// Named bit fields; empty when the register holds a single value.
+    pub fields: &'static [BitField],
+}
+
+/// A device's MMIO window and its registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DeviceRegisters {
+    /// Short device name used as a register prefix, e.g. `TELE7`.
+    pub device: &'static str,
+    /// What the device is.
+    pub description: &'static str,
+    /// First address of the window.
+    pub base: u16,
+    /// Last address of the window.
+    pub end: u16,
+    /// Registers in address order; addresses not listed read as 0.
+    pub registers: &'static [RegisterInfo],
+}
+
+impl DeviceRegisters {
+    /// Returns the absolute address of `register`.
+    #[must_use]
+    pub const fn address(&self, register: &RegisterInfo) -> u16 {
+        self.base.wrapping_add(register.offset)
+    }
+
+    /// Returns the register at `addr`, if this device names one there.
+    #[must_use]
+    pub fn register_at(&self, addr: u16) -> Option<&'static RegisterInfo> {
+        let offset = addr.checked_sub(self.base)?;
+        if addr > self.end {
+            return None;
+        }
+        self.registers
+            .iter()
+            .find(|register| register.offset == offset)
+    }
+
+    /// Splits `value`, read from `addr`, into the named register's fields.
+    ///
+    /// Returns `None` when this device names no register at `addr`.
+    #[must_use]
+    pub fn decode(&self, addr: u16, value: u16) -> Option<RegisterDecode> {
+        let register = self.register_at(addr)?;
+        Some(RegisterDecode {
+            device: self.device,
+            register: register.name,
+            address: addr,
+            value,
+            fields: register
+                .fields
+                .iter()
+                .map(|field| FieldValue {
+                    name: field.name,
+                    value: field.extract(value),
+                })
+                .collect(),
+        })
+    }
+}
+
+/// One bit field's share of a register value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FieldValue {
+    /// Field name.
+    pub name: &'static str,
+    /// The field's bits, shifted down to bit 0.
+    pub value: u16,
+}
+
+/// A register value decoded against its device's layout.
+///
+/// Displays as `TELE7_CTRL: ENABLE=1, LIVE_READ=0`, or as the hex value
+/// for registers without fields.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RegisterDecode {
+    /// Owning device, e.g. `TELE7`.
+    pub device: &'static str,
+    /// Register name, e.g. `CTRL`.
+    pub register: &'static str,
+    /// Absolute register address.
+    pub address: u16,
+    /// Raw register value.
+    pub value: u16,
+    /// Field values in table order; empty when the register has no fields.
+    pub fields: Vec<FieldValue>,
+}
+
+impl fmt::Display for RegisterDecode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}_{}: ", self.device, self.register)?;
+        if self.fields.is_empty() {
+            return write!(f, "0x{:04X}", self.value);
+        }
+        for (index, field) in self.fields.iter().enumerate() {
+            if index > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}={}", field.name, field.value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Shorthand for a field in the static tables.
+pub(crate) const fn field(name: &'static str, lsb: u8, width: u8) -> BitField {
+    BitField { name, lsb, width }
+}
+
+/// Shorthand for a register in the static tables.
+pub(crate) const fn register(
+    name: &'static str,
+    offset: u16,
+    access: RegisterAccess,
+    description: &'static str,
+    fields: &'static [BitField],
+) -> RegisterInfo {
+    RegisterInfo {
+        name,
+        offset,
+        access,
+        description,
+        fields,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+    use crate::peripherals::{
+        CONSOLE_REGISTERS, DMA_REGISTERS, MPU_REGISTERS, PASTE_REGISTERS, TELE7_REGISTERS,
+    };
+
+    const ALL: [&DeviceRegisters; 5] = [
+        &TELE7_REGISTERS,
+        &CONSOLE_REGISTERS,
+        &MPU_REGISTERS,
+        &DMA_REGISTERS,
+        &PASTE_REGISTERS,
+    ];
+
+    #[test]
+    fn tables_stay_inside_their_windows() {
+        for device in ALL {
+            assert_eq!(device.registers[0].name, "ID", "{}", device.device);
+            assert_eq!(device.registers[1].name, "VERSION", "{}", device.device);
+            let mut last = None;
+            for register in device.registers {
+                assert!(device.address(register) <= device.end, "{}", register.name);
+                assert!(
+                    last < Some(register.offset),
+                    "{} out of order",
+                    register.name
+                );
+                last = Some(register.offset);
+                for field in register.fields {
+                    assert!(field.width > 0 && field.lsb + field.width <= 16);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fields_extract_their_bits() {
+        let ctrl = TELE7_REGISTERS.register_at(0xE122).unwrap();
+        assert_eq!(ctrl.name, "CTRL");
+        let decoded: Vec<_> = ctrl
+            .fields
+            .iter()
+            .map(|field| (field.name, field.extract(0b11)))
+            .collect();
+        assert_eq!(decoded, [("ENABLE", 1), ("LIVE_READ", 1)]);
+        assert_eq!(field("WIDE", 0, 16).extract(0xBEEF), 0xBEEF);
+        assert!(TELE7_REGISTERS.register_at(0xE128).is_none());
+        assert!(TELE7_REGISTERS.register_at(0xE130).is_none());
+    }
+    #[test]
+    fn decode_names_the_register_and_its_fields() {
+        let decoded = TELE7_REGISTERS.decode(0xE122, 0b01).unwrap();
+        assert_eq!(decoded.to_string(), "TELE7_CTRL: ENABLE=1, LIVE_READ=0");
+        let border = TELE7_REGISTERS.decode(0xE125, 3).unwrap();
+        assert_eq!(border.to_string(), "TELE7_BORDER: 0x0003");
+        assert!(TELE7_REGISTERS.decode(0xE128, 0).is_none());
+    }
+}
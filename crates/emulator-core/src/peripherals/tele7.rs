@@ -2,8 +2,24 @@
 //!
 //! Provides MMIO interface for the TELE-7 40x25 character display.
 
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
 use crate::api::{MmioBus, MmioError, MmioWriteResult};
 
+use super::registers::{
+    field, register, DeviceRegisters,
+    RegisterAccess::{ReadOnly, ReadWrite},
+};
+
+use super::console::{DebugConsole, CONSOLE_BASE, CONSOLE_END};
+use super::dma::{DmaController, DMA_BASE, DMA_END};
+use super::paste::{PasteBuffer, PASTE_BASE, PASTE_END};
+use crate::CoreState;
+
 /// TELE-7 MMIO register base address.
 pub const TELE7_BASE: u16 = 0xE120;
 
@@ -16,14 +32,53 @@ pub const TELE7_ID: u16 = 0x0745;
 /// TELE-7 device version.
 pub const TELE7_VERSION: u16 = 0x0003;
 
+/// TELE-7 register layout.
+pub const TELE7_REGISTERS: DeviceRegisters = DeviceRegisters {
+    device: "TELE7",
+    description: "TELE-7 textual display",
+    base: TELE7_BASE,
+    end: TELE7_END,
+    registers: &[
+        register("ID", 0, ReadOnly, "Device identifier", &[]),
+        register("VERSION", 1, ReadOnly, "Device revision", &[]),
+        register(
+            "CTRL",
+            2,
+            ReadWrite,
+            "Control bits",
+            &[field("ENABLE", 0, 1), field("LIVE_READ", 1, 1)],
+        ),
+        register(
+            "STATUS",
+            3,
+            ReadOnly,
+            "Status flags",
+            &[
+                field("ENABLED", 0, 1),
+                field("PAGE_MAPPED", 1, 1),
+                field("FAULT", 2, 1),
+                field("BLINK_PHASE", 3, 1),
+            ],
+        ),
+        register(
+            "PAGE_BASE",
+            4,
+            ReadWrite,
+            "Base address of the page buffer",
+            &[],
+        ),
+        register("BORDER", 5, ReadWrite, "Border colour, 0-7", &[]),
+        register("ORIGIN", 6, ReadWrite, "First displayed row, 0-24", &[]),
+        register("BLINK_DIV", 7, ReadWrite, "Ticks per blink phase", &[]),
+    ],
+};
+
 const PAGE_SIZE_WORDS: usize = 500;
 #[allow(clippy::cast_possible_truncation)]
 const PAGE_SIZE_BYTES: u16 = PAGE_SIZE_WORDS as u16 * 2;
 
-#[allow(dead_code)]
 const COLS: usize = 40;
 
-#[allow(dead_code)]
 const ROWS: usize = 25;
 
 const DEFAULT_BLINK_DIV: u16 = 50;
@@ -153,7 +208,7 @@ impl Tele7State {
 /// TELE-7 Textual Display Device peripheral.
 ///
 /// Implements the `MmioBus` trait for integration with the emulator core.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Tele7Peripheral {
     #[allow(dead_code)]
     config: Tele7Config,
@@ -194,6 +249,22 @@ impl Tele7Peripheral {
         self.state = Tele7State::default();
     }
 
+    /// Returns the register at `addr`; unmapped registers read as 0.
+    #[must_use]
+    pub fn register(&self, addr: u16) -> u16 {
+        match addr {
+            0xE120 => TELE7_ID,
+            0xE121 => TELE7_VERSION,
+            0xE122 => self.state.ctrl & 0x07,
+            0xE123 => self.state.status_bits(),
+            0xE124 => self.state.page_base,
+            0xE125 => self.state.border,
+            0xE126 => self.state.origin,
+            0xE127 => self.state.blink_div,
+            _ => 0,
+        }
+    }
+
     #[allow(clippy::missing_const_for_fn)]
     fn validate_page_base(&mut self, addr: u16) {
         let end = addr.wrapping_add(PAGE_SIZE_BYTES);
@@ -231,21 +302,39 @@ impl Tele7Peripheral {
         }
         buffer
     }
+
+    /// Renders the visible screen as plain text.
+    ///
+    /// Rows appear in display order, starting from the ORIGIN row. Control
+    /// codes and codes outside the printable range 0x20-0x7E show as
+    /// spaces, and colour, mosaic and flash state are ignored. Trailing
+    /// spaces on each row and trailing blank rows are dropped, so a disabled
+    /// or faulted display renders as an empty string.
+    #[must_use]
+    pub fn render_text(&self, memory: &[u8]) -> String {
+        if !self.state.is_enabled() || !self.state.page_mapped() {
+            return String::new();
+        }
+        let origin = usize::from(self.state.origin());
+        let rows: Vec<String> = (0..ROWS)
+            .map(|row| {
+                let start = ((origin + row) % ROWS) * COLS;
+                let line: String = (start..start + COLS)
+                    .map(|idx| match self.read_page_byte(memory, idx) {
+                        code @ 0x20..=0x7E => char::from(code),
+                        _ => ' ',
+                    })
+                    .collect();
+                line.trim_end().to_string()
+            })
+            .collect();
+        rows.join("\n").trim_end().to_string()
+    }
 }
 
 impl MmioBus for Tele7Peripheral {
     fn read16(&mut self, addr: u16) -> Result<u16, MmioError> {
-        match addr {
-            0xE120 => Ok(TELE7_ID),
-            0xE121 => Ok(TELE7_VERSION),
-            0xE122 => Ok(self.state.ctrl & 0x07),
-            0xE123 => Ok(self.state.status_bits()),
-            0xE124 => Ok(self.state.page_base),
-            0xE125 => Ok(self.state.border),
-            0xE126 => Ok(self.state.origin),
-            0xE127 => Ok(self.state.blink_div),
-            _ => Ok(0),
-        }
+        Ok(self.register(addr))
     }
 
     fn write16(&mut self, addr: u16, value: u16) -> Result<MmioWriteResult, MmioError> {
@@ -269,11 +358,38 @@ impl MmioBus for Tele7Peripheral {
         }
         Ok(MmioWriteResult::Applied)
     }
+
+    fn on_tick(&mut self) {
+        self.state.tick();
+    }
+
+    fn advance_ticks(&mut self, ticks: u32) {
+        self.state.tick_count = self.state.tick_count.wrapping_add(ticks);
+    }
+
+    fn peek16(&self, addr: u16) -> Option<u16> {
+        Some(self.register(addr))
+    }
+
+    fn reset(&mut self) {
+        Self::reset(self);
+    }
+
+    fn describe_registers(&self) -> Vec<&'static DeviceRegisters> {
+        vec![&TELE7_REGISTERS]
+    }
 }
 
 /// Composite MMIO bus supporting multiple peripheral devices.
+///
+/// Cloning copies every attached peripheral's state, so hosts can
+/// checkpoint the bus alongside a [`CoreState`](crate::CoreState).
+#[derive(Clone)]
 pub struct CompositeMmio {
     tele7: Option<Tele7Peripheral>,
+    console: Option<DebugConsole>,
+    dma: Option<DmaController>,
+    paste: Option<PasteBuffer>,
 }
 
 impl Default for CompositeMmio {
@@ -286,7 +402,12 @@ impl CompositeMmio {
     /// Creates a new empty composite MMIO bus.
     #[must_use]
     pub const fn new() -> Self {
-        Self { tele7: None }
+        Self {
+            tele7: None,
+            console: None,
+            dma: None,
+            paste: None,
+        }
     }
 
     /// Adds a TELE-7 peripheral to the bus.
@@ -310,10 +431,73 @@ impl CompositeMmio {
         self.tele7.as_mut()
     }
 
+    /// Adds a debug console to the bus.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn with_console(mut self, console: DebugConsole) -> Self {
+        self.console = Some(console);
+        self
+    }
+
+    /// Returns a reference to the debug console, if present.
+    #[must_use]
+    pub const fn console(&self) -> Option<&DebugConsole> {
+        self.console.as_ref()
+    }
+
+    /// Returns a mutable reference to the debug console, if present.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn console_mut(&mut self) -> Option<&mut DebugConsole> {
+        self.console.as_mut()
+    }
+
+    /// Adds a DMA controller to the bus.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn with_dma(mut self, dma: DmaController) -> Self {
+        self.dma = Some(dma);
+        self
+    }
+
+    /// Returns a reference to the DMA controller, if present.
+    #[must_use]
+    pub const fn dma(&self) -> Option<&DmaController> {
+        self.dma.as_ref()
+    }
+
+    /// Returns a mutable reference to the DMA controller, if present.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn dma_mut(&mut self) -> Option<&mut DmaController> {
+        self.dma.as_mut()
+    }
+
+    /// Adds a paste buffer to the bus.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn with_paste(mut self, paste: PasteBuffer) -> Self {
+        self.paste = Some(paste);
+        self
+    }
+
+    /// Returns a reference to the paste buffer, if present.
+    #[must_use]
+    pub const fn paste(&self) -> Option<&PasteBuffer> {
+        self.paste.as_ref()
+    }
+
+    /// Returns a mutable reference to the paste buffer, if present.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn paste_mut(&mut self) -> Option<&mut PasteBuffer> {
+        self.paste.as_mut()
+    }
+
     /// Advances tick counter for all peripherals.
     pub fn tick(&mut self) {
         if let Some(t7) = self.tele7.as_mut() {
-            t7.state_mut().tick();
+            t7.on_tick();
         }
     }
 }
@@ -325,7 +509,22 @@ impl MmioBus for CompositeMmio {
                 return t7.read16(addr);
             }
         }
-        Ok(0)
+        if let Some(ref mut console) = self.console {
+            if (CONSOLE_BASE..=CONSOLE_END).contains(&addr) {
+                return console.read16(addr);
+            }
+        }
+        if let Some(ref mut dma) = self.dma {
+            if (DMA_BASE..=DMA_END).contains(&addr) {
+                return dma.read16(addr);
+            }
+        }
+        if let Some(ref mut paste) = self.paste {
+            if (PASTE_BASE..=PASTE_END).contains(&addr) {
+                return paste.read16(addr);
+            }
+        }
+        Err(MmioError::Unmapped)
     }
 
     fn write16(&mut self, addr: u16, value: u16) -> Result<MmioWriteResult, MmioError> {
@@ -334,8 +533,95 @@ impl MmioBus for CompositeMmio {
                 return t7.write16(addr, value);
             }
         }
+        if let Some(ref mut console) = self.console {
+            if (CONSOLE_BASE..=CONSOLE_END).contains(&addr) {
+                return console.write16(addr, value);
+            }
+        }
+        if let Some(ref mut dma) = self.dma {
+            if (DMA_BASE..=DMA_END).contains(&addr) {
+                return dma.write16(addr, value);
+            }
+        }
+        if let Some(ref mut paste) = self.paste {
+            if (PASTE_BASE..=PASTE_END).contains(&addr) {
+                return paste.write16(addr, value);
+            }
+        }
         Ok(MmioWriteResult::Applied)
     }
+
+    fn on_tick(&mut self) {
+        self.tick();
+    }
+
+    fn advance_ticks(&mut self, ticks: u32) {
+        if let Some(t7) = self.tele7.as_mut() {
+            t7.advance_ticks(ticks);
+        }
+    }
+
+    fn on_tick_memory(&mut self, state: &mut CoreState) {
+        if let Some(dma) = self.dma.as_mut() {
+            dma.transfer(state);
+        }
+    }
+
+    fn peek16(&self, addr: u16) -> Option<u16> {
+        if let Some(t7) = &self.tele7 {
+            if (0xE120..=0xE12F).contains(&addr) {
+                return t7.peek16(addr);
+            }
+        }
+        if let Some(console) = &self.console {
+            if (CONSOLE_BASE..=CONSOLE_END).contains(&addr) {
+                return console.peek16(addr);
+            }
+        }
+        if let Some(dma) = &self.dma {
+            if (DMA_BASE..=DMA_END).contains(&addr) {
+                return dma.peek16(addr);
+            }
+        }
+        if let Some(paste) = &self.paste {
+            if (PASTE_BASE..=PASTE_END).contains(&addr) {
+                return paste.peek16(addr);
+            }
+        }
+        Some(0)
+    }
+
+    fn reset(&mut self) {
+        if let Some(t7) = self.tele7.as_mut() {
+            MmioBus::reset(t7);
+        }
+        if let Some(console) = self.console.as_mut() {
+            MmioBus::reset(console);
+        }
+        if let Some(dma) = self.dma.as_mut() {
+            MmioBus::reset(dma);
+        }
+        if let Some(paste) = self.paste.as_mut() {
+            MmioBus::reset(paste);
+        }
+    }
+
+    fn describe_registers(&self) -> Vec<&'static DeviceRegisters> {
+        let mut devices = Vec::new();
+        if let Some(t7) = &self.tele7 {
+            devices.extend(t7.describe_registers());
+        }
+        if let Some(console) = &self.console {
+            devices.extend(console.describe_registers());
+        }
+        if let Some(dma) = &self.dma {
+            devices.extend(dma.describe_registers());
+        }
+        if let Some(paste) = &self.paste {
+            devices.extend(paste.describe_registers());
+        }
+        devices
+    }
 }
 
 #[cfg(test)]
@@ -399,6 +685,27 @@ mod tests {
         assert_eq!(t7.read16(0xE121).unwrap(), TELE7_VERSION);
     }
 
+    #[test]
+    fn tele7_render_text_follows_origin_and_skips_control_codes() {
+        let mut memory = vec![0u8; 0x10000];
+        let mut t7 = Tele7Peripheral::default();
+        memory[0x4000..0x4004].copy_from_slice(&[0x01, b'H', b'I', 0x80]);
+        memory[0x4000 + 24 * 40..0x4000 + 24 * 40 + 2].copy_from_slice(b"Z ");
+        assert_eq!(t7.render_text(&memory), "");
+
+        t7.write16(0xE122, 0x01).unwrap();
+        let text = t7.render_text(&memory);
+        assert!(text.starts_with(" HI\n\n"));
+        assert!(text.ends_with("\nZ"));
+        assert_eq!(text.lines().count(), 25);
+
+        t7.write16(0xE126, 24).unwrap();
+        assert_eq!(t7.render_text(&memory), "Z\n HI");
+
+        t7.write16(0xE124, 0x4001).unwrap();
+        assert_eq!(t7.render_text(&memory), "");
+    }
+
     #[test]
     fn tele7_status_bits() {
         let mut t7 = Tele7Peripheral::default();
@@ -493,6 +800,69 @@ mod tests {
         assert!(mmio.tele7().unwrap().state().is_enabled());
     }
 
+    #[test]
+    fn composite_mmio_delegates_to_console() {
+        use crate::peripherals::{CONSOLE_DATA, CONSOLE_ID};
+
+        let mut mmio = CompositeMmio::new();
+        mmio.write16(CONSOLE_DATA, u16::from(b'!')).unwrap();
+        assert!(mmio.console().is_none());
+
+        let mut mmio = CompositeMmio::new().with_console(DebugConsole::new());
+        assert_eq!(mmio.read16(CONSOLE_BASE).unwrap(), CONSOLE_ID);
+        mmio.write16(CONSOLE_DATA, u16::from(b'!')).unwrap();
+        assert_eq!(mmio.console_mut().unwrap().drain(), b"!");
+    }
+
+    #[test]
+    fn composite_mmio_describes_attached_devices() {
+        let mmio = CompositeMmio::new()
+            .with_paste(PasteBuffer::new())
+            .with_tele7(Tele7Peripheral::default());
+        let devices: Vec<_> = mmio
+            .describe_registers()
+            .iter()
+            .map(|device| device.device)
+            .collect();
+        assert_eq!(devices, ["TELE7", "PASTE"]);
+    }
+
+    #[test]
+    fn composite_mmio_reset_matches_power_on() {
+        use crate::peripherals::{CONSOLE_DATA, DMA_SRC, PASTE_DATA};
+
+        let power_on = || {
+            let mut paste = PasteBuffer::new();
+            paste.paste_text("hi");
+            CompositeMmio::new()
+                .with_tele7(Tele7Peripheral::default())
+                .with_console(DebugConsole::new())
+                .with_dma(DmaController::new())
+                .with_paste(paste)
+        };
+        let mut mmio = power_on();
+        mmio.write16(0xE122, 0x07).unwrap();
+        mmio.write16(0xE126, 40).unwrap();
+        mmio.write16(0xE127, 3).unwrap();
+        mmio.write16(CONSOLE_DATA, u16::from(b'!')).unwrap();
+        mmio.write16(DMA_SRC, 0x4000).unwrap();
+        mmio.read16(PASTE_DATA).unwrap();
+        mmio.advance_ticks(5);
+
+        MmioBus::reset(&mut mmio);
+
+        let fresh = power_on();
+        for addr in TELE7_BASE..=PASTE_END {
+            assert_eq!(mmio.peek16(addr), fresh.peek16(addr), "{addr:#06X}");
+        }
+        assert_eq!(
+            format!("{:?}", mmio.tele7().unwrap().state()),
+            format!("{:?}", fresh.tele7().unwrap().state())
+        );
+        assert!(mmio.console().unwrap().output().is_empty());
+        assert_eq!(mmio.paste().unwrap().bytes(), b"hi");
+    }
+
     #[test]
     fn composite_mmio_tick() {
         let mut mmio =
@@ -502,6 +872,21 @@ mod tests {
         // Should not panic
     }
 
+    #[test]
+    fn end_tick_advances_blink_phase() {
+        use crate::{end_tick, CoreState};
+
+        let mut state = CoreState::default();
+        let mut mmio =
+            CompositeMmio::new().with_tele7(Tele7Peripheral::new(Tele7Config::default()));
+
+        for _ in 0..DEFAULT_BLINK_DIV {
+            end_tick(&mut state, &mut mmio, None);
+        }
+
+        assert!(mmio.tele7().unwrap().state().blink_phase());
+    }
+
     #[test]
     fn tele7_execution_flow() {
         use crate::{step_one, CoreConfig, CoreState, GeneralRegister, StepOutcome};
@@ -566,6 +951,11 @@ mod tests {
 
         // Execute HALT
         let outcome = step_one(&mut state, &mut mmio, &config);
-        assert!(matches!(outcome, StepOutcome::HaltedForTick));
+        assert!(matches!(
+            outcome,
+            StepOutcome::HaltedForTick {
+                reason: crate::HaltReason::Instruction
+            }
+        ));
     }
 }
@@ -0,0 +1,99 @@
+//! Canonical trap cause and software service numbers.
+//!
+//! `TRAP` and `SWI` always dispatch with CAUSE [`TRAP_CAUSE_SOFTWARE`], so
+//! the core itself cannot tell services apart. Programs pick one by loading a
+//! service number into [`SERVICE_REGISTER`] before `SWI`, with arguments in
+//! R2 and R3, and the handler answers with a status in R0 ([`SVC_OK`] or
+//! [`SVC_UNKNOWN`]). R0 cannot carry the number because dispatch overwrites
+//! it with CAUSE.
+//!
+//! The table is the single source for these numbers: the assembler
+//! generates its `services.n1` include from [`SERVICES`], `nullbyte-asm
+//! version --json` lists it, and the stdlib `swi_dispatch` handler is tested
+//! against it.
+
+/// CAUSE value `TRAP` and `SWI` dispatch with.
+pub const TRAP_CAUSE_SOFTWARE: u16 = 0x0000;
+
+/// General register that holds the service number at `SWI`.
+pub const SERVICE_REGISTER: u8 = 1;
+
+/// Does nothing and returns [`SVC_OK`].
+pub const SVC_NOP: u16 = 0x0000;
+/// Writes the low byte of R2 to the debug console.
+pub const SVC_PUTC: u16 = 0x0001;
+/// Records the low byte of R2 as the debug console exit status.
+pub const SVC_EXIT: u16 = 0x0002;
+
+/// Status returned in R0 by a service that completed.
+pub const SVC_OK: u16 = 0x0000;
+/// Status returned in R0 for a service number with no handler.
+pub const SVC_UNKNOWN: u16 = 0xFFFF;
+
+/// One software service a handler may provide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Service {
+    /// Symbolic name, used as the `.equ` name in assembly.
+    pub name: &'static str,
+    /// Value loaded into [`SERVICE_REGISTER`].
+    pub number: u16,
+    /// One-line description, including the arguments it reads.
+    pub summary: &'static str,
+}
+
+/// All services, in number order.
+pub const SERVICES: &[Service] = &[
+    Service {
+        name: "SVC_NOP",
+        number: SVC_NOP,
+        summary: "Do nothing",
+    },
+    Service {
+        name: "SVC_PUTC",
+        number: SVC_PUTC,
+        summary: "Write the low byte of R2 to the debug console",
+    },
+    Service {
+        name: "SVC_EXIT",
+        number: SVC_EXIT,
+        summary: "Record the low byte of R2 as the exit status",
+    },
+];
+
+/// Named values that are not services but belong in the same include:
+/// the trap cause and the statuses a handler returns.
+pub const SERVICE_CONSTANTS: &[(&str, u16)] = &[
+    ("TRAP_CAUSE_SOFTWARE", TRAP_CAUSE_SOFTWARE),
+    ("SVC_OK", SVC_OK),
+    ("SVC_UNKNOWN", SVC_UNKNOWN),
+];
+
+/// Looks up a service by its number.
+#[must_use]
+pub fn find_service(number: u16) -> Option<&'static Service> {
+    SERVICES.iter().find(|service| service.number == number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_service, SERVICES, SERVICE_CONSTANTS, SVC_EXIT};
+
+    #[test]
+    fn service_numbers_are_dense_and_names_unique() {
+        for (index, service) in SERVICES.iter().enumerate() {
+            assert_eq!(usize::from(service.number), index, "{}", service.name);
+        }
+        let mut names: alloc::vec::Vec<_> = SERVICES
+            .iter()
+            .map(|service| service.name)
+            .chain(SERVICE_CONSTANTS.iter().map(|(name, _)| *name))
+            .collect();
+        let count = names.len();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), count);
+
+        assert_eq!(find_service(SVC_EXIT).unwrap().name, "SVC_EXIT");
+        assert!(find_service(0x00FF).is_none());
+    }
+}
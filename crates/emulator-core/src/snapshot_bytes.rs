@@ -0,0 +1,409 @@
+//! Compact binary encoding for [`CoreSnapshot`].
+//!
+//! Hosts with `serde` can serialize snapshots in any format they like; this
+//! encoding exists so that std-only tools (the assembler's test fixtures in
+//! particular) can save and load machine state without extra dependencies.
+//!
+//! Layout, all multi-byte integers big-endian like the rest of the machine:
+//!
+//! | Field | Size |
+//! |-------|------|
+//! | magic `N1SN` | 4 |
+//! | snapshot version | 2 |
+//! | profile (`0=Authority`, `1=Restricted`) | 1 |
+//! | `R0..R7`, `PC`, `SP`, `FLAGS`, `TICK`, `CAP`, `CAUSE`, `EVP` | 15 × 2 |
+//! | memory | 65 536 |
+//! | event queue entries | [`EVENT_QUEUE_CAPACITY`] |
+//! | event queue length, run-state tag, latched fault code | 3 × 1 |
+//! | denied MMIO write count | 2 |
+//! | timing tag (`0=v1`, `1=fast-io`, `2=custom`), version 2 and later | 1 |
+//! | custom cycle costs in [`CYCLE_COST_TABLE`] order, tag 2 only | [`CYCLE_COST_KIND_COUNT`](crate::CYCLE_COST_KIND_COUNT) × 2 |
+//! | open-bus tag (`0=zero`, `1=ones`, `2=last`), version 3 and later | 1 |
+//! | MMIO bus latch, version 3 and later | 2 |
+//! | MPU `CTRL` through `REGION3_ATTR` in register order, version 4 only | 14 × 2 |
+//!
+//! Version 1 snapshots carry no timing section and decode as
+//! [`TimingModel::V1`]; encoding a snapshot as version 1 drops the timing.
+//! Versions 1 and 2 carry no open-bus section and decode as
+//! [`OpenBus::Zero`] with a zero latch. Versions 1 to 3 carry no MPU
+//! section and decode with the MPU disabled and its regions cleared.
+
+use alloc::vec::Vec;
+
+use thiserror::Error;
+
+use crate::{
+    CanonicalStateLayout, CoreProfile, CoreSnapshot, CycleCostTable, Mpu, OpenBus, SnapshotVersion,
+    TimingModel, ADDRESS_SPACE_BYTES, CYCLE_COST_TABLE, EVENT_QUEUE_CAPACITY,
+    GENERAL_REGISTER_COUNT, MPU_CTRL, MPU_END, MPU_FAULT_ADDR,
+};
+
+/// Leading bytes of every encoded snapshot.
+pub const SNAPSHOT_MAGIC: [u8; 4] = *b"N1SN";
+
+/// Failures decoding a binary snapshot with [`CoreSnapshot::from_bytes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+pub enum SnapshotDecodeError {
+    /// Input did not start with [`SNAPSHOT_MAGIC`].
+    #[error("not a snapshot: missing N1SN header")]
+    BadMagic,
+    /// Snapshot version is not known to this build.
+    #[error("unsupported snapshot version: {0}")]
+    UnsupportedVersion(u16),
+    /// Profile byte was outside the defined encoding domain.
+    #[error("invalid profile tag: {0}")]
+    InvalidProfile(u8),
+    /// Timing tag was outside the defined encoding domain.
+    #[error("invalid timing tag: {0}")]
+    InvalidTiming(u8),
+    /// Open-bus tag was outside the defined encoding domain.
+    #[error("invalid open-bus tag: {0}")]
+    InvalidOpenBus(u8),
+    /// Input ended before the named field.
+    #[error("snapshot truncated at {0}")]
+    Truncated(&'static str),
+    /// Input continued past the last field.
+    #[error("{0} unexpected trailing byte(s) after snapshot")]
+    TrailingBytes(usize),
+}
+
+impl CoreSnapshot {
+    /// Encodes this snapshot in the binary layout described in the
+    /// [module docs](crate::snapshot_bytes).
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let state = &self.state;
+        let mut out = Vec::with_capacity(64 + ADDRESS_SPACE_BYTES + EVENT_QUEUE_CAPACITY);
+        out.extend_from_slice(&SNAPSHOT_MAGIC);
+        out.extend_from_slice(&(self.version as u16).to_be_bytes());
+        out.push(match state.profile {
+            CoreProfile::Authority => 0,
+            CoreProfile::Restricted => 1,
+        });
+        let special = [
+            state.pc,
+            state.sp,
+            state.flags,
+            state.tick,
+            state.cap,
+            state.cause,
+            state.evp,
+        ];
+        for value in state.gpr.iter().chain(&special) {
+            out.extend_from_slice(&value.to_be_bytes());
+        }
+        out.extend_from_slice(&state.memory);
+        out.extend_from_slice(&state.event_queue);
+        out.push(state.event_queue_len);
+        out.push(state.run_state_tag);
+        out.push(state.latched_fault_code);
+        out.extend_from_slice(&state.mmio_denied_write_count.to_be_bytes());
+        if self.version != SnapshotVersion::V1 {
+            match state.timing {
+                TimingModel::V1 => out.push(0),
+                TimingModel::FastIo => out.push(1),
+                TimingModel::Custom(table) => {
+                    out.push(2);
+                    for (_, cycles) in table.entries() {
+                        out.extend_from_slice(&cycles.to_be_bytes());
+                    }
+                }
+            }
+        }
+        if matches!(self.version, SnapshotVersion::V3 | SnapshotVersion::V4) {
+            out.push(match state.open_bus {
+                OpenBus::Zero => 0,
+                OpenBus::Ones => 1,
+                OpenBus::LastValue => 2,
+            });
+            out.extend_from_slice(&state.mmio_bus_latch.to_be_bytes());
+        }
+        if self.version == SnapshotVersion::V4 {
+            for addr in MPU_CTRL..=MPU_END {
+                out.extend_from_slice(&state.mpu.register(addr).to_be_bytes());
+            }
+        }
+        out
+    }
+
+    /// Decodes a snapshot produced by [`CoreSnapshot::to_bytes`].
+    ///
+    /// Only the framing is checked here; layout invariants such as the
+    /// run-state tag are validated by [`CoreSnapshot::try_into_core_state`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SnapshotDecodeError`] when the header, version, profile,
+    /// timing or open-bus tag is unrecognised or the input length does not
+    /// match the layout.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotDecodeError> {
+        let mut reader = Reader { bytes };
+        if reader.take(SNAPSHOT_MAGIC.len(), "magic")? != SNAPSHOT_MAGIC {
+            return Err(SnapshotDecodeError::BadMagic);
+        }
+        let raw_version = reader.u16("version")?;
+        let version = SnapshotVersion::from_u16(raw_version)
+            .ok_or(SnapshotDecodeError::UnsupportedVersion(raw_version))?;
+        let profile = match reader.u8("profile")? {
+            0 => CoreProfile::Authority,
+            1 => CoreProfile::Restricted,
+            other => return Err(SnapshotDecodeError::InvalidProfile(other)),
+        };
+
+        let mut gpr = [0; GENERAL_REGISTER_COUNT];
+        for value in &mut gpr {
+            *value = reader.u16("registers")?;
+        }
+        let pc = reader.u16("registers")?;
+        let sp = reader.u16("registers")?;
+        let flags = reader.u16("registers")?;
+        let tick = reader.u16("registers")?;
+        let cap = reader.u16("registers")?;
+        let cause = reader.u16("registers")?;
+        let evp = reader.u16("registers")?;
+
+        let memory = reader.take(ADDRESS_SPACE_BYTES, "memory")?.into();
+        let mut event_queue = [0; EVENT_QUEUE_CAPACITY];
+        event_queue.copy_from_slice(reader.take(EVENT_QUEUE_CAPACITY, "event queue")?);
+        let event_queue_len = reader.u8("event queue")?;
+        let run_state_tag = reader.u8("run state")?;
+        let latched_fault_code = reader.u8("run state")?;
+        let mmio_denied_write_count = reader.u16("MMIO counters")?;
+        let timing = match version {
+            SnapshotVersion::V1 => TimingModel::V1,
+            SnapshotVersion::V2 | SnapshotVersion::V3 | SnapshotVersion::V4 => {
+                match reader.u8("timing")? {
+                    0 => TimingModel::V1,
+                    1 => TimingModel::FastIo,
+                    2 => {
+                        let mut table = CycleCostTable::V1;
+                        for (kind, _) in CYCLE_COST_TABLE {
+                            table = table.with_cost(*kind, reader.u16("timing")?);
+                        }
+                        TimingModel::Custom(table)
+                    }
+                    other => return Err(SnapshotDecodeError::InvalidTiming(other)),
+                }
+            }
+        };
+        let (open_bus, mmio_bus_latch) = match version {
+            SnapshotVersion::V1 | SnapshotVersion::V2 => (OpenBus::Zero, 0),
+            SnapshotVersion::V3 | SnapshotVersion::V4 => {
+                let open_bus = match reader.u8("open bus")? {
+                    0 => OpenBus::Zero,
+                    1 => OpenBus::Ones,
+                    2 => OpenBus::LastValue,
+                    other => return Err(SnapshotDecodeError::InvalidOpenBus(other)),
+                };
+                (open_bus, reader.u16("open bus")?)
+            }
+        };
+        let mut mpu = Mpu::new();
+        if version == SnapshotVersion::V4 {
+            for addr in MPU_CTRL..=MPU_END {
+                let value = reader.u16("MPU")?;
+                if addr == MPU_FAULT_ADDR {
+                    mpu.record_violation(value);
+                } else {
+                    mpu.set_register(addr, value);
+                }
+            }
+        }
+        if !reader.bytes.is_empty() {
+            return Err(SnapshotDecodeError::TrailingBytes(reader.bytes.len()));
+        }
+
+        Ok(Self {
+            version,
+            state: CanonicalStateLayout {
+                profile,
+                gpr,
+                pc,
+                sp,
+                flags,
+                tick,
+                cap,
+                cause,
+                evp,
+                memory,
+                event_queue,
+                event_queue_len,
+                run_state_tag,
+                latched_fault_code,
+                mmio_denied_write_count,
+                timing,
+                open_bus,
+                mmio_bus_latch,
+                mpu,
+            },
+        })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    const fn take(
+        &mut self,
+        len: usize,
+        field: &'static str,
+    ) -> Result<&'a [u8], SnapshotDecodeError> {
+        if self.bytes.len() < len {
+            return Err(SnapshotDecodeError::Truncated(field));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self, field: &'static str) -> Result<u8, SnapshotDecodeError> {
+        Ok(self.take(1, field)?[0])
+    }
+
+    fn u16(&mut self, field: &'static str) -> Result<u16, SnapshotDecodeError> {
+        let pair = self.take(2, field)?;
+        Ok(u16::from_be_bytes([pair[0], pair[1]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SnapshotDecodeError, SNAPSHOT_MAGIC};
+    use crate::{
+        CoreProfile, CoreSnapshot, CoreState, CycleCostKind, CycleCostTable, FaultCode,
+        GeneralRegister, Mpu, OpenBus, RunState, SnapshotVersion, TimingModel, MPU_CTRL,
+    };
+
+    fn sample_state() -> CoreState {
+        let mut state = CoreState {
+            profile: CoreProfile::Restricted,
+            ..CoreState::default()
+        };
+        state.arch.set_gpr(GeneralRegister::R5, 0xBEEF);
+        state.arch.set_pc(0x0120);
+        state.memory[0x4000] = 0x42;
+        state.event_queue.enqueue(7).unwrap();
+        state.run_state = RunState::FaultLatched(FaultCode::IllegalEncoding);
+        state
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let snapshot = CoreSnapshot::from_core_state(crate::SnapshotVersion::V2, &sample_state());
+        let bytes = snapshot.to_bytes();
+
+        assert_eq!(bytes[..4], SNAPSHOT_MAGIC);
+        let decoded = CoreSnapshot::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, snapshot);
+        assert_eq!(decoded.try_into_core_state().unwrap(), sample_state());
+    }
+
+    #[test]
+    fn rejects_bad_framing() {
+        let bytes =
+            CoreSnapshot::from_core_state(crate::SnapshotVersion::V2, &sample_state()).to_bytes();
+
+        assert_eq!(
+            CoreSnapshot::from_bytes(b"NOPE"),
+            Err(SnapshotDecodeError::BadMagic)
+        );
+        assert_eq!(
+            CoreSnapshot::from_bytes(&bytes[..100]),
+            Err(SnapshotDecodeError::Truncated("memory"))
+        );
+
+        let mut future = bytes.clone();
+        future[5] = 9;
+        assert_eq!(
+            CoreSnapshot::from_bytes(&future),
+            Err(SnapshotDecodeError::UnsupportedVersion(9))
+        );
+
+        let mut bad_timing = bytes.clone();
+        *bad_timing.last_mut().unwrap() = 7;
+        assert_eq!(
+            CoreSnapshot::from_bytes(&bad_timing),
+            Err(SnapshotDecodeError::InvalidTiming(7))
+        );
+
+        let mut padded = bytes;
+        padded.push(0);
+        assert_eq!(
+            CoreSnapshot::from_bytes(&padded),
+            Err(SnapshotDecodeError::TrailingBytes(1))
+        );
+    }
+
+    #[test]
+    fn records_the_timing_model() {
+        let mut state = sample_state();
+        let table = CycleCostTable::V1.with_cost(CycleCostKind::Load, 7);
+        state.timing = TimingModel::Custom(table);
+
+        let bytes = CoreSnapshot::from_core_state(SnapshotVersion::V2, &state).to_bytes();
+        let restored = CoreSnapshot::from_bytes(&bytes)
+            .unwrap()
+            .try_into_core_state()
+            .unwrap();
+        assert_eq!(restored.timing, TimingModel::Custom(table));
+
+        let v1 = CoreSnapshot::from_core_state(SnapshotVersion::V1, &state).to_bytes();
+        assert_eq!(v1.len() + 1 + 2 * crate::CYCLE_COST_KIND_COUNT, bytes.len());
+        let restored = CoreSnapshot::from_bytes(&v1).unwrap();
+        assert_eq!(restored.state.timing, TimingModel::V1);
+    }
+
+    #[test]
+    fn records_the_open_bus_policy() {
+        let mut state = sample_state();
+        state.open_bus = OpenBus::LastValue;
+        state.mmio_bus_latch = 0xA55A;
+
+        let bytes = CoreSnapshot::from_core_state(SnapshotVersion::V3, &state).to_bytes();
+        let restored = CoreSnapshot::from_bytes(&bytes)
+            .unwrap()
+            .try_into_core_state()
+            .unwrap();
+        assert_eq!(restored, state);
+
+        let mut bad_tag = bytes.clone();
+        let tag = bad_tag.len() - 3;
+        bad_tag[tag] = 9;
+        assert_eq!(
+            CoreSnapshot::from_bytes(&bad_tag),
+            Err(SnapshotDecodeError::InvalidOpenBus(9))
+        );
+
+        let v2 = CoreSnapshot::from_core_state(SnapshotVersion::V2, &state).to_bytes();
+        assert_eq!(v2.len() + 3, bytes.len());
+        let restored = CoreSnapshot::from_bytes(&v2).unwrap();
+        assert_eq!(restored.state.open_bus, OpenBus::Zero);
+        assert_eq!(restored.state.mmio_bus_latch, 0);
+    }
+
+    #[test]
+    fn records_the_mpu_registers() {
+        let mut state = sample_state();
+        state.mpu.set_register(crate::MPU_REGION_BASE, 0x4000);
+        state.mpu.set_register(crate::MPU_REGION_BASE + 1, 0x7FFF);
+        state
+            .mpu
+            .set_register(crate::MPU_REGION_BASE + 2, crate::MPU_ATTR_ENABLE);
+        state.mpu.set_register(MPU_CTRL, crate::MPU_CTRL_ENABLE);
+        state.mpu.record_violation(0x4321);
+
+        let bytes = CoreSnapshot::from_core_state(SnapshotVersion::V4, &state).to_bytes();
+        let restored = CoreSnapshot::from_bytes(&bytes)
+            .unwrap()
+            .try_into_core_state()
+            .unwrap();
+        assert_eq!(restored, state);
+
+        let v3 = CoreSnapshot::from_core_state(SnapshotVersion::V3, &state).to_bytes();
+        assert_eq!(v3.len() + 28, bytes.len());
+        let restored = CoreSnapshot::from_bytes(&v3).unwrap();
+        assert_eq!(restored.state.mpu, Mpu::new());
+    }
+}
@@ -0,0 +1,248 @@
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{CoreState, GeneralRegister, RunState};
+
+/// One host-visible difference between two core states.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateDifference {
+    /// A register holds different values.
+    Register {
+        /// Register name, e.g. `R3` or `PC`.
+        name: &'static str,
+        /// Value in the first state.
+        before: u16,
+        /// Value in the second state.
+        after: u16,
+    },
+    /// A contiguous run of differing memory bytes.
+    Memory {
+        /// First differing address.
+        start: u16,
+        /// Bytes in the first state.
+        before: Vec<u8>,
+        /// Bytes in the second state.
+        after: Vec<u8>,
+    },
+    /// Pending events differ.
+    EventQueue {
+        /// Queued events in the first state, in dequeue order.
+        before: Vec<u8>,
+        /// Queued events in the second state, in dequeue order.
+        after: Vec<u8>,
+    },
+    /// Run states differ.
+    RunState {
+        /// Run state of the first state.
+        before: RunState,
+        /// Run state of the second state.
+        after: RunState,
+    },
+    /// Denied MMIO write counters differ.
+    MmioDeniedWrites {
+        /// Counter in the first state.
+        before: u16,
+        /// Counter in the second state.
+        after: u16,
+    },
+}
+
+impl core::fmt::Display for StateDifference {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Register {
+                name,
+                before,
+                after,
+            } => write!(f, "{name}: 0x{before:04X} -> 0x{after:04X}"),
+            Self::Memory {
+                start,
+                before,
+                after,
+            } => write!(
+                f,
+                "memory 0x{start:04X}..+{}: {} -> {}",
+                before.len(),
+                hex_bytes(before),
+                hex_bytes(after)
+            ),
+            Self::EventQueue { before, after } => {
+                write!(
+                    f,
+                    "event queue: [{}] -> [{}]",
+                    hex_bytes(before),
+                    hex_bytes(after)
+                )
+            }
+            Self::RunState { before, after } => write!(f, "run state: {before:?} -> {after:?}"),
+            Self::MmioDeniedWrites { before, after } => {
+                write!(f, "denied MMIO writes: {before} -> {after}")
+            }
+        }
+    }
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Lists the host-visible differences between two core states.
+///
+/// Compares registers, memory, the event queue, run state and the denied
+/// MMIO write counter; adjacent differing bytes are merged into one
+/// [`StateDifference::Memory`] entry. Profile, PC history and breakpoints
+/// are host configuration and are not compared. An empty result means the
+/// states are observably identical.
+#[must_use]
+pub fn diff_states(before: &CoreState, after: &CoreState) -> Vec<StateDifference> {
+    let mut differences = Vec::new();
+
+    for (name, read) in REGISTERS {
+        let (old, new) = (read(before), read(after));
+        if old != new {
+            differences.push(StateDifference::Register {
+                name,
+                before: old,
+                after: new,
+            });
+        }
+    }
+
+    let mut address = 0;
+    let len = before.memory.len().min(after.memory.len());
+    while address < len {
+        if before.memory[address] == after.memory[address] {
+            address += 1;
+            continue;
+        }
+        let start = address;
+        while address < len && before.memory[address] != after.memory[address] {
+            address += 1;
+        }
+        differences.push(StateDifference::Memory {
+            start: u16::try_from(start).unwrap_or(u16::MAX),
+            before: before.memory[start..address].to_vec(),
+            after: after.memory[start..address].to_vec(),
+        });
+    }
+
+    let (old_queue, new_queue) = (queued(before), queued(after));
+    if old_queue != new_queue {
+        differences.push(StateDifference::EventQueue {
+            before: old_queue,
+            after: new_queue,
+        });
+    }
+
+    if before.run_state != after.run_state {
+        differences.push(StateDifference::RunState {
+            before: before.run_state,
+            after: after.run_state,
+        });
+    }
+
+    if before.mmio_denied_write_count != after.mmio_denied_write_count {
+        differences.push(StateDifference::MmioDeniedWrites {
+            before: before.mmio_denied_write_count,
+            after: after.mmio_denied_write_count,
+        });
+    }
+
+    differences
+}
+
+type RegisterRead = fn(&CoreState) -> u16;
+
+pub(super) const REGISTERS: [(&str, RegisterRead); 15] = [
+    ("R0", |state| state.arch.gpr(GeneralRegister::R0)),
+    ("R1", |state| state.arch.gpr(GeneralRegister::R1)),
+    ("R2", |state| state.arch.gpr(GeneralRegister::R2)),
+    ("R3", |state| state.arch.gpr(GeneralRegister::R3)),
+    ("R4", |state| state.arch.gpr(GeneralRegister::R4)),
+    ("R5", |state| state.arch.gpr(GeneralRegister::R5)),
+    ("R6", |state| state.arch.gpr(GeneralRegister::R6)),
+    ("R7", |state| state.arch.gpr(GeneralRegister::R7)),
+    ("PC", |state| state.arch.pc()),
+    ("SP", |state| state.arch.sp()),
+    ("FLAGS", |state| state.arch.flags()),
+    ("TICK", |state| state.arch.tick()),
+    ("CAP", |state| state.arch.cap()),
+    ("CAUSE", |state| state.arch.cause()),
+    ("EVP", |state| state.arch.evp()),
+];
+
+fn queued(state: &CoreState) -> Vec<u8> {
+    state.event_queue.events[..usize::from(state.event_queue.len)].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_states, StateDifference};
+    use crate::{CoreState, FaultCode, GeneralRegister, RunState};
+
+    #[test]
+    fn identical_states_have_no_differences() {
+        let state = CoreState::default();
+        assert!(diff_states(&state, &state.clone()).is_empty());
+    }
+
+    #[test]
+    fn reports_registers_memory_runs_and_run_state() {
+        let before = CoreState::default();
+        let mut after = before.clone();
+        after.arch.set_gpr(GeneralRegister::R2, 7);
+        after.memory[0x4000] = 1;
+        after.memory[0x4001] = 2;
+        after.memory[0x4010] = 3;
+        after.run_state = RunState::FaultLatched(FaultCode::IllegalEncoding);
+
+        let differences = diff_states(&before, &after);
+
+        assert_eq!(
+            differences,
+            vec![
+                StateDifference::Register {
+                    name: "R2",
+                    before: 0,
+                    after: 7,
+                },
+                StateDifference::Memory {
+                    start: 0x4000,
+                    before: vec![0, 0],
+                    after: vec![1, 2],
+                },
+                StateDifference::Memory {
+                    start: 0x4010,
+                    before: vec![0],
+                    after: vec![3],
+                },
+                StateDifference::RunState {
+                    before: RunState::Running,
+                    after: RunState::FaultLatched(FaultCode::IllegalEncoding),
+                },
+            ]
+        );
+        assert_eq!(
+            differences[1].to_string(),
+            "memory 0x4000..+2: 00 00 -> 01 02"
+        );
+    }
+
+    #[test]
+    fn reports_event_queue_contents() {
+        let before = CoreState::default();
+        let mut after = before.clone();
+        after.event_queue.enqueue(9).unwrap();
+
+        assert_eq!(
+            diff_states(&before, &after),
+            vec![StateDifference::EventQueue {
+                before: vec![],
+                after: vec![9],
+            }]
+        );
+    }
+}
@@ -0,0 +1,119 @@
+//! Stable 64-bit digest of the host-visible core state.
+//!
+//! [`CoreState::state_digest`] hashes the fields [`diff_states`] compares
+//! plus the open-bus policy, the MMIO bus latch and the MPU registers, all
+//! of which change how the program goes on to run. Replay checks, golden
+//! tests and hosts polling for changes can compare one number instead of
+//! two whole states. Equal states always digest equal; different states
+//! digest differently except for the odd 64-bit collision, so a match is a
+//! fast path, not a proof.
+//!
+//! The digest is FNV-1a (64-bit, offset basis `0xCBF29CE484222325`, prime
+//! `0x100000001B3`) over these bytes, in order, with multi-byte integers
+//! big-endian:
+//!
+//! | Field | Size |
+//! |-------|------|
+//! | `R0..R7`, `PC`, `SP`, `FLAGS`, `TICK`, `CAP`, `CAUSE`, `EVP` | 15 × 2 |
+//! | memory | 65 536 |
+//! | event queue length `n` | 1 |
+//! | queued events in dequeue order | `n` |
+//! | run-state tag and latched fault code, as in the canonical snapshot | 2 × 1 |
+//! | denied MMIO write count | 2 |
+//! | open-bus tag (`0=zero`, `1=ones`, `2=last`) | 1 |
+//! | MMIO bus latch | 2 |
+//! | MPU `CTRL` through `REGION3_ATTR` in register order | 14 × 2 |
+//!
+//! Profile, timing, PC history, tick usage, the memory write log and
+//! breakpoints are host configuration or debugging aids and are left out.
+//! The algorithm and field order are fixed; a change to either is a
+//! breaking change to every recorded digest.
+//!
+//! [`diff_states`]: crate::diff_states
+
+use super::diff::REGISTERS;
+use crate::compat::{fnv1a, FNV_OFFSET};
+use crate::{CanonicalStateLayout, CoreState, OpenBus, MPU_CTRL, MPU_END};
+
+impl CoreState {
+    /// Returns the stable digest described in the
+    /// [module docs](crate::state::digest).
+    #[must_use]
+    pub fn state_digest(&self) -> u64 {
+        let mut hash = FNV_OFFSET;
+        for (_, read) in REGISTERS {
+            hash = fnv1a(hash, &read(self).to_be_bytes());
+        }
+        hash = fnv1a(hash, &self.memory);
+        let queued = &self.event_queue.events[..usize::from(self.event_queue.len)];
+        hash = fnv1a(hash, &[self.event_queue.len]);
+        hash = fnv1a(hash, queued);
+        let run_state: [u8; 2] = CanonicalStateLayout::run_state_tags(self.run_state).into();
+        hash = fnv1a(hash, &run_state);
+        hash = fnv1a(hash, &self.mmio_denied_write_count.to_be_bytes());
+        let open_bus = match self.open_bus {
+            OpenBus::Zero => 0,
+            OpenBus::Ones => 1,
+            OpenBus::LastValue => 2,
+        };
+        hash = fnv1a(hash, &[open_bus]);
+        hash = fnv1a(hash, &self.mmio_bus_latch.to_be_bytes());
+        for addr in MPU_CTRL..=MPU_END {
+            hash = fnv1a(hash, &self.mpu.register(addr).to_be_bytes());
+        }
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        diff_states, CoreConfig, CoreProfile, CoreState, FaultCode, GeneralRegister, OpenBus,
+        RunState, MPU_CTRL, MPU_CTRL_ENABLE,
+    };
+
+    #[test]
+    fn digest_is_pinned_for_the_reset_state() {
+        assert_eq!(CoreState::default().state_digest(), 0x3FBB_C513_0859_E350);
+    }
+
+    #[test]
+    fn every_hashed_field_changes_the_digest() {
+        let base = CoreState::default();
+        let changes: [fn(&mut CoreState); 9] = [
+            |state| state.arch.set_gpr(GeneralRegister::R7, 1),
+            |state| state.memory[0xFFFF] = 1,
+            |state| {
+                state.enqueue_event(0).unwrap();
+            },
+            |state| state.run_state = RunState::FaultLatched(FaultCode::IllegalEncoding),
+            |state| state.mmio_denied_write_count = 1,
+            |state| state.open_bus = OpenBus::Ones,
+            |state| state.mmio_bus_latch = 1,
+            |state| state.mpu.set_register(MPU_CTRL, MPU_CTRL_ENABLE),
+            |state| state.mpu.record_violation(1),
+        ];
+        for change in changes {
+            let mut state = base.clone();
+            change(&mut state);
+            assert_ne!(state.state_digest(), base.state_digest());
+        }
+    }
+
+    #[test]
+    fn host_configuration_does_not_change_the_digest() {
+        let base = CoreState::default();
+        let mut configured = CoreState::with_config(&CoreConfig {
+            pc_history_depth: 4,
+            ..CoreConfig::default()
+        });
+        configured.profile = CoreProfile::Restricted;
+        configured.arch.set_cap_core_owned(base.arch.cap());
+        configured.breakpoints.set(0x0100, None);
+        // Stale queue slots past the length are not queued events.
+        configured.event_queue.events[0] = 0x42;
+
+        assert!(diff_states(&base, &configured).is_empty());
+        assert_eq!(configured.state_digest(), base.state_digest());
+    }
+}
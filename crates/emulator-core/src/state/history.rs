@@ -0,0 +1,168 @@
+use alloc::vec::Vec;
+
+/// One retired instruction recorded in the PC history ring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct PcHistoryEntry {
+    /// Address the instruction was fetched from.
+    pub pc: u16,
+    /// Raw primary instruction word at `pc`.
+    pub raw_word: u16,
+}
+
+/// Fixed-size ring of the most recently retired instructions.
+///
+/// A capacity of zero disables recording entirely, which is the default so
+/// that the hot step path pays nothing unless a host asks for crash context.
+///
+/// Equality compares capacity and the oldest-first contents, not where the
+/// ring currently wraps.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct PcHistory {
+    capacity: u16,
+    entries: Vec<PcHistoryEntry>,
+    next: usize,
+}
+
+impl PcHistory {
+    /// Creates an empty ring holding at most `capacity` entries.
+    #[must_use]
+    pub fn new(capacity: u16) -> Self {
+        Self {
+            capacity,
+            entries: Vec::with_capacity(usize::from(capacity)),
+            next: 0,
+        }
+    }
+
+    /// Rebuilds a ring from entries in oldest-first order.
+    ///
+    /// Only the newest `capacity` entries are kept.
+    #[must_use]
+    pub fn from_entries(capacity: u16, entries: &[PcHistoryEntry]) -> Self {
+        let mut history = Self::new(capacity);
+        for entry in entries {
+            history.record(*entry);
+        }
+        history
+    }
+
+    /// Maximum number of retained entries.
+    #[must_use]
+    pub const fn capacity(&self) -> u16 {
+        self.capacity
+    }
+
+    /// Returns true when recording is enabled.
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Number of entries currently retained.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true when nothing has been recorded.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Records a retired instruction, evicting the oldest entry when full.
+    pub fn record(&mut self, entry: PcHistoryEntry) {
+        let capacity = usize::from(self.capacity);
+        if capacity == 0 {
+            return;
+        }
+        if self.entries.len() < capacity {
+            self.entries.push(entry);
+        } else {
+            self.entries[self.next] = entry;
+        }
+        self.next = (self.next + 1) % capacity;
+    }
+
+    /// Returns retained entries, oldest first.
+    #[must_use]
+    pub fn entries(&self) -> Vec<PcHistoryEntry> {
+        if self.entries.len() < usize::from(self.capacity) {
+            return self.entries.clone();
+        }
+        let (newer, older) = self.entries.split_at(self.next);
+        older.iter().chain(newer).copied().collect()
+    }
+
+    /// Returns retained program counters, oldest first.
+    #[must_use]
+    pub fn pcs(&self) -> Vec<u16> {
+        self.entries().iter().map(|entry| entry.pc).collect()
+    }
+
+    /// Discards all entries while keeping the capacity.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.next = 0;
+    }
+}
+
+impl PartialEq for PcHistory {
+    fn eq(&self, other: &Self) -> bool {
+        self.capacity == other.capacity && self.entries() == other.entries()
+    }
+}
+
+impl Eq for PcHistory {}
+
+#[cfg(test)]
+mod tests {
+    use super::{PcHistory, PcHistoryEntry};
+
+    fn entry(pc: u16) -> PcHistoryEntry {
+        PcHistoryEntry { pc, raw_word: 0 }
+    }
+
+    #[test]
+    fn disabled_history_records_nothing() {
+        let mut history = PcHistory::default();
+        history.record(entry(2));
+
+        assert!(!history.is_enabled());
+        assert!(history.is_empty());
+    }
+
+    #[test]
+    fn ring_keeps_newest_entries_in_order() {
+        let mut history = PcHistory::new(3);
+        for pc in [0, 2, 4, 6, 8] {
+            history.record(entry(pc));
+        }
+
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.pcs(), vec![4, 6, 8]);
+    }
+
+    #[test]
+    fn from_entries_roundtrips_ring_contents() {
+        let mut history = PcHistory::new(2);
+        for pc in [0, 2, 4] {
+            history.record(entry(pc));
+        }
+
+        let restored = PcHistory::from_entries(history.capacity(), &history.entries());
+        assert_eq!(restored, history);
+    }
+
+    #[test]
+    fn clear_keeps_capacity() {
+        let mut history = PcHistory::new(2);
+        history.record(entry(0));
+        history.clear();
+
+        assert!(history.is_empty());
+        assert_eq!(history.capacity(), 2);
+    }
+}
@@ -1,12 +1,23 @@
 //! Architectural CPU state model primitives.
 
+/// Field-by-field comparison of two core states.
+pub mod diff;
+/// Stable hash of the host-visible core state.
+pub mod digest;
+/// Ring buffer of recently retired instructions.
+pub mod history;
 /// Architectural register file types and storage model.
 pub mod registers;
 /// Host-visible run-state machine types.
 pub mod run_state;
+/// Bounded log of memory writes for hosts animating what a step touched.
+pub mod writes;
 
+pub use diff::{diff_states, StateDifference};
+pub use history::{PcHistory, PcHistoryEntry};
 pub use registers::{
     ArchitecturalState, GeneralRegister, CAP_AUTHORITY_DEFAULT_MASK, CAP_RESTRICTED_DEFAULT_MASK,
-    GENERAL_REGISTER_COUNT,
+    EVP_OVERFLOW, GENERAL_REGISTER_COUNT,
 };
 pub use run_state::RunState;
+pub use writes::{MemoryWrite, MemoryWriteLog};
@@ -14,6 +14,8 @@ pub const FLAGS_I: u16 = 1 << 4;
 pub const FLAGS_F: u16 = 1 << 5;
 /// Mask of architecturally active `FLAGS` bits (`Z/N/C/V/I/F`).
 pub const FLAGS_ACTIVE_MASK: u16 = FLAGS_Z | FLAGS_N | FLAGS_C | FLAGS_V | FLAGS_I | FLAGS_F;
+/// `EVP` bit latched when the host enqueues onto a full event queue.
+pub const EVP_OVERFLOW: u16 = 1 << 15;
 /// Authority-profile default capability mask (`CAP[0..3] = 1`).
 pub const CAP_AUTHORITY_DEFAULT_MASK: u16 = 0x000F;
 /// Restricted-profile default capability mask (all capability bits disabled).
@@ -0,0 +1,125 @@
+use alloc::vec::Vec;
+
+/// One big-endian word written to memory, covering `address` and
+/// `address + 1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct MemoryWrite {
+    /// Address of the high byte.
+    pub address: u16,
+    /// Word written.
+    pub value: u16,
+}
+
+/// Bounded, in-order log of the memory writes made by instructions and by
+/// trap, event and fault dispatch.
+///
+/// Writes accumulate until the host clears the log, so a host that clears it
+/// before each step or run call sees exactly the bytes that call touched
+/// without diffing the address space. Once `capacity` writes are held,
+/// further writes are only counted. A capacity of zero disables recording,
+/// which is the default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct MemoryWriteLog {
+    capacity: u16,
+    writes: Vec<MemoryWrite>,
+    dropped: u32,
+}
+
+impl MemoryWriteLog {
+    /// Creates an empty log holding at most `capacity` writes.
+    #[must_use]
+    pub const fn new(capacity: u16) -> Self {
+        Self {
+            capacity,
+            writes: Vec::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Maximum number of retained writes.
+    #[must_use]
+    pub const fn capacity(&self) -> u16 {
+        self.capacity
+    }
+
+    /// Returns true when recording is enabled.
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Records a word write, counting it as dropped when the log is full.
+    pub fn record(&mut self, address: u16, value: u16) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.writes.len() < usize::from(self.capacity) {
+            self.writes.push(MemoryWrite { address, value });
+        } else {
+            self.dropped = self.dropped.saturating_add(1);
+        }
+    }
+
+    /// Retained writes, oldest first.
+    #[must_use]
+    pub fn writes(&self) -> &[MemoryWrite] {
+        &self.writes
+    }
+
+    /// Writes made after the log filled up, which were not retained.
+    #[must_use]
+    pub const fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// Discards all writes while keeping the capacity.
+    pub fn clear(&mut self) {
+        self.writes.clear();
+        self.dropped = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemoryWrite, MemoryWriteLog};
+
+    #[test]
+    fn disabled_log_records_nothing() {
+        let mut log = MemoryWriteLog::default();
+        log.record(0x4000, 1);
+
+        assert!(!log.is_enabled());
+        assert!(log.writes().is_empty());
+        assert_eq!(log.dropped(), 0);
+    }
+
+    #[test]
+    fn full_log_counts_dropped_writes_until_cleared() {
+        let mut log = MemoryWriteLog::new(2);
+        for value in 1..=4 {
+            log.record(0x4000 + 2 * value, value);
+        }
+
+        assert_eq!(
+            log.writes(),
+            [
+                MemoryWrite {
+                    address: 0x4002,
+                    value: 1,
+                },
+                MemoryWrite {
+                    address: 0x4004,
+                    value: 2,
+                },
+            ]
+        );
+        assert_eq!(log.dropped(), 2);
+
+        log.clear();
+        assert!(log.writes().is_empty());
+        assert_eq!(log.dropped(), 0);
+        assert_eq!(log.capacity(), 2);
+    }
+}
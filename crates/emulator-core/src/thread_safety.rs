@@ -0,0 +1,107 @@
+//! Compile-time audit of the thread-safety contract.
+//!
+//! Every host-facing state and peripheral type is plain owned data: no
+//! `Rc`, `Cell`, `RefCell` or other interior mutability. A core and its
+//! MMIO bus can therefore be moved to a worker thread as a unit, and two
+//! cores running on different threads cannot observe each other. Adding a
+//! field that breaks this fails the build here rather than in a host.
+
+use crate::{
+    BlockCache, BreakpointTable, CompositeMmio, CoreConfig, CoreSnapshot, CoreState, DebugConsole,
+    Decoder, DmaController, ExperimentalOpcodes, ParamBlock, PasteBuffer, ReplayEventStream,
+    ReplayResult, RunOutcome, SimpleTraceSink, StaticDiagProvider, StepOutcome, Tele7Peripheral,
+    TickBatch, WatchExpr,
+};
+
+const fn assert_send_sync<T: Send + Sync>() {}
+
+const _: () = {
+    assert_send_sync::<CoreState>();
+    assert_send_sync::<CoreConfig>();
+    assert_send_sync::<CoreSnapshot>();
+    assert_send_sync::<StepOutcome>();
+    assert_send_sync::<RunOutcome>();
+    assert_send_sync::<TickBatch>();
+    assert_send_sync::<BlockCache>();
+    assert_send_sync::<ReplayEventStream>();
+    assert_send_sync::<ReplayResult>();
+    assert_send_sync::<ExperimentalOpcodes>();
+    assert_send_sync::<SimpleTraceSink>();
+    assert_send_sync::<Decoder>();
+    assert_send_sync::<BreakpointTable>();
+    assert_send_sync::<WatchExpr>();
+    assert_send_sync::<ParamBlock>();
+    assert_send_sync::<StaticDiagProvider>();
+    assert_send_sync::<Tele7Peripheral>();
+    assert_send_sync::<DebugConsole>();
+    assert_send_sync::<DmaController>();
+    assert_send_sync::<PasteBuffer>();
+    assert_send_sync::<CompositeMmio>();
+};
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::{run_ticks_with_budget, CompositeMmio, CoreConfig, CoreState, DebugConsole};
+
+    /// Increments the word at 0x4000 and writes it to the debug console
+    /// once per tick.
+    const COUNTER_PROGRAM: [u8; 24] = [
+        0x10, 0x05, 0xE1, 0x32, // MOV R0, #0xE132
+        0x14, 0x05, 0x40, 0x00, // MOV R2, #0x4000
+        0x22, 0x81, // loop: LOAD R1, [R2]
+        0x42, 0x45, 0x00, 0x01, // ADD R1, R1, #0x0001
+        0x32, 0x81, // STORE R1, [R2]
+        0x32, 0x01, // STORE R1, [R0]
+        0x00, 0x10, // HALT
+        0x60, 0x35, 0xFF, 0xF0, // JMP #loop
+    ];
+
+    fn run_counter(ticks: u32) -> (CoreState, Vec<u8>) {
+        let config = CoreConfig::default();
+        let mut state = CoreState::with_config(&config);
+        state.memory[..COUNTER_PROGRAM.len()].copy_from_slice(&COUNTER_PROGRAM);
+        let mut mmio = CompositeMmio::new().with_console(DebugConsole::new());
+        let batch = run_ticks_with_budget(&mut state, &mut mmio, &config, ticks);
+        assert_eq!(batch.fault, None);
+        let output = mmio
+            .console_mut()
+            .map(DebugConsole::drain)
+            .unwrap_or_default();
+        (state, output)
+    }
+
+    #[test]
+    fn cores_on_worker_threads_match_a_core_on_this_thread() {
+        let expected = run_counter(40);
+        assert_eq!(expected.1, (1..=40).collect::<Vec<u8>>());
+
+        let workers: Vec<_> = (0..4).map(|_| thread::spawn(|| run_counter(40))).collect();
+        for worker in workers {
+            assert_eq!(worker.join().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn a_core_moved_mid_run_continues_deterministically() {
+        let config = CoreConfig::default();
+        let mut state = CoreState::with_config(&config);
+        state.memory[..COUNTER_PROGRAM.len()].copy_from_slice(&COUNTER_PROGRAM);
+        let mut mmio = CompositeMmio::new().with_console(DebugConsole::new());
+        run_ticks_with_budget(&mut state, &mut mmio, &config, 15);
+
+        let (state, mut mmio) = thread::spawn(move || {
+            run_ticks_with_budget(&mut state, &mut mmio, &config, 25);
+            (state, mmio)
+        })
+        .join()
+        .unwrap();
+
+        let output = mmio
+            .console_mut()
+            .map(DebugConsole::drain)
+            .unwrap_or_default();
+        assert_eq!((state, output), run_counter(40));
+    }
+}
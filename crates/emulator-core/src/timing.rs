@@ -1,3 +1,5 @@
+use crate::encoding::OpcodeEncoding;
+
 /// Instruction and dispatch forms that have fixed cycle costs in the core.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CycleCostKind {
@@ -35,6 +37,8 @@ pub enum CycleCostKind {
     Call,
     /// Subroutine return.
     Ret,
+    /// Special-register read.
+    Mrs,
     /// Stack push.
     Push,
     /// Stack pop.
@@ -63,7 +67,7 @@ pub enum CycleCostKind {
     EretReturn,
 }
 
-/// Single source-of-truth cycle-cost table for fixed-cost instruction/dispatch forms.
+/// Reference (`v1`) cycle-cost table for fixed-cost instruction/dispatch forms.
 pub const CYCLE_COST_TABLE: &[(CycleCostKind, u16)] = &[
     (CycleCostKind::Nop, 1),
     (CycleCostKind::Sync, 1),
@@ -82,6 +86,7 @@ pub const CYCLE_COST_TABLE: &[(CycleCostKind, u16)] = &[
     (CycleCostKind::Jump, 2),
     (CycleCostKind::Call, 2),
     (CycleCostKind::Ret, 2),
+    (CycleCostKind::Mrs, 1),
     (CycleCostKind::Push, 1),
     (CycleCostKind::Pop, 1),
     (CycleCostKind::MmioIn, 4),
@@ -97,7 +102,7 @@ pub const CYCLE_COST_TABLE: &[(CycleCostKind, u16)] = &[
     (CycleCostKind::EretReturn, 4),
 ];
 
-/// Looks up the cycle cost for a cycle-cost kind.
+/// Looks up the reference cycle cost for a cycle-cost kind.
 #[must_use]
 pub fn cycle_cost(kind: CycleCostKind) -> Option<u16> {
     CYCLE_COST_TABLE
@@ -105,11 +110,194 @@ pub fn cycle_cost(kind: CycleCostKind) -> Option<u16> {
         .find_map(|(entry_kind, cycles)| (*entry_kind == kind).then_some(*cycles))
 }
 
+/// Number of [`CycleCostKind`] variants.
+pub const CYCLE_COST_KIND_COUNT: usize = CYCLE_COST_TABLE.len();
+
+/// A complete cycle-cost table with one entry per [`CycleCostKind`].
+///
+/// Hosts experimenting with budgets start from [`CycleCostTable::V1`] and
+/// override individual costs, then run with [`TimingModel::Custom`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct CycleCostTable {
+    costs: [u16; CYCLE_COST_KIND_COUNT],
+}
+
+impl CycleCostTable {
+    /// The reference costs from [`CYCLE_COST_TABLE`].
+    pub const V1: Self = {
+        let mut costs = [0; CYCLE_COST_KIND_COUNT];
+        let mut index = 0;
+        while index < CYCLE_COST_KIND_COUNT {
+            let (kind, cycles) = CYCLE_COST_TABLE[index];
+            costs[kind as usize] = cycles;
+            index += 1;
+        }
+        Self { costs }
+    };
+
+    /// The reference costs with every MMIO instruction (`IN`, `OUT`,
+    /// `BSET`, `BCLR`, `BTEST`) costing one cycle.
+    pub const FAST_IO: Self = Self::V1
+        .with_cost(CycleCostKind::MmioIn, 1)
+        .with_cost(CycleCostKind::MmioOut, 1)
+        .with_cost(CycleCostKind::MmioBitSet, 1)
+        .with_cost(CycleCostKind::MmioBitClear, 1)
+        .with_cost(CycleCostKind::MmioBitTest, 1);
+
+    /// Builds a table from `(kind, cycles)` pairs; kinds not listed keep
+    /// their [`V1`](Self::V1) cost.
+    #[must_use]
+    pub fn from_entries(entries: &[(CycleCostKind, u16)]) -> Self {
+        entries.iter().fold(Self::V1, |table, (kind, cycles)| {
+            table.with_cost(*kind, *cycles)
+        })
+    }
+
+    /// Returns this table with `kind` costing `cycles`.
+    #[must_use]
+    pub const fn with_cost(mut self, kind: CycleCostKind, cycles: u16) -> Self {
+        self.costs[kind as usize] = cycles;
+        self
+    }
+
+    /// Cycle cost of `kind`.
+    #[must_use]
+    pub const fn cost(&self, kind: CycleCostKind) -> u16 {
+        self.costs[kind as usize]
+    }
+
+    /// Every kind with its cost, in [`CYCLE_COST_TABLE`] order.
+    pub fn entries(&self) -> impl Iterator<Item = (CycleCostKind, u16)> + '_ {
+        CYCLE_COST_TABLE
+            .iter()
+            .map(|(kind, _)| (*kind, self.cost(*kind)))
+    }
+}
+
+impl Default for CycleCostTable {
+    fn default() -> Self {
+        Self::V1
+    }
+}
+
+/// Cycle-cost table a core runs with, selected through
+/// [`CoreConfig::timing`](crate::CoreConfig::timing).
+///
+/// The choice is part of [`CoreState`](crate::CoreState) and of canonical
+/// snapshots, so a restored machine keeps the timing it was saved with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum TimingModel {
+    /// The reference costs ([`CycleCostTable::V1`]).
+    #[default]
+    V1,
+    /// Reference costs with one-cycle MMIO ([`CycleCostTable::FAST_IO`]).
+    FastIo,
+    /// A table supplied by the host.
+    Custom(CycleCostTable),
+}
+
+impl TimingModel {
+    /// Names accepted by [`TimingModel::named`].
+    pub const NAMES: [&'static str; 2] = ["v1", "fast-io"];
+
+    /// Looks up a built-in model by name.
+    #[must_use]
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "v1" => Some(Self::V1),
+            "fast-io" => Some(Self::FastIo),
+            _ => None,
+        }
+    }
+
+    /// Name of the model: one of [`NAMES`](Self::NAMES), or `custom`.
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::V1 => "v1",
+            Self::FastIo => "fast-io",
+            Self::Custom(_) => "custom",
+        }
+    }
+
+    /// The cycle-cost table this model charges.
+    #[must_use]
+    pub const fn table(&self) -> CycleCostTable {
+        match self {
+            Self::V1 => CycleCostTable::V1,
+            Self::FastIo => CycleCostTable::FAST_IO,
+            Self::Custom(table) => *table,
+        }
+    }
+
+    /// Cycle cost of `kind` under this model.
+    #[must_use]
+    pub const fn cost(&self, kind: CycleCostKind) -> u16 {
+        self.table().cost(kind)
+    }
+}
+
+/// Cycle-cost kinds an instruction encoding can retire with.
+///
+/// Conditional branches list both outcomes (not taken first), and the shared
+/// `CALL`/`RET` encoding lists both forms. Trap and event dispatch costs are
+/// charged separately and are not included.
+#[must_use]
+pub const fn cycle_cost_kinds(encoding: OpcodeEncoding) -> &'static [CycleCostKind] {
+    match encoding {
+        OpcodeEncoding::Nop => &[CycleCostKind::Nop],
+        OpcodeEncoding::Sync => &[CycleCostKind::Sync],
+        OpcodeEncoding::Halt => &[CycleCostKind::Halt],
+        OpcodeEncoding::Trap => &[CycleCostKind::TrapIssue],
+        OpcodeEncoding::Swi => &[CycleCostKind::SwiIssue],
+        OpcodeEncoding::Mov => &[CycleCostKind::Mov],
+        OpcodeEncoding::Mrs => &[CycleCostKind::Mrs],
+        OpcodeEncoding::Load => &[CycleCostKind::Load],
+        OpcodeEncoding::Store => &[CycleCostKind::Store],
+        OpcodeEncoding::Add
+        | OpcodeEncoding::Sub
+        | OpcodeEncoding::And
+        | OpcodeEncoding::Or
+        | OpcodeEncoding::Xor
+        | OpcodeEncoding::Shl
+        | OpcodeEncoding::Shr
+        | OpcodeEncoding::Cmp => &[CycleCostKind::Alu],
+        OpcodeEncoding::Mul | OpcodeEncoding::Mulh => &[CycleCostKind::Mul],
+        OpcodeEncoding::Div | OpcodeEncoding::Mod => &[CycleCostKind::Div],
+        OpcodeEncoding::Qadd | OpcodeEncoding::Qsub | OpcodeEncoding::Scv => {
+            &[CycleCostKind::SaturatingHelper]
+        }
+        OpcodeEncoding::Beq
+        | OpcodeEncoding::Bne
+        | OpcodeEncoding::Blt
+        | OpcodeEncoding::Ble
+        | OpcodeEncoding::Bgt
+        | OpcodeEncoding::Bge => &[CycleCostKind::BranchNotTaken, CycleCostKind::BranchTaken],
+        OpcodeEncoding::Jmp => &[CycleCostKind::Jump],
+        OpcodeEncoding::CallOrRet => &[CycleCostKind::Call, CycleCostKind::Ret],
+        OpcodeEncoding::Push => &[CycleCostKind::Push],
+        OpcodeEncoding::Pop => &[CycleCostKind::Pop],
+        OpcodeEncoding::In => &[CycleCostKind::MmioIn],
+        OpcodeEncoding::Out => &[CycleCostKind::MmioOut],
+        OpcodeEncoding::Bset => &[CycleCostKind::MmioBitSet],
+        OpcodeEncoding::Bclr => &[CycleCostKind::MmioBitClear],
+        OpcodeEncoding::Btest => &[CycleCostKind::MmioBitTest],
+        OpcodeEncoding::Ewait => &[CycleCostKind::Ewait],
+        OpcodeEncoding::Eget => &[CycleCostKind::Eget],
+        OpcodeEncoding::Eret => &[CycleCostKind::EretReturn],
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashSet;
 
-    use super::{cycle_cost, CycleCostKind, CYCLE_COST_TABLE};
+    use super::{
+        cycle_cost, cycle_cost_kinds, CycleCostKind, CycleCostTable, TimingModel, CYCLE_COST_TABLE,
+    };
+    use crate::encoding::OPCODE_ENCODING_TABLE;
 
     #[test]
     fn table_contains_unique_kinds() {
@@ -135,4 +323,46 @@ mod tests {
             assert_eq!(cycle_cost(*kind), Some(*expected_cycles));
         }
     }
+
+    #[test]
+    fn every_encoding_has_priced_kinds() {
+        for (_, _, encoding) in OPCODE_ENCODING_TABLE {
+            let kinds = cycle_cost_kinds(*encoding);
+            assert!(!kinds.is_empty(), "{encoding:?} has no cycle kinds");
+            assert!(kinds.iter().all(|kind| cycle_cost(*kind).is_some()));
+        }
+    }
+
+    #[test]
+    fn table_is_in_declaration_order() {
+        for (index, (kind, _)) in CYCLE_COST_TABLE.iter().enumerate() {
+            assert_eq!(*kind as usize, index, "{kind:?} is out of order");
+        }
+    }
+
+    #[test]
+    fn timing_models_charge_their_tables() {
+        for (kind, cycles) in CYCLE_COST_TABLE {
+            assert_eq!(TimingModel::V1.cost(*kind), *cycles);
+        }
+        assert_eq!(TimingModel::FastIo.cost(CycleCostKind::MmioOut), 1);
+        assert_eq!(TimingModel::FastIo.cost(CycleCostKind::Load), 2);
+
+        let table = CycleCostTable::from_entries(&[(CycleCostKind::Div, 9)]);
+        let custom = TimingModel::Custom(table);
+        assert_eq!(custom.cost(CycleCostKind::Div), 9);
+        assert_eq!(custom.cost(CycleCostKind::Mul), 2);
+        assert_eq!(custom.name(), "custom");
+    }
+
+    #[test]
+    fn named_models_round_trip() {
+        for name in TimingModel::NAMES {
+            assert_eq!(
+                TimingModel::named(name).map(|model| model.name()),
+                Some(name)
+            );
+        }
+        assert_eq!(TimingModel::named("turbo"), None);
+    }
 }
@@ -7,7 +7,7 @@ use rstest as _;
 use serde as _;
 use thiserror as _;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 fn assembler_binary() -> PathBuf {
@@ -23,9 +23,18 @@ fn isa_test_dir() -> PathBuf {
         .join("isa")
 }
 
+/// Suite generated by `nullbyte-asm isa-suite`.
+fn conformance_test_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("conformance")
+}
+
 fn run_isa_test(name: &str) -> (bool, String) {
-    let test_path = isa_test_dir().join(name);
+    run_test_file(&isa_test_dir().join(name))
+}
 
+fn run_test_file(test_path: &Path) -> (bool, String) {
     if !test_path.exists() {
         return (
             false,
@@ -34,7 +43,8 @@ fn run_isa_test(name: &str) -> (bool, String) {
     }
 
     let output = Command::new(assembler_binary())
-        .args(["test", test_path.to_str().unwrap()])
+        .arg("test")
+        .arg(test_path)
         .output()
         .expect("failed to run nullbyte-asm");
 
@@ -63,3 +73,25 @@ isa_test!(isa_stack, "stack.n1.md");
 isa_test!(isa_mmio, "mmio.n1.md");
 isa_test!(isa_atomic, "atomic.n1.md");
 isa_test!(isa_event, "event.n1.md");
+isa_test!(isa_handler, "handler.n1.md");
+
+macro_rules! conformance_test {
+    ($name:ident, $filename:expr) => {
+        #[test]
+        fn $name() {
+            let (success, output) = run_test_file(&conformance_test_dir().join($filename));
+            assert!(success, "Conformance test failed:\n{output}");
+        }
+    };
+}
+
+conformance_test!(conformance_control, "control.n1.md");
+conformance_test!(conformance_mov, "mov.n1.md");
+conformance_test!(conformance_load_store, "load_store.n1.md");
+conformance_test!(conformance_alu, "alu.n1.md");
+conformance_test!(conformance_math, "math.n1.md");
+conformance_test!(conformance_branch, "branch.n1.md");
+conformance_test!(conformance_stack, "stack.n1.md");
+conformance_test!(conformance_mmio, "mmio.n1.md");
+conformance_test!(conformance_event, "event.n1.md");
+conformance_test!(conformance_faults, "faults.n1.md");
@@ -16,7 +16,7 @@ use emulator_core::execute::compute_effective_address;
 use emulator_core::{
     cycle_cost, write_u16_be, AddressingMode, CoreConfig, CoreProfile, CoreState, CycleCostKind,
     DecodedInstruction, Decoder, DiagCoreFields, EventEnqueueError, FaultCode, GeneralRegister,
-    MmioBus, MmioError, MmioWriteResult, OpcodeEncoding, RunState, StepOutcome,
+    HaltReason, MmioBus, MmioError, MmioWriteResult, OpcodeEncoding, RunState, StepOutcome,
     OPCODE_ENCODING_TABLE, VEC_EVENT, VEC_FAULT, VEC_TRAP,
 };
 use proptest as _;
@@ -111,7 +111,12 @@ fn unit_opcode_semantics_table_covers_all_encodings() {
 
         match encoding {
             OpcodeEncoding::Halt => {
-                assert!(matches!(outcome, StepOutcome::HaltedForTick));
+                assert!(matches!(
+                    outcome,
+                    StepOutcome::HaltedForTick {
+                        reason: HaltReason::Instruction
+                    }
+                ));
             }
             OpcodeEncoding::Trap | OpcodeEncoding::Swi => {
                 assert!(matches!(outcome, StepOutcome::TrapDispatch { .. }));
@@ -270,7 +275,12 @@ fn unit_cycle_accounting_by_opcode_and_boundary_behavior() {
     load_primary(&mut state, encode(0x2, 0, 0, 0x0, 0));
 
     let outcome = emulator_core::step_one(&mut state, &mut mmio, &config);
-    assert!(matches!(outcome, StepOutcome::HaltedForTick));
+    assert!(matches!(
+        outcome,
+        StepOutcome::HaltedForTick {
+            reason: HaltReason::BudgetExhausted
+        }
+    ));
 }
 
 #[test]
@@ -407,7 +417,12 @@ fn integration_reset_defaults_and_first_fetch_from_boot_pc() {
     let config = CoreConfig::default();
     let outcome = emulator_core::step_one(&mut state, &mut mmio, &config);
 
-    assert!(matches!(outcome, StepOutcome::HaltedForTick));
+    assert!(matches!(
+        outcome,
+        StepOutcome::HaltedForTick {
+            reason: HaltReason::Instruction
+        }
+    ));
     assert_eq!(state.arch.pc(), 0x0002);
 }
 
@@ -13,8 +13,8 @@ use std::panic::{self, AssertUnwindSafe};
 use emulator_core::{
     replay_from_snapshot, validate_fetch_access, validate_mmio_alignment, validate_mmio_width,
     validate_word_alignment, write_u16_be, CoreConfig, CoreSnapshot, CoreState, Decoder, FaultCode,
-    GeneralRegister, MmioBus, MmioError, MmioWriteResult, ReplayEventStream, RunBoundary, RunState,
-    SnapshotVersion, StepOutcome, VEC_FAULT,
+    GeneralRegister, HaltReason, MmioBus, MmioError, MmioWriteResult, ReplayEventStream,
+    RunBoundary, RunState, SnapshotVersion, StepOutcome, VEC_FAULT,
 };
 use proptest::prelude::*;
 use rstest as _;
@@ -71,7 +71,12 @@ fn integration_ewait_and_budget_interaction_on_empty_queue() {
     let mut mmio = NoopMmio;
 
     let first = emulator_core::step_one(&mut state, &mut mmio, &config);
-    assert_eq!(first, StepOutcome::HaltedForTick);
+    assert_eq!(
+        first,
+        StepOutcome::HaltedForTick {
+            reason: HaltReason::BudgetExhausted
+        }
+    );
     assert_eq!(state.arch.pc(), 0x0000);
     assert_eq!(state.arch.tick(), 640);
 
@@ -213,7 +218,12 @@ fn integration_halt_remainder_of_tick_and_next_tick_resume() {
     let mut mmio = NoopMmio;
 
     let first = emulator_core::step_one(&mut state, &mut mmio, &config);
-    assert_eq!(first, StepOutcome::HaltedForTick);
+    assert_eq!(
+        first,
+        StepOutcome::HaltedForTick {
+            reason: HaltReason::Instruction
+        }
+    );
     assert_eq!(state.arch.pc(), 0x0002);
     assert_eq!(state.run_state, RunState::HaltedForTick);
 
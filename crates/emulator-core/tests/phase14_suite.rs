@@ -52,7 +52,7 @@ fn replay_bytes(result: &ReplayResult) -> Vec<u8> {
             bytes.push(0x10);
             bytes.extend_from_slice(&cycles.to_le_bytes());
         }
-        StepOutcome::HaltedForTick => bytes.push(0x11),
+        StepOutcome::HaltedForTick { .. } => bytes.push(0x11),
         StepOutcome::TrapDispatch { cause } => {
             bytes.push(0x12);
             bytes.extend_from_slice(&cause.to_le_bytes());
@@ -0,0 +1,219 @@
+//! Periodic checkpoints for rewinding a running program.
+//!
+//! [`Autosave`] captures the machine every `interval` ended ticks into a
+//! ring of at most `capacity` checkpoints. Only the oldest checkpoint keeps
+//! a full memory image; each later one stores the runs of bytes that
+//! changed since the checkpoint before it, so a ring covering a few seconds
+//! costs little more than the memory the program actually touches.
+
+use std::collections::VecDeque;
+
+use emulator_core::{CompositeMmio, CoreState};
+
+/// One captured point in the ring.
+struct Checkpoint {
+    /// Ticks ended since the ring started when this was captured.
+    tick: u64,
+    /// Machine state with its memory left empty.
+    state: CoreState,
+    /// Peripheral state.
+    mmio: CompositeMmio,
+    /// Memory runs that differ from the previous checkpoint, as start
+    /// address and bytes. Always empty for the oldest checkpoint.
+    delta: Vec<(usize, Vec<u8>)>,
+}
+
+/// Summary of a checkpoint for hosts listing what they can rewind to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckpointSummary {
+    /// Ticks ended since the ring started when the checkpoint was taken.
+    pub tick: u64,
+    /// Program counter at the checkpoint.
+    pub pc: u16,
+    /// Memory bytes the checkpoint stores beyond the previous one.
+    pub delta_bytes: usize,
+}
+
+/// A bounded ring of checkpoints taken every `interval` ticks.
+pub struct Autosave {
+    interval: u32,
+    capacity: usize,
+    /// Ticks ended since the newest checkpoint.
+    since_last: u32,
+    /// Ticks ended since the ring started.
+    ticks: u64,
+    /// Memory at the oldest checkpoint.
+    base_memory: Box<[u8]>,
+    /// Memory at the newest checkpoint, for diffing the next capture.
+    last_memory: Box<[u8]>,
+    checkpoints: VecDeque<Checkpoint>,
+}
+
+impl Autosave {
+    /// Starts a ring with a checkpoint of the current machine. Both
+    /// `interval` and `capacity` must be non-zero.
+    pub fn new(
+        interval: u32,
+        capacity: usize,
+        state: &mut CoreState,
+        mmio: &CompositeMmio,
+    ) -> Self {
+        let mut autosave = Self {
+            interval,
+            capacity,
+            since_last: 0,
+            ticks: 0,
+            base_memory: Box::default(),
+            last_memory: Box::default(),
+            checkpoints: VecDeque::with_capacity(capacity),
+        };
+        autosave.restart(state, mmio);
+        autosave
+    }
+
+    /// Drops every checkpoint and starts again from the current machine,
+    /// for when a load or reset makes the old ones meaningless.
+    pub fn restart(&mut self, state: &mut CoreState, mmio: &CompositeMmio) {
+        self.checkpoints.clear();
+        self.since_last = 0;
+        self.ticks = 0;
+        self.base_memory.clone_from(&state.memory);
+        self.last_memory.clone_from(&state.memory);
+        self.checkpoints.push_back(Checkpoint {
+            tick: 0,
+            state: without_memory(state),
+            mmio: mmio.clone(),
+            delta: Vec::new(),
+        });
+    }
+
+    /// Ticks that can end before the next checkpoint is due, so batch runs
+    /// can stop exactly on it.
+    pub const fn ticks_until_due(&self) -> u32 {
+        self.interval - self.since_last
+    }
+
+    /// Counts `ended` ticks, capturing the machine if a checkpoint falls
+    /// due. Runs must not end more than [`Autosave::ticks_until_due`] ticks
+    /// between calls.
+    pub fn ticks_ended(&mut self, ended: u32, state: &mut CoreState, mmio: &CompositeMmio) {
+        self.ticks += u64::from(ended);
+        self.since_last += ended;
+        if self.since_last >= self.interval {
+            self.capture(state, mmio);
+        }
+    }
+
+    fn capture(&mut self, state: &mut CoreState, mmio: &CompositeMmio) {
+        self.since_last = 0;
+        let delta = changed_runs(&state.memory, &self.last_memory);
+        apply(&mut self.last_memory, &delta);
+        self.checkpoints.push_back(Checkpoint {
+            tick: self.ticks,
+            state: without_memory(state),
+            mmio: mmio.clone(),
+            delta,
+        });
+        if self.checkpoints.len() > self.capacity {
+            self.checkpoints.pop_front();
+            if let Some(oldest) = self.checkpoints.front_mut() {
+                apply(&mut self.base_memory, &std::mem::take(&mut oldest.delta));
+            }
+        }
+    }
+
+    /// Checkpoints from oldest to newest.
+    pub fn summaries(&self) -> Vec<CheckpointSummary> {
+        self.checkpoints
+            .iter()
+            .map(|checkpoint| CheckpointSummary {
+                tick: checkpoint.tick,
+                pc: checkpoint.state.arch.pc(),
+                delta_bytes: checkpoint.delta.iter().map(|(_, bytes)| bytes.len()).sum(),
+            })
+            .collect()
+    }
+
+    /// Ticks ended since the ring started.
+    pub const fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Rebuilds the machine at checkpoint `index` (0 is the oldest) and
+    /// drops every newer checkpoint, so the ring carries on from there.
+    /// Returns `None` when there is no such checkpoint.
+    pub fn restore(&mut self, index: usize) -> Option<(CoreState, CompositeMmio)> {
+        let checkpoint = self.checkpoints.get(index)?;
+        let mut memory = self.base_memory.clone();
+        for later in self.checkpoints.range(1..=index) {
+            apply(&mut memory, &later.delta);
+        }
+        let mut state = checkpoint.state.clone();
+        let mmio = checkpoint.mmio.clone();
+        self.ticks = checkpoint.tick;
+        self.since_last = 0;
+        self.checkpoints.truncate(index + 1);
+        self.last_memory.clone_from(&memory);
+        state.memory = memory;
+        Some((state, mmio))
+    }
+}
+
+/// Clones `state` without copying its memory.
+fn without_memory(state: &mut CoreState) -> CoreState {
+    let memory = std::mem::take(&mut state.memory);
+    let copy = state.clone();
+    state.memory = memory;
+    copy
+}
+
+/// Runs of bytes in `current` that differ from `previous`.
+fn changed_runs(current: &[u8], previous: &[u8]) -> Vec<(usize, Vec<u8>)> {
+    let mut runs: Vec<(usize, Vec<u8>)> = Vec::new();
+    for (address, (now, before)) in current.iter().zip(previous).enumerate() {
+        if now == before {
+            continue;
+        }
+        match runs.last_mut() {
+            Some((start, bytes)) if *start + bytes.len() == address => bytes.push(*now),
+            _ => runs.push((address, vec![*now])),
+        }
+    }
+    runs
+}
+
+fn apply(memory: &mut [u8], delta: &[(usize, Vec<u8>)]) {
+    for (start, bytes) in delta {
+        memory[*start..start + bytes.len()].copy_from_slice(bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Autosave;
+    use emulator_core::{CompositeMmio, CoreState};
+
+    #[test]
+    fn evicted_checkpoints_fold_into_the_base_image() {
+        let mut state = CoreState::default();
+        let mmio = CompositeMmio::new();
+        let mut autosave = Autosave::new(2, 3, &mut state, &mmio);
+
+        for value in 1..=5_u8 {
+            state.memory[0x4000 + usize::from(value)] = value;
+            state.memory[0x4000] = value;
+            autosave.ticks_ended(2, &mut state, &mmio);
+        }
+
+        let ticks: Vec<u64> = autosave.summaries().iter().map(|c| c.tick).collect();
+        assert_eq!(ticks, [6, 8, 10]);
+        assert_eq!(autosave.summaries()[0].delta_bytes, 0);
+        assert_eq!(autosave.summaries()[1].delta_bytes, 2);
+
+        let (oldest, _) = autosave.restore(0).unwrap();
+        assert_eq!(oldest.memory[0x4000..0x4006], [3, 1, 2, 3, 0, 0]);
+        assert_eq!(autosave.summaries().len(), 1);
+        assert_eq!(autosave.ticks(), 6);
+        assert!(autosave.restore(1).is_none());
+    }
+}
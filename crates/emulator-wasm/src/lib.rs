@@ -1,26 +1,103 @@
+mod autosave;
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::{Rc, Weak};
+
 use assembler::assembler::{assemble_from_source, AssembleResult};
+use assembler::budget::{block_budgets, BlockBudget};
+use assembler::bundle::{BUNDLE_FORMAT, BUNDLE_VERSION};
+use assembler::metadata::language_metadata;
+use assembler::preview::encode_single_line;
+use assembler::symbols::{Symbol, SymbolTable};
 use emulator_core::{
-    disassemble_window, run_one, step_one, CompositeMmio, CoreConfig, CoreState, RunBoundary,
-    RunOutcome, RunState, StepOutcome, Tele7Config, Tele7Peripheral,
+    check_run_boundary, decode_memory_region, disassemble_window, end_tick, run_fast_forward,
+    run_one, run_ticks_with_budget, step_one, step_out, step_over, write_params, AddressingMode,
+    Breakpoint, BreakpointHit, BytePattern, CompositeMmio, CoreConfig, CoreState, CycleCostTable,
+    DebugConsole, DeviceRegisters, DmaController, FaultCode, HaltReason, MemoryRegion, MemoryWrite,
+    MmioBus, Mpu, PasteBuffer, RunBoundary, RunOutcome, RunState, StepOutcome, StepStop,
+    SteppingOutcome, Tele7Config, Tele7Peripheral, TickBatch, TimingModel, WatchExpr,
+    ADDRESS_SPACE_BYTES, CAP_MPU_BIT, CYCLE_COST_TABLE, MPU_BASE,
 };
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
+use autosave::Autosave;
+
+/// Number of retired PCs reported in [`ExecutionMetadata::pc_history`].
+const PC_HISTORY_DEPTH: u16 = 32;
+
+/// Number of ended ticks reported in [`ExecutionMetadata::tick_usage`]: one
+/// simulated second.
+const TICK_USAGE_DEPTH: u16 = 100;
+
+/// Number of memory writes one step, tick or run call reports before
+/// further writes are only counted.
+const MEMORY_WRITE_LOG_DEPTH: u16 = 256;
+
 /// JS-compatible version of `StepOutcome`.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum WasmStepOutcome {
     Retired { cycles: u16 },
-    HaltedForTick,
+    HaltedForTick { reason: WasmHaltReason },
     TrapDispatch { cause: u16 },
     EventDispatch { event_id: u8 },
     Fault { cause: u8 },
 }
 
-/// JS-compatible version of `RunOutcome`.
+/// JS-compatible version of `HaltReason`.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum WasmHaltReason {
+    Instruction,
+    BudgetExhausted,
+}
+
+impl From<HaltReason> for WasmHaltReason {
+    fn from(value: HaltReason) -> Self {
+        match value {
+            HaltReason::Instruction => Self::Instruction,
+            HaltReason::BudgetExhausted => Self::BudgetExhausted,
+        }
+    }
+}
+
+/// JS-compatible version of `RunOutcome`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct WasmRunOutcome {
     pub steps: u32,
     pub final_step: WasmStepOutcome,
+    pub breakpoint: Option<WasmBreakpointHit>,
+    /// Memory writes the call made, in order.
+    pub memory_writes: Vec<MemoryWrite>,
+    /// Writes made after `memory_writes` filled up, which are not listed.
+    pub memory_writes_dropped: u32,
+}
+
+/// Memory writes made by the last step, tick or run call.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MemoryWrites {
+    /// Words written, as {address, value}, in order.
+    pub writes: Vec<MemoryWrite>,
+    /// Writes made after `writes` filled up, which are not listed.
+    pub dropped: u32,
+}
+
+/// Result of `WasmCore::run_until`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum WasmRunUntilOutcome {
+    /// The run ended at the requested boundary, or on a dispatch or fault.
+    Boundary(WasmRunOutcome),
+    /// `max_steps` instructions executed without reaching the boundary.
+    StoppedAtStepLimit {
+        /// Number of steps executed.
+        steps: u32,
+        /// Outcome of the last step executed, if any.
+        final_step: Option<WasmStepOutcome>,
+        /// Program counter where execution stopped.
+        pc: u16,
+        /// Tick counter where execution stopped.
+        tick: u16,
+    },
 }
 
 /// JS-compatible run boundary selector.
@@ -30,13 +107,17 @@ pub enum WasmRunBoundary {
     TickBoundary,
     Halted,
     Fault,
+    StepCount(u32),
+    Breakpoint,
 }
 
 impl From<StepOutcome> for WasmStepOutcome {
     fn from(value: StepOutcome) -> Self {
         match value {
             StepOutcome::Retired { cycles } => Self::Retired { cycles },
-            StepOutcome::HaltedForTick => Self::HaltedForTick,
+            StepOutcome::HaltedForTick { reason } => Self::HaltedForTick {
+                reason: reason.into(),
+            },
             StepOutcome::TrapDispatch { cause } => Self::TrapDispatch { cause },
             StepOutcome::EventDispatch { event_id } => Self::EventDispatch { event_id },
             StepOutcome::Fault { cause } => Self::Fault {
@@ -52,6 +133,8 @@ impl From<RunBoundary> for WasmRunBoundary {
             RunBoundary::TickBoundary => Self::TickBoundary,
             RunBoundary::Halted => Self::Halted,
             RunBoundary::Fault => Self::Fault,
+            RunBoundary::StepCount(steps) => Self::StepCount(steps),
+            RunBoundary::Breakpoint => Self::Breakpoint,
         }
     }
 }
@@ -62,6 +145,8 @@ impl From<WasmRunBoundary> for RunBoundary {
             WasmRunBoundary::TickBoundary => Self::TickBoundary,
             WasmRunBoundary::Halted => Self::Halted,
             WasmRunBoundary::Fault => Self::Fault,
+            WasmRunBoundary::StepCount(steps) => Self::StepCount(steps),
+            WasmRunBoundary::Breakpoint => Self::Breakpoint,
         }
     }
 }
@@ -71,6 +156,51 @@ impl From<RunOutcome> for WasmRunOutcome {
         Self {
             steps: value.steps,
             final_step: value.final_step.into(),
+            breakpoint: value.breakpoint.map(Into::into),
+            memory_writes: Vec::new(),
+            memory_writes_dropped: 0,
+        }
+    }
+}
+
+/// JS-compatible version of `TickStats`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WasmTickStats {
+    pub steps: u32,
+    pub cycles: u16,
+    pub reason: WasmHaltReason,
+}
+
+/// JS-compatible version of `TickBatch`, with pacing metadata.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WasmTickBatch {
+    pub ticks: Vec<WasmTickStats>,
+    pub total_steps: u64,
+    pub total_cycles: u64,
+    /// Fault code that ended the batch early, if any.
+    pub fault: Option<u8>,
+    /// Simulated milliseconds covered by the completed ticks.
+    pub simulated_ms: u64,
+}
+
+impl From<TickBatch> for WasmTickBatch {
+    fn from(value: TickBatch) -> Self {
+        let simulated_ms =
+            u64::try_from(value.simulated_duration().as_millis()).unwrap_or(u64::MAX);
+        Self {
+            ticks: value
+                .ticks
+                .iter()
+                .map(|tick| WasmTickStats {
+                    steps: tick.steps,
+                    cycles: tick.cycles,
+                    reason: tick.reason.into(),
+                })
+                .collect(),
+            total_steps: value.total_steps,
+            total_cycles: value.total_cycles,
+            fault: value.fault.map(FaultCode::as_u8),
+            simulated_ms,
         }
     }
 }
@@ -90,6 +220,86 @@ pub struct SourceMapEntry {
     pub source: String,
 }
 
+/// Label in a program bundle's symbol table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleSymbol {
+    /// Label name.
+    pub name: String,
+    /// Address the label resolves to.
+    pub address: u16,
+}
+
+/// Title, author and version a program declares with `.title`, `.author`
+/// and `.version`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgramInfo {
+    /// Display title.
+    pub title: Option<String>,
+    /// Author.
+    pub author: Option<String>,
+    /// Program version.
+    pub version: Option<String>,
+}
+
+impl From<&AssembleResult> for ProgramInfo {
+    fn from(result: &AssembleResult) -> Self {
+        Self {
+            title: result.info.title.clone(),
+            author: result.info.author.clone(),
+            version: result.info.version.clone(),
+        }
+    }
+}
+
+/// Program bundle written by `nullbyte-asm bundle`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgramBundle {
+    /// Always `nullbyte-bundle`.
+    pub format: String,
+    /// Bundle layout version.
+    pub version: u32,
+    /// File name the inlined source assembles under.
+    pub name: String,
+    /// Build ID of the binary.
+    pub build_id: String,
+    /// Entry address set by `.entry`, if any.
+    pub entry: Option<u16>,
+    /// Program title, author and version; absent in older bundles.
+    #[serde(default)]
+    pub info: ProgramInfo,
+    /// Source with includes inlined.
+    pub source: String,
+    /// Assembled binary bytes.
+    pub binary: Vec<u8>,
+    /// Source map entries; lines point into `source`.
+    pub source_map: Vec<SourceMapEntry>,
+    /// Labels and their addresses.
+    pub symbols: Vec<BundleSymbol>,
+}
+
+/// What `load_bundle` installed, for the host to set up its views in one go.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleMetadata {
+    /// File name of the bundled source, registered as a virtual file.
+    pub name: String,
+    /// Build ID recorded in the bundle.
+    pub build_id: String,
+    /// Address execution starts at.
+    pub entry: u16,
+    /// Title, author and version recorded in the bundle.
+    pub info: ProgramInfo,
+    /// Size of the loaded binary in bytes.
+    pub size_bytes: usize,
+    /// Virtual files now readable through `read_file`.
+    pub files: Vec<String>,
+    /// Installed source map.
+    pub source_map: Vec<SourceMapEntry>,
+    /// Labels and their addresses, sorted by name.
+    pub symbols: BTreeMap<String, u16>,
+    /// Machine state after loading.
+    pub execution: ExecutionMetadata,
+}
+
 /// Diagnostic severity.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum DiagnosticSeverity {
@@ -121,6 +331,45 @@ pub struct AssembleOnlyResult {
     pub diagnostics: Vec<Diagnostic>,
     /// Build ID (hash of binary for change detection).
     pub build_id: String,
+    /// Entry address set by `.entry`, if any.
+    pub entry: Option<u16>,
+    /// Title, author and version declared by the program.
+    pub info: ProgramInfo,
+    /// Worst-case cycle estimates per basic block, in address order.
+    pub budgets: Vec<BudgetAnnotation>,
+}
+
+/// Worst-case cycle estimate for one basic block, for gutter annotations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetAnnotation {
+    /// Address of the block's first instruction.
+    pub start: u16,
+    /// Address just past the block's last instruction.
+    pub end: u16,
+    /// File containing the block's first instruction.
+    pub file: String,
+    /// 1-indexed line of the block's first instruction.
+    pub line: usize,
+    /// Most cycles one pass through the block can take.
+    pub cycles: u32,
+    /// Most cycles one loop iteration can take, when the block heads a loop.
+    pub loop_cycles: Option<u32>,
+    /// Gutter text, e.g. `loop body: 37 cycles/iteration`.
+    pub annotation: String,
+}
+
+impl From<BlockBudget> for BudgetAnnotation {
+    fn from(budget: BlockBudget) -> Self {
+        Self {
+            annotation: budget.annotation(),
+            start: budget.start,
+            end: budget.end,
+            file: budget.file,
+            line: budget.line,
+            cycles: budget.cycles,
+            loop_cycles: budget.loop_cycles,
+        }
+    }
 }
 
 /// Execution metadata for editor overlays.
@@ -138,14 +387,472 @@ pub struct ExecutionMetadata {
     pub has_fault: bool,
     /// Latched fault code if any.
     pub fault_code: Option<u8>,
+    /// Most recently retired program counters, oldest first.
+    pub pc_history: Vec<u16>,
+    /// Cycles used by recently ended ticks, oldest first.
+    pub tick_usage: Vec<u16>,
+    /// Cycle budget each tick's usage is measured against.
+    pub tick_budget: u16,
+    /// `CoreState::state_digest` as 16 hex digits.
+    pub state_digest: String,
+}
+
+/// Result of running until a breakpoint.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BreakpointRunResult {
+    /// Number of steps executed.
+    pub steps: u32,
+    /// Outcome of the last step executed.
+    pub final_step: Option<WasmStepOutcome>,
+    /// Breakpoint that stopped the run, if any.
+    pub breakpoint: Option<WasmBreakpointHit>,
+}
+
+/// JS-compatible version of `BreakpointHit`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WasmBreakpointHit {
+    /// Address of the breakpoint.
+    pub address: u16,
+    /// Qualifying hits including this one.
+    pub hit_count: u32,
+    /// Whether the breakpoint was temporary and has been removed.
+    pub removed: bool,
+}
+
+impl From<BreakpointHit> for WasmBreakpointHit {
+    fn from(hit: BreakpointHit) -> Self {
+        Self {
+            address: hit.address,
+            hit_count: hit.hit_count,
+            removed: hit.removed,
+        }
+    }
+}
+
+/// JS-compatible version of `StepStop`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum WasmStepStop {
+    Completed,
+    Breakpoint(WasmBreakpointHit),
+    HaltedForTick,
+    Fault,
+    StepLimit,
+}
+
+impl From<StepStop> for WasmStepStop {
+    fn from(stop: StepStop) -> Self {
+        match stop {
+            StepStop::Completed => Self::Completed,
+            StepStop::Breakpoint(hit) => Self::Breakpoint(hit.into()),
+            StepStop::HaltedForTick => Self::HaltedForTick,
+            StepStop::Fault => Self::Fault,
+            StepStop::StepLimit => Self::StepLimit,
+        }
+    }
+}
+
+/// JS-compatible version of `SteppingOutcome`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WasmSteppingOutcome {
+    pub steps: u32,
+    pub final_step: Option<WasmStepOutcome>,
+    pub stop: WasmStepStop,
+}
+
+impl From<SteppingOutcome> for WasmSteppingOutcome {
+    fn from(outcome: SteppingOutcome) -> Self {
+        Self {
+            steps: outcome.steps,
+            final_step: outcome.final_step.map(Into::into),
+            stop: outcome.stop.into(),
+        }
+    }
+}
+
+/// Breakpoint entry reported by `list_breakpoints`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BreakpointInfo {
+    /// Instruction address.
+    pub address: u16,
+    /// Whether the breakpoint has a condition.
+    pub conditional: bool,
+    /// Remaining qualifying hits to skip.
+    pub ignore_count: u32,
+    /// Whether the breakpoint removes itself after firing.
+    pub temporary: bool,
+    /// Qualifying hits so far.
+    pub hit_count: u32,
+}
+
+/// Autosave checkpoint entry reported by `list_checkpoints`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CheckpointInfo {
+    /// Index to pass to `restore_checkpoint`; 0 is the oldest.
+    pub index: usize,
+    /// Ticks ended since the ring started when the checkpoint was taken.
+    pub tick: u64,
+    /// Ticks ended since the checkpoint was taken.
+    pub ticks_ago: u64,
+    /// Program counter at the checkpoint.
+    pub pc: u16,
+    /// Memory bytes the checkpoint stores beyond the previous one.
+    pub delta_bytes: usize,
+}
+
+/// Result of a hot-swap rebuild.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotSwapResult {
+    /// Build ID of the newly loaded binary.
+    pub build_id: String,
+    /// Memory regions rewritten with the new image as [start, end] pairs (inclusive).
+    pub patched_regions: Vec<[u16; 2]>,
+    /// Whether PC, registers and RAM were kept (false means a clean reload).
+    pub state_preserved: bool,
+}
+
+/// Completion data for the editor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditorMetadata {
+    /// Instructions and pseudo-instructions.
+    pub mnemonics: Vec<EditorMnemonic>,
+    /// Assembler directives.
+    pub directives: Vec<EditorDirective>,
+    /// Register names.
+    pub registers: Vec<String>,
+}
+
+/// A mnemonic and its usage forms.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditorMnemonic {
+    pub name: String,
+    pub signatures: Vec<String>,
+    pub pseudo: bool,
+}
+
+/// A directive and its argument forms.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditorDirective {
+    pub name: String,
+    pub signatures: Vec<String>,
+}
+
+/// Annotation for one byte of memory, for labelling hex-viewer rows.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MemoryAnnotation {
+    /// Byte address.
+    pub address: u16,
+    /// Memory region name (`ROM`, `RAM`, `MMIO`, `DIAG` or `RESERVED`).
+    pub region: String,
+    /// Nearest label at or below `address` in the same region, if any.
+    pub symbol: Option<String>,
+    /// Offset of `address` from `symbol`.
+    pub offset: Option<u16>,
+}
+
+/// Current value of an MMIO register split into its bit fields, for editor
+/// hovers.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MmioDecode {
+    /// Owning device, e.g. `TELE7`.
+    pub device: String,
+    /// Register name, e.g. `CTRL`.
+    pub register: String,
+    /// Register address.
+    pub address: u16,
+    /// Raw register value.
+    pub value: u16,
+    /// Bit field values, lowest field first.
+    pub fields: Vec<MmioField>,
+    /// One-line rendering, e.g. `TELE7_CTRL: ENABLE=1, LIVE_READ=0`.
+    pub summary: String,
+}
+
+/// One bit field of an [`MmioDecode`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MmioField {
+    pub name: String,
+    pub value: u16,
+}
+
+/// Encoding preview for one source line, for editor hovers.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LinePreview {
+    /// Emitted bytes.
+    pub bytes: Vec<u8>,
+    /// Size in 16-bit words.
+    pub size_words: usize,
+    /// Fewest cycles the instruction retires in.
+    pub min_cycles: Option<u16>,
+    /// Most cycles the instruction retires in (branch taken).
+    pub max_cycles: Option<u16>,
+    /// Operand addressing mode name, e.g. `immediate`.
+    pub addressing_mode: Option<String>,
 }
 
+/// Code/data layout of an assembled program, used to decide whether a rebuild
+/// can be patched in without disturbing execution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProgramLayout {
+    /// Label addresses, sorted by name.
+    symbols: BTreeMap<String, u16>,
+    /// Start address and length of every emitted line.
+    boundaries: Vec<(u16, usize)>,
+    /// Address execution starts at after a reset.
+    entry: u16,
+}
+
+impl ProgramLayout {
+    /// Returns the nearest label at or below `address` within the same
+    /// memory region, skipping assembler-generated labels.
+    fn nearest_symbol(&self, address: u16) -> Option<(&str, u16)> {
+        let region = decode_memory_region(address);
+        self.symbols
+            .iter()
+            .filter(|(name, &symbol)| {
+                !name.starts_with('.')
+                    && symbol <= address
+                    && decode_memory_region(symbol) == region
+            })
+            .max_by_key(|(name, &symbol)| (symbol, std::cmp::Reverse(name.as_str())))
+            .map(|(name, &symbol)| (name.as_str(), address - symbol))
+    }
+
+    fn of(result: &AssembleResult) -> Self {
+        Self {
+            symbols: result
+                .symbols
+                .iter()
+                .map(|(name, symbol)| (name.clone(), symbol.address))
+                .collect(),
+            boundaries: result
+                .listing
+                .iter()
+                .map(|entry| (entry.address, entry.bytes.len()))
+                .collect(),
+            entry: result.entry.unwrap_or_default(),
+        }
+    }
+}
+
+/// Everything a loaded program brings with it besides the machine state.
+///
+/// Cores hold it behind an `Rc`, so cores loaded from one [`SharedProgram`]
+/// share a single copy; a core that changes its program (hot swap) detaches
+/// its own copy first.
+#[derive(Debug, Clone, Default)]
+struct ProgramAssets {
+    /// Memory image, padded to the full address space; empty before the
+    /// first load.
+    binary: Vec<u8>,
+    layout: Option<ProgramLayout>,
+    /// Virtual files by name, for `read_file`.
+    files: BTreeMap<String, String>,
+    source_map: Vec<SourceMapEntry>,
+    info: ProgramInfo,
+}
+
+impl ProgramAssets {
+    fn assembled(result: AssembleResult, source: &str, file_name: &str) -> Self {
+        Self {
+            binary: padded_image(&result.binary),
+            layout: Some(ProgramLayout::of(&result)),
+            files: BTreeMap::from([(file_name.to_string(), source.to_string())]),
+            info: ProgramInfo::from(&result),
+            source_map: result
+                .listing
+                .into_iter()
+                .map(|entry| SourceMapEntry {
+                    address: entry.address,
+                    len_bytes: entry.bytes.len(),
+                    file: file_name.to_string(),
+                    line: entry.line,
+                    source: entry.source,
+                })
+                .collect(),
+        }
+    }
+
+    fn from_bundle(bundle: ProgramBundle) -> Self {
+        let layout = ProgramLayout {
+            symbols: bundle
+                .symbols
+                .iter()
+                .map(|symbol| (symbol.name.clone(), symbol.address))
+                .collect(),
+            boundaries: bundle
+                .source_map
+                .iter()
+                .map(|entry| (entry.address, entry.len_bytes))
+                .collect(),
+            entry: bundle.entry.unwrap_or_default(),
+        };
+        Self {
+            binary: padded_image(&bundle.binary),
+            layout: Some(layout),
+            files: BTreeMap::from([(bundle.name, bundle.source)]),
+            source_map: bundle.source_map,
+            info: bundle.info,
+        }
+    }
+}
+
+/// An assembled program that any number of [`WasmCore`]s can load without
+/// each keeping its own copy of the image, source map and symbols.
+///
+/// Cores stay independent once loaded: each runs, resets and hot swaps on
+/// its own state.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct SharedProgram {
+    assets: Rc<ProgramAssets>,
+}
+
+#[wasm_bindgen]
+impl SharedProgram {
+    /// Assembles `source` once for loading into several cores.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when assembly fails.
+    pub fn assemble(source: &str, file_name: &str) -> Result<Self, JsValue> {
+        let result = assemble_from_source(source, file_name)
+            .map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(Self {
+            assets: Rc::new(ProgramAssets::assembled(result, source, file_name)),
+        })
+    }
+
+    /// Parses a bundle written by `nullbyte-asm bundle` once for loading
+    /// into several cores.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when the JSON is malformed or the bundle
+    /// format or version is unsupported.
+    pub fn from_bundle(json: &str) -> Result<Self, JsValue> {
+        let bundle = parse_bundle(json).map_err(|err| JsValue::from_str(&err))?;
+        Ok(Self {
+            assets: Rc::new(ProgramAssets::from_bundle(bundle)),
+        })
+    }
+
+    /// Returns how many cores currently share this program.
+    #[must_use]
+    pub fn core_count(&self) -> usize {
+        Rc::strong_count(&self.assets) - 1
+    }
+}
+
+/// Inputs a subscribed core has not applied yet.
+type Inbox = Rc<RefCell<Vec<FanoutInput>>>;
+
+/// One host input queued through an [`InputFanout`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FanoutInput {
+    Event(u8),
+    Paste(String),
+}
+
+/// Sends the same host input to every subscribed [`WasmCore`], so cores
+/// running one program side by side see identical input.
+///
+/// Cores subscribe with [`WasmCore::subscribe`]. A core dropped by the host
+/// is forgotten on the next send.
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct InputFanout {
+    inboxes: Vec<Weak<RefCell<Vec<FanoutInput>>>>,
+}
+
+#[wasm_bindgen]
+impl InputFanout {
+    #[wasm_bindgen(constructor)]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues `event_id` for every subscribed core, as `enqueue_event`.
+    ///
+    /// Returns the number of cores it was sent to.
+    pub fn enqueue_event(&mut self, event_id: u8) -> usize {
+        self.send(&FanoutInput::Event(event_id))
+    }
+
+    /// Pastes `text` into every subscribed core, as `paste_text`.
+    ///
+    /// Returns the number of cores it was sent to.
+    pub fn paste_text(&mut self, text: &str) -> usize {
+        self.send(&FanoutInput::Paste(text.to_string()))
+    }
+
+    /// Returns the number of cores still subscribed.
+    #[must_use]
+    pub fn subscribers(&self) -> usize {
+        self.inboxes
+            .iter()
+            .filter(|inbox| inbox.strong_count() > 0)
+            .count()
+    }
+}
+
+impl InputFanout {
+    fn send(&mut self, input: &FanoutInput) -> usize {
+        self.inboxes.retain(|inbox| inbox.strong_count() > 0);
+        for inbox in self.inboxes.iter().filter_map(Weak::upgrade) {
+            inbox.borrow_mut().push(input.clone());
+        }
+        self.inboxes.len()
+    }
+}
+
+/// Returns mnemonics, directives and register names for editor completion.
+///
+/// # Errors
+///
+/// Returns a JS error value when result serialization fails.
+#[wasm_bindgen]
+pub fn get_editor_metadata() -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(&editor_metadata())
+        .map_err(|err| JsValue::from_str(&err.to_string()))
+}
+
+fn editor_metadata() -> EditorMetadata {
+    let metadata = language_metadata();
+    EditorMetadata {
+        mnemonics: metadata
+            .mnemonics
+            .into_iter()
+            .map(|info| EditorMnemonic {
+                name: info.name,
+                signatures: info.signatures,
+                pseudo: info.pseudo,
+            })
+            .collect(),
+        directives: metadata
+            .directives
+            .into_iter()
+            .map(|info| EditorDirective {
+                name: info.name,
+                signatures: info.signatures,
+            })
+            .collect(),
+        registers: metadata.registers,
+    }
+}
+
+/// Core batch runner: `run_ticks_with_budget` or `run_fast_forward`.
+type BatchRunner = fn(&mut CoreState, &mut dyn MmioBus, &CoreConfig, u32) -> TickBatch;
+
 #[wasm_bindgen]
 pub struct WasmCore {
     state: CoreState,
     config: CoreConfig,
     mmio: CompositeMmio,
-    original_binary: Vec<u8>,
+    program: Rc<ProgramAssets>,
+    params: Vec<u8>,
+    autosave: Option<Autosave>,
+    inbox: Option<Inbox>,
 }
 
 #[wasm_bindgen]
@@ -153,35 +860,74 @@ impl WasmCore {
     #[must_use]
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
+        Self::with_config(CoreConfig::default())
+    }
+
+    /// Creates a core for untrusted programs, using the
+    /// `CoreConfig::sandbox` preset: restricted capabilities, a lower tick
+    /// budget, and every run call capped at one simulated second or its
+    /// step equivalent, whatever count the caller passes.
+    #[must_use]
+    pub fn new_sandboxed() -> Self {
+        Self::with_config(CoreConfig::sandbox())
+    }
+
+    fn with_config(base: CoreConfig) -> Self {
         console_error_panic_hook::set_once();
-        let config = CoreConfig::default();
-        let mmio = CompositeMmio::new().with_tele7(Tele7Peripheral::new(Tele7Config::default()));
+        let config = CoreConfig {
+            pc_history_depth: PC_HISTORY_DEPTH,
+            tick_usage_depth: TICK_USAGE_DEPTH,
+            memory_write_log_depth: MEMORY_WRITE_LOG_DEPTH,
+            ..base
+        };
+        let mmio = CompositeMmio::new()
+            .with_tele7(Tele7Peripheral::new(Tele7Config::default()))
+            .with_console(DebugConsole::new())
+            .with_dma(DmaController::new())
+            .with_paste(PasteBuffer::new());
         Self {
             state: CoreState::with_config(&config),
             config,
             mmio,
-            original_binary: Vec::new(),
+            program: Rc::default(),
+            params: Vec::new(),
+            autosave: None,
+            inbox: None,
         }
     }
 
     fn load_program_with_tracking(&mut self, program: &[u8]) {
         let len = program.len().min(self.state.memory.len());
         self.state.memory[..len].copy_from_slice(&program[..len]);
-        self.original_binary = program.to_vec();
-        while self.original_binary.len() < self.state.memory.len() {
-            self.original_binary.push(0);
-        }
+        Rc::make_mut(&mut self.program).binary = padded_image(program);
+        self.restart_autosave();
+    }
+
+    /// Makes `program` the loaded program: copies its image into memory and
+    /// moves PC to its entry point, which later resets start from too.
+    fn install_program(&mut self, program: Rc<ProgramAssets>) {
+        let entry = program.layout.as_ref().map_or(0, |layout| layout.entry);
+        self.config.reset_pc = entry;
+        self.state.arch.set_pc(entry);
+        self.state.memory.copy_from_slice(&program.binary);
+        self.program = program;
+        self.restart_autosave();
     }
 
     /// Loads a program into memory starting at address 0x0000.
     pub fn load_program(&mut self, program: &[u8]) {
         let len = program.len().min(self.state.memory.len());
         self.state.memory[..len].copy_from_slice(&program[..len]);
+        self.restart_autosave();
     }
 
     /// Assembles assembly source text (`.n1` or `.n1.md`) and loads it.
     ///
     /// `file_name` is used to select plain vs literate extraction semantics.
+    /// PC moves to the program's `.entry` label (0x0000 without one), and
+    /// later resets start there too. The source is registered as a virtual
+    /// file under `file_name` and its source map and `.title`, `.author` and
+    /// `.version` metadata installed (see `get_program_info`).
     ///
     /// # Errors
     ///
@@ -195,43 +941,180 @@ impl WasmCore {
         let result = assemble_from_source(source, file_name)
             .map_err(|err| JsValue::from_str(&err.to_string()))?;
 
-        self.load_program_with_tracking(&result.binary);
+        self.install_program(Rc::new(ProgramAssets::assembled(result, source, file_name)));
         Ok(())
     }
 
-    /// Assembles source text without loading into memory.
+    /// Loads a program built once with `SharedProgram`, sharing its image,
+    /// symbols, source map and virtual files with every other core that
+    /// loads it instead of copying them.
     ///
-    /// Returns a JSON object containing:
-    /// - `binary`: array of bytes
-    /// - `source_map`: array of {address, `len_bytes`, file, line, source}
-    /// - `diagnostics`: array of {severity, file, line, message}
-    /// - `build_id`: hash string for change detection
+    /// Behaves like `assemble_and_load_program` or `load_bundle` otherwise:
+    /// PC moves to the program's entry point, and later resets start there.
+    pub fn load_shared_program(&mut self, program: &SharedProgram) {
+        self.install_program(Rc::clone(&program.assets));
+    }
+
+    /// Sends every input `fanout` queues to this core, replacing any
+    /// earlier subscription.
+    ///
+    /// Inputs are applied at the start of the core's next step, tick or run
+    /// call, as if `enqueue_event` or `paste_text` had been called then.
+    pub fn subscribe(&mut self, fanout: &mut InputFanout) {
+        let inbox = Inbox::default();
+        fanout.inboxes.push(Rc::downgrade(&inbox));
+        self.inbox = Some(inbox);
+    }
+
+    /// Stops receiving input from the fan-out this core subscribed to,
+    /// discarding anything not yet applied.
+    pub fn unsubscribe(&mut self) {
+        self.inbox = None;
+    }
+
+    /// Loads a program bundle written by `nullbyte-asm bundle` from its
+    /// JSON text.
+    ///
+    /// The bundled source is registered as a virtual file (see `read_file`),
+    /// the binary is loaded as-is without reassembling, and the bundle's
+    /// symbols and source map back the same lookups and overlays as an
+    /// assembled program. PC moves to the bundle's entry point, and later
+    /// resets start there too.
+    ///
+    /// Returns a `BundleMetadata` object describing everything installed.
     ///
     /// # Errors
     ///
-    /// Returns a JS error value when assembly fails.
-    pub fn assemble_only(&self, source: &str, file_name: &str) -> Result<JsValue, JsValue> {
-        let result = assemble_from_source(source, file_name)
-            .map_err(|err| JsValue::from_str(&err.to_string()))?;
+    /// Returns a JS error value when the text is not a bundle or uses an
+    /// unsupported format version; the loaded program is unchanged then.
+    pub fn load_bundle(&mut self, json: &str) -> Result<JsValue, JsValue> {
+        let metadata = self
+            .load_bundle_internal(json)
+            .map_err(|err| JsValue::from_str(&err))?;
+        serde_wasm_bindgen::to_value(&metadata).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
 
-        let assemble_result = convert_assemble_result(result, file_name);
+    /// Returns the text of a virtual file registered by `load_bundle` or
+    /// `assemble_and_load_program`, or `undefined` when there is none by
+    /// that name.
+    #[must_use]
+    pub fn read_file(&self, name: &str) -> Option<String> {
+        self.program.files.get(name).cloned()
+    }
 
-        serde_wasm_bindgen::to_value(&assemble_result)
+    /// Returns the loaded program's title, author and version as
+    /// {title, author, version}, each null when the program does not set it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when result serialization fails.
+    pub fn get_program_info(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.program.info)
             .map_err(|err| JsValue::from_str(&err.to_string()))
     }
 
-    /// Patches memory at a specific address range.
-    ///
-    /// This is a targeted update that only modifies the specified range,
-    /// preserving execution state (registers, flags, etc.).
+    /// Returns the loaded program's source map as an array of
+    /// {address, `len_bytes`, file, line, source}.
     ///
     /// # Errors
     ///
-    /// Returns a JS error if the address range is invalid.
-    #[allow(clippy::cast_possible_truncation)]
-    pub fn patch_memory(&mut self, address: u16, data: &[u8]) -> Result<(), JsValue> {
-        let start = address as usize;
-        let end = start.saturating_add(data.len());
+    /// Returns a JS error value when result serialization fails.
+    pub fn get_source_map(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.program.source_map)
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    fn load_bundle_internal(&mut self, json: &str) -> Result<BundleMetadata, String> {
+        let bundle = parse_bundle(json)?;
+        let name = bundle.name.clone();
+        let build_id = bundle.build_id.clone();
+        let size_bytes = bundle.binary.len();
+        self.install_program(Rc::new(ProgramAssets::from_bundle(bundle)));
+
+        let program = &self.program;
+        Ok(BundleMetadata {
+            name,
+            build_id,
+            entry: self.config.reset_pc,
+            info: program.info.clone(),
+            size_bytes,
+            files: program.files.keys().cloned().collect(),
+            source_map: program.source_map.clone(),
+            symbols: program
+                .layout
+                .as_ref()
+                .map(|layout| layout.symbols.clone())
+                .unwrap_or_default(),
+            execution: self.get_metadata_internal(),
+        })
+    }
+
+    /// Reassembles source text and swaps it into the running machine.
+    ///
+    /// The new image is diffed against the previously loaded one and only the
+    /// changed regions are written to memory. When every label and line keeps
+    /// its address, PC, registers and the rest of memory are left untouched so
+    /// execution continues with the edited code; otherwise the core is reset
+    /// and the new program loaded from scratch.
+    ///
+    /// Returns a JSON object containing:
+    /// - `build_id`: hash string of the new binary
+    /// - `patched_regions`: array of [start, end] pairs that were rewritten
+    /// - `state_preserved`: boolean, false when a clean reload was needed
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when assembly fails; the loaded program and
+    /// machine state are unchanged in that case.
+    pub fn rebuild_and_hot_swap(
+        &mut self,
+        source: &str,
+        file_name: &str,
+    ) -> Result<JsValue, JsValue> {
+        let result = assemble_from_source(source, file_name)
+            .map_err(|err| JsValue::from_str(&err.to_string()))?;
+
+        let swap = self.hot_swap_internal(result, source, file_name);
+        serde_wasm_bindgen::to_value(&swap).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// Assembles source text without loading into memory.
+    ///
+    /// Returns a JSON object containing:
+    /// - `binary`: array of bytes
+    /// - `source_map`: array of {address, `len_bytes`, file, line, source}
+    /// - `diagnostics`: array of {severity, file, line, message}
+    /// - `build_id`: hash string for change detection
+    /// - `entry`: address set by `.entry`, or null
+    /// - `info`: {title, author, version} from the metadata directives
+    /// - `budgets`: array of {start, end, file, line, cycles, `loop_cycles`,
+    ///   annotation}, one per basic block with its worst-case cycle count
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when assembly fails.
+    pub fn assemble_only(&self, source: &str, file_name: &str) -> Result<JsValue, JsValue> {
+        let result = assemble_from_source(source, file_name)
+            .map_err(|err| JsValue::from_str(&err.to_string()))?;
+
+        let assemble_result = convert_assemble_result(result, file_name);
+
+        serde_wasm_bindgen::to_value(&assemble_result)
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// Patches memory at a specific address range.
+    ///
+    /// This is a targeted update that only modifies the specified range,
+    /// preserving execution state (registers, flags, etc.).
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error if the address range is invalid.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn patch_memory(&mut self, address: u16, data: &[u8]) -> Result<(), JsValue> {
+        let start = address as usize;
+        let end = start.saturating_add(data.len());
 
         if end > self.state.memory.len() {
             return Err(JsValue::from_str(&format!(
@@ -258,20 +1141,53 @@ impl WasmCore {
         serde_wasm_bindgen::to_value(&metadata).map_err(|err| JsValue::from_str(&err.to_string()))
     }
 
+    /// Returns a stable hash of registers, memory, the event queue and run
+    /// state as 16 hex digits, for hosts that only need to know whether the
+    /// machine changed since they last looked.
+    #[must_use]
+    pub fn state_digest(&self) -> String {
+        format!("{:016x}", self.state.state_digest())
+    }
+
+    /// Returns the memory writes made by the last step, tick or run call as
+    /// {writes: [{address, value}], dropped}, so hosts can highlight exactly
+    /// the words an instruction touched. Writes by peripherals at tick end
+    /// are not included.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when serialization fails.
+    pub fn get_memory_writes(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.memory_writes_internal())
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// Queues `event_id` for the program as a host input would, before the
+    /// next step. Returns false when the queue is full; the core then
+    /// latches the overflow and faults at its next dispatch check, like the
+    /// hardware does.
+    pub fn enqueue_event(&mut self, event_id: u8) -> bool {
+        self.state.enqueue_event(event_id).is_ok()
+    }
+
     /// Resets the core to its initial state.
+    ///
+    /// Breakpoints are kept.
     pub fn reset(&mut self) {
-        self.state = CoreState::with_config(&self.config);
+        self.reset_state();
+        self.restart_autosave();
     }
 
     /// Resets the core and reloads the last loaded program.
     ///
-    /// This is a "clean run" that resets all state.
+    /// This is a "clean run" that resets all state except breakpoints.
     pub fn reset_and_reload(&mut self) {
-        self.state = CoreState::with_config(&self.config);
-        if !self.original_binary.is_empty() {
-            let len = self.original_binary.len().min(self.state.memory.len());
-            self.state.memory[..len].copy_from_slice(&self.original_binary[..len]);
+        self.reset_state();
+        if !self.program.binary.is_empty() {
+            let len = self.program.binary.len().min(self.state.memory.len());
+            self.state.memory[..len].copy_from_slice(&self.program.binary[..len]);
         }
+        self.restart_autosave();
     }
 
     /// Executes a single instruction and returns the outcome as a JSON object.
@@ -287,6 +1203,11 @@ impl WasmCore {
     /// Executes one complete tick (until tick boundary) and returns the outcome.
     /// Resets TICK to 0 and transitions from `HaltedForTick` to Running.
     ///
+    /// Trap and event dispatches inside the tick do not end it, so a tick
+    /// here matches one from the native `run_ticks_with_budget`; the
+    /// outcome's `steps` covers the whole tick. A latched fault ends the
+    /// call without ending the tick.
+    ///
     /// # Errors
     ///
     /// Returns a JS error value when result serialization fails.
@@ -298,19 +1219,277 @@ impl WasmCore {
     /// Runs until the supplied boundary and returns the run outcome as JSON.
     ///
     /// `boundary_val` accepts serialized `WasmRunBoundary` values, or defaults to
-    /// `TickBoundary` if parsing fails.
+    /// `TickBoundary` if parsing fails. Reaching `TickBoundary` ends the tick
+    /// exactly like [`WasmCore::tick`]: TICK resets, peripherals advance and
+    /// the core is ready to run again. When `max_steps` is given, at most
+    /// that many instructions execute and a program that gets no further
+    /// reports `StoppedAtStepLimit` instead of blocking the page; when it is
+    /// omitted the run is bounded only by the core's run limits.
     ///
     /// # Errors
     ///
     /// Returns a JS error value when result serialization fails.
-    pub fn run_until(&mut self, boundary_val: JsValue) -> Result<JsValue, JsValue> {
+    pub fn run_until(
+        &mut self,
+        boundary_val: JsValue,
+        max_steps: Option<u32>,
+    ) -> Result<JsValue, JsValue> {
         let boundary = serde_wasm_bindgen::from_value::<WasmRunBoundary>(boundary_val)
             .unwrap_or_default()
             .into();
-        let outcome = self.run_internal(boundary);
+        let outcome = self.run_internal(boundary, max_steps);
         serde_wasm_bindgen::to_value(&outcome).map_err(|err| JsValue::from_str(&err.to_string()))
     }
 
+    /// Runs up to `n_ticks` whole ticks in one call and returns per-tick step
+    /// and cycle counts.
+    ///
+    /// Meant for hosts pacing execution at 100 Hz: run the ticks that are
+    /// due, then wait until wall-clock time catches up with `simulated_ms`.
+    /// The batch ends early if a fault latches.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when result serialization fails.
+    pub fn run_ticks(&mut self, n_ticks: u32) -> Result<JsValue, JsValue> {
+        let batch = self.run_ticks_internal(n_ticks);
+        serde_wasm_bindgen::to_value(&batch).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// Skips ahead `n_ticks` whole ticks as fast as possible, for "skip
+    /// ahead" controls, and returns the same batch report as
+    /// [`WasmCore::run_ticks`].
+    ///
+    /// The result is identical to `run_ticks`; peripheral tick work is
+    /// batched instead of run at every tick, so hosts should render the
+    /// display once afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when result serialization fails.
+    pub fn fast_forward(&mut self, n_ticks: u32) -> Result<JsValue, JsValue> {
+        let batch = self.fast_forward_internal(n_ticks);
+        serde_wasm_bindgen::to_value(&batch).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// Starts capturing a checkpoint every `interval_ticks` ended ticks,
+    /// keeping the newest `capacity` of them, so hosts can offer "rewind 1
+    /// second" without snapshot plumbing of their own.
+    ///
+    /// The current machine is captured straight away. Loading a program or
+    /// resetting drops the checkpoints and starts the ring again. A zero
+    /// interval or capacity turns autosave off.
+    pub fn enable_autosave(&mut self, interval_ticks: u32, capacity: usize) {
+        self.autosave = (interval_ticks > 0 && capacity > 0)
+            .then(|| Autosave::new(interval_ticks, capacity, &mut self.state, &self.mmio));
+    }
+
+    /// Stops autosave and drops its checkpoints.
+    pub fn disable_autosave(&mut self) {
+        self.autosave = None;
+    }
+
+    /// Lists autosave checkpoints, oldest first, as an array of {index,
+    /// tick, `ticks_ago`, pc, `delta_bytes`}. Empty when autosave is off.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when result serialization fails.
+    pub fn list_checkpoints(&self) -> Result<JsValue, JsValue> {
+        let checkpoints = self.list_checkpoints_internal();
+        serde_wasm_bindgen::to_value(&checkpoints)
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// Rewinds registers, memory and peripherals to checkpoint `index` from
+    /// [`WasmCore::list_checkpoints`] and drops every newer checkpoint.
+    /// Breakpoints are kept. Returns false, changing nothing, when there is
+    /// no such checkpoint.
+    pub fn restore_checkpoint(&mut self, index: usize) -> bool {
+        let Some((mut state, mmio)) = self
+            .autosave
+            .as_mut()
+            .and_then(|autosave| autosave.restore(index))
+        else {
+            return false;
+        };
+        state.breakpoints = std::mem::take(&mut self.state.breakpoints);
+        self.state = state;
+        self.mmio = mmio;
+        true
+    }
+
+    /// Sets a breakpoint at `address`, replacing any existing one there.
+    ///
+    /// `condition` is a watch expression such as `R3 == 0 && [0x4100] != 0`;
+    /// the breakpoint only fires when it evaluates non-zero. An empty
+    /// condition makes the breakpoint unconditional. The first
+    /// `ignore_count` qualifying hits are skipped, and a `temporary`
+    /// breakpoint removes itself after it fires.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when the condition does not parse.
+    pub fn set_breakpoint(
+        &mut self,
+        address: u16,
+        condition: &str,
+        ignore_count: u32,
+        temporary: bool,
+    ) -> Result<(), JsValue> {
+        let mut breakpoint = Breakpoint::new(address).with_ignore_count(ignore_count);
+        if !condition.trim().is_empty() {
+            let expr = WatchExpr::parse(condition.trim())
+                .map_err(|err| JsValue::from_str(&err.to_string()))?;
+            breakpoint = breakpoint.with_condition(expr);
+        }
+        if temporary {
+            breakpoint = breakpoint.temporary();
+        }
+        self.state.breakpoints.insert(breakpoint);
+        Ok(())
+    }
+
+    /// Returns all breakpoints in address order as a JSON array.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when result serialization fails.
+    pub fn list_breakpoints(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.breakpoint_infos())
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// Removes the breakpoint at `address`, returning whether one existed.
+    pub fn remove_breakpoint(&mut self, address: u16) -> bool {
+        self.state.breakpoints.remove(address)
+    }
+
+    /// Removes all breakpoints.
+    pub fn clear_breakpoints(&mut self) {
+        self.state.breakpoints.clear();
+    }
+
+    /// Evaluates a watch expression against the current state.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when the expression does not parse.
+    pub fn evaluate_watch(&self, expression: &str) -> Result<u16, JsValue> {
+        let expr =
+            WatchExpr::parse(expression).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(expr.evaluate(&self.state.arch, &self.state.memory))
+    }
+
+    /// Steps until a breakpoint fires, the core halts or faults, or
+    /// `max_steps` instructions have executed.
+    ///
+    /// The instruction at the current PC always executes first, so calling
+    /// this again after a hit resumes past the breakpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when result serialization fails.
+    pub fn run_to_breakpoint(&mut self, max_steps: u32) -> Result<JsValue, JsValue> {
+        let result = self.run_to_breakpoint_internal(max_steps);
+        serde_wasm_bindgen::to_value(&result).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// Executes one instruction, running a `CALL` and its callee as a
+    /// single step.
+    ///
+    /// Stops early at a breakpoint, tick boundary, fault, or after
+    /// `max_steps` instructions.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when result serialization fails.
+    pub fn step_over(&mut self, max_steps: u32) -> Result<JsValue, JsValue> {
+        let outcome = self.step_over_internal(max_steps);
+        serde_wasm_bindgen::to_value(&outcome).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// Runs until the current subroutine or handler returns to its caller.
+    ///
+    /// Stops early at a breakpoint, tick boundary, fault, or after
+    /// `max_steps` instructions.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when result serialization fails.
+    pub fn step_out(&mut self, max_steps: u32) -> Result<JsValue, JsValue> {
+        let outcome = self.step_out_internal(max_steps);
+        serde_wasm_bindgen::to_value(&outcome).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// Returns the loaded program's labels as a JSON object of name to address.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when result serialization fails.
+    pub fn get_symbols(&self) -> Result<JsValue, JsValue> {
+        let symbols = self
+            .program
+            .layout
+            .as_ref()
+            .map(|layout| layout.symbols.clone())
+            .unwrap_or_default();
+        serde_wasm_bindgen::to_value(&symbols).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// Encodes a single source line as if placed at `pc`, resolving labels
+    /// against the loaded program, and returns a `LinePreview`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when the line does not parse or encode, or
+    /// when result serialization fails.
+    pub fn encode_single_line(&self, line: &str, pc: u16) -> Result<JsValue, JsValue> {
+        let preview = self
+            .encode_single_line_internal(line, pc)
+            .map_err(|err| JsValue::from_str(&err))?;
+        serde_wasm_bindgen::to_value(&preview).map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// Annotates `len` bytes starting at `addr` with their memory region and
+    /// nearest preceding label, as a JSON array of `MemoryAnnotation`.
+    ///
+    /// The range is clamped to the end of the address space.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when result serialization fails.
+    pub fn annotate_memory(&self, addr: u16, len: usize) -> Result<JsValue, JsValue> {
+        let annotations = self.annotate_memory_internal(addr, len);
+        serde_wasm_bindgen::to_value(&annotations)
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// Searches memory for a byte pattern such as `12 ?? 34`, where `??`
+    /// matches any byte, and returns every match as a `MemoryAnnotation`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when the pattern does not parse, or when
+    /// result serialization fails.
+    pub fn find_bytes(&self, pattern: &str) -> Result<JsValue, JsValue> {
+        let pattern =
+            BytePattern::parse(pattern).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        serde_wasm_bindgen::to_value(&self.find_internal(&pattern))
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// Searches memory for a 16-bit value stored big-endian at any address,
+    /// returning every match as a `MemoryAnnotation`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when result serialization fails.
+    pub fn find_word(&self, value: u16) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.find_internal(&BytePattern::word(value)))
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
     /// Returns the full core state as a JSON object.
     ///
     /// # Errors
@@ -334,6 +1513,149 @@ impl WasmCore {
             .is_some_and(|tele7| tele7.state().is_enabled())
     }
 
+    /// Sets the host parameter block and writes it to RAM at `0xDF00`.
+    ///
+    /// `bytes` is an encoded block (see `emulator_core::params`) of at most
+    /// 256 bytes; an empty array clears it. The block is written again on
+    /// every reset, so it survives `reset` and `reset_and_reload`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when the block is larger than 256 bytes.
+    pub fn set_params(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        write_params(&mut self.state, bytes).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        self.params = bytes.to_vec();
+        Ok(())
+    }
+
+    /// Selects a built-in cycle-cost table, `v1` or `fast-io`.
+    ///
+    /// Applies from the next instruction and is kept across resets.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value for an unknown model name.
+    pub fn set_timing(&mut self, name: &str) -> Result<(), JsValue> {
+        let timing = TimingModel::named(name)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown timing model '{name}'")))?;
+        self.apply_timing(timing);
+        Ok(())
+    }
+
+    /// Selects a custom cycle-cost table: one cost per cycle-cost kind, in
+    /// the core's cycle-cost table order.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when `costs` does not have one entry per
+    /// kind.
+    pub fn set_custom_timing(&mut self, costs: &[u16]) -> Result<(), JsValue> {
+        if costs.len() != CYCLE_COST_TABLE.len() {
+            return Err(JsValue::from_str(&format!(
+                "expected {} cycle costs, got {}",
+                CYCLE_COST_TABLE.len(),
+                costs.len()
+            )));
+        }
+        let table = CYCLE_COST_TABLE
+            .iter()
+            .zip(costs)
+            .fold(CycleCostTable::V1, |table, ((kind, _), cycles)| {
+                table.with_cost(*kind, *cycles)
+            });
+        self.apply_timing(TimingModel::Custom(table));
+        Ok(())
+    }
+
+    /// Returns the name of the active timing model (`v1`, `fast-io` or
+    /// `custom`).
+    #[must_use]
+    pub fn timing(&self) -> String {
+        self.state.timing.name().to_string()
+    }
+
+    /// Returns the text the program wrote to the debug console since the
+    /// last call, leaving the console empty. Invalid UTF-8 is replaced.
+    pub fn drain_console(&mut self) -> String {
+        self.mmio.console_mut().map_or_else(String::new, |console| {
+            String::from_utf8_lossy(&console.drain()).into_owned()
+        })
+    }
+
+    /// Replaces the paste buffer's text, as when the user pastes into the
+    /// running program. Returns how many bytes were kept.
+    ///
+    /// Call between runs so the paste lands at a tick boundary. The text
+    /// survives [`reset`](Self::reset), which only rewinds the read cursor.
+    pub fn paste_text(&mut self, text: &str) -> usize {
+        self.mmio
+            .paste_mut()
+            .map_or(0, |paste| paste.paste_text(text))
+    }
+
+    /// Returns the paste buffer's state as JSON, to be stored with a saved
+    /// run and handed back to [`restore_paste_state`](Self::restore_paste_state).
+    #[must_use]
+    pub fn paste_state(&self) -> String {
+        self.mmio
+            .paste()
+            .and_then(|paste| serde_json::to_string(paste).ok())
+            .unwrap_or_default()
+    }
+
+    /// Restores paste buffer state saved by [`paste_state`](Self::paste_state).
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when the JSON is not a saved paste buffer.
+    pub fn restore_paste_state(&mut self, json: &str) -> Result<(), JsValue> {
+        let saved: PasteBuffer = serde_json::from_str(json)
+            .map_err(|err| JsValue::from_str(&format!("invalid paste state: {err}")))?;
+        if let Some(paste) = self.mmio.paste_mut() {
+            *paste = saved;
+        }
+        Ok(())
+    }
+
+    /// Returns the exit status the program wrote to the debug console, or
+    /// `undefined` if it has not exited since the last reset.
+    #[must_use]
+    pub fn exit_status(&self) -> Option<u8> {
+        self.mmio.console().and_then(DebugConsole::exit_status)
+    }
+
+    /// Returns the register layout of every attached MMIO device, in
+    /// address order, for decoded register views.
+    ///
+    /// Returns a JSON array of devices, each with `device`, `description`,
+    /// `base`, `end` and `registers`; each register has `name`, `offset`
+    /// from `base`, `access` (`ReadOnly`, `WriteOnly` or `ReadWrite`),
+    /// `description` and `fields` (`name`, `lsb`, `width`). The MPU is
+    /// listed only while its capability bit is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when result serialization fails.
+    pub fn describe_registers(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.device_registers())
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
+    /// Decodes the current value of the MMIO register at `addr`.
+    ///
+    /// Returns an `MmioDecode` with the owning device, register name, raw
+    /// value, bit field values and a one-line `summary`, or `null` when no
+    /// attached device names a register at `addr`. Reading the value has
+    /// no side effects, so hovering `PASTE_DATA` does not consume a byte.
+    ///
+    /// # Errors
+    ///
+    /// Returns a JS error value when result serialization fails.
+    pub fn decode_mmio(&self, addr: u16) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(&self.decode_mmio_internal(addr))
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
     /// Disassembles a window of instructions around the given program counter.
     ///
     /// Returns a JSON array of disassembly rows. Each row contains:
@@ -413,40 +1735,403 @@ impl Default for WasmCore {
 }
 
 impl WasmCore {
-    const fn resume_from_halted(&mut self) {
+    /// Ends a tick the core halted for, so every way of leaving
+    /// `HaltedForTick` advances peripherals exactly once.
+    fn resume_from_halted(&mut self) {
         if matches!(self.state.run_state, RunState::HaltedForTick) {
-            self.state.arch.set_tick(0);
-            self.state.run_state = RunState::Running;
+            end_tick(&mut self.state, &mut self.mmio, None);
+            self.ticks_ended(1);
+        }
+    }
+
+    /// Counts ticks for autosave, capturing a checkpoint when one is due.
+    fn ticks_ended(&mut self, ended: u32) {
+        if let Some(autosave) = self.autosave.as_mut() {
+            autosave.ticks_ended(ended, &mut self.state, &self.mmio);
+        }
+    }
+
+    fn restart_autosave(&mut self) {
+        if let Some(autosave) = self.autosave.as_mut() {
+            autosave.restart(&mut self.state, &self.mmio);
+        }
+    }
+
+    /// Runs `n_ticks` through `run`, split so every autosave checkpoint
+    /// lands on its tick, and reports the pieces as one batch.
+    #[allow(clippy::cast_possible_truncation)]
+    fn run_batch(&mut self, n_ticks: u32, run: BatchRunner) -> TickBatch {
+        self.begin_call();
+        let mut remaining = self.config.run_limits.ticks(n_ticks);
+        let mut batch = TickBatch::default();
+        loop {
+            let chunk = self.autosave.as_ref().map_or(remaining, |autosave| {
+                remaining.min(autosave.ticks_until_due())
+            });
+            let part = run(&mut self.state, &mut self.mmio, &self.config, chunk);
+            remaining -= chunk;
+            self.ticks_ended(part.ticks.len() as u32);
+            batch.append(part);
+            if remaining == 0 || batch.fault.is_some() {
+                return batch;
+            }
+        }
+    }
+
+    fn list_checkpoints_internal(&self) -> Vec<CheckpointInfo> {
+        let Some(autosave) = &self.autosave else {
+            return Vec::new();
+        };
+        autosave
+            .summaries()
+            .into_iter()
+            .enumerate()
+            .map(|(index, summary)| CheckpointInfo {
+                index,
+                tick: summary.tick,
+                ticks_ago: autosave.ticks() - summary.tick,
+                pc: summary.pc,
+                delta_bytes: summary.delta_bytes,
+            })
+            .collect()
+    }
+
+    fn device_registers(&self) -> Vec<&'static DeviceRegisters> {
+        let mut devices = self.mmio.describe_registers();
+        if self.state.capability_enabled(CAP_MPU_BIT) {
+            devices.extend(Mpu::new().describe_registers());
+            devices.sort_by_key(|device| device.base);
+        }
+        devices
+    }
+
+    fn decode_mmio_internal(&self, addr: u16) -> Option<MmioDecode> {
+        let device = self
+            .device_registers()
+            .into_iter()
+            .find(|device| (device.base..=device.end).contains(&addr))?;
+        let value = if device.base == MPU_BASE {
+            self.state.mpu.register(addr)
+        } else {
+            self.mmio.peek16(addr)?
+        };
+        let decoded = device.decode(addr, value)?;
+        Some(MmioDecode {
+            device: decoded.device.to_string(),
+            register: decoded.register.to_string(),
+            address: addr,
+            value,
+            fields: decoded
+                .fields
+                .iter()
+                .map(|field| MmioField {
+                    name: field.name.to_string(),
+                    value: field.value,
+                })
+                .collect(),
+            summary: decoded.to_string(),
+        })
+    }
+
+    /// Starts a step, tick or run call: clears the write log and applies
+    /// input the subscribed fan-out has queued since the last call.
+    fn begin_call(&mut self) {
+        self.state.write_log.clear();
+        let inputs = self
+            .inbox
+            .as_ref()
+            .map(|inbox| std::mem::take(&mut *inbox.borrow_mut()))
+            .unwrap_or_default();
+        for input in inputs {
+            match input {
+                FanoutInput::Event(event_id) => {
+                    let _ = self.state.enqueue_event(event_id);
+                }
+                FanoutInput::Paste(text) => {
+                    self.paste_text(&text);
+                }
+            }
         }
     }
 
     fn step_internal(&mut self) -> WasmStepOutcome {
+        self.begin_call();
+        self.step_once()
+    }
+
+    fn step_once(&mut self) -> WasmStepOutcome {
         self.resume_from_halted();
         step_one(&mut self.state, &mut self.mmio, &self.config).into()
     }
 
-    fn tick_internal(&mut self) -> WasmRunOutcome {
+    fn memory_writes_internal(&self) -> MemoryWrites {
+        MemoryWrites {
+            writes: self.state.write_log.writes().to_vec(),
+            dropped: self.state.write_log.dropped(),
+        }
+    }
+
+    /// Converts `outcome`, attaching the writes logged during the call.
+    fn run_outcome(&self, outcome: RunOutcome) -> WasmRunOutcome {
+        WasmRunOutcome {
+            memory_writes: self.state.write_log.writes().to_vec(),
+            memory_writes_dropped: self.state.write_log.dropped(),
+            ..outcome.into()
+        }
+    }
+
+    fn breakpoint_infos(&self) -> Vec<BreakpointInfo> {
+        self.state
+            .breakpoints
+            .iter()
+            .map(|breakpoint| BreakpointInfo {
+                address: breakpoint.address,
+                conditional: breakpoint.condition.is_some(),
+                ignore_count: breakpoint.ignore_count,
+                temporary: breakpoint.temporary,
+                hit_count: breakpoint.hit_count,
+            })
+            .collect()
+    }
+
+    fn step_over_internal(&mut self, max_steps: u32) -> WasmSteppingOutcome {
+        self.begin_call();
+        self.resume_from_halted();
+        step_over(&mut self.state, &mut self.mmio, &self.config, max_steps).into()
+    }
+
+    fn step_out_internal(&mut self, max_steps: u32) -> WasmSteppingOutcome {
+        self.begin_call();
+        self.resume_from_halted();
+        step_out(&mut self.state, &mut self.mmio, &self.config, max_steps).into()
+    }
+
+    fn annotate_memory_internal(&self, addr: u16, len: usize) -> Vec<MemoryAnnotation> {
+        let end = (usize::from(addr) + len).min(self.state.memory.len());
+        (usize::from(addr)..end)
+            .filter_map(|address| u16::try_from(address).ok())
+            .map(|address| self.annotate_address(address))
+            .collect()
+    }
+
+    fn annotate_address(&self, address: u16) -> MemoryAnnotation {
+        let nearest = self
+            .program
+            .layout
+            .as_ref()
+            .and_then(|layout| layout.nearest_symbol(address));
+        MemoryAnnotation {
+            address,
+            region: region_name(decode_memory_region(address)).to_string(),
+            symbol: nearest.map(|(name, _)| name.to_string()),
+            offset: nearest.map(|(_, offset)| offset),
+        }
+    }
+
+    fn find_internal(&self, pattern: &BytePattern) -> Vec<MemoryAnnotation> {
+        pattern
+            .find(&self.state.memory)
+            .into_iter()
+            .map(|address| self.annotate_address(address))
+            .collect()
+    }
+
+    fn encode_single_line_internal(&self, line: &str, pc: u16) -> Result<LinePreview, String> {
+        let symbols: SymbolTable = self
+            .program
+            .layout
+            .as_ref()
+            .map(|layout| {
+                layout
+                    .symbols
+                    .iter()
+                    .map(|(name, &address)| {
+                        let symbol = Symbol {
+                            address,
+                            defined_at: 0,
+                        };
+                        (name.clone(), symbol)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let encoding = encode_single_line(line, pc, &symbols).map_err(|err| err.to_string())?;
+        Ok(LinePreview {
+            bytes: encoding.bytes,
+            size_words: encoding.size_words,
+            min_cycles: encoding.min_cycles,
+            max_cycles: encoding.max_cycles,
+            addressing_mode: encoding
+                .addressing_mode
+                .map(|mode| addressing_mode_name(mode).to_string()),
+        })
+    }
+
+    const fn apply_timing(&mut self, timing: TimingModel) {
+        self.config.timing = timing;
+        self.state.timing = timing;
+    }
+
+    fn reset_state(&mut self) {
+        let breakpoints = std::mem::take(&mut self.state.breakpoints);
+        self.state = CoreState::with_config(&self.config);
+        self.state.breakpoints = breakpoints;
+        // Validated when set, so this cannot fail.
+        let _ = write_params(&mut self.state, &self.params);
+        self.mmio.reset();
+    }
+
+    fn run_to_breakpoint_internal(&mut self, max_steps: u32) -> BreakpointRunResult {
+        let mut result = BreakpointRunResult {
+            steps: 0,
+            final_step: None,
+            breakpoint: None,
+        };
+        let max_steps = self.config.run_limits.steps(max_steps);
+        self.begin_call();
+        while result.steps < max_steps {
+            let outcome = self.step_once();
+            result.steps += 1;
+            result.final_step = Some(outcome);
+            if matches!(
+                outcome,
+                WasmStepOutcome::HaltedForTick { .. } | WasmStepOutcome::Fault { .. }
+            ) {
+                break;
+            }
+            if let Some(hit) = self
+                .state
+                .breakpoints
+                .check(&self.state.arch, &self.state.memory)
+            {
+                result.breakpoint = Some(hit.into());
+                break;
+            }
+        }
+        result
+    }
+
+    fn tick_internal(&mut self) -> WasmRunOutcome {
+        self.begin_call();
+        self.resume_from_halted();
+        let mut steps = 0;
+        loop {
+            let outcome = run_one(
+                &mut self.state,
+                &mut self.mmio,
+                &self.config,
+                RunBoundary::TickBoundary,
+            );
+            steps += outcome.steps;
+            if matches!(outcome.final_step, StepOutcome::HaltedForTick { .. }) {
+                end_tick(&mut self.state, &mut self.mmio, None);
+                self.ticks_ended(1);
+            } else if !matches!(self.state.run_state, RunState::FaultLatched(_)) {
+                continue;
+            }
+            return self.run_outcome(RunOutcome { steps, ..outcome });
+        }
+    }
+
+    fn run_ticks_internal(&mut self, n_ticks: u32) -> WasmTickBatch {
+        self.run_batch(n_ticks, run_ticks_with_budget).into()
+    }
+
+    fn fast_forward_internal(&mut self, n_ticks: u32) -> WasmTickBatch {
+        self.run_batch(n_ticks, run_fast_forward).into()
+    }
+
+    /// Steps like `run_one` with the same stop rules, giving up after
+    /// `max_steps` instructions when given.
+    ///
+    /// A run that stops at [`RunBoundary::TickBoundary`] ends the tick like
+    /// `tick_internal` does; other boundaries leave the core halted for
+    /// inspection until the next call resumes it.
+    fn run_internal(
+        &mut self,
+        boundary: RunBoundary,
+        max_steps: Option<u32>,
+    ) -> WasmRunUntilOutcome {
+        self.begin_call();
         self.resume_from_halted();
-        let outcome = run_one(
-            &mut self.state,
-            &mut self.mmio,
-            &self.config,
-            RunBoundary::TickBoundary,
-        );
-        self.state.arch.set_tick(0);
-        self.mmio.tick();
-        if matches!(self.state.run_state, RunState::HaltedForTick) {
-            self.state.run_state = RunState::Running;
+        let max_steps = self.config.run_limits.steps(max_steps.unwrap_or(u32::MAX));
+        let mut steps = 0;
+        let mut final_step = None;
+        while steps < max_steps {
+            let outcome = step_one(&mut self.state, &mut self.mmio, &self.config);
+            steps += 1;
+            final_step = Some(outcome);
+
+            if let Some(done) = check_run_boundary(&mut self.state, boundary, outcome, steps) {
+                if boundary == RunBoundary::TickBoundary
+                    && matches!(outcome, StepOutcome::HaltedForTick { .. })
+                {
+                    end_tick(&mut self.state, &mut self.mmio, None);
+                    self.ticks_ended(1);
+                }
+                return WasmRunUntilOutcome::Boundary(self.run_outcome(done));
+            }
+        }
+
+        WasmRunUntilOutcome::StoppedAtStepLimit {
+            steps,
+            final_step: final_step.map(Into::into),
+            pc: self.state.arch.pc(),
+            tick: self.state.arch.tick(),
         }
-        outcome.into()
     }
 
-    fn run_internal(&mut self, boundary: RunBoundary) -> WasmRunOutcome {
-        run_one(&mut self.state, &mut self.mmio, &self.config, boundary).into()
+    #[allow(clippy::cast_possible_truncation)]
+    fn hot_swap_internal(
+        &mut self,
+        result: AssembleResult,
+        source: &str,
+        file_name: &str,
+    ) -> HotSwapResult {
+        let build_id = format!("{:016x}", compute_build_id(&result.binary));
+        let binary = result.binary.clone();
+        // The edited program replaces the old one whole, source map and
+        // files included; other cores sharing the old one keep it.
+        let program = ProgramAssets::assembled(result, source, file_name);
+
+        if self.program.layout != program.layout {
+            self.config.reset_pc = program.layout.as_ref().map_or(0, |layout| layout.entry);
+            self.reset_state();
+            self.program = Rc::new(program);
+            self.load_program_with_tracking(&binary);
+            let end = binary.len().min(self.state.memory.len());
+            let patched_regions = if end == 0 {
+                Vec::new()
+            } else {
+                vec![[0, (end - 1) as u16]]
+            };
+            return HotSwapResult {
+                build_id,
+                patched_regions,
+                state_preserved: false,
+            };
+        }
+
+        // Diff the new image against the old one, not against live memory,
+        // so RAM the program has written is left alone.
+        let patched_regions = compute_changed_regions(&program.binary, &self.program.binary);
+        for [start, end] in &patched_regions {
+            let range = usize::from(*start)..=usize::from(*end);
+            self.state.memory[range.clone()].copy_from_slice(&program.binary[range]);
+        }
+        self.program = Rc::new(program);
+        // Older checkpoints hold the old code, so rewinding would undo the swap.
+        self.restart_autosave();
+
+        HotSwapResult {
+            build_id,
+            patched_regions,
+            state_preserved: true,
+        }
     }
 
     fn get_metadata_internal(&self) -> ExecutionMetadata {
-        let changed_regions = compute_changed_regions(&self.state.memory, &self.original_binary);
+        let changed_regions = compute_changed_regions(&self.state.memory, &self.program.binary);
 
         let (has_fault, fault_code) = match self.state.run_state {
             RunState::FaultLatched(code) => (true, Some(code.as_u8())),
@@ -467,11 +2152,42 @@ impl WasmCore {
             changed_regions,
             has_fault,
             fault_code,
+            pc_history: self.state.pc_history.pcs(),
+            tick_usage: self.state.tick_usage.cycles(),
+            tick_budget: self.config.tick_budget_cycles,
+            state_digest: self.state_digest(),
         }
     }
 }
 
+const fn region_name(region: MemoryRegion) -> &'static str {
+    match region {
+        MemoryRegion::Rom => "ROM",
+        MemoryRegion::Ram => "RAM",
+        MemoryRegion::Mmio => "MMIO",
+        MemoryRegion::Diag => "DIAG",
+        MemoryRegion::Reserved => "RESERVED",
+    }
+}
+
+const fn addressing_mode_name(mode: AddressingMode) -> &'static str {
+    match mode {
+        AddressingMode::DirectRegister => "register",
+        AddressingMode::IndirectRegister => "indirect",
+        AddressingMode::IndirectAutoIncrement => "indirect-increment",
+        AddressingMode::SignExtendedDisplacement => "displacement",
+        AddressingMode::ZeroExtendedDisplacement => "absolute",
+        AddressingMode::Immediate => "immediate",
+        AddressingMode::Reserved110 | AddressingMode::Reserved111 => "reserved",
+    }
+}
+
 fn convert_assemble_result(result: AssembleResult, _file_name: &str) -> AssembleOnlyResult {
+    let budgets = block_budgets(&result)
+        .into_iter()
+        .map(BudgetAnnotation::from)
+        .collect();
+    let info = ProgramInfo::from(&result);
     let source_map: Vec<SourceMapEntry> = result
         .listing
         .into_iter()
@@ -506,7 +2222,30 @@ fn convert_assemble_result(result: AssembleResult, _file_name: &str) -> Assemble
         source_map,
         diagnostics,
         build_id,
+        entry: result.entry,
+        info,
+        budgets,
+    }
+}
+
+/// Parses and version-checks a bundle written by `nullbyte-asm bundle`.
+fn parse_bundle(json: &str) -> Result<ProgramBundle, String> {
+    let bundle: ProgramBundle =
+        serde_json::from_str(json).map_err(|err| format!("invalid bundle: {err}"))?;
+    if bundle.format != BUNDLE_FORMAT || bundle.version != BUNDLE_VERSION {
+        return Err(format!(
+            "unsupported bundle: format '{}' version {} (expected '{BUNDLE_FORMAT}' version {BUNDLE_VERSION})",
+            bundle.format, bundle.version
+        ));
     }
+    Ok(bundle)
+}
+
+/// Returns `binary` as a full memory image, zero-filled past its end.
+fn padded_image(binary: &[u8]) -> Vec<u8> {
+    let mut image = binary[..binary.len().min(ADDRESS_SPACE_BYTES)].to_vec();
+    image.resize(ADDRESS_SPACE_BYTES, 0);
+    image
 }
 
 fn compute_build_id(binary: &[u8]) -> u64 {
@@ -570,9 +2309,15 @@ fn coalesce_adjacent_regions(regions: Vec<[u16; 2]>) -> Vec<[u16; 2]> {
 #[cfg(test)]
 mod tests {
     use super::{
-        assemble_from_source, compute_changed_regions, convert_assemble_result, WasmCore,
-        WasmRunBoundary, WasmStepOutcome,
+        assemble_from_source, compute_changed_regions, convert_assemble_result, editor_metadata,
+        InputFanout, SharedProgram, WasmCore, WasmHaltReason, WasmRunBoundary, WasmRunUntilOutcome,
+        WasmStepOutcome, WasmStepStop,
+    };
+    use emulator_core::{
+        run_ticks_with_budget, BytePattern, CoreConfig, CoreState, GeneralRegister, MemoryWrite,
+        MmioBus, PasteBuffer, RunLimits, RunState, CYCLE_COST_TABLE, TELE7_BASE, TELE7_END,
     };
+    use std::rc::Rc;
 
     #[test]
     fn step_executes_loaded_nop_and_advances_pc_tick() {
@@ -592,11 +2337,189 @@ mod tests {
         // 0xF000 encodes a reserved primary opcode and must fault immediately.
         core.load_program(&[0xF0, 0x00]);
 
-        let outcome = core.run_internal(WasmRunBoundary::Fault.into());
+        let WasmRunUntilOutcome::Boundary(outcome) =
+            core.run_internal(WasmRunBoundary::Fault.into(), Some(16))
+        else {
+            panic!("fault should end the run");
+        };
         assert_eq!(outcome.steps, 1);
         assert!(matches!(outcome.final_step, WasmStepOutcome::Fault { .. }));
     }
 
+    #[test]
+    fn run_until_breakpoint_boundary_reports_hit() {
+        let mut core = WasmCore::new();
+        core.load_program(&[]);
+        core.set_breakpoint(0x0004, "", 0, false).unwrap();
+
+        let WasmRunUntilOutcome::Boundary(outcome) =
+            core.run_internal(WasmRunBoundary::Breakpoint.into(), Some(100))
+        else {
+            panic!("breakpoint should end the run");
+        };
+        assert_eq!(outcome.steps, 2);
+        assert_eq!(outcome.breakpoint.map(|hit| hit.address), Some(0x0004));
+
+        let WasmRunUntilOutcome::Boundary(outcome) =
+            core.run_internal(WasmRunBoundary::StepCount(3).into(), Some(100))
+        else {
+            panic!("step count should end the run");
+        };
+        assert_eq!(outcome.steps, 3);
+        assert_eq!(core.state.arch.pc(), 0x000A);
+    }
+
+    #[test]
+    fn run_until_stops_at_step_limit() {
+        let mut core = WasmCore::new();
+        // Empty ROM is all NOPs, so nothing reaches a HALT.
+        core.load_program(&[]);
+
+        let outcome = core.run_internal(WasmRunBoundary::Halted.into(), Some(10));
+
+        assert_eq!(
+            outcome,
+            WasmRunUntilOutcome::StoppedAtStepLimit {
+                steps: 10,
+                final_step: Some(WasmStepOutcome::Retired { cycles: 1 }),
+                pc: 20,
+                tick: 10,
+            }
+        );
+    }
+
+    #[test]
+    fn run_until_without_step_limit_runs_to_the_boundary() {
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program("NOP\nNOP\nNOP\nHALT\n", "nops.n1")
+            .unwrap();
+
+        let WasmRunUntilOutcome::Boundary(outcome) =
+            core.run_internal(WasmRunBoundary::Halted.into(), None)
+        else {
+            panic!("HALT should end the run");
+        };
+        assert_eq!(outcome.steps, 4);
+    }
+
+    #[test]
+    fn run_ticks_reports_each_tick() {
+        let mut core = WasmCore::new();
+        core.load_program(&[]);
+
+        let batch = core.run_ticks_internal(2);
+
+        assert_eq!(batch.ticks.len(), 2);
+        assert_eq!(batch.ticks[0].steps, 640);
+        assert_eq!(batch.ticks[0].reason, WasmHaltReason::BudgetExhausted);
+        assert_eq!(batch.total_cycles, 1280);
+        assert_eq!(batch.simulated_ms, 20);
+        assert_eq!(batch.fault, None);
+    }
+
+    #[test]
+    fn sandboxed_core_caps_every_run_call() {
+        let mut core = WasmCore::new_sandboxed();
+        core.load_program(&[]);
+
+        let batch = core.run_ticks_internal(u32::MAX);
+        assert_eq!(batch.ticks.len(), 100);
+        assert_eq!(batch.ticks[0].steps, 320);
+        assert_eq!(batch.simulated_ms, 1000);
+        assert_eq!(core.fast_forward_internal(1_000).ticks.len(), 100);
+
+        assert_eq!(core.run_to_breakpoint_internal(u32::MAX).steps, 320);
+        assert_eq!(core.config.run_limits, RunLimits::SANDBOX);
+        assert_eq!(core.get_metadata_internal().tick_budget, 320);
+    }
+
+    #[test]
+    fn fast_forward_matches_run_ticks() {
+        let program = [
+            0x10, 0x05, 0xE1, 0x22, // MOV R0, #0xE122
+            0x12, 0x05, 0x00, 0x01, // MOV R1, #0x0001
+            0x32, 0x01, // STORE R1, [R0]
+            0x00, 0x10, // HALT
+            0x60, 0x35, 0xFF, 0xFA, // JMP #-6
+        ];
+        let mut expected = WasmCore::new();
+        expected.load_program(&program);
+        let expected_batch = expected.run_ticks_internal(120);
+
+        let mut core = WasmCore::new();
+        core.load_program(&program);
+        let batch = core.fast_forward_internal(120);
+
+        assert_eq!(batch, expected_batch);
+        assert_eq!(core.state, expected.state);
+        assert_eq!(
+            core.mmio.tele7().map(|t7| t7.state().blink_phase()),
+            expected.mmio.tele7().map(|t7| t7.state().blink_phase())
+        );
+    }
+
+    #[test]
+    fn autosave_checkpoints_rewind_to_earlier_ticks() {
+        let source = "\
+main:
+    MOV R0, #0x4000
+loop:
+    LOAD R1, [R0]
+    ADD R1, R1, #1
+    STORE R1, [R0]
+    HALT
+    JMP #loop
+";
+        let mut expected = WasmCore::new();
+        expected
+            .assemble_and_load_program(source, "count.n1")
+            .unwrap();
+        let expected_batch = expected.run_ticks_internal(100);
+
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program(source, "count.n1").unwrap();
+        core.enable_autosave(100, 2);
+        let batch = core.run_ticks_internal(250);
+        assert_eq!(batch.ticks.len(), 250);
+
+        let checkpoints = core.list_checkpoints_internal();
+        let ticks: Vec<(u64, u64)> = checkpoints.iter().map(|c| (c.tick, c.ticks_ago)).collect();
+        assert_eq!(ticks, [(100, 150), (200, 50)]);
+        assert_eq!(checkpoints[1].delta_bytes, 1);
+
+        core.set_breakpoint(0x0004, "", 0, false).unwrap();
+        assert!(core.restore_checkpoint(0));
+        assert_eq!(core.state.memory[0x4000..0x4002], [0x00, 0x64]);
+        assert_eq!(core.state.arch, expected.state.arch);
+        assert_eq!(core.breakpoint_infos().len(), 1);
+        assert_eq!(core.list_checkpoints_internal().len(), 1);
+        assert!(!core.restore_checkpoint(1));
+
+        core.run_ticks_internal(100);
+        assert_eq!(batch.ticks[..100], expected_batch.ticks[..]);
+        assert_eq!(core.list_checkpoints_internal()[1].tick, 200);
+
+        core.reset_and_reload();
+        assert_eq!(core.list_checkpoints_internal()[0].tick, 0);
+        core.disable_autosave();
+        assert!(core.list_checkpoints_internal().is_empty());
+    }
+
+    #[test]
+    fn run_until_tick_boundary_ends_the_tick() {
+        let mut core = WasmCore::new();
+        core.load_program(&[]);
+
+        for _ in 0..50 {
+            let outcome = core.run_internal(WasmRunBoundary::TickBoundary.into(), Some(1000));
+            assert!(matches!(outcome, WasmRunUntilOutcome::Boundary(_)));
+            assert_eq!(core.state.arch.tick(), 0);
+            assert_eq!(core.state.run_state, RunState::Running);
+        }
+
+        assert!(core.mmio.tele7().unwrap().state().blink_phase());
+    }
+
     #[test]
     fn tele7_self_test_source_enables_display_via_wasm_api() {
         let mut core = WasmCore::new();
@@ -631,6 +2554,113 @@ mod tests {
         );
     }
 
+    #[test]
+    fn drain_console_returns_output_once() {
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program(
+            "MOV R0, #0xE132\nMOV R1, #0x4F\nSTORE R1, [R0]\nMOV R1, #0x4B\nSTORE R1, [R0]\nHALT\n",
+            "console.n1",
+        )
+        .expect("source assembly should succeed");
+
+        for _ in 0..5 {
+            let _ = core.step_internal();
+        }
+
+        assert_eq!(core.drain_console(), "OK");
+        assert_eq!(core.drain_console(), "");
+        assert_eq!(core.exit_status(), None);
+    }
+
+    #[test]
+    fn timing_model_is_kept_across_resets() {
+        let mut core = WasmCore::new();
+        core.set_timing("fast-io").unwrap();
+        core.reset();
+        assert_eq!(core.timing(), "fast-io");
+
+        let mut costs: Vec<u16> = CYCLE_COST_TABLE.iter().map(|(_, cycles)| *cycles).collect();
+        costs[0] = 3;
+        core.set_custom_timing(&costs).unwrap();
+        assert_eq!(core.timing(), "custom");
+        assert_eq!(core.state.timing.cost(CYCLE_COST_TABLE[0].0), 3);
+    }
+
+    #[test]
+    fn set_params_survives_reset() {
+        let mut core = WasmCore::new();
+        core.set_params(&[0x50, 0x42, 0x00, 0x00]).unwrap();
+        assert_eq!(
+            &core.state.memory[0xDF00..0xDF04],
+            &[0x50, 0x42, 0x00, 0x00]
+        );
+
+        core.reset();
+        assert_eq!(&core.state.memory[0xDF00..0xDF02], &[0x50, 0x42]);
+    }
+
+    #[test]
+    fn pasted_text_is_read_through_mmio_and_survives_reset() {
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program(
+            "MOV R0, #0xE164\nLOAD R1, [R0]\nLOAD R2, [R0]\nHALT\n",
+            "paste.n1",
+        )
+        .expect("source assembly should succeed");
+        assert_eq!(core.paste_text("hi"), 2);
+        let saved = core.paste_state();
+
+        for _ in 0..3 {
+            let _ = core.step_internal();
+        }
+        assert_eq!(core.state.arch.gpr(GeneralRegister::R1), u16::from(b'h'));
+        assert_eq!(core.state.arch.gpr(GeneralRegister::R2), u16::from(b'i'));
+
+        core.reset();
+        assert_eq!(core.mmio.paste().map(PasteBuffer::bytes), Some(&b"hi"[..]));
+        assert_eq!(core.mmio.paste().map(PasteBuffer::position), Some(0));
+
+        core.paste_text("other");
+        core.restore_paste_state(&saved).unwrap();
+        assert_eq!(core.mmio.paste().map(PasteBuffer::bytes), Some(&b"hi"[..]));
+    }
+
+    #[test]
+    fn register_layouts_follow_the_attached_devices() {
+        let mut core = WasmCore::new();
+        let names = |core: &WasmCore| -> Vec<&str> {
+            core.device_registers()
+                .iter()
+                .map(|device| device.device)
+                .collect()
+        };
+        assert_eq!(names(&core), ["TELE7", "CONSOLE", "DMA", "PASTE"]);
+
+        let cap = core.state.arch.cap() | 1 << emulator_core::CAP_MPU_BIT;
+        core.state.arch.set_cap_core_owned(cap);
+        assert_eq!(names(&core), ["TELE7", "CONSOLE", "MPU", "DMA", "PASTE"]);
+    }
+
+    #[test]
+    fn decode_mmio_reads_registers_without_side_effects() {
+        let mut core = WasmCore::new();
+        core.mmio.write16(0xE122, 0b01).unwrap();
+        let ctrl = core.decode_mmio_internal(0xE122).unwrap();
+        assert_eq!(ctrl.summary, "TELE7_CTRL: ENABLE=1, LIVE_READ=0");
+        assert_eq!(ctrl.fields.len(), 2);
+
+        core.paste_text("hi");
+        let data = core
+            .decode_mmio_internal(emulator_core::PASTE_DATA)
+            .unwrap();
+        assert_eq!(data.value, u16::from(b'h'));
+        assert_eq!(core.mmio.paste().map(PasteBuffer::position), Some(0));
+
+        assert!(core.decode_mmio_internal(0xE128).is_none());
+        assert!(core.decode_mmio_internal(0x4000).is_none());
+        assert!(core.decode_mmio_internal(emulator_core::MPU_CTRL).is_none());
+    }
+
     #[test]
     fn patch_memory_writes_to_specified_address() {
         let mut core = WasmCore::new();
@@ -665,6 +2695,318 @@ mod tests {
         assert_eq!(metadata.tick, 0);
         assert!(!metadata.has_fault);
         assert!(metadata.changed_regions.is_empty());
+        assert!(metadata.pc_history.is_empty());
+        assert!(metadata.tick_usage.is_empty());
+        assert_eq!(metadata.tick_budget, 640);
+        assert_eq!(metadata.state_digest, core.state_digest());
+
+        core.step_internal();
+        assert_ne!(core.state_digest(), metadata.state_digest);
+    }
+
+    #[test]
+    fn ticks_match_native_batches_through_dispatches() {
+        let source = "JMP #main\n.org 0x0008\n.word 0x0020\nmain:\nADD R0, R0, #1\nSWI\n\
+                      HALT\nJMP #main\n.org 0x0020\nADD R1, R1, #1\nERET\n";
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program(source, "dispatch.n1")
+            .expect("program should assemble");
+        let config = CoreConfig::default();
+        let mut native = CoreState::with_config(&config);
+        native.memory = core.state.memory.clone();
+        let mut mmio = core.mmio.clone();
+
+        for tick in 0..4 {
+            if tick == 1 {
+                assert!(core.enqueue_event(7));
+                native.enqueue_event(7).unwrap();
+            }
+            let outcome = core.tick_internal();
+            let batch = run_ticks_with_budget(&mut native, &mut mmio, &config, 1);
+            assert_eq!(outcome.steps, batch.ticks[0].steps, "tick {tick}");
+            assert_eq!(
+                core.state.state_digest(),
+                native.state_digest(),
+                "tick {tick}"
+            );
+        }
+        assert_eq!(core.state.arch.gpr(GeneralRegister::R1), 4);
+    }
+
+    #[test]
+    fn memory_writes_cover_only_the_last_call() {
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program(
+            "MOV R0, #0x4000\nMOV R1, #0x1234\nSTORE R1, [R0]\nHALT\nNOP\nHALT\n",
+            "writes.n1",
+        )
+        .expect("program should assemble");
+
+        let outcome = core.tick_internal();
+        let store = MemoryWrite {
+            address: 0x4000,
+            value: 0x1234,
+        };
+        assert_eq!(outcome.memory_writes, vec![store]);
+        assert_eq!(core.memory_writes_internal().writes, vec![store]);
+
+        core.step_internal();
+        let writes = core.memory_writes_internal();
+        assert!(writes.writes.is_empty());
+        assert_eq!(writes.dropped, 0);
+    }
+
+    #[test]
+    fn execution_metadata_reports_tick_usage() {
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program("loop:\nNOP\nNOP\nHALT\nJMP #loop\n", "usage.n1")
+            .expect("program should assemble");
+
+        core.tick_internal();
+        core.tick_internal();
+
+        assert_eq!(core.get_metadata_internal().tick_usage, vec![3, 5]);
+    }
+
+    #[test]
+    fn run_to_breakpoint_stops_when_condition_holds() {
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program("loop:\nADD R0, R0, #1\nJMP #loop\n", "bp.n1")
+            .expect("program should assemble");
+        core.set_breakpoint(0x0000, "R0 == 3", 0, false)
+            .expect("condition should parse");
+
+        let result = core.run_to_breakpoint_internal(1_000);
+
+        assert_eq!(result.breakpoint.map(|hit| hit.address), Some(0x0000));
+        assert_eq!(core.state.arch.gpr(GeneralRegister::R0), 3);
+        assert_eq!(core.evaluate_watch("R0 + 1").unwrap(), 4);
+
+        core.reset();
+        assert!(core.state.breakpoints.get(0x0000).is_some());
+        assert!(core.remove_breakpoint(0x0000));
+    }
+
+    #[test]
+    fn run_to_breakpoint_honours_skip_count_and_one_shot() {
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program("loop:\nADD R0, R0, #1\nJMP #loop\n", "bp.n1")
+            .expect("program should assemble");
+        core.set_breakpoint(0x0004, "", 100, true)
+            .expect("breakpoint should be set");
+
+        let result = core.run_to_breakpoint_internal(10_000);
+
+        let hit = result.breakpoint.expect("breakpoint should fire");
+        assert_eq!(hit.hit_count, 101);
+        assert!(hit.removed);
+        assert_eq!(core.state.arch.gpr(GeneralRegister::R0), 101);
+        assert!(core.breakpoint_infos().is_empty());
+
+        let result = core.run_to_breakpoint_internal(50);
+        assert_eq!(result.breakpoint, None);
+        assert_eq!(result.steps, 50);
+    }
+
+    #[test]
+    fn step_over_and_step_out_follow_calls() {
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program("CALL #sub\nHALT\nsub:\nNOP\nNOP\nRET\n", "calls.n1")
+            .expect("program should assemble");
+
+        let over = core.step_over_internal(100);
+        assert_eq!(over.stop, WasmStepStop::Completed);
+        assert_eq!(over.steps, 4);
+        assert_eq!(core.state.arch.pc(), 0x0004);
+
+        core.reset_and_reload();
+        core.step_internal();
+        core.step_internal();
+        let out = core.step_out_internal(100);
+        assert_eq!(out.stop, WasmStepStop::Completed);
+        assert_eq!(out.steps, 2);
+        assert_eq!(core.state.arch.pc(), 0x0004);
+    }
+
+    #[test]
+    fn annotate_memory_labels_bytes_with_region_and_symbol() {
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program(
+            "start:\nNOP\nloop:\nJMP #loop\n.org 0x4000\nbuffer:\n.word 0\n",
+            "annotate.n1",
+        )
+        .expect("program should assemble");
+
+        let rows = core.annotate_memory_internal(0x0000, 4);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0].region, "ROM");
+        assert_eq!(rows[0].symbol.as_deref(), Some("start"));
+        assert_eq!(rows[1].offset, Some(1));
+        assert_eq!(rows[3].symbol.as_deref(), Some("loop"));
+        assert_eq!(rows[3].offset, Some(1));
+
+        let rows = core.annotate_memory_internal(0x4001, 1);
+        assert_eq!(rows[0].region, "RAM");
+        assert_eq!(rows[0].symbol.as_deref(), Some("buffer"));
+        assert_eq!(rows[0].offset, Some(1));
+
+        let rows = core.annotate_memory_internal(0xE000, 1);
+        assert_eq!(rows[0].region, "MMIO");
+        assert_eq!(rows[0].symbol, None);
+
+        assert_eq!(core.annotate_memory_internal(0xFFFE, 8).len(), 2);
+    }
+
+    #[test]
+    fn load_bundle_installs_binary_files_and_source_map() {
+        let json = r#"{
+  "format": "nullbyte-bundle",
+  "version": 1,
+  "name": "game.n1",
+  "build_id": "0123456789abcdef",
+  "entry": 2,
+  "source": "NOP\u000astart:\u000aHALT\u000a",
+  "binary": [0, 0, 1, 0],
+  "source_map": [
+    {"address": 0, "len_bytes": 2, "file": "game.n1", "line": 1, "source": "NOP"},
+    {"address": 2, "len_bytes": 2, "file": "game.n1", "line": 3, "source": "HALT"}
+  ],
+  "symbols": [
+    {"name": "start", "address": 2}
+  ]
+}"#;
+
+        let mut core = WasmCore::new();
+        let metadata = core.load_bundle_internal(json).expect("bundle should load");
+        assert_eq!(metadata.name, "game.n1");
+        assert_eq!(metadata.entry, 2);
+        assert_eq!(metadata.size_bytes, 4);
+        assert_eq!(metadata.files, ["game.n1"]);
+        assert_eq!(metadata.symbols.get("start"), Some(&2));
+        assert_eq!(metadata.source_map[1].line, 3);
+        assert_eq!(metadata.execution.pc, 2);
+
+        assert_eq!(core.config.reset_pc, 2);
+        assert_eq!(&core.state.memory[..4], &[0, 0, 1, 0]);
+        assert_eq!(
+            core.read_file("game.n1").as_deref(),
+            Some("NOP\nstart:\nHALT\n")
+        );
+        assert_eq!(core.read_file("other.n1"), None);
+        let layout = core
+            .program
+            .layout
+            .as_ref()
+            .expect("bundle sets the layout");
+        assert_eq!(layout.boundaries, [(0, 2), (2, 2)]);
+
+        let stale = json.replace("\"version\": 1", "\"version\": 99");
+        let err = core.load_bundle_internal(&stale).unwrap_err();
+        assert!(err.contains("version 99"), "{err}");
+        assert!(core
+            .load_bundle_internal("not json")
+            .unwrap_err()
+            .starts_with("invalid bundle"));
+        assert!(core.read_file("game.n1").is_some());
+        assert_eq!(core.config.reset_pc, 2);
+
+        core.assemble_and_load_program("NOP\nHALT\n", "edit.n1")
+            .expect("program should assemble");
+        assert_eq!(core.read_file("game.n1"), None);
+        assert_eq!(core.read_file("edit.n1").as_deref(), Some("NOP\nHALT\n"));
+        assert_eq!(core.program.source_map[1].file, "edit.n1");
+        assert_eq!(core.program.source_map[1].line, 2);
+    }
+
+    #[test]
+    fn program_info_follows_the_loaded_program() {
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program(".title \"Snake\"\n.version \"1.0\"\nHALT\n", "snake.n1")
+            .unwrap();
+        assert_eq!(core.program.info.title.as_deref(), Some("Snake"));
+        assert_eq!(core.program.info.author, None);
+        assert_eq!(core.program.info.version.as_deref(), Some("1.0"));
+
+        let source = ".title \"Snake\"\n.version \"1.1\"\nHALT\n";
+        let edited = assemble_from_source(source, "snake.n1").unwrap();
+        assert!(
+            core.hot_swap_internal(edited, source, "snake.n1")
+                .state_preserved
+        );
+        assert_eq!(core.program.info.version.as_deref(), Some("1.1"));
+
+        let json = r#"{"format": "nullbyte-bundle", "version": 1, "name": "g.n1",
+            "build_id": "0", "entry": null,
+            "info": {"title": "Game", "author": "Ada", "version": null},
+            "source": "HALT\u000a", "binary": [0, 16], "source_map": [], "symbols": []}"#;
+        let metadata = core.load_bundle_internal(json).unwrap();
+        assert_eq!(metadata.info.author.as_deref(), Some("Ada"));
+        assert_eq!(core.program.info.title.as_deref(), Some("Game"));
+        assert_eq!(core.program.info.version, None);
+    }
+
+    #[test]
+    fn find_reports_matches_with_region_and_symbol() {
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program("start:\nNOP\n.org 0x4000\nscore:\n.word 0\n", "find.n1")
+            .expect("program should assemble");
+        core.patch_memory(0x4000, &[0xBE, 0xEF])
+            .expect("RAM patch should succeed");
+
+        let matches = core.find_internal(&BytePattern::word(0xBEEF));
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].address, 0x4000);
+        assert_eq!(matches[0].region, "RAM");
+        assert_eq!(matches[0].symbol.as_deref(), Some("score"));
+
+        let pattern = BytePattern::parse("?? EF").expect("pattern should parse");
+        let addresses: Vec<u16> = core
+            .find_internal(&pattern)
+            .iter()
+            .map(|row| row.address)
+            .collect();
+        assert_eq!(addresses, [0x4000]);
+    }
+
+    #[test]
+    fn encode_single_line_resolves_loaded_symbols() {
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program("start:\nNOP\nHALT\n", "test.n1")
+            .expect("program should assemble");
+
+        let preview = core
+            .encode_single_line_internal("JMP #start", 0x0004)
+            .unwrap();
+        assert_eq!(preview.bytes, vec![0x60, 0x35, 0xFF, 0xF8]);
+        assert_eq!(preview.size_words, 2);
+        assert_eq!(preview.min_cycles, Some(2));
+        assert_eq!(preview.addressing_mode.as_deref(), Some("immediate"));
+
+        assert!(core.encode_single_line_internal("JMP #missing", 0).is_err());
+    }
+
+    #[test]
+    fn editor_metadata_lists_language_elements() {
+        let metadata = editor_metadata();
+
+        assert!(metadata.mnemonics.iter().any(|m| m.name == "MOV"));
+        assert!(metadata.directives.iter().any(|d| d.name == ".org"));
+        assert_eq!(metadata.registers.first().map(String::as_str), Some("R0"));
+    }
+
+    #[test]
+    fn get_execution_metadata_reports_pc_history() {
+        let mut core = WasmCore::new();
+        core.load_program_with_tracking(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x10]);
+
+        for _ in 0..2 {
+            core.step_internal();
+        }
+
+        assert_eq!(
+            core.get_metadata_internal().pc_history,
+            vec![0x0000, 0x0002]
+        );
     }
 
     #[test]
@@ -694,6 +3036,37 @@ mod tests {
         assert_eq!(core.state.arch.pc(), 0);
     }
 
+    #[test]
+    fn reset_and_reload_power_cycles_peripherals() {
+        let source = "MOV R1, #7\nSTORE R1, #0xE122\nMOV R1, #40\nSTORE R1, #0xE126\n\
+                      MOV R1, #0x21\nSTORE R1, #0xE132\nidle:\nHALT\nJMP #idle\n";
+        let program = assemble_from_source(source, "power.n1").unwrap().binary;
+        let mut core = WasmCore::new();
+        core.load_program_with_tracking(&program);
+        let tele7 = |core: &WasmCore| {
+            let registers: Vec<_> = (TELE7_BASE..=TELE7_END)
+                .map(|addr| core.mmio.peek16(addr))
+                .collect();
+            (
+                registers,
+                format!("{:?}", core.mmio.tele7().unwrap().state()),
+            )
+        };
+        let power_on = (core.state.state_digest(), tele7(&core));
+
+        core.run_ticks_internal(20);
+        let first_run = (core.state.state_digest(), tele7(&core));
+        assert!(core.mmio.tele7().unwrap().state().is_enabled());
+        assert_eq!(core.mmio.console().unwrap().output(), b"!");
+
+        core.reset_and_reload();
+        assert_eq!((core.state.state_digest(), tele7(&core)), power_on);
+        assert!(core.mmio.console().unwrap().output().is_empty());
+
+        core.run_ticks_internal(20);
+        assert_eq!((core.state.state_digest(), tele7(&core)), first_run);
+    }
+
     #[test]
     fn assemble_and_load_with_metadata_loads_binary() {
         let mut core = WasmCore::new();
@@ -703,10 +3076,10 @@ mod tests {
         let res = result.unwrap();
         core.load_program_with_tracking(&res.binary);
 
-        assert!(!core.original_binary.is_empty());
-        assert_eq!(core.original_binary[0], 0x00);
-        assert_eq!(core.original_binary[2], 0x00);
-        assert_eq!(core.original_binary[3], 0x10);
+        assert!(!core.program.binary.is_empty());
+        assert_eq!(core.program.binary[0], 0x00);
+        assert_eq!(core.program.binary[2], 0x00);
+        assert_eq!(core.program.binary[3], 0x10);
     }
 
     #[test]
@@ -719,6 +3092,204 @@ mod tests {
         assert!(!converted.build_id.is_empty());
     }
 
+    #[test]
+    fn convert_assemble_result_annotates_loop_budgets() {
+        let source = "MOV R0, #3\nloop:\nSUB R0, R0, #1\nBNE #loop\nHALT\n";
+        let result = assemble_from_source(source, "budget.n1").unwrap();
+        let converted = convert_assemble_result(result, "budget.n1");
+
+        let starts: Vec<u16> = converted.budgets.iter().map(|b| b.start).collect();
+        assert_eq!(starts, [0x0000, 0x0004, 0x000C]);
+        let body = &converted.budgets[1];
+        assert_eq!(body.line, 3);
+        assert_eq!(body.loop_cycles, Some(3));
+        assert_eq!(body.annotation, "loop body: 3 cycles/iteration");
+    }
+
+    #[test]
+    fn assembled_entry_sets_pc_and_survives_reset() {
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program(
+            ".word 0\nmain:\n.entry main\nMOV R1, #1\nHALT\n",
+            "entry.n1",
+        )
+        .unwrap();
+        assert_eq!(core.state.arch.pc(), 2);
+
+        let _ = core.step_internal();
+        assert_eq!(core.state.arch.gpr(GeneralRegister::R1), 1);
+
+        core.reset_and_reload();
+        assert_eq!(core.state.arch.pc(), 2);
+        assert_eq!(core.state.arch.gpr(GeneralRegister::R1), 0);
+    }
+
+    #[test]
+    fn hot_swap_patches_changed_constant_and_keeps_state() {
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program("loop:\nMOV R1, #1\nJMP #loop\n", "hot.n1")
+            .unwrap();
+        let _ = core.step_internal();
+        assert_eq!(core.state.arch.gpr(GeneralRegister::R1), 1);
+        core.state.memory[0x4000] = 0xAB;
+
+        let source = "loop:\nMOV R1, #2\nJMP #loop\n";
+        let edited = assemble_from_source(source, "hot.n1").unwrap();
+        let swap = core.hot_swap_internal(edited, source, "hot.n1");
+
+        assert!(swap.state_preserved);
+        assert_eq!(swap.patched_regions, vec![[3, 3]]);
+        assert_eq!(core.state.arch.pc(), 4);
+        assert_eq!(core.state.arch.gpr(GeneralRegister::R1), 1);
+        assert_eq!(core.state.memory[0x4000], 0xAB);
+
+        let _ = core.step_internal();
+        let _ = core.step_internal();
+        assert_eq!(core.state.arch.gpr(GeneralRegister::R1), 2);
+    }
+
+    #[test]
+    fn hot_swap_reloads_when_layout_changes() {
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program("MOV R1, #1\nHALT\n", "hot.n1")
+            .unwrap();
+        let _ = core.step_internal();
+
+        let source = "NOP\nMOV R1, #1\nHALT\n";
+        let edited = assemble_from_source(source, "hot.n1").unwrap();
+        let swap = core.hot_swap_internal(edited, source, "hot.n1");
+
+        assert!(!swap.state_preserved);
+        assert_eq!(swap.patched_regions, vec![[0, 7]]);
+        assert_eq!(core.state.arch.pc(), 0);
+        assert_eq!(core.state.arch.gpr(GeneralRegister::R1), 0);
+        assert_eq!(&core.state.memory[..2], &[0x00, 0x00]);
+    }
+
+    #[test]
+    fn hot_swap_without_prior_assembly_reloads() {
+        let mut core = WasmCore::new();
+        let edited = assemble_from_source("HALT\n", "hot.n1").unwrap();
+
+        let swap = core.hot_swap_internal(edited, "HALT\n", "hot.n1");
+
+        assert!(!swap.state_preserved);
+        assert_eq!(swap.build_id.len(), 16);
+    }
+
+    #[test]
+    fn hot_swap_replaces_source_map_and_files() {
+        let mut core = WasmCore::new();
+        core.assemble_and_load_program("MOV R1, #1\nHALT\n", "hot.n1")
+            .unwrap();
+
+        let source = "MOV R1, #2 ; edited\nHALT\n";
+        let edited = assemble_from_source(source, "hot.n1").unwrap();
+        assert!(
+            core.hot_swap_internal(edited, source, "hot.n1")
+                .state_preserved
+        );
+
+        assert_eq!(core.read_file("hot.n1").as_deref(), Some(source));
+        assert_eq!(core.program.source_map[0].line, 1);
+        assert!(core.program.source_map[0].source.contains("#2"));
+
+        let source = "NOP\nMOV R1, #2\nHALT\n";
+        let edited = assemble_from_source(source, "moved.n1").unwrap();
+        assert!(
+            !core
+                .hot_swap_internal(edited, source, "moved.n1")
+                .state_preserved
+        );
+        assert_eq!(core.read_file("hot.n1"), None);
+        assert_eq!(core.read_file("moved.n1").as_deref(), Some(source));
+        assert_eq!(core.program.source_map[1].file, "moved.n1");
+        assert_eq!(core.program.source_map[1].line, 2);
+    }
+
+    #[test]
+    fn shared_program_is_loaded_without_copies_and_detaches_on_hot_swap() {
+        let program = SharedProgram::assemble(
+            ".entry start\n.org 0x0010\nstart:\nMOV R1, #1\nHALT\n",
+            "shared.n1",
+        )
+        .unwrap();
+        let mut first = WasmCore::new();
+        let mut second = WasmCore::new();
+        first.load_shared_program(&program);
+        second.load_shared_program(&program);
+        assert!(Rc::ptr_eq(&first.program, &second.program));
+        assert_eq!(program.core_count(), 2);
+        assert_eq!(second.state.arch.pc(), 0x0010);
+        assert_eq!(second.read_file("shared.n1").unwrap().lines().count(), 5);
+
+        let _ = first.step_internal();
+        assert_eq!(first.state.arch.gpr(GeneralRegister::R1), 1);
+        assert_eq!(second.state.arch.gpr(GeneralRegister::R1), 0);
+
+        let source = ".entry start\n.org 0x0010\nstart:\nMOV R1, #2\nHALT\n";
+        let edited = assemble_from_source(source, "shared.n1").unwrap();
+        assert!(
+            first
+                .hot_swap_internal(edited, source, "shared.n1")
+                .state_preserved
+        );
+        assert!(!Rc::ptr_eq(&first.program, &second.program));
+        assert_eq!(program.core_count(), 1);
+        assert_eq!(second.program.binary, program.assets.binary);
+
+        second.reset_and_reload();
+        let _ = second.step_internal();
+        assert_eq!(second.state.arch.gpr(GeneralRegister::R1), 1);
+    }
+
+    #[test]
+    fn input_fanout_delivers_the_same_input_to_every_subscriber() {
+        let program = SharedProgram::assemble(
+            "MOV R0, #0xE164\nLOAD R1, [R0]\nLOAD R2, [R0]\nHALT\n",
+            "paste.n1",
+        )
+        .unwrap();
+        let mut fanout = InputFanout::new();
+        let mut cores: Vec<_> = (0..3).map(|_| WasmCore::new()).collect();
+        for core in &mut cores {
+            core.load_shared_program(&program);
+            core.subscribe(&mut fanout);
+        }
+        cores[2].unsubscribe();
+        let dropped = {
+            let mut core = WasmCore::new();
+            core.subscribe(&mut fanout);
+            core
+        };
+        assert_eq!(fanout.subscribers(), 3);
+        drop(dropped);
+
+        assert_eq!(fanout.paste_text("hi"), 2);
+        assert_eq!(fanout.enqueue_event(7), 2);
+        assert_eq!(fanout.subscribers(), 2);
+
+        for core in &mut cores {
+            for _ in 0..3 {
+                let _ = core.step_internal();
+            }
+        }
+        assert_eq!(cores[0].state.state_digest(), cores[1].state.state_digest());
+        assert_eq!(
+            cores[1].state.arch.gpr(GeneralRegister::R1),
+            u16::from(b'h')
+        );
+        assert_eq!(
+            cores[1].state.arch.gpr(GeneralRegister::R2),
+            u16::from(b'i')
+        );
+        assert_eq!(
+            cores[2].mmio.paste().map(PasteBuffer::bytes),
+            Some(&b""[..])
+        );
+        assert_ne!(cores[0].state.state_digest(), cores[2].state.state_digest());
+    }
+
     #[test]
     fn compute_changed_regions_detects_single_byte_change() {
         let current = [0xFF, 0x00, 0x00, 0x00];